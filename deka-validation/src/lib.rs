@@ -103,6 +103,8 @@ pub fn format_validation_error_extended(
             severity: severity.to_string(),
             docs_link,
             suggestion: None,
+            error_code: None,
+            notes: Vec::new(),
         }),
     )
 }
@@ -134,6 +136,45 @@ pub fn format_validation_error_with_suggestion(
             severity: severity.to_string(),
             docs_link,
             suggestion,
+            error_code: None,
+            notes: Vec::new(),
+        }),
+    )
+}
+
+/// Format a diagnostic that carries a stable error code (e.g. `PX0301`) and
+/// trailing notes. Each note is rendered as its own `= note:` line.
+#[wasm_bindgen]
+pub fn format_diagnostic(
+    code: &str,
+    file_path: &str,
+    error_kind: &str,
+    error_code: &str,
+    line_num: usize,
+    col_num: usize,
+    message: &str,
+    help: &str,
+    underline_length: usize,
+    severity: &str,
+    docs_link: Option<String>,
+    suggestion: Option<String>,
+    notes: Vec<String>,
+) -> String {
+    format_error_impl(
+        code,
+        file_path,
+        error_kind,
+        line_num,
+        col_num,
+        message,
+        help,
+        underline_length,
+        Some(ExtraFormatInfo {
+            severity: severity.to_string(),
+            docs_link,
+            suggestion,
+            error_code: Some(error_code.to_string()).filter(|value| !value.is_empty()),
+            notes,
         }),
     )
 }
@@ -143,6 +184,8 @@ struct ExtraFormatInfo {
     severity: String,
     docs_link: Option<String>,
     suggestion: Option<String>,
+    error_code: Option<String>,
+    notes: Vec<String>,
 }
 
 fn format_error_impl(
@@ -184,7 +227,14 @@ fn format_error_impl(
     let kind_color = color_for_kind(error_kind).unwrap_or(severity_color);
     let icon = colorize(icon, severity_color, use_color);
     let label = colorize(label, severity_color, use_color);
-    let kind_label = colorize(error_kind, kind_color, use_color);
+    let kind_label = match extra.as_ref().and_then(|extra| extra.error_code.as_deref()) {
+        Some(error_code) => format!(
+            "{} {}",
+            colorize(error_kind, kind_color, use_color),
+            colorize(&format!("[{}]", error_code), kind_color, use_color)
+        ),
+        None => colorize(error_kind, kind_color, use_color),
+    };
 
     let mut out = format!(
         "\n{}\n\
@@ -225,6 +275,9 @@ fn format_error_impl(
         if show_help {
             out.push_str(&format!("= help: {}\n", help));
         }
+        for note in extra.notes.iter().filter(|note| !note.trim().is_empty()) {
+            out.push_str(&format!("= note: {}\n", note));
+        }
         if let Some(suggestion_value) = suggestion {
             let suggestion_label = colorize("suggestion", "\x1b[36m", use_color);
             out.push_str(&format!("= {}: {}\n", suggestion_label, suggestion_value));
@@ -354,4 +407,27 @@ mod tests {
         assert!(error.contains("❌ Error"));
         assert!(error.contains("999 │"));
     }
+
+    #[test]
+    fn test_diagnostic_with_code_and_notes() {
+        let error = format_diagnostic(
+            "$x = 1;",
+            "main.phpx",
+            "Type Error",
+            "PX0301",
+            1,
+            1,
+            "Type mismatch",
+            "Fix the annotation.",
+            2,
+            "error",
+            None,
+            None,
+            vec!["main.phpx:3:1: declared here".to_string()],
+        );
+
+        assert!(error.contains("❌ Type Error [PX0301]"));
+        assert!(error.contains("= help: Fix the annotation."));
+        assert!(error.contains("= note: main.phpx:3:1: declared here"));
+    }
 }
@@ -0,0 +1,4 @@
+-- Tarball integrity and publisher signatures for package releases
+ALTER TABLE "package_releases" ADD COLUMN IF NOT EXISTS "integrity" TEXT;
+ALTER TABLE "package_releases" ADD COLUMN IF NOT EXISTS "signature" TEXT;
+ALTER TABLE "package_releases" ADD COLUMN IF NOT EXISTS "signing_key" TEXT;
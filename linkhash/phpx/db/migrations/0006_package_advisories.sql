@@ -0,0 +1,19 @@
+-- Security advisories against published packages, served in OSV format
+
+CREATE TABLE IF NOT EXISTS "package_advisories" (
+  "id" BIGSERIAL PRIMARY KEY,
+  "advisory_id" TEXT NOT NULL UNIQUE,
+  "package_name" TEXT NOT NULL,
+  "summary" TEXT NOT NULL,
+  "details" TEXT,
+  "severity" TEXT NOT NULL,
+  "aliases" JSONB NOT NULL DEFAULT '[]'::jsonb,
+  "ranges" JSONB NOT NULL,
+  "reporter" TEXT NOT NULL,
+  "published_at" TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  "withdrawn_at" TIMESTAMPTZ,
+  CHECK ("severity" IN ('low', 'moderate', 'high', 'critical'))
+);
+
+CREATE INDEX IF NOT EXISTS "idx_package_advisories_package"
+  ON "package_advisories"("package_name");
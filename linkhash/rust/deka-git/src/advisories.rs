@@ -0,0 +1,248 @@
+use chrono::{DateTime, Datelike, Utc};
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use crate::db;
+
+/// OSV ecosystem name linkhash packages are published under.
+pub const OSV_ECOSYSTEM: &str = "linkhash";
+
+const SEVERITIES: &[&str] = &["low", "moderate", "high", "critical"];
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct PackageAdvisory {
+    pub advisory_id: String,
+    pub package_name: String,
+    pub summary: String,
+    pub details: Option<String>,
+    pub severity: String,
+    pub aliases: serde_json::Value,
+    pub ranges: serde_json::Value,
+    pub reporter: String,
+    pub published_at: DateTime<Utc>,
+    pub withdrawn_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateAdvisoryRequest {
+    /// Defaults to a generated `LHSA-<year>-<hex>` id.
+    pub id: Option<String>,
+    pub package: String,
+    pub summary: String,
+    pub details: Option<String>,
+    pub severity: String,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    pub ranges: Vec<AffectedRange>,
+}
+
+/// One affected interval: `introduced <= v < fixed`, or `<= last_affected`
+/// when no fix has shipped yet.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AffectedRange {
+    #[serde(default = "default_introduced")]
+    pub introduced: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fixed: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_affected: Option<String>,
+}
+
+fn default_introduced() -> String {
+    "0".to_string()
+}
+
+impl PackageAdvisory {
+    /// Render the advisory as an OSV record.
+    pub fn to_osv(&self) -> serde_json::Value {
+        let ranges: Vec<AffectedRange> =
+            serde_json::from_value(self.ranges.clone()).unwrap_or_default();
+        let events: Vec<serde_json::Value> = ranges
+            .iter()
+            .map(|range| {
+                let mut events = vec![serde_json::json!({ "introduced": range.introduced })];
+                if let Some(fixed) = &range.fixed {
+                    events.push(serde_json::json!({ "fixed": fixed }));
+                } else if let Some(last) = &range.last_affected {
+                    events.push(serde_json::json!({ "last_affected": last }));
+                }
+                serde_json::json!({ "type": "SEMVER", "events": events })
+            })
+            .collect();
+        serde_json::json!({
+            "schema_version": "1.6.0",
+            "id": self.advisory_id,
+            "aliases": self.aliases,
+            "summary": self.summary,
+            "details": self.details.clone().unwrap_or_default(),
+            "published": self.published_at.to_rfc3339(),
+            "withdrawn": self.withdrawn_at.map(|at| at.to_rfc3339()),
+            "affected": [{
+                "package": { "ecosystem": OSV_ECOSYSTEM, "name": self.package_name },
+                "ranges": events,
+            }],
+            "database_specific": { "severity": self.severity.to_uppercase() },
+        })
+    }
+}
+
+/// Active advisories, optionally for one package.
+pub async fn list_advisories(package: Option<&str>) -> Result<Vec<PackageAdvisory>, sqlx::Error> {
+    let pool = db::pool();
+    sqlx::query_as::<_, PackageAdvisory>(
+        r#"
+        SELECT advisory_id, package_name, summary, details, severity, aliases, ranges, reporter, published_at, withdrawn_at
+        FROM package_advisories
+        WHERE withdrawn_at IS NULL AND ($1::text IS NULL OR package_name = $1)
+        ORDER BY published_at DESC
+        "#,
+    )
+    .bind(package)
+    .fetch_all(pool)
+    .await
+}
+
+/// Record an advisory. Only the owner of the package's releases may report
+/// against it.
+pub async fn create_advisory(
+    reporter: &str,
+    req: CreateAdvisoryRequest,
+) -> Result<PackageAdvisory, anyhow::Error> {
+    let severity = req.severity.trim().to_ascii_lowercase();
+    validate_advisory(&severity, &req.ranges)?;
+    if req.summary.trim().is_empty() {
+        anyhow::bail!("advisory summary is required");
+    }
+
+    let pool = db::pool();
+    let owner: Option<String> = sqlx::query_scalar(
+        r#"
+        SELECT owner
+        FROM package_releases
+        WHERE package_name = $1
+        ORDER BY created_at DESC
+        LIMIT 1
+        "#,
+    )
+    .bind(&req.package)
+    .fetch_optional(pool)
+    .await?;
+    match owner {
+        None => anyhow::bail!("package {} has no published releases", req.package),
+        Some(owner) if owner != reporter => {
+            anyhow::bail!("only {} can report advisories for {}", owner, req.package)
+        }
+        Some(_) => {}
+    }
+
+    let advisory_id = req.id.clone().unwrap_or_else(generate_advisory_id);
+    let row = sqlx::query_as::<_, PackageAdvisory>(
+        r#"
+        INSERT INTO package_advisories
+            (advisory_id, package_name, summary, details, severity, aliases, ranges, reporter)
+        VALUES
+            ($1, $2, $3, $4, $5, $6, $7, $8)
+        RETURNING advisory_id, package_name, summary, details, severity, aliases, ranges, reporter, published_at, withdrawn_at
+        "#,
+    )
+    .bind(&advisory_id)
+    .bind(&req.package)
+    .bind(req.summary.trim())
+    .bind(&req.details)
+    .bind(&severity)
+    .bind(serde_json::to_value(&req.aliases)?)
+    .bind(serde_json::to_value(&req.ranges)?)
+    .bind(reporter)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(row)
+}
+
+fn validate_advisory(severity: &str, ranges: &[AffectedRange]) -> Result<(), anyhow::Error> {
+    if !SEVERITIES.contains(&severity) {
+        anyhow::bail!(
+            "invalid severity {} (expected one of {})",
+            severity,
+            SEVERITIES.join(", ")
+        );
+    }
+    if ranges.is_empty() {
+        anyhow::bail!("advisory must list at least one affected range");
+    }
+    for range in ranges {
+        let introduced = match range.introduced.as_str() {
+            "0" => Version::new(0, 0, 0),
+            raw => parse_version(raw)?,
+        };
+        let end = match (&range.fixed, &range.last_affected) {
+            (Some(_), Some(_)) => {
+                anyhow::bail!("a range can set fixed or last_affected, not both")
+            }
+            (Some(fixed), None) => Some(parse_version(fixed)?),
+            (None, Some(last)) => Some(parse_version(last)?),
+            (None, None) => None,
+        };
+        if end.is_some_and(|end| end < introduced) {
+            anyhow::bail!(
+                "range ends before version {} is introduced",
+                range.introduced
+            );
+        }
+    }
+    Ok(())
+}
+
+fn parse_version(raw: &str) -> Result<Version, anyhow::Error> {
+    Version::parse(raw).map_err(|e| anyhow::anyhow!("invalid version {}: {}", raw, e))
+}
+
+fn generate_advisory_id() -> String {
+    format!("LHSA-{}-{:08x}", Utc::now().year(), rand::random::<u32>())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(introduced: &str, fixed: Option<&str>) -> AffectedRange {
+        AffectedRange {
+            introduced: introduced.to_string(),
+            fixed: fixed.map(str::to_string),
+            last_affected: None,
+        }
+    }
+
+    #[test]
+    fn validates_severity_and_ranges() {
+        assert!(validate_advisory("high", &[range("0", Some("1.2.3"))]).is_ok());
+        assert!(validate_advisory("severe", &[range("0", None)]).is_err());
+        assert!(validate_advisory("low", &[]).is_err());
+        assert!(validate_advisory("low", &[range("2.0.0", Some("1.0.0"))]).is_err());
+        assert!(validate_advisory("low", &[range("1.x", None)]).is_err());
+    }
+
+    #[test]
+    fn renders_osv_records() {
+        let advisory = PackageAdvisory {
+            advisory_id: "LHSA-2026-0001".to_string(),
+            package_name: "@acme/http".to_string(),
+            summary: "header injection".to_string(),
+            details: None,
+            severity: "high".to_string(),
+            aliases: serde_json::json!(["CVE-2026-1234"]),
+            ranges: serde_json::to_value(vec![range("1.0.0", Some("1.4.2"))]).unwrap(),
+            reporter: "acme".to_string(),
+            published_at: Utc::now(),
+            withdrawn_at: None,
+        };
+        let osv = advisory.to_osv();
+        assert_eq!(osv["affected"][0]["package"]["ecosystem"], "linkhash");
+        assert_eq!(
+            osv["affected"][0]["ranges"][0]["events"],
+            serde_json::json!([{ "introduced": "1.0.0" }, { "fixed": "1.4.2" }])
+        );
+        assert_eq!(osv["database_specific"]["severity"], "HIGH");
+    }
+}
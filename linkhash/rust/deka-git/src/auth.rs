@@ -351,6 +351,23 @@ pub async fn list_ssh_keys(user_id: i64) -> Result<Vec<SshKeyRecord>, sqlx::Erro
     .await
 }
 
+/// Public keys of `username`, used to check package release signatures.
+pub async fn list_ssh_keys_for_username(username: &str) -> Result<Vec<SshKeyRecord>, sqlx::Error> {
+    let pool = crate::db::pool();
+    sqlx::query_as::<_, SshKeyRecord>(
+        r#"
+        SELECT k.key_name, k.algorithm, k.public_key, k.fingerprint, k.created_at
+        FROM user_ssh_keys k
+        JOIN users u ON u.id = k.user_id
+        WHERE u.username = $1
+        ORDER BY k.created_at DESC
+        "#,
+    )
+    .bind(username)
+    .fetch_all(pool)
+    .await
+}
+
 pub async fn add_ssh_key(
     user_id: i64,
     key_name: &str,
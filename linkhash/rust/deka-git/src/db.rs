@@ -81,6 +81,7 @@ async fn verify_schema_ready(pool: &PgPool) -> anyhow::Result<()> {
         "user_tokens",
         "user_ssh_keys",
         "package_releases",
+        "package_advisories",
         "issues",
         "issue_comments",
         "labels",
@@ -141,10 +142,31 @@ async fn verify_schema_ready(pool: &PgPool) -> anyhow::Result<()> {
             "api_change_kind",
             "required_bump",
             "capability_metadata",
+            "integrity",
+            "signature",
+            "signing_key",
             "created_at",
         ],
     )
     .await?;
+    require_columns(
+        pool,
+        "package_advisories",
+        &[
+            "id",
+            "advisory_id",
+            "package_name",
+            "summary",
+            "details",
+            "severity",
+            "aliases",
+            "ranges",
+            "reporter",
+            "published_at",
+            "withdrawn_at",
+        ],
+    )
+    .await?;
     require_columns(
         pool,
         "issues",
@@ -10,6 +10,7 @@ use std::collections::HashMap;
 use std::net::SocketAddr;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
+mod advisories;
 mod auth;
 mod config;
 mod db;
@@ -72,6 +73,7 @@ async fn main() {
         .route("/api/auth/me", get(handle_auth_me))
         .route("/api/packages/preflight", post(handle_preflight_package))
         .route("/api/packages/publish", post(handle_publish_package))
+        .route("/api/advisories", post(handle_create_advisory))
         .route("/api/user/ssh-keys", get(handle_list_ssh_keys))
         .route("/api/user/ssh-keys", post(handle_add_ssh_key))
         .route(
@@ -132,8 +134,10 @@ async fn main() {
             "/api/public/repos/:owner/:repo/resolve",
             get(handle_resolve_repo_ref),
         )
+        .route("/api/users/:username/keys", get(handle_list_user_keys))
         .route("/api/auth/signup", post(handle_auth_signup))
         .route("/api/auth/login", post(handle_auth_login))
+        .route("/api/advisories", get(handle_list_advisories))
         .route("/api/packages/:name", get(handle_get_package))
         .route("/api/packages/:name/:version", get(handle_get_release))
         .route(
@@ -586,6 +590,32 @@ async fn handle_list_ssh_keys(req: Request) -> impl IntoResponse {
     }
 }
 
+/// Public signing keys of a user, so installers can verify their releases.
+async fn handle_list_user_keys(Path(username): Path<String>) -> impl IntoResponse {
+    match auth::list_ssh_keys_for_username(&username).await {
+        Ok(keys) => {
+            let keys: Vec<serde_json::Value> = keys
+                .into_iter()
+                .map(|key| {
+                    serde_json::json!({
+                        "algorithm": key.algorithm,
+                        "public_key": key.public_key,
+                        "fingerprint": key.fingerprint,
+                    })
+                })
+                .collect();
+            (StatusCode::OK, Json(serde_json::json!({ "keys": keys })))
+        }
+        Err(e) => {
+            tracing::error!("list keys for {} failed: {}", username, e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": e.to_string() })),
+            )
+        }
+    }
+}
+
 async fn handle_add_ssh_key(req: Request) -> impl IntoResponse {
     let auth_user = match auth::get_auth_user(&req) {
         Some(user) => user.clone(),
@@ -696,6 +726,70 @@ async fn handle_publish_package(req: Request) -> impl IntoResponse {
     }
 }
 
+#[derive(Debug, Deserialize)]
+struct AdvisoriesQuery {
+    package: Option<String>,
+}
+
+async fn handle_list_advisories(Query(query): Query<AdvisoriesQuery>) -> impl IntoResponse {
+    match advisories::list_advisories(query.package.as_deref()).await {
+        Ok(rows) => {
+            let vulns: Vec<serde_json::Value> = rows
+                .iter()
+                .map(advisories::PackageAdvisory::to_osv)
+                .collect();
+            (StatusCode::OK, Json(serde_json::json!({ "vulns": vulns })))
+        }
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e.to_string() })),
+        ),
+    }
+}
+
+async fn handle_create_advisory(req: Request) -> impl IntoResponse {
+    let auth_user = match auth::get_auth_user(&req) {
+        Some(user) => user.clone(),
+        None => {
+            return (
+                StatusCode::UNAUTHORIZED,
+                Json(serde_json::json!({ "error": "Authentication required" })),
+            )
+        }
+    };
+
+    let body = match axum::body::to_bytes(req.into_body(), 1024 * 1024).await {
+        Ok(b) => b,
+        Err(_) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({ "error": "Invalid body" })),
+            )
+        }
+    };
+
+    let payload: advisories::CreateAdvisoryRequest = match serde_json::from_slice(&body) {
+        Ok(v) => v,
+        Err(e) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({ "error": e.to_string() })),
+            )
+        }
+    };
+
+    match advisories::create_advisory(&auth_user.username, payload).await {
+        Ok(advisory) => (
+            StatusCode::CREATED,
+            Json(serde_json::json!({ "advisory": advisory.to_osv() })),
+        ),
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({ "error": e.to_string() })),
+        ),
+    }
+}
+
 async fn handle_preflight_package(req: Request) -> impl IntoResponse {
     let auth_user = match auth::get_auth_user(&req) {
         Some(user) => user.clone(),
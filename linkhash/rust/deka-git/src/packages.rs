@@ -1,6 +1,9 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
 use chrono::{DateTime, Utc};
+use ed25519_dalek::{Signature, VerifyingKey};
 use semver::Version;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha512};
 use sqlx::FromRow;
 use std::collections::BTreeMap;
 use std::process::Command;
@@ -13,6 +16,18 @@ pub struct PublishPackageRequest {
     pub git_ref: Option<String>,
     pub description: Option<String>,
     pub manifest: Option<serde_json::Value>,
+    /// Publisher signature over the release tarball; see `release_signing_message`.
+    pub signature: Option<ReleaseSignature>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReleaseSignature {
+    /// `sha512-<base64>` of the uncompressed tar the publisher built and signed.
+    pub tar_digest: String,
+    /// Base64 ed25519 signature.
+    pub signature: String,
+    /// Fingerprint of one of the publisher's registered ssh keys.
+    pub key_fingerprint: String,
 }
 
 #[derive(Debug, Serialize, FromRow)]
@@ -28,6 +43,9 @@ pub struct PackageRelease {
     pub api_change_kind: Option<String>,
     pub required_bump: Option<String>,
     pub capability_metadata: Option<serde_json::Value>,
+    pub integrity: Option<String>,
+    pub signature: Option<String>,
+    pub signing_key: Option<String>,
     pub created_at: DateTime<Utc>,
 }
 
@@ -254,15 +272,23 @@ pub async fn publish(
     let snapshot = build_api_snapshot(&repo_path, &git_ref)?;
     let snapshot_json = serde_json::to_value(snapshot)?;
     let capability_meta_json = serde_json::to_value(preflight.capabilities.clone())?;
+    let integrity = sri_sha512(&build_tarball(owner, &req.repo, &git_ref)?);
+    let signing_key = match &req.signature {
+        Some(signature) => {
+            let tar_digest = sri_sha512(&git_archive(owner, &req.repo, &git_ref, "tar")?);
+            Some(verify_publish_signature(owner, &req, &tar_digest, signature).await?)
+        }
+        None => None,
+    };
 
     let pool = crate::db::pool();
     let row = sqlx::query_as::<_, PackageRelease>(
         r#"
         INSERT INTO package_releases
-            (package_name, version, owner, repo, git_ref, description, manifest, api_snapshot, api_change_kind, required_bump, capability_metadata)
+            (package_name, version, owner, repo, git_ref, description, manifest, api_snapshot, api_change_kind, required_bump, capability_metadata, integrity, signature, signing_key)
         VALUES
-            ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
-        RETURNING package_name, version, owner, repo, git_ref, description, manifest, api_snapshot, api_change_kind, required_bump, capability_metadata, created_at
+            ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+        RETURNING package_name, version, owner, repo, git_ref, description, manifest, api_snapshot, api_change_kind, required_bump, capability_metadata, integrity, signature, signing_key, created_at
         "#,
     )
     .bind(&req.name)
@@ -276,6 +302,9 @@ pub async fn publish(
     .bind(preflight.detected_change.as_str())
     .bind(preflight.required_bump.as_str())
     .bind(&capability_meta_json)
+    .bind(&integrity)
+    .bind(req.signature.as_ref().map(|signature| &signature.signature))
+    .bind(&signing_key)
     .fetch_one(pool)
     .await?;
 
@@ -310,7 +339,7 @@ pub async fn get_release(name: &str, version: &str) -> Result<Option<PackageRele
     let pool = crate::db::pool();
     sqlx::query_as::<_, PackageRelease>(
         r#"
-        SELECT package_name, version, owner, repo, git_ref, description, manifest, api_snapshot, api_change_kind, required_bump, capability_metadata, created_at
+        SELECT package_name, version, owner, repo, git_ref, description, manifest, api_snapshot, api_change_kind, required_bump, capability_metadata, integrity, signature, signing_key, created_at
         FROM package_releases
         WHERE package_name = $1 AND version = $2
         "#,
@@ -428,22 +457,38 @@ pub async fn get_release_blob(
 }
 
 pub fn build_release_tarball(release: &PackageRelease) -> Result<Vec<u8>, anyhow::Error> {
-    let repo_path = crate::repo::storage::get_repo_path(&release.owner, &release.repo);
-    if !repo_path.exists() {
-        anyhow::bail!(
-            "Repository not found for package {}@{}",
+    build_tarball(&release.owner, &release.repo, &release.git_ref).map_err(|e| {
+        anyhow::anyhow!(
+            "package {}@{}: {}",
             release.package_name,
-            release.version
-        );
+            release.version,
+            e
+        )
+    })
+}
+
+fn build_tarball(owner: &str, repo: &str, git_ref: &str) -> Result<Vec<u8>, anyhow::Error> {
+    git_archive(owner, repo, git_ref, "tar.gz")
+}
+
+fn git_archive(
+    owner: &str,
+    repo: &str,
+    git_ref: &str,
+    format: &str,
+) -> Result<Vec<u8>, anyhow::Error> {
+    let repo_path = crate::repo::storage::get_repo_path(owner, repo);
+    if !repo_path.exists() {
+        anyhow::bail!("Repository not found: {}/{}", owner, repo);
     }
 
     let prefix = "package/".to_string();
     let output = Command::new("git")
         .arg(format!("--git-dir={}", repo_path.display()))
         .arg("archive")
-        .arg("--format=tar.gz")
+        .arg(format!("--format={}", format))
         .arg(format!("--prefix={}", prefix))
-        .arg(&release.git_ref)
+        .arg(git_ref)
         .output()?;
 
     if !output.status.success() {
@@ -456,6 +501,65 @@ pub fn build_release_tarball(release: &PackageRelease) -> Result<Vec<u8>, anyhow
     Ok(output.stdout)
 }
 
+fn sri_sha512(bytes: &[u8]) -> String {
+    format!("sha512-{}", BASE64.encode(Sha512::digest(bytes)))
+}
+
+/// Statement a publisher signs for a release. Must stay byte-for-byte in
+/// sync with `pm::signature::release_message` in the deka runtime.
+fn release_signing_message(name: &str, version: &str, tar_digest: &str) -> String {
+    format!("deka-release-v2\n{}\n{}\n{}\n", name, version, tar_digest)
+}
+
+/// Check a publish signature against the uncompressed tar of the release and
+/// the owner's registered keys. Returns the fingerprint of the signing key.
+async fn verify_publish_signature(
+    owner: &str,
+    req: &PublishPackageRequest,
+    tar_digest: &str,
+    signature: &ReleaseSignature,
+) -> Result<String, anyhow::Error> {
+    if signature.tar_digest != tar_digest {
+        anyhow::bail!(
+            "signed tar {} does not match the registry build {} (push the tag you signed, or publish with --no-sign)",
+            signature.tar_digest,
+            tar_digest
+        );
+    }
+    let keys = crate::auth::list_ssh_keys_for_username(owner).await?;
+    let key = keys
+        .iter()
+        .find(|key| key.fingerprint == signature.key_fingerprint)
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "signing key {} is not registered for {} (add it with /api/user/ssh-keys)",
+                signature.key_fingerprint,
+                owner
+            )
+        })?;
+    let message = release_signing_message(&req.name, &req.version, tar_digest);
+    verify_ed25519(&key.public_key, message.as_bytes(), &signature.signature)?;
+    Ok(key.fingerprint.clone())
+}
+
+fn verify_ed25519(public_key: &str, message: &[u8], signature: &str) -> Result<(), anyhow::Error> {
+    let encoded = public_key
+        .split_whitespace()
+        .nth(1)
+        .ok_or_else(|| anyhow::anyhow!("invalid ssh public key"))?;
+    let blob = BASE64.decode(encoded)?;
+    // ssh wire format: string "ssh-ed25519", string key(32)
+    let key_bytes: [u8; 32] = blob
+        .get(blob.len().saturating_sub(32)..)
+        .filter(|_| blob.len() == 4 + 11 + 4 + 32)
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| anyhow::anyhow!("invalid ssh-ed25519 key blob"))?;
+    let key = VerifyingKey::from_bytes(&key_bytes)?;
+    let signature = Signature::from_slice(&BASE64.decode(signature.trim())?)?;
+    key.verify_strict(message, &signature)
+        .map_err(|_| anyhow::anyhow!("release signature does not verify"))
+}
+
 impl ApiChangeKind {
     fn as_str(self) -> &'static str {
         match self {
@@ -597,7 +701,7 @@ async fn latest_release_for_package(name: &str) -> Result<Option<PackageRelease>
     let pool = crate::db::pool();
     sqlx::query_as::<_, PackageRelease>(
         r#"
-        SELECT package_name, version, owner, repo, git_ref, description, manifest, api_snapshot, api_change_kind, required_bump, capability_metadata, created_at
+        SELECT package_name, version, owner, repo, git_ref, description, manifest, api_snapshot, api_change_kind, required_bump, capability_metadata, integrity, signature, signing_key, created_at
         FROM package_releases
         WHERE package_name = $1
         ORDER BY created_at DESC
@@ -1381,6 +1485,25 @@ export function sum($a: int, $b: int): int {
         assert_eq!(entry.examples, vec!["sum(1, 2)".to_string()]);
     }
 
+    #[test]
+    fn verifies_release_signatures() {
+        use ed25519_dalek::{Signer, SigningKey};
+
+        let key = SigningKey::from_bytes(&[3; 32]);
+        let mut blob = Vec::new();
+        for field in [b"ssh-ed25519".as_slice(), key.verifying_key().as_bytes()] {
+            blob.extend_from_slice(&(field.len() as u32).to_be_bytes());
+            blob.extend_from_slice(field);
+        }
+        let public_key = format!("ssh-ed25519 {} dev@example", BASE64.encode(blob));
+        let message = release_signing_message("@acme/http", "1.2.0", "sha512-abc");
+        let signature = BASE64.encode(key.sign(message.as_bytes()).to_bytes());
+
+        assert!(verify_ed25519(&public_key, message.as_bytes(), &signature).is_ok());
+        let tampered = release_signing_message("@acme/http", "1.2.1", "sha512-abc");
+        assert!(verify_ed25519(&public_key, tampered.as_bytes(), &signature).is_err());
+    }
+
     #[test]
     fn parses_ls_tree_rows() {
         let row = "100644 blob abcdef1234567890 42\tsrc/main.phpx";
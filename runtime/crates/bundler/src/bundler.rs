@@ -5,7 +5,8 @@ use std::sync::{Arc, Mutex};
 use runtime_core::module_spec::module_spec_aliases;
 use swc_bundler::{BundleKind, Bundler, Config, Hook, Load, ModuleData, ModuleType};
 use swc_common::{
-    FileName, GLOBALS, Globals, Mark, SourceMap, comments::SingleThreadedComments, sync::Lrc,
+    BytePos, FileName, GLOBALS, Globals, LineCol, Mark, SourceMap,
+    comments::SingleThreadedComments, sync::Lrc,
 };
 use swc_ecma_ast::{EsVersion, KeyValueProp, Pass, Program};
 use swc_ecma_codegen::{Emitter, text_writer::JsWriter};
@@ -19,6 +20,7 @@ use swc_ecma_minifier::optimize;
 use swc_ecma_minifier::option::{CompressOptions, MangleOptions, MinifyOptions};
 
 use crate::css_bundler::{self, CssAsset};
+use crate::source_map::{InputSourceMap, SourceMapBuilder};
 
 const REACT_SOURCE: &str = include_str!("../src-ts/vendor/react.esm.js");
 const REACT_DOM_CLIENT_SOURCE: &str = include_str!("../src-ts/vendor/react-dom-client.esm.js");
@@ -38,6 +40,15 @@ pub struct BundleOptions {
 
 pub trait VirtualSource: Send + Sync {
     fn load_virtual(&self, path: &Path) -> Result<Option<String>, String>;
+
+    /// Like `load_virtual`, also returning the v3 source map of a generated
+    /// module so the bundle map can point at its original source.
+    fn load_virtual_with_source_map(
+        &self,
+        path: &Path,
+    ) -> Result<Option<(String, Option<String>)>, String> {
+        Ok(self.load_virtual(path)?.map(|source| (source, None)))
+    }
 }
 
 pub fn bundle_virtual_entry(
@@ -45,13 +56,39 @@ pub fn bundle_virtual_entry(
     options: BundleOptions,
     provider: Arc<dyn VirtualSource>,
 ) -> Result<String, String> {
+    bundle_virtual(entry_path, options, provider, false).map(|(code, _)| code)
+}
+
+/// Bundle like `bundle_virtual_entry`, also returning a v3 source map for the
+/// output. Sources are named relative to the project root.
+pub fn bundle_virtual_entry_with_source_map(
+    entry_path: &Path,
+    options: BundleOptions,
+    provider: Arc<dyn VirtualSource>,
+) -> Result<(String, String), String> {
+    let (code, map) = bundle_virtual(entry_path, options, provider, true)?;
+    Ok((code, map.unwrap_or_default()))
+}
+
+fn bundle_virtual(
+    entry_path: &Path,
+    options: BundleOptions,
+    provider: Arc<dyn VirtualSource>,
+    source_map: bool,
+) -> Result<(String, Option<String>), String> {
     let cm: Lrc<SourceMap> = Default::default();
     let globals = Globals::new();
+    let input_maps = Arc::new(Mutex::new(HashMap::new()));
     let loader = VirtualLoader {
         cm: cm.clone(),
         css_collector: Arc::new(Mutex::new(CssCollector::default())),
         provider,
+        input_maps: input_maps.clone(),
     };
+    let project_root = options
+        .project_root
+        .canonicalize()
+        .unwrap_or_else(|_| options.project_root.clone());
     let resolver = DekaResolver::new(options.project_root)?;
 
     let mut bundler = Bundler::new(
@@ -115,19 +152,82 @@ pub fn bundle_virtual_entry(
     };
 
     let mut buf = Vec::new();
+    let mut positions = Vec::new();
     {
         let mut emitter = Emitter {
             cfg: swc_ecma_codegen::Config::default(),
             comments: None,
             cm: cm.clone(),
-            wr: JsWriter::new(cm.clone(), "\n", &mut buf, None),
+            wr: JsWriter::new(
+                cm.clone(),
+                "\n",
+                &mut buf,
+                source_map.then_some(&mut positions),
+            ),
         };
         emitter
             .emit_module(&module)
             .map_err(|err| err.to_string())?;
     }
 
-    String::from_utf8(buf).map_err(|err| err.to_string())
+    let code = String::from_utf8(buf).map_err(|err| err.to_string())?;
+    if !source_map {
+        return Ok((code, None));
+    }
+    let input_maps = input_maps
+        .lock()
+        .map_err(|_| "Source map lock failed".to_string())?;
+    let file = entry_path
+        .file_stem()
+        .map(|stem| format!("{}.js", stem.to_string_lossy()))
+        .unwrap_or_default();
+    let map = build_source_map(&cm, &positions, &input_maps, &project_root, &file);
+    Ok((code, Some(map)))
+}
+
+/// Compose the bundle map from SWC's emitted positions, routing modules that
+/// came with their own map through it.
+fn build_source_map(
+    cm: &Lrc<SourceMap>,
+    positions: &[(BytePos, LineCol)],
+    input_maps: &HashMap<PathBuf, String>,
+    project_root: &Path,
+    file: &str,
+) -> String {
+    let mut parsed: HashMap<PathBuf, Option<InputSourceMap>> = HashMap::new();
+    let mut builder = SourceMapBuilder::default();
+    for (pos, generated) in positions {
+        if pos.is_dummy() {
+            continue;
+        }
+        let Ok(loc) = cm.try_lookup_char_pos(*pos) else {
+            continue;
+        };
+        let line = loc.line.saturating_sub(1) as u32;
+        let column = loc.col.0 as u32;
+        let name = match &*loc.file.name {
+            FileName::Real(path) => {
+                let input = parsed.entry(path.clone()).or_insert_with(|| {
+                    let mut input = InputSourceMap::parse(input_maps.get(path)?).ok()?;
+                    input.strip_source_prefix(project_root);
+                    Some(input)
+                });
+                if let Some(input) = input {
+                    builder.add_through(generated.line, generated.col, input, line, column);
+                    continue;
+                }
+                path.strip_prefix(project_root)
+                    .unwrap_or(path)
+                    .to_string_lossy()
+                    .replace('\\', "/")
+            }
+            FileName::Custom(name) => name.clone(),
+            _ => continue,
+        };
+        let id = builder.add_source(&name, Some(String::from(&*loc.file.src)));
+        builder.add(generated.line, generated.col, (id, line, column));
+    }
+    builder.to_json(file)
 }
 
 pub fn bundle_browser(entry: &str) -> Result<String, String> {
@@ -439,17 +539,24 @@ struct VirtualLoader {
     cm: Lrc<SourceMap>,
     css_collector: Arc<Mutex<CssCollector>>,
     provider: Arc<dyn VirtualSource>,
+    input_maps: Arc<Mutex<HashMap<PathBuf, String>>>,
 }
 
 impl Load for VirtualLoader {
     fn load(&self, file: &FileName) -> Result<ModuleData, anyhow::Error> {
         let (source, path) = match file {
             FileName::Real(path) => {
-                if let Some(source) = self
+                if let Some((source, map)) = self
                     .provider
-                    .load_virtual(path)
+                    .load_virtual_with_source_map(path)
                     .map_err(|err| anyhow::Error::msg(err))?
                 {
+                    if let Some(map) = map {
+                        self.input_maps
+                            .lock()
+                            .map_err(|_| anyhow::Error::msg("Source map lock failed"))?
+                            .insert(path.clone(), map);
+                    }
                     (source, Some(path.clone()))
                 } else if path.extension().and_then(|ext| ext.to_str()) == Some("css") {
                     let (source, module_path) = self.load_css_module(path)?;
@@ -2,6 +2,7 @@ pub mod bundler;
 pub mod cache;
 pub mod css_bundler;
 pub mod parallel_bundler;
+mod source_map;
 
 pub use bundler::*;
 pub use cache::*;
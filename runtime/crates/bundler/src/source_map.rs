@@ -0,0 +1,215 @@
+//! Source map v3 composition for bundled output.
+//!
+//! SWC reports which loaded module every emitted token came from. Modules
+//! that were themselves generated (PHPX compiled to JS) hand the bundler their
+//! own map, which is applied on top so the bundle maps straight back to the
+//! original `.phpx` source.
+
+use runtime_core::vlq::{decode_vlq, encode_vlq};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// An original position: source index, line and column (all 0-based).
+type Origin = (usize, u32, u32);
+
+/// A parsed single-module input map, used to look up original positions.
+pub(crate) struct InputSourceMap {
+    sources: Vec<String>,
+    sources_content: Vec<Option<String>>,
+    /// Per generated line, `(generated column, origin)` sorted by column.
+    lines: Vec<Vec<(u32, Origin)>>,
+}
+
+impl InputSourceMap {
+    pub(crate) fn parse(json: &str) -> Result<Self, String> {
+        let value: serde_json::Value =
+            serde_json::from_str(json).map_err(|err| format!("invalid source map: {err}"))?;
+        let strings = |key: &str| -> Vec<Option<String>> {
+            value
+                .get(key)
+                .and_then(|v| v.as_array())
+                .map(|items| {
+                    items
+                        .iter()
+                        .map(|item| item.as_str().map(str::to_string))
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        let sources = strings("sources")
+            .into_iter()
+            .map(Option::unwrap_or_default)
+            .collect();
+        let sources_content = strings("sourcesContent");
+        let mappings = value
+            .get("mappings")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| "source map has no mappings".to_string())?;
+
+        let mut lines = Vec::new();
+        let (mut source, mut line, mut column) = (0i64, 0i64, 0i64);
+        for encoded_line in mappings.split(';') {
+            let mut segments = Vec::new();
+            let mut generated_column = 0i64;
+            for segment in encoded_line.split(',').filter(|s| !s.is_empty()) {
+                let fields = decode_vlq(segment)?;
+                generated_column += fields[0];
+                if fields.len() >= 4 {
+                    source += fields[1];
+                    line += fields[2];
+                    column += fields[3];
+                    segments.push((
+                        generated_column as u32,
+                        (source as usize, line as u32, column as u32),
+                    ));
+                }
+            }
+            segments.sort_by_key(|(col, _)| *col);
+            lines.push(segments);
+        }
+
+        Ok(Self {
+            sources,
+            sources_content,
+            lines,
+        })
+    }
+
+    /// Rename absolute sources under `root` to root-relative paths.
+    pub(crate) fn strip_source_prefix(&mut self, root: &Path) {
+        for source in &mut self.sources {
+            if let Ok(relative) = Path::new(source.as_str()).strip_prefix(root) {
+                *source = relative.to_string_lossy().replace('\\', "/");
+            }
+        }
+    }
+
+    /// Closest mapping at or before the position, falling back to the last
+    /// mapping of an earlier line (statements often span several lines).
+    fn lookup(&self, line: u32, column: u32) -> Option<Origin> {
+        let line = line as usize;
+        if let Some(segments) = self.lines.get(line)
+            && let Some((_, origin)) = segments.iter().rev().find(|(col, _)| *col <= column)
+        {
+            return Some(*origin);
+        }
+        self.lines[..line.min(self.lines.len())]
+            .iter()
+            .rev()
+            .find_map(|segments| segments.last().map(|(_, origin)| *origin))
+    }
+}
+
+/// Builds the bundle's map, deduplicating sources across modules.
+#[derive(Default)]
+pub(crate) struct SourceMapBuilder {
+    sources: Vec<String>,
+    sources_content: Vec<Option<String>>,
+    source_ids: HashMap<String, usize>,
+    mappings: Vec<(u32, u32, Origin)>,
+}
+
+impl SourceMapBuilder {
+    pub(crate) fn add_source(&mut self, name: &str, content: Option<String>) -> usize {
+        if let Some(id) = self.source_ids.get(name) {
+            return *id;
+        }
+        let id = self.sources.len();
+        self.sources.push(name.to_string());
+        self.sources_content.push(content);
+        self.source_ids.insert(name.to_string(), id);
+        id
+    }
+
+    pub(crate) fn add(&mut self, generated_line: u32, generated_column: u32, origin: Origin) {
+        self.mappings
+            .push((generated_line, generated_column, origin));
+    }
+
+    /// Map a position in a generated module through its input map, adding the
+    /// original source on first use.
+    pub(crate) fn add_through(
+        &mut self,
+        generated_line: u32,
+        generated_column: u32,
+        input: &InputSourceMap,
+        line: u32,
+        column: u32,
+    ) {
+        let Some((source, line, column)) = input.lookup(line, column) else {
+            return;
+        };
+        let Some(name) = input.sources.get(source) else {
+            return;
+        };
+        let content = input.sources_content.get(source).cloned().flatten();
+        let id = self.add_source(name, content);
+        self.add(generated_line, generated_column, (id, line, column));
+    }
+
+    pub(crate) fn to_json(&self, file: &str) -> String {
+        let mut mappings = self.mappings.clone();
+        mappings.sort_by_key(|(line, column, _)| (*line, *column));
+        mappings.dedup_by_key(|(line, column, _)| (*line, *column));
+
+        let mut out = String::new();
+        let mut current_line = 0;
+        let mut first_in_line = true;
+        let (mut prev_column, mut prev_source, mut prev_line, mut prev_source_column) =
+            (0i64, 0i64, 0i64, 0i64);
+        for (line, column, (source, source_line, source_column)) in mappings {
+            while current_line < line {
+                out.push(';');
+                current_line += 1;
+                prev_column = 0;
+                first_in_line = true;
+            }
+            if !first_in_line {
+                out.push(',');
+            }
+            first_in_line = false;
+            encode_vlq(&mut out, column as i64 - prev_column);
+            encode_vlq(&mut out, source as i64 - prev_source);
+            encode_vlq(&mut out, source_line as i64 - prev_line);
+            encode_vlq(&mut out, source_column as i64 - prev_source_column);
+            prev_column = column as i64;
+            prev_source = source as i64;
+            prev_line = source_line as i64;
+            prev_source_column = source_column as i64;
+        }
+
+        serde_json::json!({
+            "version": 3,
+            "file": file,
+            "sources": self.sources,
+            "sourcesContent": self.sources_content,
+            "names": [],
+            "mappings": out,
+        })
+        .to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chains_positions_through_an_input_map() {
+        // Generated line 2 of `index.phpx.js` came from line 1 of `index.phpx`;
+        // lines 3 and 4 belong to the same statement.
+        let input = InputSourceMap::parse(
+            r#"{"version":3,"sources":["index.phpx"],"sourcesContent":["<src>"],"names":[],"mappings":";;AACA,IAAI"}"#,
+        )
+        .unwrap();
+        let mut builder = SourceMapBuilder::default();
+        builder.add_through(0, 4, &input, 2, 0);
+        builder.add_through(1, 0, &input, 4, 0);
+        builder.add_through(2, 0, &input, 0, 0);
+
+        let json: serde_json::Value = serde_json::from_str(&builder.to_json("out.js")).unwrap();
+        assert_eq!(json["sources"], serde_json::json!(["index.phpx"]));
+        assert_eq!(json["sourcesContent"], serde_json::json!(["<src>"]));
+        assert_eq!(json["mappings"], "IACA;AAAI");
+    }
+}
@@ -0,0 +1,105 @@
+use core::{CommandSpec, Context, ParamSpec, Registry};
+use pm::{Severity, run_audit};
+use stdio::{error, log, raw, warn};
+
+const COMMAND: CommandSpec = CommandSpec {
+    name: "audit",
+    category: "package",
+    summary: "check deka.lock against vulnerability advisories",
+    aliases: &[],
+    subcommands: &[],
+    handler: cmd,
+};
+
+pub fn register(registry: &mut Registry) {
+    registry.add_command(COMMAND);
+    registry.add_param(ParamSpec {
+        name: "--advisories",
+        description: "OSV advisory file, directory or URL (default: linkhash /api/advisories)",
+    });
+    registry.add_param(ParamSpec {
+        name: "--audit-level",
+        description: "exit non-zero for findings at or above this severity (low|moderate|high|critical)",
+    });
+}
+
+fn cmd(context: &Context) {
+    let level = match context.args.params.get("--audit-level") {
+        Some(raw) => match Severity::parse(raw) {
+            Some(level) => level,
+            None => {
+                error(
+                    "audit",
+                    &format!(
+                        "invalid --audit-level {} (expected low|moderate|high|critical)",
+                        raw
+                    ),
+                );
+                std::process::exit(1);
+            }
+        },
+        None => Severity::Low,
+    };
+    let source = context.args.params.get("--advisories").map(String::as_str);
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let findings = match runtime.block_on(run_audit(source)) {
+        Ok(findings) => findings,
+        Err(err) => {
+            error("audit", &err.to_string());
+            std::process::exit(1);
+        }
+    };
+    let failing = findings.iter().filter(|f| f.severity >= level).count();
+
+    if context.args.flags.contains_key("--json") {
+        let out = serde_json::json!({
+            "ok": failing == 0,
+            "auditLevel": level.as_str(),
+            "findings": findings.iter().map(|f| f.to_json()).collect::<Vec<_>>(),
+        });
+        raw(&serde_json::to_string_pretty(&out).unwrap_or_else(|_| "{}".to_string()));
+    } else if findings.is_empty() {
+        log("audit", "no known vulnerabilities in deka.lock");
+    } else {
+        for finding in &findings {
+            raw(&format!(
+                "{:<9} {}@{} ({})  {}",
+                finding.severity.as_str(),
+                finding.name,
+                finding.version,
+                finding.ecosystem.as_str(),
+                finding.advisory
+            ));
+            if !finding.summary.is_empty() {
+                raw(&format!("          {}", finding.summary));
+            }
+            raw(&format!(
+                "          affected: {}",
+                finding.affected.join(" || ")
+            ));
+            if finding.fixed.is_empty() {
+                raw("          fixed in: no fix available");
+            } else {
+                raw(&format!("          fixed in: {}", finding.fixed.join(", ")));
+            }
+            if let Some(url) = &finding.url {
+                raw(&format!("          {}", url));
+            }
+        }
+        let message = format!(
+            "{} finding(s), {} at or above {}",
+            findings.len(),
+            failing,
+            level.as_str()
+        );
+        if failing > 0 {
+            error("audit", &message);
+        } else {
+            warn("audit", &message);
+        }
+    }
+
+    if failing > 0 {
+        std::process::exit(1);
+    }
+}
@@ -1,6 +1,10 @@
-use bundler::{bundle_virtual_entry, BundleOptions, VirtualSource};
+use bundler::{bundle_virtual_entry_with_source_map, BundleOptions, VirtualSource};
 use core::{CommandSpec, Context, ParamSpec, Registry};
-use phpx_js::{compile_phpx_source_to_js, parse_source_module_meta, SourceModuleMeta};
+use modules_php::validation::diagnostics::{ErrorFormat, ERROR_FORMAT_ENV};
+use phpx_js::{
+    compile_phpx_source_to_js_with_source_map, parse_source_module_meta, SourceMap,
+    SourceModuleMeta,
+};
 use std::collections::{BTreeMap, BTreeSet};
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -31,11 +35,51 @@ pub fn register(registry: &mut Registry) {
         name: "--out",
         description: "output JavaScript file path",
     });
+    registry.add_param(ParamSpec {
+        name: "--error-format",
+        description: "diagnostic output for build and run: human (default) or json",
+    });
 }
 
 pub fn cmd(context: &Context) {
+    let format = match apply_error_format(context) {
+        Ok(format) => format,
+        Err(err) => {
+            stdio::error("build", &err);
+            return;
+        }
+    };
     if let Err(err) = run(context) {
-        stdio::error("build", &err);
+        report_error("build", &err, format);
+    }
+}
+
+/// Reads `--error-format` and exports it as `DEKA_ERROR_FORMAT` so every PHPX
+/// compile in this process (including the runtime's module loader) reports
+/// diagnostics in the requested format.
+pub(crate) fn apply_error_format(context: &Context) -> Result<ErrorFormat, String> {
+    let Some(raw) = context.args.params.get("--error-format") else {
+        return Ok(ErrorFormat::from_env());
+    };
+    let format = ErrorFormat::parse(raw).ok_or_else(|| {
+        format!(
+            "invalid --error-format '{}'; expected 'human' or 'json'",
+            raw
+        )
+    })?;
+    // SAFETY: set once during CLI startup, before the runtime spawns worker threads.
+    unsafe {
+        std::env::set_var(ERROR_FORMAT_ENV, format.as_str());
+    }
+    Ok(format)
+}
+
+/// JSON diagnostics are emitted verbatim so tools can parse stderr line by line.
+pub(crate) fn report_error(action: &str, err: &str, format: ErrorFormat) {
+    if format == ErrorFormat::Json && err.trim_start().starts_with('{') {
+        eprint!("{}", err);
+    } else {
+        stdio::error(action, err);
     }
 }
 
@@ -689,6 +733,7 @@ fn inject_app_html(index_html: &str, app_html: &str) -> String {
 
 struct JsBuildOutput {
     js: String,
+    source_map: Option<SourceMap>,
     meta: SourceModuleMeta,
     project_root: PathBuf,
 }
@@ -701,7 +746,14 @@ fn build_single_file_to_path(input_path: &Path, output_path: &Path) -> Result<()
             .map_err(|err| format!("failed to create {}: {}", parent.display(), err))?;
     }
 
-    fs::write(output_path, output.js)
+    let js = match output.source_map {
+        Some(mut map) => {
+            map.file = file_name(output_path);
+            write_with_source_map(output_path, &output.js, &map.to_json())?
+        }
+        None => output.js,
+    };
+    fs::write(output_path, js)
         .map_err(|err| format!("failed to write {}: {}", output_path.display(), err))?;
 
     let import_map_path = resolve_import_map_path(output_path);
@@ -720,10 +772,18 @@ fn build_single_file_bundle_to_path(
     let output = build_single_file_to_string(input_path)?;
     let prelude = phpx_js::build_stdlib_prelude(&output.project_root)?;
     let entry_js = format!("{prelude}\n{}", output.js);
+    let entry_map = output.source_map.map(|mut map| {
+        map.offset_generated_lines(prelude.matches('\n').count() + 1);
+        map.to_json()
+    });
     let entry_path = fs::canonicalize(input_path)
         .map_err(|err| format!("failed to resolve {}: {}", input_path.display(), err))?;
-    let provider = Arc::new(PhpxBundleProvider::new(entry_path.clone(), entry_js));
-    let bundle = bundle_virtual_entry(
+    let provider = Arc::new(PhpxBundleProvider::new(
+        entry_path.clone(),
+        entry_js,
+        entry_map,
+    ));
+    let (bundle, map) = bundle_virtual_entry_with_source_map(
         &entry_path,
         BundleOptions {
             project_root: output.project_root,
@@ -738,12 +798,31 @@ fn build_single_file_bundle_to_path(
             .map_err(|err| format!("failed to create {}: {}", parent.display(), err))?;
     }
 
+    let bundle = write_with_source_map(output_path, &bundle, &map)?;
     fs::write(output_path, bundle)
         .map_err(|err| format!("failed to write {}: {}", output_path.display(), err))?;
 
     Ok(())
 }
 
+/// Write `map` to `<output>.map` and return `js` with a comment pointing at it.
+fn write_with_source_map(output_path: &Path, js: &str, map: &str) -> Result<String, String> {
+    let map_path = PathBuf::from(format!("{}.map", output_path.display()));
+    fs::write(&map_path, map)
+        .map_err(|err| format!("failed to write {}: {}", map_path.display(), err))?;
+    Ok(format!(
+        "{}\n{}",
+        js.trim_end(),
+        SourceMap::to_url_comment(&file_name(&map_path))
+    ))
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
 fn build_single_file_to_string(input_path: &Path) -> Result<JsBuildOutput, String> {
     let input = input_path
         .to_str()
@@ -756,10 +835,11 @@ fn build_single_file_to_string(input_path: &Path) -> Result<JsBuildOutput, Strin
     let project_root = resolve_project_root(input_path)?;
     ensure_project_layout(&project_root, &meta)?;
 
-    let js = compile_phpx_source_to_js(&source, input, meta.clone())?;
+    let (js, source_map) = compile_phpx_source_to_js_with_source_map(&source, input, meta.clone())?;
 
     Ok(JsBuildOutput {
         js,
+        source_map,
         meta,
         project_root,
     })
@@ -768,21 +848,30 @@ fn build_single_file_to_string(input_path: &Path) -> Result<JsBuildOutput, Strin
 struct PhpxBundleProvider {
     entry_path: PathBuf,
     entry_source: String,
+    entry_map: Option<String>,
 }
 
 impl PhpxBundleProvider {
-    fn new(entry_path: PathBuf, entry_source: String) -> Self {
+    fn new(entry_path: PathBuf, entry_source: String, entry_map: Option<String>) -> Self {
         Self {
             entry_path,
             entry_source,
+            entry_map,
         }
     }
 }
 
 impl VirtualSource for PhpxBundleProvider {
     fn load_virtual(&self, path: &Path) -> Result<Option<String>, String> {
+        Ok(self.load_virtual_with_source_map(path)?.map(|(js, _)| js))
+    }
+
+    fn load_virtual_with_source_map(
+        &self,
+        path: &Path,
+    ) -> Result<Option<(String, Option<String>)>, String> {
         if path == self.entry_path {
-            return Ok(Some(self.entry_source.clone()));
+            return Ok(Some((self.entry_source.clone(), self.entry_map.clone())));
         }
 
         if path.extension().and_then(|ext| ext.to_str()) != Some("phpx") {
@@ -795,8 +884,8 @@ impl VirtualSource for PhpxBundleProvider {
         let source =
             fs::read_to_string(path).map_err(|err| format!("failed to read {}: {}", input, err))?;
         let meta = parse_source_module_meta(&source);
-        let js = compile_phpx_source_to_js(&source, input, meta)?;
-        Ok(Some(js))
+        let (js, map) = compile_phpx_source_to_js_with_source_map(&source, input, meta)?;
+        Ok(Some((js, map.map(|map| map.to_json()))))
     }
 }
 
@@ -1000,6 +1089,61 @@ $data = ["name" => "Sami", "role" => "owner"]
         assert!(js.contains("{\"name\": \"Sami\", \"role\": \"owner\"}"));
     }
 
+    #[test]
+    fn string_keyed_arrays_are_plain_objects_that_survive_structured_clone() {
+        let source = r#"
+$data = ["name" => "Sami", "role" => "owner"];
+$more = [...$data, "team" => "core"];
+"#;
+        let arena = Bump::new();
+        let mut parser =
+            Parser::new_with_mode(Lexer::new(source.as_bytes()), &arena, ParserMode::Phpx);
+        let program = parser.parse_program();
+        let js = emit_js_from_ast(&program, source.as_bytes(), SourceModuleMeta::empty())
+            .expect("subset emit");
+        assert!(js.contains("let data = {\"name\": \"Sami\", \"role\": \"owner\"};"));
+
+        // Proxies cannot be cloned, so run the module when node is around.
+        let Ok(mut node) = std::process::Command::new("node")
+            .arg("--input-type=module")
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .spawn()
+        else {
+            return;
+        };
+        let script = format!(
+            "{}\nconsole.log(JSON.stringify(structuredClone([globalThis.data, globalThis.more])));\n",
+            js
+        );
+        std::io::Write::write_all(&mut node.stdin.take().expect("stdin"), script.as_bytes())
+            .expect("write script");
+        let output = node.wait_with_output().expect("run node");
+        assert!(output.status.success());
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout).trim(),
+            r#"[{"name":"Sami","role":"owner"},{"name":"Sami","role":"owner","team":"core"}]"#
+        );
+    }
+
+    #[test]
+    fn keyed_arrays_keep_integer_like_keys_in_insertion_order() {
+        let source = r#"
+$data = ["x" => 1, 10 => "ten", "2" => "two"]
+"#;
+        let arena = Bump::new();
+        let mut parser =
+            Parser::new_with_mode(Lexer::new(source.as_bytes()), &arena, ParserMode::Phpx);
+        let program = parser.parse_program();
+        let js = emit_js_from_ast(&program, source.as_bytes(), SourceModuleMeta::empty())
+            .expect("subset emit");
+        assert!(js.contains(
+            "globalThis.__phpx_array([{ key: \"x\", value: 1 }, { key: 10, value: \"ten\" }, { key: \"2\", value: \"two\" }])"
+        ));
+        assert!(js.contains("return globalThis.__phpx_assoc(keys.map("));
+        assert!(js.contains("ownKeys(target) { return [...order,"));
+    }
+
     #[test]
     fn emits_method_and_nullsafe_access() {
         let source = r#"
@@ -1040,7 +1184,7 @@ foreach ($items as $idx => $item) {
             .expect("subset emit");
         assert!(js.contains("while ((i < 2))"));
         assert!(js.contains("for (let j = 0; (j < 2); (j = (j + 1)))"));
-        assert!(js.contains("for (const [idx , item] of Object.entries(items))"));
+        assert!(js.contains("for (const [idx , item] of globalThis.__phpx_entries(items))"));
     }
 
     #[test]
@@ -1397,47 +1541,303 @@ $out = `echo hi`;
     }
 
     #[test]
-    fn reports_specific_error_for_yield_expression() {
+    fn lowers_yield_to_generator_functions() {
         let source = r#"
-function gen() {
-  yield 1;
+function gen($n) {
+  $sent = yield 1;
+  yield "k" => $n;
+  yield from inner();
+  $fn = function() { return 1; };
+}
+function plain() {
+  $fn = function() { yield 2; };
+  return $fn;
+}
+foreach (gen(1) as $v) {
+  echo $v;
 }
 "#;
         let arena = Bump::new();
         let mut parser =
             Parser::new_with_mode(Lexer::new(source.as_bytes()), &arena, ParserMode::Phpx);
         let program = parser.parse_program();
-        let err = emit_js_from_ast(&program, source.as_bytes(), SourceModuleMeta::empty())
-            .expect_err("subset emit should fail");
-        assert!(err.contains("yield expressions are not supported"));
+        let js = emit_js_from_ast(&program, source.as_bytes(), SourceModuleMeta::empty())
+            .expect("subset emit");
+        assert!(
+            js.contains("function gen(n) {\nreturn globalThis.__phpx_generator(function* () {\n")
+        );
+        assert!(js.contains("sent = ((yield 1) ?? null)"));
+        assert!(js.contains("((yield new globalThis.__phpx_yield_pair(\"k\", n)) ?? null)"));
+        assert!(js.contains("(yield* globalThis.__phpx_yield_from("));
+        assert!(js.contains("}.apply(this, arguments));"));
+        assert!(js.contains(
+            "function plain() {\nlet fn = function() {\nreturn globalThis.__phpx_generator("
+        ));
+        assert!(js.contains("for (const v of globalThis.__phpx_values(gen(1)))"));
+        assert!(js.contains("globalThis.Generator = class Generator"));
     }
 
     #[test]
-    fn reports_specific_error_for_namespace_statement() {
+    fn lowers_array_append_and_complex_array_literals() {
         let source = r#"
-namespace Demo;
+function build($key, $rest) {
+  $items[] = 1;
+  $items[] .= "x";
+  $groups[$key][] = 2;
+  $mixed = ["a", $key => 1, ...$rest];
+  $computed = [$key => 1, "b" => 2];
+  return [$items, $groups, $mixed, $computed];
+}
 "#;
         let arena = Bump::new();
         let mut parser =
             Parser::new_with_mode(Lexer::new(source.as_bytes()), &arena, ParserMode::Phpx);
         let program = parser.parse_program();
+        let js = emit_js_from_ast(&program, source.as_bytes(), SourceModuleMeta::empty())
+            .expect("subset emit");
+        assert!(js.contains("let items = [];\nglobalThis.__phpx_array_push((items ??= []), 1);"));
+        assert!(js.contains("globalThis.__phpx_array_push((items ??= []), ('' + \"x\"))"));
+        assert!(
+            js.contains("let groups = [];\nglobalThis.__phpx_array_push((groups[key] ??= []), 2);")
+        );
+        assert!(js.contains(
+            "globalThis.__phpx_array([{ value: \"a\" }, { key: key, value: 1 }, { spread: rest }])"
+        ));
+        assert!(js.contains(
+            "globalThis.__phpx_array([{ key: key, value: 1 }, { key: \"b\", value: 2 }])"
+        ));
+    }
+
+    #[test]
+    fn reports_specific_error_for_use_function_statement() {
+        let source = r#"
+namespace Demo;
+use function Demo\Util\helper;
+"#;
+        let arena = Bump::new();
+        let mut parser = Parser::new_with_mode(
+            Lexer::new(source.as_bytes()),
+            &arena,
+            ParserMode::PhpxInternal,
+        );
+        let program = parser.parse_program();
         let err = emit_js_from_ast(&program, source.as_bytes(), SourceModuleMeta::empty())
             .expect_err("subset emit should fail");
-        assert!(err.contains("namespace declarations are not supported"));
+        assert!(err.contains("use function/const declarations are not supported"));
+    }
+
+    #[test]
+    fn lowers_classes_with_promotion_and_inheritance() {
+        let source = r#"
+namespace App\Models;
+
+abstract class Base {
+  const KIND = 'base';
+  protected static $count = 0;
+  public function __construct(protected string $label) {
+    static::$count = static::$count + 1;
+  }
+  abstract public function area(): float;
+}
+
+class Square extends Base {
+  private $cache = null;
+  public function __construct(private float $side) {
+    parent::__construct('square');
+  }
+  public function area(): float {
+    return $this->side * $this->side;
+  }
+  public static function unit() {
+    return new static(1);
+  }
+}
+$name = Square::class;
+"#;
+        let arena = Bump::new();
+        let mut parser = Parser::new_with_mode(
+            Lexer::new(source.as_bytes()),
+            &arena,
+            ParserMode::PhpxInternal,
+        );
+        let program = parser.parse_program();
+        assert!(program.errors.is_empty(), "{:?}", program.errors);
+        let js = emit_js_from_ast(&program, source.as_bytes(), SourceModuleMeta::empty())
+            .expect("subset emit");
+        assert!(js.contains("class Base {"));
+        assert!(js.contains("  static KIND = \"base\";"));
+        assert!(js.contains("class Square extends Base {"));
+        assert!(js.contains("  #cache = null;"));
+        assert!(js.contains("super(\"square\");\nthis.#side = side;"));
+        assert!(js.contains("return (this.#side * this.#side);"));
+        assert!(js.contains("return new this(1);"));
+        assert!(!js.contains("area() {\n  }"));
+        assert!(
+            js.contains("globalThis.__phpx_register_class(\"App\\\\Models\\\\Square\", Square);")
+        );
+        assert!(js.contains("let name = \"App\\\\Models\\\\Square\";"));
+    }
+
+    #[test]
+    fn package_methods_closures_and_awaits_run_in_the_package_scope() {
+        let source = r#"
+class Box {
+  public function get() { return probe('method'); }
+}
+
+export function make() {
+  return ['cb' => function() { return probe('closure'); }, 'arrow' => fn() => probe('arrow')];
+}
+
+async function slow() {
+  $t = await tick();
+  return probe('after');
+}
+
+export { slow }
+"#;
+        let js = phpx_js::compile_phpx_source_to_js(
+            source,
+            "php_modules/@acme/widgets/index.phpx",
+            parse_source_module_meta(source),
+        )
+        .expect("compile");
+        assert!(
+            js.contains(
+                "get() {\nreturn __deka$scope_call(() => {\nreturn globalThis.probe(\"method\");"
+            ),
+            "{}",
+            js
+        );
+        assert!(js.contains(
+            "{\"cb\": function() {\nreturn __deka$scope_call(() => {\nreturn globalThis.probe(\"closure\");"
+        ));
+        assert!(js.contains(
+            "\"arrow\": () => __deka$scope_call(() => globalThis.probe(\"arrow\"), undefined, [])"
+        ));
+        assert!(js.contains(
+            "async function slow() {\nconst __deka$scope = __deka$scope_begin();\ntry {\n"
+        ));
+        assert!(js.contains(
+            "let t = __deka$scope_resumed(__deka$scope, await __deka$scope_await(__deka$scope, globalThis.tick()));"
+        ));
+        assert!(js.contains("} finally {\n__deka$scope_end(__deka$scope);\n}"));
     }
 
     #[test]
-    fn reports_specific_error_for_class_statement() {
+    fn emits_parent_classes_before_subclasses_declared_earlier() {
         let source = r#"
-class User {}
+class C extends B implements Named {}
+class B extends A {}
+interface Named extends Base {}
+interface Base {}
+class A implements Named {}
 "#;
         let arena = Bump::new();
-        let mut parser =
-            Parser::new_with_mode(Lexer::new(source.as_bytes()), &arena, ParserMode::Phpx);
+        let mut parser = Parser::new_with_mode(
+            Lexer::new(source.as_bytes()),
+            &arena,
+            ParserMode::PhpxInternal,
+        );
+        let program = parser.parse_program();
+        assert!(program.errors.is_empty(), "{:?}", program.errors);
+        let js = emit_js_from_ast(&program, source.as_bytes(), SourceModuleMeta::empty())
+            .expect("subset emit");
+        let at = |needle: &str| js.find(needle).expect(needle);
+        assert!(at("class Base {") < at("class Named {"));
+        assert!(at("class Named {") < at("class A {"));
+        assert!(at("class A {") < at("class B extends A {"));
+        assert!(at("class B extends A {") < at("class C extends B {"));
+    }
+
+    #[test]
+    fn private_keys_only_apply_to_receivers_of_the_same_class() {
+        let source = r#"
+class Secret {
+  private $value = 'inner';
+  public function peek($other) { return $other->value; }
+  public function same(self $other) { return $other->value; }
+  public function mine() { return $this->value . (new Secret())->value; }
+}
+"#;
+        let arena = Bump::new();
+        let mut parser = Parser::new_with_mode(
+            Lexer::new(source.as_bytes()),
+            &arena,
+            ParserMode::PhpxInternal,
+        );
+        let program = parser.parse_program();
+        assert!(program.errors.is_empty(), "{:?}", program.errors);
+        let js = emit_js_from_ast(&program, source.as_bytes(), SourceModuleMeta::empty())
+            .expect("subset emit");
+        assert!(js.contains("peek(other) {\nreturn other.value;"), "{}", js);
+        assert!(js.contains("same(other) {\nreturn other.#value;"), "{}", js);
+        assert!(
+            js.contains("return (this.#value + new Secret().#value);"),
+            "{}",
+            js
+        );
+    }
+
+    #[test]
+    fn lowers_interfaces_traits_and_use_aliases() {
+        let source = r#"
+namespace Lib {
+  interface Named {}
+  trait Greets {
+    public function greet() { return 'hi ' . $this->name; }
+    public function wave() { return 'wave'; }
+  }
+}
+
+namespace App {
+  use Lib\Named;
+  use Lib\Greets;
+  use Vendor\Clock as C;
+
+  class User implements Named {
+    use Greets { wave as private; }
+    public $name = 'ada';
+  }
+  $ok = (new User()) instanceof Named;
+  $now = C::now();
+}
+"#;
+        let arena = Bump::new();
+        let mut parser = Parser::new_with_mode(
+            Lexer::new(source.as_bytes()),
+            &arena,
+            ParserMode::PhpxInternal,
+        );
+        let program = parser.parse_program();
+        assert!(program.errors.is_empty(), "{:?}", program.errors);
+        let js = emit_js_from_ast(&program, source.as_bytes(), SourceModuleMeta::empty())
+            .expect("subset emit");
+        assert!(js.contains("class Named {\n  static [Symbol.hasInstance](value)"));
+        assert!(js.contains("class User {\n  static __phpx_interfaces = [Named];"));
+        assert!(js.contains("  greet() {"));
+        assert!(js.contains("  #wave() {"));
+        assert!(js.contains("(new User() instanceof Named)"));
+        assert!(js.contains("globalThis.__phpx_class(\"Vendor\\\\Clock\").now()"));
+    }
+
+    #[test]
+    fn reports_trait_declared_in_another_module() {
+        let source = r#"
+class User {
+  use Missing;
+}
+"#;
+        let arena = Bump::new();
+        let mut parser = Parser::new_with_mode(
+            Lexer::new(source.as_bytes()),
+            &arena,
+            ParserMode::PhpxInternal,
+        );
         let program = parser.parse_program();
         let err = emit_js_from_ast(&program, source.as_bytes(), SourceModuleMeta::empty())
             .expect_err("subset emit should fail");
-        assert!(err.contains("class-like declarations are not supported"));
+        assert!(err.contains("trait Missing must be declared in the same module"));
     }
 
     #[test]
@@ -1647,4 +2047,41 @@ import { y } from 'https://cdn.example/x.js'
         assert!(!imports.contains_key("./local.js"));
         assert!(!imports.contains_key("https://cdn.example/x.js"));
     }
+
+    #[test]
+    fn maps_method_body_statements_back_to_phpx_source() {
+        let source = r#"
+class Greeter {
+  public function greet($name) {
+    $message = "hi " . $name;
+    return $message;
+  }
+}
+$text = (new Greeter())->greet('ada');
+"#;
+        let arena = Bump::new();
+        let mut parser =
+            Parser::new_with_mode(Lexer::new(source.as_bytes()), &arena, ParserMode::Phpx);
+        let program = parser.parse_program();
+        let (js, map) = phpx_js::emit_js_from_ast_with_source_map(
+            &program,
+            source,
+            SourceModuleMeta::empty(),
+            "app/greeter.phpx",
+        )
+        .expect("subset emit");
+        assert!(!js.contains('\0'));
+        assert_eq!(map.source, "app/greeter.phpx");
+        let return_line = js
+            .lines()
+            .position(|line| line.trim() == "return message;")
+            .expect("method return");
+        let mapping = map
+            .mappings
+            .iter()
+            .find(|mapping| mapping.generated_line == return_line)
+            .expect("method body mapping");
+        assert_eq!((mapping.source_line, mapping.source_column), (4, 4));
+        assert!(map.to_json().contains("\"sourcesContent\""));
+    }
 }
@@ -742,7 +742,9 @@ fn render_name(name: &Name<'_>, source: &[u8]) -> String {
 
 fn render_ast_type(ty: &AstType<'_>, source: &[u8]) -> String {
     match ty {
-        AstType::Simple(tok) => String::from_utf8_lossy(tok.text(source)).to_string(),
+        AstType::Simple(tok) | AstType::Literal(tok) => {
+            String::from_utf8_lossy(tok.text(source)).to_string()
+        }
         AstType::Name(name) => render_name(name, source),
         AstType::Union(parts) => parts
             .iter()
@@ -755,6 +757,7 @@ fn render_ast_type(ty: &AstType<'_>, source: &[u8]) -> String {
             .collect::<Vec<_>>()
             .join("&"),
         AstType::Nullable(inner) => format!("?{}", render_ast_type(inner, source)),
+        AstType::KeyOf(inner) => format!("keyof {}", render_ast_type(inner, source)),
         AstType::ObjectShape(fields) => {
             let rendered = fields
                 .iter()
@@ -772,6 +775,30 @@ fn render_ast_type(ty: &AstType<'_>, source: &[u8]) -> String {
                 .join(", ");
             format!("{{ {} }}", rendered)
         }
+        AstType::Tuple(items) => format!(
+            "[{}]",
+            items
+                .iter()
+                .map(|item| render_ast_type(item, source))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        AstType::Function {
+            params,
+            return_type,
+        } => {
+            let rendered = params
+                .iter()
+                .map(|param| render_ast_type(param, source))
+                .collect::<Vec<_>>()
+                .join(", ");
+            match return_type {
+                Some(return_type) => {
+                    format!("fn({}): {}", rendered, render_ast_type(return_type, source))
+                }
+                None => format!("fn({})", rendered),
+            }
+        }
         AstType::Applied { base, args } => format!(
             "{}<{}>",
             render_ast_type(base, source),
@@ -56,6 +56,16 @@ pub fn register(registry: &mut Registry) {
         aliases: &[],
         description: "rehash php package integrity and update deka.lock",
     });
+    registry.add_flag(FlagSpec {
+        name: "--offline",
+        aliases: &[],
+        description: "install from the package cache only, without network access",
+    });
+    registry.add_flag(FlagSpec {
+        name: "--frozen-lockfile",
+        aliases: &[],
+        description: "fail instead of updating deka.lock",
+    });
     registry.add_param(ParamSpec {
         name: "--payload",
         description: "path to a JSON payload describing the install",
@@ -128,6 +138,8 @@ fn build_payload(context: &Context) -> Result<InstallPayload> {
             prompt: false,
             quiet: false,
             rehash: false,
+            offline: false,
+            frozen_lockfile: false,
         }
     };
 
@@ -156,6 +168,12 @@ fn apply_flags(payload: &mut InstallPayload, context: &Context) {
     if context.args.flags.contains_key("--rehash") {
         payload.rehash = true;
     }
+    if context.args.flags.contains_key("--offline") {
+        payload.offline = true;
+    }
+    if context.args.flags.contains_key("--frozen-lockfile") {
+        payload.frozen_lockfile = true;
+    }
 }
 
 fn parse_spec_list(value: &str) -> Vec<String> {
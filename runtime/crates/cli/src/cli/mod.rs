@@ -5,6 +5,8 @@ use stdio::{ascii, error as stdio_error, raw};
 
 // define & export cli's submodules
 #[cfg(feature = "native")]
+pub mod audit;
+#[cfg(feature = "native")]
 pub mod auth;
 #[cfg(feature = "native")]
 pub mod auth_store;
@@ -22,14 +24,20 @@ pub mod install;
 #[cfg(feature = "native")]
 pub mod lsp;
 #[cfg(feature = "native")]
+pub mod outdated;
+#[cfg(feature = "native")]
 pub mod pkg;
 #[cfg(feature = "native")]
 pub mod publish;
 #[cfg(feature = "native")]
 pub mod release;
 #[cfg(feature = "native")]
+pub mod remove;
+#[cfg(feature = "native")]
 pub mod run;
 #[cfg(feature = "native")]
+pub mod security;
+#[cfg(feature = "native")]
 pub mod self_cmd;
 #[cfg(feature = "native")]
 pub mod serve;
@@ -37,6 +45,8 @@ pub mod serve;
 pub mod task;
 #[cfg(feature = "native")]
 pub mod test;
+#[cfg(feature = "native")]
+pub mod update;
 pub mod user;
 
 pub fn register_global_flags(registry: &mut Registry) {
@@ -155,6 +165,11 @@ pub fn register_global_flags(registry: &mut Registry) {
         aliases: &[],
         description: "disable interactive security prompts",
     });
+    registry.add_flag(FlagSpec {
+        name: "--strict-security",
+        aliases: &[],
+        description: "refuse to start when the security policy has warnings",
+    });
 }
 
 pub fn register_global_params(registry: &mut Registry) {
@@ -0,0 +1,64 @@
+use core::{CommandSpec, Context, Registry};
+use pm::{OutdatedPackage, outdated_packages};
+use stdio::{error, log, raw};
+
+const COMMAND: CommandSpec = CommandSpec {
+    name: "outdated",
+    category: "package",
+    summary: "list dependencies with newer versions available",
+    aliases: &[],
+    subcommands: &[],
+    handler: cmd,
+};
+
+pub fn register(registry: &mut Registry) {
+    registry.add_command(COMMAND);
+}
+
+fn cmd(context: &Context) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let packages = match runtime.block_on(outdated_packages()) {
+        Ok(packages) => packages,
+        Err(err) => {
+            error("outdated", &err.to_string());
+            std::process::exit(1);
+        }
+    };
+    let outdated: Vec<&OutdatedPackage> = packages.iter().filter(|p| p.is_outdated()).collect();
+
+    if context.args.flags.contains_key("--json") {
+        let out: Vec<serde_json::Value> = outdated
+            .iter()
+            .map(|package| {
+                serde_json::json!({
+                    "ecosystem": package.ecosystem.as_str(),
+                    "name": package.name,
+                    "range": package.range,
+                    "current": package.current,
+                    "wanted": package.wanted,
+                    "latest": package.latest,
+                })
+            })
+            .collect();
+        raw(&serde_json::to_string_pretty(&out).unwrap_or_else(|_| "[]".to_string()));
+        return;
+    }
+    if outdated.is_empty() {
+        log("outdated", "all dependencies are up to date");
+        return;
+    }
+    raw(&format!(
+        "{:<32} {:<12} {:<12} {:<12} {}",
+        "package", "current", "wanted", "latest", "ecosystem"
+    ));
+    for package in outdated {
+        raw(&format!(
+            "{:<32} {:<12} {:<12} {:<12} {}",
+            package.name,
+            package.current.as_deref().unwrap_or("missing"),
+            package.wanted.as_deref().unwrap_or("-"),
+            package.latest.as_deref().unwrap_or("-"),
+            package.ecosystem.as_str()
+        ));
+    }
+}
@@ -1,7 +1,10 @@
 use anyhow::{Context as AnyhowContext, Result, bail};
 use core::{CommandSpec, Context, FlagSpec, ParamSpec, Registry};
+use pm::cache::compute_sha512;
+use pm::signature::{SigningIdentity, release_message};
 use serde_json::json;
 use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use stdio;
 
@@ -55,6 +58,15 @@ pub fn register(registry: &mut Registry) {
         aliases: &["-y"],
         description: "auto-apply publish guard-rail fixes",
     });
+    registry.add_param(ParamSpec {
+        name: "--signing-key",
+        description: "ed25519 private key to sign the release (default: DEKA_SIGNING_KEY or ~/.ssh/id_ed25519)",
+    });
+    registry.add_flag(FlagSpec {
+        name: "--no-sign",
+        aliases: &[],
+        description: "publish without a release signature",
+    });
 }
 
 pub fn cmd(context: &Context) {
@@ -199,6 +211,16 @@ fn build_request(context: &Context) -> Result<PublishRequest> {
     if let Some(manifest) = local_manifest {
         payload["manifest"] = manifest.raw;
     }
+    if !context.args.flags.contains_key("--no-sign") {
+        match signing_key_path(params.get("--signing-key")) {
+            Some(key_path) => {
+                payload["signature"] = sign_release(&key_path, &name, &version, &git_ref)?;
+            }
+            None => stdio::warn_simple(
+                "no signing key found (~/.ssh/id_ed25519); publishing an unsigned release",
+            ),
+        }
+    }
 
     Ok(PublishRequest {
         endpoint,
@@ -440,6 +462,59 @@ fn load_local_deka_manifest() -> Option<LocalManifest> {
     })
 }
 
+/// Explicit `--signing-key`, then `DEKA_SIGNING_KEY`, then `~/.ssh/id_ed25519`
+/// when it exists.
+fn signing_key_path(explicit: Option<&String>) -> Option<PathBuf> {
+    if let Some(path) = explicit {
+        return Some(PathBuf::from(path));
+    }
+    if let Ok(path) = std::env::var("DEKA_SIGNING_KEY") {
+        return Some(PathBuf::from(path));
+    }
+    let default = PathBuf::from(std::env::var("HOME").ok()?)
+        .join(".ssh")
+        .join("id_ed25519");
+    default.is_file().then_some(default)
+}
+
+/// Build the uncompressed release tar the way linkhash does and sign its
+/// digest. The registry rebuilds the tar from the pushed tag and rejects the
+/// publish when the digests differ. The gzip layer is not signed because its
+/// bytes change between git and zlib versions.
+fn sign_release(
+    key_path: &Path,
+    name: &str,
+    version: &str,
+    git_ref: &str,
+) -> Result<serde_json::Value> {
+    let identity = SigningIdentity::from_file(key_path)
+        .context("cannot sign the release (pass --no-sign to publish unsigned)")?;
+    let output = Command::new("git")
+        .arg("archive")
+        .arg("--format=tar")
+        .arg("--prefix=package/")
+        .arg(git_ref)
+        .output()
+        .context("failed to run `git archive`")?;
+    if !output.status.success() {
+        bail!(
+            "failed to build release tar for signing: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    let tar_digest = compute_sha512(&output.stdout);
+    let message = release_message(name, version, &tar_digest);
+    stdio::log(
+        "publish",
+        &format!("signing {} with {}", tar_digest, identity.fingerprint),
+    );
+    Ok(json!({
+        "tar_digest": tar_digest,
+        "signature": identity.sign(message.as_bytes()),
+        "key_fingerprint": identity.fingerprint,
+    }))
+}
+
 fn local_git_ref_exists(reference: &str) -> bool {
     let output = Command::new("git")
         .arg("rev-parse")
@@ -0,0 +1,31 @@
+use core::{CommandSpec, Context, Registry};
+use pm::run_remove;
+use pm::spec::Ecosystem;
+use stdio::error;
+
+const COMMAND: CommandSpec = CommandSpec {
+    name: "remove",
+    category: "package",
+    summary: "remove dependencies and prune packages nothing else needs",
+    aliases: &["rm"],
+    subcommands: &[],
+    handler: cmd,
+};
+
+pub fn register(registry: &mut Registry) {
+    registry.add_command(COMMAND);
+}
+
+fn cmd(context: &Context) {
+    let ecosystem = context
+        .args
+        .params
+        .get("--ecosystem")
+        .and_then(|value| Ecosystem::from_str(value));
+    let quiet = context.args.flags.contains_key("--quiet") || context.args.flags.contains_key("-q");
+    let names = context.args.positionals.clone();
+    if let Err(err) = run_remove(names, ecosystem, quiet) {
+        error("remove", &err.to_string());
+        std::process::exit(1);
+    }
+}
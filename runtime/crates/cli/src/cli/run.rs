@@ -25,6 +25,10 @@ pub fn register(registry: &mut Registry) {
 }
 
 pub fn cmd(context: &Context) {
+    if let Err(err) = super::build::apply_error_format(context) {
+        stdio::error("run", &err);
+        std::process::exit(1);
+    }
     if let Some(exit_code) = try_run_deka_script(context) {
         std::process::exit(exit_code);
     }
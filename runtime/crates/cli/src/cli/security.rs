@@ -0,0 +1,313 @@
+use std::path::Path;
+
+use core::{CommandSpec, Context, FlagSpec, ParamSpec, Registry, SubcommandSpec, parse_time_bound};
+use runtime_core::security_audit::{
+    AuditConfig, AuditDecision, AuditEvent, AuditFormat, AuditQuery, SQLITE_SELECT, read_jsonl,
+    summarize,
+};
+use runtime_core::security_policy::{
+    PolicyDiagnostic, PolicyDiagnosticLevel, parse_deka_security_policy, policy_to_json,
+};
+use stdio::{error, log, raw, warn};
+
+const AUDIT: SubcommandSpec = SubcommandSpec {
+    name: "audit",
+    summary: "query and summarise the security audit log",
+    aliases: &[],
+    handler: cmd_audit,
+};
+
+const CHECK: SubcommandSpec = SubcommandSpec {
+    name: "check",
+    summary: "validate deka.json and print the effective security policy",
+    aliases: &[],
+    handler: cmd_check,
+};
+
+const SUBCOMMANDS: &[SubcommandSpec] = &[AUDIT, CHECK];
+
+const COMMAND: CommandSpec = CommandSpec {
+    name: "security",
+    category: "security",
+    summary: "inspect security policy decisions",
+    aliases: &[],
+    subcommands: SUBCOMMANDS,
+    handler: cmd,
+};
+
+pub fn register(registry: &mut Registry) {
+    registry.add_command(COMMAND);
+    registry.add_flag(FlagSpec {
+        name: "--summary",
+        aliases: &[],
+        description: "group audit events by capability and decision",
+    });
+    registry.add_param(ParamSpec {
+        name: "--capability",
+        description: "filter audit events by capability (read|write|net|env|run|db|wasm)",
+    });
+    registry.add_param(ParamSpec {
+        name: "--decision",
+        description: "filter audit events by decision (allow|deny|prompt-granted|prompt-denied|persisted)",
+    });
+    registry.add_param(ParamSpec {
+        name: "--module",
+        description: "filter audit events by originating package",
+    });
+    registry.add_param(ParamSpec {
+        name: "--request",
+        description: "filter audit events by request id",
+    });
+    registry.add_param(ParamSpec {
+        name: "--target",
+        description: "filter audit events by target substring",
+    });
+    registry.add_param(ParamSpec {
+        name: "--file",
+        description: "audit log to read instead of security.audit from deka.json",
+    });
+}
+
+fn cmd(_context: &Context) {
+    error(
+        "security",
+        "missing subcommand. use: deka security audit|check",
+    );
+}
+
+fn cmd_check(context: &Context) {
+    let check = match runtime::check_security_policy(context) {
+        Ok(check) => check,
+        Err(message) => {
+            error("security", &message);
+            std::process::exit(1);
+        }
+    };
+    let blocking = check.blocking().len();
+
+    if context.args.flags.contains_key("--json") {
+        let out = serde_json::json!({
+            "ok": blocking == 0,
+            "strict": check.strict,
+            "diagnostics": check
+                .diagnostics
+                .iter()
+                .map(PolicyDiagnostic::to_json)
+                .collect::<Vec<_>>(),
+            "policy": policy_to_json(&check.policy),
+        });
+        raw(&serde_json::to_string_pretty(&out).unwrap_or_else(|_| "{}".to_string()));
+    } else {
+        for diag in &check.diagnostics {
+            match diag.level {
+                PolicyDiagnosticLevel::Error => error("security", &diag.to_string()),
+                PolicyDiagnosticLevel::Warning => warn("security", &diag.to_string()),
+            }
+        }
+        raw(
+            &serde_json::to_string_pretty(&policy_to_json(&check.policy))
+                .unwrap_or_else(|_| "{}".to_string()),
+        );
+        if blocking == 0 {
+            log("security", "policy ok");
+        } else if check.strict {
+            error(
+                "security",
+                &format!("{} blocking diagnostic(s) (strict mode)", blocking),
+            );
+        } else {
+            error("security", &format!("{} error(s)", blocking));
+        }
+    }
+
+    if blocking > 0 {
+        std::process::exit(1);
+    }
+}
+
+fn cmd_audit(context: &Context) {
+    let cwd = context.env.cwd.clone();
+    let config = match audit_config(context, &cwd) {
+        Ok(config) => config,
+        Err(message) => audit_error(&message),
+    };
+    let path = config.resolve_path(&cwd);
+    if !path.exists() {
+        log(
+            "security",
+            &format!("no audit events recorded yet ({})", path.display()),
+        );
+        return;
+    }
+    let events = match read_events(&config, &path) {
+        Ok(events) => events,
+        Err(message) => audit_error(&message),
+    };
+    let query = match audit_query(context) {
+        Ok(query) => query,
+        Err(message) => audit_error(&message),
+    };
+    let matched: Vec<AuditEvent> = events.into_iter().filter(|e| query.matches(e)).collect();
+    let json = context.args.flags.contains_key("--json");
+
+    if context.args.flags.contains_key("--summary") {
+        let rows = summarize(&matched);
+        if json {
+            let out: Vec<serde_json::Value> = rows
+                .iter()
+                .map(|row| {
+                    serde_json::json!({
+                        "capability": row.capability,
+                        "decision": row.decision.as_str(),
+                        "count": row.count,
+                        "distinct_targets": row.distinct_targets,
+                        "top_target": row.top_target,
+                    })
+                })
+                .collect();
+            raw(&serde_json::to_string_pretty(&out).unwrap_or_else(|_| "[]".to_string()));
+            return;
+        }
+        if rows.is_empty() {
+            log("security", "no matching audit events");
+            return;
+        }
+        raw(&format!(
+            "{:<10} {:<15} {:>7} {:>8}  {}",
+            "capability", "decision", "count", "targets", "top target"
+        ));
+        for row in rows {
+            raw(&format!(
+                "{:<10} {:<15} {:>7} {:>8}  {}",
+                row.capability,
+                row.decision.as_str(),
+                row.count,
+                row.distinct_targets,
+                row.top_target
+            ));
+        }
+        return;
+    }
+
+    let limit = match context.args.params.get("--limit") {
+        Some(value) => match value.parse::<usize>() {
+            Ok(limit) => limit,
+            Err(_) => audit_error("--limit expects a number"),
+        },
+        None => 50,
+    };
+    let start = matched.len().saturating_sub(limit);
+    let recent = &matched[start..];
+    if json {
+        let out: Vec<serde_json::Value> = recent.iter().map(AuditEvent::to_json).collect();
+        raw(&serde_json::to_string_pretty(&out).unwrap_or_else(|_| "[]".to_string()));
+        return;
+    }
+    if recent.is_empty() {
+        log("security", "no matching audit events");
+        return;
+    }
+    raw(&format!(
+        "{:<14} {:<15} {:<10} {:<36} {:<18} {}",
+        "ts_ms", "decision", "capability", "target", "module", "rule"
+    ));
+    for event in recent {
+        raw(&format!(
+            "{:<14} {:<15} {:<10} {:<36} {:<18} {}",
+            event.ts_ms,
+            event.decision.as_str(),
+            event.capability,
+            event.target,
+            event.module.as_deref().unwrap_or("-"),
+            event.rule.as_deref().unwrap_or("-")
+        ));
+    }
+}
+
+fn audit_error(message: &str) -> ! {
+    error("security", message);
+    std::process::exit(1);
+}
+
+fn audit_config(context: &Context, cwd: &Path) -> Result<AuditConfig, String> {
+    if let Some(file) = context.args.params.get("--file") {
+        return Ok(AuditConfig {
+            path: file.clone(),
+            format: AuditFormat::from_path(file),
+        });
+    }
+    let path = cwd.join("deka.json");
+    let raw = std::fs::read_to_string(&path)
+        .map_err(|err| format!("failed to read {}: {}", path.display(), err))?;
+    let json: serde_json::Value = serde_json::from_str(&raw)
+        .map_err(|err| format!("invalid JSON in {}: {}", path.display(), err))?;
+    parse_deka_security_policy(&json)
+        .policy
+        .audit
+        .ok_or_else(|| {
+            "no audit log configured. set security.audit in deka.json or pass --file".to_string()
+        })
+}
+
+fn read_events(config: &AuditConfig, path: &Path) -> Result<Vec<AuditEvent>, String> {
+    match config.format {
+        AuditFormat::Jsonl => {
+            read_jsonl(path).map_err(|err| format!("failed to read {}: {}", path.display(), err))
+        }
+        AuditFormat::Sqlite => read_sqlite(path),
+    }
+}
+
+fn read_sqlite(path: &Path) -> Result<Vec<AuditEvent>, String> {
+    let conn = rusqlite::Connection::open(path)
+        .map_err(|err| format!("failed to open {}: {}", path.display(), err))?;
+    let mut stmt = conn
+        .prepare(SQLITE_SELECT)
+        .map_err(|err| format!("failed to query {}: {}", path.display(), err))?;
+    let rows = stmt
+        .query_map([], |row| {
+            let decision: String = row.get(1)?;
+            Ok(AuditDecision::parse(&decision).map(|decision| AuditEvent {
+                ts_ms: row.get::<_, i64>(0).unwrap_or(0).max(0) as u64,
+                decision,
+                capability: row.get(2).unwrap_or_default(),
+                target: row.get(3).unwrap_or_default(),
+                origin: row.get(4).unwrap_or(None),
+                module: row.get(5).unwrap_or(None),
+                request_id: row.get(6).unwrap_or(None),
+                rule: row.get(7).unwrap_or(None),
+            }))
+        })
+        .map_err(|err| format!("failed to query {}: {}", path.display(), err))?;
+    let mut events = Vec::new();
+    for row in rows {
+        let event = row.map_err(|err| format!("failed to read {}: {}", path.display(), err))?;
+        events.extend(event);
+    }
+    Ok(events)
+}
+
+fn audit_query(context: &Context) -> Result<AuditQuery, String> {
+    let params = &context.args.params;
+    let decision = match params.get("--decision") {
+        Some(value) => Some(AuditDecision::parse(value).ok_or_else(|| {
+            "--decision expects allow|deny|prompt-granted|prompt-denied|persisted".to_string()
+        })?),
+        None => None,
+    };
+    Ok(AuditQuery {
+        since_ms: params
+            .get("--since")
+            .map(|value| parse_time_bound(value, "--since"))
+            .transpose()?,
+        until_ms: params
+            .get("--until")
+            .map(|value| parse_time_bound(value, "--until"))
+            .transpose()?,
+        capability: params.get("--capability").cloned(),
+        decision,
+        module: params.get("--module").cloned(),
+        request_id: params.get("--request").cloned(),
+        target_contains: params.get("--target").cloned(),
+    })
+}
@@ -16,6 +16,16 @@ pub fn register(registry: &mut Registry) {
         aliases: &[],
         description: "enable development runtime mode (watch + hmr scaffolding)",
     });
+    registry.add_flag(FlagSpec {
+        name: "--inspect",
+        aliases: &[],
+        description: "expose isolates to Chrome DevTools / VS Code (--inspect=host:port, default 127.0.0.1:9229)",
+    });
+    registry.add_flag(FlagSpec {
+        name: "--inspect-brk",
+        aliases: &[],
+        description: "like --inspect, but pause before the entry module runs until a debugger attaches",
+    });
 }
 
 pub fn cmd(context: &Context) {
@@ -0,0 +1,32 @@
+use core::{CommandSpec, Context, Registry};
+use pm::run_update;
+use pm::spec::Ecosystem;
+use stdio::error;
+
+const COMMAND: CommandSpec = CommandSpec {
+    name: "update",
+    category: "package",
+    summary: "update dependencies to the newest versions their ranges allow",
+    aliases: &["up"],
+    subcommands: &[],
+    handler: cmd,
+};
+
+pub fn register(registry: &mut Registry) {
+    registry.add_command(COMMAND);
+}
+
+fn cmd(context: &Context) {
+    let ecosystem = context
+        .args
+        .params
+        .get("--ecosystem")
+        .and_then(|value| Ecosystem::from_str(value));
+    let quiet = context.args.flags.contains_key("--quiet") || context.args.flags.contains_key("-q");
+    let names = context.args.positionals.clone();
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    if let Err(err) = runtime.block_on(run_update(names, ecosystem, quiet)) {
+        error("update", &err.to_string());
+        std::process::exit(1);
+    }
+}
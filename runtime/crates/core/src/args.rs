@@ -73,6 +73,17 @@ impl Args {
         let mut iter = args.iter().enumerate();
         while let Some((_i, arg)) = iter.next() {
             let arg_str = arg.as_str();
+            // `--name=value` sets the param (or flag with an optional value, e.g. `--inspect=host:port`).
+            if let Some((name, value)) = arg_str.split_once('=')
+                && name.starts_with("--")
+                && (flag_tokens.contains(name) || param_tokens.contains(name))
+            {
+                if flag_tokens.contains(name) {
+                    flags.insert(name.to_string(), true);
+                }
+                params.insert(name.to_string(), value.to_string());
+                continue;
+            }
             if flag_tokens.contains(arg_str) {
                 flags.insert(arg.clone(), true);
                 continue;
@@ -191,3 +202,49 @@ fn levenshtein(a: &str, b: &str) -> usize {
 
     prev[b_len]
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::{FlagSpec, ParamSpec};
+
+    fn registry() -> Registry {
+        let mut registry = Registry::new();
+        registry.add_flag(FlagSpec {
+            name: "--inspect",
+            aliases: &[],
+            description: "",
+        });
+        registry.add_param(ParamSpec {
+            name: "--port",
+            description: "",
+        });
+        registry
+    }
+
+    #[test]
+    fn equals_form_sets_params_and_optional_flag_values() {
+        let args = vec![
+            "--inspect=0.0.0.0:9230".to_string(),
+            "--port=8080".to_string(),
+        ];
+        let outcome = Args::collect(args, &registry());
+        assert!(outcome.errors.is_empty());
+        assert_eq!(outcome.args.flags.get("--inspect"), Some(&true));
+        assert_eq!(
+            outcome.args.params.get("--inspect").map(String::as_str),
+            Some("0.0.0.0:9230")
+        );
+        assert_eq!(
+            outcome.args.params.get("--port").map(String::as_str),
+            Some("8080")
+        );
+    }
+
+    #[test]
+    fn bare_flag_has_no_value() {
+        let outcome = Args::collect(vec!["--inspect".to_string()], &registry());
+        assert_eq!(outcome.args.flags.get("--inspect"), Some(&true));
+        assert!(!outcome.args.params.contains_key("--inspect"));
+    }
+}
@@ -2,6 +2,7 @@ mod args;
 mod context;
 mod handler;
 mod registry;
+mod time;
 
 pub use args::{Args, ParseError, ParseErrorKind, ParseOutcome, parse_env};
 pub use context::{Context, ContextError, EnvContext, HandlerContext};
@@ -10,3 +11,4 @@ pub use handler::{
     ServeConfig, ServeMode, StaticServeConfig, resolve_handler_path,
 };
 pub use registry::{CommandSpec, FlagSpec, ParamSpec, Registry, SubcommandSpec};
+pub use time::parse_time_bound;
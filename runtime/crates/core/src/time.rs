@@ -0,0 +1,56 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Parses a `--since`/`--until` style bound: a relative window (`30s`, `15m`,
+/// `2h`, `7d`) back from now, or an absolute epoch-ms timestamp.
+pub fn parse_time_bound(value: &str, name: &str) -> Result<u64, String> {
+    let value = value.trim();
+    if let Ok(epoch_ms) = value.parse::<u64>() {
+        return Ok(epoch_ms);
+    }
+
+    let invalid = || format!("{} expects 30s|15m|2h|7d or epoch ms", name);
+    let (split, _) = value.char_indices().last().ok_or_else(invalid)?;
+    let (amount, unit) = value.split_at(split);
+    let seconds_per_unit = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => return Err(invalid()),
+    };
+    let amount = amount.parse::<u64>().map_err(|_| invalid())?;
+    let window = Duration::from_secs(amount.saturating_mul(seconds_per_unit));
+    Ok(now_millis().saturating_sub(window.as_millis() as u64))
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_relative_windows_and_epoch_ms() {
+        assert_eq!(
+            parse_time_bound("1700000000000", "--since"),
+            Ok(1_700_000_000_000)
+        );
+        let before = now_millis();
+        let bound = parse_time_bound("2h", "--since").unwrap();
+        assert!(bound <= before.saturating_sub(2 * 60 * 60 * 1000) + 1000);
+        assert!(bound + 2 * 60 * 60 * 1000 >= before);
+    }
+
+    #[test]
+    fn rejects_bad_units_without_panicking_on_multibyte_input() {
+        for value in ["", "h", "10w", "10é", "é", "1０d"] {
+            let err = parse_time_bound(value, "--until").unwrap_err();
+            assert_eq!(err, "--until expects 30s|15m|2h|7d or epoch ms");
+        }
+    }
+}
@@ -1,4 +1,6 @@
+use pool::{AdmissionConfig, ExecutionBudget};
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::path::PathBuf;
 
 #[derive(Debug, Default, Deserialize)]
@@ -19,6 +21,17 @@ pub struct ServeConfig {
     pub mode: Option<ServeMode>,
     pub entry: Option<String>,
     pub directory_listing: Option<bool>,
+    pub budget: Option<BudgetConfig>,
+    pub admission: Option<AdmissionConfig>,
+}
+
+/// `serve.budget` in deka.json: pool-wide CPU/wall budgets plus per-handler overrides.
+#[derive(Debug, Default, Deserialize)]
+pub struct BudgetConfig {
+    #[serde(flatten)]
+    pub default: ExecutionBudget,
+    #[serde(default)]
+    pub handlers: HashMap<String, ExecutionBudget>,
 }
 
 impl ServeConfig {
@@ -250,6 +263,7 @@ pub struct CodeCacheConfig {
 #[derive(Debug, Default, Deserialize)]
 pub struct IntrospectConfig {
     pub retention_days: Option<u64>,
+    pub max_size_mb: Option<u64>,
     pub db_path: Option<String>,
     pub profiling: Option<bool>,
 }
@@ -286,6 +300,14 @@ impl RuntimeConfig {
             .unwrap_or(7)
     }
 
+    pub fn introspect_max_size_bytes(&self) -> u64 {
+        self.introspect
+            .as_ref()
+            .and_then(|config| config.max_size_mb)
+            .unwrap_or(0)
+            .saturating_mul(1024 * 1024)
+    }
+
     pub fn introspect_db_path(&self) -> Option<PathBuf> {
         if let Some(path) = self
             .introspect
@@ -388,6 +410,40 @@ mod tests {
         assert_eq!(resolved_canon, configured_canon);
     }
 
+    #[test]
+    fn serve_budget_reads_defaults_and_handler_overrides() {
+        let dir = temp_dir("deka_engine_serve_budget");
+        fs::write(
+            dir.join("deka.json"),
+            r#"{"serve":{"budget":{"cpu_ms":500,"handlers":{"api.phpx":{"wall_ms":2000}}}}}"#,
+        )
+        .expect("write deka.json");
+
+        let config = ServeConfig::load(&dir);
+        let budget = config.budget.expect("budget");
+        assert_eq!(budget.default.cpu_ms, Some(500));
+        assert_eq!(budget.default.wall_ms, None);
+        assert_eq!(budget.handlers["api.phpx"].wall_ms, Some(2000));
+        assert_eq!(budget.handlers["api.phpx"].cpu_ms, None);
+    }
+
+    #[test]
+    fn serve_admission_reads_limits_and_keeps_default_priority_paths() {
+        let dir = temp_dir("deka_engine_serve_admission");
+        fs::write(
+            dir.join("deka.json"),
+            r#"{"serve":{"admission":{"max_queue":8,"max_concurrency":2,"handlers":{"api.phpx":{"max_concurrency":1}}}}}"#,
+        )
+        .expect("write deka.json");
+
+        let config = ServeConfig::load(&dir);
+        let admission = config.admission.expect("admission");
+        assert_eq!(admission.limits("api.phpx"), (8, 1));
+        assert_eq!(admission.limits("main.phpx"), (8, 2));
+        assert_eq!(admission.retry_after_secs, 1);
+        assert!(admission.priority_paths.contains(&"/health".to_string()));
+    }
+
     #[test]
     fn app_directory_defaults_to_php_mode() {
         let dir = temp_dir("deka_engine_app_router");
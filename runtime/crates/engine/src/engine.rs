@@ -24,11 +24,15 @@ impl RuntimeEngine {
         let server_pool = IsolatePool::new(server_pool_config, Arc::clone(&extensions_provider));
         let user_pool = IsolatePool::new(user_pool_config, extensions_provider);
         let retention_days = runtime_config.introspect_retention_days();
+        let max_size_bytes = runtime_config.introspect_max_size_bytes();
         let archive = runtime_config.introspect_db_path().and_then(|path| {
             if retention_days == 0 {
                 None
             } else {
-                Some(IntrospectArchive::new(path, retention_days))
+                Some(
+                    IntrospectArchive::new(path, retention_days)
+                        .with_max_size_bytes(max_size_bytes),
+                )
             }
         });
 
@@ -1,14 +1,58 @@
 use std::path::PathBuf;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use rusqlite::{Connection, params};
+use rusqlite::types::Value;
+use rusqlite::{Connection, params, params_from_iter};
 
 use pool::{RequestOpTiming, RequestState, RequestTrace};
 
+const TRACE_COLUMNS: &str = "id, handler_name, isolate_id, worker_id, started_at_ms, state, duration_ms, error, op_timings, queue_wait_ms, warm_time_us, total_time_us, heap_before_bytes, heap_after_bytes, heap_delta_bytes, response_status, response_body, termination_stack, php_stack";
+
 #[derive(Clone)]
 pub struct IntrospectArchive {
     db_path: PathBuf,
     retention_days: u64,
+    max_size_bytes: u64,
+}
+
+/// Ordering applied to archive search results.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TraceOrder {
+    #[default]
+    Recent,
+    Slowest,
+}
+
+/// Filters for searching archived request traces. Unset fields match everything.
+#[derive(Debug, Clone, Default)]
+pub struct TraceQuery {
+    pub handler: Option<String>,
+    /// Request state as stored in the archive (`completed`, `failed`, `queue_timeout`).
+    pub state: Option<String>,
+    pub response_status: Option<u16>,
+    /// Only failed/timed-out requests and responses with a 5xx status.
+    pub errors_only: bool,
+    /// Case-insensitive substring of the recorded error message.
+    pub error_contains: Option<String>,
+    pub min_duration_ms: Option<u64>,
+    pub max_duration_ms: Option<u64>,
+    pub since_ms: Option<u64>,
+    pub until_ms: Option<u64>,
+    pub order: TraceOrder,
+    pub limit: usize,
+}
+
+/// Latency summary for a single handler over the queried window.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct HandlerLatencyStats {
+    pub handler_name: String,
+    pub requests: u64,
+    pub errors: u64,
+    pub avg_ms: f64,
+    pub p50_ms: u64,
+    pub p95_ms: u64,
+    pub p99_ms: u64,
+    pub max_ms: u64,
 }
 
 impl IntrospectArchive {
@@ -16,9 +60,21 @@ impl IntrospectArchive {
         Self {
             db_path,
             retention_days,
+            max_size_bytes: 0,
         }
     }
 
+    /// Cap the archive size; the oldest traces are dropped once the cap is exceeded.
+    /// A value of `0` disables the cap.
+    pub fn with_max_size_bytes(mut self, max_size_bytes: u64) -> Self {
+        self.max_size_bytes = max_size_bytes;
+        self
+    }
+
+    pub fn db_path(&self) -> &std::path::Path {
+        &self.db_path
+    }
+
     pub fn record_traces(&self, traces: &[RequestTrace]) -> rusqlite::Result<()> {
         if traces.is_empty() {
             return Ok(());
@@ -30,14 +86,24 @@ impl IntrospectArchive {
             {
                 let mut stmt = tx.prepare(
                     "INSERT OR REPLACE INTO request_traces
-                    (id, handler_name, isolate_id, worker_id, started_at_ms, state, duration_ms, error, op_timings, queue_wait_ms, warm_time_us, total_time_us, heap_before_bytes, heap_after_bytes, heap_delta_bytes, response_status, response_body)
-                    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)",
+                    (id, handler_name, isolate_id, worker_id, started_at_ms, state, duration_ms, error, op_timings, queue_wait_ms, warm_time_us, total_time_us, heap_before_bytes, heap_after_bytes, heap_delta_bytes, response_status, response_body, termination_stack, php_stack)
+                    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19)",
                 )?;
 
                 for trace in traces {
                     let (state, duration_ms, error) = state_parts(&trace.state);
                     let op_timings = serde_json::to_string(&trace.op_timings)
                         .unwrap_or_else(|_| "[]".to_string());
+                    let termination_stack = if trace.termination_stack.is_empty() {
+                        None
+                    } else {
+                        serde_json::to_string(&trace.termination_stack).ok()
+                    };
+                    let php_stack = if trace.php_stack.is_empty() {
+                        None
+                    } else {
+                        serde_json::to_string(&trace.php_stack).ok()
+                    };
                     stmt.execute(params![
                         trace.id,
                         trace.handler_name,
@@ -56,6 +122,8 @@ impl IntrospectArchive {
                         trace.heap_delta_bytes as i64,
                         trace.response_status.map(|v| v as i64),
                         trace.response_body,
+                        termination_stack,
+                        php_stack,
                     ])?;
                 }
             }
@@ -74,6 +142,73 @@ impl IntrospectArchive {
         self.fetch_traces_with_conn(&conn, limit, Some(cutoff_ms))
     }
 
+    pub fn query_traces(&self, query: &TraceQuery) -> rusqlite::Result<Vec<RequestTrace>> {
+        let conn = self.connect()?;
+        let (where_sql, mut values) = query_filters(query);
+        let order_sql = match query.order {
+            TraceOrder::Recent => "started_at_ms DESC",
+            TraceOrder::Slowest => "duration_ms DESC, started_at_ms DESC",
+        };
+        let limit = if query.limit == 0 { 100 } else { query.limit };
+        values.push(Value::Integer(limit as i64));
+        let sql = format!(
+            "SELECT {} FROM request_traces{} ORDER BY {} LIMIT ?",
+            TRACE_COLUMNS, where_sql, order_sql
+        );
+
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map(params_from_iter(values), row_to_trace)?;
+        rows.collect()
+    }
+
+    /// Compute per-handler latency percentiles over the traces matching `query`.
+    /// Ordering and limit on the query are ignored.
+    pub fn handler_stats(&self, query: &TraceQuery) -> rusqlite::Result<Vec<HandlerLatencyStats>> {
+        let conn = self.connect()?;
+        let (where_sql, values) = query_filters(query);
+        let duration_filter = if where_sql.is_empty() {
+            " WHERE duration_ms IS NOT NULL"
+        } else {
+            " AND duration_ms IS NOT NULL"
+        };
+        let sql = format!(
+            "SELECT handler_name, duration_ms, state, response_status
+             FROM request_traces{}{}
+             ORDER BY handler_name ASC, duration_ms ASC",
+            where_sql, duration_filter
+        );
+
+        let mut stmt = conn.prepare(&sql)?;
+        let mut rows = stmt.query(params_from_iter(values))?;
+        let mut stats = Vec::new();
+        let mut current: Option<(String, Vec<u64>, u64)> = None;
+        while let Some(row) = rows.next()? {
+            let handler_name: String = row.get(0)?;
+            let duration_ms = row.get::<_, i64>(1)?.max(0) as u64;
+            let state: String = row.get(2)?;
+            let response_status: Option<i64> = row.get(3)?;
+            let is_error = state != "completed" || response_status.is_some_and(|v| v >= 500);
+
+            match current.as_mut() {
+                Some((name, durations, errors)) if *name == handler_name => {
+                    durations.push(duration_ms);
+                    *errors += is_error as u64;
+                }
+                _ => {
+                    if let Some((name, durations, errors)) = current.take() {
+                        stats.push(latency_stats(name, &durations, errors));
+                    }
+                    current = Some((handler_name, vec![duration_ms], is_error as u64));
+                }
+            }
+        }
+        if let Some((name, durations, errors)) = current.take() {
+            stats.push(latency_stats(name, &durations, errors));
+        }
+
+        Ok(stats)
+    }
+
     fn fetch_traces_with_conn(
         &self,
         conn: &Connection,
@@ -82,13 +217,13 @@ impl IntrospectArchive {
     ) -> rusqlite::Result<Vec<RequestTrace>> {
         let mut traces = Vec::new();
         let sql = if cutoff_ms.is_some() {
-            "SELECT id, handler_name, isolate_id, worker_id, started_at_ms, state, duration_ms, error, op_timings, queue_wait_ms, warm_time_us, total_time_us, heap_before_bytes, heap_after_bytes, heap_delta_bytes, response_status, response_body
+            "SELECT id, handler_name, isolate_id, worker_id, started_at_ms, state, duration_ms, error, op_timings, queue_wait_ms, warm_time_us, total_time_us, heap_before_bytes, heap_after_bytes, heap_delta_bytes, response_status, response_body, termination_stack, php_stack
              FROM request_traces
              WHERE started_at_ms <= ?1
              ORDER BY started_at_ms DESC
              LIMIT ?2"
         } else {
-            "SELECT id, handler_name, isolate_id, worker_id, started_at_ms, state, duration_ms, error, op_timings, queue_wait_ms, warm_time_us, total_time_us, heap_before_bytes, heap_after_bytes, heap_delta_bytes, response_status, response_body
+            "SELECT id, handler_name, isolate_id, worker_id, started_at_ms, state, duration_ms, error, op_timings, queue_wait_ms, warm_time_us, total_time_us, heap_before_bytes, heap_after_bytes, heap_delta_bytes, response_status, response_body, termination_stack, php_stack
              FROM request_traces
              ORDER BY started_at_ms DESC
              LIMIT ?1"
@@ -132,10 +267,14 @@ impl IntrospectArchive {
                 heap_after_bytes INTEGER,
                 heap_delta_bytes INTEGER,
                 response_status INTEGER,
-                response_body TEXT
+                response_body TEXT,
+                termination_stack TEXT,
+                php_stack TEXT
             );
             CREATE INDEX IF NOT EXISTS request_traces_started_at
-            ON request_traces(started_at_ms DESC);",
+            ON request_traces(started_at_ms DESC);
+            CREATE INDEX IF NOT EXISTS request_traces_handler
+            ON request_traces(handler_name, started_at_ms DESC);",
         )?;
         if let Err(err) = conn.execute("ALTER TABLE request_traces ADD COLUMN op_timings TEXT", [])
         {
@@ -207,24 +346,151 @@ impl IntrospectArchive {
                 return Err(err);
             }
         }
+        if let Err(err) = conn.execute(
+            "ALTER TABLE request_traces ADD COLUMN termination_stack TEXT",
+            [],
+        ) {
+            if !err.to_string().contains("duplicate column") {
+                return Err(err);
+            }
+        }
+        if let Err(err) = conn.execute("ALTER TABLE request_traces ADD COLUMN php_stack TEXT", []) {
+            if !err.to_string().contains("duplicate column") {
+                return Err(err);
+            }
+        }
         Ok(conn)
     }
 
     fn prune(&self, conn: &Connection) -> rusqlite::Result<()> {
-        if self.retention_days == 0 {
+        if self.retention_days > 0 {
+            let cutoff = now_millis().saturating_sub(
+                Duration::from_secs(self.retention_days * 24 * 60 * 60).as_millis() as u64,
+            );
+
+            conn.execute(
+                "DELETE FROM request_traces WHERE started_at_ms < ?1",
+                params![cutoff as i64],
+            )?;
+        }
+
+        self.prune_to_size(conn)
+    }
+
+    fn prune_to_size(&self, conn: &Connection) -> rusqlite::Result<()> {
+        if self.max_size_bytes == 0 {
             return Ok(());
         }
 
-        let cutoff = now_millis().saturating_sub(
-            Duration::from_secs(self.retention_days * 24 * 60 * 60).as_millis() as u64,
-        );
+        // Deleted rows go to the freelist and are reused, so compare live pages
+        // against the cap rather than the file size on disk.
+        loop {
+            let used_bytes = used_size_bytes(conn)?;
+            if used_bytes <= self.max_size_bytes {
+                return Ok(());
+            }
 
-        conn.execute(
-            "DELETE FROM request_traces WHERE started_at_ms < ?1",
-            params![cutoff as i64],
-        )?;
-        Ok(())
+            let count: i64 =
+                conn.query_row("SELECT COUNT(*) FROM request_traces", [], |row| row.get(0))?;
+            if count == 0 {
+                return Ok(());
+            }
+
+            let batch = (count / 10).max(1);
+            conn.execute(
+                "DELETE FROM request_traces WHERE id IN (
+                    SELECT id FROM request_traces ORDER BY started_at_ms ASC LIMIT ?1
+                )",
+                params![batch],
+            )?;
+        }
+    }
+}
+
+fn used_size_bytes(conn: &Connection) -> rusqlite::Result<u64> {
+    let page_size: i64 = conn.query_row("PRAGMA page_size", [], |row| row.get(0))?;
+    let page_count: i64 = conn.query_row("PRAGMA page_count", [], |row| row.get(0))?;
+    let freelist_count: i64 = conn.query_row("PRAGMA freelist_count", [], |row| row.get(0))?;
+    Ok((page_count - freelist_count).max(0) as u64 * page_size.max(0) as u64)
+}
+
+fn query_filters(query: &TraceQuery) -> (String, Vec<Value>) {
+    let mut clauses: Vec<&str> = Vec::new();
+    let mut values = Vec::new();
+
+    if let Some(handler) = &query.handler {
+        clauses.push("handler_name = ?");
+        values.push(Value::Text(handler.clone()));
+    }
+    if let Some(state) = &query.state {
+        clauses.push("state = ?");
+        values.push(Value::Text(state.clone()));
+    }
+    if let Some(status) = query.response_status {
+        clauses.push("response_status = ?");
+        values.push(Value::Integer(status as i64));
+    }
+    if query.errors_only {
+        clauses.push("(state IN ('failed', 'queue_timeout') OR response_status >= 500)");
+    }
+    if let Some(needle) = &query.error_contains {
+        clauses.push("instr(lower(error), lower(?)) > 0");
+        values.push(Value::Text(needle.clone()));
+    }
+    if let Some(min) = query.min_duration_ms {
+        clauses.push("duration_ms >= ?");
+        values.push(Value::Integer(min as i64));
+    }
+    if let Some(max) = query.max_duration_ms {
+        clauses.push("duration_ms <= ?");
+        values.push(Value::Integer(max as i64));
+    }
+    if let Some(since) = query.since_ms {
+        clauses.push("started_at_ms >= ?");
+        values.push(Value::Integer(since as i64));
+    }
+    if let Some(until) = query.until_ms {
+        clauses.push("started_at_ms <= ?");
+        values.push(Value::Integer(until as i64));
+    }
+
+    if clauses.is_empty() {
+        (String::new(), values)
+    } else {
+        (format!(" WHERE {}", clauses.join(" AND ")), values)
+    }
+}
+
+fn latency_stats(
+    handler_name: String,
+    sorted_durations: &[u64],
+    errors: u64,
+) -> HandlerLatencyStats {
+    let requests = sorted_durations.len() as u64;
+    let total: u64 = sorted_durations.iter().sum();
+    HandlerLatencyStats {
+        handler_name,
+        requests,
+        errors,
+        avg_ms: if requests == 0 {
+            0.0
+        } else {
+            total as f64 / requests as f64
+        },
+        p50_ms: percentile(sorted_durations, 50.0),
+        p95_ms: percentile(sorted_durations, 95.0),
+        p99_ms: percentile(sorted_durations, 99.0),
+        max_ms: sorted_durations.last().copied().unwrap_or(0),
+    }
+}
+
+/// Nearest-rank percentile over an ascending slice.
+fn percentile(sorted: &[u64], pct: f64) -> u64 {
+    if sorted.is_empty() {
+        return 0;
     }
+    let rank = ((pct / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
 }
 
 fn state_parts(state: &RequestState) -> (String, Option<u64>, Option<String>) {
@@ -261,6 +527,18 @@ fn row_to_trace(row: &rusqlite::Row) -> rusqlite::Result<RequestTrace> {
     let heap_delta_bytes: Option<i64> = row.get(14).ok();
     let response_status: Option<i64> = row.get(15).ok();
     let response_body: Option<String> = row.get(16).ok();
+    let termination_stack: Vec<String> = row
+        .get::<_, Option<String>>(17)
+        .ok()
+        .flatten()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+    let php_stack: Vec<String> = row
+        .get::<_, Option<String>>(18)
+        .ok()
+        .flatten()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
 
     let request_state = match state.as_str() {
         "completed" => RequestState::Completed {
@@ -292,6 +570,8 @@ fn row_to_trace(row: &rusqlite::Row) -> rusqlite::Result<RequestTrace> {
         heap_delta_bytes: heap_delta_bytes.unwrap_or(0) as i64,
         response_status: response_status.and_then(|v| u16::try_from(v).ok()),
         response_body,
+        termination_stack,
+        php_stack,
     })
 }
 
@@ -301,3 +581,183 @@ fn now_millis() -> u64 {
         .unwrap_or_else(|_| Duration::from_secs(0))
         .as_millis() as u64
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_db(prefix: &str) -> PathBuf {
+        let nonce = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("clock")
+            .as_nanos();
+        std::env::temp_dir().join(format!("{}_{}.db", prefix, nonce))
+    }
+
+    fn trace(id: &str, handler: &str, started_at_ms: u64, state: RequestState) -> RequestTrace {
+        RequestTrace {
+            id: id.to_string(),
+            handler_name: handler.to_string(),
+            isolate_id: "iso".to_string(),
+            worker_id: 0,
+            started_at_ms,
+            state,
+            op_timings: Vec::new(),
+            queue_wait_ms: 0,
+            warm_time_us: 0,
+            total_time_us: 0,
+            heap_before_bytes: 0,
+            heap_after_bytes: 0,
+            heap_delta_bytes: 0,
+            response_status: Some(200),
+            response_body: None,
+            termination_stack: Vec::new(),
+            php_stack: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn query_filters_by_handler_error_and_duration() {
+        let archive = IntrospectArchive::new(temp_db("deka_archive_query"), 0);
+        let now = now_millis();
+        archive
+            .record_traces(&[
+                trace("a", "api", now, RequestState::Completed { duration_ms: 5 }),
+                trace(
+                    "b",
+                    "api",
+                    now + 1,
+                    RequestState::Completed { duration_ms: 250 },
+                ),
+                trace(
+                    "c",
+                    "api",
+                    now + 2,
+                    RequestState::Failed {
+                        error: "TypeError: boom".to_string(),
+                        duration_ms: 12,
+                    },
+                ),
+                trace(
+                    "d",
+                    "web",
+                    now + 3,
+                    RequestState::Completed { duration_ms: 900 },
+                ),
+            ])
+            .expect("record");
+
+        let slow = archive
+            .query_traces(&TraceQuery {
+                handler: Some("api".to_string()),
+                min_duration_ms: Some(100),
+                ..TraceQuery::default()
+            })
+            .expect("query");
+        assert_eq!(slow.len(), 1);
+        assert_eq!(slow[0].id, "b");
+
+        let errors = archive
+            .query_traces(&TraceQuery {
+                errors_only: true,
+                error_contains: Some("typeerror".to_string()),
+                ..TraceQuery::default()
+            })
+            .expect("query");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].id, "c");
+
+        let slowest = archive
+            .query_traces(&TraceQuery {
+                order: TraceOrder::Slowest,
+                limit: 2,
+                ..TraceQuery::default()
+            })
+            .expect("query");
+        let ids: Vec<_> = slowest.iter().map(|t| t.id.as_str()).collect();
+        assert_eq!(ids, vec!["d", "b"]);
+    }
+
+    #[test]
+    fn termination_stacks_round_trip() {
+        let archive = IntrospectArchive::new(temp_db("deka_archive_stacks"), 0);
+        let mut terminated = trace(
+            "t",
+            "api",
+            now_millis(),
+            RequestState::Failed {
+                error: "Handler exceeded CPU budget of 50ms".to_string(),
+                duration_ms: 50,
+            },
+        );
+        terminated.termination_stack = vec!["spin (file:///app/index.mjs:3:5)".to_string()];
+        terminated.php_stack = vec!["spin (app/index.phpx:2:3)".to_string()];
+        archive.record_traces(&[terminated]).expect("record");
+
+        let traces = archive.query_traces(&TraceQuery::default()).expect("query");
+        assert_eq!(
+            traces[0].termination_stack,
+            ["spin (file:///app/index.mjs:3:5)"]
+        );
+        assert_eq!(traces[0].php_stack, ["spin (app/index.phpx:2:3)"]);
+    }
+
+    #[test]
+    fn handler_stats_reports_percentiles() {
+        let archive = IntrospectArchive::new(temp_db("deka_archive_stats"), 0);
+        let now = now_millis();
+        let traces: Vec<_> = (1..=100)
+            .map(|i| {
+                trace(
+                    &format!("t{}", i),
+                    "api",
+                    now + i,
+                    RequestState::Completed { duration_ms: i },
+                )
+            })
+            .collect();
+        archive.record_traces(&traces).expect("record");
+
+        let stats = archive
+            .handler_stats(&TraceQuery::default())
+            .expect("stats");
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].requests, 100);
+        assert_eq!(stats[0].p50_ms, 50);
+        assert_eq!(stats[0].p95_ms, 95);
+        assert_eq!(stats[0].p99_ms, 99);
+        assert_eq!(stats[0].max_ms, 100);
+    }
+
+    #[test]
+    fn size_cap_drops_oldest_traces() {
+        let archive =
+            IntrospectArchive::new(temp_db("deka_archive_size"), 0).with_max_size_bytes(64 * 1024);
+        let now = now_millis();
+        let body = "x".repeat(2048);
+        let traces: Vec<_> = (0..200)
+            .map(|i| {
+                let mut t = trace(
+                    &format!("t{}", i),
+                    "api",
+                    now + i,
+                    RequestState::Completed { duration_ms: 1 },
+                );
+                t.response_body = Some(body.clone());
+                t
+            })
+            .collect();
+        archive.record_traces(&traces).expect("record");
+
+        let conn = archive.connect().expect("connect");
+        assert!(used_size_bytes(&conn).expect("size") <= 64 * 1024);
+        let remaining = archive
+            .query_traces(&TraceQuery {
+                limit: 1000,
+                ..TraceQuery::default()
+            })
+            .expect("query");
+        assert!(!remaining.is_empty());
+        assert_eq!(remaining[0].id, "t199");
+    }
+}
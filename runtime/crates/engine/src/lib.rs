@@ -11,7 +11,7 @@ use pool::HandlerKey;
 pub use dispatch::{execute_request, execute_request_parts, execute_request_value};
 pub use engine::{RuntimeEngine, engine, set_engine};
 pub use envelope::{RequestEnvelope, ResponseEnvelope};
-pub use introspect_archive::IntrospectArchive;
+pub use introspect_archive::{HandlerLatencyStats, IntrospectArchive, TraceOrder, TraceQuery};
 
 pub struct RuntimeState {
     pub engine: Arc<engine::RuntimeEngine>,
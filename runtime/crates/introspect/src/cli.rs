@@ -1,4 +1,7 @@
-use core::{CommandSpec, Context, FlagSpec, ParamSpec, Registry, SubcommandSpec};
+use core::{CommandSpec, Context, FlagSpec, ParamSpec, Registry, SubcommandSpec, parse_time_bound};
+use engine::config::RuntimeConfig;
+use engine::{HandlerLatencyStats, IntrospectArchive, TraceOrder, TraceQuery};
+use pool::{RequestState, RequestTrace};
 use std::path::PathBuf;
 
 const COMMAND: CommandSpec = CommandSpec {
@@ -31,6 +34,24 @@ const COMMAND: CommandSpec = CommandSpec {
             aliases: &[],
             handler: cmd_kill,
         },
+        SubcommandSpec {
+            name: "slow",
+            summary: "list the slowest archived requests",
+            aliases: &[],
+            handler: cmd_slow,
+        },
+        SubcommandSpec {
+            name: "errors",
+            summary: "list failed archived requests",
+            aliases: &[],
+            handler: cmd_errors,
+        },
+        SubcommandSpec {
+            name: "stats",
+            summary: "show archived latency percentiles per handler",
+            aliases: &[],
+            handler: cmd_stats,
+        },
     ],
     handler: cmd_default,
 };
@@ -65,11 +86,39 @@ pub fn register(registry: &mut Registry) {
     });
     registry.add_param(ParamSpec {
         name: "--limit",
-        description: "limit number of rows (top command)",
+        description: "limit number of rows (top, slow, errors)",
     });
     registry.add_param(ParamSpec {
         name: "-l",
-        description: "limit number of rows (top command)",
+        description: "limit number of rows (top, slow, errors)",
+    });
+    registry.add_param(ParamSpec {
+        name: "--handler",
+        description: "filter archived requests by handler (slow/errors/stats)",
+    });
+    registry.add_param(ParamSpec {
+        name: "--since",
+        description: "archive window start: 30s|15m|2h|7d ago or epoch ms",
+    });
+    registry.add_param(ParamSpec {
+        name: "--until",
+        description: "archive window end: 30s|15m|2h|7d ago or epoch ms",
+    });
+    registry.add_param(ParamSpec {
+        name: "--status",
+        description: "filter by HTTP status or state (completed|failed|queue_timeout)",
+    });
+    registry.add_param(ParamSpec {
+        name: "--min-ms",
+        description: "minimum request duration in ms",
+    });
+    registry.add_param(ParamSpec {
+        name: "--max-ms",
+        description: "maximum request duration in ms",
+    });
+    registry.add_param(ParamSpec {
+        name: "--error",
+        description: "filter by error message substring",
     });
 }
 
@@ -288,3 +337,166 @@ fn execute_introspect_command(wrapper_path: &PathBuf, args: Vec<String>, context
 
     runtime::run(context);
 }
+
+// Slow subcommand
+pub fn cmd_slow(context: &Context) {
+    let archive = open_archive();
+    let mut query = archive_query(context);
+    query.order = TraceOrder::Slowest;
+
+    match archive.query_traces(&query) {
+        Ok(traces) => print_traces(context, &traces),
+        Err(err) => archive_error(&err.to_string()),
+    }
+}
+
+// Errors subcommand
+pub fn cmd_errors(context: &Context) {
+    let archive = open_archive();
+    let mut query = archive_query(context);
+    query.errors_only = true;
+
+    match archive.query_traces(&query) {
+        Ok(traces) => print_traces(context, &traces),
+        Err(err) => archive_error(&err.to_string()),
+    }
+}
+
+// Stats subcommand
+pub fn cmd_stats(context: &Context) {
+    let archive = open_archive();
+    let query = archive_query(context);
+
+    match archive.handler_stats(&query) {
+        Ok(stats) => print_stats(context, &stats),
+        Err(err) => archive_error(&err.to_string()),
+    }
+}
+
+fn open_archive() -> IntrospectArchive {
+    let config = RuntimeConfig::load();
+    let Some(path) = config.introspect_db_path() else {
+        archive_error("no introspect archive path configured");
+    };
+    if !path.exists() {
+        archive_error(&format!("no introspect archive at {}", path.display()));
+    }
+    IntrospectArchive::new(path, config.introspect_retention_days())
+}
+
+fn archive_error(message: &str) -> ! {
+    stdio::error("introspect", message);
+    std::process::exit(1);
+}
+
+fn archive_query(context: &Context) -> TraceQuery {
+    let params = &context.args.params;
+    let mut query = TraceQuery {
+        handler: params.get("--handler").cloned(),
+        error_contains: params.get("--error").cloned(),
+        limit: 20,
+        ..TraceQuery::default()
+    };
+
+    if let Some(status) = params.get("--status") {
+        match status.parse::<u16>() {
+            Ok(code) => query.response_status = Some(code),
+            Err(_) => query.state = Some(status.to_ascii_lowercase()),
+        }
+    }
+    if let Some(limit) = params.get("--limit").or_else(|| params.get("-l")) {
+        query.limit = parse_number(limit, "--limit") as usize;
+    }
+    if let Some(value) = params.get("--min-ms") {
+        query.min_duration_ms = Some(parse_number(value, "--min-ms"));
+    }
+    if let Some(value) = params.get("--max-ms") {
+        query.max_duration_ms = Some(parse_number(value, "--max-ms"));
+    }
+    if let Some(value) = params.get("--since") {
+        query.since_ms =
+            Some(parse_time_bound(value, "--since").unwrap_or_else(|e| archive_error(&e)));
+    }
+    if let Some(value) = params.get("--until") {
+        query.until_ms =
+            Some(parse_time_bound(value, "--until").unwrap_or_else(|e| archive_error(&e)));
+    }
+
+    query
+}
+
+fn parse_number(value: &str, name: &str) -> u64 {
+    value
+        .parse::<u64>()
+        .unwrap_or_else(|_| archive_error(&format!("{} expects a number, got {}", name, value)))
+}
+
+fn print_traces(context: &Context, traces: &[RequestTrace]) {
+    if context.args.flags.contains_key("--json") {
+        let json = serde_json::to_string_pretty(traces).unwrap_or_else(|_| "[]".to_string());
+        stdio::raw(&json);
+        return;
+    }
+
+    if traces.is_empty() {
+        stdio::log("introspect", "no matching archived requests");
+        return;
+    }
+
+    stdio::raw(&format!(
+        "{:<24} {:<28} {:>8} {:>6}  {}",
+        "started", "handler", "ms", "status", "detail"
+    ));
+    for trace in traces {
+        let (duration_ms, detail) = match &trace.state {
+            RequestState::Executing => (0, "executing".to_string()),
+            RequestState::Completed { duration_ms } => (*duration_ms, String::new()),
+            RequestState::Failed { error, duration_ms } => {
+                (*duration_ms, error.lines().next().unwrap_or("").to_string())
+            }
+            RequestState::QueueTimeout { waited_ms } => (*waited_ms, "queue timeout".to_string()),
+        };
+        let status = trace
+            .response_status
+            .map(|code| code.to_string())
+            .unwrap_or_else(|| "-".to_string());
+        stdio::raw(&format!(
+            "{:<24} {:<28} {:>8} {:>6}  {}",
+            trace.started_at_ms, trace.handler_name, duration_ms, status, detail
+        ));
+        for frame in &trace.php_stack {
+            stdio::raw(&format!("    at {}", frame));
+        }
+    }
+}
+
+fn print_stats(context: &Context, stats: &[HandlerLatencyStats]) {
+    if context.args.flags.contains_key("--json") {
+        let json = serde_json::to_string_pretty(stats).unwrap_or_else(|_| "[]".to_string());
+        stdio::raw(&json);
+        return;
+    }
+
+    if stats.is_empty() {
+        stdio::log("introspect", "no matching archived requests");
+        return;
+    }
+
+    stdio::raw(&format!(
+        "{:<28} {:>8} {:>7} {:>9} {:>7} {:>7} {:>7} {:>7}",
+        "handler", "requests", "errors", "avg ms", "p50", "p95", "p99", "max"
+    ));
+    for entry in stats {
+        stdio::raw(&format!(
+            "{:<28} {:>8} {:>7} {:>9.1} {:>7} {:>7} {:>7} {:>7}",
+            entry.handler_name,
+            entry.requests,
+            entry.errors,
+            entry.avg_ms,
+            entry.p50_ms,
+            entry.p95_ms,
+            entry.p99_ms,
+            entry.max_ms
+        ));
+    }
+}
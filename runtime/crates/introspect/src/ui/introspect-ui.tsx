@@ -58,6 +58,7 @@ type RequestTrace = {
   heap_delta_bytes?: number
   response_status?: number
   response_body?: string | null
+  php_stack?: string[]
 }
 
 type KnownRequest = {
@@ -333,6 +334,14 @@ function detailEntries(
     }
   }
 
+  const phpStack = trace.php_stack ?? []
+  if (phpStack.length > 0) {
+    entries.push({ text: 'php stack:', isError: true })
+    for (const frame of phpStack) {
+      entries.push({ text: `  at ${frame}`, isError: true })
+    }
+  }
+
   entries.push({ text: `queue: ${queueMs} ms`, isError: false })
   entries.push({ text: `warm: ${warmMs ? warmMs.toFixed(2) : '0.00'} ms`, isError: false })
   entries.push({ text: `total: ${totalMs ? totalMs.toFixed(2) : '0.00'} ms`, isError: false })
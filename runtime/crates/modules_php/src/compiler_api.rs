@@ -4,6 +4,7 @@ use bumpalo::Bump;
 use php_rs::parser::lexer::Lexer;
 use php_rs::parser::parser::{Parser, ParserMode};
 
+use crate::validation::diagnostics::apply_suppressions;
 use crate::validation::exports::validate_exports;
 use crate::validation::generics::validate_generics;
 use crate::validation::imports::validate_imports;
@@ -22,9 +23,12 @@ use crate::validation::patterns::validate_match_exhaustiveness;
 use crate::validation::phpx_rules::{
     validate_no_exceptions, validate_no_namespace, validate_no_null, validate_no_oop,
 };
+use crate::validation::signatures::collect_import_signatures;
 use crate::validation::structs::{validate_struct_definitions, validate_struct_literals};
 use crate::validation::syntax::validate_syntax;
-use crate::validation::type_checker::{check_types, check_types_with_externals};
+use crate::validation::type_checker::{
+    check_types, check_types_with_externals, check_types_with_imports,
+};
 use crate::validation::type_syntax::validate_type_annotations;
 use crate::validation::{ErrorKind, Severity, ValidationError, ValidationResult};
 use php_rs::phpx::typeck::{ExternalFunctionSig, external_functions_from_stub};
@@ -78,7 +82,10 @@ fn compile_phpx_with_mode<'a>(
     if strict {
         errors.extend(validate_type_annotations(&program, source));
 
-        let type_errors = if wasm_functions.is_empty() {
+        let imports = collect_import_signatures(source, file_path);
+        let type_errors = if !imports.is_empty() {
+            check_types_with_imports(&program, source, Some(file_path), &wasm_functions, &imports)
+        } else if wasm_functions.is_empty() {
             check_types(&program, source, Some(file_path))
         } else {
             check_types_with_externals(&program, source, Some(file_path), &wasm_functions)
@@ -110,6 +117,8 @@ fn compile_phpx_with_mode<'a>(
 
     errors.extend(validate_match_exhaustiveness(&program, source));
 
+    apply_suppressions(source, file_path, &mut errors, &mut warnings);
+
     if has_parse_errors {
         wasm_functions.clear();
     }
@@ -126,7 +135,7 @@ fn compile_phpx_with_mode<'a>(
     }
 }
 
-fn preprocess_phpx_source(source: &str) -> String {
+pub(crate) fn preprocess_phpx_source(source: &str) -> String {
     let line_refs: Vec<&str> = source.lines().collect();
     let bounds = frontmatter_bounds(&line_refs);
     let mut output = String::with_capacity(source.len());
@@ -267,6 +276,10 @@ fn wasm_stub_error(
         suggestion: None,
         underline_length,
         severity: Severity::Error,
+        code: None,
+        related: Vec::new(),
+        notes: Vec::new(),
+        fixes: Vec::new(),
     }
 }
 
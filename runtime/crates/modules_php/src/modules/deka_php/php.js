@@ -1,5 +1,5 @@
 // Generated by build.rs (PHP prelude). Do not edit.
-const { op_php_get_wasm, op_php_parse_phpx_types, op_php_read_file_sync, op_php_write_file_sync, op_php_mkdirs, op_php_set_privileged, op_php_sha256, op_php_random_bytes, op_php_read_env, op_php_db_call_proto, op_php_db_proto_encode, op_php_db_proto_decode, op_php_net_call_proto, op_php_net_proto_encode, op_php_net_proto_decode, op_php_fs_call_proto, op_php_fs_proto_encode, op_php_fs_proto_decode, op_php_cwd, op_php_file_exists, op_php_path_resolve, op_php_read_dir, op_php_parse_wit } = Deno.core.ops;
+const { op_php_get_wasm, op_php_parse_phpx_types, op_php_read_file_sync, op_php_write_file_sync, op_php_mkdirs, op_php_set_privileged, op_php_security_scope_enter, op_php_security_scope_exit, op_php_sha256, op_php_random_bytes, op_php_read_env, op_php_db_call_proto, op_php_db_proto_encode, op_php_db_proto_decode, op_php_net_call_proto, op_php_net_proto_encode, op_php_net_proto_decode, op_php_fs_call_proto, op_php_fs_proto_encode, op_php_fs_proto_decode, op_php_cwd, op_php_file_exists, op_php_path_resolve, op_php_read_dir, op_php_parse_wit } = Deno.core.ops;
 const console = {
     log: (...args)=>{
         const message = args.map((a)=>String(a)).join(' ');
@@ -378,6 +378,15 @@ function normalizeModuleId(raw) {
     if (spec.endsWith('/index')) return spec.slice(0, -'/index'.length);
     return spec;
 }
+// Installed packages live under php_modules/@scope/name; the stdlib and
+// project modules are not attributed to a package.
+function packageNameFromModuleId(moduleId) {
+    const id = normalizeModuleId(moduleId);
+    if (!id.startsWith('@') || id.startsWith('@/')) return '';
+    const parts = id.split('/');
+    if (parts.length < 2 || !parts[0].slice(1) || !parts[1]) return '';
+    return `${parts[0]}/${parts[1]}`;
+}
 function lockCacheModules(lock) {
     return lock && lock.php && lock.php.cache && lock.php.cache.modules
         ? lock.php.cache.modules
@@ -2260,9 +2269,21 @@ function buildExportWrappers(moduleId, moduleNamespace, exports, typeInfo) {
         code += " if ($argc >= 6) { $args[] = $f; }";
         code += ` $sig = ${sigAccess};`;
         code += " if ($sig) { $args = \\__phpx_coerce_args($sig, $args); }";
-        code += ` $result = call_user_func_array('${escapePhpString(target)}', $args);`;
+        const packageName = packageNameFromModuleId(moduleId);
+        if (packageName) {
+            // The scope token stays in this frame; package code cannot read it.
+            code += ` $scope = \\__phpx_scope_enter('${escapePhpString(packageName)}');`;
+            code += " if ($scope === null) { return null; }";
+            code += ` try { $result = call_user_func_array('${escapePhpString(target)}', $args); }`;
+            code += " finally { \\__phpx_scope_exit($scope); }";
+        } else {
+            code += ` $result = call_user_func_array('${escapePhpString(target)}', $args);`;
+        }
         code += " if ($sig && \\__phpx_array_has_key($sig, 'return')) { $result = \\__phpx_coerce_return($sig['return'], $result); }";
         code += " $result = \\__phpx_to_php($result);";
+        if (packageName) {
+            code += ` $result = \\__phpx_scoped_value('${escapePhpString(packageName)}', $result);`;
+        }
         code += " return $result;";
         code += " }\n";
     }
@@ -2735,6 +2756,7 @@ function buildLazyModuleRegistry(modules, entryPath) {
     const cacheRoot = resolveModuleCacheRoot(entryPath);
     ensureDir(cacheRoot);
     let lockDirty = false;
+    const packagePaths = [];
     for (const module of modules.values()){
         const deps = [];
         if (Array.isArray(module.deps)) {
@@ -2778,7 +2800,19 @@ function buildLazyModuleRegistry(modules, entryPath) {
         const depsList = deps.map((dep)=>`'${escapePhpString(dep)}'`).join(', ');
         const depsPhp = deps.length === 0 ? 'array()' : `array(${depsList})`;
         out += `__phpx_register_lazy('${escapePhpString(module.moduleId)}', '${escapePhpString(cachePath)}', ${depsPhp});\n`;
+        const packageName = packageNameFromModuleId(module.moduleId);
+        if (packageName) {
+            packagePaths.push([cachePath, packageName]);
+        }
     }
+    // Package attribution is keyed by the file being required and compiled
+    // into a function, so rewriting $GLOBALS['__PHPX_LAZY'] cannot drop it.
+    out += "if (!function_exists('__phpx_lazy_package')) { function __phpx_lazy_package($path) {\n";
+    for (const [cachePath, packageName] of packagePaths){
+        out += `  if ($path === '${escapePhpString(cachePath)}') { return '${escapePhpString(packageName)}'; }\n`;
+    }
+    out += "  return '';\n";
+    out += "} }\n";
     if (lockDirty) {
         writeDekaLock(lockInfo.lockPath, lock);
     }
@@ -3247,6 +3281,37 @@ function buildPhpxBridgePrelude() {
     out += "  $deny_dynamic = is_array($deny) && !empty($deny['dynamic']);\n";
     out += "  return $allow_dynamic && !$deny_dynamic;\n";
     out += "} }\n";
+    out += "if (!function_exists('__phpx_scope_enter')) { function __phpx_scope_enter($package) {\n";
+    out += "  $res = \\__deka_wasm_call('__deka_scope', 'enter', $package);\n";
+    out += "  if (!is_array($res) || empty($res['ok'])) { return __phpx_fail('security scope enter failed for ' . $package . ': ' . __phpx_array_get($res, 'error', 'bridge unavailable')); }\n";
+    out += "  return __phpx_array_get($res, 'token', '');\n";
+    out += "} }\n";
+    out += "if (!function_exists('__phpx_scope_exit')) { function __phpx_scope_exit($token) { \\__deka_wasm_call('__deka_scope', 'exit', $token); } }\n";
+    out += "if (!function_exists('__phpx_scoped_callable')) { function __phpx_scoped_callable($package, $callable) {\n";
+    out += "  return function ($a = null, $b = null, $c = null, $d = null, $e = null, $f = null) use ($package, $callable) {\n";
+    out += "    $argc = func_num_args(); $args = array();\n";
+    out += "    if ($argc >= 1) { $args[] = $a; } if ($argc >= 2) { $args[] = $b; } if ($argc >= 3) { $args[] = $c; }\n";
+    out += "    if ($argc >= 4) { $args[] = $d; } if ($argc >= 5) { $args[] = $e; } if ($argc >= 6) { $args[] = $f; }\n";
+    out += "    $scope = __phpx_scope_enter($package);\n";
+    out += "    if ($scope === null) { return null; }\n";
+    out += "    try { $result = call_user_func_array($callable, $args); } finally { __phpx_scope_exit($scope); }\n";
+    out += "    return __phpx_scoped_value($package, $result);\n";
+    out += "  };\n";
+    out += "} }\n";
+    // Closures handed back inside arrays run in the package scope as well.
+    out += "if (!function_exists('__phpx_scoped_value')) { function __phpx_scoped_value($package, $value) {\n";
+    out += "  if ($value instanceof \\Closure) { return __phpx_scoped_callable($package, $value); }\n";
+    out += "  if (is_array($value)) { foreach ($value as $key => $item) { $value[$key] = __phpx_scoped_value($package, $item); } }\n";
+    out += "  return $value;\n";
+    out += "} }\n";
+    // Package module bodies run from a frame of their own, so the scope token
+    // held by __phpx_require_scoped is not among the variables they can see.
+    out += "if (!function_exists('__phpx_require')) { function __phpx_require($__phpx_path) { require $__phpx_path; } }\n";
+    out += "if (!function_exists('__phpx_require_scoped')) { function __phpx_require_scoped($package, $path) {\n";
+    out += "  $scope = __phpx_scope_enter($package);\n";
+    out += "  if ($scope === null) { return null; }\n";
+    out += "  try { __phpx_require($path); } finally { __phpx_scope_exit($scope); }\n";
+    out += "} }\n";
     out += "if (!function_exists('__phpx_load')) { function __phpx_load($moduleId) {\n";
     out += "  if (isset($GLOBALS['__PHPX_MODULES'][$moduleId])) { return; }\n";
     out += "  if (!isset($GLOBALS['__PHPX_LAZY'][$moduleId])) { return __phpx_fail('Unknown phpx module: ' . $moduleId); }\n";
@@ -3262,7 +3327,9 @@ function buildPhpxBridgePrelude() {
     out += "  $deps = __phpx_array_get($entry, 'deps', array());\n";
     out += "  $dep_count = is_array($deps) ? count($deps) : 0;\n";
     out += "  for ($i = 0; $i < $dep_count; $i++) { $dep = $deps[$i]; __phpx_load($dep); }\n";
-    out += "  if (isset($entry['path'])) { require $entry['path']; }\n";
+    out += "  $package = isset($entry['path']) && function_exists('__phpx_lazy_package') ? __phpx_lazy_package($entry['path']) : '';\n";
+    out += "  if ($package !== '') { __phpx_require_scoped($package, $entry['path']); }\n";
+    out += "  else if (isset($entry['path'])) { require $entry['path']; }\n";
     out += "  else if (isset($entry['code'])) { if (!__phpx_dynamic_allowed()) { return __phpx_fail('SECURITY_DYNAMIC_EXEC_DENIED: dynamic module eval is blocked'); } eval($entry['code']); }\n";
     out += "  array_pop($GLOBALS['__PHPX_LOAD_STACK']);\n";
     out += "  if (!isset($GLOBALS['__PHPX_MODULES'][$moduleId])) {\n";
@@ -4517,6 +4584,24 @@ function routeHostCall(kind, action, payload) {
         }
         return { ok: false, error: 'fs protobuf bridge ops unavailable' };
     }
+    if (kind === 'scope') {
+        const act = String(action || '');
+        if (typeof op_php_security_scope_enter !== 'function' || typeof op_php_security_scope_exit !== 'function') {
+            return { ok: true };
+        }
+        // Enter hands back a token; exit only leaves the scope that token names.
+        if (act === 'enter') {
+            return { ok: true, token: op_php_security_scope_enter(String(payload || '')) };
+        }
+        if (act === 'exit') {
+            op_php_security_scope_exit(String(payload || ''));
+            return { ok: true };
+        }
+        return {
+            ok: false,
+            error: `unknown scope action '${act}'`
+        };
+    }
     if (kind === 'time') {
         const act = String(action || '');
         const req = payload || {};
@@ -4726,6 +4811,7 @@ function phpWasmCall(modulePtr, moduleLen, exportPtr, exportLen, argsPtr, argsLe
         const isFsBridge = moduleId === '__deka_fs' || moduleId.includes('__deka_fs');
         const isCryptoBridge = moduleId === '__deka_crypto' || moduleId.includes('__deka_crypto');
         const isTimeBridge = moduleId === '__deka_time' || moduleId.includes('__deka_time');
+        const isScopeBridge = moduleId === '__deka_scope';
         const isLegacyHostBridge = moduleId.startsWith('__deka_');
         if (isDbBridge || isNetBridge || isFsBridge || isCryptoBridge || isTimeBridge || isLegacyHostBridge) {
             let payload = null;
@@ -4743,7 +4829,9 @@ function phpWasmCall(modulePtr, moduleLen, exportPtr, exportLen, argsPtr, argsLe
             }
             let result = null;
             try {
-                if (isDbBridge || moduleId.includes('db')) {
+                if (isScopeBridge) {
+                    result = routeHostCall('scope', String(exportName || ''), payload);
+                } else if (isDbBridge || moduleId.includes('db')) {
                     const actionName = String(exportName || '');
                     if (actionName.startsWith('crypto:')) {
                         result = routeHostCall('crypto', actionName.slice('crypto:'.length), payload);
@@ -8,7 +8,7 @@ use native_tls::{TlsConnector, TlsStream};
 use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
 use php_rs::parser::ast::{ClassKind, ClassMember, Program, Stmt, Type as AstType};
 use php_rs::parser::lexer::Lexer;
-use php_rs::parser::lexer::token::Token;
+use php_rs::parser::lexer::token::{Token, TokenKind};
 use php_rs::parser::parser::{Parser, ParserMode, detect_parser_mode};
 use bytes::BytesMut;
 use postgres::{
@@ -18,14 +18,16 @@ use postgres::{
 use std::error::Error as StdError;
 use serde_json::{Map, Value};
 use prost::Message as ProstMessage;
+use runtime_core::security_audit::{AuditDecision, AuditEvent, AuditFormat, append_jsonl};
 use runtime_core::security_policy::{RuleList, SecurityPolicy, parse_deka_security_policy};
+use runtime_core::security_rules::{NetRule, NetTarget, glob_match, has_glob, path_glob_match};
 use rusqlite::types::ValueRef as SqliteValueRef;
 use rusqlite::{Connection as SqliteConnection, params_from_iter as sqlite_params_from_iter};
 use std::cell::Cell;
 use std::collections::{HashMap, HashSet};
 use std::fs::{File as StdFile, OpenOptions};
 use std::io::{IsTerminal, Read, Write};
-use std::net::{TcpStream, ToSocketAddrs};
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
 use std::sync::{Mutex, OnceLock};
 use std::time::{Duration, Instant};
 use wit_parser::{Resolve, Results, Type, TypeDefKind, TypeId, WorldItem, WorldKey};
@@ -971,6 +973,26 @@ impl<'a> TypeResolver<'a> {
                 }
                 BridgeType::ObjectShape { fields: out }
             }
+            AstType::Function { .. } => BridgeType::Mixed,
+            AstType::Literal(token) => BridgeType::Primitive {
+                name: if token.kind == TokenKind::LNumber {
+                    "int".to_string()
+                } else {
+                    "string".to_string()
+                },
+            },
+            AstType::KeyOf(_) => BridgeType::Primitive {
+                name: "string".to_string(),
+            },
+            AstType::Tuple(items) => {
+                let types = items
+                    .iter()
+                    .map(|item| self.convert_type_internal(item, alias_guard, subs))
+                    .collect::<Vec<_>>();
+                BridgeType::Array {
+                    element: Some(Box::new(BridgeType::Union { types })),
+                }
+            }
             AstType::Applied { base, args } => {
                 let base_name = self
                     .type_name(base)
@@ -1006,8 +1028,8 @@ impl<'a> TypeResolver<'a> {
                     let err = converted_args.get(1).cloned().map(Box::new);
                     return BridgeType::Result { ok, err };
                 }
-                if base_id == "array" {
-                    let element = converted_args.get(0).cloned().map(Box::new);
+                if base_id == "array" || base_id == "list" {
+                    let element = converted_args.last().cloned().map(Box::new);
                     return BridgeType::Array { element };
                 }
                 BridgeType::Applied {
@@ -1290,6 +1312,37 @@ fn op_php_set_privileged(#[number] enabled: i64, #[string] label: String) {
     set_security_privileged(enabled != 0, label);
 }
 
+#[op2]
+#[string]
+fn op_php_security_scope_enter(
+    #[string] package: String,
+) -> Result<String, deno_core::error::CoreError> {
+    enter_security_package(package)
+        .map_err(|e| deno_core::error::CoreError::from(std::io::Error::other(e)))
+}
+
+#[op2(fast)]
+fn op_php_security_scope_exit(#[string] token: String) -> Result<(), deno_core::error::CoreError> {
+    exit_security_package(&token)
+        .map_err(|e| deno_core::error::CoreError::from(std::io::Error::other(e)))
+}
+
+#[op2(fast)]
+fn op_php_security_scope_suspend(
+    #[string] token: String,
+) -> Result<(), deno_core::error::CoreError> {
+    suspend_security_package(&token)
+        .map_err(|e| deno_core::error::CoreError::from(std::io::Error::other(e)))
+}
+
+#[op2(fast)]
+fn op_php_security_scope_resume(
+    #[string] token: String,
+) -> Result<(), deno_core::error::CoreError> {
+    resume_security_package(&token)
+        .map_err(|e| deno_core::error::CoreError::from(std::io::Error::other(e)))
+}
+
 #[op2]
 #[string]
 fn op_php_sha256(#[string] data: String) -> String {
@@ -1319,7 +1372,11 @@ fn op_php_random_bytes(#[number] len: i64) -> Vec<u8> {
 #[op2]
 #[serde]
 fn op_php_read_env() -> HashMap<String, String> {
-    if enforce_env(None).is_err() {
+    let policy = security_policy_from_env();
+    // Scoped rules (`APP_*`) expose just the matching keys rather than prompting
+    // for the whole environment.
+    let scoped = matches!(policy.allow.env, RuleList::List(_));
+    if !scoped && enforce_env(None).is_err() {
         return HashMap::new();
     }
     let mut merged = HashMap::new();
@@ -1329,6 +1386,10 @@ fn op_php_read_env() -> HashMap<String, String> {
     for (key, value) in read_dotenv_from_cwd() {
         merged.insert(key, value);
     }
+    merged.retain(|key, _| {
+        (!scoped || rule_allows("env", &policy.allow.env, Some(key)))
+            && !rule_denies("env", &policy.deny.env, Some(key))
+    });
     merged
 }
 
@@ -1895,6 +1956,10 @@ fn core_err(msg: impl Into<String>) -> deno_core::error::CoreError {
     deno_core::error::CoreError::from(std::io::Error::other(msg.into()))
 }
 
+static INVALID_POLICY_REPORTED: OnceLock<()> = OnceLock::new();
+
+/// An absent policy means an embedder that never set one and gets the
+/// defaults; a policy that is present but unreadable fails closed.
 fn security_policy_from_env() -> SecurityPolicy {
     let raw = match std::env::var("DEKA_SECURITY_POLICY") {
         Ok(v) => v,
@@ -1902,16 +1967,37 @@ fn security_policy_from_env() -> SecurityPolicy {
     };
     let json = match serde_json::from_str::<serde_json::Value>(&raw) {
         Ok(v) => v,
-        Err(_) => return SecurityPolicy::default(),
+        Err(err) => {
+            report_invalid_policy(&[format!("invalid JSON: {}", err)]);
+            return SecurityPolicy::deny_all();
+        }
     };
     let parsed = parse_deka_security_policy(&json);
     if parsed.has_errors() {
-        SecurityPolicy::default()
+        let lines = parsed
+            .blocking(false)
+            .iter()
+            .map(|diag| diag.to_string())
+            .collect::<Vec<_>>();
+        report_invalid_policy(&lines);
+        SecurityPolicy::deny_all()
     } else {
         parsed.policy
     }
 }
 
+fn report_invalid_policy(lines: &[String]) {
+    if INVALID_POLICY_REPORTED.set(()).is_ok() {
+        stdio::error(
+            "security",
+            &format!(
+                "DEKA_SECURITY_POLICY is invalid, denying all capabilities:\n{}",
+                lines.join("\n")
+            ),
+        );
+    }
+}
+
 fn rule_allows(capability: &str, rule: &RuleList, target: Option<&str>) -> bool {
     match rule {
         RuleList::None => false,
@@ -1938,18 +2024,49 @@ fn match_rule_item(capability: &str, rule_item: &str, target: &str) -> bool {
     if rule_item == "*" {
         return true;
     }
-    if matches!(capability, "read" | "write" | "wasm") {
-        return path_matches(rule_item, target);
+    match capability {
+        "read" | "write" | "wasm" => path_matches(rule_item, target),
+        "net" => NetRule::parse(rule_item).is_ok_and(|rule| rule.allows(&NetTarget::parse(target))),
+        "env" if has_glob(rule_item) => glob_match(rule_item, target),
+        _ => rule_item == target,
     }
-    rule_item == target
 }
 
 fn path_matches(rule_item: &str, target: &str) -> bool {
     let target_path = normalize_path(target);
+    if has_glob(rule_item) {
+        // Like prefix rules, a glob that matches a directory covers everything below it.
+        let pattern = normalize_glob_pattern(rule_item);
+        return target_path
+            .ancestors()
+            .any(|path| path_glob_match(&pattern, &path.to_string_lossy().replace('\\', "/")));
+    }
     let rule_path = normalize_path(rule_item);
     target_path.starts_with(&rule_path)
 }
 
+/// Resolve the literal directory prefix of a glob the same way targets are
+/// resolved, leaving the wildcard segments untouched.
+fn normalize_glob_pattern(pattern: &str) -> String {
+    let pattern = pattern.replace('\\', "/");
+    let segments: Vec<&str> = pattern.split('/').collect();
+    let literal = segments
+        .iter()
+        .position(|segment| has_glob(segment))
+        .unwrap_or(segments.len());
+    let prefix = segments[..literal].join("/");
+    let base = if prefix.is_empty() && pattern.starts_with('/') {
+        "/".to_string()
+    } else if prefix.is_empty() {
+        ".".to_string()
+    } else {
+        prefix
+    };
+    let base = normalize_path(&base).to_string_lossy().replace('\\', "/");
+    let rest = segments[literal..].join("/");
+    format!("{}/{}", base.trim_end_matches('/'), rest)
+}
+
 fn normalize_path(value: &str) -> std::path::PathBuf {
     let path = std::path::Path::new(value);
     let resolved = if path.is_absolute() {
@@ -1965,6 +2082,83 @@ fn normalize_path(value: &str) -> std::path::PathBuf {
 thread_local! {
     static SECURITY_PRIVILEGED: Cell<bool> = Cell::new(false);
     static SECURITY_PRIVILEGED_LABEL: std::cell::RefCell<Option<String>> = const { std::cell::RefCell::new(None) };
+    static SECURITY_PACKAGE_STACK: std::cell::RefCell<Vec<SecurityScope>> = const { std::cell::RefCell::new(Vec::new()) };
+    static SECURITY_SUSPENDED_SCOPES: std::cell::RefCell<Vec<SecurityScope>> = const { std::cell::RefCell::new(Vec::new()) };
+}
+
+struct SecurityScope {
+    package: String,
+    token: String,
+}
+
+/// Export wrappers of installed packages bracket each call with enter/exit so
+/// bridge calls can be attributed to the package that made them. Enter hands
+/// out a random token that only the wrapper holds; exit requires it, so
+/// package code cannot leave the scope it runs in.
+fn enter_security_package(package: String) -> Result<String, String> {
+    let mut bytes = [0u8; 16];
+    getrandom::getrandom(&mut bytes)
+        .map_err(|e| format!("security scope enter: random source unavailable: {}", e))?;
+    let token = bytes
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<String>();
+    SECURITY_PACKAGE_STACK.with(|stack| {
+        stack.borrow_mut().push(SecurityScope {
+            package,
+            token: token.clone(),
+        })
+    });
+    Ok(token)
+}
+
+/// Scopes are removed by token rather than popped, and an unknown token
+/// leaves the stack untouched.
+fn exit_security_package(token: &str) -> Result<(), String> {
+    take_security_scope(&SECURITY_PACKAGE_STACK, token)
+        .map(|_| ())
+        .ok_or_else(|| "security scope exit: unknown scope token".to_string())
+}
+
+/// An async package call parks its scope while it awaits, so code that runs
+/// in the meantime is not attributed to the package. The scope keeps its
+/// token and is pushed back when the call resumes.
+fn suspend_security_package(token: &str) -> Result<(), String> {
+    let scope = take_security_scope(&SECURITY_PACKAGE_STACK, token)
+        .ok_or_else(|| "security scope suspend: unknown scope token".to_string())?;
+    SECURITY_SUSPENDED_SCOPES.with(|parked| parked.borrow_mut().push(scope));
+    Ok(())
+}
+
+fn resume_security_package(token: &str) -> Result<(), String> {
+    let scope = take_security_scope(&SECURITY_SUSPENDED_SCOPES, token)
+        .ok_or_else(|| "security scope resume: unknown scope token".to_string())?;
+    SECURITY_PACKAGE_STACK.with(|stack| stack.borrow_mut().push(scope));
+    Ok(())
+}
+
+fn take_security_scope(
+    key: &'static std::thread::LocalKey<std::cell::RefCell<Vec<SecurityScope>>>,
+    token: &str,
+) -> Option<SecurityScope> {
+    key.with(|scopes| {
+        let mut scopes = scopes.borrow_mut();
+        let index = scopes.iter().rposition(|scope| scope.token == token)?;
+        Some(scopes.remove(index))
+    })
+}
+
+/// Distinct packages on the current call stack, outermost first.
+fn security_packages_on_stack() -> Vec<String> {
+    SECURITY_PACKAGE_STACK.with(|stack| {
+        let mut out: Vec<String> = Vec::new();
+        for scope in stack.borrow().iter() {
+            if !out.contains(&scope.package) {
+                out.push(scope.package.clone());
+            }
+        }
+        out
+    })
 }
 
 fn set_security_privileged(enabled: bool, label: Option<String>) {
@@ -2014,9 +2208,12 @@ fn is_internal_security_target(target: &str) -> bool {
 #[cfg(test)]
 mod security_rule_tests {
     use super::{
-        RuleList, classify_security_origin, is_internal_security_target, is_runtime_safe_env_key,
-        match_rule_item, normalize_rel_like, prompt_scope_key, rule_allows, rule_denies,
+        RuleList, classify_security_origin, enter_security_package, exit_security_package,
+        is_internal_security_target, is_runtime_safe_env_key, match_rule_item, normalize_rel_like,
+        package_scope_violation, prompt_scope_key, resume_security_package, rule_allows,
+        rule_denies, security_packages_on_stack, suspend_security_package,
     };
+    use runtime_core::security_policy::parse_deka_security_policy;
     use std::fs;
     use std::path::PathBuf;
     use std::time::{SystemTime, UNIX_EPOCH};
@@ -2062,6 +2259,122 @@ mod security_rule_tests {
         assert!(!match_rule_item("env", "DATABASE_URL", "PATH"));
     }
 
+    #[test]
+    fn env_and_net_rules_accept_patterns() {
+        assert!(match_rule_item("env", "APP_*", "APP_SECRET"));
+        assert!(!match_rule_item("env", "APP_*", "DATABASE_URL"));
+        assert!(match_rule_item(
+            "net",
+            "*.internal.example:443",
+            "api.internal.example:443"
+        ));
+        assert!(!match_rule_item(
+            "net",
+            "*.internal.example:443",
+            "api.internal.example:80"
+        ));
+        assert!(match_rule_item("net", "10.0.0.0/8", "10.2.3.4:5432"));
+        assert!(match_rule_item("net", "localhost", "localhost:5432"));
+        assert!(!match_rule_item("run", "g*", "git"));
+    }
+
+    #[test]
+    fn package_scope_narrows_app_grants() {
+        let policy = parse_deka_security_policy(&serde_json::json!({
+            "security": {
+                "allow": { "net": true, "env": true },
+                "packages": {
+                    "@acme/mailer": {
+                        "allow": { "net": ["smtp.example.com:587"], "env": ["MAIL_*"] },
+                        "deny": { "env": ["MAIL_PASSWORD_OLD"] }
+                    }
+                }
+            }
+        }))
+        .policy;
+        let check = |packages: &[&str], capability: &str, target: &str| {
+            let packages: Vec<String> = packages.iter().map(|p| p.to_string()).collect();
+            package_scope_violation(
+                &policy,
+                &packages,
+                capability,
+                Some(target),
+                |rule| rule_allows(capability, rule, Some(target)),
+                |rule| rule_denies(capability, rule, Some(target)),
+            )
+        };
+
+        assert!(check(&[], "net", "evil.example:443").is_none());
+        assert!(check(&["@acme/mailer"], "net", "smtp.example.com:587").is_none());
+        assert!(check(&["@acme/mailer"], "env", "MAIL_HOST").is_none());
+        let denied = check(&["@acme/mailer"], "env", "MAIL_PASSWORD_OLD").unwrap();
+        assert!(denied.starts_with("SECURITY_PACKAGE_DENIED"));
+        let ungranted = check(&["@acme/mailer"], "net", "evil.example:443").unwrap();
+        assert!(ungranted.contains("security.packages[\"@acme/mailer\"].allow.net"));
+        // Unlisted packages get nothing once scoping is on, even when called
+        // through a granted package.
+        assert!(check(&["@evil/pkg"], "net", "smtp.example.com:587").is_some());
+        assert!(
+            check(
+                &["@acme/mailer", "@evil/pkg"],
+                "net",
+                "smtp.example.com:587"
+            )
+            .is_some()
+        );
+    }
+
+    #[test]
+    fn package_code_cannot_exit_its_own_scope() {
+        let wrapper = enter_security_package("@evil/pkg".to_string()).unwrap();
+        // Package code reaches the bridge but never sees the wrapper's token.
+        assert!(exit_security_package("").is_err());
+        assert!(exit_security_package("00000000000000000000000000000000").is_err());
+        let own = enter_security_package("@evil/pkg".to_string()).unwrap();
+        exit_security_package(&own).unwrap();
+        assert_eq!(security_packages_on_stack(), vec!["@evil/pkg".to_string()]);
+
+        exit_security_package(&wrapper).unwrap();
+        assert!(security_packages_on_stack().is_empty());
+        assert!(exit_security_package(&wrapper).is_err());
+    }
+
+    #[test]
+    fn suspended_scopes_leave_the_stack_until_resumed() {
+        let call = enter_security_package("@acme/async".to_string()).unwrap();
+        suspend_security_package(&call).unwrap();
+        assert!(security_packages_on_stack().is_empty());
+        // Only the call that parked the scope can bring it back.
+        assert!(resume_security_package("00000000000000000000000000000000").is_err());
+        assert!(suspend_security_package(&call).is_err());
+
+        resume_security_package(&call).unwrap();
+        assert_eq!(
+            security_packages_on_stack(),
+            vec!["@acme/async".to_string()]
+        );
+        assert!(resume_security_package(&call).is_err());
+        exit_security_package(&call).unwrap();
+        assert!(security_packages_on_stack().is_empty());
+    }
+
+    #[test]
+    fn read_allows_glob_path() {
+        let root = temp_dir();
+        let nested = root.join("packages/a/src");
+        fs::create_dir_all(&nested).unwrap();
+        let file = nested.join("main.phpx");
+        fs::write(&file, "ok").unwrap();
+        let other = root.join("packages/a/secret.txt");
+        fs::write(&other, "no").unwrap();
+        let rule = RuleList::List(vec![format!("{}/packages/*/src", root.to_string_lossy())]);
+        assert!(rule_allows("read", &rule, Some(&file.to_string_lossy())));
+        assert!(!rule_allows("read", &rule, Some(&other.to_string_lossy())));
+        let rule = RuleList::List(vec![format!("{}/**/*.phpx", root.to_string_lossy())]);
+        assert!(rule_allows("read", &rule, Some(&file.to_string_lossy())));
+        assert!(!rule_allows("read", &rule, Some(&other.to_string_lossy())));
+    }
+
     #[test]
     fn internal_security_targets_match_expected_paths() {
         assert!(is_internal_security_target("deka.lock"));
@@ -2128,6 +2441,12 @@ fn enforce_scope(
         return Ok(());
     }
     if rule_denies(capability, deny_rule, target) {
+        audit_decision(
+            AuditDecision::Deny,
+            capability,
+            target,
+            matched_rule("deny", capability, deny_rule, target),
+        );
         return Err(core_err(format!(
             "SECURITY_POLICY_DENY_PRECEDENCE: capability={} target={} denied by policy",
             capability,
@@ -2136,7 +2455,12 @@ fn enforce_scope(
     }
     if !rule_allows(capability, allow_rule, target) {
         if prompt_enabled() && prompt_grant(capability, target)? {
-            return Ok(());
+            return enforce_package_scope(
+                capability,
+                target,
+                |rule| rule_allows(capability, rule, target),
+                |rule| rule_denies(capability, rule, target),
+            );
         }
         let origin = classify_security_origin(capability, target);
         let mut message = format!(
@@ -2149,11 +2473,179 @@ fn enforce_scope(
             message.push_str(" Hint: ");
             message.push_str(&hint);
         }
+        audit_decision(AuditDecision::Deny, capability, target, None);
         return Err(core_err(message));
     }
+    enforce_package_scope(
+        capability,
+        target,
+        |rule| rule_allows(capability, rule, target),
+        |rule| rule_denies(capability, rule, target),
+    )?;
+    audit_decision(
+        AuditDecision::Allow,
+        capability,
+        target,
+        matched_rule("allow", capability, allow_rule, target),
+    );
     Ok(())
 }
 
+/// Describe the first rule item that matched, for the audit log.
+fn matched_rule(
+    kind: &str,
+    capability: &str,
+    rule: &RuleList,
+    target: Option<&str>,
+) -> Option<String> {
+    let item = match rule {
+        RuleList::None => return None,
+        RuleList::All => "true",
+        RuleList::List(items) => {
+            let target = target?;
+            items
+                .iter()
+                .map(String::as_str)
+                .find(|item| match_rule_item(capability, item, target))?
+        }
+    };
+    Some(format!("{}.{}: {}", kind, capability, item))
+}
+
+thread_local! {
+    static AUDIT_SQLITE: std::cell::RefCell<Option<(std::path::PathBuf, SqliteConnection)>> =
+        const { std::cell::RefCell::new(None) };
+}
+
+/// Append a decision to the `security.audit` log, if one is configured.
+/// Audit failures never change the outcome of the check itself.
+fn audit_decision(
+    decision: AuditDecision,
+    capability: &str,
+    target: Option<&str>,
+    rule: Option<String>,
+) {
+    let policy = security_policy_from_env();
+    let Some(config) = policy.audit.as_ref() else {
+        return;
+    };
+    let mut event = AuditEvent::new(decision, capability, target);
+    event.origin = Some(classify_security_origin(capability, target).to_string());
+    event.module = security_packages_on_stack().pop();
+    event.rule = rule;
+    let root = project_root()
+        .or_else(|| std::env::current_dir().ok())
+        .unwrap_or_else(|| std::path::PathBuf::from("."));
+    let path = config.resolve_path(&root);
+    let result = match config.format {
+        AuditFormat::Jsonl => append_jsonl(&path, &event).map_err(|err| err.to_string()),
+        AuditFormat::Sqlite => append_audit_sqlite(&path, &event),
+    };
+    if let Err(err) = result {
+        stdio::debug(
+            "security",
+            &format!("audit log write failed ({}): {}", path.display(), err),
+        );
+    }
+}
+
+fn append_audit_sqlite(path: &std::path::Path, event: &AuditEvent) -> Result<(), String> {
+    AUDIT_SQLITE.with(|slot| {
+        let mut slot = slot.borrow_mut();
+        if slot.as_ref().is_none_or(|(open_path, _)| open_path != path) {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent).map_err(|err| err.to_string())?;
+            }
+            let conn = SqliteConnection::open(path).map_err(|err| err.to_string())?;
+            conn.execute_batch(runtime_core::security_audit::SQLITE_SCHEMA)
+                .map_err(|err| err.to_string())?;
+            *slot = Some((path.to_path_buf(), conn));
+        }
+        let (_, conn) = slot.as_ref().expect("audit connection opened");
+        conn.execute(
+            runtime_core::security_audit::SQLITE_INSERT,
+            rusqlite::params![
+                event.ts_ms as i64,
+                event.decision.as_str(),
+                event.capability,
+                event.target,
+                event.origin,
+                event.module,
+                event.request_id,
+                event.rule,
+            ],
+        )
+        .map(|_| ())
+        .map_err(|err| err.to_string())
+    })
+}
+
+/// Narrow an app-level grant to what `security.packages` gives every installed
+/// package on the call stack, so a dependency cannot borrow the app's grants.
+fn enforce_package_scope(
+    capability: &str,
+    target: Option<&str>,
+    allows: impl Fn(&RuleList) -> bool,
+    denies: impl Fn(&RuleList) -> bool,
+) -> Result<(), deno_core::error::CoreError> {
+    if security_privileged_enabled() {
+        return Ok(());
+    }
+    let packages = security_packages_on_stack();
+    if packages.is_empty() {
+        return Ok(());
+    }
+    let policy = security_policy_from_env();
+    match package_scope_violation(&policy, &packages, capability, target, allows, denies) {
+        Some(message) => {
+            audit_decision(
+                AuditDecision::Deny,
+                capability,
+                target,
+                Some("security.packages".to_string()),
+            );
+            Err(core_err(message))
+        }
+        None => Ok(()),
+    }
+}
+
+fn package_scope_violation(
+    policy: &SecurityPolicy,
+    packages: &[String],
+    capability: &str,
+    target: Option<&str>,
+    allows: impl Fn(&RuleList) -> bool,
+    denies: impl Fn(&RuleList) -> bool,
+) -> Option<String> {
+    if !policy.scopes_packages() {
+        return None;
+    }
+    let target_label = target.unwrap_or("*");
+    for package in packages {
+        let scope = policy.packages.get(package);
+        let denied = scope
+            .and_then(|scope| scope.deny.rule(capability))
+            .is_some_and(&denies);
+        if denied {
+            return Some(format!(
+                "SECURITY_PACKAGE_DENIED: package={} capability={} target={} denied by package policy",
+                package, capability, target_label
+            ));
+        }
+        let allowed = scope
+            .and_then(|scope| scope.allow.rule(capability))
+            .is_some_and(&allows);
+        if !allowed {
+            return Some(format!(
+                "SECURITY_PACKAGE_CAPABILITY_DENIED: package={} capability={} target={} not granted to this package. Hint: security.packages[\"{}\"].allow.{} = [\"{}\"]",
+                package, capability, target_label, package, capability, target_label
+            ));
+        }
+    }
+    None
+}
+
 fn security_enforcement_enabled() -> bool {
     true
 }
@@ -2189,6 +2681,13 @@ fn prompt_grant(
             .lock()
             .map_err(|_| core_err("security prompt lock poisoned"))?;
         if grants.contains(&key) {
+            drop(grants);
+            audit_decision(
+                AuditDecision::Allow,
+                capability,
+                target,
+                Some(format!("prompt: {}", key)),
+            );
             return Ok(true);
         }
     }
@@ -2216,11 +2715,22 @@ fn prompt_grant(
     match std::io::stdin().read_line(&mut line) {
         Ok(_) => {
             let accepted = matches!(line.trim().to_ascii_lowercase().as_str(), "y" | "yes");
+            audit_decision(
+                if accepted {
+                    AuditDecision::PromptGranted
+                } else {
+                    AuditDecision::PromptDenied
+                },
+                capability,
+                target,
+                Some(format!("prompt: {}", key)),
+            );
             if accepted {
                 let mut grants = prompt_grants()
                     .lock()
                     .map_err(|_| core_err("security prompt lock poisoned"))?;
                 grants.insert(key);
+                drop(grants);
                 if let Err(err) = update_deka_json_allow(capability, target) {
                     eprintln!("[security] note: failed to update deka.json: {}", err);
                 } else {
@@ -2601,12 +3111,19 @@ fn update_deka_json_allow(capability: &str, target: Option<&str>) -> Result<(),
         }
     }
 
+    let persisted = list.join(", ");
     *entry = Value::Array(list.into_iter().map(Value::String).collect());
 
     let payload = serde_json::to_string_pretty(&doc)
         .map_err(|err| format!("failed to serialize deka.json: {}", err))?;
     std::fs::write(&path, payload)
         .map_err(|err| format!("failed to write {}: {}", path.display(), err))?;
+    audit_decision(
+        AuditDecision::Persisted,
+        capability,
+        Some(target),
+        Some(format!("allow.{}: {}", capability, persisted)),
+    );
     Ok(())
 }
 
@@ -2691,6 +3208,104 @@ fn enforce_net(target: Option<&str>) -> Result<(), deno_core::error::CoreError>
     enforce_scope("net", &policy.allow.net, &policy.deny.net, target)
 }
 
+/// Check a resolved connect target: deny rules match the name or any resolved
+/// address, IP allow rules must cover every resolved address.
+fn enforce_net_target(target: &NetTarget, label: &str) -> Result<(), deno_core::error::CoreError> {
+    if !security_enforcement_enabled() {
+        return Ok(());
+    }
+    let policy = security_policy_from_env();
+    let matches = |rule: &RuleList, check: fn(&NetRule, &NetTarget) -> bool| match rule {
+        RuleList::None => false,
+        RuleList::All => true,
+        RuleList::List(items) => items
+            .iter()
+            .any(|item| item == "*" || NetRule::parse(item).is_ok_and(|rule| check(&rule, target))),
+    };
+    let matched = |kind: &str, rule: &RuleList, check: fn(&NetRule, &NetTarget) -> bool| {
+        let item = match rule {
+            RuleList::None => return None,
+            RuleList::All => "true",
+            RuleList::List(items) => items
+                .iter()
+                .map(String::as_str)
+                .find(|item| {
+                    *item == "*" || NetRule::parse(item).is_ok_and(|rule| check(&rule, target))
+                })?,
+        };
+        Some(format!("{}.net: {}", kind, item))
+    };
+    if matches(&policy.deny.net, NetRule::denies) {
+        audit_decision(
+            AuditDecision::Deny,
+            "net",
+            Some(label),
+            matched("deny", &policy.deny.net, NetRule::denies),
+        );
+        return Err(core_err(format!(
+            "SECURITY_POLICY_DENY_PRECEDENCE: capability=net target={} denied by policy",
+            label
+        )));
+    }
+    if matches(&policy.allow.net, NetRule::allows) {
+        enforce_package_scope(
+            "net",
+            Some(label),
+            |rule| matches(rule, NetRule::allows),
+            |rule| matches(rule, NetRule::denies),
+        )?;
+        audit_decision(
+            AuditDecision::Allow,
+            "net",
+            Some(label),
+            matched("allow", &policy.allow.net, NetRule::allows),
+        );
+        return Ok(());
+    }
+    // Not covered by the policy: fall through to the prompt / denial path.
+    enforce_scope("net", &RuleList::None, &RuleList::None, Some(label))
+}
+
+/// Resolve a connect target once, enforce the policy against the name and its
+/// addresses, then pin the connection to the checked addresses so a second
+/// lookup cannot rebind it to a denied IP. A failed lookup fails the connect.
+fn enforce_net_connect(payload: &mut serde_json::Value) -> Result<(), deno_core::error::CoreError> {
+    let host = payload
+        .get("host")
+        .and_then(|v| v.as_str())
+        .unwrap_or("127.0.0.1")
+        .trim_matches('\0')
+        .to_string();
+    let port = payload.get("port").and_then(|v| v.as_u64()).unwrap_or(0) as u16;
+    if port == 0 {
+        return enforce_net(Some(&host));
+    }
+    let label = if host.contains(':') && !host.starts_with('[') {
+        format!("[{}]:{}", host, port)
+    } else {
+        format!("{}:{}", host, port)
+    };
+    let addrs: Vec<SocketAddr> = label
+        .to_socket_addrs()
+        .map_err(|e| core_err(format!("connect: resolve failed for {}: {}", label, e)))?
+        .collect();
+    if addrs.is_empty() {
+        return Err(core_err(format!(
+            "connect: no resolved address for {}",
+            label
+        )));
+    }
+    let target = NetTarget::parse(&label).with_addrs(addrs.iter().map(|addr| addr.ip()));
+    enforce_net_target(&target, &label)?;
+    payload["addrs"] = serde_json::Value::Array(
+        addrs
+            .iter()
+            .map(|addr| serde_json::Value::String(addr.to_string()))
+            .collect(),
+    );
+    Ok(())
+}
+
 fn enforce_env(target: Option<&str>) -> Result<(), deno_core::error::CoreError> {
     let policy = security_policy_from_env();
     enforce_scope("env", &policy.allow.env, &policy.deny.env, target)
@@ -3265,12 +3880,6 @@ fn net_call_impl(
     let args_obj = args.as_object().cloned().unwrap_or_default();
     match action.as_str() {
         "connect" => {
-            let host = args_obj
-                .get("host")
-                .and_then(|v| v.as_str())
-                .unwrap_or("127.0.0.1")
-                .trim_matches('\0')
-                .to_string();
             let port = args_obj.get("port").and_then(|v| v.as_u64()).unwrap_or(0) as u16;
             if port == 0 {
                 return Ok(
@@ -3281,15 +3890,38 @@ fn net_call_impl(
                 .get("timeout_ms")
                 .and_then(|v| v.as_u64())
                 .unwrap_or(5000);
-            let addr = format!("{}:{}", host, port);
-            let mut addrs = addr
-                .to_socket_addrs()
-                .map_err(|e| err(format!("connect: resolve failed: {}", e)))?;
-            let target = addrs
-                .next()
-                .ok_or_else(|| err("connect: no resolved address".to_string()))?;
-            let stream = TcpStream::connect_timeout(&target, Duration::from_millis(timeout_ms))
-                .map_err(|e| err(format!("connect: {}", e)))?;
+            // Only the addresses `enforce_net_connect` resolved and checked are
+            // dialed; resolving the host again here would reopen a rebinding window.
+            let pinned: Vec<SocketAddr> = args_obj
+                .get("addrs")
+                .and_then(|v| v.as_array())
+                .map(|addrs| {
+                    addrs
+                        .iter()
+                        .filter_map(|addr| addr.as_str()?.parse::<SocketAddr>().ok())
+                        .collect()
+                })
+                .unwrap_or_default();
+            if pinned.is_empty() {
+                return Err(err("connect: target was not resolved".to_string()));
+            }
+            let mut last_error = None;
+            let mut connected = None;
+            for target in &pinned {
+                match TcpStream::connect_timeout(target, Duration::from_millis(timeout_ms)) {
+                    Ok(stream) => {
+                        connected = Some(stream);
+                        break;
+                    }
+                    Err(e) => last_error = Some(e),
+                }
+            }
+            let stream = connected.ok_or_else(|| {
+                err(format!(
+                    "connect: {}",
+                    last_error.map(|e| e.to_string()).unwrap_or_default()
+                ))
+            })?;
             let mut state = net_state()
                 .lock()
                 .map_err(|_| err("net lock poisoned".to_string()))?;
@@ -3689,9 +4321,13 @@ fn net_call_proto_impl(request: &[u8]) -> Result<Vec<u8>, deno_core::error::Core
     let started = Instant::now();
     let req = proto::bridge_v1::NetRequest::decode(request)
         .map_err(|e| core_err(format!("net proto decode failed: {}", e)))?;
-    let (action, payload, kind) = net_proto_request_to_action_payload(&req)?;
-    let net_target = payload.get("host").and_then(|v| v.as_str()).or(Some("*"));
-    enforce_net(net_target)?;
+    let (action, mut payload, kind) = net_proto_request_to_action_payload(&req)?;
+    if action == "connect" {
+        enforce_net_connect(&mut payload)?;
+    } else {
+        let net_target = payload.get("host").and_then(|v| v.as_str()).or(Some("*"));
+        enforce_net(net_target)?;
+    }
     let response_json = net_call_impl(action, payload)?;
     let response = net_json_response_to_proto(&response_json, kind);
     let out = response.encode_to_vec();
@@ -4562,6 +5198,10 @@ deno_core::extension!(
         op_php_write_file_sync,
         op_php_mkdirs,
         op_php_set_privileged,
+        op_php_security_scope_enter,
+        op_php_security_scope_exit,
+        op_php_security_scope_suspend,
+        op_php_security_scope_resume,
         op_php_sha256,
         op_php_random_bytes,
         op_php_read_env,
@@ -4855,6 +5495,20 @@ mod tests {
         let _ = std::fs::remove_file(path);
     }
 
+    #[test]
+    fn net_connect_never_dials_an_unchecked_address() {
+        let mut payload = serde_json::json!({
+            "host": "deka-unresolvable.invalid",
+            "port": 443,
+        });
+        assert!(enforce_net_connect(&mut payload).is_err());
+        assert!(payload.get("addrs").is_none());
+
+        let err = net_call_impl("connect".to_string(), payload)
+            .expect_err("connect without pinned addresses must fail");
+        assert!(err.to_string().contains("not resolved"), "{}", err);
+    }
+
     #[test]
     fn net_proto_tcp_parity_sanity() {
         let listener = TcpListener::bind("127.0.0.1:0").expect("bind listener");
@@ -4868,15 +5522,14 @@ mod tests {
             }
         });
 
-        let json_connect = net_call_impl(
-            "connect".to_string(),
-            serde_json::json!({
-                "host": "127.0.0.1",
-                "port": addr.port(),
-                "timeout_ms": 3000
-            }),
-        )
-        .expect("json connect failed");
+        let mut connect_payload = serde_json::json!({
+            "host": "127.0.0.1",
+            "port": addr.port(),
+            "timeout_ms": 3000
+        });
+        enforce_net_connect(&mut connect_payload).expect("resolve connect target");
+        let json_connect =
+            net_call_impl("connect".to_string(), connect_payload).expect("json connect failed");
         assert_ok(&json_connect);
         let json_handle = json_connect
             .get("handle")
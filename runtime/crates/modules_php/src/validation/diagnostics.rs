@@ -0,0 +1,310 @@
+//! Suppression and machine-readable output for PHPX diagnostics.
+//!
+//! Codes can be silenced inline with `phpx-ignore` comments (see
+//! `php_rs::phpx::diagnostics::Suppressions`) or project-wide in `deka.json`:
+//!
+//! ```json
+//! { "phpx": { "diagnostics": { "ignore": ["PX0306"] } } }
+//! ```
+//!
+//! `deka build` and `deka run` accept `--error-format=json`, which sets
+//! `DEKA_ERROR_FORMAT=json` so every compile in the process reports one JSON
+//! object per diagnostic instead of the boxed human output.
+
+use std::path::{Path, PathBuf};
+
+use php_rs::phpx::diagnostics::{Suppressions, describe};
+use serde::Serialize;
+use serde_json::Value;
+
+use super::{
+    Fix, RelatedLocation, Severity, ValidationError, ValidationWarning, format_multiple_errors,
+};
+
+pub const ERROR_FORMAT_ENV: &str = "DEKA_ERROR_FORMAT";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ErrorFormat {
+    #[default]
+    Human,
+    Json,
+}
+
+impl ErrorFormat {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "human" | "text" => Some(Self::Human),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+
+    pub fn from_env() -> Self {
+        std::env::var(ERROR_FORMAT_ENV)
+            .ok()
+            .and_then(|value| Self::parse(&value))
+            .unwrap_or_default()
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Human => "human",
+            Self::Json => "json",
+        }
+    }
+}
+
+/// Drops diagnostics silenced by `phpx-ignore` comments in `source` or by
+/// `phpx.diagnostics.ignore` in the nearest `deka.json` above `file_path`.
+pub fn apply_suppressions(
+    source: &str,
+    file_path: &str,
+    errors: &mut Vec<ValidationError>,
+    warnings: &mut Vec<ValidationWarning>,
+) {
+    let mut suppressions = Suppressions::from_source(source);
+    for code in project_ignored_codes(file_path) {
+        suppressions.ignore_everywhere(code);
+    }
+    if suppressions.is_empty() {
+        return;
+    }
+    errors.retain(|error| !suppressions.is_suppressed(error.line, error.code()));
+    warnings.retain(|warning| !suppressions.is_suppressed(warning.line, warning.code()));
+}
+
+/// Codes listed in `phpx.diagnostics.ignore` of the nearest `deka.json` above
+/// `file_path`, upper-cased.
+pub fn project_ignored_codes(file_path: &str) -> Vec<String> {
+    let Some(manifest) = find_manifest(Path::new(file_path)) else {
+        return Vec::new();
+    };
+    let Ok(raw) = std::fs::read_to_string(&manifest) else {
+        return Vec::new();
+    };
+    let Ok(parsed) = serde_json::from_str::<Value>(&raw) else {
+        return Vec::new();
+    };
+    parsed
+        .get("phpx")
+        .and_then(|phpx| phpx.get("diagnostics"))
+        .and_then(|diagnostics| diagnostics.get("ignore"))
+        .and_then(|ignore| ignore.as_array())
+        .map(|codes| {
+            codes
+                .iter()
+                .filter_map(|code| code.as_str())
+                .map(|code| code.trim().to_ascii_uppercase())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn find_manifest(file_path: &Path) -> Option<PathBuf> {
+    let start = if file_path.is_dir() {
+        file_path
+    } else {
+        file_path.parent()?
+    };
+    start
+        .ancestors()
+        .map(|dir| dir.join("deka.json"))
+        .find(|candidate| candidate.is_file())
+}
+
+/// Wire format of a diagnostic for `--error-format=json`. Field names are part
+/// of the CLI contract; add fields rather than renaming them.
+#[derive(Debug, Serialize)]
+struct JsonDiagnostic<'a> {
+    code: &'static str,
+    summary: Option<&'static str>,
+    severity: &'static str,
+    kind: &'static str,
+    message: &'a str,
+    help: Option<&'a str>,
+    file: &'a str,
+    line: usize,
+    column: usize,
+    length: usize,
+    related: &'a [RelatedLocation],
+    notes: &'a [String],
+    fixes: &'a [Fix],
+}
+
+fn severity_name(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Info => "info",
+    }
+}
+
+fn non_empty(value: &str) -> Option<&str> {
+    let trimmed = value.trim();
+    if trimmed.is_empty() {
+        None
+    } else {
+        Some(trimmed)
+    }
+}
+
+macro_rules! json_diagnostic {
+    ($diag:expr, $file:expr) => {
+        JsonDiagnostic {
+            code: $diag.code(),
+            summary: describe($diag.code()),
+            severity: severity_name($diag.severity),
+            kind: $diag.kind.as_str(),
+            message: &$diag.message,
+            help: non_empty(&$diag.help_text),
+            file: $file,
+            line: $diag.line,
+            column: $diag.column,
+            length: $diag.underline_length,
+            related: &$diag.related,
+            notes: &$diag.notes,
+            fixes: &$diag.fixes,
+        }
+    };
+}
+
+/// Renders diagnostics as newline-delimited JSON, errors first.
+pub fn format_json_diagnostics(
+    file_path: &str,
+    errors: &[ValidationError],
+    warnings: &[ValidationWarning],
+) -> String {
+    let mut out = String::new();
+    for error in errors {
+        push_json_line(&mut out, &json_diagnostic!(error, file_path));
+    }
+    for warning in warnings {
+        push_json_line(&mut out, &json_diagnostic!(warning, file_path));
+    }
+    out
+}
+
+fn push_json_line(out: &mut String, diagnostic: &JsonDiagnostic<'_>) {
+    if let Ok(line) = serde_json::to_string(diagnostic) {
+        out.push_str(&line);
+        out.push('\n');
+    }
+}
+
+pub fn format_diagnostics(
+    source: &str,
+    file_path: &str,
+    errors: &[ValidationError],
+    warnings: &[ValidationWarning],
+    format: ErrorFormat,
+) -> String {
+    match format {
+        ErrorFormat::Human => format_multiple_errors(source, file_path, errors, warnings),
+        ErrorFormat::Json => format_json_diagnostics(file_path, errors, warnings),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validation::ErrorKind;
+    use php_rs::phpx::diagnostics::{Applicability, codes};
+    use std::fs;
+
+    fn error_at(line: usize, code: Option<&'static str>) -> ValidationError {
+        ValidationError {
+            kind: ErrorKind::TypeError,
+            line,
+            column: 1,
+            message: "Type mismatch: expected int, got string".to_string(),
+            help_text: "Fix the type mismatch or update the annotation.".to_string(),
+            suggestion: None,
+            underline_length: 3,
+            severity: Severity::Error,
+            code,
+            related: Vec::new(),
+            notes: Vec::new(),
+            fixes: Vec::new(),
+        }
+    }
+
+    fn temp_project(name: &str) -> PathBuf {
+        let stamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|value| value.as_nanos())
+            .unwrap_or(0);
+        let dir = std::env::temp_dir().join(format!("phpx_diag_{}_{}", name, stamp));
+        fs::create_dir_all(&dir).expect("mkdir project");
+        dir
+    }
+
+    #[test]
+    fn parses_error_formats() {
+        assert_eq!(ErrorFormat::parse("json"), Some(ErrorFormat::Json));
+        assert_eq!(ErrorFormat::parse(" Human "), Some(ErrorFormat::Human));
+        assert_eq!(ErrorFormat::parse("xml"), None);
+    }
+
+    #[test]
+    fn inline_comment_suppresses_matching_code_only() {
+        let source = "$a = 1;\n// phpx-ignore PX0301\n$b = 2;\n";
+        let mut errors = vec![
+            error_at(3, Some(codes::TYPE_MISMATCH)),
+            error_at(3, Some(codes::UNKNOWN_MEMBER)),
+            error_at(1, Some(codes::TYPE_MISMATCH)),
+        ];
+        let mut warnings = Vec::new();
+        apply_suppressions(source, "/nonexistent/main.phpx", &mut errors, &mut warnings);
+        assert_eq!(errors.len(), 2);
+        assert!(
+            errors
+                .iter()
+                .all(|error| !(error.line == 3 && error.code() == codes::TYPE_MISMATCH))
+        );
+    }
+
+    #[test]
+    fn deka_json_ignores_codes_project_wide() {
+        let project = temp_project("ignore");
+        fs::write(
+            project.join("deka.json"),
+            r#"{ "phpx": { "diagnostics": { "ignore": ["px0301"] } } }"#,
+        )
+        .expect("write deka.json");
+        let app = project.join("app");
+        fs::create_dir_all(&app).expect("mkdir app");
+        let file = app.join("main.phpx");
+        let mut errors = vec![
+            error_at(1, Some(codes::TYPE_MISMATCH)),
+            error_at(2, Some(codes::UNKNOWN_TYPE)),
+        ];
+        let mut warnings = Vec::new();
+        apply_suppressions("", file.to_str().expect("path"), &mut errors, &mut warnings);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].code(), codes::UNKNOWN_TYPE);
+        let _ = fs::remove_dir_all(project);
+    }
+
+    #[test]
+    fn json_output_is_one_object_per_line() {
+        let mut error = error_at(2, None);
+        error.fixes.push(Fix {
+            message: "use '$name'".to_string(),
+            line: 2,
+            column: 5,
+            length: 4,
+            replacement: "$name".to_string(),
+            applicability: Applicability::MaybeIncorrect,
+        });
+        let out = format_json_diagnostics("main.phpx", &[error], &[]);
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 1);
+        let parsed: Value = serde_json::from_str(lines[0]).expect("valid json");
+        assert_eq!(parsed["code"], codes::TYPE_ERROR);
+        assert_eq!(parsed["severity"], "error");
+        assert_eq!(parsed["file"], "main.phpx");
+        assert_eq!(parsed["line"], 2);
+        assert_eq!(parsed["fixes"][0]["replacement"], "$name");
+        assert_eq!(parsed["fixes"][0]["applicability"], "maybe-incorrect");
+    }
+}
@@ -11,9 +11,11 @@ use crate::validation::imports::{
 #[derive(Debug, Clone)]
 pub(crate) struct ExportSpec {
     pub(crate) name: String,
+    /// Declaration (or re-exported name in `from`) the export refers to.
+    pub(crate) binding: String,
+    pub(crate) from: Option<String>,
     line: usize,
     column: usize,
-    is_reexport: bool,
 }
 
 pub fn validate_exports(source: &str, file_path: &str, program: &Program) -> Vec<ValidationError> {
@@ -119,7 +121,7 @@ pub fn validate_exports(source: &str, file_path: &str, program: &Program) -> Vec
     }
 
     for spec in exports {
-        if spec.is_reexport {
+        if spec.from.is_some() {
             continue;
         }
         if exportables.contains(&spec.name) {
@@ -232,10 +234,11 @@ pub(crate) fn parse_export_function(
     }
 
     Ok(ExportSpec {
+        binding: name.clone(),
         name,
+        from: None,
         line: line_number,
         column: find_column(raw_line, "export"),
-        is_reexport: false,
     })
 }
 
@@ -276,9 +279,8 @@ pub(crate) fn parse_export_list_line(
     let specifiers = &rest[..close_idx];
     let mut after = rest[close_idx + 1..].trim_start();
 
-    let mut is_reexport = false;
+    let mut reexport_from = None;
     if let Some(after_from) = after.strip_prefix("from") {
-        is_reexport = true;
         after = after_from.trim_start();
         let (from, after_from) = parse_quoted_string(after).ok_or_else(|| {
             export_error(
@@ -302,6 +304,7 @@ pub(crate) fn parse_export_list_line(
             ));
         }
         after = after_from.trim_start();
+        reexport_from = Some(from);
     }
 
     let after = after.trim_start_matches(';').trim();
@@ -358,7 +361,7 @@ pub(crate) fn parse_export_list_line(
             ));
         }
 
-        if !is_reexport && imported != local && local != "default" {
+        if reexport_from.is_none() && imported != local && local != "default" {
             return Err(export_error(
                 line_number,
                 find_column(raw_line, spec),
@@ -370,9 +373,10 @@ pub(crate) fn parse_export_list_line(
 
         exports.push(ExportSpec {
             name: local.to_string(),
+            binding: imported.to_string(),
+            from: reexport_from.clone(),
             line: line_number,
             column: find_column(raw_line, local),
-            is_reexport,
         });
     }
 
@@ -445,5 +449,9 @@ fn export_error_with_suggestion(
         suggestion: suggestion.map(|value| value.to_string()),
         underline_length: underline_length.max(1),
         severity: Severity::Error,
+        code: None,
+        related: Vec::new(),
+        notes: Vec::new(),
+        fixes: Vec::new(),
     }
 }
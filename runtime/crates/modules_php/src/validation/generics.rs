@@ -94,6 +94,10 @@ impl GenericValidator<'_> {
                     suggestion: None,
                     underline_length: 1,
                     severity: Severity::Warning,
+                    code: None,
+                    related: Vec::new(),
+                    notes: Vec::new(),
+                    fixes: Vec::new(),
                 });
             }
         }
@@ -130,6 +134,10 @@ impl GenericValidator<'_> {
                     suggestion: None,
                     underline_length: 1,
                     severity: Severity::Warning,
+                    code: None,
+                    related: Vec::new(),
+                    notes: Vec::new(),
+                    fixes: Vec::new(),
                 });
             }
         }
@@ -396,6 +396,10 @@ fn import_error_with_suggestion(
         suggestion: suggestion.map(|value| value.to_string()),
         underline_length: underline_length.max(1),
         severity: Severity::Error,
+        code: None,
+        related: Vec::new(),
+        notes: Vec::new(),
+        fixes: Vec::new(),
     }
 }
 
@@ -415,6 +419,10 @@ fn import_warning(
         suggestion: None,
         underline_length: underline_length.max(1),
         severity: Severity::Warning,
+        code: None,
+        related: Vec::new(),
+        notes: Vec::new(),
+        fixes: Vec::new(),
     }
 }
 
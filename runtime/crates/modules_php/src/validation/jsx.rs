@@ -219,6 +219,10 @@ pub fn validate_template_section(source: &str, file_path: &str) -> Vec<Validatio
             suggestion: None,
             underline_length,
             severity: Severity::Error,
+            code: None,
+            related: Vec::new(),
+            notes: Vec::new(),
+            fixes: Vec::new(),
         });
     }
 
@@ -260,6 +264,10 @@ pub fn validate_template_section(source: &str, file_path: &str) -> Vec<Validatio
                 suggestion: None,
                 underline_length,
                 severity: Severity::Error,
+                code: Some(err.code),
+                related: Vec::new(),
+                notes: err.notes,
+                fixes: Vec::new(),
             });
         }
     }
@@ -769,6 +777,10 @@ fn jsx_error(span: Span, source: &str, message: String, help_text: &str) -> Vali
         suggestion: None,
         underline_length,
         severity: Severity::Error,
+        code: None,
+        related: Vec::new(),
+        notes: Vec::new(),
+        fixes: Vec::new(),
     }
 }
 
@@ -788,6 +800,10 @@ fn frontmatter_error(
         suggestion: None,
         underline_length: underline_length.max(1),
         severity: Severity::Error,
+        code: None,
+        related: Vec::new(),
+        notes: Vec::new(),
+        fixes: Vec::new(),
     }
 }
 
@@ -1,3 +1,4 @@
+pub mod diagnostics;
 pub mod exports;
 pub mod generics;
 pub mod imports;
@@ -5,12 +6,14 @@ pub mod jsx;
 pub mod modules;
 pub mod patterns;
 pub mod phpx_rules;
+pub mod signatures;
 pub mod structs;
 pub mod syntax;
 pub mod type_checker;
 pub mod type_syntax;
 
 use php_rs::parser::ast::Program;
+use php_rs::phpx::diagnostics::{Applicability, codes};
 use php_rs::phpx::typeck::ExternalFunctionSig;
 use serde::Serialize;
 use std::collections::HashMap;
@@ -67,6 +70,52 @@ impl ErrorKind {
             ErrorKind::PatternError => "Pattern Error",
         }
     }
+
+    /// Code reported for diagnostics of this kind that don't carry a more
+    /// specific one.
+    pub fn default_code(&self) -> &'static str {
+        match self {
+            ErrorKind::SyntaxError => codes::SYNTAX_ERROR,
+            ErrorKind::UnexpectedToken => codes::UNEXPECTED_TOKEN,
+            ErrorKind::InvalidToken => codes::INVALID_TOKEN,
+            ErrorKind::TypeError => codes::TYPE_ERROR,
+            ErrorKind::TypeMismatch => codes::TYPE_MISMATCH,
+            ErrorKind::UnknownType => codes::UNKNOWN_TYPE,
+            ErrorKind::ImportError => codes::IMPORT_ERROR,
+            ErrorKind::ExportError => codes::EXPORT_ERROR,
+            ErrorKind::ModuleError => codes::MODULE_ERROR,
+            ErrorKind::WasmError => codes::WASM_ERROR,
+            ErrorKind::NullNotAllowed => codes::NULL_NOT_ALLOWED,
+            ErrorKind::ExceptionNotAllowed => codes::EXCEPTION_NOT_ALLOWED,
+            ErrorKind::OopNotAllowed => codes::OOP_NOT_ALLOWED,
+            ErrorKind::NamespaceNotAllowed => codes::NAMESPACE_NOT_ALLOWED,
+            ErrorKind::JsxError => codes::JSX_ERROR,
+            ErrorKind::StructError => codes::STRUCT_ERROR,
+            ErrorKind::EnumError => codes::ENUM_ERROR,
+            ErrorKind::PatternError => codes::PATTERN_ERROR,
+        }
+    }
+}
+
+/// A secondary source location attached to a diagnostic.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RelatedLocation {
+    pub line: usize,
+    pub column: usize,
+    pub length: usize,
+    pub message: String,
+}
+
+/// A suggested edit: replace `length` characters at `line:column` with
+/// `replacement`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Fix {
+    pub message: String,
+    pub line: usize,
+    pub column: usize,
+    pub length: usize,
+    pub replacement: String,
+    pub applicability: Applicability,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -79,6 +128,17 @@ pub struct ValidationError {
     pub suggestion: Option<String>,
     pub underline_length: usize,
     pub severity: Severity,
+    /// Stable `PX####` code; `None` reports the kind's default code.
+    pub code: Option<&'static str>,
+    pub related: Vec<RelatedLocation>,
+    pub notes: Vec<String>,
+    pub fixes: Vec<Fix>,
+}
+
+impl ValidationError {
+    pub fn code(&self) -> &'static str {
+        self.code.unwrap_or_else(|| self.kind.default_code())
+    }
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -91,6 +151,17 @@ pub struct ValidationWarning {
     pub suggestion: Option<String>,
     pub underline_length: usize,
     pub severity: Severity,
+    /// Stable `PX####` code; `None` reports the kind's default code.
+    pub code: Option<&'static str>,
+    pub related: Vec<RelatedLocation>,
+    pub notes: Vec<String>,
+    pub fixes: Vec<Fix>,
+}
+
+impl ValidationWarning {
+    pub fn code(&self) -> &'static str {
+        self.code.unwrap_or_else(|| self.kind.default_code())
+    }
 }
 
 #[derive(Debug)]
@@ -102,10 +173,11 @@ pub struct ValidationResult<'a> {
 }
 
 pub fn format_validation_error(source: &str, file_path: &str, error: &ValidationError) -> String {
-    deka_validation::format_validation_error_with_suggestion(
+    deka_validation::format_diagnostic(
         source,
         file_path,
         error.kind.as_str(),
+        error.code(),
         error.line,
         error.column,
         &error.message,
@@ -114,6 +186,7 @@ pub fn format_validation_error(source: &str, file_path: &str, error: &Validation
         severity_label(error.severity),
         docs_link_for_kind(error.kind),
         error.suggestion.clone(),
+        rendered_notes(file_path, &error.notes, &error.related),
     )
 }
 
@@ -122,10 +195,11 @@ pub fn format_validation_warning(
     file_path: &str,
     warning: &ValidationWarning,
 ) -> String {
-    deka_validation::format_validation_error_with_suggestion(
+    deka_validation::format_diagnostic(
         source,
         file_path,
         warning.kind.as_str(),
+        warning.code(),
         warning.line,
         warning.column,
         &warning.message,
@@ -134,9 +208,21 @@ pub fn format_validation_warning(
         severity_label(warning.severity),
         docs_link_for_kind(warning.kind),
         warning.suggestion.clone(),
+        rendered_notes(file_path, &warning.notes, &warning.related),
     )
 }
 
+fn rendered_notes(file_path: &str, notes: &[String], related: &[RelatedLocation]) -> Vec<String> {
+    let mut out = notes.to_vec();
+    for location in related {
+        out.push(format!(
+            "{}:{}:{}: {}",
+            file_path, location.line, location.column, location.message
+        ));
+    }
+    out
+}
+
 pub fn format_multiple_errors(
     source: &str,
     file_path: &str,
@@ -219,6 +305,10 @@ pub fn parse_errors_to_validation_errors(
                 },
                 underline_length: underline_len,
                 severity: Severity::Error,
+                code: None,
+                related: Vec::new(),
+                notes: Vec::new(),
+                fixes: Vec::new(),
             }
         })
         .collect()
@@ -6,11 +6,12 @@ use php_rs::parser::ast::visitor::{Visitor, walk_expr};
 use php_rs::parser::ast::{Expr, ExprId, Program, Stmt};
 use php_rs::parser::lexer::Lexer;
 use php_rs::parser::parser::{Parser, ParserMode};
+use php_rs::phpx::diagnostics::codes;
 use serde_json::Value;
 use crate::integrity::compute_package_integrity;
 
 use super::{ErrorKind, Severity, ValidationError};
-use crate::validation::exports::{parse_export_function, parse_export_list_line};
+use crate::validation::exports::{ExportSpec, parse_export_function, parse_export_list_line};
 use crate::validation::imports::{
     ImportKind, ImportSpec, consume_comment_line, frontmatter_bounds, parse_import_line,
     strip_php_tags_inline,
@@ -118,7 +119,7 @@ pub fn validate_target_capabilities(source: &str, file_path: &str) -> Vec<Valida
             continue;
         }
         if let Some((capability, reason, suggestion)) = adwa_capability_block(&spec.from) {
-            errors.push(module_error(
+            let mut error = module_error(
                 spec.line,
                 spec.column,
                 spec.from.len().max(1),
@@ -130,7 +131,9 @@ pub fn validate_target_capabilities(source: &str, file_path: &str) -> Vec<Valida
                     "Switch target or avoid {} APIs in browser-targeted modules. {}",
                     capability, suggestion
                 ),
-            ));
+            );
+            error.code = Some(codes::TARGET_CAPABILITY);
+            errors.push(error);
         }
     }
     errors
@@ -413,11 +416,18 @@ pub(crate) fn collect_import_specs(source: &str, file_path: &str) -> Vec<ImportS
 }
 
 fn collect_exports(source: &str, file_path: &str) -> HashSet<String> {
+    collect_export_specs(source, file_path)
+        .into_iter()
+        .map(|spec| spec.name)
+        .collect()
+}
+
+pub(crate) fn collect_export_specs(source: &str, file_path: &str) -> Vec<ExportSpec> {
     let lines: Vec<&str> = source.lines().collect();
     let bounds = frontmatter_bounds(&lines);
     let scan_end = bounds.map(|(_, end)| end).unwrap_or(lines.len());
     let mut in_block_comment = false;
-    let mut exports = HashSet::new();
+    let mut exports = Vec::new();
     for (idx, line) in lines.iter().enumerate().take(scan_end) {
         if let Some((start, end)) = bounds {
             if idx == start || idx == end {
@@ -434,15 +444,13 @@ fn collect_exports(source: &str, file_path: &str) -> HashSet<String> {
         }
         if trimmed.starts_with("export function") || trimmed.starts_with("export async function") {
             if let Ok(spec) = parse_export_function(trimmed, line, idx + 1, file_path) {
-                exports.insert(spec.name);
+                exports.push(spec);
             }
             continue;
         }
         if trimmed.starts_with("export {") {
-            if let Ok(specs) = parse_export_list_line(trimmed, line, idx + 1, file_path) {
-                for spec in specs {
-                    exports.insert(spec.name);
-                }
+            if let Ok(mut specs) = parse_export_list_line(trimmed, line, idx + 1, file_path) {
+                exports.append(&mut specs);
             }
         }
     }
@@ -597,12 +605,12 @@ fn find_project_root(start: &Path) -> Option<PathBuf> {
     None
 }
 
-struct ResolvedImportTarget {
-    module_id: String,
-    file_path: PathBuf,
+pub(crate) struct ResolvedImportTarget {
+    pub(crate) module_id: String,
+    pub(crate) file_path: PathBuf,
 }
 
-fn resolve_import_target(
+pub(crate) fn resolve_import_target(
     raw: &str,
     current_file_path: &str,
     modules_root: Option<&Path>,
@@ -1262,6 +1270,10 @@ fn module_error(
         suggestion: None,
         underline_length: underline_length.max(1),
         severity: Severity::Error,
+        code: None,
+        related: Vec::new(),
+        notes: Vec::new(),
+        fixes: Vec::new(),
     }
 }
 
@@ -1281,6 +1293,10 @@ fn wasm_error(
         suggestion: None,
         underline_length: underline_length.max(1),
         severity: Severity::Error,
+        code: None,
+        related: Vec::new(),
+        notes: Vec::new(),
+        fixes: Vec::new(),
     }
 }
 
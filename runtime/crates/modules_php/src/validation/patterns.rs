@@ -284,6 +284,10 @@ fn pattern_error(span: Span, source: &str, message: String, help_text: &str) ->
         suggestion: None,
         underline_length,
         severity: Severity::Error,
+        code: None,
+        related: Vec::new(),
+        notes: Vec::new(),
+        fixes: Vec::new(),
     }
 }
 
@@ -111,6 +111,10 @@ impl NoNullValidator<'_> {
             suggestion: None,
             underline_length,
             severity: Severity::Error,
+            code: None,
+            related: Vec::new(),
+            notes: Vec::new(),
+            fixes: Vec::new(),
         });
     }
 }
@@ -158,6 +162,10 @@ impl NoExceptionValidator<'_> {
             suggestion: None,
             underline_length,
             severity: Severity::Error,
+            code: None,
+            related: Vec::new(),
+            notes: Vec::new(),
+            fixes: Vec::new(),
         });
     }
 }
@@ -251,6 +259,10 @@ impl NoOopValidator<'_> {
             suggestion: None,
             underline_length,
             severity: Severity::Error,
+            code: None,
+            related: Vec::new(),
+            notes: Vec::new(),
+            fixes: Vec::new(),
         });
     }
 }
@@ -298,6 +310,10 @@ impl NoNamespaceValidator<'_> {
             suggestion: None,
             underline_length,
             severity: Severity::Error,
+            code: None,
+            related: Vec::new(),
+            notes: Vec::new(),
+            fixes: Vec::new(),
         });
     }
 }
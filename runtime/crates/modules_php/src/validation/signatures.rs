@@ -0,0 +1,316 @@
+//! Exported type signatures of imported `.phpx` modules.
+//!
+//! Every module is parsed once per hash. The hash covers the module source,
+//! the parser mode and the hashes of the modules it imports, so editing a
+//! dependency invalidates every module that (transitively) re-exports from
+//! it. The cache keeps only the latest entry per module path.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use bumpalo::Bump;
+use php_rs::parser::lexer::Lexer;
+use php_rs::parser::parser::{Parser, ParserMode};
+use php_rs::phpx::typeck::{ModuleSignatures, module_signatures};
+use sha2::{Digest, Sha256};
+
+use crate::compiler_api::preprocess_phpx_source;
+use crate::validation::imports::ImportKind;
+use crate::validation::modules::{
+    collect_export_specs, collect_import_specs, resolve_import_target, resolve_modules_root,
+};
+
+/// Module path -> (hash, signatures) of the last version parsed.
+type SignatureCache = HashMap<String, (String, Arc<ModuleSignatures>)>;
+
+static SIGNATURE_CACHE: OnceLock<Mutex<SignatureCache>> = OnceLock::new();
+
+fn signature_cache() -> &'static Mutex<SignatureCache> {
+    SIGNATURE_CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Signatures `source` sees through its `.phpx` imports, bound under their
+/// local names. Unresolvable imports are skipped; module resolution reports
+/// them.
+pub(crate) fn collect_import_signatures(source: &str, file_path: &str) -> ModuleSignatures {
+    let mut loader = SignatureLoader {
+        modules_root: resolve_modules_root(file_path),
+        loaded: HashMap::new(),
+        visiting: HashSet::new(),
+    };
+    let mut imports = ModuleSignatures::default();
+    for spec in collect_import_specs(source, file_path) {
+        if spec.kind == ImportKind::Wasm {
+            continue;
+        }
+        if let Some(module) = loader.load_specifier(&spec.from, file_path) {
+            imports.bind(&spec.local, &spec.imported, &module.signatures);
+        }
+    }
+    imports
+}
+
+/// Fingerprint of the signatures `source` exports, as seen by its importers
+/// (see [`ModuleSignatures::fingerprint`]). Editing a function body keeps
+/// it; changing an exported parameter or return type does not.
+pub fn export_fingerprint(source: &str, file_path: &str) -> String {
+    let mut loader = SignatureLoader {
+        modules_root: resolve_modules_root(file_path),
+        loaded: HashMap::new(),
+        visiting: HashSet::new(),
+    };
+    loader
+        .load_source(source, file_path)
+        .map(|module| module.signatures.fingerprint())
+        .unwrap_or_default()
+}
+
+#[derive(Clone)]
+struct LoadedModule {
+    hash: String,
+    signatures: Arc<ModuleSignatures>,
+}
+
+struct SignatureLoader {
+    modules_root: Option<PathBuf>,
+    loaded: HashMap<PathBuf, Option<LoadedModule>>,
+    visiting: HashSet<PathBuf>,
+}
+
+impl SignatureLoader {
+    fn load_specifier(&mut self, raw: &str, importer: &str) -> Option<LoadedModule> {
+        let target =
+            resolve_import_target(raw, importer, self.modules_root.as_deref(), None).ok()?;
+        self.load(&target.file_path)
+    }
+
+    fn load(&mut self, path: &Path) -> Option<LoadedModule> {
+        let key = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        if let Some(loaded) = self.loaded.get(&key) {
+            return loaded.clone();
+        }
+        // Cycles are reported by module resolution; the back edge just
+        // contributes no signatures.
+        if !self.visiting.insert(key.clone()) {
+            return None;
+        }
+        let loaded = self.load_uncached(path);
+        self.visiting.remove(&key);
+        self.loaded.insert(key, loaded.clone());
+        loaded
+    }
+
+    fn load_uncached(&mut self, path: &Path) -> Option<LoadedModule> {
+        let source = std::fs::read_to_string(path).ok()?;
+        self.load_source(&source, &path.to_string_lossy())
+    }
+
+    fn load_source(&mut self, source: &str, file_path: &str) -> Option<LoadedModule> {
+        let imports = collect_import_specs(source, file_path);
+        let exports = collect_export_specs(source, file_path);
+        let mut dependencies: HashMap<String, Option<LoadedModule>> = HashMap::new();
+        let specifiers = imports
+            .iter()
+            .filter(|spec| spec.kind != ImportKind::Wasm)
+            .map(|spec| spec.from.as_str())
+            .chain(exports.iter().filter_map(|spec| spec.from.as_deref()));
+        for raw in specifiers {
+            if !dependencies.contains_key(raw) {
+                let module = self.load_specifier(raw, file_path);
+                dependencies.insert(raw.to_string(), module);
+            }
+        }
+
+        let mode = if file_path.replace('\\', "/").contains("/php_modules/") {
+            ParserMode::PhpxInternal
+        } else {
+            ParserMode::Phpx
+        };
+        let mut hasher = Sha256::new();
+        hasher.update(format!("{:?}", mode).as_bytes());
+        hasher.update(b"\0");
+        hasher.update(source.as_bytes());
+        let mut dependency_hashes = dependencies
+            .iter()
+            .map(|(raw, module)| {
+                let hash = module.as_ref().map(|m| m.hash.as_str()).unwrap_or("");
+                format!("{}:{}", raw, hash)
+            })
+            .collect::<Vec<_>>();
+        dependency_hashes.sort();
+        for entry in dependency_hashes {
+            hasher.update(b"\0");
+            hasher.update(entry.as_bytes());
+        }
+        let hash = format!("{:x}", hasher.finalize());
+
+        if let Some(cached) = signature_cache().lock().ok().and_then(|cache| {
+            cache
+                .get(file_path)
+                .filter(|(cached_hash, _)| *cached_hash == hash)
+                .map(|(_, signatures)| signatures.clone())
+        }) {
+            return Some(LoadedModule {
+                hash,
+                signatures: cached,
+            });
+        }
+
+        let mut bound = ModuleSignatures::default();
+        for spec in imports.iter().filter(|spec| spec.kind != ImportKind::Wasm) {
+            if let Some(Some(module)) = dependencies.get(&spec.from) {
+                bound.bind(&spec.local, &spec.imported, &module.signatures);
+            }
+        }
+
+        let parser_source = preprocess_phpx_source(source);
+        let arena = Bump::new();
+        let mut parser = Parser::new_with_mode(Lexer::new(parser_source.as_bytes()), &arena, mode);
+        let program = parser.parse_program();
+        let mut signatures = if program.errors.is_empty() {
+            let local_exports = exports
+                .iter()
+                .filter(|spec| spec.from.is_none())
+                .map(|spec| (spec.name.clone(), spec.binding.clone()))
+                .collect::<Vec<_>>();
+            module_signatures(&program, parser_source.as_bytes(), &bound, &local_exports)
+        } else {
+            ModuleSignatures::default()
+        };
+        for spec in &exports {
+            let Some(from) = spec.from.as_deref() else {
+                continue;
+            };
+            if let Some(Some(module)) = dependencies.get(from) {
+                signatures.bind(&spec.name, &spec.binding, &module.signatures);
+            }
+        }
+
+        let signatures = Arc::new(signatures);
+        if let Ok(mut cache) = signature_cache().lock() {
+            cache.insert(file_path.to_string(), (hash.clone(), signatures.clone()));
+        }
+        Some(LoadedModule { hash, signatures })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SignatureLoader, collect_import_signatures, signature_cache};
+    use crate::compiler_api::compile_phpx;
+    use bumpalo::Bump;
+    use std::collections::{HashMap, HashSet};
+    use std::fs;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    const USERS: &str = "struct User {\n    $id: int;\n    $name: string;\n}\n\nexport function find_user($id: int): User {\n    return User { $id: $id, $name: \"ada\" };\n}\n\nexport { User }\n";
+    const BARREL: &str = "export { find_user as lookup, User } from './users.phpx'\n";
+
+    fn make_temp_project(name: &str) -> PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("clock")
+            .as_nanos();
+        let root = std::env::temp_dir().join(format!("deka_signatures_test_{name}_{nanos}"));
+        fs::create_dir_all(root.join("php_modules")).expect("create php_modules");
+        fs::create_dir_all(root.join("app")).expect("create app");
+        fs::write(root.join("deka.lock"), "{}").expect("write lockfile");
+        fs::write(root.join("app").join("users.phpx"), USERS).expect("write users");
+        fs::write(root.join("app").join("barrel.phpx"), BARREL).expect("write barrel");
+        root
+    }
+
+    fn loader() -> SignatureLoader {
+        SignatureLoader {
+            modules_root: None,
+            loaded: HashMap::new(),
+            visiting: HashSet::new(),
+        }
+    }
+
+    #[test]
+    fn re_exported_calls_are_type_checked() {
+        let root = make_temp_project("reexport");
+        let entry = root.join("app").join("main.phpx");
+        let entry_path = entry.to_string_lossy().to_string();
+
+        let source = "import { lookup, User } from './barrel.phpx'\n\nfunction name_of($u: User): string {\n    return $u.name;\n}\n\n$name = name_of(lookup(\"7\"));\n";
+        let imports = collect_import_signatures(source, &entry_path);
+        let mut exports = imports.exports().collect::<Vec<_>>();
+        exports.sort();
+        assert_eq!(exports, vec!["User", "lookup"]);
+
+        let arena = Bump::new();
+        let result = compile_phpx(source, &entry_path, &arena);
+        let messages = result
+            .errors
+            .iter()
+            .map(|err| err.message.as_str())
+            .collect::<Vec<_>>();
+        assert!(
+            messages
+                .iter()
+                .any(|msg| msg.contains("Argument 1 type mismatch: expected int")),
+            "{:?}",
+            messages
+        );
+
+        let source = source.replace("lookup(\"7\")", "lookup(7)");
+        let arena = Bump::new();
+        let result = compile_phpx(&source, &entry_path, &arena);
+        assert!(
+            !result
+                .errors
+                .iter()
+                .any(|err| err.message.contains("type mismatch")),
+            "{:?}",
+            result.errors
+        );
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn signatures_are_cached_by_module_and_dependency_hash() {
+        let root = make_temp_project("cache");
+        let barrel = root.join("app").join("barrel.phpx");
+
+        let first = loader().load(&barrel).expect("load barrel");
+        let second = loader().load(&barrel).expect("reload barrel");
+        assert_eq!(first.hash, second.hash);
+        assert!(Arc::ptr_eq(&first.signatures, &second.signatures));
+
+        // Editing a dependency changes the hash of every module above it.
+        let users = USERS.replace("$id: int): User", "$id: string): User");
+        fs::write(root.join("app").join("users.phpx"), users).expect("rewrite users");
+        let third = loader().load(&barrel).expect("load edited barrel");
+        assert_ne!(first.hash, third.hash);
+        assert!(!Arc::ptr_eq(&first.signatures, &third.signatures));
+
+        // Only the latest version of each module stays cached.
+        let barrel_path = barrel.to_string_lossy().to_string();
+        let cache = signature_cache().lock().expect("signature cache");
+        assert_eq!(
+            cache.get(&barrel_path).map(|(hash, _)| hash),
+            Some(&third.hash)
+        );
+        drop(cache);
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn signature_hash_covers_parser_mode() {
+        let root = make_temp_project("mode");
+        let internal = root.join("php_modules").join("users.phpx");
+        fs::write(&internal, USERS).expect("write internal users");
+
+        let app = loader()
+            .load(&root.join("app").join("users.phpx"))
+            .expect("load app users");
+        let internal = loader().load(&internal).expect("load internal users");
+        assert_ne!(app.hash, internal.hash);
+        let _ = fs::remove_dir_all(root);
+    }
+}
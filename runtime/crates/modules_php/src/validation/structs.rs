@@ -323,6 +323,10 @@ fn struct_error(span: Span, source: &str, message: String, help_text: &str) -> V
         suggestion: None,
         underline_length,
         severity: Severity::Error,
+        code: None,
+        related: Vec::new(),
+        notes: Vec::new(),
+        fixes: Vec::new(),
     }
 }
 
@@ -2,11 +2,11 @@ use std::path::Path;
 
 use php_rs::parser::ast::Program;
 use php_rs::phpx::typeck::{
-    ExternalFunctionSig, TypeError as PhpTypeError, check_program_with_path,
-    check_program_with_path_and_externals,
+    ExternalFunctionSig, ModuleSignatures, TypeError as PhpTypeError, check_program_with_imports,
+    check_program_with_path, check_program_with_path_and_externals,
 };
 
-use super::{ErrorKind, Severity, ValidationError};
+use super::{ErrorKind, Fix, RelatedLocation, Severity, ValidationError};
 
 pub fn check_types(
     program: &Program,
@@ -39,17 +39,68 @@ pub fn check_types_with_externals(
     }
 }
 
+pub fn check_types_with_imports(
+    program: &Program,
+    source: &str,
+    file_path: Option<&str>,
+    externals: &std::collections::HashMap<String, ExternalFunctionSig>,
+    imports: &ModuleSignatures,
+) -> Vec<ValidationError> {
+    let path = file_path.filter(|path| !path.is_empty()).map(Path::new);
+    match check_program_with_imports(program, source.as_bytes(), path, externals, imports) {
+        Ok(()) => Vec::new(),
+        Err(errors) => errors
+            .into_iter()
+            .map(|err| to_validation_error(err, source))
+            .collect(),
+    }
+}
+
 fn to_validation_error(error: PhpTypeError, source: &str) -> ValidationError {
     let (line, column, underline_length) = span_location(error.span, source);
+    let related = error
+        .related
+        .iter()
+        .map(|related| {
+            let (line, column, length) = span_location(related.span, source);
+            RelatedLocation {
+                line,
+                column,
+                length,
+                message: related.message.clone(),
+            }
+        })
+        .collect();
+    let fixes: Vec<Fix> = error
+        .suggestions
+        .iter()
+        .map(|suggestion| {
+            let (line, column, _) = span_location(suggestion.span, source);
+            Fix {
+                message: suggestion.message.clone(),
+                line,
+                column,
+                length: suggestion.span.len(),
+                replacement: suggestion.replacement.clone(),
+                applicability: suggestion.applicability,
+            }
+        })
+        .collect();
     ValidationError {
         kind: ErrorKind::TypeError,
         line,
         column,
         message: error.message,
         help_text: "Fix the type mismatch or update the annotation.".to_string(),
-        suggestion: None,
+        suggestion: fixes
+            .first()
+            .map(|fix| format!("{}: `{}`", fix.message, fix.replacement)),
         underline_length,
         severity: Severity::Error,
+        code: Some(error.code),
+        related,
+        notes: error.notes,
+        fixes,
     }
 }
 
@@ -49,8 +49,9 @@ impl TypeSyntaxValidator<'_> {
                     self.push_error(
                         ErrorKind::TypeError,
                         type_span(ty),
-                        "Only int|float unions are supported in PHPX.".to_string(),
-                        "Use int|float or refactor to a struct/enum.",
+                        "Only int|float, literal, or object shape unions are supported in PHPX."
+                            .to_string(),
+                        "Use int|float, a union of literals, or refactor to a struct/enum.",
                     );
                 }
             }
@@ -66,21 +67,27 @@ impl TypeSyntaxValidator<'_> {
                 };
                 let base_last = base_name.rsplit('\\').next().unwrap_or(&base_name);
                 let expected = match base_last {
-                    "Option" => Some(1),
-                    "Result" => Some(2),
-                    "Promise" => Some(1),
-                    "array" | "Array" => Some(1),
+                    "Option" => Some((1, 1)),
+                    "Result" => Some((2, 2)),
+                    "Promise" => Some((1, 1)),
+                    "array" | "Array" => Some((1, 2)),
+                    "list" => Some((1, 1)),
                     _ => None,
                 };
 
-                if let Some(expected_count) = expected {
-                    if args.len() != expected_count {
+                if let Some((min, max)) = expected {
+                    if args.len() < min || args.len() > max {
+                        let count = if min == max {
+                            min.to_string()
+                        } else {
+                            format!("{} or {}", min, max)
+                        };
                         self.push_error(
                             ErrorKind::TypeError,
                             type_span(base),
                             format!(
                                 "Generic '{}' expects {} type argument(s).",
-                                base_last, expected_count
+                                base_last, count
                             ),
                             "Update the type arguments to match the expected arity.",
                         );
@@ -90,7 +97,7 @@ impl TypeSyntaxValidator<'_> {
                         ErrorKind::TypeError,
                         type_span(base),
                         format!("Unsupported generic base type '{}'.", base_last),
-                        "Use Option<T>, Result<T, E>, Promise<T>, or array<T>.",
+                        "Use Option<T>, Result<T, E>, Promise<T>, list<T>, or array<K, V>.",
                     );
                 }
             }
@@ -119,6 +126,10 @@ impl TypeSyntaxValidator<'_> {
             suggestion: None,
             underline_length,
             severity: Severity::Error,
+            code: None,
+            related: Vec::new(),
+            notes: Vec::new(),
+            fixes: Vec::new(),
         });
     }
 }
@@ -138,12 +149,20 @@ fn span_location(span: Span, source: &str) -> (usize, usize, usize) {
 
 fn type_span(ty: &Type) -> Span {
     match ty {
-        Type::Simple(token) => token.span,
+        Type::Simple(token) | Type::Literal(token) => token.span,
         Type::Name(name) => name.span,
-        Type::Union(types) | Type::Intersection(types) => {
+        Type::Union(types) | Type::Intersection(types) | Type::Tuple(types) => {
             types.first().map(type_span).unwrap_or_default()
         }
-        Type::Nullable(inner) => type_span(inner),
+        Type::Function {
+            params,
+            return_type,
+        } => params
+            .first()
+            .or(*return_type)
+            .map(type_span)
+            .unwrap_or_default(),
+        Type::Nullable(inner) | Type::KeyOf(inner) => type_span(inner),
         Type::ObjectShape(fields) => fields.first().map(|field| field.span).unwrap_or_default(),
         Type::Applied { base, .. } => type_span(base),
     }
@@ -174,6 +193,11 @@ fn is_null_type(ty: &Type) -> bool {
 }
 
 fn is_supported_union(types: &[Type], source: &str) -> bool {
+    if types.iter().all(|ty| is_literal_type(ty, source))
+        || types.iter().all(|ty| matches!(ty, Type::ObjectShape(_)))
+    {
+        return true;
+    }
     if types.len() != 2 {
         return false;
     }
@@ -184,3 +208,13 @@ fn is_supported_union(types: &[Type], source: &str) -> bool {
     (left.eq_ignore_ascii_case("int") && right.eq_ignore_ascii_case("float"))
         || (left.eq_ignore_ascii_case("float") && right.eq_ignore_ascii_case("int"))
 }
+
+fn is_literal_type(ty: &Type, source: &str) -> bool {
+    match ty {
+        Type::Literal(_) => true,
+        Type::Simple(_) => type_base_name(ty, source).is_some_and(|name| {
+            name.eq_ignore_ascii_case("true") || name.eq_ignore_ascii_case("false")
+        }),
+        _ => false,
+    }
+}
@@ -997,6 +997,13 @@ pub enum Type<'ast> {
     Intersection(&'ast [Type<'ast>]),
     Nullable(&'ast Type<'ast>),
     ObjectShape(&'ast [ObjectShapeField<'ast>]),
+    Tuple(&'ast [Type<'ast>]),
+    Function {
+        params: &'ast [Type<'ast>],
+        return_type: Option<&'ast Type<'ast>>,
+    },
+    Literal(&'ast Token),
+    KeyOf(&'ast Type<'ast>),
     Applied {
         base: &'ast Type<'ast>,
         args: &'ast [Type<'ast>],
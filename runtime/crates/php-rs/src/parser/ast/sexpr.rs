@@ -1415,6 +1415,38 @@ impl<'a, 'ast> Visitor<'ast> for SExprFormatter<'a> {
                 }
                 self.write(")");
             }
+            Type::Tuple(types) => {
+                self.write("(tuple");
+                for t in *types {
+                    self.write(" ");
+                    self.visit_type(t);
+                }
+                self.write(")");
+            }
+            Type::Function {
+                params,
+                return_type,
+            } => {
+                self.write("(fn-type (");
+                for (i, t) in params.iter().enumerate() {
+                    if i > 0 {
+                        self.write(" ");
+                    }
+                    self.visit_type(t);
+                }
+                self.write(")");
+                if let Some(rt) = return_type {
+                    self.write(" ");
+                    self.visit_type(rt);
+                }
+                self.write(")");
+            }
+            Type::Literal(t) => self.write(&String::from_utf8_lossy(t.text(self.source))),
+            Type::KeyOf(t) => {
+                self.write("(keyof ");
+                self.visit_type(t);
+                self.write(")");
+            }
             Type::Applied { base, args } => {
                 self.write("(type-app ");
                 self.visit_type(base);
@@ -602,10 +602,21 @@ pub fn walk_name<'ast, V: Visitor<'ast> + ?Sized>(_: &mut V, name: &Name<'ast>)
 
 pub fn walk_type<'ast, V: Visitor<'ast> + ?Sized>(visitor: &mut V, ty: &'ast Type<'ast>) {
     match ty {
-        Type::Simple(_) => {}
+        Type::Simple(_) | Type::Literal(_) => {}
         Type::Name(name) => visitor.visit_name(name),
-        Type::Union(types) | Type::Intersection(types) => walk_types(visitor, types),
-        Type::Nullable(inner) => visitor.visit_type(inner),
+        Type::Union(types) | Type::Intersection(types) | Type::Tuple(types) => {
+            walk_types(visitor, types)
+        }
+        Type::Function {
+            params,
+            return_type,
+        } => {
+            walk_types(visitor, params);
+            if let Some(return_type) = return_type {
+                visitor.visit_type(return_type);
+            }
+        }
+        Type::Nullable(inner) | Type::KeyOf(inner) => visitor.visit_type(inner),
         Type::ObjectShape(fields) => {
             for field in fields.iter() {
                 visitor.visit_type(field.ty);
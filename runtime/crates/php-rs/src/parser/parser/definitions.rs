@@ -54,7 +54,7 @@ impl<'src, 'ast> Parser<'src, 'ast> {
         } else {
             self.current_token.span.start
         };
-        if self.is_phpx() && kind == ClassKind::Class {
+        if self.is_phpx() && !self.allow_phpx_classes() && kind == ClassKind::Class {
             self.errors.push(ParseError::new(
                 self.current_token.span,
                 "classes are not allowed in PHPX; use struct instead",
@@ -354,7 +354,7 @@ impl<'src, 'ast> Parser<'src, 'ast> {
                 }
             }
         }
-        if self.is_phpx() {
+        if self.is_phpx() && !self.allow_phpx_classes() {
             if let Some(first) = extends.first() {
                 self.errors.push(ParseError::new(
                     first.span,
@@ -417,7 +417,7 @@ impl<'src, 'ast> Parser<'src, 'ast> {
         } else {
             self.current_token.span.start
         };
-        if self.is_phpx() {
+        if self.is_phpx() && !self.allow_phpx_classes() {
             self.errors.push(ParseError::new(
                 self.current_token.span,
                 "traits are not allowed in PHPX",
@@ -681,7 +681,7 @@ impl<'src, 'ast> Parser<'src, 'ast> {
         }
 
         if self.current_token.kind == TokenKind::Use {
-            if self.is_phpx() {
+            if self.is_phpx() && !self.allow_phpx_classes() {
                 let is_struct = matches!(
                     ctx,
                     ClassMemberCtx::Class {
@@ -1633,7 +1633,7 @@ impl<'src, 'ast> Parser<'src, 'ast> {
                 self.parse_arrow_function(attributes, true, false, start)
             }
             TokenKind::New => {
-                if self.is_phpx() {
+                if self.is_phpx() && !self.allow_phpx_classes() {
                     self.errors.push(ParseError::new(
                         token.span,
                         "new is not allowed in PHPX; use struct literals instead",
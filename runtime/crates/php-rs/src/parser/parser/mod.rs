@@ -126,6 +126,12 @@ impl<'src, 'ast> Parser<'src, 'ast> {
         self.mode == ParserMode::PhpxInternal
     }
 
+    /// Internal std modules may declare classes, traits and interface
+    /// hierarchies; the JS emitter lowers them to ES classes.
+    pub(super) fn allow_phpx_classes(&self) -> bool {
+        self.mode == ParserMode::PhpxInternal
+    }
+
     pub(super) fn take_param_destructure_prologue(&mut self) -> &'ast [StmtId<'ast>] {
         let prologue = std::mem::take(&mut self.param_destructure_prologue);
         self.arena.alloc_slice_copy(&prologue)
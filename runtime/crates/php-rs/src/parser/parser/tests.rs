@@ -1,8 +1,9 @@
 use bumpalo::Bump;
 use std::path::Path;
 
-use crate::parser::ast::{ClassKind, ClassMember, Expr, Stmt};
+use crate::parser::ast::{ClassKind, ClassMember, Expr, Stmt, Type};
 use crate::parser::lexer::Lexer;
+use crate::parser::lexer::token::TokenKind;
 use crate::parser::parser::{Parser, ParserMode, detect_parser_mode};
 
 #[test]
@@ -331,6 +332,49 @@ fn phpx_parses_interface_shape_fields() {
     }
 }
 
+#[test]
+fn phpx_parses_tuple_and_element_typed_array_types() {
+    let code =
+        "function pair($xs: list<int>, $m: array<string, int>): [int, string] { return [1, 'a']; }";
+    let arena = Bump::new();
+    let mut parser = Parser::new_with_mode(Lexer::new(code.as_bytes()), &arena, ParserMode::Phpx);
+    let program = parser.parse_program();
+
+    assert!(
+        program.errors.is_empty(),
+        "unexpected parser errors: {:?}",
+        program.errors
+    );
+
+    let func_stmt = program
+        .statements
+        .iter()
+        .find(|s| matches!(***s, Stmt::Function { .. }))
+        .expect("expected function statement");
+
+    match &**func_stmt {
+        Stmt::Function {
+            params,
+            return_type,
+            ..
+        } => {
+            assert!(matches!(
+                params[0].ty,
+                Some(Type::Applied { args, .. }) if args.len() == 1
+            ));
+            assert!(matches!(
+                params[1].ty,
+                Some(Type::Applied { args, .. }) if args.len() == 2
+            ));
+            match return_type {
+                Some(Type::Tuple(items)) => assert_eq!(items.len(), 2),
+                other => panic!("expected tuple return type, got {:?}", other),
+            }
+        }
+        other => panic!("expected function stmt, got {:?}", other),
+    }
+}
+
 #[test]
 fn phpx_parses_async_function_and_await() {
     let code = "async function load($p: Promise<int>): Promise<int> {\n  return await $p\n}\n$v = await load($p)\n";
@@ -602,3 +646,119 @@ function App($props: object) {
         program.errors
     );
 }
+
+#[test]
+fn phpx_internal_allows_classes_traits_and_new() {
+    let code = r#"
+namespace deka\std;
+
+interface Named extends Stringable {}
+trait Greets {
+  public function greet() { return 'hi'; }
+}
+class User implements Named {
+  use Greets;
+  public function __construct(public string $name) {}
+}
+$user = new User('ada');
+"#;
+    let arena = Bump::new();
+    let mut parser = Parser::new_with_mode(
+        Lexer::new(code.as_bytes()),
+        &arena,
+        ParserMode::PhpxInternal,
+    );
+    let program = parser.parse_program();
+    assert!(
+        program.errors.is_empty(),
+        "unexpected parser errors: {:?}",
+        program.errors
+    );
+
+    let arena = Bump::new();
+    let mut parser = Parser::new_with_mode(Lexer::new(code.as_bytes()), &arena, ParserMode::Phpx);
+    let program = parser.parse_program();
+    let messages: Vec<&str> = program.errors.iter().map(|e| e.message).collect();
+    assert!(messages.contains(&"classes are not allowed in PHPX; use struct instead"));
+    assert!(messages.contains(&"traits are not allowed in PHPX"));
+    assert!(messages.contains(&"new is not allowed in PHPX; use struct literals instead"));
+}
+
+#[test]
+fn phpx_parses_function_types() {
+    let code = "function apply($f: fn(int, string): bool, $g: fn(), $h: fn(int): string): void {}";
+    let arena = Bump::new();
+    let mut parser = Parser::new_with_mode(Lexer::new(code.as_bytes()), &arena, ParserMode::Phpx);
+    let program = parser.parse_program();
+
+    assert!(
+        program.errors.is_empty(),
+        "unexpected parser errors: {:?}",
+        program.errors
+    );
+
+    let func_stmt = program
+        .statements
+        .iter()
+        .find(|s| matches!(***s, Stmt::Function { .. }))
+        .expect("expected function statement");
+
+    match &**func_stmt {
+        Stmt::Function { params, .. } => {
+            assert!(matches!(
+                params[0].ty,
+                Some(Type::Function {
+                    params,
+                    return_type: Some(_),
+                }) if params.len() == 2
+            ));
+            assert!(matches!(
+                params[1].ty,
+                Some(Type::Function {
+                    params,
+                    return_type: None,
+                }) if params.is_empty()
+            ));
+            assert!(matches!(
+                params[2].ty,
+                Some(Type::Function {
+                    params: [Type::Simple(token)],
+                    return_type: Some(_),
+                }) if token.kind == TokenKind::TypeInt
+            ));
+        }
+        other => panic!("expected function stmt, got {:?}", other),
+    }
+}
+
+#[test]
+fn phpx_parses_literal_and_keyof_types() {
+    let code = "function f($m: \"get\"|'post'|1, $k: keyof User): void {}";
+    let arena = Bump::new();
+    let mut parser = Parser::new_with_mode(Lexer::new(code.as_bytes()), &arena, ParserMode::Phpx);
+    let program = parser.parse_program();
+
+    assert!(
+        program.errors.is_empty(),
+        "unexpected parser errors: {:?}",
+        program.errors
+    );
+
+    let func_stmt = program
+        .statements
+        .iter()
+        .find(|s| matches!(***s, Stmt::Function { .. }))
+        .expect("expected function statement");
+
+    match &**func_stmt {
+        Stmt::Function { params, .. } => {
+            assert!(matches!(
+                params[0].ty,
+                Some(Type::Union(types))
+                    if types.len() == 3 && types.iter().all(|t| matches!(t, Type::Literal(_)))
+            ));
+            assert!(matches!(params[1].ty, Some(Type::KeyOf(Type::Name(_)))));
+        }
+        other => panic!("expected function stmt, got {:?}", other),
+    }
+}
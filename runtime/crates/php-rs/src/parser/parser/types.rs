@@ -1,6 +1,7 @@
 use super::Parser;
 use crate::parser::ast::{ObjectShapeField, Type};
-use crate::parser::lexer::token::TokenKind;
+use crate::parser::lexer::token::{Token, TokenKind};
+use crate::parser::span::Span;
 
 impl<'src, 'ast> Parser<'src, 'ast> {
     fn parse_type_atomic(&mut self) -> Option<Type<'ast>> {
@@ -24,6 +25,37 @@ impl<'src, 'ast> Parser<'src, 'ast> {
             self.parse_object_shape_type()
         } else if self.is_phpx() && self.current_token.kind == TokenKind::OpenBrace {
             self.parse_object_shape_fields()
+        } else if self.is_phpx() && self.current_token.kind == TokenKind::OpenBracket {
+            self.parse_tuple_type()
+        } else if self.is_phpx()
+            && self.current_token.kind == TokenKind::Fn
+            && (self.next_token.kind == TokenKind::OpenParen
+                || cast_type_kind(self.next_token.kind).is_some())
+        {
+            self.parse_function_type()
+        } else if self.is_phpx()
+            && matches!(
+                self.current_token.kind,
+                TokenKind::StringLiteral | TokenKind::LNumber
+            )
+        {
+            let t = self.arena.alloc(self.current_token);
+            self.bump();
+            Some(Type::Literal(t))
+        } else if self.is_phpx()
+            && self.current_token.kind == TokenKind::Identifier
+            && self.lexer.slice(self.current_token.span) == b"keyof"
+            && matches!(
+                self.next_token.kind,
+                TokenKind::Identifier
+                    | TokenKind::NsSeparator
+                    | TokenKind::OpenBrace
+                    | TokenKind::TypeObject
+            )
+        {
+            self.bump(); // consume 'keyof'
+            let inner = self.parse_type_atomic()?;
+            Some(Type::KeyOf(self.arena.alloc(inner)))
         } else if self.current_token.kind == TokenKind::OpenParen {
             self.bump();
             let ty = self.parse_type()?;
@@ -147,6 +179,87 @@ impl<'src, 'ast> Parser<'src, 'ast> {
         Some(Type::ObjectShape(fields.into_bump_slice()))
     }
 
+    fn parse_tuple_type(&mut self) -> Option<Type<'ast>> {
+        self.bump(); // consume '['
+        let mut items = bumpalo::collections::Vec::new_in(self.arena);
+        while self.current_token.kind != TokenKind::CloseBracket
+            && self.current_token.kind != TokenKind::Eof
+        {
+            match self.parse_type() {
+                Some(ty) => items.push(ty),
+                None => break,
+            }
+            if self.current_token.kind == TokenKind::Comma {
+                self.bump();
+                continue;
+            }
+            break;
+        }
+        if self.current_token.kind == TokenKind::CloseBracket {
+            self.bump();
+        }
+
+        Some(Type::Tuple(items.into_bump_slice()))
+    }
+
+    fn parse_function_type(&mut self) -> Option<Type<'ast>> {
+        self.bump(); // consume 'fn'
+        let mut params = bumpalo::collections::Vec::new_in(self.arena);
+        if let Some(kind) = cast_type_kind(self.current_token.kind) {
+            // `fn(int)` lexes as a cast token; recover the single parameter type from it.
+            let span = self.current_token.span;
+            let text = self.lexer.slice(span);
+            let start = text
+                .iter()
+                .position(|b| b.is_ascii_alphabetic())
+                .unwrap_or(0);
+            let end = text
+                .iter()
+                .rposition(|b| b.is_ascii_alphabetic())
+                .map_or(0, |i| i + 1);
+            let token = self.arena.alloc(Token {
+                kind,
+                span: Span::new(span.start + start, span.start + end),
+            });
+            self.bump();
+            params.push(Type::Simple(token));
+            return Some(Type::Function {
+                params: params.into_bump_slice(),
+                return_type: self.parse_function_type_return(),
+            });
+        }
+        self.bump(); // consume '('
+        while self.current_token.kind != TokenKind::CloseParen
+            && self.current_token.kind != TokenKind::Eof
+        {
+            match self.parse_type() {
+                Some(ty) => params.push(ty),
+                None => break,
+            }
+            if self.current_token.kind == TokenKind::Comma {
+                self.bump();
+                continue;
+            }
+            break;
+        }
+        if self.current_token.kind == TokenKind::CloseParen {
+            self.bump();
+        }
+
+        Some(Type::Function {
+            params: params.into_bump_slice(),
+            return_type: self.parse_function_type_return(),
+        })
+    }
+
+    fn parse_function_type_return(&mut self) -> Option<&'ast Type<'ast>> {
+        if self.current_token.kind != TokenKind::Colon {
+            return None;
+        }
+        self.bump(); // consume ':'
+        self.parse_type().map(|ty| &*self.arena.alloc(ty))
+    }
+
     fn parse_type_args(&mut self) -> Option<&'ast [Type<'ast>]> {
         if self.current_token.kind != TokenKind::Lt {
             return None;
@@ -237,3 +350,16 @@ impl<'src, 'ast> Parser<'src, 'ast> {
         Some(left)
     }
 }
+
+fn cast_type_kind(kind: TokenKind) -> Option<TokenKind> {
+    match kind {
+        TokenKind::IntCast => Some(TokenKind::TypeInt),
+        TokenKind::FloatCast => Some(TokenKind::TypeFloat),
+        TokenKind::StringCast => Some(TokenKind::TypeString),
+        TokenKind::BoolCast => Some(TokenKind::TypeBool),
+        TokenKind::ArrayCast => Some(TokenKind::Array),
+        TokenKind::ObjectCast => Some(TokenKind::TypeObject),
+        TokenKind::VoidCast => Some(TokenKind::TypeVoid),
+        _ => None,
+    }
+}
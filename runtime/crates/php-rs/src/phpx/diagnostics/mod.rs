@@ -0,0 +1,277 @@
+//! Shared diagnostic vocabulary for the PHPX toolchain.
+//!
+//! Every diagnostic emitted by the parser, validators and type checker carries a
+//! stable `PX####` code from [`codes`]. Codes are grouped by hundreds:
+//!
+//! | range    | area                                  |
+//! |----------|---------------------------------------|
+//! | `PX00xx` | syntax                                |
+//! | `PX01xx` | modules, imports, exports, wasm       |
+//! | `PX02xx` | PHPX language restrictions            |
+//! | `PX03xx` | type checking                         |
+//! | `PX04xx` | structs, enums, annotations, patterns |
+//! | `PX05xx` | JSX                                   |
+//!
+//! Codes are never reused; retired codes stay in [`REGISTRY`] so that
+//! suppressions in old sources keep parsing.
+
+use crate::parser::span::Span;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+pub mod codes {
+    pub const SYNTAX_ERROR: &str = "PX0001";
+    pub const UNEXPECTED_TOKEN: &str = "PX0002";
+    pub const INVALID_TOKEN: &str = "PX0003";
+
+    pub const IMPORT_ERROR: &str = "PX0100";
+    pub const EXPORT_ERROR: &str = "PX0101";
+    pub const MODULE_ERROR: &str = "PX0102";
+    pub const WASM_ERROR: &str = "PX0103";
+    pub const TARGET_CAPABILITY: &str = "PX0104";
+
+    pub const NULL_NOT_ALLOWED: &str = "PX0200";
+    pub const EXCEPTION_NOT_ALLOWED: &str = "PX0201";
+    pub const OOP_NOT_ALLOWED: &str = "PX0202";
+    pub const NAMESPACE_NOT_ALLOWED: &str = "PX0203";
+
+    pub const TYPE_ERROR: &str = "PX0300";
+    pub const TYPE_MISMATCH: &str = "PX0301";
+    pub const UNKNOWN_TYPE: &str = "PX0302";
+    pub const UNKNOWN_MEMBER: &str = "PX0303";
+    pub const MISSING_FIELD: &str = "PX0304";
+    pub const ARGUMENT_COUNT: &str = "PX0305";
+    pub const TYPE_ARGUMENTS: &str = "PX0306";
+    pub const UNSATISFIED_CONSTRAINT: &str = "PX0307";
+    pub const DUPLICATE_DECLARATION: &str = "PX0308";
+    pub const TYPE_ALIAS: &str = "PX0309";
+    pub const ASYNC_AWAIT: &str = "PX0310";
+    pub const INFERENCE_FAILED: &str = "PX0311";
+    pub const UNKNOWN_NAME: &str = "PX0312";
+
+    pub const STRUCT_ERROR: &str = "PX0400";
+    pub const ENUM_ERROR: &str = "PX0401";
+    pub const NON_EXHAUSTIVE_MATCH: &str = "PX0402";
+    pub const ANNOTATION_ERROR: &str = "PX0403";
+    pub const PATTERN_ERROR: &str = "PX0404";
+
+    pub const JSX_ERROR: &str = "PX0500";
+    pub const JSX_COMPONENT: &str = "PX0501";
+    pub const JSX_PROP: &str = "PX0502";
+}
+
+/// Every known code with a one-line summary, in code order.
+pub const REGISTRY: &[(&str, &str)] = &[
+    (codes::SYNTAX_ERROR, "syntax error"),
+    (codes::UNEXPECTED_TOKEN, "unexpected token"),
+    (codes::INVALID_TOKEN, "invalid token"),
+    (codes::IMPORT_ERROR, "unresolved or invalid import"),
+    (codes::EXPORT_ERROR, "invalid export"),
+    (codes::MODULE_ERROR, "module resolution failed"),
+    (codes::WASM_ERROR, "invalid wasm module or stub"),
+    (codes::TARGET_CAPABILITY, "module is unavailable for the build target"),
+    (codes::NULL_NOT_ALLOWED, "null is not allowed in PHPX"),
+    (codes::EXCEPTION_NOT_ALLOWED, "exceptions are not allowed in PHPX"),
+    (codes::OOP_NOT_ALLOWED, "classes and objects are not allowed in PHPX"),
+    (codes::NAMESPACE_NOT_ALLOWED, "namespaces are not allowed in PHPX"),
+    (codes::TYPE_ERROR, "type error"),
+    (codes::TYPE_MISMATCH, "mismatched types"),
+    (codes::UNKNOWN_TYPE, "unknown type"),
+    (codes::UNKNOWN_MEMBER, "unknown field, method or case"),
+    (codes::MISSING_FIELD, "missing required field"),
+    (codes::ARGUMENT_COUNT, "wrong number of arguments"),
+    (codes::TYPE_ARGUMENTS, "wrong number of type arguments"),
+    (codes::UNSATISFIED_CONSTRAINT, "type argument does not satisfy its constraint"),
+    (codes::DUPLICATE_DECLARATION, "duplicate declaration"),
+    (codes::TYPE_ALIAS, "invalid type alias"),
+    (codes::ASYNC_AWAIT, "invalid async/await usage"),
+    (codes::INFERENCE_FAILED, "type parameter could not be inferred"),
+    (codes::UNKNOWN_NAME, "unknown variable or function"),
+    (codes::STRUCT_ERROR, "invalid struct"),
+    (codes::ENUM_ERROR, "invalid enum or enum case"),
+    (codes::NON_EXHAUSTIVE_MATCH, "non-exhaustive match"),
+    (codes::ANNOTATION_ERROR, "invalid struct field annotation"),
+    (codes::PATTERN_ERROR, "invalid match pattern"),
+    (codes::JSX_ERROR, "invalid JSX"),
+    (codes::JSX_COMPONENT, "invalid JSX component"),
+    (codes::JSX_PROP, "invalid JSX prop, attribute or children"),
+];
+
+pub fn describe(code: &str) -> Option<&'static str> {
+    REGISTRY
+        .iter()
+        .find(|(known, _)| *known == code)
+        .map(|(_, summary)| *summary)
+}
+
+pub fn is_known_code(code: &str) -> bool {
+    describe(code).is_some()
+}
+
+/// How safely a suggestion can be applied without a human looking at it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Applicability {
+    /// The replacement is known to be correct; editors may apply it automatically.
+    MachineApplicable,
+    /// The replacement is probably what the user wants but should be reviewed.
+    MaybeIncorrect,
+}
+
+/// A source edit that resolves a diagnostic: replace `span` with `replacement`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Suggestion {
+    pub message: String,
+    pub span: Span,
+    pub replacement: String,
+    pub applicability: Applicability,
+}
+
+impl Suggestion {
+    pub fn machine_applicable(
+        message: impl Into<String>,
+        span: Span,
+        replacement: impl Into<String>,
+    ) -> Self {
+        Self {
+            message: message.into(),
+            span,
+            replacement: replacement.into(),
+            applicability: Applicability::MachineApplicable,
+        }
+    }
+
+    pub fn maybe_incorrect(
+        message: impl Into<String>,
+        span: Span,
+        replacement: impl Into<String>,
+    ) -> Self {
+        Self {
+            message: message.into(),
+            span,
+            replacement: replacement.into(),
+            applicability: Applicability::MaybeIncorrect,
+        }
+    }
+}
+
+/// A secondary location that helps explain a diagnostic, e.g. the declaration
+/// a mismatched value was checked against.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RelatedSpan {
+    pub span: Span,
+    pub message: String,
+}
+
+/// Codes silenced by `phpx-ignore` comments in a source file.
+///
+/// * `// phpx-ignore PX0301` on its own line silences the next line.
+/// * `// phpx-ignore PX0301` after code silences its own line.
+/// * `// phpx-ignore-file PX0301` silences the whole file.
+///
+/// Several codes may be listed, separated by spaces or commas. `#` and `/* */`
+/// comments work the same way. A directive without codes is ignored so that
+/// suppressions always name what they hide.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Suppressions {
+    file: HashSet<String>,
+    lines: HashMap<usize, HashSet<String>>,
+}
+
+impl Suppressions {
+    pub fn from_source(source: &str) -> Self {
+        let mut out = Self::default();
+        for (idx, line) in source.lines().enumerate() {
+            let Some((directive_at, file_wide, codes)) = parse_ignore_directive(line) else {
+                continue;
+            };
+            if file_wide {
+                out.file.extend(codes);
+                continue;
+            }
+            let before = line[..directive_at].trim();
+            let is_comment_only = before.is_empty()
+                || before == "//"
+                || before == "#"
+                || before == "/*"
+                || before == "<!--";
+            // Lines are 1-based; a comment-only directive targets the line after it.
+            let target = if is_comment_only { idx + 2 } else { idx + 1 };
+            out.lines.entry(target).or_default().extend(codes);
+        }
+        out
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.file.is_empty() && self.lines.is_empty()
+    }
+
+    pub fn ignore_everywhere(&mut self, code: impl Into<String>) {
+        self.file.insert(code.into());
+    }
+
+    pub fn is_suppressed(&self, line: usize, code: &str) -> bool {
+        self.file.contains(code)
+            || self
+                .lines
+                .get(&line)
+                .is_some_and(|codes| codes.contains(code))
+    }
+}
+
+fn parse_ignore_directive(line: &str) -> Option<(usize, bool, Vec<String>)> {
+    let comment_at = comment_start(line)?;
+    let at = comment_at + line[comment_at..].find("phpx-ignore")?;
+    let rest = &line[at + "phpx-ignore".len()..];
+    let (file_wide, rest) = match rest.strip_prefix("-file") {
+        Some(rest) => (true, rest),
+        None => (false, rest),
+    };
+    if !rest.is_empty() && !rest.starts_with([' ', '\t', ':']) {
+        return None;
+    }
+    let codes: Vec<String> = rest
+        .split(|ch: char| ch.is_whitespace() || ch == ',' || ch == ':')
+        .map(|token| token.trim_end_matches("*/").trim_end_matches("-->"))
+        .filter(|token| is_code_token(token))
+        .map(|token| token.to_string())
+        .collect();
+    if codes.is_empty() {
+        return None;
+    }
+    Some((at, file_wide, codes))
+}
+
+/// Byte offset of the first `//`, `#`, `/*` or `<!--` outside a string
+/// literal, so directive text inside strings is never read as a comment.
+fn comment_start(line: &str) -> Option<usize> {
+    let bytes = line.as_bytes();
+    let mut quote = None;
+    let mut idx = 0;
+    while idx < bytes.len() {
+        let byte = bytes[idx];
+        match quote {
+            Some(_) if byte == b'\\' => idx += 1,
+            Some(open) if byte == open => quote = None,
+            Some(_) => {}
+            None => match byte {
+                b'"' | b'\'' | b'`' => quote = Some(byte),
+                b'#' => return Some(idx),
+                b'/' if matches!(bytes.get(idx + 1), Some(b'/' | b'*')) => return Some(idx),
+                b'<' if line[idx..].starts_with("<!--") => return Some(idx),
+                _ => {}
+            },
+        }
+        idx += 1;
+    }
+    None
+}
+
+fn is_code_token(token: &str) -> bool {
+    token.len() == 6
+        && token.starts_with("PX")
+        && token[2..].chars().all(|ch| ch.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,41 @@
+use super::*;
+
+#[test]
+fn registry_codes_are_unique_and_well_formed() {
+    let mut seen = HashSet::new();
+    for (code, summary) in REGISTRY {
+        assert!(is_code_token(code), "malformed code {}", code);
+        assert!(seen.insert(*code), "duplicate code {}", code);
+        assert!(!summary.is_empty());
+    }
+}
+
+#[test]
+fn suppressions_target_next_line_same_line_or_file() {
+    let source = "<?php\n\
+        // phpx-ignore PX0301\n\
+        $a = 1;\n\
+        $b = 2; # phpx-ignore PX0303, PX0304\n\
+        /* phpx-ignore-file PX0200 */\n\
+        // phpx-ignore\n\
+        $c = 3;\n";
+    let suppressions = Suppressions::from_source(source);
+    assert!(suppressions.is_suppressed(3, "PX0301"));
+    assert!(!suppressions.is_suppressed(2, "PX0301"));
+    assert!(suppressions.is_suppressed(4, "PX0303"));
+    assert!(suppressions.is_suppressed(4, "PX0304"));
+    assert!(!suppressions.is_suppressed(4, "PX0301"));
+    assert!(suppressions.is_suppressed(42, "PX0200"));
+    assert!(!suppressions.is_suppressed(7, "PX0301"));
+}
+
+#[test]
+fn directives_inside_string_literals_are_not_comments() {
+    let source = "$s = \"phpx-ignore PX0301\";\n\
+        $t = 'a # phpx-ignore PX0302';\n\
+        $u = \"x\"; // phpx-ignore PX0303\n";
+    let suppressions = Suppressions::from_source(source);
+    assert!(!suppressions.is_suppressed(1, "PX0301"));
+    assert!(!suppressions.is_suppressed(2, "PX0302"));
+    assert!(suppressions.is_suppressed(3, "PX0303"));
+}
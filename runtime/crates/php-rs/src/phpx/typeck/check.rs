@@ -5,10 +5,14 @@ use crate::parser::ast::{
 };
 use crate::parser::lexer::token::TokenKind;
 use crate::parser::span::Span;
+use crate::phpx::diagnostics::{RelatedSpan, Suggestion, codes};
+use crate::phpx::typeck::elements::intrinsic_element;
 use crate::phpx::typeck::infer::{
-    EnumCaseInfo, EnumInfo, EnumParamInfo, InferContext, StructInfo, infer_expr,
+    EnumCaseInfo, EnumInfo, EnumParamInfo, InferContext, StructInfo, infer_expr, int_literal_value,
+    literal_type,
 };
-use crate::phpx::typeck::types::{ObjectField, PrimitiveType, Type, merge_types};
+use crate::phpx::typeck::stdlib::stdlib_functions;
+use crate::phpx::typeck::types::{LiteralValue, ObjectField, PrimitiveType, Type, merge_types};
 use regex::Regex;
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fs;
@@ -34,6 +38,16 @@ struct FunctionSig {
     variadic: bool,
 }
 
+impl FunctionSig {
+    fn param_type(&self, idx: usize) -> Option<&Type> {
+        match self.params.get(idx) {
+            Some(param) => param.ty.as_ref(),
+            None if self.variadic => self.params.last().and_then(|p| p.ty.as_ref()),
+            None => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ExternalTypeParamSig {
     pub name: String,
@@ -85,6 +99,11 @@ enum StructFieldResolution {
 pub struct TypeError {
     pub span: Span,
     pub message: String,
+    /// Stable `PX####` code, see `phpx::diagnostics::codes`.
+    pub code: &'static str,
+    pub related: Vec<RelatedSpan>,
+    pub notes: Vec<String>,
+    pub suggestions: Vec<Suggestion>,
 }
 
 struct JsxExprValidator {
@@ -97,22 +116,25 @@ impl<'ast> Visitor<'ast> for JsxExprValidator {
             Expr::Assign { span, .. }
             | Expr::AssignRef { span, .. }
             | Expr::AssignOp { span, .. } => {
-                self.errors.push(TypeError {
+                self.errors.push(TypeError::new(
                     span,
-                    message: "Statements not allowed in JSX expressions".to_string(),
-                });
+                    codes::JSX_ERROR,
+                    "Statements not allowed in JSX expressions".to_string(),
+                ));
             }
             Expr::Yield { span, .. } => {
-                self.errors.push(TypeError {
+                self.errors.push(TypeError::new(
                     span,
-                    message: "Statements not allowed in JSX expressions".to_string(),
-                });
+                    codes::JSX_ERROR,
+                    "Statements not allowed in JSX expressions".to_string(),
+                ));
             }
             Expr::Error { span } => {
-                self.errors.push(TypeError {
+                self.errors.push(TypeError::new(
                     span,
-                    message: "Invalid JSX expression".to_string(),
-                });
+                    codes::JSX_ERROR,
+                    "Invalid JSX expression".to_string(),
+                ));
             }
             _ => {}
         }
@@ -121,9 +143,38 @@ impl<'ast> Visitor<'ast> for JsxExprValidator {
 }
 
 impl TypeError {
+    pub fn new(span: Span, code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            span,
+            code,
+            message: message.into(),
+            related: Vec::new(),
+            notes: Vec::new(),
+            suggestions: Vec::new(),
+        }
+    }
+
+    pub fn with_related(mut self, span: Span, message: impl Into<String>) -> Self {
+        self.related.push(RelatedSpan {
+            span,
+            message: message.into(),
+        });
+        self
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    pub fn with_suggestion(mut self, suggestion: Suggestion) -> Self {
+        self.suggestions.push(suggestion);
+        self
+    }
+
     pub fn to_human_readable(&self, source: &[u8]) -> String {
         let Some(info) = self.span.line_info(source) else {
-            return format!("type error: {}", self.message);
+            return format!("type error[{}]: {}", self.code, self.message);
         };
         let line_str = String::from_utf8_lossy(info.line_text);
         let gutter_width = info.line.to_string().len();
@@ -141,7 +192,8 @@ impl TypeError {
         marker.push_str(&"^".repeat(highlight_len));
 
         format!(
-            "type error: {}\n --> line {}, column {}\n{gutter}|\n{line_no:>width$} | {line_src}\n{gutter}| {marker}",
+            "type error[{}]: {}\n --> line {}, column {}\n{gutter}|\n{line_no:>width$} | {line_src}\n{gutter}| {marker}",
+            self.code,
             self.message,
             info.line,
             info.column,
@@ -263,6 +315,177 @@ pub fn external_functions_from_stub(
     out
 }
 
+/// Exported declarations of a PHPX module as seen by its importers: functions
+/// and types bound to export names, plus every struct, interface, enum and
+/// type alias those signatures can refer to (including ones the module itself
+/// imported).
+#[derive(Debug, Clone, Default)]
+pub struct ModuleSignatures {
+    functions: HashMap<String, FunctionSig>,
+    types: HashMap<String, String>,
+    structs: HashMap<String, StructInfo>,
+    struct_methods: HashMap<String, HashMap<String, MethodSig>>,
+    interfaces: HashMap<String, InterfaceInfo>,
+    enums: HashMap<String, EnumInfo>,
+    enum_methods: HashMap<String, HashMap<String, MethodSig>>,
+    type_aliases: HashMap<String, TypeAliasInfo>,
+}
+
+impl ModuleSignatures {
+    pub fn is_empty(&self) -> bool {
+        self.functions.is_empty() && self.types.is_empty()
+    }
+
+    pub fn exports(&self) -> impl Iterator<Item = &str> {
+        self.functions
+            .keys()
+            .chain(self.types.keys())
+            .map(|name| name.as_str())
+    }
+
+    /// Stable rendering of everything importers can see, for cache keys.
+    /// Declaration spans are left out, so edits that only move code (or
+    /// change function bodies) keep the same fingerprint.
+    pub fn fingerprint(&self) -> String {
+        let mut lines = Vec::new();
+        for (name, sig) in &self.functions {
+            lines.push(format!("function {} {:?}", name, sig));
+        }
+        for (name, local) in &self.types {
+            lines.push(format!("type {} = {}", name, local));
+        }
+        for (name, info) in &self.structs {
+            lines.push(format!("struct {} {:?}", name, info));
+        }
+        for (name, info) in &self.interfaces {
+            lines.push(format!("interface {} {:?}", name, info.fields));
+            for (method, sig) in &info.methods {
+                lines.push(format!("interface {}::{} {:?}", name, method, sig));
+            }
+        }
+        for (name, info) in &self.enums {
+            lines.push(format!("enum {} {:?}", name, info));
+        }
+        for (owner, methods) in self.struct_methods.iter().chain(&self.enum_methods) {
+            for (method, sig) in methods {
+                lines.push(format!("method {}::{} {:?}", owner, method, sig));
+            }
+        }
+        for (name, alias) in &self.type_aliases {
+            lines.push(format!(
+                "alias {} {:?} = {:?}",
+                name, alias.params, alias.ty
+            ));
+        }
+        lines.sort();
+        lines.join("\n")
+    }
+
+    /// Binds export `imported` of `from` under `local`, for an import or a
+    /// re-export. Returns false when `from` has no such export.
+    pub fn bind(&mut self, local: &str, imported: &str, from: &ModuleSignatures) -> bool {
+        merge_missing(&mut self.structs, &from.structs);
+        merge_missing(&mut self.struct_methods, &from.struct_methods);
+        merge_missing(&mut self.interfaces, &from.interfaces);
+        merge_missing(&mut self.enums, &from.enums);
+        merge_missing(&mut self.enum_methods, &from.enum_methods);
+        merge_missing(&mut self.type_aliases, &from.type_aliases);
+        if let Some(sig) = from.functions.get(imported) {
+            self.functions.insert(local.to_string(), sig.clone());
+            return true;
+        }
+        if let Some(declared) = from.types.get(imported) {
+            self.types.insert(local.to_string(), declared.clone());
+            return true;
+        }
+        false
+    }
+}
+
+fn merge_missing<V: Clone>(into: &mut HashMap<String, V>, from: &HashMap<String, V>) {
+    for (name, value) in from {
+        into.entry(name.clone()).or_insert_with(|| value.clone());
+    }
+}
+
+/// Type-checks a module against the signatures of the modules it imports.
+pub fn check_program_with_imports(
+    program: &Program,
+    source: &[u8],
+    file_path: Option<&Path>,
+    externals: &HashMap<String, ExternalFunctionSig>,
+    imports: &ModuleSignatures,
+) -> Result<(), Vec<TypeError>> {
+    let mut ctx = CheckContext::new_with_externals(source, file_path, externals);
+    ctx.import_signatures(program, imports);
+    ctx.check_program(program);
+
+    if ctx.errors.is_empty() {
+        Ok(())
+    } else {
+        Err(ctx.errors)
+    }
+}
+
+/// Collects the signatures a module exports. `exports` pairs each exported
+/// name with the local declaration (or import) it refers to.
+pub fn module_signatures(
+    program: &Program,
+    source: &[u8],
+    imports: &ModuleSignatures,
+    exports: &[(String, String)],
+) -> ModuleSignatures {
+    let mut ctx = CheckContext::new(source, None);
+    ctx.import_signatures(program, imports);
+    ctx.collect_declarations(program);
+
+    let mut out = ModuleSignatures {
+        functions: HashMap::new(),
+        types: HashMap::new(),
+        structs: ctx.structs,
+        struct_methods: ctx.struct_methods,
+        interfaces: ctx.interfaces,
+        enums: ctx.enums,
+        enum_methods: ctx.enum_methods,
+        type_aliases: ctx.type_aliases,
+    };
+    for (exported, local) in exports {
+        if let Some(sig) = ctx.functions.get(local) {
+            out.functions.insert(exported.clone(), sig.clone());
+        } else if out.structs.contains_key(local)
+            || out.interfaces.contains_key(local)
+            || out.enums.contains_key(local)
+            || out.type_aliases.contains_key(local)
+        {
+            out.types.insert(exported.clone(), local.clone());
+        }
+    }
+    out
+}
+
+/// Fields of every object-shaped type alias declared in a stub.
+pub(crate) fn object_shapes_from_stub(
+    program: &Program,
+    source: &[u8],
+) -> HashMap<String, BTreeMap<String, ObjectField>> {
+    let mut ctx = CheckContext::new(source, None);
+    ctx.collect_type_aliases(program);
+
+    let mut out = HashMap::new();
+    for (name, alias) in ctx.type_aliases.iter() {
+        let fields = match &alias.ty {
+            Type::ObjectShape(fields) => fields.clone(),
+            Type::Applied { base, args } if base == "Object" => match args.first() {
+                Some(Type::ObjectShape(fields)) => fields.clone(),
+                _ => continue,
+            },
+            _ => continue,
+        };
+        out.insert(name.clone(), fields);
+    }
+    out
+}
+
 struct CheckContext<'a> {
     #[allow(dead_code)]
     source: &'a [u8],
@@ -274,6 +497,7 @@ struct CheckContext<'a> {
     enum_methods: HashMap<String, HashMap<String, MethodSig>>,
     interfaces: HashMap<String, InterfaceInfo>,
     interface_shapes: HashMap<String, BTreeMap<String, ObjectField>>,
+    field_names: HashMap<String, Vec<String>>,
     functions: HashMap<String, FunctionSig>,
     function_returns: HashMap<String, Type>,
     imported: HashMap<String, String>,
@@ -296,6 +520,7 @@ impl<'a> CheckContext<'a> {
             enum_methods: HashMap::new(),
             interfaces: HashMap::new(),
             interface_shapes: HashMap::new(),
+            field_names: HashMap::new(),
             functions: HashMap::new(),
             function_returns: HashMap::new(),
             imported: HashMap::new(),
@@ -327,6 +552,15 @@ impl<'a> CheckContext<'a> {
     fn check_program(&mut self, program: &Program<'a>) {
         self.check_wasm_stubs();
         self.collect_imported_names();
+        self.collect_declarations(program);
+        let mut env: HashMap<String, Type> = HashMap::new();
+        let mut explicit: HashSet<String> = HashSet::new();
+        for stmt in program.statements.iter() {
+            self.check_stmt(stmt, &mut env, &mut explicit, None);
+        }
+    }
+
+    fn collect_declarations(&mut self, program: &Program<'a>) {
         self.collect_struct_names(program);
         self.collect_interface_names(program);
         self.collect_enum_names(program);
@@ -337,11 +571,106 @@ impl<'a> CheckContext<'a> {
         self.collect_enum_methods(program);
         self.collect_enum_cases(program);
         self.collect_functions(program);
-        let mut env: HashMap<String, Type> = HashMap::new();
-        let mut explicit: HashSet<String> = HashSet::new();
+    }
+
+    /// Makes imported signatures visible before local declarations are
+    /// collected. Names the module declares itself are skipped so local
+    /// declarations win and are not reported as conflicts.
+    fn import_signatures(&mut self, program: &Program<'a>, imports: &ModuleSignatures) {
+        let declared = self.declared_names(program);
+        for (name, info) in imports.structs.iter() {
+            if declared.contains(name) {
+                continue;
+            }
+            self.field_names
+                .insert(name.clone(), info.fields.keys().cloned().collect());
+            self.structs.insert(name.clone(), info.clone());
+        }
+        for (name, info) in imports.interfaces.iter() {
+            if declared.contains(name) {
+                continue;
+            }
+            self.field_names
+                .insert(name.clone(), info.fields.keys().cloned().collect());
+            self.interface_shapes
+                .insert(name.clone(), info.fields.clone());
+            self.interfaces.insert(name.clone(), info.clone());
+        }
+        for (name, info) in imports.enums.iter() {
+            if !declared.contains(name) {
+                self.enums.insert(name.clone(), info.clone());
+            }
+        }
+        for (name, methods) in imports.struct_methods.iter() {
+            if !declared.contains(name) {
+                self.struct_methods.insert(name.clone(), methods.clone());
+            }
+        }
+        for (name, methods) in imports.enum_methods.iter() {
+            if !declared.contains(name) {
+                self.enum_methods.insert(name.clone(), methods.clone());
+            }
+        }
+        for (name, info) in imports.type_aliases.iter() {
+            if !declared.contains(name) {
+                self.type_aliases.insert(name.clone(), info.clone());
+            }
+        }
+        for (local, sig) in imports.functions.iter() {
+            if declared.contains(local) {
+                continue;
+            }
+            if let Some(ret) = &sig.return_type {
+                self.function_returns.insert(local.clone(), ret.clone());
+            }
+            self.functions.insert(local.clone(), sig.clone());
+        }
+        // `import { User as Account }` reaches the declaration through an alias.
+        for (local, target) in imports.types.iter() {
+            if local == target || declared.contains(local) {
+                continue;
+            }
+            let info = match imports.type_aliases.get(target) {
+                Some(alias) => alias.clone(),
+                None => {
+                    let ty = if imports.structs.contains_key(target) {
+                        Type::Struct(target.clone())
+                    } else if imports.interfaces.contains_key(target) {
+                        Type::Interface(target.clone())
+                    } else if imports.enums.contains_key(target) {
+                        Type::Enum(target.clone())
+                    } else {
+                        continue;
+                    };
+                    TypeAliasInfo {
+                        params: Vec::new(),
+                        ty,
+                        span: Span::new(0, 0),
+                    }
+                }
+            };
+            self.type_aliases.insert(local.clone(), info);
+        }
+    }
+
+    fn declared_names(&self, program: &Program<'a>) -> HashSet<String> {
+        let mut names = HashSet::new();
         for stmt in program.statements.iter() {
-            self.check_stmt(stmt, &mut env, &mut explicit, None);
+            let name = match stmt {
+                Stmt::Class {
+                    kind: ClassKind::Struct,
+                    name,
+                    ..
+                }
+                | Stmt::Interface { name, .. }
+                | Stmt::Enum { name, .. }
+                | Stmt::TypeAlias { name, .. }
+                | Stmt::Function { name, .. } => name,
+                _ => continue,
+            };
+            names.insert(token_text(self.source, name.span));
         }
+        names
     }
 
     fn validate_jsx_expr(&mut self, expr: ExprId<'a>) {
@@ -354,6 +683,8 @@ impl<'a> CheckContext<'a> {
         &mut self,
         name: &Name<'a>,
         attributes: &'a [crate::parser::ast::JsxAttribute<'a>],
+        children: &'a [JsxChild<'a>],
+        env: &HashMap<String, Type>,
     ) {
         let raw = token_text(self.source, name.span);
         let raw = raw.trim();
@@ -373,30 +704,42 @@ impl<'a> CheckContext<'a> {
         let has_uppercase = last.chars().any(|ch| ch.is_ascii_uppercase());
 
         if !is_component && has_uppercase {
-            self.errors.push(TypeError {
-                span: name.span,
-                message: format!(
+            let mut error = TypeError::new(
+                name.span,
+                codes::JSX_COMPONENT,
+                format!(
                     "JSX component '{}' must be capitalized (use <{} />)",
                     last,
                     capitalize_jsx_name(last)
                 ),
-            });
+            );
+            if raw == last {
+                error = error.with_suggestion(Suggestion::machine_applicable(
+                    "capitalize the component name",
+                    name.span,
+                    capitalize_jsx_name(last),
+                ));
+            }
+            self.errors.push(error);
             return;
         }
 
         if is_component && !self.is_known_component_name(last) {
-            self.errors.push(TypeError {
-                span: name.span,
-                message: format!(
+            self.errors.push(TypeError::new(
+                name.span,
+                codes::JSX_COMPONENT,
+                format!(
                     "Unknown component '{}'; import it or define function {}()",
                     last, last
                 ),
-            });
+            ));
             return;
         }
 
         if is_component {
-            self.validate_component_props(last, attributes, name.span);
+            self.validate_component_props(last, attributes, children, name.span, env);
+        } else if raw == last {
+            self.validate_intrinsic_attributes(last, attributes, env);
         }
     }
 
@@ -404,7 +747,9 @@ impl<'a> CheckContext<'a> {
         &mut self,
         component: &str,
         attributes: &'a [crate::parser::ast::JsxAttribute<'a>],
+        children: &'a [JsxChild<'a>],
         span: Span,
+        env: &HashMap<String, Type>,
     ) {
         self.validate_component_signature(component, span);
 
@@ -419,24 +764,27 @@ impl<'a> CheckContext<'a> {
         match component {
             "Link" => {
                 if !attrs.contains("to") {
-                    self.errors.push(TypeError {
+                    self.errors.push(TypeError::new(
                         span,
-                        message: "Link requires prop 'to'".to_string(),
-                    });
+                        codes::JSX_PROP,
+                        "Link requires prop 'to'".to_string(),
+                    ));
                 }
             }
             "ContextProvider" => {
                 if !attrs.contains("ctx") {
-                    self.errors.push(TypeError {
+                    self.errors.push(TypeError::new(
                         span,
-                        message: "ContextProvider requires prop 'ctx'".to_string(),
-                    });
+                        codes::JSX_PROP,
+                        "ContextProvider requires prop 'ctx'".to_string(),
+                    ));
                 }
                 if !attrs.contains("value") {
-                    self.errors.push(TypeError {
+                    self.errors.push(TypeError::new(
                         span,
-                        message: "ContextProvider requires prop 'value'".to_string(),
-                    });
+                        codes::JSX_PROP,
+                        "ContextProvider requires prop 'value'".to_string(),
+                    ));
                 }
             }
             _ => {}
@@ -456,7 +804,7 @@ impl<'a> CheckContext<'a> {
         };
 
         for (attr_name, attr_span) in attr_spans.iter() {
-            if expected_fields.contains_key(attr_name) {
+            if expected_fields.contains_key(attr_name) || is_reserved_component_prop(attr_name) {
                 continue;
             }
             let suggestion = nearest_name(attr_name, expected_fields.keys().map(|k| k.as_str()));
@@ -464,23 +812,232 @@ impl<'a> CheckContext<'a> {
             if let Some(suggested) = suggestion {
                 message.push_str(&format!("; did you mean '{}'?", suggested));
             }
-            self.errors.push(TypeError {
-                span: *attr_span,
-                message,
-            });
+            let mut error = TypeError::new(*attr_span, codes::JSX_PROP, message);
+            if let Some(suggested) = suggestion {
+                error = error.with_suggestion(Suggestion::maybe_incorrect(
+                    format!("rename to '{}'", suggested),
+                    *attr_span,
+                    suggested,
+                ));
+            }
+            self.errors.push(error);
+        }
+
+        for attr in attributes.iter() {
+            let attr_name = token_text(self.source, attr.name.span);
+            let Some(field) = expected_fields.get(&attr_name) else {
+                continue;
+            };
+            let actual = self.jsx_attribute_type(attr, &field.ty, env);
+            if !self.is_assignable(&actual, &field.ty) {
+                self.errors.push(TypeError::new(
+                    attr.span,
+                    codes::JSX_PROP,
+                    format!(
+                        "Prop '{}' for component '{}' expects {}, got {}",
+                        attr_name, component, field.ty, actual
+                    ),
+                ));
+            }
+        }
+
+        let child_types = self.jsx_child_types(children, env);
+        match expected_fields.get("children") {
+            Some(field) => self.validate_jsx_children(component, &field.ty, &child_types, span),
+            None if !child_types.is_empty() => {
+                self.errors.push(TypeError::new(
+                    span,
+                    codes::JSX_PROP,
+                    format!(
+                        "Component '{}' does not accept children; add a 'children' field to its props type",
+                        component
+                    ),
+                ));
+            }
+            None => {}
         }
 
         for (field_name, field) in expected_fields.iter() {
             if field.optional || attrs.contains(field_name) {
                 continue;
             }
-            self.errors.push(TypeError {
+            if field_name == "children" && !child_types.is_empty() {
+                continue;
+            }
+            self.errors.push(TypeError::new(
                 span,
-                message: format!(
+                codes::JSX_PROP,
+                format!(
                     "Missing required prop '{}' for component '{}'",
                     field_name, component
                 ),
-            });
+            ));
+        }
+    }
+
+    /// Attributes of lowercase tags are checked against the bundled element
+    /// schema (`elements.d.phpx`); tags missing from it are left alone.
+    fn validate_intrinsic_attributes(
+        &mut self,
+        tag: &str,
+        attributes: &'a [crate::parser::ast::JsxAttribute<'a>],
+        env: &HashMap<String, Type>,
+    ) {
+        let Some(schema) = intrinsic_element(tag) else {
+            return;
+        };
+        for attr in attributes.iter() {
+            let attr_name = token_text(self.source, attr.name.span);
+            if is_passthrough_attribute(&attr_name) {
+                continue;
+            }
+            let Some(field) = schema.get(&attr_name) else {
+                let suggestion = nearest_name(&attr_name, schema.keys().map(|k| k.as_str()));
+                let mut message = format!("Unknown attribute '{}' on <{}>", attr_name, tag);
+                if let Some(suggested) = suggestion {
+                    message.push_str(&format!("; did you mean '{}'?", suggested));
+                }
+                let mut error = TypeError::new(attr.name.span, codes::JSX_PROP, message);
+                if let Some(suggested) = suggestion {
+                    error = error.with_suggestion(Suggestion::maybe_incorrect(
+                        format!("rename to '{}'", suggested),
+                        attr.name.span,
+                        suggested,
+                    ));
+                }
+                self.errors.push(error);
+                continue;
+            };
+            let actual = self.jsx_attribute_type(attr, &field.ty, env);
+            // `false` drops the attribute from the rendered markup.
+            let expected = Type::Union(vec![
+                field.ty.clone(),
+                Type::Literal(LiteralValue::Bool(false)),
+            ]);
+            if !self.is_assignable(&actual, &expected) {
+                self.errors.push(TypeError::new(
+                    attr.span,
+                    codes::JSX_PROP,
+                    format!(
+                        "Attribute '{}' on <{}> expects {}, got {}",
+                        attr_name, tag, field.ty, actual
+                    ),
+                ));
+            }
+        }
+    }
+
+    /// Type of an attribute value; closures are typed against the expected
+    /// function type so untyped parameters pick up the handler signature.
+    fn jsx_attribute_type(
+        &mut self,
+        attr: &crate::parser::ast::JsxAttribute<'a>,
+        expected: &Type,
+        env: &HashMap<String, Type>,
+    ) -> Type {
+        let Some(value) = attr.value else {
+            return Type::Literal(LiteralValue::Bool(true));
+        };
+        let contextual = match expected {
+            Type::Function { .. } => self.contextual_closure_type(value, expected, env),
+            _ => None,
+        };
+        contextual.unwrap_or_else(|| self.infer_expr_with_env(value, env))
+    }
+
+    /// Spans and types of the children passed to an element, skipping
+    /// whitespace-only text.
+    fn jsx_child_types(
+        &self,
+        children: &'a [JsxChild<'a>],
+        env: &HashMap<String, Type>,
+    ) -> Vec<(Span, Type)> {
+        let mut out = Vec::new();
+        for child in children.iter() {
+            match *child {
+                JsxChild::Text(span) => {
+                    if token_text(self.source, span).trim().is_empty() {
+                        continue;
+                    }
+                    out.push((span, Type::Primitive(PrimitiveType::String)));
+                }
+                JsxChild::Expr(expr) => {
+                    out.push((expr.span(), self.infer_expr_with_env(expr, env)));
+                }
+            }
+        }
+        out
+    }
+
+    /// Checks children against a `children` prop. A tuple type declares
+    /// positional slots; any other type is the type of each child, with lists
+    /// checked element-wise.
+    fn validate_jsx_children(
+        &mut self,
+        component: &str,
+        expected: &Type,
+        children: &[(Span, Type)],
+        span: Span,
+    ) {
+        let slot_type = match expected {
+            Type::Tuple(slots) => {
+                if slots.len() != children.len() {
+                    self.errors.push(TypeError::new(
+                        span,
+                        codes::JSX_PROP,
+                        format!(
+                            "Component '{}' expects {} children, got {}",
+                            component,
+                            slots.len(),
+                            children.len()
+                        ),
+                    ));
+                    return;
+                }
+                for (idx, ((child_span, actual), slot)) in
+                    children.iter().zip(slots.iter()).enumerate()
+                {
+                    if !self.is_child_assignable(actual, slot) {
+                        self.errors.push(TypeError::new(
+                            *child_span,
+                            codes::JSX_PROP,
+                            format!(
+                                "Child {} of component '{}' expects {}, got {}",
+                                idx + 1,
+                                component,
+                                slot,
+                                actual
+                            ),
+                        ));
+                    }
+                }
+                return;
+            }
+            Type::List(elem) => elem.as_ref().clone(),
+            Type::Map { value, .. } => value.as_ref().clone(),
+            Type::Array => return,
+            other => other.clone(),
+        };
+        for (child_span, actual) in children.iter() {
+            if !self.is_child_assignable(actual, &slot_type) {
+                self.errors.push(TypeError::new(
+                    *child_span,
+                    codes::JSX_PROP,
+                    format!(
+                        "Children of component '{}' must be {}, got {}",
+                        component, slot_type, actual
+                    ),
+                ));
+            }
+        }
+    }
+
+    /// A list child (e.g. a mapped collection) is spread into its parent.
+    fn is_child_assignable(&self, actual: &Type, expected: &Type) -> bool {
+        match actual {
+            Type::List(inner) => self.is_assignable(inner, expected),
+            Type::Array => true,
+            _ => self.is_assignable(actual, expected),
         }
     }
 
@@ -500,46 +1057,50 @@ impl<'a> CheckContext<'a> {
         };
 
         if sig.variadic || sig.params.len() != 1 {
-            self.errors.push(TypeError {
+            self.errors.push(TypeError::new(
                 span,
-                message: format!(
+                codes::JSX_COMPONENT,
+                format!(
                     "JSX component '{}' must accept exactly one typed props parameter",
                     component
                 ),
-            });
+            ));
             return;
         }
 
         let Some(props_ty) = sig.params.first().and_then(|param| param.ty.clone()) else {
-            self.errors.push(TypeError {
+            self.errors.push(TypeError::new(
                 span,
-                message: format!(
+                codes::JSX_COMPONENT,
+                format!(
                     "JSX component '{}' props parameter must be typed (use interface or Object<{{...}}>)",
                     component
                 ),
-            });
+            ));
             return;
         };
 
         if let Type::Struct(name) = &props_ty {
-            self.errors.push(TypeError {
+            self.errors.push(TypeError::new(
                 span,
-                message: format!(
+                codes::JSX_COMPONENT,
+                format!(
                     "JSX component '{}' props type '{}' cannot be a struct; use interface '{}' or Object<{{...}}>",
                     component, name, name
                 ),
-            });
+            ));
             return;
         }
 
         if !self.is_component_props_type(&props_ty) {
-            self.errors.push(TypeError {
+            self.errors.push(TypeError::new(
                 span,
-                message: format!(
+                codes::JSX_COMPONENT,
+                format!(
                     "JSX component '{}' props type must be interface or object shape, got {}",
                     component, props_ty
                 ),
-            });
+            ));
         }
     }
 
@@ -666,19 +1227,21 @@ impl<'a> CheckContext<'a> {
                     if let Some(expr) = expr {
                         if let Expr::Null { span: null_span } = *expr {
                             if self.strict_null && !self.type_allows_null(expected) {
-                                self.errors.push(TypeError {
-                                    span: *null_span,
-                                    message: "Null is not allowed in PHPX; use Option<T> instead"
+                                self.errors.push(TypeError::new(
+                                    *null_span,
+                                    codes::NULL_NOT_ALLOWED,
+                                    "Null is not allowed in PHPX; use Option<T> instead"
                                         .to_string(),
-                                });
+                                ));
                             }
                         }
                     } else if self.strict_null && !self.type_allows_null(expected) {
-                        self.errors.push(TypeError {
-                            span: *span,
-                            message: "Null is not allowed in PHPX; use Option<T> instead"
+                        self.errors.push(TypeError::new(
+                            *span,
+                            codes::NULL_NOT_ALLOWED,
+                            "Null is not allowed in PHPX; use Option<T> instead"
                                 .to_string(),
-                        });
+                        ));
                     }
                     if let Some(expr) = expr {
                         if let Expr::ObjectLiteral {
@@ -690,23 +1253,25 @@ impl<'a> CheckContext<'a> {
                         }
                     }
                     if !self.is_assignable(&actual, expected) {
-                        self.errors.push(TypeError {
-                            span: *span,
-                            message: format!(
+                        self.errors.push(TypeError::new(
+                            *span,
+                            codes::TYPE_MISMATCH,
+                            format!(
                                 "Return type mismatch: expected {}, got {}",
                                 expected, actual
                             ),
-                        });
+                        ));
                     }
                 }
                 if self.strict_null && return_type.is_none() {
                     if let Some(expr) = expr {
                         if let Expr::Null { span: null_span } = *expr {
-                            self.errors.push(TypeError {
-                                span: *null_span,
-                                message: "Null is not allowed in PHPX; use Option<T> instead"
+                            self.errors.push(TypeError::new(
+                                *null_span,
+                                codes::NULL_NOT_ALLOWED,
+                                "Null is not allowed in PHPX; use Option<T> instead"
                                     .to_string(),
-                            });
+                            ));
                         }
                     }
                 }
@@ -714,10 +1279,11 @@ impl<'a> CheckContext<'a> {
             Stmt::Expression { expr, .. } => {
                 if self.strict_null {
                     if let Expr::Null { span } = *expr {
-                    self.errors.push(TypeError {
-                        span: *span,
-                        message: "Null is not allowed in PHPX; use Option<T> instead".to_string(),
-                    });
+                    self.errors.push(TypeError::new(
+                        *span,
+                        codes::NULL_NOT_ALLOWED,
+                        "Null is not allowed in PHPX; use Option<T> instead".to_string(),
+                    ));
                 }
                 }
                 let _ = self.check_expr(expr, env, explicit);
@@ -795,16 +1361,36 @@ impl<'a> CheckContext<'a> {
                 body,
                 ..
             } => {
-                let _ = self.check_expr(expr, env, explicit);
+                let iter_ty = self.check_expr(expr, env, explicit);
+                if let Type::Primitive(_) = iter_ty {
+                    self.errors.push(TypeError::new(
+                        expr.span(),
+                        codes::TYPE_MISMATCH,
+                        format!("foreach expects an array, got {}", iter_ty),
+                    ));
+                }
+                let value_ty = iter_ty.element_type().unwrap_or(Type::Unknown);
+                let key_ty = iter_ty.key_type().unwrap_or(Type::Unknown);
                 let mut loop_env = env.clone();
                 let mut loop_explicit = explicit.clone();
 
-                if let Expr::Variable { name, .. } = *value_var {
-                    let value_name = token_text(self.source, *name)
-                        .trim_start_matches('$')
-                        .to_string();
-                    loop_env.insert(value_name.clone(), Type::Unknown);
-                    loop_explicit.insert(value_name);
+                match *value_var {
+                    Expr::Variable { name, .. } => {
+                        let value_name = token_text(self.source, *name)
+                            .trim_start_matches('$')
+                            .to_string();
+                        loop_env.insert(value_name.clone(), value_ty);
+                        loop_explicit.insert(value_name);
+                    }
+                    Expr::Array { .. } | Expr::ObjectLiteral { .. } => {
+                        self.assign_to_target(
+                            value_var,
+                            &value_ty,
+                            &mut loop_env,
+                            &mut loop_explicit,
+                        );
+                    }
+                    _ => {}
                 }
 
                 if let Some(key_expr) = key_var {
@@ -812,7 +1398,7 @@ impl<'a> CheckContext<'a> {
                         let key_name = token_text(self.source, *name)
                             .trim_start_matches('$')
                             .to_string();
-                        loop_env.insert(key_name.clone(), Type::Unknown);
+                        loop_env.insert(key_name.clone(), key_ty);
                         loop_explicit.insert(key_name);
                     }
                 }
@@ -847,13 +1433,14 @@ impl<'a> CheckContext<'a> {
                         if let Some(ty) = param.ty {
                             let resolved = self.resolve_type_with_params(ty, &type_param_set);
                             if let Type::Struct(ref name) = resolved {
-                                self.errors.push(TypeError {
-                                    span: param.span,
-                                    message: format!(
+                                self.errors.push(TypeError::new(
+                                    param.span,
+                                    codes::TYPE_ERROR,
+                                    format!(
                                         "Destructured parameter '${}' cannot use struct type '{}'; use interface '{}' or Object<{{...}}>",
                                         param_name, name, name
                                     ),
-                                });
+                                ));
                             }
                             // Keep the original carrier variable in scope so lowered
                             // destructuring assignments (e.g. $name = $name.name) type-check.
@@ -879,13 +1466,14 @@ impl<'a> CheckContext<'a> {
                             let expected = self.resolve_type_with_params(ty, &type_param_set);
                             let actual = self.check_expr(default, env, explicit);
                             if !self.is_assignable(&actual, &expected) {
-                                self.errors.push(TypeError {
-                                    span: param.span,
-                                    message: format!(
+                                self.errors.push(TypeError::new(
+                                    param.span,
+                                    codes::TYPE_MISMATCH,
+                                    format!(
                                         "Default parameter type mismatch: expected {}, got {}",
                                         expected, actual
                                     ),
-                                });
+                                ));
                             }
                         }
                     }
@@ -900,13 +1488,14 @@ impl<'a> CheckContext<'a> {
                             Some(args.first().cloned().unwrap_or(Type::Unknown))
                         }
                         Some(other) => {
-                            self.errors.push(TypeError {
-                                span: stmt.span(),
-                                message: format!(
+                            self.errors.push(TypeError::new(
+                                stmt.span(),
+                                codes::ASYNC_AWAIT,
+                                format!(
                                     "Async function must declare Promise<T> return type, got {}",
                                     other
                                 ),
-                            });
+                            ));
                             Some(Type::Unknown)
                         }
                         None => None,
@@ -1068,7 +1657,15 @@ impl<'a> CheckContext<'a> {
                 if let Some(suggested) = suggestion {
                     message.push_str(&format!("; did you mean '${}'?", suggested));
                 }
-                self.errors.push(TypeError { span, message });
+                let mut error = TypeError::new(span, codes::UNKNOWN_NAME, message);
+                if let Some(suggested) = suggestion {
+                    error = error.with_suggestion(Suggestion::maybe_incorrect(
+                        format!("use '${}'", suggested),
+                        span,
+                        format!("${}", suggested),
+                    ));
+                }
+                self.errors.push(error);
                 Type::Unknown
             }
             Expr::Null { .. } => Type::Primitive(PrimitiveType::Null),
@@ -1105,11 +1702,12 @@ impl<'a> CheckContext<'a> {
                 span,
             } => {
                 if self.is_null_comparison(op, left, right) && !self.allow_null_comparisons() {
-                    self.errors.push(TypeError {
+                    self.errors.push(TypeError::new(
                         span,
-                        message: "Null comparisons are not allowed in PHPX; use isset() instead"
+                        codes::NULL_NOT_ALLOWED,
+                        "Null comparisons are not allowed in PHPX; use isset() instead"
                             .to_string(),
-                    });
+                    ));
                 }
                 let _ = self.check_expr(left, env, explicit);
                 let _ = self.check_expr(right, env, explicit);
@@ -1131,10 +1729,11 @@ impl<'a> CheckContext<'a> {
                 for arg in args.iter() {
                     let _ = self.check_expr(arg.value, env, explicit);
                 }
-                self.errors.push(TypeError {
+                self.errors.push(TypeError::new(
                     span,
-                    message: "new is not allowed in PHPX; use struct literals".to_string(),
-                });
+                    codes::OOP_NOT_ALLOWED,
+                    "new is not allowed in PHPX; use struct literals".to_string(),
+                ));
                 Type::Unknown
             }
             Expr::MethodCall {
@@ -1254,7 +1853,7 @@ impl<'a> CheckContext<'a> {
                     }
                     let _ = self.check_expr(item.value, env, explicit);
                 }
-                Type::Array
+                self.infer_expr_with_env(expr, env)
             }
             Expr::ObjectLiteral { items, .. } => {
                 for item in items.iter() {
@@ -1268,7 +1867,7 @@ impl<'a> CheckContext<'a> {
                 children,
                 ..
             } => {
-                self.validate_jsx_element(&name, attributes);
+                self.validate_jsx_element(&name, attributes, children, env);
                 for attr in attributes.iter() {
                     if let Some(value) = attr.value {
                         self.validate_jsx_expr(value);
@@ -1298,10 +1897,11 @@ impl<'a> CheckContext<'a> {
                 let info = if let Some(info) = self.structs.get(&struct_name) {
                     info.clone()
                 } else {
-                    self.errors.push(TypeError {
-                        span: span,
-                        message: format!("Unknown struct '{}'", struct_name),
-                    });
+                    self.errors.push(TypeError::new(
+                        span,
+                        codes::UNKNOWN_MEMBER,
+                        format!("Unknown struct '{}'", struct_name),
+                    ));
                     return Type::Unknown;
                 };
 
@@ -1311,37 +1911,40 @@ impl<'a> CheckContext<'a> {
                     let field_name = field_name.trim_start_matches('$').to_string();
 
                     if !seen.insert(field_name.clone()) {
-                        self.errors.push(TypeError {
-                            span: field.span,
-                            message: format!(
+                        self.errors.push(TypeError::new(
+                            field.span,
+                            codes::DUPLICATE_DECLARATION,
+                            format!(
                                 "Duplicate field '{}' in struct literal '{}'",
                                 field_name, struct_name
                             ),
-                        });
+                        ));
                         continue;
                     }
 
                     let expected = info.fields.get(&field_name);
                     if expected.is_none() {
-                        self.errors.push(TypeError {
-                            span: field.span,
-                            message: format!(
+                        self.errors.push(TypeError::new(
+                            field.span,
+                            codes::UNKNOWN_MEMBER,
+                            format!(
                                 "Unknown field '{}' in struct literal '{}'",
                                 field_name, struct_name
                             ),
-                        });
+                        ));
                     }
 
                     let actual = self.check_expr(field.value, env, explicit);
                     if let Some(expected) = expected {
                         if !self.is_assignable(&actual, expected) {
-                            self.errors.push(TypeError {
-                                span: field.span,
-                                message: format!(
+                            self.errors.push(TypeError::new(
+                                field.span,
+                                codes::TYPE_MISMATCH,
+                                format!(
                                     "Field '{}' expects {}, got {}",
                                     field_name, expected, actual
                                 ),
-                            });
+                            ));
                         }
                     }
                 }
@@ -1351,24 +1954,26 @@ impl<'a> CheckContext<'a> {
                         continue;
                     }
                     if !seen.contains(field) {
-                        self.errors.push(TypeError {
-                            span: span,
-                            message: format!(
+                        self.errors.push(TypeError::new(
+                            span,
+                            codes::MISSING_FIELD,
+                            format!(
                                 "Missing field '{}' in struct literal '{}'",
                                 field, struct_name
                             ),
-                        });
+                        ));
                     }
                 }
 
                 Type::Struct(struct_name)
             }
             Expr::ArrayDimFetch { array, dim, .. } => {
-                let _ = self.check_expr(array, env, explicit);
+                let array_ty = self.check_expr(array, env, explicit);
                 if let Some(dim) = dim {
-                    let _ = self.check_expr(dim, env, explicit);
+                    let dim_ty = self.check_expr(dim, env, explicit);
+                    self.check_array_key(&array_ty, &dim_ty, dim.span());
                 }
-                Type::Unknown
+                self.infer_expr_with_env(expr, env)
             }
             Expr::Ternary {
                 condition,
@@ -1404,15 +2009,22 @@ impl<'a> CheckContext<'a> {
                 match_ty
             }
             Expr::AnonymousClass { span, .. } => {
-                self.errors.push(TypeError {
+                self.errors.push(TypeError::new(
                     span,
-                    message: "Anonymous classes are not allowed in PHPX".to_string(),
-                });
+                    codes::OOP_NOT_ALLOWED,
+                    "Anonymous classes are not allowed in PHPX".to_string(),
+                ));
                 Type::Unknown
             }
-            Expr::Closure { params, body, .. } => {
+            Expr::Closure {
+                params,
+                body,
+                return_type,
+                ..
+            } => {
                 let mut inner_env = env.clone();
                 let mut inner_explicit = explicit.clone();
+                let mut param_tys = Vec::new();
                 for param in params.iter() {
                     let param_name = token_text(self.source, param.name.span)
                         .trim_start_matches('$')
@@ -1422,17 +2034,30 @@ impl<'a> CheckContext<'a> {
                     } else {
                         Type::Unknown
                     };
+                    if param.default.is_none() && !param.variadic {
+                        param_tys.push(param_ty.clone());
+                    }
                     inner_env.insert(param_name.clone(), param_ty);
                     inner_explicit.insert(param_name);
                 }
+                let ret = return_type.map(|ty| self.resolve_type(ty));
                 for stmt in body.iter() {
-                    self.check_stmt(stmt, &mut inner_env, &mut inner_explicit, None);
+                    self.check_stmt(stmt, &mut inner_env, &mut inner_explicit, ret.as_ref());
+                }
+                Type::Function {
+                    params: param_tys,
+                    ret: Box::new(ret.unwrap_or(Type::Unknown)),
                 }
-                Type::Unknown
             }
-            Expr::ArrowFunction { params, expr, .. } => {
+            Expr::ArrowFunction {
+                params,
+                return_type,
+                expr,
+                ..
+            } => {
                 let mut inner_env = env.clone();
                 let mut inner_explicit = explicit.clone();
+                let mut param_tys = Vec::new();
                 for param in params.iter() {
                     let param_name = token_text(self.source, param.name.span)
                         .trim_start_matches('$')
@@ -1442,18 +2067,42 @@ impl<'a> CheckContext<'a> {
                     } else {
                         Type::Unknown
                     };
+                    if param.default.is_none() && !param.variadic {
+                        param_tys.push(param_ty.clone());
+                    }
                     inner_env.insert(param_name.clone(), param_ty);
                     inner_explicit.insert(param_name);
                 }
-                let _ = self.check_expr(expr, &mut inner_env, &mut inner_explicit);
-                Type::Unknown
+                let body_ty = self.check_expr(expr, &mut inner_env, &mut inner_explicit);
+                let ret = match return_type {
+                    Some(ty) => {
+                        let declared = self.resolve_type(ty);
+                        if !self.is_assignable(&body_ty, &declared) {
+                            self.errors.push(TypeError::new(
+                                expr.span(),
+                                codes::TYPE_MISMATCH,
+                                format!(
+                                    "Return type mismatch: expected {}, got {}",
+                                    declared, body_ty
+                                ),
+                            ));
+                        }
+                        declared
+                    }
+                    None => body_ty,
+                };
+                Type::Function {
+                    params: param_tys,
+                    ret: Box::new(ret),
+                }
             }
             Expr::Await { expr, span } => {
                 if self.fn_depth > 0 && self.async_depth == 0 {
-                    self.errors.push(TypeError {
+                    self.errors.push(TypeError::new(
                         span,
-                        message: "await is only allowed in async functions (or at top-level in PHPX modules)".to_string(),
-                    });
+                        codes::ASYNC_AWAIT,
+                        "await is only allowed in async functions (or at top-level in PHPX modules)".to_string(),
+                    ));
                 }
                 let awaited_ty = self.check_expr(expr, env, explicit);
                 match awaited_ty {
@@ -1462,10 +2111,11 @@ impl<'a> CheckContext<'a> {
                     }
                     Type::Unknown => Type::Unknown,
                     other => {
-                        self.errors.push(TypeError {
+                        self.errors.push(TypeError::new(
                             span,
-                            message: format!("await expects Promise<T>, got {}", other),
-                        });
+                            codes::ASYNC_AWAIT,
+                            format!("await expects Promise<T>, got {}", other),
+                        ));
                         Type::Unknown
                     }
                 }
@@ -1656,27 +2306,38 @@ impl<'a> CheckContext<'a> {
         env: &HashMap<String, Type>,
     ) {
         if case_info.params.is_empty() {
-            self.errors.push(TypeError {
+            let mut error = TypeError::new(
                 span,
-                message: format!(
+                codes::ENUM_ERROR,
+                format!(
                     "Enum case {}::{} has no payload; use {}::{} without calling it",
                     enum_name, case_name, enum_name, case_name
                 ),
-            });
+            );
+            let call = token_text(self.source, span);
+            if let Some((callee, _)) = call.split_once('(') {
+                error = error.with_suggestion(Suggestion::machine_applicable(
+                    "remove the call",
+                    span,
+                    callee.trim_end(),
+                ));
+            }
+            self.errors.push(error);
             return;
         }
 
         if args.len() != case_info.params.len() {
-            self.errors.push(TypeError {
+            self.errors.push(TypeError::new(
                 span,
-                message: format!(
+                codes::ARGUMENT_COUNT,
+                format!(
                     "Enum case {}::{} expects {} arguments, got {}",
                     enum_name,
                     case_name,
                     case_info.params.len(),
                     args.len()
                 ),
-            });
+            ));
             return;
         }
 
@@ -1692,9 +2353,10 @@ impl<'a> CheckContext<'a> {
                     self.check_object_literal_against_type(items, expected, obj_span, env);
                 }
                 if !self.is_assignable(&actual, expected) {
-                    self.errors.push(TypeError {
-                        span: arg.span,
-                        message: format!(
+                    self.errors.push(TypeError::new(
+                        arg.span,
+                        codes::ENUM_ERROR,
+                        format!(
                             "Enum case {}::{} argument {} has type {}, expected {}",
                             enum_name,
                             case_name,
@@ -1702,7 +2364,7 @@ impl<'a> CheckContext<'a> {
                             actual,
                             expected
                         ),
-                    });
+                    ));
                 }
             }
         }
@@ -1760,6 +2422,10 @@ impl<'a> CheckContext<'a> {
         arms: &'a [crate::parser::ast::MatchArm<'a>],
         _env: &HashMap<String, Type>,
     ) {
+        if let Some(members) = literal_union_members(cond_ty) {
+            self.check_literal_match_exhaustive(cond_ty, &members, arms);
+            return;
+        }
         let Some((enum_names, allows_null)) = self.enum_names_from_type(cond_ty) else {
             return;
         };
@@ -1783,13 +2449,14 @@ impl<'a> CheckContext<'a> {
                     if let Some(entry) = covered.get_mut(&enum_name) {
                         entry.insert(case_name);
                     } else {
-                        self.errors.push(TypeError {
-                            span: arm.span,
-                            message: format!(
+                        self.errors.push(TypeError::new(
+                            arm.span,
+                            codes::PATTERN_ERROR,
+                            format!(
                                 "Match arm uses enum case '{}::{}' that is not part of this match",
                                 enum_name, case_name
                             ),
-                        });
+                        ));
                         return;
                     }
                 } else {
@@ -1812,23 +2479,73 @@ impl<'a> CheckContext<'a> {
             };
             for case_name in case_names.iter() {
                 if !seen.contains(case_name) {
-                    self.errors.push(TypeError {
-                        span: arms.last().map(|arm| arm.span).unwrap_or_default(),
-                        message: format!(
+                    self.errors.push(TypeError::new(
+                        arms.last().map(|arm| arm.span).unwrap_or_default(),
+                        codes::NON_EXHAUSTIVE_MATCH,
+                        format!(
                             "Match on {} is not exhaustive; missing case {}::{}",
                             enum_name, enum_name, case_name
                         ),
-                    });
+                    ));
                     return;
                 }
             }
         }
 
         if allows_null && !null_covered {
-            self.errors.push(TypeError {
-                span: arms.last().map(|arm| arm.span).unwrap_or_default(),
-                message: "Match on nullable enum is not exhaustive; missing null arm".to_string(),
-            });
+            self.errors.push(TypeError::new(
+                arms.last().map(|arm| arm.span).unwrap_or_default(),
+                codes::NON_EXHAUSTIVE_MATCH,
+                "Match on nullable enum is not exhaustive; missing null arm".to_string(),
+            ));
+        }
+    }
+
+    fn check_literal_match_exhaustive(
+        &mut self,
+        cond_ty: &Type,
+        members: &[Type],
+        arms: &'a [crate::parser::ast::MatchArm<'a>],
+    ) {
+        let mut covered = Vec::new();
+        for arm in arms.iter() {
+            let Some(conds) = arm.conditions else {
+                return;
+            };
+            for cond in conds.iter() {
+                let Some(literal @ Type::Literal(_)) = literal_type(cond) else {
+                    // Mixed conditions: skip exhaustiveness checking.
+                    return;
+                };
+                if !members.contains(&literal) {
+                    self.errors.push(TypeError::new(
+                        arm.span,
+                        codes::PATTERN_ERROR,
+                        format!(
+                            "Match arm uses {} which is not part of {}",
+                            literal, cond_ty
+                        ),
+                    ));
+                    return;
+                }
+                covered.push(literal);
+            }
+        }
+        let missing = members
+            .iter()
+            .filter(|member| !covered.contains(member))
+            .map(|member| member.name())
+            .collect::<Vec<_>>();
+        if !missing.is_empty() {
+            self.errors.push(TypeError::new(
+                arms.last().map(|arm| arm.span).unwrap_or_default(),
+                codes::NON_EXHAUSTIVE_MATCH,
+                format!(
+                    "Match on {} is not exhaustive; missing {}",
+                    cond_ty,
+                    missing.join(", ")
+                ),
+            ));
         }
     }
 
@@ -1838,10 +2555,19 @@ impl<'a> CheckContext<'a> {
         arm: &crate::parser::ast::MatchArm<'a>,
         env: &mut HashMap<String, Type>,
     ) {
-        let Some(var_name) = self.extract_var_name(condition) else {
+        let Some(conds) = arm.conditions else {
             return;
         };
-        let Some(conds) = arm.conditions else {
+        let literals = conds
+            .iter()
+            .map(|cond| literal_type(cond))
+            .collect::<Option<Vec<_>>>()
+            .filter(|types| types.iter().all(|ty| matches!(ty, Type::Literal(_))));
+        if let Some(literals) = literals {
+            self.narrow_to_literals(condition, &literals, env, true);
+            return;
+        }
+        let Some(var_name) = self.extract_var_name(condition) else {
             return;
         };
         let current_ty = env.get(&var_name);
@@ -1903,6 +2629,17 @@ impl<'a> CheckContext<'a> {
             Expr::Binary {
                 op, left, right, ..
             } => {
+                if let Some((subject, literal)) = self.literal_compare(left, right) {
+                    match op {
+                        BinaryOp::EqEqEq => {
+                            self.narrow_to_literals(subject, &[literal], env, truthy)
+                        }
+                        BinaryOp::NotEqEq => {
+                            self.narrow_to_literals(subject, &[literal], env, !truthy)
+                        }
+                        _ => {}
+                    }
+                }
                 if let Some(var_name) = self.null_compare_var(left, right) {
                     match op {
                         BinaryOp::EqEqEq | BinaryOp::EqEq => {
@@ -1936,6 +2673,72 @@ impl<'a> CheckContext<'a> {
         }
     }
 
+    fn literal_compare(&self, left: ExprId<'a>, right: ExprId<'a>) -> Option<(ExprId<'a>, Type)> {
+        match (literal_type(right), literal_type(left)) {
+            (Some(literal @ Type::Literal(_)), _) => Some((left, literal)),
+            (_, Some(literal @ Type::Literal(_))) => Some((right, literal)),
+            _ => None,
+        }
+    }
+
+    /// Narrows a variable, or the object-shape union behind `$var.field`, by comparison
+    /// against literals. `matched` keeps only what the literals allow; otherwise the
+    /// literals are excluded.
+    fn narrow_to_literals(
+        &self,
+        subject: ExprId<'a>,
+        literals: &[Type],
+        env: &mut HashMap<String, Type>,
+        matched: bool,
+    ) {
+        if let Some(name) = self.extract_var_name(subject) {
+            let Some(existing) = env.get(&name) else {
+                return;
+            };
+            let narrowed = narrow_type_by_literals(existing, literals, matched);
+            env.insert(name, narrowed);
+            return;
+        }
+        let Expr::DotAccess {
+            target, property, ..
+        } = *subject
+        else {
+            return;
+        };
+        let Some(name) = self.extract_var_name(target) else {
+            return;
+        };
+        let Some(Type::Union(options)) = env.get(&name) else {
+            return;
+        };
+        let field_name = token_text(self.source, property.span);
+        let kept = options
+            .iter()
+            .filter(|option| {
+                let Type::ObjectShape(fields) = option else {
+                    return true;
+                };
+                let Some(field) = fields.get(&field_name) else {
+                    return true;
+                };
+                if matched {
+                    literals
+                        .iter()
+                        .any(|literal| is_assignable_base(literal, &field.ty))
+                } else {
+                    !literals.contains(&field.ty)
+                }
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        let narrowed = match kept.len() {
+            0 => return,
+            1 => kept.into_iter().next().unwrap_or(Type::Unknown),
+            _ => Type::Union(kept),
+        };
+        env.insert(name, narrowed);
+    }
+
     fn null_compare_var(&self, left: ExprId<'a>, right: ExprId<'a>) -> Option<String> {
         if matches!(*left, Expr::Null { .. }) {
             return self.extract_var_name(right);
@@ -1958,19 +2761,21 @@ impl<'a> CheckContext<'a> {
 
     fn check_static_class_ref(&mut self, class: ExprId<'a>, span: Span) {
         let Some(name) = self.extract_static_ident(class) else {
-            self.errors.push(TypeError {
+            self.errors.push(TypeError::new(
                 span,
-                message: "Dynamic class references are not allowed in PHPX".to_string(),
-            });
+                codes::OOP_NOT_ALLOWED,
+                "Dynamic class references are not allowed in PHPX".to_string(),
+            ));
             return;
         };
         if self.structs.contains_key(&name) || self.enums.contains_key(&name) {
             return;
         }
-        self.errors.push(TypeError {
+        self.errors.push(TypeError::new(
             span,
-            message: format!("Unknown type '{}' in PHPX; classes are not allowed", name),
-        });
+            codes::UNKNOWN_TYPE,
+            format!("Unknown type '{}' in PHPX; classes are not allowed", name),
+        ));
     }
 
     fn remove_null_from_var(&self, name: &str, env: &mut HashMap<String, Type>) {
@@ -2022,10 +2827,30 @@ impl<'a> CheckContext<'a> {
         match target_ty {
             Type::ObjectShape(fields) => {
                 if !fields.contains_key(&prop_name) {
-                    self.errors.push(TypeError {
+                    self.errors.push(TypeError::new(
                         span,
-                        message: format!("Unknown object field '{}'", prop_name),
-                    });
+                        codes::UNKNOWN_MEMBER,
+                        format!("Unknown object field '{}'", prop_name),
+                    ));
+                }
+            }
+            Type::Union(ref options)
+                if options
+                    .iter()
+                    .all(|option| matches!(option, Type::ObjectShape(_))) =>
+            {
+                let missing = options.iter().any(|option| {
+                    matches!(option, Type::ObjectShape(fields) if !fields.contains_key(&prop_name))
+                });
+                if missing {
+                    self.errors.push(TypeError::new(
+                        span,
+                        codes::TYPE_ERROR,
+                        format!(
+                            "Object field '{}' is not present on every member of {}; narrow the union first",
+                            prop_name, target_ty
+                        ),
+                    ));
                 }
             }
             Type::Struct(name) => {
@@ -2035,16 +2860,18 @@ impl<'a> CheckContext<'a> {
                 match self.resolve_struct_field(&name, &prop_name) {
                     StructFieldResolution::Found(_) => {}
                     StructFieldResolution::Ambiguous => {
-                        self.errors.push(TypeError {
+                        self.errors.push(TypeError::new(
                             span,
-                            message: format!("Ambiguous promoted field '{}::{}'", name, prop_name),
-                        });
+                            codes::TYPE_ERROR,
+                            format!("Ambiguous promoted field '{}::{}'", name, prop_name),
+                        ));
                     }
                     StructFieldResolution::Missing => {
-                        self.errors.push(TypeError {
+                        self.errors.push(TypeError::new(
                             span,
-                            message: format!("Unknown struct field '{}::{}'", name, prop_name),
-                        });
+                            codes::UNKNOWN_MEMBER,
+                            format!("Unknown struct field '{}::{}'", name, prop_name),
+                        ));
                     }
                 }
             }
@@ -2053,18 +2880,20 @@ impl<'a> CheckContext<'a> {
                     return;
                 };
                 if !info.fields.contains_key(&prop_name) {
-                    self.errors.push(TypeError {
+                    self.errors.push(TypeError::new(
                         span,
-                        message: format!("Unknown interface field '{}::{}'", name, prop_name),
-                    });
+                        codes::UNKNOWN_MEMBER,
+                        format!("Unknown interface field '{}::{}'", name, prop_name),
+                    ));
                 }
             }
             Type::Enum(name) => {
                 if !self.enum_allows_field(&name, &prop_name) {
-                    self.errors.push(TypeError {
+                    self.errors.push(TypeError::new(
                         span,
-                        message: format!("Unknown enum field '{}::{}'", name, prop_name),
-                    });
+                        codes::UNKNOWN_MEMBER,
+                        format!("Unknown enum field '{}::{}'", name, prop_name),
+                    ));
                 }
             }
             Type::EnumCase {
@@ -2073,23 +2902,25 @@ impl<'a> CheckContext<'a> {
                 ..
             } => {
                 if !self.enum_case_allows_field(&enum_name, &case_name, &prop_name) {
-                    self.errors.push(TypeError {
+                    self.errors.push(TypeError::new(
                         span,
-                        message: format!(
+                        codes::UNKNOWN_MEMBER,
+                        format!(
                             "Unknown enum field '{}::{}::{}'",
                             enum_name, case_name, prop_name
                         ),
-                    });
+                    ));
                 }
             }
             Type::Applied { base, .. }
                 if base.eq_ignore_ascii_case("Option") || base.eq_ignore_ascii_case("Result") =>
             {
                 if prop_name != "name" {
-                    self.errors.push(TypeError {
+                    self.errors.push(TypeError::new(
                         span,
-                        message: format!("Unknown enum field '{}::{}'", base, prop_name),
-                    });
+                        codes::UNKNOWN_MEMBER,
+                        format!("Unknown enum field '{}::{}'", base, prop_name),
+                    ));
                 }
             }
             Type::Union(types) => {
@@ -2165,10 +2996,11 @@ impl<'a> CheckContext<'a> {
                     }
                 }
                 if invalid || (any_ok && missing) {
-                    self.errors.push(TypeError {
+                    self.errors.push(TypeError::new(
                         span,
-                        message: format!("Unknown object field '{}' for union type", prop_name),
-                    });
+                        codes::UNKNOWN_MEMBER,
+                        format!("Unknown object field '{}' for union type", prop_name),
+                    ));
                 }
             }
             _ => {}
@@ -2315,21 +3147,23 @@ impl<'a> CheckContext<'a> {
             let key = object_key_name(item.key, self.source);
             seen.insert(key.clone());
             let Some(expected_field) = expected.get(&key) else {
-                self.errors.push(TypeError {
-                    span: item.span,
-                    message: format!("Unknown object field '{}' in object literal", key),
-                });
+                self.errors.push(TypeError::new(
+                    item.span,
+                    codes::UNKNOWN_MEMBER,
+                    format!("Unknown object field '{}' in object literal", key),
+                ));
                 continue;
             };
             let actual = self.infer_expr_with_env(item.value, env);
             if !self.is_assignable(&actual, &expected_field.ty) {
-                self.errors.push(TypeError {
-                    span: item.span,
-                    message: format!(
+                self.errors.push(TypeError::new(
+                    item.span,
+                    codes::TYPE_MISMATCH,
+                    format!(
                         "Object field '{}' has type {}, expected {}",
                         key, actual, expected_field.ty
                     ),
-                });
+                ));
             }
         }
 
@@ -2338,10 +3172,11 @@ impl<'a> CheckContext<'a> {
                 continue;
             }
             if !seen.contains(name) {
-                self.errors.push(TypeError {
+                self.errors.push(TypeError::new(
                     span,
-                    message: format!("Missing required object field '{}'", name),
-                });
+                    codes::MISSING_FIELD,
+                    format!("Missing required object field '{}'", name),
+                ));
             }
         }
     }
@@ -2354,6 +3189,7 @@ impl<'a> CheckContext<'a> {
     ) {
         match pattern {
             Type::TypeParam(name) => {
+                let actual = &actual.widen();
                 if let Some(existing) = inferred.get(name) {
                     if matches!(existing, Type::Unknown) {
                         inferred.insert(name.clone(), actual.clone());
@@ -2419,17 +3255,76 @@ impl<'a> CheckContext<'a> {
                             }
                         }
                     }
-                } else if base.eq_ignore_ascii_case("array")
-                    && matches!(actual, Type::Array)
-                    && args.len() == 1
+                }
+            }
+            Type::List(element) => {
+                if let Some(actual_element) = actual.element_type() {
+                    self.infer_type_params(element, &actual_element, inferred);
+                }
+            }
+            Type::Map { key, value } => {
+                if let (Some(actual_key), Some(actual_value)) =
+                    (actual.key_type(), actual.element_type())
                 {
-                    self.infer_type_params(&args[0], &Type::Unknown, inferred);
+                    if !matches!(actual_key, Type::Unknown) {
+                        self.infer_type_params(key, &actual_key, inferred);
+                    }
+                    self.infer_type_params(value, &actual_value, inferred);
+                }
+            }
+            Type::Tuple(items) => {
+                if let Type::Tuple(actual_items) = actual {
+                    for (item, actual_item) in items.iter().zip(actual_items.iter()) {
+                        self.infer_type_params(item, actual_item, inferred);
+                    }
+                }
+            }
+            Type::Function { params, ret } => {
+                if let Type::Function {
+                    params: actual_params,
+                    ret: actual_ret,
+                } = actual
+                {
+                    for (param, actual_param) in params.iter().zip(actual_params.iter()) {
+                        if !matches!(actual_param, Type::Unknown) {
+                            self.infer_type_params(param, actual_param, inferred);
+                        }
+                    }
+                    self.infer_type_params(ret, actual_ret, inferred);
+                } else {
+                    // Callables we cannot see into (names, unknown values) leave
+                    // the signature's type parameters open rather than uninferred.
+                    for param in params.iter() {
+                        self.infer_type_params(param, &Type::Unknown, inferred);
+                    }
+                    self.infer_type_params(ret, &Type::Unknown, inferred);
                 }
             }
             _ => {}
         }
     }
 
+    fn element_type_at(&self, array_ty: &Type, dim: Option<ExprId<'a>>) -> Option<Type> {
+        if let (Type::Tuple(items), Some(Expr::Integer { value, .. })) = (array_ty, dim) {
+            let idx = std::str::from_utf8(value).ok()?.parse::<usize>().ok()?;
+            return items.get(idx).cloned();
+        }
+        array_ty.element_type()
+    }
+
+    fn check_array_key(&mut self, array_ty: &Type, dim_ty: &Type, span: Span) {
+        let Some(key_ty) = array_ty.key_type() else {
+            return;
+        };
+        if !self.is_assignable(dim_ty, &key_ty) {
+            self.errors.push(TypeError::new(
+                span,
+                codes::TYPE_MISMATCH,
+                format!("Index type mismatch: expected {}, got {}", key_ty, dim_ty),
+            ));
+        }
+    }
+
     fn assign_to_target(
         &mut self,
         target: ExprId<'a>,
@@ -2445,41 +3340,46 @@ impl<'a> CheckContext<'a> {
                 if let Some(existing) = env.get(&name) {
                     if explicit.contains(&name) {
                         if self.strict_null && is_null && !self.type_allows_null(existing) {
-                            self.errors.push(TypeError {
+                            self.errors.push(TypeError::new(
                                 span,
-                                message: "Null is not allowed in PHPX; use Option<T> instead"
+                                codes::NULL_NOT_ALLOWED,
+                                "Null is not allowed in PHPX; use Option<T> instead"
                                     .to_string(),
-                            });
+                            ));
                         }
                         if !self.is_assignable(value_ty, existing) {
-                            self.errors.push(TypeError {
+                            self.errors.push(TypeError::new(
                                 span,
-                                message: format!(
+                                codes::TYPE_MISMATCH,
+                                format!(
                                     "Type mismatch: expected {}, got {}",
                                     existing, value_ty
                                 ),
-                            });
+                            ));
                         }
                     } else {
                         if self.strict_null && is_null {
-                            self.errors.push(TypeError {
+                            self.errors.push(TypeError::new(
                                 span,
-                                message: "Null is not allowed in PHPX; use Option<T> instead"
+                                codes::NULL_NOT_ALLOWED,
+                                "Null is not allowed in PHPX; use Option<T> instead"
                                     .to_string(),
-                            });
+                            ));
                         }
-                        let merged = merge_types(existing, value_ty);
+                        let merged = merge_types(existing, &value_ty.widen());
                         env.insert(name.clone(), merged);
                     }
                 } else {
                     if self.strict_null && is_null {
-                        self.errors.push(TypeError {
+                        self.errors.push(TypeError::new(
                             span,
-                            message: "Null is not allowed in PHPX; use Option<T> instead"
+                            codes::NULL_NOT_ALLOWED,
+                            "Null is not allowed in PHPX; use Option<T> instead"
                                 .to_string(),
-                        });
+                        ));
                     }
-                    env.insert(name.clone(), value_ty.clone());
+                    // Untyped locals widen literals so later writes of the same base type are allowed.
+                    env.insert(name.clone(), value_ty.widen());
                 }
             }
             Expr::DotAccess {
@@ -2489,6 +3389,59 @@ impl<'a> CheckContext<'a> {
             } => {
                 self.check_dot_access(target, property, span, env);
             }
+            Expr::ArrayDimFetch { array, dim, span } => {
+                let array_ty = self.check_expr(array, env, explicit);
+                let dim_ty = dim.map(|dim| self.check_expr(dim, env, explicit));
+                let local = self
+                    .extract_var_name(array)
+                    .filter(|name| !explicit.contains(name));
+                if let (None, Some(dim), Some(dim_ty)) = (&local, dim, &dim_ty) {
+                    self.check_array_key(&array_ty, dim_ty, dim.span());
+                }
+                if matches!(array_ty, Type::Array) {
+                    return;
+                }
+                let Some(element_ty) = self.element_type_at(&array_ty, dim) else {
+                    return;
+                };
+                match local {
+                    Some(name) => {
+                        // Untyped locals take on the key and element types written
+                        // into them; a string key turns a list into a map.
+                        let int = Type::Primitive(PrimitiveType::Int);
+                        let key_ty = dim_ty.map(|ty| ty.widen()).unwrap_or(int.clone());
+                        let written = if matches!(array_ty, Type::Map { .. })
+                            || !self.is_assignable(&key_ty, &int)
+                        {
+                            Type::Map {
+                                key: Box::new(merge_types(
+                                    &array_ty.key_type().unwrap_or(Type::Unknown),
+                                    &key_ty,
+                                )),
+                                value: Box::new(merge_types(
+                                    &array_ty.element_type().unwrap_or(Type::Unknown),
+                                    &value_ty.widen(),
+                                )),
+                            }
+                        } else {
+                            merge_types(&array_ty, &Type::List(Box::new(value_ty.widen())))
+                        };
+                        env.insert(name, written);
+                    }
+                    None => {
+                        if !self.is_assignable(value_ty, &element_ty) {
+                            self.errors.push(TypeError::new(
+                                span,
+                                codes::TYPE_MISMATCH,
+                                format!(
+                                    "Type mismatch: expected {}, got {}",
+                                    element_ty, value_ty
+                                ),
+                            ));
+                        }
+                    }
+                }
+            }
             Expr::Assign { var, expr, .. } => {
                 let default_ty = self.check_expr(expr, env, explicit);
                 let merged = merge_types(value_ty, &default_ty);
@@ -2559,13 +3512,12 @@ impl<'a> CheckContext<'a> {
                 .and_then(|info| info.fields.get(key))
                 .map(|field| field.ty.clone())
                 .unwrap_or(Type::Unknown),
-            Type::Applied { base, args } => {
-                if base.eq_ignore_ascii_case("array") {
-                    args.first().cloned().unwrap_or(Type::Unknown)
-                } else {
-                    Type::Unknown
-                }
-            }
+            Type::Tuple(items) => key
+                .parse::<usize>()
+                .ok()
+                .and_then(|idx| items.get(idx).cloned())
+                .unwrap_or(Type::Unknown),
+            Type::List(_) | Type::Map { .. } => source_ty.element_type().unwrap_or(Type::Unknown),
             Type::Union(types) => {
                 let mut parts = Vec::new();
                 for ty in types {
@@ -2588,11 +3540,19 @@ impl<'a> CheckContext<'a> {
 
     fn collect_struct_names(&mut self, program: &Program<'a>) {
         for stmt in program.statements.iter() {
-            if let Stmt::Class { kind, name, .. } = stmt {
+            if let Stmt::Class {
+                kind,
+                name,
+                members,
+                ..
+            } = stmt
+            {
                 if *kind != ClassKind::Struct {
                     continue;
                 }
                 let class_name = token_text(self.source, name.span);
+                let names = self.declared_field_names(members);
+                self.field_names.insert(class_name.clone(), names);
                 self.structs
                     .entry(class_name)
                     .or_insert_with(|| StructInfo {
@@ -2606,8 +3566,10 @@ impl<'a> CheckContext<'a> {
 
     fn collect_interface_names(&mut self, program: &Program<'a>) {
         for stmt in program.statements.iter() {
-            if let Stmt::Interface { name, .. } = stmt {
+            if let Stmt::Interface { name, members, .. } = stmt {
                 let iface_name = token_text(self.source, name.span);
+                let names = self.declared_field_names(members);
+                self.field_names.insert(iface_name.clone(), names);
                 self.interfaces
                     .entry(iface_name.clone())
                     .or_insert_with(|| InterfaceInfo {
@@ -2619,6 +3581,28 @@ impl<'a> CheckContext<'a> {
         }
     }
 
+    /// Field names declared directly on a struct or interface, available before
+    /// field types are resolved so `keyof` works inside type aliases.
+    fn declared_field_names(&self, members: &[ClassMember<'a>]) -> Vec<String> {
+        let mut names = Vec::new();
+        for member in members.iter() {
+            match member {
+                ClassMember::Property { entries, .. } => {
+                    for entry in entries.iter() {
+                        let field_name = token_text(self.source, entry.name.span);
+                        names.push(field_name.trim_start_matches('$').to_string());
+                    }
+                }
+                ClassMember::PropertyHook { name, .. } => {
+                    let field_name = token_text(self.source, name.span);
+                    names.push(field_name.trim_start_matches('$').to_string());
+                }
+                _ => {}
+            }
+        }
+        names
+    }
+
     fn collect_enum_names(&mut self, program: &Program<'a>) {
         for stmt in program.statements.iter() {
             let Stmt::Enum {
@@ -2694,13 +3678,14 @@ impl<'a> CheckContext<'a> {
                                     &declared_fields,
                                 );
                                 if embed_names.contains(&field_name) {
-                                    self.errors.push(TypeError {
-                                        span: entry.name.span,
-                                        message: format!(
+                                    self.errors.push(TypeError::new(
+                                        entry.name.span,
+                                        codes::DUPLICATE_DECLARATION,
+                                        format!(
                                             "Struct '{}' already embeds '{}'",
                                             class_name, field_name
                                         ),
-                                    });
+                                    ));
                                 }
                                 fields.insert(
                                     field_name.clone(),
@@ -2717,13 +3702,14 @@ impl<'a> CheckContext<'a> {
                             let field_name = token_text(self.source, name.span);
                             let field_name = field_name.trim_start_matches('$').to_string();
                             if embed_names.contains(&field_name) {
-                                self.errors.push(TypeError {
-                                    span: name.span,
-                                    message: format!(
+                                self.errors.push(TypeError::new(
+                                    name.span,
+                                    codes::DUPLICATE_DECLARATION,
+                                    format!(
                                         "Struct '{}' already embeds '{}'",
                                         class_name, field_name
                                     ),
-                                });
+                                ));
                             }
                             let field_type =
                                 ty.map(|ty| self.resolve_type(ty)).unwrap_or(Type::Unknown);
@@ -2736,32 +3722,35 @@ impl<'a> CheckContext<'a> {
                             for embed in types.iter() {
                                 let embed_name = token_text(self.source, embed.span);
                                 if embed_name == class_name {
-                                    self.errors.push(TypeError {
-                                        span: embed.span,
-                                        message: "Struct cannot embed itself".to_string(),
-                                    });
+                                    self.errors.push(TypeError::new(
+                                        embed.span,
+                                        codes::STRUCT_ERROR,
+                                        "Struct cannot embed itself".to_string(),
+                                    ));
                                     continue;
                                 }
                                 if !self.structs.contains_key(&embed_name) {
-                                    self.errors.push(TypeError {
-                                        span: embed.span,
-                                        message: format!(
+                                    self.errors.push(TypeError::new(
+                                        embed.span,
+                                        codes::UNKNOWN_MEMBER,
+                                        format!(
                                             "Unknown embedded struct '{}'",
                                             embed_name
                                         ),
-                                    });
+                                    ));
                                     continue;
                                 }
                                 if fields.contains_key(&embed_name)
                                     || embed_names.contains(&embed_name)
                                 {
-                                    self.errors.push(TypeError {
-                                        span: embed.span,
-                                        message: format!(
+                                    self.errors.push(TypeError::new(
+                                        embed.span,
+                                        codes::DUPLICATE_DECLARATION,
+                                        format!(
                                             "Duplicate embedded struct '{}'",
                                             embed_name
                                         ),
-                                    });
+                                    ));
                                     continue;
                                 }
                                 embed_names.insert(embed_name.clone());
@@ -2819,7 +3808,7 @@ impl<'a> CheckContext<'a> {
                                 field_name,
                                 ObjectField {
                                     ty: field_ty.clone(),
-                                    optional: false,
+                                    optional: entry.default.is_some(),
                                 },
                             );
                         }
@@ -2925,10 +3914,11 @@ impl<'a> CheckContext<'a> {
                 };
                 let case_name = token_text(self.source, case_name.span);
                 if cases.contains_key(&case_name) {
-                    self.errors.push(TypeError {
-                        span: *span,
-                        message: format!("Duplicate enum case '{}::{}'", enum_name, case_name),
-                    });
+                    self.errors.push(TypeError::new(
+                        *span,
+                        codes::DUPLICATE_DECLARATION,
+                        format!("Duplicate enum case '{}::{}'", enum_name, case_name),
+                    ));
                     continue;
                 }
                 let mut params = Vec::new();
@@ -2936,36 +3926,40 @@ impl<'a> CheckContext<'a> {
                     let mut seen_params = HashSet::new();
                     for param in payload.iter() {
                         if param.by_ref {
-                            self.errors.push(TypeError {
-                                span: param.span,
-                                message: "Enum case payload parameters cannot be by-reference"
+                            self.errors.push(TypeError::new(
+                                param.span,
+                                codes::ENUM_ERROR,
+                                "Enum case payload parameters cannot be by-reference"
                                     .to_string(),
-                            });
+                            ));
                         }
                         if param.variadic {
-                            self.errors.push(TypeError {
-                                span: param.span,
-                                message: "Enum case payload parameters cannot be variadic"
+                            self.errors.push(TypeError::new(
+                                param.span,
+                                codes::ENUM_ERROR,
+                                "Enum case payload parameters cannot be variadic"
                                     .to_string(),
-                            });
+                            ));
                         }
                         if param.default.is_some() {
-                            self.errors.push(TypeError {
-                                span: param.span,
-                                message: "Enum case payload parameters cannot have default values"
+                            self.errors.push(TypeError::new(
+                                param.span,
+                                codes::ENUM_ERROR,
+                                "Enum case payload parameters cannot have default values"
                                     .to_string(),
-                            });
+                            ));
                         }
                         let name = token_text(self.source, param.name.span);
                         let name = name.trim_start_matches('$').to_string();
                         if !seen_params.insert(name.clone()) {
-                            self.errors.push(TypeError {
-                                span: param.span,
-                                message: format!(
+                            self.errors.push(TypeError::new(
+                                param.span,
+                                codes::DUPLICATE_DECLARATION,
+                                format!(
                                     "Duplicate payload field '{}' on enum case {}::{}",
                                     name, enum_name, case_name
                                 ),
-                            });
+                            ));
                         }
                         let ty = param.ty.map(|ty| self.resolve_type(ty));
                         params.push(EnumParamInfo { name, ty });
@@ -3061,31 +4055,39 @@ impl<'a> CheckContext<'a> {
             };
             let alias_name = token_text(self.source, name.span);
             if is_builtin_type_name(&alias_name) {
-                self.errors.push(TypeError {
-                    span: *span,
-                    message: format!("Type alias '{}' shadows a builtin type", alias_name),
-                });
+                self.errors.push(TypeError::new(
+                    *span,
+                    codes::TYPE_ALIAS,
+                    format!("Type alias '{}' shadows a builtin type", alias_name),
+                ));
                 continue;
             }
             if self.structs.contains_key(&alias_name) {
-                self.errors.push(TypeError {
-                    span: *span,
-                    message: format!("Type alias '{}' conflicts with struct name", alias_name),
-                });
+                self.errors.push(TypeError::new(
+                    *span,
+                    codes::TYPE_ALIAS,
+                    format!("Type alias '{}' conflicts with struct name", alias_name),
+                ));
                 continue;
             }
             if self.enums.contains_key(&alias_name) {
-                self.errors.push(TypeError {
-                    span: *span,
-                    message: format!("Type alias '{}' conflicts with enum name", alias_name),
-                });
+                self.errors.push(TypeError::new(
+                    *span,
+                    codes::TYPE_ALIAS,
+                    format!("Type alias '{}' conflicts with enum name", alias_name),
+                ));
                 continue;
             }
-            if self.type_aliases.contains_key(&alias_name) {
-                self.errors.push(TypeError {
-                    span: *span,
-                    message: format!("Duplicate type alias '{}'", alias_name),
-                });
+            if let Some(existing) = self.type_aliases.get(&alias_name) {
+                let previous = existing.span;
+                self.errors.push(
+                    TypeError::new(
+                        *span,
+                        codes::TYPE_ALIAS,
+                        format!("Duplicate type alias '{}'", alias_name),
+                    )
+                    .with_related(previous, "first defined here"),
+                );
                 continue;
             }
             let (param_sigs, param_set) = self.collect_type_param_sigs(type_params);
@@ -3113,10 +4115,11 @@ impl<'a> CheckContext<'a> {
         for param in params.iter() {
             let name = token_text(self.source, param.name.span);
             if !seen.insert(name.clone()) {
-                self.errors.push(TypeError {
-                    span: param.span,
-                    message: format!("Duplicate type parameter '{}'", name),
-                });
+                self.errors.push(TypeError::new(
+                    param.span,
+                    codes::DUPLICATE_DECLARATION,
+                    format!("Duplicate type parameter '{}'", name),
+                ));
             }
             names.push(name);
         }
@@ -3145,7 +4148,7 @@ impl<'a> CheckContext<'a> {
         };
         let name = token_text(self.source, span);
         if name.starts_with('$') {
-            return Type::Unknown;
+            return self.check_function_value_call(&name, args, env, span);
         }
         if (name == "__deka_wasm_call"
             || name == "__deka_wasm_call_async"
@@ -3154,31 +4157,39 @@ impl<'a> CheckContext<'a> {
             || name == "__deka_bridge")
             && !self.allow_internal_bridge_call()
         {
-            self.errors.push(TypeError {
-                span: Span::new(span.start, span.end),
-                message: format!(
+            self.errors.push(TypeError::new(
+                Span::new(span.start, span.end),
+                codes::IMPORT_ERROR,
+                format!(
                     "{} is internal-only; import public modules instead (for example: db, postgres, mysql, sqlite, tcp, tls, encoding/json)",
                     name
                 ),
-            });
+            ));
             return Type::Unknown;
         }
-        let Some(sig) = self.functions.get(&name) else {
-            return Type::Unknown;
+        let from_stdlib = !self.functions.contains_key(&name);
+        let sig = match self.functions.get(&name) {
+            Some(sig) => sig.clone(),
+            // Stubs only model the common call shape (a single array for
+            // array_map, no mode flag for array_filter); others go unchecked.
+            None => match stdlib_functions().get(&name) {
+                Some(sig) if args.len() <= sig.params.len() => sig.to_internal(),
+                _ => return self.builtin_call_type(&name, args, env),
+            },
         };
-        let sig = sig.clone();
 
         let required = sig.params.iter().filter(|p| p.required).count();
         if args.len() < required {
-            self.errors.push(TypeError {
-                span: Span::new(span.start, span.end),
-                message: format!(
+            self.errors.push(TypeError::new(
+                Span::new(span.start, span.end),
+                codes::ARGUMENT_COUNT,
+                format!(
                     "Missing arguments for {}(): expected at least {}, got {}",
                     name,
                     required,
                     args.len()
                 ),
-            });
+            ));
         }
 
         let mut actuals = Vec::new();
@@ -3188,32 +4199,35 @@ impl<'a> CheckContext<'a> {
 
         let mut inferred = HashMap::new();
         if !sig.type_params.is_empty() {
-            let mut idx = 0;
-            while idx < args.len() {
-                let param_ty = if idx >= sig.params.len() {
-                    if sig.variadic {
-                        sig.params.last().and_then(|p| p.ty.as_ref())
-                    } else {
-                        None
-                    }
-                } else {
-                    sig.params[idx].ty.as_ref()
+            for (idx, actual) in actuals.iter().enumerate() {
+                if let Some(param_ty) = sig.param_type(idx) {
+                    self.infer_type_params(param_ty, actual, &mut inferred);
+                }
+            }
+
+            // Closures are typed against their parameter's function type once
+            // the other arguments have pinned down its type parameters.
+            for (idx, arg) in args.iter().enumerate() {
+                let Some(param_ty) = sig.param_type(idx) else {
+                    continue;
                 };
-                if let Some(param_ty) = param_ty {
-                    self.infer_type_params(param_ty, &actuals[idx], &mut inferred);
+                let expected = substitute_type(param_ty, &inferred);
+                if let Some(actual) = self.contextual_closure_type(arg.value, &expected, env) {
+                    self.infer_type_params(param_ty, &actual, &mut inferred);
+                    actuals[idx] = actual;
                 }
-                idx += 1;
             }
 
             for param in sig.type_params.iter() {
                 if !inferred.contains_key(&param.name) {
-                    self.errors.push(TypeError {
-                        span: Span::new(span.start, span.end),
-                        message: format!(
+                    self.errors.push(TypeError::new(
+                        Span::new(span.start, span.end),
+                        codes::INFERENCE_FAILED,
+                        format!(
                             "Unable to infer type parameter '{}' for {}()",
                             param.name, name
                         ),
-                    });
+                    ));
                 }
             }
 
@@ -3223,13 +4237,14 @@ impl<'a> CheckContext<'a> {
                 };
                 if let Some(constraint) = &param.constraint {
                     if !self.is_assignable(inferred_ty, constraint) {
-                        self.errors.push(TypeError {
-                            span: Span::new(span.start, span.end),
-                            message: format!(
+                        self.errors.push(TypeError::new(
+                            Span::new(span.start, span.end),
+                            codes::UNSATISFIED_CONSTRAINT,
+                            format!(
                                 "Type argument for '{}' does not satisfy constraint {}",
                                 param.name, constraint
                             ),
-                        });
+                        ));
                     }
                 }
             }
@@ -3237,53 +4252,233 @@ impl<'a> CheckContext<'a> {
 
         let mut idx = 0;
         while idx < args.len() {
-            let param_ty = if idx >= sig.params.len() {
-                if sig.variadic {
-                    sig.params.last().and_then(|p| p.ty.as_ref())
-                } else {
-                    None
-                }
-            } else {
-                sig.params[idx].ty.as_ref()
-            };
-            if let Some(param_ty) = param_ty {
+            if let Some(param_ty) = sig.param_type(idx) {
                 let expected = substitute_type(param_ty, &inferred);
+                if let Some(actual) = self.contextual_closure_type(args[idx].value, &expected, env)
+                {
+                    actuals[idx] = actual;
+                }
                 if matches!(actuals[idx], Type::Primitive(PrimitiveType::Null))
                     && self.strict_null
                     && !self.type_allows_null(&expected)
                 {
-                    self.errors.push(TypeError {
-                        span: args[idx].span,
-                        message: "Null is not allowed in PHPX; use Option<T> instead".to_string(),
-                    });
+                    self.errors.push(TypeError::new(
+                        args[idx].span,
+                        codes::NULL_NOT_ALLOWED,
+                        "Null is not allowed in PHPX; use Option<T> instead".to_string(),
+                    ));
                 }
                 if let Expr::ObjectLiteral { items, span } = *args[idx].value {
                     self.check_object_literal_against_type(items, &expected, span, env);
                 }
                 if !self.is_assignable(&actuals[idx], &expected) {
-                    self.errors.push(TypeError {
-                        span: args[idx].span,
-                        message: format!(
+                    self.errors.push(TypeError::new(
+                        args[idx].span,
+                        codes::TYPE_MISMATCH,
+                        format!(
                             "Argument {} type mismatch: expected {}, got {}",
                             idx + 1,
                             expected,
                             actuals[idx]
                         ),
-                    });
+                    ));
                 }
             } else if self.strict_null
                 && matches!(actuals[idx], Type::Primitive(PrimitiveType::Null))
             {
-                self.errors.push(TypeError {
-                    span: args[idx].span,
-                    message: "Null is not allowed in PHPX; use Option<T> instead".to_string(),
-                });
+                self.errors.push(TypeError::new(
+                    args[idx].span,
+                    codes::NULL_NOT_ALLOWED,
+                    "Null is not allowed in PHPX; use Option<T> instead".to_string(),
+                ));
             }
             idx += 1;
         }
 
         let ret = sig.return_type.clone().unwrap_or(Type::Unknown);
-        substitute_type(&ret, &inferred)
+        let ret = substitute_type(&ret, &inferred);
+        if from_stdlib {
+            let refined = self.builtin_call_type(&name, args, env);
+            if !matches!(refined, Type::Unknown) {
+                return refined;
+            }
+        }
+        ret
+    }
+
+    /// Result types for the array builtins whose element type follows from
+    /// their arguments; other builtins stay `unknown`.
+    fn builtin_call_type(
+        &mut self,
+        name: &str,
+        args: &'a [crate::parser::ast::Arg<'a>],
+        env: &HashMap<String, Type>,
+    ) -> Type {
+        let array_arg = |idx: usize, this: &Self| {
+            args.get(idx)
+                .map(|arg| this.infer_expr_with_env(arg.value, env))
+                .unwrap_or(Type::Unknown)
+        };
+        match name.to_ascii_lowercase().as_str() {
+            "array_map" if args.len() == 2 => {
+                let array_ty = array_arg(1, self);
+                let Some(element) = array_ty.element_type() else {
+                    return Type::Unknown;
+                };
+                let mapped = self.callback_return_type(args[0].value, &[element], env);
+                match array_ty {
+                    Type::Map { key, .. } => Type::Map {
+                        key,
+                        value: Box::new(mapped),
+                    },
+                    Type::Array => Type::Array,
+                    _ => Type::List(Box::new(mapped)),
+                }
+            }
+            "array_filter" if !args.is_empty() => {
+                let array_ty = array_arg(0, self);
+                if matches!(array_ty, Type::Array) {
+                    return Type::Array;
+                }
+                // Filtering keeps the original keys, so lists become int-keyed maps.
+                match (array_ty.key_type(), array_ty.element_type()) {
+                    (Some(key), Some(value)) => Type::Map {
+                        key: Box::new(key),
+                        value: Box::new(value),
+                    },
+                    _ => Type::Unknown,
+                }
+            }
+            "array_values" if args.len() == 1 => {
+                let array_ty = array_arg(0, self);
+                if matches!(array_ty, Type::Array) {
+                    return Type::Array;
+                }
+                match array_ty.element_type() {
+                    Some(element) => Type::List(Box::new(element)),
+                    None => Type::Unknown,
+                }
+            }
+            _ => Type::Unknown,
+        }
+    }
+
+    fn callback_return_type(
+        &mut self,
+        callback: ExprId<'a>,
+        param_tys: &[Type],
+        env: &HashMap<String, Type>,
+    ) -> Type {
+        let expected = Type::Function {
+            params: param_tys.to_vec(),
+            ret: Box::new(Type::Unknown),
+        };
+        let callback_ty = self
+            .contextual_closure_type(callback, &expected, env)
+            .unwrap_or_else(|| self.infer_expr_with_env(callback, env));
+        match callback_ty {
+            Type::Function { ret, .. } => *ret,
+            _ => Type::Unknown,
+        }
+    }
+
+    /// Signature of a closure literal passed where `expected` is known.
+    /// Unannotated parameters take the expected parameter types, so arrow
+    /// function bodies infer against them. The closure itself has already
+    /// been checked as an argument, so errors raised here are dropped.
+    fn contextual_closure_type(
+        &mut self,
+        expr: ExprId<'a>,
+        expected: &Type,
+        env: &HashMap<String, Type>,
+    ) -> Option<Type> {
+        let (params, return_type, body) = match *expr {
+            Expr::ArrowFunction {
+                params,
+                return_type,
+                expr,
+                ..
+            } => (params, return_type, Some(expr)),
+            Expr::Closure {
+                params,
+                return_type,
+                ..
+            } => (params, return_type, None),
+            _ => return None,
+        };
+        let expected_params = match expected {
+            Type::Function { params, .. } => params.as_slice(),
+            _ => &[],
+        };
+        let error_mark = self.errors.len();
+        let mut inner_env = env.clone();
+        let mut param_tys = Vec::new();
+        for (idx, param) in params.iter().enumerate() {
+            let param_name = token_text(self.source, param.name.span)
+                .trim_start_matches('$')
+                .to_string();
+            let param_ty = match param.ty {
+                Some(ty) => self.resolve_type(ty),
+                None => expected_params.get(idx).cloned().unwrap_or(Type::Unknown),
+            };
+            if param.default.is_none() && !param.variadic {
+                param_tys.push(param_ty.clone());
+            }
+            inner_env.insert(param_name, param_ty);
+        }
+        let ret = match (return_type, body) {
+            (Some(ty), _) => self.resolve_type(ty),
+            (None, Some(body)) => self.infer_expr_with_env(body, &inner_env),
+            (None, None) => Type::Unknown,
+        };
+        self.errors.truncate(error_mark);
+        Some(Type::Function {
+            params: param_tys,
+            ret: Box::new(ret),
+        })
+    }
+
+    fn check_function_value_call(
+        &mut self,
+        name: &str,
+        args: &'a [crate::parser::ast::Arg<'a>],
+        env: &HashMap<String, Type>,
+        span: Span,
+    ) -> Type {
+        let Some(Type::Function { params, ret }) = env.get(name.trim_start_matches('$')).cloned()
+        else {
+            return Type::Unknown;
+        };
+        if args.len() < params.len() {
+            self.errors.push(TypeError::new(
+                span,
+                codes::ARGUMENT_COUNT,
+                format!(
+                    "Missing arguments for {}(): expected at least {}, got {}",
+                    name,
+                    params.len(),
+                    args.len()
+                ),
+            ));
+        }
+        for (idx, (arg, expected)) in args.iter().zip(params.iter()).enumerate() {
+            let actual = self
+                .contextual_closure_type(arg.value, expected, env)
+                .unwrap_or_else(|| self.infer_expr_with_env(arg.value, env));
+            if !self.is_assignable(&actual, expected) {
+                self.errors.push(TypeError::new(
+                    arg.span,
+                    codes::TYPE_MISMATCH,
+                    format!(
+                        "Argument {} type mismatch: expected {}, got {}",
+                        idx + 1,
+                        expected,
+                        actual
+                    ),
+                ));
+            }
+        }
+        *ret
     }
 
     fn allow_internal_bridge_call(&self) -> bool {
@@ -3340,25 +4535,27 @@ impl<'a> CheckContext<'a> {
 
         let Some(sig) = sig else {
             if let Some(owner) = owner_label {
-                self.errors.push(TypeError {
+                self.errors.push(TypeError::new(
                     span,
-                    message: format!("Unknown method '{}' on {}", method_name, owner),
-                });
+                    codes::UNKNOWN_MEMBER,
+                    format!("Unknown method '{}' on {}", method_name, owner),
+                ));
             }
             return Type::Unknown;
         };
 
         let required = sig.params.iter().filter(|p| p.required).count();
         if args.len() < required {
-            self.errors.push(TypeError {
+            self.errors.push(TypeError::new(
                 span,
-                message: format!(
+                codes::ARGUMENT_COUNT,
+                format!(
                     "Missing arguments for {}(): expected at least {}, got {}",
                     method_name,
                     required,
                     args.len()
                 ),
-            });
+            ));
         }
 
         let mut actuals = Vec::new();
@@ -3382,32 +4579,35 @@ impl<'a> CheckContext<'a> {
                     && self.strict_null
                     && !self.type_allows_null(param_ty)
                 {
-                    self.errors.push(TypeError {
-                        span: args[idx].span,
-                        message: "Null is not allowed in PHPX; use Option<T> instead".to_string(),
-                    });
+                    self.errors.push(TypeError::new(
+                        args[idx].span,
+                        codes::NULL_NOT_ALLOWED,
+                        "Null is not allowed in PHPX; use Option<T> instead".to_string(),
+                    ));
                 }
                 if let Expr::ObjectLiteral { items, span } = *args[idx].value {
                     self.check_object_literal_against_type(items, param_ty, span, env);
                 }
                 if !self.is_assignable(&actuals[idx], param_ty) {
-                    self.errors.push(TypeError {
-                        span: args[idx].span,
-                        message: format!(
+                    self.errors.push(TypeError::new(
+                        args[idx].span,
+                        codes::TYPE_MISMATCH,
+                        format!(
                             "Argument {} type mismatch: expected {}, got {}",
                             idx + 1,
                             param_ty,
                             actuals[idx]
                         ),
-                    });
+                    ));
                 }
             } else if self.strict_null
                 && matches!(actuals[idx], Type::Primitive(PrimitiveType::Null))
             {
-                self.errors.push(TypeError {
-                    span: args[idx].span,
-                    message: "Null is not allowed in PHPX; use Option<T> instead".to_string(),
-                });
+                self.errors.push(TypeError::new(
+                    args[idx].span,
+                    codes::NULL_NOT_ALLOWED,
+                    "Null is not allowed in PHPX; use Option<T> instead".to_string(),
+                ));
             }
             idx += 1;
         }
@@ -3427,73 +4627,81 @@ impl<'a> CheckContext<'a> {
         for ann in entry.annotations.iter() {
             let ann_name = token_text(self.source, ann.name.span).to_string();
             if !seen.insert(ann_name.clone()) {
-                self.errors.push(TypeError {
-                    span: ann.span,
-                    message: format!(
+                self.errors.push(TypeError::new(
+                    ann.span,
+                    codes::ANNOTATION_ERROR,
+                    format!(
                         "Duplicate annotation '@{}' on struct field '{}::{}'",
                         ann_name, struct_name, field_name
                     ),
-                });
+                ));
                 continue;
             }
 
             match ann_name.as_str() {
                 "id" | "unique" | "autoIncrement" => {
                     if !ann.args.is_empty() {
-                        self.errors.push(TypeError {
-                            span: ann.span,
-                            message: format!(
+                        self.errors.push(TypeError::new(
+                            ann.span,
+                            codes::ANNOTATION_ERROR,
+                            format!(
                                 "Annotation '@{}' does not accept arguments",
                                 ann_name
                             ),
-                        });
+                        ));
                     }
                 }
                 "index" => {
                     if ann.args.len() > 1 {
-                        self.errors.push(TypeError {
-                            span: ann.span,
-                            message: "Annotation '@index' accepts at most one argument".to_string(),
-                        });
+                        self.errors.push(TypeError::new(
+                            ann.span,
+                            codes::ANNOTATION_ERROR,
+                            "Annotation '@index' accepts at most one argument".to_string(),
+                        ));
                     }
                     if ann.args.len() == 1 && !matches!(ann.args[0], Expr::String { .. }) {
-                        self.errors.push(TypeError {
-                            span: ann.args[0].span(),
-                            message: "Annotation '@index' argument must be a string literal"
+                        self.errors.push(TypeError::new(
+                            ann.args[0].span(),
+                            codes::ANNOTATION_ERROR,
+                            "Annotation '@index' argument must be a string literal"
                                 .to_string(),
-                        });
+                        ));
                     }
                 }
                 "map" => {
                     if ann.args.len() != 1 {
-                        self.errors.push(TypeError {
-                            span: ann.span,
-                            message: "Annotation '@map' requires exactly one string argument"
+                        self.errors.push(TypeError::new(
+                            ann.span,
+                            codes::ANNOTATION_ERROR,
+                            "Annotation '@map' requires exactly one string argument"
                                 .to_string(),
-                        });
+                        ));
                     } else if !matches!(ann.args[0], Expr::String { .. }) {
-                        self.errors.push(TypeError {
-                            span: ann.args[0].span(),
-                            message: "Annotation '@map' argument must be a string literal"
+                        self.errors.push(TypeError::new(
+                            ann.args[0].span(),
+                            codes::ANNOTATION_ERROR,
+                            "Annotation '@map' argument must be a string literal"
                                 .to_string(),
-                        });
+                        ));
                     }
                 }
                 "default" => {
                     if ann.args.len() != 1 {
-                        self.errors.push(TypeError {
-                            span: ann.span,
-                            message: "Annotation '@default' requires exactly one argument"
+                        self.errors.push(TypeError::new(
+                            ann.span,
+                            codes::ANNOTATION_ERROR,
+                            "Annotation '@default' requires exactly one argument"
                                 .to_string(),
-                        });
+                        ));
                     }
                 }
                 "relation" => {
                     if ann.args.len() != 3 {
-                        self.errors.push(TypeError {
-                            span: ann.span,
-                            message: "Annotation '@relation' requires exactly three string arguments: kind, model, foreignKey".to_string(),
-                        });
+                        self.errors.push(TypeError::new(
+                            ann.span,
+                            codes::ANNOTATION_ERROR,
+                            "Annotation '@relation' requires exactly three string arguments: kind, model, foreignKey".to_string(),
+                        ));
                     } else {
                         let normalize = |raw: &[u8]| {
                             let s = String::from_utf8_lossy(raw).to_string();
@@ -3516,81 +4724,86 @@ impl<'a> CheckContext<'a> {
                         };
 
                         if kind.is_none() {
-                            self.errors.push(TypeError {
-                                span: ann.args[0].span(),
-                                message: "Annotation '@relation' first argument (kind) must be a string literal".to_string(),
-                            });
+                            self.errors.push(TypeError::new(
+                                ann.args[0].span(),
+                                codes::ANNOTATION_ERROR,
+                                "Annotation '@relation' first argument (kind) must be a string literal".to_string(),
+                            ));
                         }
                         if model.is_none() {
-                            self.errors.push(TypeError {
-                                span: ann.args[1].span(),
-                                message: "Annotation '@relation' second argument (model) must be a string literal".to_string(),
-                            });
+                            self.errors.push(TypeError::new(
+                                ann.args[1].span(),
+                                codes::ANNOTATION_ERROR,
+                                "Annotation '@relation' second argument (model) must be a string literal".to_string(),
+                            ));
                         }
                         if foreign_key.is_none() {
-                            self.errors.push(TypeError {
-                                span: ann.args[2].span(),
-                                message: "Annotation '@relation' third argument (foreignKey) must be a string literal".to_string(),
-                            });
+                            self.errors.push(TypeError::new(
+                                ann.args[2].span(),
+                                codes::ANNOTATION_ERROR,
+                                "Annotation '@relation' third argument (foreignKey) must be a string literal".to_string(),
+                            ));
                         }
 
                         if let Some(kind) = kind {
                             if kind != "hasMany" && kind != "belongsTo" && kind != "hasOne" {
-                                self.errors.push(TypeError {
-                                    span: ann.args[0].span(),
-                                    message: "Annotation '@relation' kind must be one of: hasMany, belongsTo, hasOne".to_string(),
-                                });
+                                self.errors.push(TypeError::new(
+                                    ann.args[0].span(),
+                                    codes::ANNOTATION_ERROR,
+                                    "Annotation '@relation' kind must be one of: hasMany, belongsTo, hasOne".to_string(),
+                                ));
                             }
                             let inferred_model = relation_model_from_field_type(field_type, &kind);
                             if let Some(expected_model) = inferred_model {
                                 if let Some(ref model) = model {
                                     if *model != expected_model {
-                                        self.errors.push(TypeError {
-                                            span: ann.args[1].span(),
-                                            message: format!(
+                                        self.errors.push(TypeError::new(
+                                            ann.args[1].span(),
+                                            codes::ANNOTATION_ERROR,
+                                            format!(
                                                 "Annotation '@relation' model '{}' does not match field type model '{}'",
                                                 model, expected_model
                                             ),
-                                        });
+                                        ));
                                     }
                                 }
                             }
                             if kind == "hasMany" {
-                                let is_array = match field_type {
-                                    Some(Type::Array) => true,
-                                    Some(Type::Applied { base, .. }) => {
-                                        base.eq_ignore_ascii_case("array")
-                                    }
-                                    _ => false,
-                                };
+                                let is_array = matches!(
+                                    field_type,
+                                    Some(Type::Array | Type::List(_) | Type::Map { .. })
+                                );
                                 if !is_array {
-                                    self.errors.push(TypeError {
-                                        span: ann.span,
-                                        message: format!(
+                                    self.errors.push(TypeError::new(
+                                        ann.span,
+                                        codes::ANNOTATION_ERROR,
+                                        format!(
                                             "Annotation '@relation(\"hasMany\", ...)' requires array field type on '{}::{}'",
                                             struct_name, field_name
                                         ),
-                                    });
+                                    ));
                                 }
                             }
                             if kind == "belongsTo" || kind == "hasOne" {
                                 if let Some(ref fk) = foreign_key {
                                     if fk == field_name {
-                                        self.errors.push(TypeError {
-                                            span: ann.args[2].span(),
-                                            message: format!(
+                                        self.errors.push(TypeError::new(
+                                            ann.args[2].span(),
+                                            codes::ANNOTATION_ERROR,
+                                            format!(
                                                 "Annotation '@relation' foreignKey '{}' cannot reference relation field '{}::{}'",
                                                 fk, struct_name, field_name
                                             ),
-                                        });
+                                        ));
                                     } else if !declared_fields.contains_key(fk) {
-                                        self.errors.push(TypeError {
-                                            span: ann.args[2].span(),
-                                            message: format!(
+                                        self.errors.push(TypeError::new(
+                                            ann.args[2].span(),
+                                            codes::ANNOTATION_ERROR,
+                                            format!(
                                                 "Annotation '@relation' foreignKey '{}' was not found on struct '{}'",
                                                 fk, struct_name
                                             ),
-                                        });
+                                        ));
                                     }
                                 }
                             }
@@ -3598,26 +4811,28 @@ impl<'a> CheckContext<'a> {
                     }
                 }
                 _ => {
-                    self.errors.push(TypeError {
-                        span: ann.span,
-                        message: format!(
+                    self.errors.push(TypeError::new(
+                        ann.span,
+                        codes::ANNOTATION_ERROR,
+                        format!(
                             "Unknown struct field annotation '@{}' on '{}::{}'",
                             ann_name, struct_name, field_name
                         ),
-                    });
+                    ));
                 }
             }
 
             if ann_name == "autoIncrement" {
                 let is_int = matches!(field_type, Some(Type::Primitive(PrimitiveType::Int)));
                 if !is_int {
-                    self.errors.push(TypeError {
-                        span: ann.span,
-                        message: format!(
+                    self.errors.push(TypeError::new(
+                        ann.span,
+                        codes::ANNOTATION_ERROR,
+                        format!(
                             "Annotation '@autoIncrement' requires int field type on '{}::{}'",
                             struct_name, field_name
                         ),
-                    });
+                    ));
                 }
             }
         }
@@ -3638,22 +4853,24 @@ impl<'a> CheckContext<'a> {
                     if let Some(expected) = ty.map(|ty| self.resolve_type(ty)) {
                         if let Some(default) = default {
                             if !self.is_constant_expr(default) {
-                                self.errors.push(TypeError {
-                                    span: member_span(member),
-                                    message: "Struct field defaults must be constant expressions"
+                                self.errors.push(TypeError::new(
+                                    member_span(member),
+                                    codes::STRUCT_ERROR,
+                                    "Struct field defaults must be constant expressions"
                                         .to_string(),
-                                });
+                                ));
                             }
                             let actual = self.infer_expr_with_env(*default, &HashMap::new());
                             if !self.is_assignable(&actual, &expected) {
                                 let prop_name = token_text(self.source, name.span);
-                                self.errors.push(TypeError {
-                                    span: member_span(member),
-                                    message: format!(
+                                self.errors.push(TypeError::new(
+                                    member_span(member),
+                                    codes::TYPE_MISMATCH,
+                                    format!(
                                         "Default value for {} has type {}, expected {}",
                                         prop_name, actual, expected
                                     ),
-                                });
+                                ));
                             }
                         }
                     }
@@ -3671,21 +4888,23 @@ impl<'a> CheckContext<'a> {
             return;
         };
         if !self.is_constant_expr(default) {
-            self.errors.push(TypeError {
-                span: entry.span,
-                message: "Struct field defaults must be constant expressions".to_string(),
-            });
+            self.errors.push(TypeError::new(
+                entry.span,
+                codes::STRUCT_ERROR,
+                "Struct field defaults must be constant expressions".to_string(),
+            ));
         }
         let actual = self.infer_expr_with_env(default, &HashMap::new());
         if !self.is_assignable(&actual, expected) {
             let name = token_text(self.source, entry.name.span);
-            self.errors.push(TypeError {
-                span: entry.span,
-                message: format!(
+            self.errors.push(TypeError::new(
+                entry.span,
+                codes::TYPE_MISMATCH,
+                format!(
                     "Default value for {} has type {}, expected {}",
                     name, actual, expected
                 ),
-            });
+            ));
         }
     }
 
@@ -3747,22 +4966,24 @@ impl<'a> CheckContext<'a> {
         match ty {
             AstType::Simple(token) => {
                 if token.kind == TokenKind::TypeNull {
-                    self.errors.push(TypeError {
-                        span: token.span,
-                        message: "Null types are not allowed in PHPX; use Option<T> instead"
+                    self.errors.push(TypeError::new(
+                        token.span,
+                        codes::NULL_NOT_ALLOWED,
+                        "Null types are not allowed in PHPX; use Option<T> instead"
                             .to_string(),
-                    });
+                    ));
                 }
                 self.resolve_named_type(token_text(self.source, token.span), visiting, params)
             }
             AstType::Name(name) => self.resolve_name_type(name, visiting, params),
             AstType::Union(types) => {
                 if let Some(span) = self.find_null_type_span(types) {
-                    self.errors.push(TypeError {
+                    self.errors.push(TypeError::new(
                         span,
-                        message: "Nullable unions are not allowed in PHPX; use Option<T> instead"
+                        codes::NULL_NOT_ALLOWED,
+                        "Nullable unions are not allowed in PHPX; use Option<T> instead"
                             .to_string(),
-                    });
+                    ));
                 }
                 let mut out = Vec::new();
                 for ty in types.iter() {
@@ -3784,11 +5005,12 @@ impl<'a> CheckContext<'a> {
                 }
             }
             AstType::Nullable(inner) => {
-                self.errors.push(TypeError {
-                    span: self.type_span(inner),
-                    message: "Nullable types are not allowed in PHPX; use Option<T> instead"
+                self.errors.push(TypeError::new(
+                    self.type_span(inner),
+                    codes::NULL_NOT_ALLOWED,
+                    "Nullable types are not allowed in PHPX; use Option<T> instead"
                         .to_string(),
-                });
+                ));
                 let inner = self.resolve_type_internal(inner, visiting, params);
                 Type::Union(vec![inner, Type::Primitive(PrimitiveType::Null)])
             }
@@ -3807,34 +5029,80 @@ impl<'a> CheckContext<'a> {
                 }
                 Type::ObjectShape(map)
             }
+            AstType::Tuple(types) => Type::Tuple(
+                types
+                    .iter()
+                    .map(|ty| self.resolve_type_internal(ty, visiting, params))
+                    .collect(),
+            ),
+            AstType::Function {
+                params: fn_params,
+                return_type,
+            } => Type::Function {
+                params: fn_params
+                    .iter()
+                    .map(|ty| self.resolve_type_internal(ty, visiting, params))
+                    .collect(),
+                ret: Box::new(
+                    return_type
+                        .map(|ty| self.resolve_type_internal(ty, visiting, params))
+                        .unwrap_or(Type::Unknown),
+                ),
+            },
+            AstType::Literal(token) => {
+                let raw = token_text(self.source, token.span);
+                if token.kind == TokenKind::StringLiteral {
+                    Type::Literal(LiteralValue::String(parse_string_key(&raw)))
+                } else {
+                    int_literal_value(raw.as_bytes())
+                        .map(|number| Type::Literal(LiteralValue::Int(number)))
+                        .unwrap_or(Type::Primitive(PrimitiveType::Int))
+                }
+            }
+            AstType::KeyOf(inner) => {
+                let target = self.resolve_type_internal(inner, visiting, params);
+                self.keyof_type(&target, self.type_span(inner))
+            }
             AstType::Applied { base, args } => {
                 let base_name = match self.base_type_name(base) {
                     Some(name) => name,
                     None => return Type::Unknown,
                 };
                 if base_name.eq_ignore_ascii_case("Option") && args.len() != 1 {
-                    self.errors.push(TypeError {
-                        span: self.type_span(base),
-                        message: "Option<T> expects exactly one type argument".to_string(),
-                    });
+                    self.errors.push(TypeError::new(
+                        self.type_span(base),
+                        codes::TYPE_ARGUMENTS,
+                        "Option<T> expects exactly one type argument".to_string(),
+                    ));
                 }
                 if base_name.eq_ignore_ascii_case("Result") && args.len() != 2 {
-                    self.errors.push(TypeError {
-                        span: self.type_span(base),
-                        message: "Result<T, E> expects exactly two type arguments".to_string(),
-                    });
+                    self.errors.push(TypeError::new(
+                        self.type_span(base),
+                        codes::TYPE_ARGUMENTS,
+                        "Result<T, E> expects exactly two type arguments".to_string(),
+                    ));
+                }
+                if base_name.eq_ignore_ascii_case("array") && !(1..=2).contains(&args.len()) {
+                    self.errors.push(TypeError::new(
+                        self.type_span(base),
+                        codes::TYPE_ARGUMENTS,
+                        "array<V> expects one or two type arguments (array<K, V>)"
+                            .to_string(),
+                    ));
                 }
-                if base_name.eq_ignore_ascii_case("array") && args.len() != 1 {
-                    self.errors.push(TypeError {
-                        span: self.type_span(base),
-                        message: "array<T> expects exactly one type argument".to_string(),
-                    });
+                if base_name.eq_ignore_ascii_case("list") && args.len() != 1 {
+                    self.errors.push(TypeError::new(
+                        self.type_span(base),
+                        codes::TYPE_ARGUMENTS,
+                        "list<T> expects exactly one type argument".to_string(),
+                    ));
                 }
                 if base_name.eq_ignore_ascii_case("Promise") && args.len() != 1 {
-                    self.errors.push(TypeError {
-                        span: self.type_span(base),
-                        message: "Promise<T> expects exactly one type argument".to_string(),
-                    });
+                    self.errors.push(TypeError::new(
+                        self.type_span(base),
+                        codes::TYPE_ARGUMENTS,
+                        "Promise<T> expects exactly one type argument".to_string(),
+                    ));
                 }
                 let mut resolved_args = Vec::new();
                 for arg in args.iter() {
@@ -3844,19 +5112,29 @@ impl<'a> CheckContext<'a> {
                     self.resolve_alias_applied(&base_name, &resolved_args, visiting)
                 {
                     instantiated
+                } else if base_name.eq_ignore_ascii_case("list") {
+                    let element = resolved_args.pop().unwrap_or(Type::Unknown);
+                    Type::List(Box::new(element))
+                } else if base_name.eq_ignore_ascii_case("array") {
+                    let value = resolved_args.pop().unwrap_or(Type::Unknown);
+                    let key = resolved_args.pop().unwrap_or_else(Type::array_key);
+                    Type::Map {
+                        key: Box::new(key),
+                        value: Box::new(value),
+                    }
                 } else {
                     if !base_name.eq_ignore_ascii_case("Option")
                         && !base_name.eq_ignore_ascii_case("Result")
-                        && !base_name.eq_ignore_ascii_case("array")
                         && !base_name.eq_ignore_ascii_case("Promise")
                     {
-                        self.errors.push(TypeError {
-                            span: self.type_span(base),
-                            message: format!(
+                        self.errors.push(TypeError::new(
+                            self.type_span(base),
+                            codes::UNKNOWN_TYPE,
+                            format!(
                                 "Unknown generic type '{}' in PHPX; classes are not allowed",
                                 base_name
                             ),
-                        });
+                        ));
                         Type::Unknown
                     } else {
                         Type::Applied {
@@ -3871,12 +5149,20 @@ impl<'a> CheckContext<'a> {
 
     fn type_span(&self, ty: &AstType<'a>) -> Span {
         match ty {
-            AstType::Simple(token) => token.span,
+            AstType::Simple(token) | AstType::Literal(token) => token.span,
             AstType::Name(name) => name.parts.first().map(|p| p.span).unwrap_or_default(),
-            AstType::Union(types) | AstType::Intersection(types) => {
+            AstType::Union(types) | AstType::Intersection(types) | AstType::Tuple(types) => {
                 types.first().map(|t| self.type_span(t)).unwrap_or_default()
             }
-            AstType::Nullable(inner) => self.type_span(inner),
+            AstType::Nullable(inner) | AstType::KeyOf(inner) => self.type_span(inner),
+            AstType::Function {
+                params,
+                return_type,
+            } => params
+                .first()
+                .or(*return_type)
+                .map(|t| self.type_span(t))
+                .unwrap_or_default(),
             AstType::ObjectShape(fields) => {
                 fields.first().map(|field| field.span).unwrap_or_default()
             }
@@ -3884,6 +5170,32 @@ impl<'a> CheckContext<'a> {
         }
     }
 
+    /// Union of the field names of an object shape, struct or interface.
+    fn keyof_type(&mut self, target: &Type, span: Span) -> Type {
+        let names = match target {
+            Type::ObjectShape(fields) => fields.keys().cloned().collect::<Vec<_>>(),
+            Type::Struct(name) | Type::Interface(name) => {
+                self.field_names.get(name).cloned().unwrap_or_default()
+            }
+            Type::TypeParam(_) => return Type::Primitive(PrimitiveType::String),
+            Type::Unknown => return Type::Unknown,
+            other => {
+                self.errors.push(TypeError::new(
+                    span,
+                    codes::TYPE_MISMATCH,
+                    format!(
+                        "keyof expects an object shape, struct or interface, got {}",
+                        other.name()
+                    ),
+                ));
+                return Type::Unknown;
+            }
+        };
+        names.into_iter().fold(Type::Unknown, |acc, name| {
+            merge_types(&acc, &Type::Literal(LiteralValue::String(name)))
+        })
+    }
+
     fn find_null_type_span(&self, types: &'a [AstType<'a>]) -> Option<Span> {
         for ty in types.iter() {
             match ty {
@@ -3916,6 +5228,10 @@ impl<'a> CheckContext<'a> {
             "string" => Type::Primitive(PrimitiveType::String),
             "null" => Type::Primitive(PrimitiveType::Null),
             "array" => Type::Array,
+            "list" => Type::List(Box::new(Type::Unknown)),
+            "void" => Type::Void,
+            "true" => Type::Literal(LiteralValue::Bool(true)),
+            "false" => Type::Literal(LiteralValue::Bool(false)),
             "object" => Type::Object,
             "mixed" => Type::Mixed,
             _ => {
@@ -3924,16 +5240,18 @@ impl<'a> CheckContext<'a> {
                 } else if let Some(alias) = self.resolve_alias(&name, visiting) {
                     alias
                 } else if name.eq_ignore_ascii_case("Option") {
-                    self.errors.push(TypeError {
-                        span: Span::new(0, 0),
-                        message: "Option<T> requires a type argument".to_string(),
-                    });
+                    self.errors.push(TypeError::new(
+                        Span::new(0, 0),
+                        codes::TYPE_ARGUMENTS,
+                        "Option<T> requires a type argument".to_string(),
+                    ));
                     Type::Unknown
                 } else if name.eq_ignore_ascii_case("Result") {
-                    self.errors.push(TypeError {
-                        span: Span::new(0, 0),
-                        message: "Result<T, E> requires type arguments".to_string(),
-                    });
+                    self.errors.push(TypeError::new(
+                        Span::new(0, 0),
+                        codes::TYPE_ARGUMENTS,
+                        "Result<T, E> requires type arguments".to_string(),
+                    ));
                     Type::Unknown
                 } else if self.enums.contains_key(&name) {
                     Type::Enum(name)
@@ -3941,6 +5259,8 @@ impl<'a> CheckContext<'a> {
                     Type::Interface(name)
                 } else if self.structs.contains_key(&name) {
                     Type::Struct(name)
+                } else if name == "VNode" {
+                    Type::VNode
                 } else {
                     Type::Object
                 }
@@ -3963,24 +5283,27 @@ impl<'a> CheckContext<'a> {
             out.push_str(text.trim_matches('\\'));
         }
         if out.eq_ignore_ascii_case("Option") {
-            self.errors.push(TypeError {
-                span: name.span,
-                message: "Option<T> requires a type argument".to_string(),
-            });
+            self.errors.push(TypeError::new(
+                name.span,
+                codes::TYPE_ARGUMENTS,
+                "Option<T> requires a type argument".to_string(),
+            ));
             return Type::Unknown;
         }
         if out.eq_ignore_ascii_case("Result") {
-            self.errors.push(TypeError {
-                span: name.span,
-                message: "Result<T, E> requires type arguments".to_string(),
-            });
+            self.errors.push(TypeError::new(
+                name.span,
+                codes::TYPE_ARGUMENTS,
+                "Result<T, E> requires type arguments".to_string(),
+            ));
             return Type::Unknown;
         }
         if !self.is_known_named_type(&out, params) {
-            self.errors.push(TypeError {
-                span: name.span,
-                message: format!("Unknown type '{}' in PHPX; classes are not allowed", out),
-            });
+            self.errors.push(TypeError::new(
+                name.span,
+                codes::UNKNOWN_TYPE,
+                format!("Unknown type '{}' in PHPX; classes are not allowed", out),
+            ));
             return Type::Unknown;
         }
         self.resolve_named_type(out, visiting, params)
@@ -3995,17 +5318,19 @@ impl<'a> CheckContext<'a> {
             None => return None,
         };
         if !info.params.is_empty() {
-            self.errors.push(TypeError {
-                span: info.span,
-                message: format!("Type alias '{}' requires type arguments", name),
-            });
+            self.errors.push(TypeError::new(
+                info.span,
+                codes::TYPE_ALIAS,
+                format!("Type alias '{}' requires type arguments", name),
+            ));
             return Some(Type::Unknown);
         }
         if !visiting.insert(name.to_string()) {
-            self.errors.push(TypeError {
-                span: info.span,
-                message: format!("Recursive type alias '{}'", name),
-            });
+            self.errors.push(TypeError::new(
+                info.span,
+                codes::TYPE_ALIAS,
+                format!("Recursive type alias '{}'", name),
+            ));
             return Some(Type::Unknown);
         }
         let resolved = info.ty.clone();
@@ -4028,6 +5353,7 @@ impl<'a> CheckContext<'a> {
             || name.eq_ignore_ascii_case("string")
             || name.eq_ignore_ascii_case("null")
             || name.eq_ignore_ascii_case("array")
+            || name.eq_ignore_ascii_case("list")
             || name.eq_ignore_ascii_case("object")
             || name.eq_ignore_ascii_case("mixed")
             || name.eq_ignore_ascii_case("option")
@@ -4036,6 +5362,7 @@ impl<'a> CheckContext<'a> {
             return true;
         }
         self.type_aliases.contains_key(name)
+            || name == "VNode"
             || self.structs.contains_key(name)
             || self.enums.contains_key(name)
             || self.interfaces.contains_key(name)
@@ -4049,22 +5376,24 @@ impl<'a> CheckContext<'a> {
     ) -> Option<Type> {
         let info = self.type_aliases.get(name)?;
         if info.params.len() != args.len() {
-            self.errors.push(TypeError {
-                span: info.span,
-                message: format!(
+            self.errors.push(TypeError::new(
+                info.span,
+                codes::TYPE_ALIAS,
+                format!(
                     "Type alias '{}' expects {} type arguments, got {}",
                     name,
                     info.params.len(),
                     args.len()
                 ),
-            });
+            ));
             return Some(Type::Unknown);
         }
         if !visiting.insert(name.to_string()) {
-            self.errors.push(TypeError {
-                span: info.span,
-                message: format!("Recursive type alias '{}'", name),
-            });
+            self.errors.push(TypeError::new(
+                info.span,
+                codes::TYPE_ALIAS,
+                format!("Recursive type alias '{}'", name),
+            ));
             return Some(Type::Unknown);
         }
         let mut mapping = HashMap::new();
@@ -4072,15 +5401,16 @@ impl<'a> CheckContext<'a> {
             let arg = args[idx].clone();
             if let Some(constraint) = &param.constraint {
                 if !self.is_assignable(&arg, constraint) {
-                    self.errors.push(TypeError {
-                        span: info.span,
-                        message: format!(
+                    self.errors.push(TypeError::new(
+                        info.span,
+                        codes::UNSATISFIED_CONSTRAINT,
+                        format!(
                             "Type argument {} for '{}' does not satisfy constraint {}",
                             idx + 1,
                             name,
                             constraint
                         ),
-                    });
+                    ));
                 }
             }
             mapping.insert(param.name.clone(), arg);
@@ -4137,10 +5467,11 @@ impl<'a> CheckContext<'a> {
             };
 
             if let Err(message) = resolve_wasm_stub(spec, file_path, &modules_root) {
-                self.errors.push(TypeError {
-                    span: Span::new(matched.start(), matched.end()),
+                self.errors.push(TypeError::new(
+                    Span::new(matched.start(), matched.end()),
+                    codes::WASM_ERROR,
                     message,
-                });
+                ));
             }
         }
     }
@@ -4197,17 +5528,10 @@ fn enum_backed_primitive(ty: &AstType<'_>) -> Option<PrimitiveType> {
 fn relation_model_from_field_type(field_type: Option<&Type>, kind: &str) -> Option<String> {
     let field_type = field_type?;
     if kind == "hasMany" {
-        match field_type {
-            Type::Applied { base, args } if base.eq_ignore_ascii_case("array") => {
-                if args.len() == 1 {
-                    if let Type::Struct(name) = &args[0] {
-                        return Some(name.clone());
-                    }
-                }
-            }
-            _ => {}
-        }
-        return None;
+        return match field_type.element_type() {
+            Some(Type::Struct(name)) => Some(name),
+            _ => None,
+        };
     }
 
     match field_type {
@@ -4228,7 +5552,8 @@ impl<'a> CheckContext<'a> {
         }
         match target {
             Type::Union(options) => {
-                return options.iter().any(|opt| self.is_assignable(source, opt));
+                return covers_bool(source, options)
+                    || options.iter().any(|opt| self.is_assignable(source, opt));
             }
             _ => {}
         }
@@ -4283,6 +5608,22 @@ impl<'a> CheckContext<'a> {
             }
             _ => {}
         }
+        if let (Type::ObjectShape(fields), Type::ObjectShape(expected)) = (source, target) {
+            return expected
+                .iter()
+                .all(|(name, expected_field)| match fields.get(name) {
+                    Some(actual_field) => {
+                        (!actual_field.optional || expected_field.optional)
+                            && self.is_assignable(&actual_field.ty, &expected_field.ty)
+                    }
+                    None => expected_field.optional,
+                });
+        }
+        if let Some(result) =
+            is_composite_assignable(source, target, &|a, b| self.is_assignable(a, b))
+        {
+            return result;
+        }
         match (source, target) {
             (Type::Interface(a), Type::Interface(b)) => a == b,
             (Type::Interface(_), Type::Object) => true,
@@ -4399,10 +5740,62 @@ impl<'a> CheckContext<'a> {
     }
 }
 
+/// Members of a union made up only of literal types.
+fn literal_union_members(ty: &Type) -> Option<Vec<Type>> {
+    match ty {
+        Type::Literal(_) => Some(vec![ty.clone()]),
+        Type::Union(options) if options.iter().all(|opt| matches!(opt, Type::Literal(_))) => {
+            Some(options.clone())
+        }
+        _ => None,
+    }
+}
+
+fn narrow_type_by_literals(ty: &Type, literals: &[Type], matched: bool) -> Type {
+    let options = match ty {
+        Type::Union(options) => options.clone(),
+        other => vec![other.clone()],
+    };
+    if matched {
+        let narrowed = literals
+            .iter()
+            .filter(|literal| {
+                options.iter().any(|option| {
+                    matches!(option, Type::Unknown | Type::Mixed)
+                        || is_assignable_base(literal, option)
+                })
+            })
+            .fold(Type::Unknown, |acc, literal| merge_types(&acc, literal));
+        if matches!(narrowed, Type::Unknown) {
+            ty.clone()
+        } else {
+            narrowed
+        }
+    } else {
+        let kept = options
+            .into_iter()
+            .filter(|option| !literals.contains(option))
+            .collect::<Vec<_>>();
+        match kept.len() {
+            0 => ty.clone(),
+            1 => kept.into_iter().next().unwrap_or(Type::Unknown),
+            _ => Type::Union(kept),
+        }
+    }
+}
+
+/// `bool` is assignable to a union that lists both `true` and `false`.
+fn covers_bool(source: &Type, options: &[Type]) -> bool {
+    matches!(source, Type::Primitive(PrimitiveType::Bool))
+        && options.contains(&Type::Literal(LiteralValue::Bool(true)))
+        && options.contains(&Type::Literal(LiteralValue::Bool(false)))
+}
+
 fn is_assignable_base(source: &Type, target: &Type) -> bool {
     match target {
         Type::Union(options) => {
-            return options.iter().any(|opt| is_assignable_base(source, opt));
+            return covers_bool(source, options)
+                || options.iter().any(|opt| is_assignable_base(source, opt));
         }
         _ => {}
     }
@@ -4412,13 +5805,26 @@ fn is_assignable_base(source: &Type, target: &Type) -> bool {
         }
         _ => {}
     }
+    if let Some(result) = is_composite_assignable(source, target, &|a, b| {
+        matches!(a, Type::Unknown | Type::Mixed)
+            || matches!(b, Type::Unknown | Type::Mixed)
+            || is_assignable_base(a, b)
+    }) {
+        return result;
+    }
     match (source, target) {
         (Type::Primitive(a), Type::Primitive(b)) => match (a, b) {
             (PrimitiveType::Int, PrimitiveType::Float) => true,
             _ => a == b,
         },
+        (Type::Literal(a), Type::Literal(b)) => a == b,
+        (Type::Literal(value), Type::Primitive(_)) => {
+            is_assignable_base(&Type::Primitive(value.primitive()), target)
+        }
         (Type::Array, Type::Array) => true,
+        (Type::Primitive(PrimitiveType::Null) | Type::Void, Type::Void) => true,
         (Type::VNode, Type::VNode) => true,
+        (Type::VNode, Type::Struct(name)) => name == "VNode",
         (Type::Struct(a), Type::Struct(b)) => a == b,
         (Type::Enum(a), Type::Enum(b)) => a == b,
         (Type::EnumCase { enum_name, .. }, Type::Enum(target_name)) => enum_name == target_name,
@@ -4464,8 +5870,6 @@ fn is_assignable_base(source: &Type, target: &Type) -> bool {
                     .zip(args_b.iter())
                     .all(|(a, b)| is_assignable_base(a, b))
         }
-        (Type::Array, Type::Applied { base, .. }) if base.eq_ignore_ascii_case("array") => true,
-        (Type::Applied { base, .. }, Type::Array) if base.eq_ignore_ascii_case("array") => true,
         (Type::ObjectShape(_), Type::Object)
         | (Type::Struct(_), Type::Object)
         | (Type::Enum(_), Type::Object)
@@ -4477,6 +5881,68 @@ fn is_assignable_base(source: &Type, target: &Type) -> bool {
     }
 }
 
+/// Assignability between array-like and function types; `None` when neither
+/// side is one. The untyped `array` stays compatible with every element-typed
+/// form, and function parameters are contravariant.
+fn is_composite_assignable(
+    source: &Type,
+    target: &Type,
+    elem: &dyn Fn(&Type, &Type) -> bool,
+) -> Option<bool> {
+    let int = Type::Primitive(PrimitiveType::Int);
+    let result = match (source, target) {
+        (Type::Array, Type::Array | Type::List(_) | Type::Map { .. } | Type::Tuple(_))
+        | (Type::List(_) | Type::Map { .. } | Type::Tuple(_), Type::Array) => true,
+        (Type::List(a), Type::List(b)) => elem(a, b),
+        (Type::List(a), Type::Map { key, value }) => elem(&int, key) && elem(a, value),
+        (Type::Tuple(items), Type::Tuple(expected)) => {
+            items.len() == expected.len()
+                && items.iter().zip(expected.iter()).all(|(a, b)| elem(a, b))
+        }
+        (Type::Tuple(items), Type::List(b)) => items.iter().all(|a| elem(a, b)),
+        (Type::Tuple(items), Type::Map { key, value }) => {
+            elem(&int, key) && items.iter().all(|a| elem(a, value))
+        }
+        (
+            Type::Map {
+                key: key_a,
+                value: value_a,
+            },
+            Type::Map {
+                key: key_b,
+                value: value_b,
+            },
+        ) => elem(key_a, key_b) && elem(value_a, value_b),
+        (
+            Type::Function {
+                params: source_params,
+                ret: source_ret,
+            },
+            Type::Function {
+                params: target_params,
+                ret: target_ret,
+            },
+        ) => {
+            source_params.len() <= target_params.len()
+                && source_params
+                    .iter()
+                    .zip(target_params.iter())
+                    .all(|(source, target)| elem(target, source))
+                && (matches!(**target_ret, Type::Void) || elem(source_ret, target_ret))
+        }
+        // PHP accepts function names as callables.
+        (
+            Type::Primitive(PrimitiveType::String) | Type::Literal(LiteralValue::String(_)),
+            Type::Function { .. },
+        )
+        | (Type::Function { .. }, Type::Object) => true,
+        (Type::List(_) | Type::Map { .. } | Type::Tuple(_) | Type::Function { .. }, _)
+        | (_, Type::List(_) | Type::Map { .. } | Type::Tuple(_) | Type::Function { .. }) => false,
+        _ => return None,
+    };
+    Some(result)
+}
+
 fn token_text(source: &[u8], span: Span) -> String {
     let start = span.start;
     let end = span.end.min(source.len());
@@ -4495,6 +5961,25 @@ fn capitalize_jsx_name(name: &str) -> String {
     out
 }
 
+/// `key` and namespaced directives such as `client:load` are handled by the
+/// JSX runtime and never reach the component.
+fn is_reserved_component_prop(name: &str) -> bool {
+    name == "key" || name.contains(':')
+}
+
+/// Attributes rendered as-is without schema checks: namespaced directives and
+/// camel-cased `data*`/`aria*` attributes.
+fn is_passthrough_attribute(name: &str) -> bool {
+    if name.contains(':') {
+        return true;
+    }
+    ["data", "aria"].iter().any(|prefix| {
+        name.strip_prefix(prefix)
+            .and_then(|rest| rest.chars().next())
+            .is_some_and(|ch| ch.is_ascii_uppercase())
+    })
+}
+
 fn is_builtin_variable(name: &str) -> bool {
     matches!(
         name,
@@ -4628,6 +6113,18 @@ fn substitute_type(ty: &Type, mapping: &HashMap<String, Type>) -> Type {
             base: base.clone(),
             args: args.iter().map(|t| substitute_type(t, mapping)).collect(),
         },
+        Type::List(element) => Type::List(Box::new(substitute_type(element, mapping))),
+        Type::Map { key, value } => Type::Map {
+            key: Box::new(substitute_type(key, mapping)),
+            value: Box::new(substitute_type(value, mapping)),
+        },
+        Type::Tuple(items) => {
+            Type::Tuple(items.iter().map(|t| substitute_type(t, mapping)).collect())
+        }
+        Type::Function { params, ret } => Type::Function {
+            params: params.iter().map(|t| substitute_type(t, mapping)).collect(),
+            ret: Box::new(substitute_type(ret, mapping)),
+        },
         _ => ty.clone(),
     }
 }
@@ -4644,6 +6141,7 @@ fn is_builtin_type_name(name: &str) -> bool {
             | "string"
             | "null"
             | "array"
+            | "list"
             | "object"
             | "mixed"
             | "void"
@@ -0,0 +1,41 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::OnceLock;
+
+use bumpalo::Bump;
+
+use crate::parser::lexer::Lexer;
+use crate::parser::parser::{Parser, ParserMode};
+use crate::phpx::typeck::check::object_shapes_from_stub;
+use crate::phpx::typeck::types::ObjectField;
+
+/// Attribute schema for intrinsic HTML elements. Each lowercase alias names a
+/// tag; `GlobalAttributes` is merged into every tag.
+const ELEMENTS_STUB: &str = include_str!("elements.d.phpx");
+
+const GLOBAL_ATTRIBUTES: &str = "GlobalAttributes";
+
+pub(crate) fn intrinsic_element(tag: &str) -> Option<&'static BTreeMap<String, ObjectField>> {
+    intrinsic_elements().get(tag)
+}
+
+fn intrinsic_elements() -> &'static HashMap<String, BTreeMap<String, ObjectField>> {
+    static ELEMENTS: OnceLock<HashMap<String, BTreeMap<String, ObjectField>>> = OnceLock::new();
+    ELEMENTS.get_or_init(|| {
+        let arena = Bump::new();
+        let mut parser =
+            Parser::new_with_mode(Lexer::new(ELEMENTS_STUB.as_bytes()), &arena, ParserMode::Phpx);
+        let program = parser.parse_program();
+        let mut shapes = object_shapes_from_stub(&program, ELEMENTS_STUB.as_bytes());
+        let global = shapes.remove(GLOBAL_ATTRIBUTES).unwrap_or_default();
+        shapes
+            .into_iter()
+            .filter(|(name, _)| name.starts_with(|ch: char| ch.is_ascii_lowercase()))
+            .map(|(name, mut fields)| {
+                for (attr, field) in global.iter() {
+                    fields.entry(attr.clone()).or_insert_with(|| field.clone());
+                }
+                (name, fields)
+            })
+            .collect()
+    })
+}
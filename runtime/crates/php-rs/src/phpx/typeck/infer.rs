@@ -1,5 +1,5 @@
-use crate::parser::ast::{BinaryOp, Expr, ObjectKey};
-use crate::phpx::typeck::types::{ObjectField, PrimitiveType, Type, merge_types};
+use crate::parser::ast::{ArrayItem, BinaryOp, Expr, ObjectKey};
+use crate::phpx::typeck::types::{LiteralValue, ObjectField, PrimitiveType, Type, merge_types};
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 
 #[derive(Debug, Clone)]
@@ -88,31 +88,17 @@ pub fn infer_expr(expr: &Expr, ctx: &InferContext) -> Type {
             let name = name.strip_prefix('$').unwrap_or(&name);
             ctx.vars.get(name).cloned().unwrap_or(Type::Unknown)
         }
-        Expr::Array { items, .. } => {
-            let mut element_ty = Type::Unknown;
-            for item in *items {
-                let value_ty = infer_expr(&item.value, ctx);
-                if item.unpack {
-                    match value_ty {
-                        Type::Applied { base, args } if base.eq_ignore_ascii_case("array") => {
-                            let inner = args.get(0).cloned().unwrap_or(Type::Unknown);
-                            element_ty = merge_types(&element_ty, &inner);
-                        }
-                        Type::Array => {
-                            element_ty = merge_types(&element_ty, &Type::Unknown);
-                        }
-                        _ => {
-                            element_ty = merge_types(&element_ty, &Type::Unknown);
-                        }
-                    }
-                } else {
-                    element_ty = merge_types(&element_ty, &value_ty);
-                }
-            }
-            Type::Applied {
-                base: "array".to_string(),
-                args: vec![element_ty],
+        Expr::Array { items, .. } => infer_array_literal(items, ctx),
+        Expr::ArrayDimFetch { array, dim, .. } => {
+            let array_ty = infer_expr(array, ctx);
+            if let (Type::Tuple(elements), Some(Expr::Integer { value, .. })) = (&array_ty, *dim) {
+                return std::str::from_utf8(value)
+                    .ok()
+                    .and_then(|raw| raw.parse::<usize>().ok())
+                    .and_then(|idx| elements.get(idx).cloned())
+                    .unwrap_or(Type::Unknown);
             }
+            array_ty.element_type().unwrap_or(Type::Unknown)
         }
         Expr::ObjectLiteral { items, .. } => {
             let mut fields = BTreeMap::new();
@@ -138,7 +124,7 @@ pub fn infer_expr(expr: &Expr, ctx: &InferContext) -> Type {
                 Type::Unknown
             }
         }
-        Expr::JsxElement { .. } | Expr::JsxFragment { .. } => Type::Unknown,
+        Expr::JsxElement { .. } | Expr::JsxFragment { .. } => Type::VNode,
         Expr::DotAccess {
             target, property, ..
         } => {
@@ -202,6 +188,15 @@ pub fn infer_expr(expr: &Expr, ctx: &InferContext) -> Type {
                         infer_enum_case_field(&enum_name, &case_name, &prop_name, ctx)
                     }
                 }
+                Type::Union(options) => options.iter().fold(Type::Unknown, |acc, option| {
+                    let Type::ObjectShape(fields) = option else {
+                        return acc;
+                    };
+                    match fields.get(&prop_name) {
+                        Some(field) => merge_types(&acc, &field.ty),
+                        None => acc,
+                    }
+                }),
                 Type::Applied { base, args: _ } => {
                     if base.eq_ignore_ascii_case("Option") || base.eq_ignore_ascii_case("Result") {
                         if prop_name == "name" {
@@ -238,14 +233,44 @@ pub fn infer_expr(expr: &Expr, ctx: &InferContext) -> Type {
         }
         Expr::Binary {
             op, left, right, ..
-        } => {
-            if *op == BinaryOp::Coalesce {
+        } => match op {
+            BinaryOp::Coalesce => {
                 let left_ty = infer_expr(left, ctx);
                 let right_ty = infer_expr(right, ctx);
-                return merge_types(&left_ty, &right_ty);
+                merge_types(&left_ty, &right_ty)
             }
-            Type::Unknown
-        }
+            BinaryOp::Concat => Type::Primitive(PrimitiveType::String),
+            BinaryOp::EqEq
+            | BinaryOp::EqEqEq
+            | BinaryOp::NotEq
+            | BinaryOp::NotEqEq
+            | BinaryOp::Lt
+            | BinaryOp::LtEq
+            | BinaryOp::Gt
+            | BinaryOp::GtEq
+            | BinaryOp::And
+            | BinaryOp::Or
+            | BinaryOp::LogicalAnd
+            | BinaryOp::LogicalOr
+            | BinaryOp::LogicalXor
+            | BinaryOp::Instanceof => Type::Primitive(PrimitiveType::Bool),
+            BinaryOp::Spaceship | BinaryOp::Mod => Type::Primitive(PrimitiveType::Int),
+            BinaryOp::Plus | BinaryOp::Minus | BinaryOp::Mul => {
+                let left_ty = infer_expr(left, ctx).widen();
+                let right_ty = infer_expr(right, ctx).widen();
+                match (left_ty, right_ty) {
+                    (Type::Primitive(PrimitiveType::Int), Type::Primitive(PrimitiveType::Int)) => {
+                        Type::Primitive(PrimitiveType::Int)
+                    }
+                    (
+                        Type::Primitive(PrimitiveType::Int | PrimitiveType::Float),
+                        Type::Primitive(PrimitiveType::Int | PrimitiveType::Float),
+                    ) => Type::Primitive(PrimitiveType::Float),
+                    _ => Type::Unknown,
+                }
+            }
+            _ => Type::Unknown,
+        },
         Expr::Ternary {
             condition,
             if_true,
@@ -371,17 +396,77 @@ pub fn infer_expr(expr: &Expr, ctx: &InferContext) -> Type {
     }
 }
 
+fn infer_array_literal(items: &[ArrayItem], ctx: &InferContext) -> Type {
+    // `[]` says nothing about its keys or elements yet.
+    if items.is_empty() {
+        return Type::Array;
+    }
+    let keyed = items.iter().any(|item| item.key.is_some());
+    let spread = items.iter().any(|item| item.unpack);
+    if !keyed && !spread {
+        return Type::Tuple(
+            items
+                .iter()
+                .map(|item| infer_expr(item.value, ctx).widen())
+                .collect(),
+        );
+    }
+
+    let mut key_ty = Type::Unknown;
+    let mut element_ty = Type::Unknown;
+    for item in items {
+        let value_ty = infer_expr(item.value, ctx).widen();
+        if item.unpack {
+            let inner = value_ty.element_type().unwrap_or(Type::Unknown);
+            element_ty = merge_types(&element_ty, &inner);
+            let inner_key = value_ty.key_type().unwrap_or(Type::Unknown);
+            key_ty = merge_types(&key_ty, &inner_key);
+            continue;
+        }
+        let item_key = match item.key {
+            Some(key) => infer_expr(key, ctx).widen(),
+            None => Type::Primitive(PrimitiveType::Int),
+        };
+        key_ty = merge_types(&key_ty, &item_key);
+        element_ty = merge_types(&element_ty, &value_ty);
+    }
+
+    if keyed {
+        Type::Map {
+            key: Box::new(key_ty),
+            value: Box::new(element_ty),
+        }
+    } else {
+        Type::List(Box::new(element_ty))
+    }
+}
+
 pub fn literal_type(expr: &Expr) -> Option<Type> {
     match expr {
-        Expr::Integer { .. } => Some(Type::Primitive(PrimitiveType::Int)),
+        Expr::Integer { value, .. } => Some(
+            int_literal_value(value)
+                .map(|number| Type::Literal(LiteralValue::Int(number)))
+                .unwrap_or(Type::Primitive(PrimitiveType::Int)),
+        ),
         Expr::Float { .. } => Some(Type::Primitive(PrimitiveType::Float)),
-        Expr::Boolean { .. } => Some(Type::Primitive(PrimitiveType::Bool)),
-        Expr::String { .. } => Some(Type::Primitive(PrimitiveType::String)),
+        Expr::Boolean { value, .. } => Some(Type::Literal(LiteralValue::Bool(*value))),
+        Expr::String { value, .. } => Some(Type::Literal(LiteralValue::String(parse_string_key(
+            &String::from_utf8_lossy(value),
+        )))),
         Expr::Null { .. } => Some(Type::Primitive(PrimitiveType::Null)),
         _ => None,
     }
 }
 
+/// Decimal integer literal value; other radixes are typed as plain `int`.
+pub fn int_literal_value(raw: &[u8]) -> Option<i64> {
+    let text = std::str::from_utf8(raw).ok()?.replace('_', "");
+    if text.len() > 1 && text.starts_with('0') {
+        return None;
+    }
+    text.parse::<i64>().ok()
+}
+
 fn token_text(source: &[u8], span: crate::parser::span::Span) -> String {
     let start = span.start;
     let end = span.end.min(source.len());
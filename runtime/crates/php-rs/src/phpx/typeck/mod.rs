@@ -1,11 +1,14 @@
 mod check;
+mod elements;
 mod infer;
+mod stdlib;
 mod types;
 
 pub use check::{
-    ExternalFunctionSig, ExternalParamSig, ExternalTypeParamSig, TypeError, check_program,
-    check_program_with_path, check_program_with_path_and_externals, external_functions_from_stub,
-    format_type_errors,
+    ExternalFunctionSig, ExternalParamSig, ExternalTypeParamSig, ModuleSignatures, TypeError,
+    check_program, check_program_with_imports, check_program_with_path,
+    check_program_with_path_and_externals, external_functions_from_stub, format_type_errors,
+    module_signatures,
 };
 pub use types::{PrimitiveType, Type};
 
@@ -0,0 +1,23 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use bumpalo::Bump;
+
+use crate::parser::lexer::Lexer;
+use crate::parser::parser::{Parser, ParserMode};
+use crate::phpx::typeck::check::{ExternalFunctionSig, external_functions_from_stub};
+
+/// Generic signatures for the callback-taking PHP builtins, so closures
+/// passed to them are checked like arguments to any PHPX function.
+const STDLIB_STUB: &str = include_str!("stdlib.d.phpx");
+
+pub(crate) fn stdlib_functions() -> &'static HashMap<String, ExternalFunctionSig> {
+    static FUNCTIONS: OnceLock<HashMap<String, ExternalFunctionSig>> = OnceLock::new();
+    FUNCTIONS.get_or_init(|| {
+        let arena = Bump::new();
+        let mut parser =
+            Parser::new_with_mode(Lexer::new(STDLIB_STUB.as_bytes()), &arena, ParserMode::Phpx);
+        let program = parser.parse_program();
+        external_functions_from_stub(&program, STDLIB_STUB.as_bytes())
+    })
+}
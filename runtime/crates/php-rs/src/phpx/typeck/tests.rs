@@ -3,7 +3,11 @@ use std::path::Path;
 
 use crate::parser::lexer::Lexer;
 use crate::parser::parser::{Parser, ParserMode};
-use crate::phpx::typeck::{check_program, check_program_with_path};
+use crate::phpx::diagnostics::{Applicability, codes};
+use crate::phpx::typeck::{
+    ModuleSignatures, TypeError, check_program, check_program_with_imports,
+    check_program_with_path, module_signatures,
+};
 
 fn normalize_phpx_snippet(code: &str) -> &str {
     let trimmed = code.trim_start();
@@ -39,6 +43,42 @@ fn check(code: &str) -> Result<(), String> {
     })
 }
 
+fn check_errors(code: &str) -> Vec<TypeError> {
+    let code = normalize_phpx_snippet(code);
+    let arena = Bump::new();
+    let mut parser = Parser::new_with_mode(Lexer::new(code.as_bytes()), &arena, ParserMode::Phpx);
+    let program = parser.parse_program();
+    assert!(program.errors.is_empty(), "unexpected parse errors");
+    check_program(&program, code.as_bytes()).err().unwrap_or_default()
+}
+
+fn signatures(code: &str, imports: &ModuleSignatures, exports: &[&str]) -> ModuleSignatures {
+    let arena = Bump::new();
+    let mut parser = Parser::new_with_mode(Lexer::new(code.as_bytes()), &arena, ParserMode::Phpx);
+    let program = parser.parse_program();
+    assert!(program.errors.is_empty(), "unexpected parse errors");
+    let exports: Vec<(String, String)> = exports
+        .iter()
+        .map(|name| (name.to_string(), name.to_string()))
+        .collect();
+    module_signatures(&program, code.as_bytes(), imports, &exports)
+}
+
+fn check_with_imports(code: &str, imports: &ModuleSignatures) -> Result<(), String> {
+    let arena = Bump::new();
+    let mut parser = Parser::new_with_mode(Lexer::new(code.as_bytes()), &arena, ParserMode::Phpx);
+    let program = parser.parse_program();
+    assert!(program.errors.is_empty(), "unexpected parse errors");
+    let externals = Default::default();
+    check_program_with_imports(&program, code.as_bytes(), None, &externals, imports).map_err(
+        |errs| {
+            errs.iter()
+                .map(|err| format!("{}\n", err.message))
+                .collect()
+        },
+    )
+}
+
 fn check_with_path(code: &str, path: &str) -> Result<(), String> {
     let code = normalize_phpx_snippet(code);
     let arena = Bump::new();
@@ -733,3 +773,524 @@ fn closure_params_are_in_scope() {
     let code = "$f = function($x: int): int { return $x + 1; };";
     assert!(check(code).is_ok());
 }
+
+#[test]
+fn list_param_checks_literal_elements() {
+    let ok = "function f($xs: list<int>) {} f([1, 2, 3]);";
+    assert!(check(ok).is_ok());
+    let bad = "function f($xs: list<int>) {} f([1, \"a\"]);";
+    let err = check(bad).expect_err("expected element type mismatch");
+    assert!(
+        err.contains("expected list<int>, got [int, string]"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn tuple_return_type_is_checked_pairwise() {
+    let ok = "function pair(): [int, string] { return [1, \"a\"]; }";
+    assert!(check(ok).is_ok());
+    let bad = "function pair(): [int, string] { return [1, 2]; }";
+    assert!(check(bad).is_err());
+}
+
+#[test]
+fn indexing_yields_element_type() {
+    let list = "function f($xs: list<int>): string { return $xs[0]; }";
+    assert!(check(list).is_err());
+    let tuple = "function f($p: [int, string]): string { return $p[1]; }";
+    assert!(check(tuple).is_ok());
+}
+
+#[test]
+fn index_type_is_checked_against_key_type() {
+    let code = "function f($m: array<string, int>): int { return $m[1]; }";
+    let err = check(code).expect_err("expected index type mismatch");
+    assert!(err.contains("Index type mismatch"), "{}", err);
+}
+
+#[test]
+fn dim_assignment_checks_element_type() {
+    let code = "function f($xs: list<int>) { $xs[] = \"a\"; }";
+    assert!(check(code).is_err());
+}
+
+#[test]
+fn dim_assignment_grows_untyped_local() {
+    let code = "function f(): list<int> { $xs = []; $xs[] = 1; return $xs; }";
+    assert!(check(code).is_ok());
+}
+
+#[test]
+fn empty_array_literal_accepts_string_keys() {
+    let code = "$out = []; $out['k'] = 1;";
+    assert!(check(code).is_ok(), "{:?}", check(code));
+    let code = "function f(): array<string, int> { $out = []; $out['a'] = 1; return $out; }";
+    assert!(check(code).is_ok(), "{:?}", check(code));
+}
+
+#[test]
+fn dim_assignment_widens_untyped_key_type() {
+    let code = "$m = ['a' => 1]; $m[5] = 2; $x = $m['a'] + $m[5];";
+    assert!(check(code).is_ok(), "{:?}", check(code));
+    let code = "$xs = [1, 2]; $xs['k'] = 3; $x = $xs['k'] + $xs[0];";
+    assert!(check(code).is_ok(), "{:?}", check(code));
+    let code = "function f($m: array<string, int>): void { $m[5] = 2; }";
+    let err = check(code).expect_err("expected index type mismatch");
+    assert!(err.contains("Index type mismatch"), "{}", err);
+}
+
+#[test]
+fn foreach_binds_element_type() {
+    let code =
+        "function f($xs: list<string>): int { foreach ($xs as $x) { return $x; } return 0; }";
+    assert!(check(code).is_err());
+    let keyed = "function f($m: array<string, int>): int { foreach ($m as $k => $v) { return $v; } return 0; }";
+    assert!(check(keyed).is_ok());
+}
+
+#[test]
+fn foreach_over_primitive_is_rejected() {
+    let code = "$n = 3; foreach ($n as $x) {}";
+    assert!(check(code).is_err());
+}
+
+#[test]
+fn array_map_infers_callback_return_type() {
+    let ok =
+        "function f($xs: list<int>): list<string> { return array_map(fn($x) => \"n\" . $x, $xs); }";
+    assert!(check(ok).is_ok());
+    let bad =
+        "function f($xs: list<int>): list<string> { return array_map(fn($x) => $x * 2, $xs); }";
+    assert!(check(bad).is_err());
+}
+
+#[test]
+fn array_map_accepts_function_name_callbacks() {
+    let code = "$upper = array_map('strtoupper', ['a']);";
+    assert!(check(code).is_ok(), "{:?}", check(code));
+}
+
+#[test]
+fn array_filter_keeps_keys_and_array_values_reindexes() {
+    let filtered = "function f($xs: list<int>): array<int, int> { return array_filter($xs, fn($x) => $x > 1); }";
+    assert!(check(filtered).is_ok());
+    let not_list =
+        "function f($xs: list<int>): list<int> { return array_filter($xs, fn($x) => $x > 1); }";
+    assert!(check(not_list).is_err());
+    let values = "function f($xs: list<int>): list<int> { return array_values(array_filter($xs, fn($x) => $x > 1)); }";
+    assert!(check(values).is_ok());
+}
+
+#[test]
+fn function_type_checks_closure_arguments() {
+    let ok = "function apply($f: fn(int): string, $x: int): string { return $f($x); } apply(fn($n) => \"n\" . $n, 1);";
+    assert!(check(ok).is_ok());
+    let bad_return = "function apply($f: fn(int): string, $x: int): string { return $f($x); } apply(fn($n) => $n * 2, 1);";
+    let err = check(bad_return).expect_err("expected callback return mismatch");
+    assert!(
+        err.contains("expected fn(int): string, got fn(int): int"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn function_type_parameters_are_contravariant() {
+    let wider = "function apply($f: fn(int): string) {} apply(fn($n: int|string): string => \"x\");";
+    assert!(check(wider).is_ok());
+    let narrower = "function apply($f: fn(int|string): string) {} apply(fn($n: int): string => \"x\");";
+    assert!(check(narrower).is_err());
+}
+
+#[test]
+fn calling_function_typed_variable_checks_arguments() {
+    let code = "$f = fn($x: int): int => $x + 1; $f(\"a\");";
+    assert!(check(code).is_err());
+    let ok = "function run($f: fn(int): int): int { return $f(1); }";
+    assert!(check(ok).is_ok());
+}
+
+#[test]
+fn stdlib_callbacks_are_checked_against_element_types() {
+    let bad = "function f($xs: list<string>) { usort($xs, fn($a: int, $b: int): int => $a - $b); }";
+    assert!(check(bad).is_err());
+    let ok = "function f($xs: list<string>) { usort($xs, fn($a, $b) => strcmp($a, $b)); }";
+    assert!(check(ok).is_ok());
+}
+
+#[test]
+fn array_reduce_infers_accumulator_type() {
+    let ok = "function sum($xs: list<int>): int { return array_reduce($xs, fn($carry, $x) => $carry + $x, 0); }";
+    assert!(check(ok).is_ok());
+    let bad = "function sum($xs: list<int>): string { return array_reduce($xs, fn($carry, $x) => $carry + $x, 0); }";
+    assert!(check(bad).is_err());
+}
+
+#[test]
+fn component_callback_props_are_checked() {
+    let ok = "interface ButtonProps { $onPress: fn(string): void; } function Button($props: ButtonProps): string { return \"\"; } $v = <Button onPress={fn($label) => strlen($label)} />;";
+    assert!(check(ok).is_ok());
+    let bad = "interface ButtonProps { $onPress: fn(string): void; } function Button($props: ButtonProps): string { return \"\"; } $v = <Button onPress={fn($n: int) => $n} />;";
+    let err = check(bad).expect_err("expected callback prop mismatch");
+    assert!(err.contains("Prop 'onPress'"), "{}", err);
+}
+
+#[test]
+fn void_return_accepts_bare_return() {
+    let code = "function f(): void { return; }";
+    assert!(check(code).is_ok());
+}
+
+#[test]
+fn literal_union_params_accept_only_members() {
+    let ok = "function send($method: \"get\"|\"post\") {} send(\"get\");";
+    assert!(check(ok).is_ok());
+    let bad = "function send($method: \"get\"|\"post\") {} send(\"put\");";
+    let err = check(bad).expect_err("expected literal mismatch");
+    assert!(
+        err.contains("expected \"get\" | \"post\", got \"put\""),
+        "{}",
+        err
+    );
+    let wide = "function send($method: \"get\"|\"post\") {} function f($m: string) { send($m); }";
+    assert!(check(wide).is_err());
+}
+
+#[test]
+fn literal_types_widen_for_untyped_locals() {
+    let code = "$method = \"get\"; $method = \"put\"; $n = 1; $n = $n + 2;";
+    assert!(check(code).is_ok());
+}
+
+#[test]
+fn int_and_bool_literal_types() {
+    let ok = "function retry($times: 1|2|3, $loud: true) {} retry(2, true);";
+    assert!(check(ok).is_ok());
+    let bad = "function retry($times: 1|2|3, $loud: true) {} retry(4, false);";
+    assert!(check(bad).is_err());
+    let bool_ok = "function f($flag: true|false) {} function g($b: bool) { f($b); }";
+    assert!(check(bool_ok).is_ok());
+}
+
+#[test]
+fn strict_equality_narrows_literal_unions() {
+    let ok = "function f($m: \"get\"|\"post\"): \"get\" { if ($m === \"get\") { return $m; } return \"get\"; }";
+    assert!(check(ok).is_ok());
+    let negated = "function f($m: \"get\"|\"post\"): \"post\" { if ($m !== \"get\") { return $m; } return \"post\"; }";
+    assert!(check(negated).is_ok());
+    let bad = "function f($m: \"get\"|\"post\"): \"post\" { return $m; }";
+    assert!(check(bad).is_err());
+}
+
+#[test]
+fn discriminated_unions_narrow_on_kind_field() {
+    let shape =
+        "type Shape = { kind: \"circle\", radius: float } | { kind: \"square\", size: float };";
+    let ok = format!(
+        "{shape} function area($s: Shape): float {{ if ($s.kind === \"circle\") {{ return $s.radius * $s.radius; }} return 0.0; }}"
+    );
+    assert!(check(&ok).is_ok());
+    let via_match = format!(
+        "{shape} function area($s: Shape): float {{ return match ($s.kind) {{ \"circle\" => $s.radius, \"square\" => $s.size }}; }}"
+    );
+    assert!(check(&via_match).is_ok());
+    let bad = format!("{shape} function radius($s: Shape): float {{ return $s.radius; }}");
+    let err = check(&bad).expect_err("expected un-narrowed field access to fail");
+    assert!(err.contains("narrow the union first"), "{}", err);
+}
+
+#[test]
+fn match_on_literal_union_must_be_exhaustive() {
+    let bad = "function f($m: \"get\"|\"post\"): int { return match ($m) { \"get\" => 1 }; }";
+    let err = check(bad).expect_err("expected exhaustiveness error");
+    assert!(err.contains("missing \"post\""), "{}", err);
+    let ok = "function f($m: \"get\"|\"post\"): int { return match ($m) { \"get\" => 1, \"post\" => 2 }; }";
+    assert!(check(ok).is_ok());
+    let with_default = "function f($m: \"get\"|\"post\"): int { return match ($m) { \"get\" => 1, default => 2 }; }";
+    assert!(check(with_default).is_ok());
+    let unknown_arm = "function f($m: \"get\"|\"post\"): int { return match ($m) { \"get\" => 1, \"post\" => 2, \"put\" => 3 }; }";
+    assert!(check(unknown_arm).is_err());
+}
+
+#[test]
+fn keyof_derives_literal_union_from_fields() {
+    let ok = "type User = { name: string, age: int }; function pick($key: keyof User) {} pick(\"name\");";
+    assert!(check(ok).is_ok());
+    let bad = "type User = { name: string, age: int }; function pick($key: keyof User) {} pick(\"email\");";
+    assert!(check(bad).is_err());
+    let on_struct = "struct Point { $x: int = 0; $y: int = 0; } type Axis = keyof Point; function axis($a: Axis) {} axis(\"z\");";
+    assert!(check(on_struct).is_err());
+    let not_object = "type Id = int; function f($k: keyof Id) {}";
+    let err = check(not_object).expect_err("expected keyof on a non-object to fail");
+    assert!(err.contains("keyof expects an object shape"), "{}", err);
+}
+
+#[test]
+fn type_errors_carry_stable_codes() {
+    let errors = check_errors("<?php function f(): int { return \"x\"; }");
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].code, codes::TYPE_MISMATCH);
+    let errors = check_errors("<?php function f($name: string): string { return $nam; }");
+    assert_eq!(errors[0].code, codes::UNKNOWN_NAME);
+    let errors = check_errors("<?php function f<T: int>($x: T) {} f(\"nope\");");
+    assert_eq!(errors[0].code, codes::UNSATISFIED_CONSTRAINT);
+    let errors = check_errors("<?php function f($x: ?int) {}");
+    assert_eq!(errors[0].code, codes::NULL_NOT_ALLOWED);
+}
+
+#[test]
+fn unknown_variable_suggests_closest_name() {
+    let errors = check_errors("<?php function f($name: string): string { return $nam; }");
+    let suggestion = errors[0].suggestions.first().expect("expected a suggestion");
+    assert_eq!(suggestion.replacement, "$name");
+    assert_eq!(suggestion.applicability, Applicability::MaybeIncorrect);
+}
+
+#[test]
+fn payloadless_enum_call_has_machine_applicable_fix() {
+    let code = "<?php enum Color { case Red; } $c = Color::Red();";
+    let errors = check_errors(code);
+    let suggestion = errors[0].suggestions.first().expect("expected a suggestion");
+    assert_eq!(suggestion.replacement, "Color::Red");
+    assert_eq!(suggestion.applicability, Applicability::MachineApplicable);
+    assert_eq!(&code[5..][suggestion.span.start..suggestion.span.end], "Color::Red()");
+}
+
+#[test]
+fn duplicate_type_alias_points_at_first_definition() {
+    let errors = check_errors("<?php type Id = int; type Id = string;");
+    let error = errors
+        .iter()
+        .find(|err| err.message.contains("Duplicate type alias"))
+        .expect("expected duplicate alias error");
+    assert_eq!(error.code, codes::TYPE_ALIAS);
+    assert_eq!(error.related.len(), 1);
+    assert!(error.related[0].span.start < error.span.start);
+}
+
+#[test]
+fn intrinsic_element_schema_is_bundled() {
+    let input = super::elements::intrinsic_element("input").expect("input schema");
+    for attr in ["type", "value", "list", "class", "onInput"] {
+        assert!(input.contains_key(attr), "missing input attribute {}", attr);
+    }
+    let label = super::elements::intrinsic_element("label").expect("label schema");
+    assert!(label.contains_key("for"));
+    assert!(super::elements::intrinsic_element("GlobalAttributes").is_none());
+    assert!(super::elements::intrinsic_element("EventHandler").is_none());
+}
+
+#[test]
+fn component_prop_values_are_checked_against_props_type() {
+    let props = "interface CounterProps { $count: int; $label: string = \"\"; } function Counter($props: CounterProps): string { return \"\"; }";
+    assert!(check(&format!("{} $v = <Counter count={{1}} />;", props)).is_ok());
+    assert!(
+        check(&format!(
+            "{} $v = <Counter count={{1}} label=\"Clicks\" key=\"a\" client:load />;",
+            props
+        ))
+        .is_ok()
+    );
+    let err = check(&format!("{} $v = <Counter count=\"one\" />;", props))
+        .expect_err("expected prop type mismatch");
+    assert!(
+        err.contains("Prop 'count' for component 'Counter' expects int"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn component_children_follow_children_prop() {
+    let leaf = "interface BadgeProps { $text: string; } function Badge($props: BadgeProps): string { return \"\"; }";
+    let err = check(&format!(
+        "{} $v = <Badge text=\"x\"><span /></Badge>;",
+        leaf
+    ))
+    .expect_err("expected children to be rejected");
+    assert!(err.contains("does not accept children"), "{}", err);
+
+    let card = "interface CardProps { $children: list<VNode>; } function Card($props: CardProps): string { return \"\"; }";
+    assert!(check(&format!("{} $v = <Card><p /><p /></Card>;", card)).is_ok());
+    let err = check(&format!("{} $v = <Card>plain text</Card>;", card))
+        .expect_err("expected text child to be rejected");
+    assert!(
+        err.contains("Children of component 'Card' must be VNode, got string"),
+        "{}",
+        err
+    );
+    let err = check(&format!("{} $v = <Card />;", card)).expect_err("expected missing children");
+    assert!(err.contains("Missing required prop 'children'"), "{}", err);
+
+    let layout = "interface LayoutProps { $children: [VNode, string]; } function Layout($props: LayoutProps): string { return \"\"; }";
+    assert!(check(&format!("{} $v = <Layout><main />footer</Layout>;", layout)).is_ok());
+    let err = check(&format!("{} $v = <Layout><main /></Layout>;", layout))
+        .expect_err("expected slot count mismatch");
+    assert!(err.contains("expects 2 children, got 1"), "{}", err);
+}
+
+#[test]
+fn intrinsic_attributes_are_checked_against_element_schema() {
+    assert!(
+        check(
+            "$v = <input type=\"text\" value={3} disabled dataTestId=\"q\" ariaLabel=\"Query\" />;"
+        )
+        .is_ok()
+    );
+    assert!(check("$v = <a href=\"/\" class={false}>Home</a>;").is_ok());
+
+    let errors = check_errors("$v = <a hre=\"/\">Home</a>;");
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert!(
+        errors[0]
+            .message
+            .contains("Unknown attribute 'hre' on <a>; did you mean 'href'?")
+    );
+    assert_eq!(errors[0].code, codes::JSX_PROP);
+    assert_eq!(errors[0].suggestions[0].replacement, "href");
+
+    let err = check("$v = <input disabled=\"yes\" />;").expect_err("expected attribute mismatch");
+    assert!(
+        err.contains("Attribute 'disabled' on <input> expects bool"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn intrinsic_event_handlers_receive_events() {
+    assert!(
+        check("$v = <button onClick={fn($event) => $event.preventDefault()}>Go</button>;").is_ok()
+    );
+    assert!(check("$v = <button onClick={fn() => 1}>Go</button>;").is_ok());
+    let err = check("$v = <button onClick={fn($n: int) => $n}>Go</button>;")
+        .expect_err("expected handler mismatch");
+    assert!(err.contains("Attribute 'onClick' on <button>"), "{}", err);
+}
+
+const USERS_MODULE: &str = r#"
+struct User {
+    $id: int;
+    $name: string;
+}
+
+interface Named {
+    $name: string;
+}
+
+type UserId = int;
+
+function find_user($id: UserId): User {
+    return User { $id: $id, $name: "ada" };
+}
+
+function first<T>($items: array<T>): T {
+    return $items[0];
+}
+
+function greet($who: Named): string {
+    return $who.name;
+}
+
+function internal_only(): int {
+    return 1;
+}
+"#;
+
+fn users_exports() -> ModuleSignatures {
+    signatures(
+        USERS_MODULE,
+        &ModuleSignatures::default(),
+        &["User", "Named", "UserId", "find_user", "first", "greet"],
+    )
+}
+
+#[test]
+fn cross_module_calls_are_checked_against_exported_signatures() {
+    let mut imports = ModuleSignatures::default();
+    let users = users_exports();
+    for name in ["User", "UserId", "find_user", "first", "greet"] {
+        assert!(imports.bind(name, name, &users), "{}", name);
+    }
+    assert!(!imports.bind("internal_only", "internal_only", &users));
+
+    assert!(
+        check_with_imports(
+            "function show($id: UserId): string { $u = find_user($id); return $u.name . greet($u); }",
+            &imports,
+        )
+        .is_ok()
+    );
+
+    let err = check_with_imports("$u = find_user(\"1\");", &imports)
+        .expect_err("expected argument mismatch");
+    assert!(
+        err.contains("Argument 1 type mismatch: expected int"),
+        "{}",
+        err
+    );
+
+    let err = check_with_imports("function f(): int { return find_user(1).name; }", &imports)
+        .expect_err("expected field type mismatch");
+    assert!(
+        err.contains("Return type mismatch: expected int, got string"),
+        "{}",
+        err
+    );
+
+    let err = check_with_imports("function f(): string { return first([1, 2]); }", &imports)
+        .expect_err("expected generic instantiation mismatch");
+    assert!(
+        err.contains("Return type mismatch: expected string, got int"),
+        "{}",
+        err
+    );
+    assert!(check_with_imports("function f(): int { return first([1, 2]); }", &imports).is_ok());
+
+    let err = check_with_imports("function f($u: User): int { return $u.nope; }", &imports)
+        .expect_err("expected unknown field");
+    assert!(err.contains("Unknown struct field 'User::nope'"), "{}", err);
+    let err = check_with_imports("function f(): string { return greet(1); }", &imports)
+        .expect_err("expected interface mismatch");
+    assert!(err.contains("expected interface Named"), "{}", err);
+}
+
+#[test]
+fn re_exported_and_renamed_imports_keep_their_signatures() {
+    let users = users_exports();
+    let mut barrel_imports = ModuleSignatures::default();
+    assert!(barrel_imports.bind("find_user", "find_user", &users));
+    assert!(barrel_imports.bind("User", "User", &users));
+    let mut barrel = signatures("", &barrel_imports, &[]);
+    assert!(barrel.bind("lookup", "find_user", &barrel_imports));
+    assert!(barrel.bind("Account", "User", &barrel_imports));
+
+    let mut imports = ModuleSignatures::default();
+    assert!(imports.bind("lookup", "lookup", &barrel));
+    assert!(imports.bind("Account", "Account", &barrel));
+    assert!(
+        check_with_imports(
+            "function f(): int { $a = lookup(7); return $a.id; }",
+            &imports
+        )
+        .is_ok()
+    );
+    let err = check_with_imports("function f(): Account { return lookup(true); }", &imports)
+        .expect_err("expected argument mismatch through re-export");
+    assert!(
+        err.contains("Argument 1 type mismatch: expected int"),
+        "{}",
+        err
+    );
+
+    // Local declarations shadow imported ones.
+    assert!(
+        check_with_imports(
+            "function lookup($key: string): string { return $key; } $s = lookup(\"k\");",
+            &imports,
+        )
+        .is_ok()
+    );
+}
@@ -10,12 +10,41 @@ pub enum PrimitiveType {
     Null,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LiteralValue {
+    String(String),
+    Int(i64),
+    Bool(bool),
+}
+
+impl LiteralValue {
+    pub fn primitive(&self) -> PrimitiveType {
+        match self {
+            LiteralValue::String(_) => PrimitiveType::String,
+            LiteralValue::Int(_) => PrimitiveType::Int,
+            LiteralValue::Bool(_) => PrimitiveType::Bool,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Type {
     Unknown,
     Mixed,
     Primitive(PrimitiveType),
+    Literal(LiteralValue),
     Array,
+    List(Box<Type>),
+    Map {
+        key: Box<Type>,
+        value: Box<Type>,
+    },
+    Tuple(Vec<Type>),
+    Function {
+        params: Vec<Type>,
+        ret: Box<Type>,
+    },
+    Void,
     Object,
     VNode,
     ObjectShape(BTreeMap<String, ObjectField>),
@@ -53,7 +82,29 @@ impl Type {
                 PrimitiveType::String => "string".to_string(),
                 PrimitiveType::Null => "null".to_string(),
             },
+            Type::Literal(value) => match value {
+                LiteralValue::String(text) => format!("{:?}", text),
+                LiteralValue::Int(number) => number.to_string(),
+                LiteralValue::Bool(flag) => flag.to_string(),
+            },
             Type::Array => "array".to_string(),
+            Type::List(element) => format!("list<{}>", element.name()),
+            Type::Map { key, value } => {
+                if **key == Type::array_key() {
+                    format!("array<{}>", value.name())
+                } else {
+                    format!("array<{}, {}>", key.name(), value.name())
+                }
+            }
+            Type::Tuple(items) => {
+                let rendered = items.iter().map(|t| t.name()).collect::<Vec<_>>();
+                format!("[{}]", rendered.join(", "))
+            }
+            Type::Function { params, ret } => {
+                let rendered = params.iter().map(|t| t.name()).collect::<Vec<_>>();
+                format!("fn({}): {}", rendered.join(", "), ret.name())
+            }
+            Type::Void => "void".to_string(),
             Type::Object => "object".to_string(),
             Type::VNode => "VNode".to_string(),
             Type::ObjectShape(fields) => {
@@ -100,6 +151,56 @@ impl Type {
             }
         }
     }
+
+    /// The key type of an `array<V>` annotation without an explicit key.
+    pub fn array_key() -> Type {
+        Type::Union(vec![
+            Type::Primitive(PrimitiveType::Int),
+            Type::Primitive(PrimitiveType::String),
+        ])
+    }
+
+    /// Element type yielded by indexing or iterating an array-like type.
+    pub fn element_type(&self) -> Option<Type> {
+        match self {
+            Type::Array => Some(Type::Unknown),
+            Type::List(element) => Some((**element).clone()),
+            Type::Map { value, .. } => Some((**value).clone()),
+            Type::Tuple(items) => Some(
+                items
+                    .iter()
+                    .fold(Type::Unknown, |acc, item| merge_types(&acc, item)),
+            ),
+            _ => None,
+        }
+    }
+
+    /// Drops literal precision, e.g. when a literal initialises an untyped local.
+    pub fn widen(&self) -> Type {
+        match self {
+            Type::Literal(value) => Type::Primitive(value.primitive()),
+            Type::Union(types) => types
+                .iter()
+                .fold(Type::Unknown, |acc, item| merge_types(&acc, &item.widen())),
+            Type::List(element) => Type::List(Box::new(element.widen())),
+            Type::Map { key, value } => Type::Map {
+                key: Box::new(key.widen()),
+                value: Box::new(value.widen()),
+            },
+            Type::Tuple(items) => Type::Tuple(items.iter().map(Type::widen).collect()),
+            _ => self.clone(),
+        }
+    }
+
+    /// Key type of an array-like type; lists and tuples are int-indexed.
+    pub fn key_type(&self) -> Option<Type> {
+        match self {
+            Type::Array => Some(Type::Unknown),
+            Type::List(_) | Type::Tuple(_) => Some(Type::Primitive(PrimitiveType::Int)),
+            Type::Map { key, .. } => Some((**key).clone()),
+            _ => None,
+        }
+    }
 }
 
 impl fmt::Display for Type {
@@ -126,12 +227,54 @@ pub fn merge_types(left: &Type, right: &Type) -> Type {
         | (Type::Primitive(PrimitiveType::Float), Type::Primitive(PrimitiveType::Int)) => {
             return Type::Primitive(PrimitiveType::Float);
         }
+        (Type::Literal(value), Type::Primitive(prim))
+        | (Type::Primitive(prim), Type::Literal(value))
+            if value.primitive() == *prim =>
+        {
+            return Type::Primitive(prim.clone());
+        }
+        (Type::Tuple(a), Type::Tuple(b)) if a.len() == b.len() => {
+            return Type::Tuple(
+                a.iter()
+                    .zip(b.iter())
+                    .map(|(a, b)| merge_types(a, b))
+                    .collect(),
+            );
+        }
+        (Type::List(_) | Type::Tuple(_), Type::List(_) | Type::Tuple(_)) => {
+            let a = left.element_type().unwrap_or(Type::Unknown);
+            let b = right.element_type().unwrap_or(Type::Unknown);
+            return Type::List(Box::new(merge_types(&a, &b)));
+        }
+        (
+            Type::Map {
+                key: key_a,
+                value: value_a,
+            },
+            Type::Map {
+                key: key_b,
+                value: value_b,
+            },
+        ) => {
+            return Type::Map {
+                key: Box::new(merge_types(key_a, key_b)),
+                value: Box::new(merge_types(value_a, value_b)),
+            };
+        }
         _ => {}
     }
     let mut out = Vec::new();
     collect_union_types(left, &mut out);
     collect_union_types(right, &mut out);
     dedupe_types(&mut out);
+    let primitives = out
+        .iter()
+        .filter_map(|ty| match ty {
+            Type::Primitive(prim) => Some(prim.clone()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    out.retain(|ty| !matches!(ty, Type::Literal(value) if primitives.contains(&value.primitive())));
     if out.len() == 1 {
         out.remove(0)
     } else {
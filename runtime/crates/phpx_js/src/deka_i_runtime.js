@@ -11,6 +11,8 @@ const __deka_i = (() => {
         return typeof value === 'number' ? ok(value) : fail('expected number')
       case 'boolean':
         return typeof value === 'boolean' ? ok(value) : fail('expected boolean')
+      case 'literal':
+        return value === schema.value ? ok(value) : fail('expected ' + JSON.stringify(schema.value))
       case 'optional':
         return value == null ? ok(value) : parseSchema(schema.inner, value)
       case 'array': {
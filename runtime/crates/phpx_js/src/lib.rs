@@ -1,19 +1,39 @@
 use bumpalo::Bump;
 use modules_php::compiler_api::{compile_phpx, compile_phpx_internal};
-use modules_php::validation::format_multiple_errors;
+use modules_php::validation::diagnostics::{ErrorFormat, format_diagnostics};
+use php_rs::parser::ast::visitor::{Visitor, walk_expr, walk_stmt};
 use php_rs::parser::ast::{
-    BinaryOp, ClassKind, ClassMember, Expr, ExprId, JsxChild, ObjectKey, Program, Stmt, StmtId,
-    Type as AstType, UnaryOp,
+    BinaryOp, ClassKind, ClassMember, Expr, ExprId, JsxChild, Name, ObjectKey, Param, Program,
+    Stmt, StmtId, TraitAdaptation, Type as AstType, UnaryOp, UseKind,
 };
+use php_rs::parser::line_index::LineIndex;
 use std::collections::{BTreeSet, HashMap, HashSet};
 use std::fs;
 use std::path::Path;
 
+mod source_map;
+
+pub use source_map::{Mapping, SourceMap};
+
+/// Identifies the emitter output format for compile caches. Bump the suffix
+/// whenever lowering changes so previously cached JS is recompiled.
+pub const COMPILER_VERSION: &str = concat!(env!("CARGO_PKG_VERSION"), "+emit.7");
+
 pub fn compile_phpx_source_to_js(
     source: &str,
     input: &str,
     meta: SourceModuleMeta,
 ) -> Result<String, String> {
+    compile_phpx_source_to_js_with_source_map(source, input, meta).map(|(js, _)| js)
+}
+
+/// Like `compile_phpx_source_to_js`, also returning a statement-level source map
+/// back to `input`. Scaffold output has no map.
+pub fn compile_phpx_source_to_js_with_source_map(
+    source: &str,
+    input: &str,
+    meta: SourceModuleMeta,
+) -> Result<(String, Option<SourceMap>), String> {
     let arena = Bump::new();
     let path = Path::new(input);
     let result = if is_internal_phpx_path(path) {
@@ -22,20 +42,29 @@ pub fn compile_phpx_source_to_js(
         compile_phpx(source, input, &arena)
     };
     if !result.errors.is_empty() {
-        let formatted = format_multiple_errors(source, input, &result.errors, &result.warnings);
+        let formatted = format_diagnostics(
+            source,
+            input,
+            &result.errors,
+            &result.warnings,
+            ErrorFormat::from_env(),
+        );
         return Err(formatted);
     }
 
-    let js = if let Some(program) = result.ast {
-        match emit_js_from_ast(&program, source.as_bytes(), meta) {
-            Ok(emitted) => emitted,
-            Err(reason) => emit_js_scaffold_with_reason(source, input, &reason),
+    let output = if let Some(program) = result.ast {
+        match emit_js_from_ast_with_source_map(&program, source, meta, input) {
+            Ok((js, map)) => (js, Some(map)),
+            Err(reason) => (emit_js_scaffold_with_reason(source, input, &reason), None),
         }
     } else {
-        emit_js_scaffold_with_reason(source, input, "no AST available after validation")
+        (
+            emit_js_scaffold_with_reason(source, input, "no AST available after validation"),
+            None,
+        )
     };
 
-    Ok(js)
+    Ok(output)
 }
 
 fn is_internal_phpx_path(path: &Path) -> bool {
@@ -44,6 +73,26 @@ fn is_internal_phpx_path(path: &Path) -> bool {
         || normalized.contains("/php_modules/")
 }
 
+/// Installed packages live under `php_modules/@scope/name`; the stdlib and
+/// project modules are not attributed to a package.
+fn package_name_from_path(input: &str) -> Option<String> {
+    let normalized = input.replace('\\', "/");
+    let rest = match normalized.strip_prefix("php_modules/") {
+        Some(rest) => rest,
+        None => normalized.split_once("/php_modules/")?.1,
+    };
+    let mut parts = rest.split('/');
+    let scope = parts
+        .next()
+        .filter(|scope| scope.len() > 1 && scope.starts_with('@'))?;
+    let name = parts.next().filter(|name| !name.is_empty())?;
+    let name = match parts.next() {
+        Some(_) => name,
+        None => name.strip_suffix(".phpx")?,
+    };
+    Some(format!("{}/{}", scope, name))
+}
+
 pub fn emit_js_from_ast(
     program: &Program<'_>,
     source: &[u8],
@@ -54,6 +103,26 @@ pub fn emit_js_from_ast(
     Ok(emitter.finish())
 }
 
+/// Like `emit_js_from_ast`, also returning a source map from the emitted
+/// statements back to `input`, with `source` embedded as its content.
+pub fn emit_js_from_ast_with_source_map(
+    program: &Program<'_>,
+    source: &str,
+    meta: SourceModuleMeta,
+    input: &str,
+) -> Result<(String, SourceMap), String> {
+    let mut emitter = JsSubsetEmitter::new(source.as_bytes(), meta);
+    emitter.package = package_name_from_path(input);
+    emitter.emit_program(program)?;
+    let (js, mappings) = emitter.finish_with_mappings();
+    let mut map = SourceMap::new(format!("{}.js", input), input).with_source_content(source);
+    let index = LineIndex::new(source.as_bytes());
+    for (line, column, offset) in mappings {
+        map.add(line, column, &index, offset);
+    }
+    Ok((js, map))
+}
+
 pub fn emit_js_scaffold_with_reason(source: &str, file_path: &str, reason: &str) -> String {
     let escaped = serde_json::to_string(source).unwrap_or_else(|_| "\"\"".to_string());
     let escaped_path =
@@ -344,6 +413,36 @@ struct EnumCaseDef {
     params: Vec<String>,
 }
 
+/// The class whose body is being lowered; resolves `self`/`static`/`parent`
+/// and private (`#`) members.
+struct ClassContext {
+    name: String,
+    local: String,
+    parent: Option<String>,
+    private_members: HashSet<String>,
+    private_statics: HashSet<String>,
+    instance_methods: HashSet<String>,
+    /// Parameters of the current method typed as this class, whose private
+    /// members are reachable as in PHP.
+    own_params: HashSet<String>,
+    in_static: bool,
+}
+
+/// `insteadof`/`as` rules from one class's `use Trait` members, keyed by
+/// lowercased names.
+#[derive(Default)]
+struct TraitRules {
+    excluded: HashSet<(String, String)>,
+    aliases: Vec<TraitAlias>,
+}
+
+struct TraitAlias {
+    trait_name: Option<String>,
+    method: String,
+    alias: Option<String>,
+    private: Option<bool>,
+}
+
 enum AssignmentTarget {
     Direct(String),
     Append(String),
@@ -351,6 +450,13 @@ enum AssignmentTarget {
 
 struct JsSubsetEmitter<'a> {
     source: &'a [u8],
+    namespace: String,
+    use_aliases: HashMap<String, String>,
+    /// Lowercased fully-qualified class/interface name -> local JS binding.
+    local_classes: HashMap<String, String>,
+    /// Traits are copied into using classes, so their members are kept.
+    traits: HashMap<String, &'a [ClassMember<'a>]>,
+    class_ctx: Option<ClassContext>,
     body: String,
     main_body: String,
     uses_jsx_runtime: bool,
@@ -361,12 +467,31 @@ struct JsSubsetEmitter<'a> {
     struct_names: HashSet<String>,
     struct_methods: HashMap<String, Vec<(String, String)>>,
     enum_cases: HashMap<String, Vec<EnumCaseDef>>,
+    /// Source offsets of lowered statements. Each statement leaves a
+    /// `\0<index>\0` marker in the output, resolved (and stripped) in
+    /// `finish_with_mappings` once method and closure bodies are spliced in.
+    stmt_marks: Vec<usize>,
+    /// Installed package this module belongs to. Its exports, top-level code
+    /// and returned closures run inside the package's security scope.
+    package: Option<String>,
+    /// Functions declared at the module top level.
+    top_level_functions: HashSet<String>,
+    /// Lowercased names of top-level classes and interfaces already in `body`.
+    emitted_classes: HashSet<String>,
+    /// Top-level classes held back until the local classes they extend or
+    /// implement are emitted: ES classes are not hoisted like PHP's.
+    pending_classes: Vec<(String, HashSet<String>, String)>,
 }
 
 impl<'a> JsSubsetEmitter<'a> {
     fn new(source: &'a [u8], meta: SourceModuleMeta) -> Self {
         Self {
             source,
+            namespace: String::new(),
+            use_aliases: HashMap::new(),
+            local_classes: HashMap::new(),
+            traits: HashMap::new(),
+            class_ctx: None,
             body: String::new(),
             main_body: String::new(),
             uses_jsx_runtime: false,
@@ -377,10 +502,21 @@ impl<'a> JsSubsetEmitter<'a> {
             struct_names: HashSet::new(),
             struct_methods: HashMap::new(),
             enum_cases: HashMap::new(),
+            stmt_marks: Vec::new(),
+            package: None,
+            top_level_functions: HashSet::new(),
+            emitted_classes: HashSet::new(),
+            pending_classes: Vec::new(),
         }
     }
 
     fn finish(self) -> String {
+        self.finish_with_mappings().0
+    }
+
+    /// Assemble the module and resolve statement marks to generated
+    /// `(line, column, source offset)` triples.
+    fn finish_with_mappings(self) -> (String, Vec<(usize, usize, usize)>) {
         let mut out = String::new();
         out.push_str("// Generated by deka build. Do not edit manually.\n");
         out.push_str("// Target semantics: JavaScript runtime semantics.\n");
@@ -388,13 +524,45 @@ impl<'a> JsSubsetEmitter<'a> {
         out.push_str("export const phpxTargetSemantics = \"js\";\n\n");
         out.push_str("if (!globalThis.panic) { globalThis.panic = (msg) => { throw new Error(String(msg)); }; }\n");
         out.push_str("if (!globalThis.function_exists) { globalThis.function_exists = (name) => typeof globalThis[name] === 'function'; }\n");
-        out.push_str("if (!globalThis.class_exists) { globalThis.class_exists = (name) => typeof globalThis[name] === 'function' || typeof globalThis[name] === 'object'; }\n");
+        out.push_str(r#"if (!globalThis.class_exists) { globalThis.class_exists = (name) => Boolean(globalThis.__phpx_classes && globalThis.__phpx_classes.has(String(name).replace(/^\\+/, '').toLowerCase())) || typeof globalThis[name] === 'function' || typeof globalThis[name] === 'object'; }"#);
+        out.push('\n');
         out.push_str("if (!globalThis.class_alias) { globalThis.class_alias = () => false; }\n\n");
         out.push_str("if (!globalThis.defined) { globalThis.defined = (name) => Object.prototype.hasOwnProperty.call(globalThis, String(name)); }\n\n");
+        out.push_str(
+            "if (!globalThis.__phpx_classes) { globalThis.__phpx_classes = new Map(); }\n",
+        );
+        out.push_str("if (!globalThis.__phpx_register_class) { globalThis.__phpx_register_class = (name, cls) => { Object.defineProperty(cls, '__phpx_name', { value: name }); globalThis.__phpx_classes.set(name.toLowerCase(), cls); const skip = ['length', 'name', 'prototype', '__phpx_name', '__phpx_interfaces']; const copy = (ifaces) => { for (const iface of ifaces || []) { for (const key of Object.getOwnPropertyNames(iface)) { if (!skip.includes(key) && !(key in cls)) Object.defineProperty(cls, key, Object.getOwnPropertyDescriptor(iface, key)); } copy(iface.__phpx_interfaces); } }; copy(cls.__phpx_interfaces); return cls; }; }\n");
+        out.push_str(r#"if (!globalThis.__phpx_class) { globalThis.__phpx_class = (name) => { const key = String(name).replace(/^\\+/, ''); const found = globalThis.__phpx_classes.get(key.toLowerCase()) ?? globalThis[key.split('\\').pop()]; if (found === undefined) throw new Error(`Class "${key}" not found`); return found; }; }"#);
+        out.push('\n');
+        out.push_str("if (!globalThis.__phpx_implements) { globalThis.__phpx_implements = (value, iface) => { const seen = new Set(); const visit = (list) => (list || []).some((item) => item === iface || (!seen.has(item) && (seen.add(item), visit(item.__phpx_interfaces)))); for (let ctor = value === null || value === undefined ? null : value.constructor; typeof ctor === 'function'; ctor = Object.getPrototypeOf(ctor)) { if (Object.prototype.hasOwnProperty.call(ctor, '__phpx_interfaces') && visit(ctor.__phpx_interfaces)) return true; } return false; }; }\n");
         out.push_str("if (!globalThis.__phpx_is_struct) { globalThis.__phpx_is_struct = (value, name) => Boolean(value && typeof value === 'object' && value.__struct === name); }\n\n");
         out.push_str("if (!globalThis.__phpx_func_num_args) { globalThis.__phpx_func_num_args = (args) => args.length; }\n");
         out.push_str("if (!globalThis.__phpx_func_get_args) { globalThis.__phpx_func_get_args = (args) => Array.prototype.slice.call(args); }\n");
         out.push_str("if (!globalThis.__phpx_func_get_arg) { globalThis.__phpx_func_get_arg = (args, idx) => (idx >= 0 && idx < args.length ? args[idx] : null); }\n\n");
+        out.push_str(r#"if (!globalThis.__phpx_array_key) { globalThis.__phpx_array_key = (key) => { if (typeof key === 'boolean') return key ? 1 : 0; if (key === null || key === undefined) return ''; if (typeof key === 'number') return Math.trunc(key); const str = String(key); return /^(0|-?[1-9][0-9]*)$/.test(str) && Number.isSafeInteger(Number(str)) ? Number(str) : str; }; }"#);
+        out.push('\n');
+        // Keyed arrays keep PHP insertion order. Plain objects already do for
+        // string keys but list integer-like keys first, so only arrays with
+        // such keys go through an ordered proxy (which structuredClone and
+        // postMessage cannot copy).
+        out.push_str(r#"if (!globalThis.__phpx_assoc) { globalThis.__phpx_assoc = (entries) => { const list = Array.isArray(entries) ? entries : Object.entries(entries); if (!list.some(([key]) => typeof globalThis.__phpx_array_key(key) === 'number')) return Object.fromEntries(list); const order = new Set(); const array = new Proxy({}, { defineProperty(target, key, desc) { if (!Reflect.defineProperty(target, key, desc)) return false; if (typeof key === 'string') order.add(key); return true; }, deleteProperty(target, key) { if (!Reflect.deleteProperty(target, key)) return false; order.delete(key); return true; }, ownKeys(target) { return [...order, ...Object.getOwnPropertySymbols(target)]; } }); for (const [key, value] of list) array[key] = value; return array; }; }"#);
+        out.push('\n');
+        out.push_str(r#"if (!globalThis.__phpx_array) { globalThis.__phpx_array = (items) => { const keys = []; const values = new Map(); let next = null; const set = (key, value) => { if (!values.has(key)) keys.push(key); values.set(key, value); if (typeof key === 'number') next = next === null ? key + 1 : Math.max(next, key + 1); }; const append = (value) => set(next ?? 0, value); for (const item of items) { if ('spread' in item) { const src = item.spread; if (src instanceof globalThis.Generator) { for (const [key, value] of src.entries()) { if (typeof key === 'number') append(value); else set(key, value); } } else if (Array.isArray(src)) { for (const value of src) append(value); } else if (src && typeof src === 'object') { for (const [raw, value] of Object.entries(src)) { const key = globalThis.__phpx_array_key(raw); if (typeof key === 'number') append(value); else set(key, value); } } else { throw new Error('Only arrays and Traversables can be unpacked'); } } else if ('key' in item) { set(globalThis.__phpx_array_key(item.key), item.value); } else { append(item.value); } } if (keys.every((key, idx) => key === idx)) return keys.map((key) => values.get(key)); return globalThis.__phpx_assoc(keys.map((key) => [key, values.get(key)])); }; }"#);
+        out.push('\n');
+        out.push_str(r#"if (!globalThis.__phpx_array_push) { globalThis.__phpx_array_push = (arr, value) => { if (Array.isArray(arr)) { arr.push(value); return value; } if (!arr || typeof arr !== 'object') throw new Error('Cannot use a scalar value as an array'); let next = 0; for (const raw of Object.keys(arr)) { const key = globalThis.__phpx_array_key(raw); if (typeof key === 'number' && key >= next) next = key + 1; } arr[next] = value; return value; }; }"#);
+        out.push('\n');
+        out.push_str(r#"if (!globalThis.__phpx_entries) { globalThis.__phpx_entries = (value) => (value instanceof globalThis.Generator ? value.entries() : Object.entries(value ?? {})); }"#);
+        out.push('\n');
+        out.push_str(r#"if (!globalThis.__phpx_values) { globalThis.__phpx_values = (value) => (Array.isArray(value) ? value : value instanceof globalThis.Generator ? value : Object.values(value ?? {})); }"#);
+        out.push('\n');
+        out.push_str(r#"if (!globalThis.__phpx_yield_pair) { globalThis.__phpx_yield_pair = class { constructor(key, value, delegated = false) { this.key = key; this.value = value; this.delegated = delegated; } }; }"#);
+        out.push('\n');
+        out.push_str(r#"if (!globalThis.Generator) { globalThis.Generator = class Generator { #it; #started = false; #done = false; #key = null; #value = null; #ret = null; #autoKey = 0; constructor(it) { this.#it = it; } #advance(res) { if (res.done) { this.#done = true; this.#key = null; this.#value = null; this.#ret = res.value ?? null; return; } const item = res.value; if (item instanceof globalThis.__phpx_yield_pair) { this.#key = item.key; this.#value = item.value; if (!item.delegated && Number.isInteger(item.key) && item.key >= this.#autoKey) this.#autoKey = item.key + 1; } else { this.#key = this.#autoKey++; this.#value = item ?? null; } } #start() { if (!this.#started) { this.#started = true; this.#advance(this.#it.next()); } } current() { this.#start(); return this.#value; } key() { this.#start(); return this.#key; } next() { this.#start(); if (!this.#done) this.#advance(this.#it.next()); } send(value) { this.#start(); if (!this.#done) this.#advance(this.#it.next(value)); return this.#value; } throw(err) { this.#start(); if (this.#done) throw err; this.#advance(this.#it.throw(err)); return this.#value; } valid() { this.#start(); return !this.#done; } rewind() { this.#start(); } getReturn() { if (!this.#done) throw new Error("Cannot get return value of a generator that hasn't returned"); return this.#ret; } *entries() { for (this.#start(); !this.#done; this.next()) yield [this.#key, this.#value]; } *[Symbol.iterator]() { for (this.#start(); !this.#done; this.next()) yield this.#value; } }; }"#);
+        out.push('\n');
+        out.push_str(r#"if (!globalThis.__phpx_generator) { globalThis.__phpx_generator = (it) => new globalThis.Generator(it); }"#);
+        out.push('\n');
+        out.push_str(r#"if (!globalThis.__phpx_yield_from) { globalThis.__phpx_yield_from = function* (source) { const Pair = globalThis.__phpx_yield_pair; if (source instanceof globalThis.Generator) { while (source.valid()) { source.send(yield new Pair(source.key(), source.current(), true)); } return source.getReturn(); } if (Array.isArray(source)) { for (let idx = 0; idx < source.length; idx += 1) yield new Pair(idx, source[idx], true); return null; } for (const [raw, value] of Object.entries(source ?? {})) yield new Pair(globalThis.__phpx_array_key(raw), value, true); return null; }; }"#);
+        out.push_str("\n\n");
         out.push_str("if (!globalThis.__deka_chr) { globalThis.__deka_chr = (code) => String.fromCharCode((Number(code) || 0) & 0xff); }\n");
         out.push_str("if (!globalThis.__deka_ord) { globalThis.__deka_ord = (value) => { const str = String(value ?? ''); return str.length ? str.charCodeAt(0) : 0; }; }\n");
         out.push_str("if (!globalThis.chr) { globalThis.chr = globalThis.__deka_chr; }\n");
@@ -527,36 +695,61 @@ impl<'a> JsSubsetEmitter<'a> {
             out.push('\n');
         }
 
+        if let Some(package) = &self.package {
+            // Hoisted so exports called through an import cycle work before
+            // this module body has run.
+            out.push_str(&format!(
+                "function __deka$scope_call(fn, self, args) {{ return typeof globalThis.__dekaScopeCall === 'function' ? globalThis.__dekaScopeCall({0}, fn, self, args) : fn.apply(self, args); }}\n\
+function __deka$scope_begin() {{ return typeof globalThis.__dekaScopeBegin === 'function' ? globalThis.__dekaScopeBegin({0}) : null; }}\n\
+function __deka$scope_await(scope, value) {{ return scope ? globalThis.__dekaScopeAwait(scope, value) : value; }}\n\
+function __deka$scope_resumed(scope, outcome) {{ return scope ? globalThis.__dekaScopeResumed(scope, outcome) : outcome; }}\n\
+function __deka$scope_end(scope) {{ if (scope) globalThis.__dekaScopeEnd(scope); }}\n\n",
+                json_string(package)
+            ));
+        }
+
         out.push_str(&self.body);
 
-        if !self.main_body.is_empty() {
+        if has_code(&self.main_body) {
             out.push('\n');
             out.push_str("const __phpx_main = async () => {\n");
-            out.push_str(&self.main_body);
+            out.push_str(&self.async_scoped(self.main_body.clone(), true));
             out.push_str("};\n");
-            out.push_str("await __phpx_main();\n");
+            if self.package.is_some() {
+                out.push_str("await __deka$scope_call(__phpx_main, undefined, []);\n");
+            } else {
+                out.push_str("await __phpx_main();\n");
+            }
         }
 
         if !self.meta.export_specs.is_empty() {
             out.push('\n');
-            out.push_str("export { ");
-            for (idx, spec) in self.meta.export_specs.iter().enumerate() {
-                if idx > 0 {
-                    out.push_str(", ");
+            let mut exports = Vec::new();
+            let mut scoped = HashSet::new();
+            for spec in &self.meta.export_specs {
+                let mut local = spec.local.clone();
+                if self.package.is_some() && self.top_level_functions.contains(&local) {
+                    if scoped.insert(local.clone()) {
+                        out.push_str(&format!(
+                            "function __deka$export_{0}() {{ return __deka$scope_call({0}, this, arguments); }}\n",
+                            local
+                        ));
+                    }
+                    local = format!("__deka$export_{}", local);
                 }
-                if spec.imported == spec.local {
-                    out.push_str(&spec.local);
+                if spec.imported == local {
+                    exports.push(local);
                 } else {
-                    out.push_str(&format!("{} as {}", spec.local, spec.imported));
+                    exports.push(format!("{} as {}", local, spec.imported));
                 }
             }
-            out.push_str(" };\n");
+            out.push_str(&format!("export {{ {} }};\n", exports.join(", ")));
         }
 
-        out
+        resolve_marks(&out, &self.stmt_marks)
     }
 
-    fn emit_program(&mut self, program: &Program<'_>) -> Result<(), String> {
+    fn emit_program(&mut self, program: &Program<'a>) -> Result<(), String> {
         let import_locals: Vec<String> = self
             .meta
             .imports
@@ -573,6 +766,7 @@ impl<'a> JsSubsetEmitter<'a> {
                     if !self.is_declared(&fn_name) {
                         self.declare_in_scope(&fn_name);
                     }
+                    self.top_level_functions.insert(fn_name);
                 }
                 Stmt::Enum { name, .. } => {
                     let enum_name = self.token_name(name);
@@ -591,12 +785,40 @@ impl<'a> JsSubsetEmitter<'a> {
                 _ => {}
             }
         }
-        for stmt in program.statements {
+        self.register_class_decls(program.statements, "");
+        self.emit_top_level(program.statements)?;
+        // Only inheritance cycles or parents that never get declared are
+        // left; keep source order and let the runtime report them.
+        for (_, _, text) in std::mem::take(&mut self.pending_classes) {
+            self.body.push_str(&text);
+        }
+        Ok(())
+    }
+
+    fn emit_top_level(&mut self, stmts: &[StmtId<'_>]) -> Result<(), String> {
+        for stmt in stmts {
+            if let Some((fqn, deps)) = self.class_decl_deps(stmt) {
+                if deps.is_empty() {
+                    self.emit_stmt(stmt)?;
+                    self.mark_class_emitted(fqn);
+                } else {
+                    let saved_body = std::mem::take(&mut self.body);
+                    let result = self.emit_stmt(stmt);
+                    let text = std::mem::replace(&mut self.body, saved_body);
+                    result?;
+                    self.pending_classes.push((fqn, deps, text));
+                }
+                continue;
+            }
             let is_decl = matches!(
                 stmt,
                 Stmt::Function { .. }
                     | Stmt::Enum { .. }
                     | Stmt::Class { .. }
+                    | Stmt::Interface { .. }
+                    | Stmt::Trait { .. }
+                    | Stmt::Namespace { .. }
+                    | Stmt::Use { .. }
                     | Stmt::Const { .. }
                     | Stmt::TypeAlias { .. }
             );
@@ -609,6 +831,97 @@ impl<'a> JsSubsetEmitter<'a> {
         Ok(())
     }
 
+    /// Lowercased name of a class or interface declaration and the local
+    /// classes it extends or implements that are not emitted yet.
+    fn class_decl_deps(&self, stmt: StmtId<'_>) -> Option<(String, HashSet<String>)> {
+        let (name, parents): (_, Vec<Name<'_>>) = match stmt {
+            Stmt::Class {
+                kind: ClassKind::Class,
+                name,
+                extends,
+                implements,
+                ..
+            } => (
+                name,
+                extends.iter().chain(implements.iter()).copied().collect(),
+            ),
+            Stmt::Interface { name, extends, .. } => (name, extends.to_vec()),
+            _ => return None,
+        };
+        let fqn = qualify(&self.namespace, &self.token_text(name)).to_ascii_lowercase();
+        let deps = parents
+            .into_iter()
+            .map(|parent| {
+                self.resolve_class_name(&self.name_text(parent))
+                    .to_ascii_lowercase()
+            })
+            .filter(|parent| {
+                *parent != fqn
+                    && self.local_classes.contains_key(parent)
+                    && !self.emitted_classes.contains(parent)
+            })
+            .collect();
+        Some((fqn, deps))
+    }
+
+    /// Record an emitted class and release held-back classes waiting on it.
+    fn mark_class_emitted(&mut self, fqn: String) {
+        let mut ready = vec![fqn];
+        while let Some(fqn) = ready.pop() {
+            let mut idx = 0;
+            while idx < self.pending_classes.len() {
+                self.pending_classes[idx].1.remove(&fqn);
+                if self.pending_classes[idx].1.is_empty() {
+                    let (name, _, text) = self.pending_classes.remove(idx);
+                    self.body.push_str(&text);
+                    ready.push(name);
+                } else {
+                    idx += 1;
+                }
+            }
+            self.emitted_classes.insert(fqn);
+        }
+    }
+
+    /// Bind every class and interface to a local name up front so bodies can
+    /// reference classes declared later in the module, and collect traits.
+    fn register_class_decls(&mut self, stmts: &'a [StmtId<'a>], namespace: &str) {
+        let mut namespace = namespace.to_string();
+        for stmt in stmts {
+            match stmt {
+                Stmt::Namespace { name, body, .. } => {
+                    let ns = name
+                        .map(|n| self.name_text(n).trim_start_matches('\\').to_string())
+                        .unwrap_or_default();
+                    match body {
+                        Some(inner) => self.register_class_decls(inner, &ns),
+                        None => namespace = ns,
+                    }
+                }
+                Stmt::Class {
+                    kind: ClassKind::Class,
+                    name,
+                    ..
+                }
+                | Stmt::Interface { name, .. } => {
+                    let short = self.token_text(name);
+                    let fqn = qualify(&namespace, &short);
+                    let mut local = self.sanitize_name(&short);
+                    if self.is_declared(&local) {
+                        local = self.sanitize_name(&fqn);
+                    }
+                    self.declare_in_scope(&local);
+                    self.local_classes.insert(fqn.to_ascii_lowercase(), local);
+                }
+                Stmt::Trait { name, members, .. } => {
+                    let fqn = qualify(&namespace, &self.token_text(name));
+                    self.traits.insert(fqn.to_ascii_lowercase(), members);
+                }
+                _ => {}
+            }
+        }
+    }
+
     fn emit_stmt_to_main(&mut self, stmt: StmtId<'_>) -> Result<(), String> {
         std::mem::swap(&mut self.body, &mut self.main_body);
         let res = self.emit_stmt(stmt);
@@ -617,12 +930,55 @@ impl<'a> JsSubsetEmitter<'a> {
     }
 
     fn emit_stmt(&mut self, stmt: StmtId<'_>) -> Result<(), String> {
+        if !matches!(stmt, Stmt::Nop { .. }) {
+            self.body.push_str(&format!("\0{}\0", self.stmt_marks.len()));
+            self.stmt_marks.push(stmt.span().start);
+        }
         match stmt {
-            Stmt::Namespace { .. } => {
-                Err("namespace declarations are not supported in JS subset emitter".to_string())
+            Stmt::Namespace { name, body, .. } => {
+                let ns = name
+                    .map(|n| self.name_text(n).trim_start_matches('\\').to_string())
+                    .unwrap_or_default();
+                match body {
+                    Some(inner) => {
+                        let saved_ns = std::mem::replace(&mut self.namespace, ns);
+                        let saved_uses = std::mem::take(&mut self.use_aliases);
+                        let result = self.emit_top_level(inner);
+                        self.namespace = saved_ns;
+                        self.use_aliases = saved_uses;
+                        result
+                    }
+                    None => {
+                        self.namespace = ns;
+                        self.use_aliases.clear();
+                        Ok(())
+                    }
+                }
             }
-            Stmt::Use { .. } => {
-                Err("use declarations are not supported in JS subset emitter".to_string())
+            Stmt::Use { uses, kind, .. } => {
+                for item in *uses {
+                    let item_kind = if item.kind == UseKind::Normal {
+                        *kind
+                    } else {
+                        item.kind
+                    };
+                    if item_kind != UseKind::Normal {
+                        return Err(
+                            "use function/const declarations are not supported in JS subset emitter"
+                                .to_string(),
+                        );
+                    }
+                    let target = self
+                        .name_text(item.name)
+                        .trim_start_matches('\\')
+                        .to_string();
+                    let alias = match item.alias {
+                        Some(alias) => self.token_text(alias),
+                        None => target.rsplit('\\').next().unwrap_or(&target).to_string(),
+                    };
+                    self.use_aliases.insert(alias.to_ascii_lowercase(), target);
+                }
+                Ok(())
             }
             Stmt::Class {
                 kind: ClassKind::Struct,
@@ -643,11 +999,21 @@ impl<'a> JsSubsetEmitter<'a> {
                 self.emit_enum(name, members)?;
                 Ok(())
             }
-            Stmt::Class { .. }
-            | Stmt::Trait { .. }
-            | Stmt::Interface { .. } => {
-                Err("class-like declarations are not supported in JS subset emitter".to_string())
-            }
+            Stmt::Class {
+                name,
+                extends,
+                implements,
+                members,
+                ..
+            } => self.emit_class(name, *extends, implements, members),
+            Stmt::Interface {
+                name,
+                extends,
+                members,
+                ..
+            } => self.emit_interface(name, extends, members),
+            // Trait members are copied into each using class.
+            Stmt::Trait { .. } => Ok(()),
             Stmt::TypeAlias { .. } => {
                 Err("type aliases are not supported in JS subset emitter".to_string())
             }
@@ -669,8 +1035,21 @@ impl<'a> JsSubsetEmitter<'a> {
 
                 let exported =
                     self.scopes.len() == 1 && self.meta.exported_functions.contains(&fn_name);
+                let generator = is_generator_body(body);
+                if generator && *is_async {
+                    return Err(
+                        "async generators are not supported in JS subset emitter".to_string()
+                    );
+                }
                 let async_kw = if *is_async { "async " } else { "" };
-                if exported {
+                if exported && self.package.is_some() {
+                    self.body.push_str(&format!(
+                        "export function {0}() {{\nreturn __deka$scope_call(__deka${0}, this, arguments);\n}}\n\n",
+                        fn_name
+                    ));
+                    self.body
+                        .push_str(&format!("{}function __deka${}({}) {{\n", async_kw, fn_name, js_params));
+                } else if exported {
                     self.body
                         .push_str(&format!("export {}function {}({}) {{\n", async_kw, fn_name, js_params));
                 } else {
@@ -678,15 +1057,26 @@ impl<'a> JsSubsetEmitter<'a> {
                         .push_str(&format!("{}function {}({}) {{\n", async_kw, fn_name, js_params));
                 }
 
+                let start = self.body.len();
                 self.push_scope();
                 for p in *params {
                     self.declare_in_scope(&self.token_name(p.name));
                 }
                 self.emit_param_default_guards(params)?;
+                if generator {
+                    self.body
+                        .push_str("return globalThis.__phpx_generator(function* () {\n");
+                }
                 for inner in *body {
                     self.emit_stmt(*inner)?;
                 }
+                if generator {
+                    self.body.push_str("}.apply(this, arguments));\n");
+                }
                 self.pop_scope();
+                let inner = self.body.split_off(start);
+                let inner = self.async_scoped(inner, *is_async);
+                self.body.push_str(&inner);
 
                 self.body.push_str("}\n\n");
                 Ok(())
@@ -773,7 +1163,7 @@ impl<'a> JsSubsetEmitter<'a> {
                 if let Some(key_var) = key_var {
                     let key_name = self.extract_var_name(*key_var)?;
                     self.body.push_str(&format!(
-                        "for (const [{} , {}] of Object.entries({})) {{\n",
+                        "for (const [{} , {}] of globalThis.__phpx_entries({})) {{\n",
                         key_name, value_name, iterable
                     ));
                     self.push_scope();
@@ -781,8 +1171,8 @@ impl<'a> JsSubsetEmitter<'a> {
                     self.declare_in_scope(&value_name);
                 } else {
                     self.body.push_str(&format!(
-                        "for (const {} of (Array.isArray({}) ? {} : Object.values(({} ?? {{}})))) {{\n",
-                        value_name, iterable, iterable, iterable
+                        "for (const {} of globalThis.__phpx_values({})) {{\n",
+                        value_name, iterable
                     ));
                     self.push_scope();
                     self.declare_in_scope(&value_name);
@@ -1027,6 +1417,15 @@ impl<'a> JsSubsetEmitter<'a> {
                         }
                     }
                 } else {
+                    // `$items[] = ...` on a fresh variable creates the array, as in PHP.
+                    if let Some(name) = self.undeclared_append_base(*expr) {
+                        self.declare_in_scope(&name);
+                        self.body.push_str(&format!("let {} = [];\n", name));
+                        if self.scopes.len() == 1 {
+                            self.body
+                                .push_str(&format!("globalThis.{} = {};\n", name, name));
+                        }
+                    }
                     let value = self.emit_expr(*expr)?;
                     self.body.push_str(&format!("{};\n", value));
                 }
@@ -1042,98 +1441,921 @@ impl<'a> JsSubsetEmitter<'a> {
         }
     }
 
-    fn emit_enum(
-        &mut self,
-        name: &php_rs::parser::lexer::token::Token,
-        members: &[ClassMember<'_>],
-    ) -> Result<(), String> {
-        let enum_name = self.token_name(name);
-        if !self.is_declared(&enum_name) {
-            self.declare_in_scope(&enum_name);
+    fn emit_enum(
+        &mut self,
+        name: &php_rs::parser::lexer::token::Token,
+        members: &[ClassMember<'_>],
+    ) -> Result<(), String> {
+        let enum_name = self.token_name(name);
+        if !self.is_declared(&enum_name) {
+            self.declare_in_scope(&enum_name);
+        }
+        let mut cases: Vec<EnumCaseDef> = Vec::new();
+        let mut methods: Vec<&ClassMember<'_>> = Vec::new();
+
+        for member in members {
+            match member {
+                ClassMember::Case { name, payload, .. } => {
+                    let case_name = self.token_name(name);
+                    let params = payload
+                        .map(|items| {
+                            items
+                                .iter()
+                                .map(|p| self.token_name(p.name))
+                                .collect::<Vec<_>>()
+                        })
+                        .unwrap_or_default();
+                    cases.push(EnumCaseDef {
+                        name: case_name,
+                        params,
+                    });
+                }
+                ClassMember::Method { .. } => methods.push(member),
+                _ => {
+                    return Err("enum members other than cases/methods are not supported in JS subset emitter".to_string());
+                }
+            }
+        }
+
+        self.enum_cases.insert(enum_name.clone(), cases.clone());
+
+        self.body.push_str(&format!("class {} {{\n", enum_name));
+        self.body.push_str("  constructor(__case, __payload) {\n");
+        self.body.push_str(&format!("    this.__enum = {};\n", json_string(&enum_name)));
+        self.body.push_str("    this.__case = __case;\n");
+        self.body.push_str("    if (__payload) {\n");
+        self.body.push_str("      Object.assign(this, __payload);\n");
+        self.body.push_str("    }\n");
+        self.body.push_str("  }\n");
+
+        for member in methods {
+            if let ClassMember::Method { name, params, body, .. } = member {
+                let method_name = self.token_name(name);
+                let js_params = params
+                    .iter()
+                    .map(|p| self.token_name(p.name))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let block = self.emit_method_block(params, body, false)?;
+                self.body
+                    .push_str(&format!("  {}({}) {{\n{}  }}\n", method_name, js_params, block));
+            }
+        }
+
+        for case in &cases {
+            if case.params.is_empty() {
+                self.body.push_str(&format!(
+                    "  static get {}() {{ return new {}({}, null); }}\n",
+                    case.name,
+                    enum_name,
+                    json_string(&case.name)
+                ));
+            } else {
+                let param_list = case.params.join(", ");
+                let payload_entries = case
+                    .params
+                    .iter()
+                    .map(|p| format!("{}: {}", json_string(p), p))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                self.body.push_str(&format!(
+                    "  static {}({}) {{ return new {}({}, {{{}}}); }}\n",
+                    case.name,
+                    param_list,
+                    enum_name,
+                    json_string(&case.name),
+                    payload_entries
+                ));
+            }
+        }
+
+        self.body.push_str("}\n");
+        Ok(())
+    }
+
+    fn emit_class(
+        &mut self,
+        name: &php_rs::parser::lexer::token::Token,
+        extends: Option<Name<'_>>,
+        implements: &[Name<'_>],
+        members: &[ClassMember<'_>],
+    ) -> Result<(), String> {
+        let fqn = qualify(&self.namespace, &self.token_text(name));
+        let local = self.class_local(&fqn);
+        let parent = extends.map(|n| self.class_ref_from_name(n));
+
+        let mut rules = TraitRules::default();
+        let mut used_traits: Vec<(String, &'a [ClassMember<'a>])> = Vec::new();
+        for member in members {
+            if let ClassMember::TraitUse {
+                traits,
+                adaptations,
+                ..
+            } = member
+            {
+                for trait_name in *traits {
+                    let trait_fqn = self.resolve_class_name(&self.name_text(*trait_name));
+                    self.collect_trait(&trait_fqn, &mut used_traits)?;
+                }
+                for adaptation in *adaptations {
+                    self.add_trait_rule(adaptation, &mut rules);
+                }
+            }
+        }
+
+        // Members declared on the class win over trait members of the same name.
+        let mut own_names = HashSet::new();
+        for member in members {
+            for (member_name, _) in self.member_names(member) {
+                own_names.insert(member_name);
+            }
+        }
+        let mut ctx = ClassContext {
+            name: fqn.clone(),
+            local: local.clone(),
+            parent: parent.clone(),
+            private_members: HashSet::new(),
+            private_statics: HashSet::new(),
+            instance_methods: HashSet::new(),
+            own_params: HashSet::new(),
+            in_static: false,
+        };
+        self.collect_member_privacy(members, None, &rules, &HashSet::new(), &mut ctx);
+        for (trait_fqn, trait_members) in &used_traits {
+            self.collect_member_privacy(
+                trait_members,
+                Some(trait_fqn),
+                &rules,
+                &own_names,
+                &mut ctx,
+            );
+        }
+        if let Some(clash) = ctx
+            .instance_methods
+            .iter()
+            .map(|m| self.member_key(&ctx.private_members, m))
+            .find(|key| self.instance_field_keys(members, &ctx).contains(key))
+        {
+            return Err(format!(
+                "class {} declares a property and a method named {}, which JS classes cannot represent",
+                fqn, clash
+            ));
+        }
+
+        let saved_ctx = self.class_ctx.replace(ctx);
+        let mut out = match &parent {
+            Some(parent) => format!("class {} extends {} {{\n", local, parent),
+            None => format!("class {} {{\n", local),
+        };
+        if !implements.is_empty() {
+            let interfaces = implements
+                .iter()
+                .map(|n| self.class_ref_from_name(*n))
+                .collect::<Vec<_>>()
+                .join(", ");
+            out.push_str(&format!("  static __phpx_interfaces = [{}];\n", interfaces));
+        }
+        let mut result = self.emit_class_members(members, None, &rules, &HashSet::new(), &mut out);
+        for (trait_fqn, trait_members) in &used_traits {
+            if result.is_err() {
+                break;
+            }
+            result = self.emit_class_members(
+                trait_members,
+                Some(trait_fqn),
+                &rules,
+                &own_names,
+                &mut out,
+            );
+        }
+        self.class_ctx = saved_ctx;
+        result?;
+        out.push_str("}\n");
+        out.push_str(&format!(
+            "globalThis.__phpx_register_class({}, {});\n\n",
+            json_string(&fqn),
+            local
+        ));
+        self.body.push_str(&out);
+        Ok(())
+    }
+
+    fn emit_interface(
+        &mut self,
+        name: &php_rs::parser::lexer::token::Token,
+        extends: &[Name<'_>],
+        members: &[ClassMember<'_>],
+    ) -> Result<(), String> {
+        let fqn = qualify(&self.namespace, &self.token_text(name));
+        let local = self.class_local(&fqn);
+        let mut out = format!("class {} {{\n", local);
+        out.push_str(
+            "  static [Symbol.hasInstance](value) { return globalThis.__phpx_implements(value, this); }\n",
+        );
+        if !extends.is_empty() {
+            let parents = extends
+                .iter()
+                .map(|n| self.class_ref_from_name(*n))
+                .collect::<Vec<_>>()
+                .join(", ");
+            out.push_str(&format!("  static __phpx_interfaces = [{}];\n", parents));
+        }
+        let saved_ctx = self.class_ctx.replace(ClassContext {
+            name: fqn.clone(),
+            local: local.clone(),
+            parent: None,
+            private_members: HashSet::new(),
+            private_statics: HashSet::new(),
+            instance_methods: HashSet::new(),
+            own_params: HashSet::new(),
+            in_static: true,
+        });
+        let mut result = Ok(());
+        for member in members {
+            if let ClassMember::Const { consts, .. } = member {
+                for constant in *consts {
+                    match self.emit_expr(constant.value) {
+                        Ok(value) => out.push_str(&format!(
+                            "  static {} = {};\n",
+                            self.token_name(constant.name),
+                            value
+                        )),
+                        Err(err) => {
+                            result = Err(err);
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+        self.class_ctx = saved_ctx;
+        result?;
+        out.push_str("}\n");
+        out.push_str(&format!(
+            "globalThis.__phpx_register_class({}, {});\n\n",
+            json_string(&fqn),
+            local
+        ));
+        self.body.push_str(&out);
+        Ok(())
+    }
+
+    fn class_local(&mut self, fqn: &str) -> String {
+        if let Some(local) = self.local_classes.get(&fqn.to_ascii_lowercase()) {
+            return local.clone();
+        }
+        let local = self.sanitize_name(fqn);
+        self.declare_in_scope(&local);
+        self.local_classes
+            .insert(fqn.to_ascii_lowercase(), local.clone());
+        local
+    }
+
+    /// Append a trait and the traits it uses itself, in declaration order.
+    fn collect_trait(
+        &self,
+        fqn: &str,
+        out: &mut Vec<(String, &'a [ClassMember<'a>])>,
+    ) -> Result<(), String> {
+        let key = fqn.to_ascii_lowercase();
+        if out.iter().any(|(name, _)| *name == key) {
+            return Ok(());
+        }
+        let Some(members) = self.traits.get(&key).copied() else {
+            return Err(format!(
+                "trait {} must be declared in the same module for the JS subset emitter",
+                fqn
+            ));
+        };
+        out.push((key, members));
+        for member in members {
+            if let ClassMember::TraitUse { traits, .. } = member {
+                for nested in *traits {
+                    let nested_fqn = self.resolve_class_name(&self.name_text(*nested));
+                    self.collect_trait(&nested_fqn, out)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn add_trait_rule(&self, adaptation: &TraitAdaptation<'_>, rules: &mut TraitRules) {
+        match adaptation {
+            TraitAdaptation::Precedence {
+                method, insteadof, ..
+            } => {
+                let method_name = self.token_text(method.method).to_ascii_lowercase();
+                for excluded in *insteadof {
+                    let trait_fqn = self.resolve_class_name(&self.name_text(*excluded));
+                    rules
+                        .excluded
+                        .insert((trait_fqn.to_ascii_lowercase(), method_name.clone()));
+                }
+            }
+            TraitAdaptation::Alias {
+                method,
+                alias,
+                visibility,
+                ..
+            } => {
+                rules.aliases.push(TraitAlias {
+                    trait_name: method.trait_name.map(|n| {
+                        self.resolve_class_name(&self.name_text(n))
+                            .to_ascii_lowercase()
+                    }),
+                    method: self.token_text(method.method).to_ascii_lowercase(),
+                    alias: alias.map(|a| self.token_text(a)),
+                    private: visibility.map(|v| self.token_text(v).eq_ignore_ascii_case("private")),
+                });
+            }
+        }
+    }
+
+    /// Lowercased PHP names a member declares, paired with whether it is a
+    /// method.
+    fn member_names(&self, member: &ClassMember<'_>) -> Vec<(String, bool)> {
+        match member {
+            ClassMember::Property { entries, .. } => entries
+                .iter()
+                .map(|e| (self.token_name(e.name).to_ascii_lowercase(), false))
+                .collect(),
+            ClassMember::Const { consts, .. } => consts
+                .iter()
+                .map(|c| (self.token_name(c.name).to_ascii_lowercase(), false))
+                .collect(),
+            ClassMember::Method { name, .. } => {
+                vec![(self.token_text(name).to_ascii_lowercase(), true)]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// The method names (original or alias) and privacy a trait method is
+    /// copied under, or nothing when it is excluded or overridden.
+    fn trait_method_copies(
+        &self,
+        trait_fqn: &str,
+        method: &str,
+        private: bool,
+        rules: &TraitRules,
+        own_names: &HashSet<String>,
+    ) -> Vec<(Option<String>, bool)> {
+        let lower = method.to_ascii_lowercase();
+        let mut copies = Vec::new();
+        let mut original_private = private;
+        for alias in &rules.aliases {
+            if alias.method != lower || alias.trait_name.as_deref().is_some_and(|t| t != trait_fqn)
+            {
+                continue;
+            }
+            match &alias.alias {
+                Some(name) if !own_names.contains(&name.to_ascii_lowercase()) => {
+                    copies.push((Some(name.clone()), alias.private.unwrap_or(private)));
+                }
+                Some(_) => {}
+                None => original_private = alias.private.unwrap_or(private),
+            }
+        }
+        let excluded = rules
+            .excluded
+            .contains(&(trait_fqn.to_string(), lower.clone()));
+        if !excluded && !own_names.contains(&lower) {
+            copies.insert(0, (None, original_private));
+        }
+        copies
+    }
+
+    fn collect_member_privacy(
+        &self,
+        members: &[ClassMember<'_>],
+        trait_fqn: Option<&str>,
+        rules: &TraitRules,
+        own_names: &HashSet<String>,
+        ctx: &mut ClassContext,
+    ) {
+        for member in members {
+            match member {
+                ClassMember::Property {
+                    modifiers, entries, ..
+                } => {
+                    let is_static = has_modifier(self.source, modifiers, "static");
+                    let private = has_modifier(self.source, modifiers, "private");
+                    for entry in *entries {
+                        let name = self.token_name(entry.name);
+                        if trait_fqn.is_some() && own_names.contains(&name.to_ascii_lowercase()) {
+                            continue;
+                        }
+                        if private && is_static {
+                            ctx.private_statics.insert(name);
+                        } else if private {
+                            ctx.private_members.insert(name);
+                        }
+                    }
+                }
+                ClassMember::Const {
+                    modifiers, consts, ..
+                } if has_modifier(self.source, modifiers, "private") => {
+                    for constant in *consts {
+                        ctx.private_statics.insert(self.token_name(constant.name));
+                    }
+                }
+                ClassMember::Method {
+                    modifiers,
+                    name,
+                    params,
+                    ..
+                } => {
+                    let php_name = self.token_text(name);
+                    if php_name.eq_ignore_ascii_case("__construct") {
+                        if trait_fqn.is_none() || !own_names.contains("__construct") {
+                            for param in params.iter().filter(|p| !p.modifiers.is_empty()) {
+                                if has_modifier(self.source, param.modifiers, "private") {
+                                    ctx.private_members.insert(self.token_name(param.name));
+                                }
+                            }
+                        }
+                        continue;
+                    }
+                    let is_static = has_modifier(self.source, modifiers, "static");
+                    let private = has_modifier(self.source, modifiers, "private");
+                    let copies = match trait_fqn {
+                        Some(trait_fqn) => self
+                            .trait_method_copies(trait_fqn, &php_name, private, rules, own_names),
+                        None => vec![(None, private)],
+                    };
+                    for (alias, private) in copies {
+                        let js_name = self.sanitize_name(alias.as_deref().unwrap_or(&php_name));
+                        if !is_static {
+                            ctx.instance_methods.insert(js_name.to_ascii_lowercase());
+                        }
+                        match (private, is_static) {
+                            (true, true) => {
+                                ctx.private_statics.insert(js_name);
+                            }
+                            (true, false) => {
+                                ctx.private_members.insert(js_name);
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn instance_field_keys(
+        &self,
+        members: &[ClassMember<'_>],
+        ctx: &ClassContext,
+    ) -> HashSet<String> {
+        let mut keys = HashSet::new();
+        for member in members {
+            match member {
+                ClassMember::Property {
+                    modifiers, entries, ..
+                } if !has_modifier(self.source, modifiers, "static") => {
+                    for entry in *entries {
+                        keys.insert(
+                            self.member_key(&ctx.private_members, &self.token_name(entry.name)),
+                        );
+                    }
+                }
+                ClassMember::Method { name, params, .. }
+                    if self.token_text(name).eq_ignore_ascii_case("__construct") =>
+                {
+                    for param in params.iter().filter(|p| !p.modifiers.is_empty()) {
+                        keys.insert(
+                            self.member_key(&ctx.private_members, &self.token_name(param.name)),
+                        );
+                    }
+                }
+                _ => {}
+            }
+        }
+        keys
+    }
+
+    fn emit_class_members(
+        &mut self,
+        members: &[ClassMember<'_>],
+        trait_fqn: Option<&str>,
+        rules: &TraitRules,
+        own_names: &HashSet<String>,
+        out: &mut String,
+    ) -> Result<(), String> {
+        for member in members {
+            match member {
+                ClassMember::Property {
+                    modifiers,
+                    ty,
+                    entries,
+                    ..
+                } => {
+                    let is_static = has_modifier(self.source, modifiers, "static");
+                    for entry in *entries {
+                        let name = self.token_name(entry.name);
+                        if trait_fqn.is_some() && own_names.contains(&name.to_ascii_lowercase()) {
+                            continue;
+                        }
+                        let key = self.class_member_key(&name, is_static);
+                        let static_kw = if is_static { "static " } else { "" };
+                        if let Some(ctx) = self.class_ctx.as_mut() {
+                            ctx.in_static = is_static;
+                        }
+                        match (entry.default, ty) {
+                            (Some(default), _) => {
+                                let value = self.emit_expr(default)?;
+                                out.push_str(&format!("  {}{} = {};\n", static_kw, key, value));
+                            }
+                            (None, Some(_)) => out.push_str(&format!("  {}{};\n", static_kw, key)),
+                            (None, None) => {
+                                out.push_str(&format!("  {}{} = null;\n", static_kw, key))
+                            }
+                        }
+                    }
+                }
+                ClassMember::Const { consts, .. } => {
+                    if let Some(ctx) = self.class_ctx.as_mut() {
+                        ctx.in_static = true;
+                    }
+                    for constant in *consts {
+                        let name = self.token_name(constant.name);
+                        if trait_fqn.is_some() && own_names.contains(&name.to_ascii_lowercase()) {
+                            continue;
+                        }
+                        let key = self.class_member_key(&name, true);
+                        let value = self.emit_expr(constant.value)?;
+                        out.push_str(&format!("  static {} = {};\n", key, value));
+                    }
+                }
+                ClassMember::Method {
+                    modifiers,
+                    name,
+                    params,
+                    body,
+                    ..
+                } => {
+                    if has_modifier(self.source, modifiers, "abstract") {
+                        continue;
+                    }
+                    let php_name = self.token_text(name);
+                    let is_static = has_modifier(self.source, modifiers, "static");
+                    let own_params = self.own_class_params(params);
+                    if let Some(ctx) = self.class_ctx.as_mut() {
+                        ctx.in_static = is_static;
+                        ctx.own_params = own_params;
+                    }
+                    if php_name.eq_ignore_ascii_case("__construct") {
+                        if trait_fqn.is_some() && own_names.contains("__construct") {
+                            continue;
+                        }
+                        self.emit_constructor(params, body, out)?;
+                        continue;
+                    }
+                    let private = has_modifier(self.source, modifiers, "private");
+                    let copies = match trait_fqn {
+                        Some(trait_fqn) => self
+                            .trait_method_copies(trait_fqn, &php_name, private, rules, own_names),
+                        None => vec![(None, private)],
+                    };
+                    for (alias, private) in copies {
+                        let js_name = self.sanitize_name(alias.as_deref().unwrap_or(&php_name));
+                        let key = if private {
+                            format!("#{}", js_name)
+                        } else {
+                            js_name.clone()
+                        };
+                        let static_kw = if is_static { "static " } else { "" };
+                        let block = self.emit_method_block(params, body, false)?;
+                        out.push_str(&format!(
+                            "  {}{}({}) {{\n{}  }}\n",
+                            static_kw,
+                            key,
+                            self.js_param_list(params),
+                            block
+                        ));
+                        if !is_static && !private && js_name == "__toString" {
+                            out.push_str("  toString() { return this.__toString(); }\n");
+                        }
+                    }
+                }
+                ClassMember::TraitUse { .. } => {}
+                ClassMember::PropertyHook { .. } => {
+                    return Err("property hooks are not supported in JS subset emitter".to_string());
+                }
+                ClassMember::Embed { .. } | ClassMember::Case { .. } => {
+                    return Err(
+                        "class members other than properties/constants/methods are not supported in JS subset emitter"
+                            .to_string(),
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Lower `__construct`: promoted parameters become fields assigned right
+    /// after `parent::__construct(...)` (or an implicit `super()`), since JS
+    /// forbids touching `this` before the parent constructor runs.
+    fn emit_constructor(
+        &mut self,
+        params: &[Param<'_>],
+        stmts: &[StmtId<'_>],
+        out: &mut String,
+    ) -> Result<(), String> {
+        let has_parent = self.class_ctx.as_ref().is_some_and(|c| c.parent.is_some());
+        let mut promotions = String::new();
+        for param in params.iter().filter(|p| !p.modifiers.is_empty()) {
+            let name = self.token_name(param.name);
+            let key = self.class_member_key(&name, false);
+            out.push_str(&format!("  {};\n", key));
+            promotions.push_str(&format!("this.{} = {};\n", key, name));
+        }
+
+        let saved = std::mem::take(&mut self.body);
+        self.push_scope();
+        for param in params {
+            self.declare_in_scope(&self.token_name(param.name));
+        }
+        let result = self.emit_constructor_body(params, stmts, has_parent, &promotions);
+        self.pop_scope();
+        let block = std::mem::replace(&mut self.body, saved);
+        result?;
+        let block = self.scoped_body(block, false);
+        out.push_str(&format!(
+            "  constructor({}) {{\n{}  }}\n",
+            self.js_param_list(params),
+            block
+        ));
+        Ok(())
+    }
+
+    fn emit_constructor_body(
+        &mut self,
+        params: &[Param<'_>],
+        stmts: &[StmtId<'_>],
+        has_parent: bool,
+        promotions: &str,
+    ) -> Result<(), String> {
+        let defaults = self.emit_param_default_guards_inline(params)?;
+        self.body.push_str(&defaults);
+        let rest = match stmts
+            .iter()
+            .position(|stmt| self.is_parent_constructor_call(*stmt))
+        {
+            Some(idx) => {
+                for stmt in &stmts[..=idx] {
+                    self.emit_stmt(*stmt)?;
+                }
+                &stmts[idx + 1..]
+            }
+            None => {
+                if has_parent {
+                    self.body.push_str("super();\n");
+                }
+                stmts
+            }
+        };
+        self.body.push_str(promotions);
+        rest.iter().try_for_each(|stmt| self.emit_stmt(*stmt))
+    }
+
+    fn is_parent_constructor_call(&self, stmt: StmtId<'_>) -> bool {
+        let Stmt::Expression { expr, .. } = stmt else {
+            return false;
+        };
+        let Expr::StaticCall { class, method, .. } = expr else {
+            return false;
+        };
+        self.is_parent_ref(*class)
+            && matches!(method, Expr::Variable { name, .. }
+                if self.span_name(*name).eq_ignore_ascii_case("__construct"))
+    }
+
+    fn is_parent_ref(&self, class: ExprId<'_>) -> bool {
+        self.class_ctx.is_some()
+            && matches!(class, Expr::Variable { name, .. }
+                if self.span_bytes(*name).eq_ignore_ascii_case(b"parent"))
+    }
+
+    fn js_param_list(&self, params: &[Param<'_>]) -> String {
+        params
+            .iter()
+            .map(|p| {
+                let name = self.token_name(p.name);
+                if p.variadic {
+                    format!("...{}", name)
+                } else {
+                    name
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn member_key(&self, private: &HashSet<String>, name: &str) -> String {
+        if private.contains(name) {
+            format!("#{}", name)
+        } else {
+            name.to_string()
+        }
+    }
+
+    /// JS property key for a member of the class being lowered.
+    fn class_member_key(&self, name: &str, is_static: bool) -> String {
+        match &self.class_ctx {
+            Some(ctx) if is_static => self.member_key(&ctx.private_statics, name),
+            Some(ctx) => self.member_key(&ctx.private_members, name),
+            None => name.to_string(),
         }
-        let mut cases: Vec<EnumCaseDef> = Vec::new();
-        let mut methods: Vec<&ClassMember<'_>> = Vec::new();
+    }
 
-        for member in members {
-            match member {
-                ClassMember::Case { name, payload, .. } => {
-                    let case_name = self.token_name(name);
-                    let params = payload
-                        .map(|items| {
-                            items
-                                .iter()
-                                .map(|p| self.token_name(p.name))
-                                .collect::<Vec<_>>()
-                        })
-                        .unwrap_or_default();
-                    cases.push(EnumCaseDef {
-                        name: case_name,
-                        params,
+    /// Fully-qualified name for a class reference, applying `use` aliases and
+    /// the current namespace.
+    fn resolve_class_name(&self, raw: &str) -> String {
+        let raw = raw.trim();
+        if let Some(rest) = raw.strip_prefix('\\') {
+            return rest.to_string();
+        }
+        let (first, rest) = match raw.split_once('\\') {
+            Some((first, rest)) => (first, Some(rest)),
+            None => (raw, None),
+        };
+        if first.eq_ignore_ascii_case("namespace") {
+            return qualify(&self.namespace, rest.unwrap_or_default());
+        }
+        if let Some(target) = self.use_aliases.get(&first.to_ascii_lowercase()) {
+            return match rest {
+                Some(rest) => format!("{}\\{}", target, rest),
+                None => target.clone(),
+            };
+        }
+        qualify(&self.namespace, raw)
+    }
+
+    /// JS expression for a named class declared here or registered by another
+    /// module; `None` leaves the name to ordinary identifier lowering.
+    fn resolve_class_ref(&self, raw: &str) -> Option<String> {
+        let fqn = self.resolve_class_name(raw);
+        if let Some(local) = self.local_classes.get(&fqn.to_ascii_lowercase()) {
+            return Some(local.clone());
+        }
+        if !raw.contains('\\') && self.is_declared(&self.sanitize_name(raw)) {
+            return None;
+        }
+        if fqn.contains('\\') {
+            return Some(format!("globalThis.__phpx_class({})", json_string(&fqn)));
+        }
+        None
+    }
+
+    fn emit_class_ref(&mut self, class: ExprId<'_>) -> Result<String, String> {
+        let Expr::Variable { name, .. } = class else {
+            return self.emit_expr(class);
+        };
+        let raw = String::from_utf8_lossy(self.span_bytes(*name)).to_string();
+        if raw.starts_with('$') {
+            return self.emit_expr(class);
+        }
+        if let Some(ctx) = &self.class_ctx {
+            match raw.to_ascii_lowercase().as_str() {
+                "self" => return Ok(ctx.local.clone()),
+                "static" if ctx.in_static => return Ok("this".to_string()),
+                "static" => return Ok("this.constructor".to_string()),
+                "parent" => {
+                    return ctx.parent.clone().ok_or_else(|| {
+                        format!("parent used in class {} which has no parent", ctx.name)
                     });
                 }
-                ClassMember::Method { .. } => methods.push(member),
-                _ => {
-                    return Err("enum members other than cases/methods are not supported in JS subset emitter".to_string());
-                }
+                _ => {}
             }
         }
+        match self.resolve_class_ref(&raw) {
+            Some(js) => Ok(js),
+            None => self.emit_expr(class),
+        }
+    }
 
-        self.enum_cases.insert(enum_name.clone(), cases.clone());
-
-        self.body.push_str(&format!("class {} {{\n", enum_name));
-        self.body.push_str("  constructor(__case, __payload) {\n");
-        self.body.push_str(&format!("    this.__enum = {};\n", json_string(&enum_name)));
-        self.body.push_str("    this.__case = __case;\n");
-        self.body.push_str("    if (__payload) {\n");
-        self.body.push_str("      Object.assign(this, __payload);\n");
-        self.body.push_str("    }\n");
-        self.body.push_str("  }\n");
+    fn class_ref_from_name(&self, name: Name<'_>) -> String {
+        let raw = self.name_text(name);
+        self.resolve_class_ref(&raw).unwrap_or_else(|| {
+            let ident = self.sanitize_name(&raw);
+            if self.is_declared(&ident) {
+                ident
+            } else {
+                format!("globalThis.{}", ident)
+            }
+        })
+    }
 
-        for member in methods {
-            if let ClassMember::Method { name, params, body, .. } = member {
-                let method_name = self.token_name(name);
-                let js_params = params
-                    .iter()
-                    .map(|p| self.token_name(p.name))
-                    .collect::<Vec<_>>()
-                    .join(", ");
-                let block = self.emit_method_block(params, body)?;
-                self.body
-                    .push_str(&format!("  {}({}) {{\n{}  }}\n", method_name, js_params, block));
+    /// `X::class`: the fully-qualified name as a string.
+    fn emit_class_name_literal(&mut self, class: ExprId<'_>) -> Result<String, String> {
+        if let Expr::Variable { name, .. } = class {
+            let raw = String::from_utf8_lossy(self.span_bytes(*name)).to_string();
+            if !raw.starts_with('$') {
+                let lower = raw.to_ascii_lowercase();
+                return match (&self.class_ctx, lower.as_str()) {
+                    (Some(ctx), "self") => Ok(json_string(&ctx.name)),
+                    (Some(_), "static" | "parent") => {
+                        Ok(format!("{}.__phpx_name", self.emit_class_ref(class)?))
+                    }
+                    _ => Ok(json_string(&self.resolve_class_name(&raw))),
+                };
             }
         }
+        let value = self.emit_expr(class)?;
+        Ok(format!("({}).constructor.__phpx_name", value))
+    }
 
-        for case in &cases {
-            if case.params.is_empty() {
-                self.body.push_str(&format!(
-                    "  static get {}() {{ return new {}({}, null); }}\n",
-                    case.name,
-                    enum_name,
-                    json_string(&case.name)
-                ));
-            } else {
-                let param_list = case.params.join(", ");
-                let payload_entries = case
-                    .params
-                    .iter()
-                    .map(|p| format!("{}: {}", json_string(p), p))
-                    .collect::<Vec<_>>()
-                    .join(", ");
-                self.body.push_str(&format!(
-                    "  static {}({}) {{ return new {}({}, {{{}}}); }}\n",
-                    case.name,
-                    param_list,
-                    enum_name,
-                    json_string(&case.name),
-                    payload_entries
-                ));
+    /// Member key for `Class::member` inside the class being lowered.
+    fn static_member_key(&self, class: ExprId<'_>, name: &str) -> String {
+        let Some(ctx) = &self.class_ctx else {
+            return name.to_string();
+        };
+        let own = matches!(class, Expr::Variable { name: span, .. } if {
+            let raw = self.span_bytes(*span);
+            raw.eq_ignore_ascii_case(b"self")
+                || raw.eq_ignore_ascii_case(b"static")
+                || self.resolve_class_ref(&String::from_utf8_lossy(raw)).as_deref()
+                    == Some(ctx.local.as_str())
+        });
+        if own {
+            self.member_key(&ctx.private_statics, name)
+        } else {
+            name.to_string()
+        }
+    }
+
+    /// Property/method key for `$obj->member` inside the class being lowered.
+    /// Only receivers known to be this class get the `#` key; anything else
+    /// may be a foreign object with a public member of the same name.
+    fn instance_member_key(&self, target: ExprId<'_>, name: String) -> String {
+        match &self.class_ctx {
+            Some(ctx) if ctx.private_members.contains(&name) && self.is_own_receiver(target) => {
+                format!("#{}", name)
             }
+            _ => name,
+        }
+    }
+
+    fn is_own_receiver(&self, target: ExprId<'_>) -> bool {
+        let Some(ctx) = &self.class_ctx else {
+            return false;
+        };
+        match target {
+            Expr::Variable { name, .. } => {
+                let raw = self.span_bytes(*name);
+                raw.eq_ignore_ascii_case(b"$this")
+                    || ctx.own_params.contains(&self.span_name(*name))
+            }
+            Expr::New { class, .. } => matches!(class, Expr::Variable { name, .. } if {
+                let raw = self.span_bytes(*name);
+                raw.eq_ignore_ascii_case(b"self")
+                    || raw.eq_ignore_ascii_case(b"static")
+                    || self.resolve_class_ref(&String::from_utf8_lossy(raw)).as_deref()
+                        == Some(ctx.local.as_str())
+            }),
+            _ => false,
         }
+    }
 
-        self.body.push_str("}\n");
-        Ok(())
+    /// Names of parameters declared as `self`, `static` or this class.
+    fn own_class_params(&self, params: &[Param<'_>]) -> HashSet<String> {
+        let Some(ctx) = &self.class_ctx else {
+            return HashSet::new();
+        };
+        params
+            .iter()
+            .filter(|param| {
+                let mut ty = param.ty;
+                while let Some(AstType::Nullable(inner)) = ty {
+                    ty = Some(*inner);
+                }
+                let raw = match ty {
+                    Some(AstType::Simple(tok)) => self.token_text(tok),
+                    Some(AstType::Name(name)) => self.name_text(*name),
+                    _ => return false,
+                };
+                raw.eq_ignore_ascii_case("self")
+                    || raw.eq_ignore_ascii_case("static")
+                    || self.resolve_class_ref(&raw).as_deref() == Some(ctx.local.as_str())
+            })
+            .map(|param| self.token_name(param.name))
+            .collect()
     }
 
+    fn name_text(&self, name: Name<'_>) -> String {
+        if name.parts.is_empty() {
+            return String::from_utf8_lossy(self.span_bytes(name.span))
+                .trim()
+                .to_string();
+        }
+        name.parts
+            .iter()
+            .map(|part| self.token_text(part))
+            .collect::<String>()
+    }
     fn emit_expr(&mut self, expr: ExprId<'_>) -> Result<String, String> {
         match expr {
             Expr::Variable { name, .. } => {
@@ -1187,7 +2409,11 @@ impl<'a> JsSubsetEmitter<'a> {
                         }
                     }
                 }
-                let rhs = self.emit_expr(*right)?;
+                let rhs = if matches!(op, BinaryOp::Instanceof) {
+                    self.emit_class_ref(*right)?
+                } else {
+                    self.emit_expr(*right)?
+                };
                 let js_op = match op {
                     BinaryOp::Plus => "+",
                     BinaryOp::Minus => "-",
@@ -1231,8 +2457,17 @@ impl<'a> JsSubsetEmitter<'a> {
                 for param in *params {
                     self.declare_in_scope(&self.token_name(param.name));
                 }
-                let body = self.emit_expr(*expr)?;
+                let mut body = self.emit_expr(*expr)?;
                 self.pop_scope();
+                if is_generator_expr(*expr) {
+                    body = format!(
+                        "globalThis.__phpx_generator(function* () {{ return {}; }}.call(this))",
+                        body
+                    );
+                }
+                if self.package.is_some() {
+                    body = format!("__deka$scope_call(() => {}, undefined, [])", body);
+                }
                 Ok(format!("({}) => {}", names.join(", "), body))
             }
             Expr::Closure {
@@ -1246,9 +2481,23 @@ impl<'a> JsSubsetEmitter<'a> {
                 for param in *params {
                     names.push(self.token_name(param.name));
                 }
-                let block = self.emit_method_block(params, body)?;
+                if *is_async && is_generator_body(body) {
+                    return Err(
+                        "async generators are not supported in JS subset emitter".to_string()
+                    );
+                }
+                let block = self.emit_method_block(params, body, *is_async)?;
                 let async_kw = if *is_async { "async " } else { "" };
-                let fn_expr = format!("{}function({}) {{\n{} }}", async_kw, names.join(", "), block);
+                let mut fn_expr = format!(
+                    "{}function({}) {{\n{} }}",
+                    async_kw,
+                    names.join(", "),
+                    block
+                );
+                // Closures declared in methods see `$this`, as in PHP.
+                if self.class_ctx.as_ref().is_some_and(|ctx| !ctx.in_static) {
+                    fn_expr = format!("({}).bind(this)", fn_expr);
+                }
                 if uses.is_empty() {
                     return Ok(fn_expr);
                 }
@@ -1304,20 +2553,18 @@ impl<'a> JsSubsetEmitter<'a> {
                 let rhs = self.emit_expr(*expr)?;
                 match self.emit_assignment_target(*var)? {
                     AssignmentTarget::Direct(target) => Ok(format!("({} = {})", target, rhs)),
-                    AssignmentTarget::Append(array) => Ok(format!(
-                        "(() => {{ const __arr = {}; const __val = {}; __arr.push(__val); return __val; }})()",
-                        array, rhs
-                    )),
+                    AssignmentTarget::Append(array) => {
+                        Ok(format!("globalThis.__phpx_array_push({}, {})", array, rhs))
+                    }
                 }
             }
             Expr::AssignRef { var, expr, .. } => {
                 let rhs = self.emit_expr(*expr)?;
                 match self.emit_assignment_target(*var)? {
                     AssignmentTarget::Direct(target) => Ok(format!("({} = {})", target, rhs)),
-                    AssignmentTarget::Append(array) => Ok(format!(
-                        "(() => {{ const __arr = {}; const __val = {}; __arr.push(__val); return __val; }})()",
-                        array, rhs
-                    )),
+                    AssignmentTarget::Append(array) => {
+                        Ok(format!("globalThis.__phpx_array_push({}, {})", array, rhs))
+                    }
                 }
             }
             Expr::AssignOp { var, op, expr, .. } => {
@@ -1339,10 +2586,18 @@ impl<'a> JsSubsetEmitter<'a> {
                 };
                 match self.emit_assignment_target(*var)? {
                     AssignmentTarget::Direct(target) => Ok(format!("({} {} {})", target, js_op, rhs)),
-                    AssignmentTarget::Append(_) => Err(
-                        "assign-op on append array access is not supported in subset emitter"
-                            .to_string(),
-                    ),
+                    // `$a[] op= x` appends `null op x`.
+                    AssignmentTarget::Append(array) => {
+                        let value = match op {
+                            php_rs::parser::ast::AssignOp::Coalesce => rhs,
+                            php_rs::parser::ast::AssignOp::Concat => format!("('' + {})", rhs),
+                            _ => format!("(null {} {})", js_op.trim_end_matches('='), rhs),
+                        };
+                        Ok(format!(
+                            "globalThis.__phpx_array_push({}, {})",
+                            array, value
+                        ))
+                    }
                 }
             }
             Expr::PropertyFetch {
@@ -1351,7 +2606,7 @@ impl<'a> JsSubsetEmitter<'a> {
                 let target_js = self.emit_expr(*target)?;
                 match *property {
                     Expr::Variable { name, .. } => {
-                        let prop = self.span_name(*name);
+                        let prop = self.instance_member_key(*target, self.span_name(*name));
                         Ok(format!("{}.{}", target_js, prop))
                     }
                     _ => {
@@ -1368,7 +2623,9 @@ impl<'a> JsSubsetEmitter<'a> {
             } => {
                 let target_js = self.emit_expr(*target)?;
                 let method_name = match *method {
-                    Expr::Variable { name, .. } => self.span_name(*name),
+                    Expr::Variable { name, .. } => {
+                        self.instance_member_key(*target, self.span_name(*name))
+                    }
                     _ => {
                         return Err(
                             "dynamic method calls are not supported in subset emitter".to_string()
@@ -1384,7 +2641,6 @@ impl<'a> JsSubsetEmitter<'a> {
                 args,
                 ..
             } => {
-                let class_js = self.emit_expr(*class)?;
                 let method_name = match *method {
                     Expr::Variable { name, .. } => self.span_name(*name),
                     _ => {
@@ -1395,12 +2651,33 @@ impl<'a> JsSubsetEmitter<'a> {
                     }
                 };
                 let args_js = self.emit_call_args(args)?;
-                Ok(format!("{}.{}({})", class_js, method_name, args_js))
+                if self.is_parent_ref(*class) {
+                    if method_name.eq_ignore_ascii_case("__construct") {
+                        return Ok(format!("super({})", args_js));
+                    }
+                    return Ok(format!("super.{}({})", method_name, args_js));
+                }
+                // `self::helper()` / `static::helper()` on an instance method
+                // keeps `$this`, as in PHP.
+                if let Some(ctx) = &self.class_ctx
+                    && !ctx.in_static
+                    && ctx
+                        .instance_methods
+                        .contains(&method_name.to_ascii_lowercase())
+                    && matches!(class, Expr::Variable { name, .. }
+                        if self.span_bytes(*name).eq_ignore_ascii_case(b"self")
+                            || self.span_bytes(*name).eq_ignore_ascii_case(b"static"))
+                {
+                    let key = self.member_key(&ctx.private_members, &method_name);
+                    return Ok(format!("this.{}({})", key, args_js));
+                }
+                let class_js = self.emit_class_ref(*class)?;
+                let key = self.static_member_key(*class, &method_name);
+                Ok(format!("{}.{}({})", class_js, key, args_js))
             }
             Expr::ClassConstFetch {
                 class, constant, ..
             } => {
-                let class_js = self.emit_expr(*class)?;
                 let const_name = match *constant {
                     Expr::Variable { name, .. } => self.span_name(*name),
                     _ => {
@@ -1410,10 +2687,17 @@ impl<'a> JsSubsetEmitter<'a> {
                         );
                     }
                 };
-                Ok(format!("{}.{}", class_js, const_name))
+                if matches!(constant, Expr::Variable { name, .. }
+                    if self.span_bytes(*name).eq_ignore_ascii_case(b"class"))
+                {
+                    return self.emit_class_name_literal(*class);
+                }
+                let class_js = self.emit_class_ref(*class)?;
+                let key = self.static_member_key(*class, &const_name);
+                Ok(format!("{}.{}", class_js, key))
             }
             Expr::New { class, args, .. } => {
-                let class_js = self.emit_expr(*class)?;
+                let class_js = self.emit_class_ref(*class)?;
                 let args_js = self.emit_call_args(args)?;
                 Ok(format!("new {}({})", class_js, args_js))
             }
@@ -1423,7 +2707,7 @@ impl<'a> JsSubsetEmitter<'a> {
                 let target_js = self.emit_expr(*target)?;
                 match *property {
                     Expr::Variable { name, .. } => {
-                        let prop = self.span_name(*name);
+                        let prop = self.instance_member_key(*target, self.span_name(*name));
                         Ok(format!("({})?.{}", target_js, prop))
                     }
                     _ => {
@@ -1440,7 +2724,9 @@ impl<'a> JsSubsetEmitter<'a> {
             } => {
                 let target_js = self.emit_expr(*target)?;
                 let method_name = match *method {
-                    Expr::Variable { name, .. } => self.span_name(*name),
+                    Expr::Variable { name, .. } => {
+                        self.instance_member_key(*target, self.span_name(*name))
+                    }
                     _ => {
                         return Err(
                             "dynamic nullsafe method calls are not supported in subset emitter"
@@ -1478,22 +2764,17 @@ impl<'a> JsSubsetEmitter<'a> {
                 }
             }
             Expr::Array { items, .. } => {
-                if !items.iter().all(|item| !item.by_ref && !item.unpack) {
-                    return Err("mixed or complex array items are not supported in subset emitter"
-                        .to_string());
+                if items.iter().any(|item| item.by_ref) {
+                    return Err(
+                        "by-reference array items are not supported in subset emitter".to_string(),
+                    );
                 }
 
-                let mut has_keys = false;
-                let mut has_no_keys = false;
-                for item in *items {
-                    if item.key.is_some() {
-                        has_keys = true;
-                    } else {
-                        has_no_keys = true;
-                    }
-                }
+                let has_unpack = items.iter().any(|item| item.unpack);
+                let has_keys = items.iter().any(|item| item.key.is_some());
+                let has_no_keys = items.iter().any(|item| item.key.is_none());
 
-                if !has_keys {
+                if !has_keys && !has_unpack {
                     let mut values = Vec::new();
                     for item in *items {
                         values.push(self.emit_expr(item.value)?);
@@ -1501,46 +2782,39 @@ impl<'a> JsSubsetEmitter<'a> {
                     return Ok(format!("[{}]", values.join(", ")));
                 }
 
-                if !has_no_keys {
+                if !has_no_keys
+                    && let Some(keys) = items
+                        .iter()
+                        .map(|item| {
+                            item.key
+                                .and_then(|key| self.emit_static_array_key(key).ok())
+                        })
+                        .collect::<Option<Vec<_>>>()
+                    && !keys.iter().any(|key| is_integer_key(key))
+                {
                     let mut entries = Vec::new();
-                    let mut all_static = true;
-                    for item in *items {
-                        let key_expr = item
-                            .key
-                            .ok_or_else(|| "keyed array expected key".to_string())?;
-                        let key = match self.emit_static_array_key(key_expr) {
-                            Ok(value) => value,
-                            Err(_) => {
-                                all_static = false;
-                                break;
-                            }
-                        };
+                    for (key, item) in keys.iter().zip(items.iter()) {
                         let value = self.emit_expr(item.value)?;
-                        entries.push(format!("{}: {}", json_string(&key), value));
-                    }
-                    if all_static {
-                        return Ok(format!("{{{}}}", entries.join(", ")));
+                        entries.push(format!("{}: {}", json_string(key), value));
                     }
+                    return Ok(format!("{{{}}}", entries.join(", ")));
                 }
 
-                let mut out = String::new();
-                out.push_str("(() => { const __out = []; let __idx = 0;\n");
-                let mut key_idx = 0;
+                // Computed keys, spreads and mixed items go through the runtime
+                // so key coercion, auto-increment indexes and overwrites follow PHP.
+                let mut entries = Vec::new();
                 for item in *items {
-                    if let Some(key_expr) = item.key {
+                    let value_js = self.emit_expr(item.value)?;
+                    if item.unpack {
+                        entries.push(format!("{{ spread: {} }}", value_js));
+                    } else if let Some(key_expr) = item.key {
                         let key_js = self.emit_expr(key_expr)?;
-                        let value_js = self.emit_expr(item.value)?;
-                        out.push_str(&format!(
-                            "const __key{key_idx} = {key_js}; __out[__key{key_idx}] = {value_js}; if (typeof __key{key_idx} === 'number' && Number.isInteger(__key{key_idx})) {{ __idx = Math.max(__idx, __key{key_idx} + 1); }}\n",
-                        ));
-                        key_idx += 1;
+                        entries.push(format!("{{ key: {}, value: {} }}", key_js, value_js));
                     } else {
-                        let value_js = self.emit_expr(item.value)?;
-                        out.push_str(&format!("__out[__idx++] = {};\n", value_js));
+                        entries.push(format!("{{ value: {} }}", value_js));
                     }
                 }
-                out.push_str("return __out; })()");
-                Ok(out)
+                Ok(format!("globalThis.__phpx_array([{}])", entries.join(", ")))
             }
             Expr::ObjectLiteral { items, .. } => {
                 let mut entries = Vec::new();
@@ -1591,7 +2865,7 @@ impl<'a> JsSubsetEmitter<'a> {
                     php_rs::parser::ast::CastKind::Bool => format!("Boolean({})", value),
                     php_rs::parser::ast::CastKind::Array => {
                         format!(
-                            "Array.isArray({0}) ? {0} : ({0} && typeof {0} === 'object' ? Object.fromEntries(Object.entries({0})) : [{0}])",
+                            "Array.isArray({0}) ? {0} : ({0} && typeof {0} === 'object' ? globalThis.__phpx_assoc(Object.entries({0})) : [{0}])",
                             value
                         )
                     }
@@ -1637,6 +2911,12 @@ impl<'a> JsSubsetEmitter<'a> {
             }
             Expr::Await { expr, .. } => {
                 let value = self.emit_expr(*expr)?;
+                if self.package.is_some() {
+                    return Ok(format!(
+                        "__deka$scope_resumed(__deka$scope, await __deka$scope_await(__deka$scope, {}))",
+                        value
+                    ));
+                }
                 Ok(format!("(await {})", value))
             }
             Expr::Eval { expr, .. } => {
@@ -1661,8 +2941,30 @@ impl<'a> JsSubsetEmitter<'a> {
             Expr::ShellExec { .. } => {
                 Err("shell execution is not supported in JS subset emitter".to_string())
             }
-            Expr::Yield { .. } => {
-                Err("yield expressions are not supported in JS subset emitter".to_string())
+            Expr::Yield {
+                key, value, from, ..
+            } => {
+                if *from {
+                    let source = match value {
+                        Some(value) => self.emit_expr(*value)?,
+                        None => return Err("yield from requires an operand".to_string()),
+                    };
+                    return Ok(format!("(yield* globalThis.__phpx_yield_from({}))", source));
+                }
+                let value_js = match value {
+                    Some(value) => self.emit_expr(*value)?,
+                    None => "null".to_string(),
+                };
+                match key {
+                    Some(key) => {
+                        let key_js = self.emit_expr(*key)?;
+                        Ok(format!(
+                            "((yield new globalThis.__phpx_yield_pair({}, {})) ?? null)",
+                            key_js, value_js
+                        ))
+                    }
+                    None => Ok(format!("((yield {}) ?? null)", value_js)),
+                }
             }
             Expr::AnonymousClass { .. } => {
                 Err("anonymous classes are not supported in JS subset emitter".to_string())
@@ -1806,19 +3108,18 @@ impl<'a> JsSubsetEmitter<'a> {
     fn emit_stmt_block_inline(&mut self, stmts: &[StmtId<'_>]) -> Result<String, String> {
         let saved = std::mem::take(&mut self.body);
         self.push_scope();
-        for stmt in stmts {
-            self.emit_stmt(*stmt)?;
-        }
+        let result = stmts.iter().try_for_each(|stmt| self.emit_stmt(*stmt));
         self.pop_scope();
         let block = std::mem::take(&mut self.body);
         self.body = saved;
-        Ok(block)
+        result.map(|()| block)
     }
 
     fn emit_method_block(
         &mut self,
         params: &[php_rs::parser::ast::Param<'_>],
         stmts: &[StmtId<'_>],
+        is_async: bool,
     ) -> Result<String, String> {
         let saved = std::mem::take(&mut self.body);
         self.push_scope();
@@ -1829,13 +3130,48 @@ impl<'a> JsSubsetEmitter<'a> {
         if !defaults.is_empty() {
             self.body.push_str(&defaults);
         }
-        for stmt in stmts {
-            self.emit_stmt(*stmt)?;
+        // Generator bodies run lazily behind a PHP-style `Generator`, after
+        // the parameter defaults have been applied eagerly as PHP does.
+        let generator = is_generator_body(stmts);
+        if generator {
+            self.body
+                .push_str("return globalThis.__phpx_generator(function* () {\n");
+        }
+        let result = stmts.iter().try_for_each(|stmt| self.emit_stmt(*stmt));
+        if generator {
+            self.body.push_str("}.apply(this, arguments));\n");
         }
         self.pop_scope();
         let block = std::mem::take(&mut self.body);
         self.body = saved;
-        Ok(block)
+        result.map(|()| self.scoped_body(block, is_async))
+    }
+
+    /// In package modules, method and closure bodies run inside the
+    /// package's security scope. The arrow keeps `this`, `super` and
+    /// `arguments` of the enclosing method.
+    fn scoped_body(&self, block: String, is_async: bool) -> String {
+        if self.package.is_none() {
+            return block;
+        }
+        let async_kw = if is_async { "async " } else { "" };
+        format!(
+            "return __deka$scope_call({}() => {{\n{}}}, undefined, []);\n",
+            async_kw,
+            self.async_scoped(block, is_async)
+        )
+    }
+
+    /// Async package bodies own a scope for the whole call; each `await`
+    /// parks it and the continuation resumes it.
+    fn async_scoped(&self, block: String, is_async: bool) -> String {
+        if self.package.is_none() || !is_async {
+            return block;
+        }
+        format!(
+            "const __deka$scope = __deka$scope_begin();\ntry {{\n{}}} finally {{\n__deka$scope_end(__deka$scope);\n}}\n",
+            block
+        )
     }
 
     fn emit_expr_list(&mut self, exprs: &[ExprId<'_>]) -> Result<String, String> {
@@ -1898,10 +3234,15 @@ impl<'a> JsSubsetEmitter<'a> {
     }
 
     fn emit_call_args(&mut self, args: &[php_rs::parser::ast::Arg<'_>]) -> Result<String, String> {
-        if args.iter().all(|a| a.name.is_none() && !a.unpack) {
+        if args.iter().all(|a| a.name.is_none()) {
             let mut rendered = Vec::with_capacity(args.len());
             for arg in args {
-                rendered.push(self.emit_expr(arg.value)?);
+                let value = self.emit_expr(arg.value)?;
+                if arg.unpack {
+                    rendered.push(format!("...{}", value));
+                } else {
+                    rendered.push(value);
+                }
             }
             return Ok(rendered.join(", "));
         }
@@ -2034,7 +3375,6 @@ impl<'a> JsSubsetEmitter<'a> {
                 Ok(String::from_utf8_lossy(bytes).to_string())
             }
             Expr::Integer { value, .. } => Ok(String::from_utf8_lossy(value).to_string()),
-            Expr::Variable { name, .. } => Ok(self.span_name(*name)),
             _ => {
                 Err("array key must be static string/int/identifier in subset emitter".to_string())
             }
@@ -2052,7 +3392,11 @@ impl<'a> JsSubsetEmitter<'a> {
             Expr::PropertyFetch { target, property, .. } => {
                 let target_js = self.emit_expr(*target)?;
                 match *property {
-                    Expr::Variable { name, .. } => Ok(format!("{}.{}", target_js, self.span_name(*name))),
+                    Expr::Variable { name, .. } => Ok(format!(
+                        "{}.{}",
+                        target_js,
+                        self.instance_member_key(*target, self.span_name(*name))
+                    )),
                     _ => {
                         let prop = self.emit_expr(*property)?;
                         Ok(format!("{}[{}]", target_js, prop))
@@ -2068,6 +3412,7 @@ impl<'a> JsSubsetEmitter<'a> {
                     Err("append array access is not supported in assignable expressions".to_string())
                 }
             }
+            Expr::ClassConstFetch { .. } => self.emit_expr(expr),
             _ => Err("assignment target is not supported in subset emitter".to_string()),
         }
     }
@@ -2078,18 +3423,66 @@ impl<'a> JsSubsetEmitter<'a> {
     ) -> Result<AssignmentTarget, String> {
         match expr {
             Expr::ArrayDimFetch { array, dim, .. } => {
-                let array_js = self.emit_expr(*array)?;
                 if let Some(dim) = dim {
+                    let array_js = if has_append(*array) {
+                        self.emit_append_container(*array)?
+                    } else {
+                        self.emit_expr(*array)?
+                    };
                     let dim_js = self.emit_expr(*dim)?;
                     Ok(AssignmentTarget::Direct(format!("{}[{}]", array_js, dim_js)))
                 } else {
-                    Ok(AssignmentTarget::Append(array_js))
+                    Ok(AssignmentTarget::Append(
+                        self.emit_append_container(*array)?,
+                    ))
                 }
             }
             _ => Ok(AssignmentTarget::Direct(self.emit_assignable_expr(expr)?)),
         }
     }
 
+    /// The array an append writes into, created on first use like PHP's
+    /// auto-vivification (`$a['k'][] = 1`, `$a[][] = 1`).
+    fn emit_append_container(&mut self, expr: ExprId<'_>) -> Result<String, String> {
+        match expr {
+            Expr::ArrayDimFetch {
+                array, dim: None, ..
+            } => Ok(format!(
+                "globalThis.__phpx_array_push({}, [])",
+                self.emit_append_container(*array)?
+            )),
+            Expr::ArrayDimFetch {
+                array,
+                dim: Some(dim),
+                ..
+            } if has_append(*array) => {
+                let array_js = self.emit_append_container(*array)?;
+                let dim_js = self.emit_expr(*dim)?;
+                Ok(format!("({}[{}] ??= [])", array_js, dim_js))
+            }
+            _ => Ok(format!("({} ??= [])", self.emit_assignable_expr(expr)?)),
+        }
+    }
+
+    fn undeclared_append_base(&self, expr: ExprId<'_>) -> Option<String> {
+        let (Expr::Assign { var, .. } | Expr::AssignRef { var, .. } | Expr::AssignOp { var, .. }) =
+            expr
+        else {
+            return None;
+        };
+        let mut target = *var;
+        let mut appends = false;
+        while let Expr::ArrayDimFetch { array, dim, .. } = target {
+            appends |= dim.is_none();
+            target = *array;
+        }
+        let Expr::Variable { name, .. } = target else {
+            return None;
+        };
+        let ident = self.span_name(*name);
+        (appends && ident != "this" && !self.is_declared(&ident)).then_some(ident)
+    }
+
     fn assignment_to_named_var(
         &mut self,
         expr: ExprId<'_>,
@@ -2281,7 +3674,7 @@ impl<'a> JsSubsetEmitter<'a> {
                     .map(|p| self.token_name(p.name))
                     .collect::<Vec<_>>()
                     .join(", ");
-                let block = self.emit_method_block(params, body)?;
+                let block = self.emit_method_block(params, body, false)?;
                 methods.push((
                     method_name,
                     format!("function({}) {{\n{} }}", js_params, block),
@@ -2297,6 +3690,13 @@ impl<'a> JsSubsetEmitter<'a> {
                 "string" => ("{ kind: 'string' }".to_string(), false),
                 "int" | "float" | "number" => ("{ kind: 'number' }".to_string(), false),
                 "bool" | "boolean" => ("{ kind: 'boolean' }".to_string(), false),
+                "true" | "false" => (
+                    format!(
+                        "{{ kind: 'literal', value: {} }}",
+                        self.token_text(tok).to_ascii_lowercase()
+                    ),
+                    false,
+                ),
                 _ => ("{ kind: 'unknown' }".to_string(), false),
             },
             AstType::Name(_) => ("{ kind: 'object' }".to_string(), false),
@@ -2318,6 +3718,16 @@ impl<'a> JsSubsetEmitter<'a> {
                 )
             }
             AstType::Intersection(_parts) => ("{ kind: 'object' }".to_string(), false),
+            AstType::Function { .. } => ("{ kind: 'unknown' }".to_string(), false),
+            AstType::Literal(tok) => {
+                let value = if tok.kind == php_rs::parser::lexer::token::TokenKind::StringLiteral {
+                    self.encode_php_string_literal(tok.text(self.source))
+                } else {
+                    self.token_text(tok).replace('_', "")
+                };
+                (format!("{{ kind: 'literal', value: {} }}", value), false)
+            }
+            AstType::KeyOf(_) => ("{ kind: 'string' }".to_string(), false),
             AstType::ObjectShape(shape_fields) => {
                 let fields = shape_fields
                     .iter()
@@ -2340,6 +3750,19 @@ impl<'a> JsSubsetEmitter<'a> {
                     false,
                 )
             }
+            AstType::Tuple(items) => {
+                let schemas = items
+                    .iter()
+                    .map(|item| self.emit_type_schema(item).0)
+                    .collect::<Vec<_>>();
+                (
+                    format!(
+                        "{{ kind: 'array', item: {{ kind: 'union', anyOf: [{}] }} }}",
+                        schemas.join(", ")
+                    ),
+                    false,
+                )
+            }
             AstType::Applied { base, args } => {
                 if let AstType::Simple(tok) = *base {
                     let base_name = self.token_name(tok);
@@ -2350,9 +3773,9 @@ impl<'a> JsSubsetEmitter<'a> {
                             .unwrap_or_else(|| "{ kind: 'unknown' }".to_string());
                         return (format!("{{ kind: 'optional', inner: {} }}", inner), true);
                     }
-                    if base_name == "array" || base_name == "Vec" {
+                    if base_name == "array" || base_name == "list" || base_name == "Vec" {
                         let inner = args
-                            .first()
+                            .last()
                             .map(|t| self.emit_type_schema(t).0)
                             .unwrap_or_else(|| "{ kind: 'unknown' }".to_string());
                         return (format!("{{ kind: 'array', item: {} }}", inner), false);
@@ -2450,6 +3873,43 @@ fn unescape_php_double(raw: &str) -> String {
     out
 }
 
+/// Resolve statement marks recorded against `buffer` into generated
+/// `(line, column, source offset)` triples, with `buffer` starting at `first_line`.
+fn resolve_marks(text: &str, marks: &[usize]) -> (String, Vec<(usize, usize, usize)>) {
+    let mut out = String::with_capacity(text.len());
+    let mut mappings = Vec::new();
+    let mut line = 0;
+    let mut line_start = 0;
+    let mut pending = Vec::new();
+    let mut parts = text.split('\0');
+    while let Some(code) = parts.next() {
+        for ch in code.chars() {
+            if !pending.is_empty() && ch != ' ' {
+                let column = out.len() - line_start;
+                for offset in pending.drain(..) {
+                    mappings.push((line, column, offset));
+                }
+            }
+            out.push(ch);
+            if ch == '\n' {
+                line += 1;
+                line_start = out.len();
+            }
+        }
+        if let Some(index) = parts.next()
+            && let Some(&offset) = index.parse::<usize>().ok().and_then(|idx| marks.get(idx))
+        {
+            pending.push(offset);
+        }
+    }
+    (out, mappings)
+}
+
+/// Whether a buffer holds anything besides statement marks.
+fn has_code(buffer: &str) -> bool {
+    buffer.split('\0').step_by(2).any(|part| !part.is_empty())
+}
+
 fn add_or_merge_import(imports: &mut Vec<ImportDecl>, from: &str, specs: Vec<ImportSpec>) {
     if let Some(existing) = imports.iter_mut().find(|decl| decl.from == from) {
         for spec in specs {
@@ -2491,6 +3951,87 @@ fn emit_deka_i_runtime() -> String {
     include_str!("deka_i_runtime.js").to_string()
 }
 
+fn qualify(namespace: &str, name: &str) -> String {
+    let name = name.trim_start_matches('\\');
+    if namespace.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}\\{}", namespace, name)
+    }
+}
+
+fn has_modifier(
+    source: &[u8],
+    modifiers: &[php_rs::parser::lexer::token::Token],
+    name: &str,
+) -> bool {
+    modifiers
+        .iter()
+        .any(|m| m.text(source).eq_ignore_ascii_case(name.as_bytes()))
+}
+
+/// Finds `yield` in a function body, without descending into nested
+/// functions, closures or classes (their yields belong to them).
+#[derive(Default)]
+struct YieldFinder {
+    found: bool,
+}
+
+impl<'ast> Visitor<'ast> for YieldFinder {
+    fn visit_stmt(&mut self, stmt: StmtId<'ast>) {
+        match stmt {
+            Stmt::Function { .. }
+            | Stmt::Class { .. }
+            | Stmt::Interface { .. }
+            | Stmt::Trait { .. }
+            | Stmt::Enum { .. } => {}
+            _ if !self.found => walk_stmt(self, stmt),
+            _ => {}
+        }
+    }
+
+    fn visit_expr(&mut self, expr: ExprId<'ast>) {
+        match expr {
+            Expr::Yield { .. } => self.found = true,
+            Expr::Closure { .. } | Expr::ArrowFunction { .. } | Expr::AnonymousClass { .. } => {}
+            _ if !self.found => walk_expr(self, expr),
+            _ => {}
+        }
+    }
+}
+
+fn has_append(expr: ExprId<'_>) -> bool {
+    match expr {
+        Expr::ArrayDimFetch { dim: None, .. } => true,
+        Expr::ArrayDimFetch { array, .. } => has_append(array),
+        _ => false,
+    }
+}
+
+fn is_generator_body<'ast>(stmts: &[StmtId<'ast>]) -> bool {
+    let mut finder = YieldFinder::default();
+    for stmt in stmts {
+        finder.visit_stmt(*stmt);
+    }
+    finder.found
+}
+
+fn is_generator_expr<'ast>(expr: ExprId<'ast>) -> bool {
+    let mut finder = YieldFinder::default();
+    finder.visit_expr(expr);
+    finder.found
+}
+
+/// Whether PHP stores `key` as an integer array key (`"5"`, `"-3"`, not `"05"`).
+fn is_integer_key(key: &str) -> bool {
+    let digits = key.strip_prefix('-').unwrap_or(key);
+    !digits.is_empty()
+        && digits.bytes().all(|b| b.is_ascii_digit())
+        && (digits == "0" || !digits.starts_with('0'))
+        && key != "-0"
+        && key.parse::<i64>().is_ok()
+}
+
 fn json_string(input: &str) -> String {
     serde_json::to_string(input).unwrap_or_else(|_| "\"\"".to_string())
 }
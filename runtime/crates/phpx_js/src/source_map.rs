@@ -0,0 +1,261 @@
+//! Source map v3 output for emitted PHPX modules.
+//!
+//! The subset emitter records a mapping at the start of every lowered
+//! statement, which is enough for debuggers to place breakpoints and step
+//! through `.phpx` source line by line.
+
+use base64::Engine;
+use php_rs::parser::line_index::LineIndex;
+use runtime_core::vlq::{decode_vlq, encode_vlq};
+
+/// One generated position mapped back to the PHPX source (all 0-based).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mapping {
+    pub generated_line: usize,
+    pub generated_column: usize,
+    pub source_line: usize,
+    pub source_column: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct SourceMap {
+    pub file: String,
+    pub source: String,
+    pub source_content: Option<String>,
+    pub mappings: Vec<Mapping>,
+}
+
+impl SourceMap {
+    pub fn new(file: impl Into<String>, source: impl Into<String>) -> Self {
+        Self {
+            file: file.into(),
+            source: source.into(),
+            source_content: None,
+            mappings: Vec::new(),
+        }
+    }
+
+    pub fn with_source_content(mut self, content: impl Into<String>) -> Self {
+        self.source_content = Some(content.into());
+        self
+    }
+
+    /// Record a mapping from a generated position to a byte offset in the source.
+    pub fn add(
+        &mut self,
+        generated_line: usize,
+        generated_column: usize,
+        source_index: &LineIndex,
+        source_offset: usize,
+    ) {
+        let (source_line, source_column) = source_index.line_col(source_offset);
+        self.mappings.push(Mapping {
+            generated_line,
+            generated_column,
+            source_line,
+            source_column,
+        });
+    }
+
+    /// Look up the source position for a generated position, using the closest
+    /// preceding mapping on the same line.
+    pub fn lookup(&self, generated_line: usize, generated_column: usize) -> Option<Mapping> {
+        self.mappings
+            .iter()
+            .filter(|m| {
+                m.generated_line == generated_line && m.generated_column <= generated_column
+            })
+            .max_by_key(|m| m.generated_column)
+            .copied()
+    }
+
+    /// Like `lookup`, but falls back to the last mapping on an earlier line,
+    /// which covers statements whose generated code spans several lines.
+    pub fn lookup_nearest(
+        &self,
+        generated_line: usize,
+        generated_column: usize,
+    ) -> Option<Mapping> {
+        self.mappings
+            .iter()
+            .filter(|m| {
+                (m.generated_line, m.generated_column) <= (generated_line, generated_column)
+            })
+            .max_by_key(|m| (m.generated_line, m.generated_column))
+            .copied()
+    }
+
+    /// Account for `lines` lines of code prepended to the generated module.
+    pub fn offset_generated_lines(&mut self, lines: usize) {
+        for mapping in &mut self.mappings {
+            mapping.generated_line += lines;
+        }
+    }
+
+    pub fn encoded_mappings(&self) -> String {
+        // Later mappings at the same position are the more specific (nested) statement.
+        let mut sorted = self.mappings.clone();
+        sorted.sort_by_key(|m| (m.generated_line, m.generated_column));
+        let mut mappings: Vec<Mapping> = Vec::with_capacity(sorted.len());
+        for mapping in sorted {
+            match mappings.last_mut() {
+                Some(last)
+                    if (last.generated_line, last.generated_column)
+                        == (mapping.generated_line, mapping.generated_column) =>
+                {
+                    *last = mapping
+                }
+                _ => mappings.push(mapping),
+            }
+        }
+
+        let mut out = String::new();
+        let mut line = 0;
+        let mut prev_generated_column = 0i64;
+        let mut prev_source_line = 0i64;
+        let mut prev_source_column = 0i64;
+        let mut first_in_line = true;
+        for mapping in mappings {
+            while line < mapping.generated_line {
+                out.push(';');
+                line += 1;
+                prev_generated_column = 0;
+                first_in_line = true;
+            }
+            if !first_in_line {
+                out.push(',');
+            }
+            first_in_line = false;
+            encode_vlq(
+                &mut out,
+                mapping.generated_column as i64 - prev_generated_column,
+            );
+            encode_vlq(&mut out, 0);
+            encode_vlq(&mut out, mapping.source_line as i64 - prev_source_line);
+            encode_vlq(&mut out, mapping.source_column as i64 - prev_source_column);
+            prev_generated_column = mapping.generated_column as i64;
+            prev_source_line = mapping.source_line as i64;
+            prev_source_column = mapping.source_column as i64;
+        }
+        out
+    }
+
+    pub fn to_json(&self) -> String {
+        let mut map = serde_json::json!({
+            "version": 3,
+            "file": self.file,
+            "sources": [self.source],
+            "names": [],
+            "mappings": self.encoded_mappings(),
+        });
+        if let Some(content) = &self.source_content {
+            map["sourcesContent"] = serde_json::json!([content]);
+        }
+        map.to_string()
+    }
+
+    /// Parse a map written by `to_json`, e.g. one read back from the compile cache.
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let value: serde_json::Value =
+            serde_json::from_str(json).map_err(|err| format!("invalid source map: {}", err))?;
+        let field = |key: &str| value.get(key).and_then(|v| v.as_str());
+        let first = |key: &str| {
+            value
+                .get(key)
+                .and_then(|v| v.get(0))
+                .and_then(|v| v.as_str())
+        };
+        let mut map = SourceMap::new(
+            field("file").unwrap_or_default(),
+            first("sources").unwrap_or_default(),
+        );
+        map.source_content = first("sourcesContent").map(str::to_string);
+
+        let mappings = field("mappings").ok_or("source map has no mappings")?;
+        let (mut source_line, mut source_column) = (0i64, 0i64);
+        for (generated_line, encoded) in mappings.split(';').enumerate() {
+            let mut generated_column = 0i64;
+            for segment in encoded.split(',').filter(|s| !s.is_empty()) {
+                let fields = decode_vlq(segment)?;
+                generated_column += fields[0];
+                if fields.len() < 4 {
+                    continue;
+                }
+                source_line += fields[2];
+                source_column += fields[3];
+                map.mappings.push(Mapping {
+                    generated_line,
+                    generated_column: generated_column as usize,
+                    source_line: source_line as usize,
+                    source_column: source_column as usize,
+                });
+            }
+        }
+        Ok(map)
+    }
+
+    /// `//# sourceMappingURL=` comment pointing at an external map file.
+    pub fn to_url_comment(url: &str) -> String {
+        format!("//# sourceMappingURL={}\n", url)
+    }
+
+    /// `//# sourceMappingURL=` comment embedding the map as a data URL.
+    pub fn to_inline_comment(&self) -> String {
+        let encoded = base64::engine::general_purpose::STANDARD.encode(self.to_json());
+        format!(
+            "//# sourceMappingURL=data:application/json;charset=utf-8;base64,{}\n",
+            encoded
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_vlq_segments_relative_to_previous() {
+        let index = LineIndex::new(b"<?php\n$a = 1;\necho $a;\n");
+        let mut map = SourceMap::new("main.js", "main.phpx");
+        map.add(2, 0, &index, 6);
+        map.add(3, 2, &index, 14);
+        assert_eq!(map.encoded_mappings(), ";;AACA;EACA");
+    }
+
+    #[test]
+    fn looks_up_closest_preceding_mapping_on_line() {
+        let index = LineIndex::new(b"a\nb\nc\n");
+        let mut map = SourceMap::new("out.js", "in.phpx");
+        map.add(0, 0, &index, 0);
+        map.add(0, 10, &index, 4);
+        assert_eq!(map.lookup(0, 12).map(|m| m.source_line), Some(2));
+        assert_eq!(map.lookup(0, 5).map(|m| m.source_line), Some(0));
+        assert_eq!(map.lookup(1, 0), None);
+    }
+
+    #[test]
+    fn nearest_lookup_falls_back_to_earlier_lines() {
+        let index = LineIndex::new(b"a\nb\nc\n");
+        let mut map = SourceMap::new("out.js", "in.phpx");
+        map.add(1, 2, &index, 2);
+        map.offset_generated_lines(3);
+        assert_eq!(map.lookup(6, 0), None);
+        assert_eq!(map.lookup_nearest(6, 0).map(|m| m.source_line), Some(1));
+        assert_eq!(map.lookup_nearest(4, 1), None);
+    }
+
+    #[test]
+    fn parses_what_it_writes() {
+        let index = LineIndex::new(b"a\nbb\nccc\n");
+        let mut map = SourceMap::new("out.js", "in.phpx").with_source_content("a\nbb\nccc\n");
+        map.add(0, 4, &index, 5);
+        map.add(2, 0, &index, 2);
+        map.add(2, 7, &index, 0);
+
+        let parsed = SourceMap::from_json(&map.to_json()).expect("parse");
+        assert_eq!(parsed.file, "out.js");
+        assert_eq!(parsed.source, "in.phpx");
+        assert_eq!(parsed.source_content, map.source_content);
+        assert_eq!(parsed.mappings, map.mappings);
+    }
+}
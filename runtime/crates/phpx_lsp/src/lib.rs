@@ -1,21 +1,25 @@
 use bumpalo::Bump;
 use modules_php::compiler_api::compile_phpx;
-use modules_php::validation::{Severity, ValidationError, ValidationWarning};
+use modules_php::validation::{Fix, RelatedLocation, Severity, ValidationError, ValidationWarning};
 use php_rs::parser::ast::{
     BinaryOp, ClassKind, ClassMember, Expr, ExprId, Name, ObjectKey, Param, Program, Stmt, StmtId,
     Type,
 };
 use php_rs::parser::lexer::token::Token;
 use php_rs::parser::span::Span;
+use php_rs::phpx::diagnostics::codes;
 use php_rs::phpx::typeck::{ExternalFunctionSig, Type as PhpType};
+use serde_json::Value;
 use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use tower_lsp::lsp_types::{
-    CompletionItem, CompletionItemKind, CompletionOptions, CompletionParams, CompletionResponse,
-    Diagnostic, DiagnosticOptions, DiagnosticServerCapabilities, DiagnosticSeverity,
+    CodeAction, CodeActionKind, CodeActionOrCommand, CodeActionParams,
+    CodeActionProviderCapability, CodeActionResponse, CompletionItem, CompletionItemKind, CompletionOptions, CompletionParams, CompletionResponse,
+    Diagnostic, DiagnosticOptions, DiagnosticRelatedInformation, DiagnosticServerCapabilities,
+    DiagnosticSeverity,
     DidChangeTextDocumentParams, DidOpenTextDocumentParams, DocumentDiagnosticParams,
     DocumentDiagnosticReport, DocumentDiagnosticReportResult, DocumentSymbol, DocumentSymbolParams,
     Documentation, FullDocumentDiagnosticReport, Hover, HoverContents, InitializeParams,
@@ -184,6 +188,7 @@ impl LanguageServer for Backend {
                 document_symbol_provider: Some(OneOf::Left(true)),
                 references_provider: Some(OneOf::Left(true)),
                 rename_provider: Some(OneOf::Left(true)),
+                code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
                 ..ServerCapabilities::default()
             },
             server_info: None,
@@ -229,6 +234,17 @@ impl LanguageServer for Backend {
         ))
     }
 
+    async fn code_action(
+        &self,
+        params: CodeActionParams,
+    ) -> tower_lsp::jsonrpc::Result<Option<CodeActionResponse>> {
+        let actions = code_actions_for_diagnostics(&params.text_document.uri, &params.context.diagnostics);
+        if actions.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(actions))
+    }
+
     async fn did_open(&self, params: DidOpenTextDocumentParams) {
         self._client
             .log_message(
@@ -589,10 +605,12 @@ fn diagnostic_from_error(file_path: &str, source: &str, error: &ValidationError)
         range: diagnostic_range(error.line, error.column, error.underline_length),
         severity: Some(severity_to_lsp(error.severity)),
         code: Some(tower_lsp::lsp_types::NumberOrString::String(
-            error.kind.as_str().to_string(),
+            error.code().to_string(),
         )),
         source: Some("phpx".to_string()),
         message: strip_ansi_codes(&rendered),
+        related_information: related_information(file_path, &error.related),
+        data: fixes_data(&error.fixes),
         ..Diagnostic::default()
     }
 }
@@ -619,14 +637,108 @@ fn diagnostic_from_warning(
         range: diagnostic_range(warning.line, warning.column, warning.underline_length),
         severity: Some(severity_to_lsp(warning.severity)),
         code: Some(tower_lsp::lsp_types::NumberOrString::String(
-            warning.kind.as_str().to_string(),
+            warning.code().to_string(),
         )),
         source: Some("phpx".to_string()),
         message: strip_ansi_codes(&rendered),
+        related_information: related_information(file_path, &warning.related),
+        data: fixes_data(&warning.fixes),
         ..Diagnostic::default()
     }
 }
 
+fn related_information(
+    file_path: &str,
+    related: &[RelatedLocation],
+) -> Option<Vec<DiagnosticRelatedInformation>> {
+    if related.is_empty() {
+        return None;
+    }
+    let uri = Url::from_file_path(file_path).ok()?;
+    Some(
+        related
+            .iter()
+            .map(|location| DiagnosticRelatedInformation {
+                location: Location {
+                    uri: uri.clone(),
+                    range: diagnostic_range(location.line, location.column, location.length),
+                },
+                message: location.message.clone(),
+            })
+            .collect(),
+    )
+}
+
+/// Fixes ride along in `Diagnostic.data` so `textDocument/codeAction` can turn
+/// them into quick fixes without recompiling the document.
+fn fixes_data(fixes: &[Fix]) -> Option<Value> {
+    if fixes.is_empty() {
+        return None;
+    }
+    serde_json::to_value(fixes)
+        .ok()
+        .map(|fixes| serde_json::json!({ "fixes": fixes }))
+}
+
+fn code_actions_for_diagnostics(uri: &Url, diagnostics: &[Diagnostic]) -> Vec<CodeActionOrCommand> {
+    let mut actions = Vec::new();
+    for diagnostic in diagnostics {
+        let Some(fixes) = diagnostic
+            .data
+            .as_ref()
+            .and_then(|data| data.get("fixes"))
+            .and_then(|fixes| fixes.as_array())
+        else {
+            continue;
+        };
+        for fix in fixes {
+            let field = |name: &str| fix.get(name).and_then(|value| value.as_u64()).unwrap_or(0);
+            let (Some(message), Some(replacement)) = (
+                fix.get("message").and_then(|value| value.as_str()),
+                fix.get("replacement").and_then(|value| value.as_str()),
+            ) else {
+                continue;
+            };
+            let line = field("line").saturating_sub(1) as u32;
+            let start = field("column").saturating_sub(1) as u32;
+            let range = Range {
+                start: Position {
+                    line,
+                    character: start,
+                },
+                end: Position {
+                    line,
+                    character: start + field("length") as u32,
+                },
+            };
+            let mut changes = HashMap::new();
+            changes.insert(
+                uri.clone(),
+                vec![TextEdit {
+                    range,
+                    new_text: replacement.to_string(),
+                }],
+            );
+            actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+                title: message.to_string(),
+                kind: Some(CodeActionKind::QUICKFIX),
+                diagnostics: Some(vec![diagnostic.clone()]),
+                edit: Some(WorkspaceEdit {
+                    changes: Some(changes),
+                    document_changes: None,
+                    change_annotations: None,
+                }),
+                is_preferred: Some(
+                    fix.get("applicability").and_then(|value| value.as_str())
+                        == Some("machine-applicable"),
+                ),
+                ..CodeAction::default()
+            }));
+        }
+    }
+    actions
+}
+
 fn severity_label(severity: Severity) -> &'static str {
     match severity {
         Severity::Error => "error",
@@ -1738,7 +1850,7 @@ fn format_function_signature(
 
 fn format_type(ty: &Type, source: &[u8]) -> String {
     match ty {
-        Type::Simple(token) => token_text(source, token),
+        Type::Simple(token) | Type::Literal(token) => token_text(source, token),
         Type::Name(name) => name_text(source, name),
         Type::Union(types) => types
             .iter()
@@ -1751,6 +1863,7 @@ fn format_type(ty: &Type, source: &[u8]) -> String {
             .collect::<Vec<_>>()
             .join(" & "),
         Type::Nullable(inner) => format!("?{}", format_type(inner, source)),
+        Type::KeyOf(inner) => format!("keyof {}", format_type(inner, source)),
         Type::ObjectShape(fields) => {
             let mut out = String::from("Object<{");
             let mut first = true;
@@ -1769,6 +1882,29 @@ fn format_type(ty: &Type, source: &[u8]) -> String {
             out.push_str("}>");
             out
         }
+        Type::Tuple(types) => format!(
+            "[{}]",
+            types
+                .iter()
+                .map(|ty| format_type(ty, source))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        Type::Function {
+            params,
+            return_type,
+        } => {
+            let rendered = params
+                .iter()
+                .map(|ty| format_type(ty, source))
+                .collect::<Vec<_>>();
+            let mut out = format!("fn({})", rendered.join(", "));
+            if let Some(return_type) = return_type {
+                out.push_str(": ");
+                out.push_str(&format_type(return_type, source));
+            }
+            out
+        }
         Type::Applied { base, args } => {
             let mut out = format_type(base, source);
             let rendered = args
@@ -2472,7 +2608,7 @@ fn unresolved_import_diagnostics(
             range: span_to_range(import.span, &line_index),
             severity: Some(DiagnosticSeverity::ERROR),
             code: Some(tower_lsp::lsp_types::NumberOrString::String(
-                "Import Error".to_string(),
+                codes::IMPORT_ERROR.to_string(),
             )),
             source: Some("phpx".to_string()),
             message: format!(
@@ -2504,7 +2640,7 @@ fn target_capability_diagnostics(source: &str, target_mode: TargetMode) -> Vec<D
                 range: span_to_range(import.module_span.unwrap_or(import.span), &line_index),
                 severity: Some(DiagnosticSeverity::ERROR),
                 code: Some(tower_lsp::lsp_types::NumberOrString::String(
-                    "Target Capability Error".to_string(),
+                    codes::TARGET_CAPABILITY.to_string(),
                 )),
                 source: Some("phpx".to_string()),
                 message: format!(
@@ -3275,7 +3411,7 @@ mod tests {
         assert_eq!(
             first.code,
             Some(tower_lsp::lsp_types::NumberOrString::String(
-                "Target Capability Error".to_string()
+                codes::TARGET_CAPABILITY.to_string()
             ))
         );
     }
@@ -3584,7 +3720,7 @@ mod tests {
         assert_eq!(
             diagnostics[0].code,
             Some(tower_lsp::lsp_types::NumberOrString::String(
-                "Import Error".to_string()
+                codes::IMPORT_ERROR.to_string()
             ))
         );
     }
@@ -3691,6 +3827,46 @@ function fullName($name: string): string {
         );
     }
 
+    #[test]
+    fn unknown_variable_diagnostic_offers_quick_fix() {
+        let source = r#"
+function fullName($name: string): string {
+  return $nam;
+}
+"#;
+        let arena = Bump::new();
+        let result = compile_phpx(source, "/tmp/var_fix.phpx", &arena);
+        let error = result
+            .errors
+            .iter()
+            .find(|error| error.message.contains("Unknown variable '$nam'"))
+            .expect("unknown variable error");
+        let diagnostic = diagnostic_from_error("/tmp/var_fix.phpx", source, error);
+        assert_eq!(
+            diagnostic.code,
+            Some(tower_lsp::lsp_types::NumberOrString::String(
+                codes::UNKNOWN_NAME.to_string()
+            ))
+        );
+
+        let uri = Url::from_file_path("/tmp/var_fix.phpx").expect("uri");
+        let actions = code_actions_for_diagnostics(&uri, &[diagnostic]);
+        assert_eq!(actions.len(), 1, "actions={actions:?}");
+        let CodeActionOrCommand::CodeAction(action) = &actions[0] else {
+            panic!("expected a code action");
+        };
+        let edits = action
+            .edit
+            .as_ref()
+            .and_then(|edit| edit.changes.as_ref())
+            .and_then(|changes| changes.get(&uri))
+            .expect("edits");
+        assert_eq!(edits[0].new_text, "$name");
+        assert_eq!(edits[0].range.start.line, 2);
+        assert_eq!(edits[0].range.start.character, 9);
+        assert_eq!(edits[0].range.end.character, 13);
+    }
+
     #[test]
     fn diagnostics_report_missing_required_props_in_template_section() {
         let source = r#"---
@@ -3777,6 +3953,10 @@ function FullName({ age: $age = 18 }: Object<{ age: int }>): int {
             suggestion: None,
             underline_length: 4,
             severity: Severity::Warning,
+            code: None,
+            related: Vec::new(),
+            notes: Vec::new(),
+            fixes: Vec::new(),
         };
         let mut unresolved = std::collections::HashSet::new();
         unresolved.insert((0, 9, 0, 13));
@@ -3794,6 +3974,10 @@ function FullName({ age: $age = 18 }: Object<{ age: int }>): int {
             suggestion: None,
             underline_length: 4,
             severity: Severity::Warning,
+            code: None,
+            related: Vec::new(),
+            notes: Vec::new(),
+            fixes: Vec::new(),
         };
         let unresolved = std::collections::HashSet::new();
         assert!(!should_skip_unused_import_warning(&warning, &unresolved));
@@ -3810,6 +3994,10 @@ function FullName({ age: $age = 18 }: Object<{ age: int }>): int {
             suggestion: None,
             underline_length: 4,
             severity: Severity::Error,
+            code: None,
+            related: Vec::new(),
+            notes: Vec::new(),
+            fixes: Vec::new(),
         };
         assert!(should_skip_template_html_diagnostic(&err));
     }
@@ -0,0 +1,677 @@
+//! `deka audit`: match every `deka.lock` entry against OSV advisories.
+//!
+//! Advisories come from an OSV JSON file (a single record, an array, a
+//! `{"vulns": [...]}` document or a directory of records) or from the linkhash
+//! `/api/advisories` endpoint. npm records apply to the node section and
+//! `linkhash` records to the php section.
+
+use crate::install::linkhash_registry_url;
+use crate::lock::{self, DekaLock, EcosystemSection};
+use crate::semver::Version;
+use crate::spec::{Ecosystem, parse_package_spec};
+use anyhow::{Context, Result, bail};
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Low,
+    Moderate,
+    High,
+    Critical,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Low => "low",
+            Severity::Moderate => "moderate",
+            Severity::High => "high",
+            Severity::Critical => "critical",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "low" => Some(Severity::Low),
+            "moderate" | "medium" => Some(Severity::Moderate),
+            "high" => Some(Severity::High),
+            "critical" => Some(Severity::Critical),
+            _ => None,
+        }
+    }
+
+    /// Qualitative rating of a CVSS base score.
+    fn from_score(score: f64) -> Self {
+        if score >= 9.0 {
+            Severity::Critical
+        } else if score >= 7.0 {
+            Severity::High
+        } else if score >= 4.0 {
+            Severity::Moderate
+        } else {
+            Severity::Low
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Advisory {
+    pub id: String,
+    pub summary: String,
+    pub aliases: Vec<String>,
+    pub severity: Severity,
+    pub url: Option<String>,
+    affected: Vec<Affected>,
+}
+
+#[derive(Debug, Clone)]
+struct Affected {
+    ecosystem: Ecosystem,
+    name: String,
+    ranges: Vec<Vec<Event>>,
+    versions: Vec<String>,
+    severity: Option<Severity>,
+}
+
+#[derive(Debug, Clone)]
+enum Event {
+    Introduced(Version),
+    Fixed(Version),
+    LastAffected(Version),
+}
+
+impl Event {
+    fn version(&self) -> &Version {
+        match self {
+            Event::Introduced(v) | Event::Fixed(v) | Event::LastAffected(v) => v,
+        }
+    }
+}
+
+/// One locked package version hit by one advisory.
+#[derive(Debug, Clone)]
+pub struct AuditFinding {
+    pub ecosystem: Ecosystem,
+    pub name: String,
+    pub version: String,
+    pub advisory: String,
+    pub aliases: Vec<String>,
+    pub summary: String,
+    pub severity: Severity,
+    /// Affected ranges as semver comparators, e.g. `>=1.0.0 <1.4.2`.
+    pub affected: Vec<String>,
+    pub fixed: Vec<String>,
+    pub url: Option<String>,
+}
+
+impl AuditFinding {
+    pub fn to_json(&self) -> Value {
+        serde_json::json!({
+            "ecosystem": self.ecosystem.as_str(),
+            "name": self.name,
+            "version": self.version,
+            "advisory": self.advisory,
+            "aliases": self.aliases,
+            "summary": self.summary,
+            "severity": self.severity.as_str(),
+            "affected": self.affected,
+            "fixed": self.fixed,
+            "url": self.url,
+        })
+    }
+}
+
+/// Audit `deka.lock` in the current directory. `source` is an OSV file,
+/// directory or URL; without one the linkhash registry is queried.
+pub async fn run_audit(source: Option<&str>) -> Result<Vec<AuditFinding>> {
+    let advisories = match source {
+        Some(url) if url.starts_with("http://") || url.starts_with("https://") => {
+            fetch_advisories(url).await?
+        }
+        Some(path) => load_advisories(Path::new(path))?,
+        None => {
+            let url = format!(
+                "{}/api/advisories",
+                linkhash_registry_url().trim_end_matches('/')
+            );
+            fetch_advisories(&url).await?
+        }
+    };
+    Ok(audit_lock(&lock::read_lockfile(), &advisories))
+}
+
+pub fn load_advisories(path: &Path) -> Result<Vec<Advisory>> {
+    if path.is_dir() {
+        let mut entries = fs::read_dir(path)
+            .with_context(|| format!("failed to read advisories {}", path.display()))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect::<Vec<_>>();
+        entries.sort();
+        let mut advisories = Vec::new();
+        for entry in entries {
+            advisories.extend(load_advisories(&entry)?);
+        }
+        return Ok(advisories);
+    }
+    let raw = fs::read_to_string(path)
+        .with_context(|| format!("failed to read advisories {}", path.display()))?;
+    let value: Value = serde_json::from_str(&raw)
+        .with_context(|| format!("invalid advisory JSON in {}", path.display()))?;
+    parse_advisories(&value)
+}
+
+async fn fetch_advisories(url: &str) -> Result<Vec<Advisory>> {
+    let response = reqwest::get(url)
+        .await
+        .with_context(|| format!("failed to fetch advisories from {}", url))?;
+    if !response.status().is_success() {
+        bail!("advisory request failed ({}): {}", response.status(), url);
+    }
+    let body = response
+        .json::<Value>()
+        .await
+        .context("failed to parse advisories")?;
+    parse_advisories(&body)
+}
+
+/// Accepts one OSV record, an array of records, or `{"vulns": [...]}`.
+/// Withdrawn records are dropped.
+pub fn parse_advisories(value: &Value) -> Result<Vec<Advisory>> {
+    let records = match value {
+        Value::Array(items) => items.iter().collect::<Vec<_>>(),
+        Value::Object(map) => match map.get("vulns") {
+            Some(Value::Array(items)) => items.iter().collect(),
+            Some(_) => bail!("`vulns` must be an array of OSV records"),
+            None => vec![value],
+        },
+        _ => bail!("expected an OSV record or a list of records"),
+    };
+    let mut advisories = Vec::new();
+    for record in records {
+        if record.get("withdrawn").is_some_and(|w| !w.is_null()) {
+            continue;
+        }
+        advisories.push(parse_record(record)?);
+    }
+    Ok(advisories)
+}
+
+fn parse_record(record: &Value) -> Result<Advisory> {
+    let Some(id) = record.get("id").and_then(Value::as_str) else {
+        bail!("OSV record is missing `id`");
+    };
+    let strings = |key: &str| -> Vec<String> {
+        record
+            .get(key)
+            .and_then(Value::as_array)
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+    let url = record
+        .get("references")
+        .and_then(Value::as_array)
+        .and_then(|refs| {
+            refs.iter()
+                .find(|r| r.get("type").and_then(Value::as_str) == Some("ADVISORY"))
+                .or_else(|| refs.first())
+        })
+        .and_then(|r| r.get("url"))
+        .and_then(Value::as_str)
+        .map(str::to_string);
+    let affected = record
+        .get("affected")
+        .and_then(Value::as_array)
+        .map(|items| items.iter().filter_map(parse_affected).collect())
+        .unwrap_or_default();
+
+    Ok(Advisory {
+        id: id.to_string(),
+        summary: record
+            .get("summary")
+            .or_else(|| record.get("details"))
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string(),
+        aliases: strings("aliases"),
+        // Records without any usable rating count as moderate.
+        severity: record_severity(record).unwrap_or(Severity::Moderate),
+        url,
+        affected,
+    })
+}
+
+/// `None` for ecosystems deka does not install from.
+fn parse_affected(item: &Value) -> Option<Affected> {
+    let package = item.get("package")?;
+    let ecosystem = match package.get("ecosystem")?.as_str()? {
+        "npm" => Ecosystem::Node,
+        "linkhash" => Ecosystem::Php,
+        _ => return None,
+    };
+    let name = package.get("name")?.as_str()?.to_string();
+    let ranges = item
+        .get("ranges")
+        .and_then(Value::as_array)
+        .map(|ranges| {
+            ranges
+                .iter()
+                .filter(|range| {
+                    matches!(
+                        range.get("type").and_then(Value::as_str),
+                        Some("SEMVER" | "ECOSYSTEM")
+                    )
+                })
+                .map(parse_events)
+                .collect()
+        })
+        .unwrap_or_default();
+    let versions = item
+        .get("versions")
+        .and_then(Value::as_array)
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(Value::as_str)
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default();
+    let severity = ["ecosystem_specific", "database_specific"]
+        .iter()
+        .find_map(|key| item.get(*key)?.get("severity")?.as_str())
+        .and_then(Severity::parse);
+    Some(Affected {
+        ecosystem,
+        name,
+        ranges,
+        versions,
+        severity,
+    })
+}
+
+fn parse_events(range: &Value) -> Vec<Event> {
+    let mut events: Vec<Event> = range
+        .get("events")
+        .and_then(Value::as_array)
+        .map(|events| {
+            events
+                .iter()
+                .filter_map(|event| {
+                    let (kind, raw) = event.as_object()?.iter().next()?;
+                    let raw = raw.as_str()?;
+                    let version = if raw == "0" {
+                        Version::new(0, 0, 0)
+                    } else {
+                        Version::parse(raw).ok()?
+                    };
+                    match kind.as_str() {
+                        "introduced" => Some(Event::Introduced(version)),
+                        "fixed" => Some(Event::Fixed(version)),
+                        "last_affected" => Some(Event::LastAffected(version)),
+                        _ => None,
+                    }
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    events.sort_by(|a, b| a.version().cmp(b.version()));
+    events
+}
+
+/// `database_specific.severity` (GHSA style) wins over CVSS scores.
+fn record_severity(record: &Value) -> Option<Severity> {
+    if let Some(severity) = record
+        .get("database_specific")
+        .and_then(|db| db.get("severity"))
+        .and_then(Value::as_str)
+        .and_then(Severity::parse)
+    {
+        return Some(severity);
+    }
+    record
+        .get("severity")
+        .and_then(Value::as_array)?
+        .iter()
+        .filter_map(|entry| entry.get("score").and_then(Value::as_str))
+        .filter_map(|score| {
+            score
+                .parse::<f64>()
+                .ok()
+                .or_else(|| cvss3_base_score(score))
+        })
+        .map(Severity::from_score)
+        .max()
+}
+
+/// Base score of a `CVSS:3.x/AV:../AC:..` vector.
+fn cvss3_base_score(vector: &str) -> Option<f64> {
+    let mut parts = vector.split('/');
+    if !parts.next()?.starts_with("CVSS:3") {
+        return None;
+    }
+    let metrics: Vec<(&str, &str)> = parts.filter_map(|part| part.split_once(':')).collect();
+    let metric = |name: &str| {
+        metrics
+            .iter()
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| *value)
+    };
+    let changed = metric("S")? == "C";
+    let av = match metric("AV")? {
+        "N" => 0.85,
+        "A" => 0.62,
+        "L" => 0.55,
+        "P" => 0.2,
+        _ => return None,
+    };
+    let ac = match metric("AC")? {
+        "L" => 0.77,
+        "H" => 0.44,
+        _ => return None,
+    };
+    let pr = match (metric("PR")?, changed) {
+        ("N", _) => 0.85,
+        ("L", false) => 0.62,
+        ("L", true) => 0.68,
+        ("H", false) => 0.27,
+        ("H", true) => 0.5,
+        _ => return None,
+    };
+    let ui = match metric("UI")? {
+        "N" => 0.85,
+        "R" => 0.62,
+        _ => return None,
+    };
+    let cia = |name: &str| match metric(name)? {
+        "H" => Some(0.56),
+        "L" => Some(0.22),
+        "N" => Some(0.0),
+        _ => None,
+    };
+    let iss = 1.0 - (1.0 - cia("C")?) * (1.0 - cia("I")?) * (1.0 - cia("A")?);
+    let impact = if changed {
+        7.52 * (iss - 0.029) - 3.25 * (iss - 0.02_f64).powi(15)
+    } else {
+        6.42 * iss
+    };
+    if impact <= 0.0 {
+        return Some(0.0);
+    }
+    let exploitability = 8.22 * av * ac * pr * ui;
+    let total = if changed {
+        1.08 * (impact + exploitability)
+    } else {
+        impact + exploitability
+    };
+    Some(round_up(total.min(10.0)))
+}
+
+/// CVSS "round up to one decimal", robust to float noise.
+fn round_up(value: f64) -> f64 {
+    let scaled = (value * 100_000.0).round() as i64;
+    if scaled % 10_000 == 0 {
+        scaled as f64 / 100_000.0
+    } else {
+        ((scaled / 10_000) + 1) as f64 / 10.0
+    }
+}
+
+pub fn audit_lock(lock: &DekaLock, advisories: &[Advisory]) -> Vec<AuditFinding> {
+    let mut findings = Vec::new();
+    for (ecosystem, section) in [(Ecosystem::Node, &lock.node), (Ecosystem::Php, &lock.php)] {
+        for (name, version) in locked_versions(section) {
+            for advisory in advisories {
+                if let Some(finding) = check(advisory, ecosystem, &name, &version) {
+                    findings.push(finding);
+                }
+            }
+        }
+    }
+    findings.sort_by(|a, b| {
+        b.severity
+            .cmp(&a.severity)
+            .then_with(|| a.name.cmp(&b.name))
+            .then_with(|| a.advisory.cmp(&b.advisory))
+    });
+    findings
+}
+
+/// `(name, version)` of every locked package, read from the descriptor so
+/// aliased lock keys still audit under their real package name.
+fn locked_versions(section: &EcosystemSection) -> Vec<(String, String)> {
+    let mut out: Vec<(String, String)> = section
+        .packages
+        .iter()
+        .filter_map(|(key, entry)| {
+            let (name, version) = parse_package_spec(&entry.0);
+            let name = if name.is_empty() { key.clone() } else { name };
+            Some((name, version?))
+        })
+        .collect();
+    out.sort();
+    out.dedup();
+    out
+}
+
+fn check(
+    advisory: &Advisory,
+    ecosystem: Ecosystem,
+    name: &str,
+    version: &str,
+) -> Option<AuditFinding> {
+    let parsed = Version::parse(version).ok();
+    let mut hit = false;
+    let mut severity = None;
+    let mut affected = Vec::new();
+    let mut fixed = Vec::new();
+    for entry in &advisory.affected {
+        if entry.ecosystem != ecosystem || entry.name != name {
+            continue;
+        }
+        let listed = entry.versions.iter().any(|v| v == version);
+        let in_range = parsed
+            .as_ref()
+            .is_some_and(|v| entry.ranges.iter().any(|events| range_contains(events, v)));
+        if !listed && !in_range {
+            continue;
+        }
+        hit = true;
+        severity = severity.max(entry.severity);
+        for events in &entry.ranges {
+            affected.push(describe_range(events));
+            fixed.extend(events.iter().filter_map(|event| match event {
+                Event::Fixed(v) => Some(v.clone()),
+                _ => None,
+            }));
+        }
+        if entry.ranges.is_empty() {
+            affected.push(format!("={}", version));
+        }
+    }
+    if !hit {
+        return None;
+    }
+    fixed.sort();
+    fixed.dedup();
+    let fixed = fixed.iter().map(Version::to_string).collect();
+    Some(AuditFinding {
+        ecosystem,
+        name: name.to_string(),
+        version: version.to_string(),
+        advisory: advisory.id.clone(),
+        aliases: advisory.aliases.clone(),
+        summary: advisory.summary.clone(),
+        severity: severity.unwrap_or(advisory.severity),
+        affected,
+        fixed,
+        url: advisory.url.clone(),
+    })
+}
+
+/// OSV range evaluation over events sorted by version.
+fn range_contains(events: &[Event], version: &Version) -> bool {
+    let mut affected = false;
+    for event in events {
+        match event {
+            Event::Introduced(v) if v <= version => affected = true,
+            Event::Fixed(v) if v <= version => affected = false,
+            Event::LastAffected(v) if v < version => affected = false,
+            _ => {}
+        }
+    }
+    affected
+}
+
+fn describe_range(events: &[Event]) -> String {
+    let parts: Vec<String> = events
+        .iter()
+        .filter_map(|event| match event {
+            Event::Introduced(v) if *v == Version::new(0, 0, 0) => None,
+            Event::Introduced(v) => Some(format!(">={}", v)),
+            Event::Fixed(v) => Some(format!("<{}", v)),
+            Event::LastAffected(v) => Some(format!("<={}", v)),
+        })
+        .collect();
+    if parts.is_empty() {
+        "*".to_string()
+    } else {
+        parts.join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Severity, audit_lock, cvss3_base_score, parse_advisories};
+    use crate::lock::DekaLock;
+    use serde_json::json;
+
+    #[test]
+    fn audits_locked_packages_against_osv_records() {
+        let mut lock = DekaLock::default();
+        lock.node.packages.insert(
+            "lodash".to_string(),
+            (
+                "lodash@4.17.15".to_string(),
+                String::new(),
+                json!({}),
+                String::new(),
+            ),
+        );
+        lock.php.packages.insert(
+            "@acme/http".to_string(),
+            (
+                "@acme/http@1.4.2".to_string(),
+                String::new(),
+                json!({}),
+                String::new(),
+            ),
+        );
+        let advisories = parse_advisories(&json!({ "vulns": [
+            {
+                "id": "GHSA-p6mc-m468-83gw",
+                "summary": "Prototype pollution in lodash",
+                "aliases": ["CVE-2020-8203"],
+                "database_specific": { "severity": "HIGH" },
+                "affected": [{
+                    "package": { "ecosystem": "npm", "name": "lodash" },
+                    "ranges": [{ "type": "SEMVER", "events": [
+                        { "introduced": "0" }, { "fixed": "4.17.19" }
+                    ]}]
+                }]
+            },
+            {
+                "id": "LHSA-2026-0001",
+                "summary": "header injection",
+                "affected": [{
+                    "package": { "ecosystem": "linkhash", "name": "@acme/http" },
+                    "ranges": [{ "type": "SEMVER", "events": [
+                        { "introduced": "1.0.0" }, { "fixed": "1.4.2" }
+                    ]}]
+                }]
+            },
+            {
+                "id": "LHSA-2026-0002",
+                "withdrawn": "2026-01-01T00:00:00Z",
+                "affected": [{
+                    "package": { "ecosystem": "npm", "name": "lodash" },
+                    "versions": ["4.17.15"]
+                }]
+            }
+        ]}))
+        .unwrap();
+
+        let findings = audit_lock(&lock, &advisories);
+        assert_eq!(findings.len(), 1);
+        let finding = &findings[0];
+        assert_eq!(finding.advisory, "GHSA-p6mc-m468-83gw");
+        assert_eq!(finding.severity, Severity::High);
+        assert_eq!(finding.affected, vec!["<4.17.19".to_string()]);
+        assert_eq!(finding.fixed, vec!["4.17.19".to_string()]);
+    }
+
+    #[test]
+    fn audits_nested_copies_and_orders_fixes_by_version() {
+        let mut lock = DekaLock::default();
+        for (key, descriptor) in [
+            ("semver", "semver@1.9.0"),
+            ("app/node_modules/semver", "semver@1.2.0"),
+            ("lib/node_modules/semver", "semver@1.11.0"),
+        ] {
+            lock.node.packages.insert(
+                key.to_string(),
+                (
+                    descriptor.to_string(),
+                    String::new(),
+                    json!({}),
+                    String::new(),
+                ),
+            );
+        }
+        let advisories = parse_advisories(&json!([{
+            "id": "GHSA-semver",
+            "affected": [{
+                "package": { "ecosystem": "npm", "name": "semver" },
+                "ranges": [{ "type": "SEMVER", "events": [
+                    { "introduced": "1.10.0" }, { "fixed": "1.12.0" },
+                    { "introduced": "0" }, { "fixed": "1.9.0" }
+                ]}]
+            }]
+        }]))
+        .unwrap();
+
+        let findings = audit_lock(&lock, &advisories);
+        let versions = findings
+            .iter()
+            .map(|finding| finding.version.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(versions, ["1.11.0", "1.2.0"]);
+        assert_eq!(findings[0].fixed, vec!["1.9.0", "1.12.0"]);
+    }
+
+    #[test]
+    fn scores_cvss_vectors() {
+        assert_eq!(
+            cvss3_base_score("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H"),
+            Some(9.8)
+        );
+        assert_eq!(
+            cvss3_base_score("CVSS:3.1/AV:N/AC:L/PR:N/UI:R/S:C/C:L/I:L/A:N"),
+            Some(6.1)
+        );
+        let advisories = parse_advisories(&json!([{
+            "id": "GHSA-x",
+            "severity": [{ "type": "CVSS_V3", "score": "CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:N/A:N" }]
+        }]))
+        .unwrap();
+        assert_eq!(advisories[0].severity, Severity::High);
+    }
+}
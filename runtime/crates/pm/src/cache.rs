@@ -1,10 +1,10 @@
-use anyhow::{Context, Result, anyhow};
+use anyhow::{Context, Result, anyhow, bail};
 use base64::{Engine, engine::general_purpose::STANDARD};
 use dirs::home_dir;
 use sha2::{Digest, Sha512};
 use std::env;
 use std::fs::{self, File};
-use std::io::{self, Read};
+use std::io::{self, Read, Write};
 use std::path::{Path, PathBuf};
 use std::process::Command;
 use tempfile::Builder;
@@ -64,6 +64,22 @@ impl CachePaths {
         self.meta.join(format!("{key}.json"))
     }
 
+    /// Cached archive for `key`, checked against `integrity` when given.
+    /// `Ok(None)` when nothing is cached; an error when the bytes do not match.
+    pub fn read_archive(&self, key: &str, integrity: Option<&str>) -> Result<Option<Vec<u8>>> {
+        let path = self.archive_path(key);
+        if !path.is_file() {
+            return Ok(None);
+        }
+        let bytes =
+            fs::read(&path).with_context(|| format!("failed to read {}", path.display()))?;
+        if let Some(expected) = integrity {
+            verify_integrity(&bytes, expected)
+                .with_context(|| format!("cached archive {} is corrupt", path.display()))?;
+        }
+        Ok(Some(bytes))
+    }
+
     pub fn project_path_for(&self, name: &str) -> PathBuf {
         let segments: Vec<&str> = name.split('/').collect();
         segments
@@ -115,6 +131,27 @@ pub fn compute_sha512(data: &[u8]) -> String {
     format!("sha512-{}", STANDARD.encode(hasher.finalize()))
 }
 
+/// Check `data` against an SRI string such as `sha512-<base64>`. Only sha512
+/// digests are compared; strings carrying none of them are accepted as-is.
+pub fn verify_integrity(data: &[u8], expected: &str) -> Result<()> {
+    let digests = expected
+        .split_whitespace()
+        .filter(|digest| digest.starts_with("sha512-"))
+        .collect::<Vec<_>>();
+    if digests.is_empty() {
+        return Ok(());
+    }
+    let actual = compute_sha512(data);
+    if digests.iter().any(|digest| *digest == actual) {
+        return Ok(());
+    }
+    bail!(
+        "integrity mismatch: expected {}, got {}",
+        digests[0],
+        actual
+    )
+}
+
 pub fn extract_tarball(archive_path: &Path, destination: &Path, tmp_root: &Path) -> Result<()> {
     let tmp = Builder::new()
         .prefix("deka-")
@@ -156,6 +193,20 @@ pub fn extract_tarball(archive_path: &Path, destination: &Path, tmp_root: &Path)
     Ok(())
 }
 
+/// Extracts archive bytes that were already verified, so the unpacked files
+/// cannot differ from what was checked even if the cached archive changes.
+pub fn extract_tarball_bytes(bytes: &[u8], destination: &Path, tmp_root: &Path) -> Result<()> {
+    let mut archive = Builder::new()
+        .prefix("deka-")
+        .suffix(".tgz")
+        .tempfile_in(tmp_root)
+        .context("failed to create temp archive")?;
+    archive
+        .write_all(bytes)
+        .context("failed to write temp archive")?;
+    extract_tarball(archive.path(), destination, tmp_root)
+}
+
 pub fn copy_package(source: &Path, destination: &Path) -> Result<()> {
     if destination.exists() {
         fs::remove_dir_all(destination)?;
@@ -227,3 +278,52 @@ pub fn read_metadata(path: &Path) -> Option<serde_json::Value> {
     file.read_to_string(&mut buf).ok()?;
     serde_json::from_str(&buf).ok()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{compute_sha512, extract_tarball_bytes, verify_integrity};
+    use std::fs;
+    use std::process::Command;
+
+    #[test]
+    fn verifies_sha512_integrity() {
+        let data = b"package bytes";
+        let digest = compute_sha512(data);
+        assert!(verify_integrity(data, &digest).is_ok());
+        assert!(verify_integrity(data, &format!("sha1-abc {}", digest)).is_ok());
+        assert!(verify_integrity(b"tampered", &digest).is_err());
+        // Legacy sha1-only integrity cannot be checked and is accepted.
+        assert!(verify_integrity(data, "sha1-abc").is_ok());
+    }
+
+    #[test]
+    fn extracting_verified_bytes_replaces_a_tampered_cache_dir() {
+        let root = tempfile::tempdir().unwrap();
+        let package = root.path().join("src").join("package");
+        fs::create_dir_all(&package).unwrap();
+        fs::write(package.join("index.js"), "ok").unwrap();
+        let archive = root.path().join("pkg.tgz");
+        let status = Command::new("tar")
+            .arg("-czf")
+            .arg(&archive)
+            .arg("-C")
+            .arg(root.path().join("src"))
+            .arg("package")
+            .status()
+            .unwrap();
+        assert!(status.success());
+        let bytes = fs::read(&archive).unwrap();
+
+        let cache_dir = root.path().join("cache");
+        fs::create_dir_all(&cache_dir).unwrap();
+        fs::write(cache_dir.join("index.js"), "tampered").unwrap();
+        fs::write(cache_dir.join("extra.js"), "injected").unwrap();
+        extract_tarball_bytes(&bytes, &cache_dir, root.path()).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(cache_dir.join("index.js")).unwrap(),
+            "ok"
+        );
+        assert!(!cache_dir.join("extra.js").exists());
+    }
+}
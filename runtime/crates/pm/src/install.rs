@@ -1,11 +1,15 @@
 use crate::{
     bun_lock::BunLock,
     cache::{
-        CachePaths, cache_key, compute_sha512, copy_package, download_tarball, extract_tarball,
+        CachePaths, cache_key, compute_sha512, copy_package, download_tarball,
+        extract_tarball_bytes, verify_integrity,
     },
     lock,
-    npm::{fetch_npm_metadata, resolve_package_version},
+    npm::NpmResolutions,
     payload::InstallPayload,
+    php_resolver::{PhpPackageSource, resolve_php_graph},
+    semver::{Version, VersionReq},
+    signature::{parse_public_key, release_message, tar_digest, verify_signature},
     spec::{Ecosystem, parse_hinted_spec, parse_package_spec},
 };
 use anyhow::{Context, Result, bail, anyhow};
@@ -15,15 +19,35 @@ use serde::Deserialize;
 use serde_json::{Map, Value, json};
 use modules_php::integrity::compute_package_integrity;
 use std::{
-    collections::{BTreeMap, HashSet, VecDeque},
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
     fs,
     io::Write,
-    path::PathBuf,
-    sync::Arc,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
     time::Instant,
 };
 use tokio::{sync::Semaphore, task::JoinSet};
 
+/// Network and lockfile restrictions for one install run.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct InstallMode {
+    /// `--offline`: resolve and install from the package cache only.
+    pub(crate) offline: bool,
+    /// `--frozen-lockfile`: fail instead of changing `deka.lock`.
+    pub(crate) frozen: bool,
+    pub(crate) quiet: bool,
+}
+
+impl InstallMode {
+    fn from_payload(payload: &InstallPayload) -> Self {
+        Self {
+            offline: payload.offline,
+            frozen: payload.frozen_lockfile,
+            quiet: payload.quiet,
+        }
+    }
+}
+
 pub async fn run_install(payload: InstallPayload) -> Result<()> {
     if payload.rehash {
         rehash_php_packages(&payload).await?;
@@ -40,10 +64,10 @@ pub async fn run_install(payload: InstallPayload) -> Result<()> {
         .as_deref()
         .and_then(Ecosystem::from_str)
         .unwrap_or(Ecosystem::Node);
-    let quiet = payload.quiet;
+    let mode = InstallMode::from_payload(&payload);
 
     if override_ecosystem == Ecosystem::Php {
-        return run_php_install(specs, quiet).await;
+        return run_php_install(specs, mode).await;
     }
 
     if payload.prompt && !payload.yes {
@@ -57,10 +81,46 @@ pub async fn run_install(payload: InstallPayload) -> Result<()> {
         }
     }
 
+    install_node_specs(specs, override_ecosystem, mode, None).await
+}
+
+/// Install node `specs` and their dependency trees. `refresh` lists packages
+/// being updated: `bun.lock` is ignored and they resolve afresh within their
+/// ranges, while every other package keeps its `deka.lock` version.
+pub(crate) async fn install_node_specs(
+    specs: Vec<String>,
+    override_ecosystem: Ecosystem,
+    mode: InstallMode,
+    refresh: Option<&HashSet<String>>,
+) -> Result<()> {
+    let quiet = mode.quiet;
     let cache = Arc::new(CachePaths::new()?);
     cache.ensure()?;
 
-    let bun_lock = BunLock::load()?.map(Arc::new);
+    let bun_lock = match refresh {
+        Some(_) => None,
+        None => BunLock::load()?.map(Arc::new),
+    };
+    let node_lock = Arc::new(lock::read_lockfile().node.packages);
+    let resolutions = if mode.offline {
+        NpmResolutions::offline(cache.node.clone())
+    } else {
+        NpmResolutions::default()
+    };
+    if mode.offline || mode.frozen || refresh.is_some() {
+        // Locked versions win whenever their ranges allow it. Nested copies
+        // are placed by the bun lock, so only hoisted entries are pinned.
+        for (name, entry) in node_lock.iter() {
+            let nested = name.contains("/node_modules/");
+            if nested || refresh.is_some_and(|names| names.contains(name)) {
+                continue;
+            }
+            if let (_, Some(version)) = parse_package_spec(&entry.0) {
+                resolutions.pin(name, &version);
+            }
+        }
+    }
+    let resolutions = Arc::new(resolutions);
     let mut ctx = InstallContext::new(cache.clone(), bun_lock.clone());
     for spec in specs {
         ctx.enqueue(spec, None, false, None);
@@ -71,6 +131,8 @@ pub async fn run_install(payload: InstallPayload) -> Result<()> {
     let mut join_set = JoinSet::new();
     let mut copy_tasks = JoinSet::new();
     let mut installed_count = 0;
+    // Lock key -> descriptor of every copy placed in node_modules.
+    let mut placed = BTreeMap::new();
 
     // Spawn initial tasks
     while let Some(task) = ctx.next_task() {
@@ -88,6 +150,8 @@ pub async fn run_install(payload: InstallPayload) -> Result<()> {
 
         let cache_clone = cache.clone();
         let bun_lock_clone = bun_lock.clone();
+        let resolutions_clone = resolutions.clone();
+        let node_lock_clone = node_lock.clone();
         let spec_clone = spec_str.clone();
         let lock_key_clone = task.lock_key.clone();
         let sem_clone = semaphore.clone();
@@ -98,6 +162,9 @@ pub async fn run_install(payload: InstallPayload) -> Result<()> {
             let result = install_node_package(
                 &cache_clone,
                 bun_lock_clone.as_deref(),
+                &resolutions_clone,
+                &node_lock_clone,
+                mode,
                 &spec_clone,
                 lock_key_clone.as_deref(),
             )
@@ -118,8 +185,9 @@ pub async fn run_install(payload: InstallPayload) -> Result<()> {
                 );
                 if let Some(dest) = destination {
                     let cache_dir = result.cache_dir.clone();
-                    let name = result.name.clone();
-                    let version = result.version.clone();
+                    let key = lock::node_lock_key(&cache.node_modules, &dest);
+                    let descriptor = format!("{}@{}", result.name, result.version);
+                    placed.insert(key.clone(), descriptor.clone());
                     let resolved = result.resolved.clone();
                     let metadata = result.metadata.clone();
                     let integrity = result.integrity.clone();
@@ -128,9 +196,11 @@ pub async fn run_install(payload: InstallPayload) -> Result<()> {
                     // Spawn copy operation without awaiting (runs in parallel)
                     copy_tasks.spawn_blocking(move || -> Result<()> {
                         copy_package(&cache_dir, &dest_clone)?;
-                        let descriptor = format!("{}@{}", name, version);
+                        if mode.frozen {
+                            return Ok(());
+                        }
                         lock::update_lock_entry(
-                            "node", &name, descriptor, resolved, metadata, integrity,
+                            "node", &key, descriptor, resolved, metadata, integrity,
                         )?;
                         Ok(())
                     });
@@ -198,6 +268,8 @@ pub async fn run_install(payload: InstallPayload) -> Result<()> {
 
             let cache_c = cache.clone();
             let lock_c = bun_lock.clone();
+            let resolutions_c = resolutions.clone();
+            let node_lock_c = node_lock.clone();
             let spec_c = spec_str.clone();
             let key_c = task.lock_key.clone();
             let sem_c = semaphore.clone();
@@ -205,9 +277,16 @@ pub async fn run_install(payload: InstallPayload) -> Result<()> {
 
             join_set.spawn(async move {
                 let _permit = sem_c.acquire().await.unwrap();
-                let result =
-                    install_node_package(&cache_c, lock_c.as_deref(), &spec_c, key_c.as_deref())
-                        .await;
+                let result = install_node_package(
+                    &cache_c,
+                    lock_c.as_deref(),
+                    &resolutions_c,
+                    &node_lock_c,
+                    mode,
+                    &spec_c,
+                    key_c.as_deref(),
+                )
+                .await;
                 (spec_c, opt, result)
             });
         }
@@ -224,17 +303,20 @@ pub async fn run_install(payload: InstallPayload) -> Result<()> {
                 );
                 if let Some(dest) = destination {
                     let cache_dir = result.cache_dir.clone();
-                    let name = result.name.clone();
-                    let version = result.version.clone();
+                    let key = lock::node_lock_key(&cache.node_modules, &dest);
+                    let descriptor = format!("{}@{}", result.name, result.version);
+                    placed.insert(key.clone(), descriptor.clone());
                     let resolved = result.resolved.clone();
                     let metadata = result.metadata.clone();
                     let integrity = result.integrity.clone();
 
                     copy_tasks.spawn_blocking(move || -> Result<()> {
                         copy_package(&cache_dir, &dest)?;
-                        let descriptor = format!("{}@{}", name, version);
+                        if mode.frozen {
+                            return Ok(());
+                        }
                         lock::update_lock_entry(
-                            "node", &name, descriptor, resolved, metadata, integrity,
+                            "node", &key, descriptor, resolved, metadata, integrity,
                         )?;
                         Ok(())
                     });
@@ -263,6 +345,15 @@ pub async fn run_install(payload: InstallPayload) -> Result<()> {
             Err(e) => return Err(anyhow::anyhow!("copy task join error: {}", e)),
         }
     }
+    if mode.frozen {
+        let drift = frozen_node_drift(&node_lock, &placed, &normalize_os(), &normalize_cpu());
+        if !drift.is_empty() {
+            bail!(
+                "deka.lock is out of date (--frozen-lockfile):\n{}",
+                drift.join("\n")
+            );
+        }
+    }
 
     let duration = Instant::now().duration_since(start);
     emit_summary(installed_count, duration.as_millis() as u64, quiet)?;
@@ -270,8 +361,10 @@ pub async fn run_install(payload: InstallPayload) -> Result<()> {
 }
 
 #[derive(Debug, Deserialize)]
-struct PhpPackageSummary {
-    latest: Option<String>,
+pub(crate) struct PhpPackageSummary {
+    #[serde(default)]
+    pub(crate) versions: Vec<String>,
+    pub(crate) latest: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -284,48 +377,217 @@ struct PhpPackageRelease {
     description: Option<String>,
     manifest: Option<Value>,
     capability_metadata: Option<Value>,
+    /// `sha512-<base64>` of the tarball the registry serves.
+    #[serde(default)]
+    integrity: Option<String>,
+    /// Publisher's base64 ed25519 signature over `release_message`.
+    #[serde(default)]
+    signature: Option<String>,
+    /// Fingerprint of the owner key that made `signature`.
+    #[serde(default)]
+    signing_key: Option<String>,
 }
 
-async fn run_php_install(specs: Vec<String>, quiet: bool) -> Result<()> {
-    if specs.is_empty() {
-        bail!("no PHP package specs provided (use --spec)");
+/// Registry-backed package source; releases are cached so the install pass
+/// reuses what resolution already fetched.
+struct LinkhashSource {
+    registry: String,
+    releases: Mutex<HashMap<(String, String), Arc<PhpPackageRelease>>>,
+    owner_keys: Mutex<HashMap<String, Arc<Vec<String>>>>,
+}
+
+impl LinkhashSource {
+    fn new(registry: String) -> Self {
+        Self {
+            registry,
+            releases: Mutex::new(HashMap::new()),
+            owner_keys: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Public keys `owner` registered in linkhash, as `ssh-ed25519` lines.
+    async fn owner_keys(&self, owner: &str) -> Result<Arc<Vec<String>>> {
+        if let Some(keys) = self.owner_keys.lock().unwrap().get(owner) {
+            return Ok(keys.clone());
+        }
+        let keys = Arc::new(fetch_owner_keys(&self.registry, owner).await?);
+        self.owner_keys
+            .lock()
+            .unwrap()
+            .insert(owner.to_string(), keys.clone());
+        Ok(keys)
     }
 
+    async fn release(&self, name: &str, version: &str) -> Result<Arc<PhpPackageRelease>> {
+        let key = (name.to_string(), version.to_string());
+        if let Some(release) = self.releases.lock().unwrap().get(&key) {
+            return Ok(release.clone());
+        }
+        let release = Arc::new(fetch_php_release(&self.registry, name, version).await?);
+        self.releases.lock().unwrap().insert(key, release.clone());
+        Ok(release)
+    }
+}
+
+impl PhpPackageSource for LinkhashSource {
+    async fn versions(&self, name: &str) -> Result<Vec<String>> {
+        let summary = fetch_php_summary(&self.registry, name).await?;
+        if summary.versions.is_empty() {
+            return Ok(summary.latest.into_iter().collect());
+        }
+        Ok(summary.versions)
+    }
+
+    async fn dependencies(&self, name: &str, version: &str) -> Result<BTreeMap<String, String>> {
+        let release = self.release(name, version).await?;
+        release_dependencies(&release)
+    }
+}
+
+pub(crate) async fn run_php_install(specs: Vec<String>, mode: InstallMode) -> Result<()> {
+    if mode.offline || mode.frozen {
+        return install_php_from_lock(specs, mode).await;
+    }
+    let mut declared = load_php_dependencies()?;
+    let mut requested = BTreeMap::new();
+    for spec in &specs {
+        let normalized = normalize_php_spec(spec)?;
+        let (name, range) = parse_package_spec(&normalized);
+        requested.insert(name, range);
+    }
+
+    let lock_before = lock::read_lockfile();
+    let mut roots = BTreeMap::new();
+    for (name, range) in &declared {
+        let req = VersionReq::parse(range)
+            .with_context(|| format!("invalid version range for {} in deka.json", name))?;
+        roots.insert(name.clone(), req);
+    }
+    for (name, range) in &requested {
+        let req = match range {
+            Some(range) => VersionReq::parse(range)
+                .with_context(|| format!("invalid version range for {}", name))?,
+            None => VersionReq::any(),
+        };
+        roots.insert(name.clone(), req);
+    }
+    // Packages locked before deka.json tracked dependencies stay pinned.
+    for (name, entry) in &lock_before.php.packages {
+        if roots.contains_key(name) || entry.2.get("requestedBy").is_some() {
+            continue;
+        }
+        if let Some(version) = locked_php_version(entry) {
+            roots.insert(name.clone(), VersionReq::exact(&version));
+        }
+    }
+    if roots.is_empty() {
+        bail!("no PHP package specs provided (use --spec or add dependencies to deka.json)");
+    }
+
+    // Explicitly requested packages move to the newest match; the rest keep
+    // their locked versions while those still satisfy the graph.
+    let locked = lock_before
+        .php
+        .packages
+        .iter()
+        .filter(|(name, _)| !requested.contains_key(*name))
+        .filter_map(|(name, entry)| locked_php_version(entry).map(|v| (name.clone(), v)))
+        .collect::<BTreeMap<_, _>>();
+
     let cache = CachePaths::new()?;
     cache.ensure()?;
-    let registry = linkhash_registry_url();
+    let source = LinkhashSource::new(linkhash_registry_url());
     let project_policy = load_project_security_policy()?;
     let start = Instant::now();
+    let resolution = resolve_php_graph(&source, &roots, &locked).await?;
     let mut installed_count = 0usize;
+    let mut entries = BTreeMap::new();
 
-    for spec in specs {
-        let normalized = normalize_php_spec(&spec)?;
-        let (name, version_hint) = parse_package_spec(&normalized);
-        let version = if let Some(v) = version_hint {
-            v
-        } else {
-            fetch_php_latest(&registry, &name).await?
-        };
-
-        let release = fetch_php_release(&registry, &name, &version).await?;
+    for package in resolution.packages.values() {
+        let name = package.name.as_str();
+        let version = package.version.to_string();
+        let descriptor = format!("{}@{}", name, version);
+        let release = source.release(name, &version).await?;
         enforce_release_policy(&release, &project_policy)?;
-        let tarball_url = package_download_url(&registry, &name, &version)?;
-        let bytes = download_tarball(&tarball_url).await?;
-        let integrity = compute_sha512(&bytes);
-
-        let key = cache_key(&format!("php+{}", name), &version);
-        let archive_path = cache.archive_path(&key);
-        fs::write(&archive_path, &bytes)
-            .with_context(|| format!("failed to write archive {}", archive_path.display()))?;
-
-        let cache_dir = cache.cache_dir(&key);
-        extract_tarball(&archive_path, &cache_dir, &cache.tmp)?;
+        let destination = php_modules_path_for(name)?;
+
+        let previous = lock_before
+            .php
+            .packages
+            .get(name)
+            .filter(|entry| entry.0 == descriptor && destination.is_dir());
+        let mut package_integrity = match previous {
+            Some(_) => compute_package_integrity(&destination).ok(),
+            None => None,
+        };
+        // Signed releases are only re-checked without the archive when the
+        // lock kept the tar digest the signature covers.
+        let reusable = match (previous, &package_integrity) {
+            (Some(entry), Some(current)) => {
+                entry.2.pointer("/moduleGraph/hash").and_then(Value::as_str)
+                    == Some(current.module_graph.as_str())
+                    && (release.signature.is_none() || locked_tar_digest(entry).is_some())
+            }
+            _ => false,
+        };
 
-        let destination = php_modules_path_for(&name)?;
-        copy_package(&cache_dir, &destination)?;
+        let (tarball_url, integrity, signature) = if let Some(entry) = previous.filter(|_| reusable)
+        {
+            let signature = verify_release_signature(
+                &source,
+                &release,
+                &entry.3,
+                locked_tar_digest(entry).unwrap_or_default(),
+                &project_policy,
+                Some(entry),
+            )
+            .await?;
+            (entry.1.clone(), entry.3.clone(), signature)
+        } else {
+            let key = cache_key(&format!("php+{}", name), &version);
+            let archive_path = cache.archive_path(&key);
+            let locked = lock_before
+                .php
+                .packages
+                .get(name)
+                .filter(|entry| entry.0 == descriptor);
+            let cached = match locked {
+                Some(entry) => cached_archive(&cache, &key, Some(&entry.3), mode)?,
+                None => None,
+            };
+            let (tarball_url, bytes) = match (locked, cached) {
+                (Some(entry), Some(bytes)) => (entry.1.clone(), bytes),
+                _ => {
+                    let tarball_url = package_download_url(&source.registry, name, &version)?;
+                    let bytes = download_tarball(&tarball_url).await?;
+                    fs::write(&archive_path, &bytes).with_context(|| {
+                        format!("failed to write archive {}", archive_path.display())
+                    })?;
+                    (tarball_url, bytes)
+                }
+            };
 
-        let package_integrity = compute_package_integrity(&destination)
-            .map_err(|err| anyhow!("failed to compute package integrity for {}: {}", name, err))?;
+            let (integrity, signature) = install_signed_php_archive(
+                &source,
+                &release,
+                &bytes,
+                &project_policy,
+                &cache,
+                &cache.cache_dir(&key),
+                &destination,
+                lock_before.php.packages.get(name),
+            )
+            .await?;
+            package_integrity = None;
+            installed_count += 1;
+            (tarball_url, integrity, signature)
+        };
+        let package_integrity = match package_integrity {
+            Some(integrity) => integrity,
+            None => compute_package_integrity(&destination).map_err(|err| {
+                anyhow!("failed to compute package integrity for {}: {}", name, err)
+            })?,
+        };
 
         let metadata = json!({
             "owner": release.owner,
@@ -341,20 +603,422 @@ async fn run_php_install(specs: Vec<String>, quiet: bool) -> Result<()> {
                 "algo": "sha256",
                 "hash": package_integrity.fs_graph,
             },
+            "dependencies": package.dependencies,
+            "requestedBy": package.requested_by,
+            "signature": signature,
         });
-        lock::update_lock_entry(
-            "php",
-            &release.package_name,
-            format!("{}@{}", release.package_name, release.version),
-            tarball_url,
-            metadata,
-            integrity,
-        )?;
+        entries.insert(
+            release.package_name.clone(),
+            (descriptor, tarball_url, metadata, integrity),
+        );
+    }
+    lock::replace_lock_section("php", entries)?;
+
+    if !requested.is_empty() {
+        for (name, range) in requested {
+            let range = match range {
+                Some(range) => range,
+                None => format!("^{}", resolution.packages[&name].version),
+            };
+            declared.insert(name, range);
+        }
+        save_php_dependencies(&declared)?;
+    }
+
+    let duration = Instant::now().duration_since(start);
+    emit_summary(installed_count, duration.as_millis() as u64, mode.quiet)?;
+    Ok(())
+}
+
+/// Install exactly what `deka.lock` records, for `--offline` and
+/// `--frozen-lockfile`. Nothing is resolved against the registry and the lock
+/// is never written; archives come from the cache or the locked URL and must
+/// match the locked integrity.
+async fn install_php_from_lock(specs: Vec<String>, mode: InstallMode) -> Result<()> {
+    let mut declared = load_php_dependencies()?;
+    let mut requested = BTreeMap::new();
+    for spec in &specs {
+        let normalized = normalize_php_spec(spec)?;
+        let (name, range) = parse_package_spec(&normalized);
+        requested.insert(name, range);
+    }
+    let lock_file = lock::read_lockfile();
+    let packages = &lock_file.php.packages;
+
+    let requirements = declared
+        .iter()
+        .map(|(name, range)| (name.clone(), Some(range.clone())))
+        .chain(requested.clone());
+    for (name, range) in requirements {
+        let req = match &range {
+            Some(range) => VersionReq::parse(range)
+                .with_context(|| format!("invalid version range for {}", name))?,
+            None => VersionReq::any(),
+        };
+        let satisfied = packages
+            .get(&name)
+            .and_then(locked_php_version)
+            .is_some_and(|version| req.matches(&version));
+        if !satisfied {
+            let wanted = format!("{}@{}", name, range.as_deref().unwrap_or("*"));
+            if mode.frozen {
+                bail!(
+                    "deka.lock is out of date: {} is not satisfied by the locked version (--frozen-lockfile)",
+                    wanted
+                );
+            }
+            bail!(
+                "offline: {} is not in deka.lock (run `deka install` online first)",
+                wanted
+            );
+        }
+    }
+    if packages.is_empty() {
+        bail!("no PHP packages locked in deka.lock");
+    }
+
+    let cache = CachePaths::new()?;
+    cache.ensure()?;
+    let project_policy = load_project_security_policy()?;
+    let start = Instant::now();
+    let mut installed_count = 0usize;
+
+    for (name, entry) in packages {
+        let Some(version) = locked_php_version(entry) else {
+            bail!(
+                "deka.lock has an invalid descriptor for {}: {}",
+                name,
+                entry.0
+            );
+        };
+        let version = version.to_string();
+        let release = locked_php_release(name, &version, &entry.2);
+        enforce_release_policy(&release, &project_policy)?;
+        verify_locked_signature(name, &version, entry, &project_policy)?;
+        let locked_graph = entry.2.pointer("/moduleGraph/hash").and_then(Value::as_str);
+        let destination = php_modules_path_for(name)?;
+        if destination.is_dir()
+            && let Ok(current) = compute_package_integrity(&destination)
+            && locked_graph == Some(current.module_graph.as_str())
+        {
+            continue;
+        }
+
+        let key = cache_key(&format!("php+{}", name), &version);
+        let archive_path = cache.archive_path(&key);
+        let bytes = match cached_archive(&cache, &key, Some(&entry.3), mode)? {
+            Some(bytes) => bytes,
+            None => {
+                if mode.offline {
+                    bail!(
+                        "offline: {} is not in the package cache (run `deka install` online first)",
+                        entry.0
+                    );
+                }
+                let bytes = download_tarball(&entry.1).await?;
+                verify_integrity(&bytes, &entry.3)
+                    .with_context(|| format!("downloaded {} does not match deka.lock", entry.0))?;
+                fs::write(&archive_path, &bytes).with_context(|| {
+                    format!("failed to write archive {}", archive_path.display())
+                })?;
+                bytes
+            }
+        };
+        if let Some(expected) = locked_tar_digest(entry)
+            && tar_digest(&bytes)? != expected
+        {
+            bail!(
+                "{} does not match the tar digest its signature covers",
+                entry.0
+            );
+        }
+        let cache_dir = cache.cache_dir(&key);
+        extract_tarball_bytes(&bytes, &cache_dir, &cache.tmp)?;
+        copy_package(&cache_dir, &destination)?;
+
+        let installed = compute_package_integrity(&destination)
+            .map_err(|err| anyhow!("failed to compute package integrity for {}: {}", name, err))?;
+        if let Some(expected) = locked_graph
+            && expected != installed.module_graph
+        {
+            bail!(
+                "{} does not match the module graph recorded in deka.lock",
+                entry.0
+            );
+        }
         installed_count += 1;
     }
 
+    if !mode.frozen {
+        let mut changed = false;
+        for (name, range) in requested {
+            if declared.contains_key(&name) && range.is_none() {
+                continue;
+            }
+            let range = match range {
+                Some(range) => range,
+                None => match packages.get(&name).and_then(locked_php_version) {
+                    Some(version) => format!("^{}", version),
+                    None => continue,
+                },
+            };
+            declared.insert(name, range);
+            changed = true;
+        }
+        if changed {
+            save_php_dependencies(&declared)?;
+        }
+    }
+
     let duration = Instant::now().duration_since(start);
-    emit_summary(installed_count, duration.as_millis() as u64, quiet)?;
+    emit_summary(installed_count, duration.as_millis() as u64, mode.quiet)?;
+    Ok(())
+}
+
+/// Release details recorded in a lock entry's metadata, enough to re-run the
+/// install-time policy checks without the registry.
+fn locked_php_release(name: &str, version: &str, metadata: &Value) -> PhpPackageRelease {
+    let text = |key: &str| {
+        metadata
+            .get(key)
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string()
+    };
+    PhpPackageRelease {
+        package_name: name.to_string(),
+        version: version.to_string(),
+        owner: text("owner"),
+        repo: text("repo"),
+        git_ref: text("gitRef"),
+        description: metadata
+            .get("description")
+            .and_then(Value::as_str)
+            .map(str::to_string),
+        manifest: metadata.get("manifest").filter(|v| !v.is_null()).cloned(),
+        capability_metadata: None,
+        integrity: None,
+        signature: None,
+        signing_key: None,
+    }
+}
+
+/// Check the publisher signature of `release` against its owner's registered
+/// keys. Returns what `deka.lock` records about the signature, or `None` for
+/// an unsigned release that policy allows.
+async fn verify_release_signature(
+    source: &LinkhashSource,
+    release: &PhpPackageRelease,
+    integrity: &str,
+    tar_digest: &str,
+    policy: &SecurityPolicy,
+    locked: Option<&lock::LockEntry>,
+) -> Result<Option<Value>> {
+    let descriptor = format!("{}@{}", release.package_name, release.version);
+    let Some(signature) = release.signature.as_deref() else {
+        // A package that was signed when it was locked must stay signed,
+        // otherwise stripping the signature would bypass the check.
+        if let Some(entry) = locked.filter(|entry| locked_signature(entry).is_some()) {
+            bail!(
+                "{} is not signed, but deka.lock recorded a signature for {}; refusing to install an unsigned release",
+                descriptor,
+                entry.0
+            );
+        }
+        if policy.require_signed_packages {
+            bail!(
+                "install blocked by security policy: {} is not signed (security.requireSignedPackages)",
+                descriptor
+            );
+        }
+        return Ok(None);
+    };
+    if let Some(expected) = release.integrity.as_deref()
+        && expected != integrity
+    {
+        bail!(
+            "{} tarball {} does not match the published integrity {}",
+            descriptor,
+            integrity,
+            expected
+        );
+    }
+    let keys = source.owner_keys(&release.owner).await?;
+    let message = release_message(&release.package_name, &release.version, tar_digest);
+    let fingerprint = verify_signature(
+        message.as_bytes(),
+        signature,
+        release.signing_key.as_deref(),
+        &keys,
+    )
+    .with_context(|| format!("signature check failed for {}", descriptor))?;
+    let public_key = keys
+        .iter()
+        .find(|line| parse_public_key(line).is_ok_and(|(_, fp)| fp == fingerprint))
+        .cloned();
+    Ok(Some(json!({
+        "keyFingerprint": fingerprint,
+        "publicKey": public_key,
+        "tarDigest": tar_digest,
+        "value": signature,
+    })))
+}
+
+/// Check the release signature against the archive bytes, then unpack them
+/// into `destination`. Nothing under php_modules changes when the check fails.
+async fn install_signed_php_archive(
+    source: &LinkhashSource,
+    release: &PhpPackageRelease,
+    bytes: &[u8],
+    policy: &SecurityPolicy,
+    cache: &CachePaths,
+    cache_dir: &Path,
+    destination: &Path,
+    locked: Option<&lock::LockEntry>,
+) -> Result<(String, Option<Value>)> {
+    let integrity = compute_sha512(bytes);
+    let digest = tar_digest(bytes)?;
+    let signature =
+        verify_release_signature(source, release, &integrity, &digest, policy, locked).await?;
+    extract_tarball_bytes(bytes, cache_dir, &cache.tmp)?;
+    copy_package(cache_dir, destination)?;
+    Ok((integrity, signature))
+}
+
+fn locked_signature(entry: &lock::LockEntry) -> Option<&Value> {
+    entry.2.get("signature").filter(|value| !value.is_null())
+}
+
+fn locked_tar_digest(entry: &lock::LockEntry) -> Option<&str> {
+    locked_signature(entry)?.get("tarDigest")?.as_str()
+}
+
+/// Re-check the signature recorded in `deka.lock` without the registry,
+/// against the key that verified it when the package was first locked.
+fn verify_locked_signature(
+    name: &str,
+    version: &str,
+    entry: &lock::LockEntry,
+    policy: &SecurityPolicy,
+) -> Result<()> {
+    let Some(recorded) = locked_signature(entry) else {
+        if policy.require_signed_packages {
+            bail!(
+                "install blocked by security policy: {} is not signed (security.requireSignedPackages)",
+                entry.0
+            );
+        }
+        return Ok(());
+    };
+    let field = |key: &str| recorded.get(key).and_then(Value::as_str);
+    let (Some(signature), Some(public_key), Some(digest)) =
+        (field("value"), field("publicKey"), field("tarDigest"))
+    else {
+        bail!("deka.lock has an incomplete signature for {}", entry.0);
+    };
+    let message = release_message(name, version, digest);
+    verify_signature(
+        message.as_bytes(),
+        signature,
+        field("keyFingerprint"),
+        &[public_key.to_string()],
+    )
+    .with_context(|| format!("signature check failed for {}", entry.0))?;
+    Ok(())
+}
+
+pub(crate) fn locked_php_version(entry: &lock::LockEntry) -> Option<Version> {
+    let (_, version) = parse_package_spec(&entry.0);
+    Version::parse(&version?).ok()
+}
+
+/// Dependency ranges a release declares in its `deka.json` manifest.
+fn release_dependencies(release: &PhpPackageRelease) -> Result<BTreeMap<String, String>> {
+    let Some(deps) = release
+        .manifest
+        .as_ref()
+        .and_then(|manifest| manifest.get("dependencies"))
+    else {
+        return Ok(BTreeMap::new());
+    };
+    let Some(deps) = deps.as_object() else {
+        bail!(
+            "{}@{} has a non-object `dependencies` manifest field",
+            release.package_name,
+            release.version
+        );
+    };
+    let mut out = BTreeMap::new();
+    for (name, range) in deps {
+        let Some(range) = range.as_str() else {
+            bail!(
+                "{}@{} declares a non-string range for {}",
+                release.package_name,
+                release.version,
+                name
+            );
+        };
+        out.insert(normalize_php_spec(name)?, range.trim().to_string());
+    }
+    Ok(out)
+}
+
+fn project_manifest_path() -> Result<PathBuf> {
+    let cwd = std::env::current_dir().context("failed to resolve current directory")?;
+    Ok(cwd.join("deka.json"))
+}
+
+/// `dependencies` from the project `deka.json`: php package -> version range.
+pub(crate) fn load_php_dependencies() -> Result<BTreeMap<String, String>> {
+    let path = project_manifest_path()?;
+    if !path.is_file() {
+        return Ok(BTreeMap::new());
+    }
+    let raw =
+        fs::read_to_string(&path).with_context(|| format!("failed to read {}", path.display()))?;
+    let json: Value = serde_json::from_str(&raw)
+        .with_context(|| format!("invalid JSON in {}", path.display()))?;
+    let mut out = BTreeMap::new();
+    let Some(deps) = json.get("dependencies") else {
+        return Ok(out);
+    };
+    let Some(deps) = deps.as_object() else {
+        bail!("`dependencies` in {} must be an object", path.display());
+    };
+    for (name, range) in deps {
+        let Some(range) = range.as_str() else {
+            bail!(
+                "`dependencies.{}` in {} must be a version range string",
+                name,
+                path.display()
+            );
+        };
+        out.insert(normalize_php_spec(name)?, range.trim().to_string());
+    }
+    Ok(out)
+}
+
+pub(crate) fn save_php_dependencies(deps: &BTreeMap<String, String>) -> Result<()> {
+    let path = project_manifest_path()?;
+    let mut doc = if path.is_file() {
+        let raw = fs::read_to_string(&path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        serde_json::from_str::<Value>(&raw)
+            .with_context(|| format!("invalid JSON in {}", path.display()))?
+    } else {
+        Value::Object(Map::new())
+    };
+    let Some(root) = doc.as_object_mut() else {
+        bail!("{} root must be an object", path.display());
+    };
+    let deps = deps
+        .iter()
+        .map(|(name, range)| (name.clone(), Value::String(range.clone())))
+        .collect::<Map<_, _>>();
+    root.insert("dependencies".to_string(), Value::Object(deps));
+    let mut out = serde_json::to_string_pretty(&doc)?;
+    out.push('\n');
+    fs::write(&path, out).with_context(|| format!("failed to write {}", path.display()))?;
     Ok(())
 }
 
@@ -497,7 +1161,7 @@ fn extract_release_capabilities(release: &PhpPackageRelease) -> Vec<String> {
     out
 }
 
-fn normalize_php_spec(spec: &str) -> Result<String> {
+pub(crate) fn normalize_php_spec(spec: &str) -> Result<String> {
     let trimmed = spec.trim();
     if trimmed.starts_with('@') {
         if is_valid_scoped_name(trimmed) {
@@ -543,11 +1207,11 @@ fn is_valid_scoped_name(spec: &str) -> bool {
             .all(|ch| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_')
 }
 
-fn linkhash_registry_url() -> String {
+pub(crate) fn linkhash_registry_url() -> String {
     std::env::var("LINKHASH_REGISTRY_URL").unwrap_or_else(|_| "http://localhost:8508".to_string())
 }
 
-async fn fetch_php_latest(registry: &str, name: &str) -> Result<String> {
+pub(crate) async fn fetch_php_summary(registry: &str, name: &str) -> Result<PhpPackageSummary> {
     let url = package_summary_url(registry, name)?;
 
     let response = reqwest::get(&url)
@@ -560,13 +1224,10 @@ async fn fetch_php_latest(registry: &str, name: &str) -> Result<String> {
             name
         );
     }
-    let payload = response
+    response
         .json::<PhpPackageSummary>()
         .await
-        .context("failed to parse package summary")?;
-    payload
-        .latest
-        .context("no latest version found for package")
+        .context("failed to parse package summary")
 }
 
 async fn fetch_php_release(registry: &str, name: &str, version: &str) -> Result<PhpPackageRelease> {
@@ -588,6 +1249,38 @@ async fn fetch_php_release(registry: &str, name: &str, version: &str) -> Result<
         .context("failed to parse package release")
 }
 
+async fn fetch_owner_keys(registry: &str, owner: &str) -> Result<Vec<String>> {
+    let url = format!(
+        "{}/api/users/{}/keys",
+        registry.trim_end_matches('/'),
+        urlencoding::encode(owner)
+    );
+    let response = reqwest::get(&url)
+        .await
+        .with_context(|| format!("failed to fetch signing keys for {}", owner))?;
+    if !response.status().is_success() {
+        bail!(
+            "signing key request failed ({}): {}",
+            response.status(),
+            owner
+        );
+    }
+    let body = response
+        .json::<Value>()
+        .await
+        .context("failed to parse signing keys")?;
+    Ok(body
+        .get("keys")
+        .and_then(Value::as_array)
+        .map(|keys| {
+            keys.iter()
+                .filter_map(|key| key.get("public_key").and_then(Value::as_str))
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
 fn package_summary_url(registry: &str, name: &str) -> Result<String> {
     let (scope, pkg) = parse_scoped_package(name)?;
     Ok(format!(
@@ -633,7 +1326,7 @@ fn parse_scoped_package(name: &str) -> Result<(&str, &str)> {
     Ok((scope, pkg))
 }
 
-fn php_modules_path_for(package_name: &str) -> Result<PathBuf> {
+pub(crate) fn php_modules_path_for(package_name: &str) -> Result<PathBuf> {
     let cwd = std::env::current_dir().context("failed to resolve current directory")?;
     let mut path = cwd.join("php_modules");
     for segment in package_name.split('/') {
@@ -647,9 +1340,19 @@ fn php_modules_path_for(package_name: &str) -> Result<PathBuf> {
 
 #[cfg(test)]
 mod tests {
-    use super::{PhpPackageRelease, enforce_release_policy, extract_release_capabilities};
+    use super::{
+        LinkhashSource, PhpPackageRelease, enforce_release_policy, extract_release_capabilities,
+        frozen_node_drift, install_signed_php_archive, verify_locked_signature,
+        verify_release_signature,
+    };
+    use crate::cache::CachePaths;
+    use crate::lock::LockEntry;
+    use flate2::{Compression, write::GzEncoder};
     use runtime_core::security_policy::{RuleList, SecurityPolicy, SecurityScope};
     use serde_json::json;
+    use std::collections::BTreeMap;
+    use std::fs;
+    use std::io::Write;
 
     fn sample_release(
         manifest: Option<serde_json::Value>,
@@ -664,6 +1367,9 @@ mod tests {
             description: None,
             manifest,
             capability_metadata,
+            integrity: None,
+            signature: None,
+            signing_key: None,
         }
     }
 
@@ -702,11 +1408,113 @@ mod tests {
                 ..SecurityScope::default()
             },
             prompt: true,
+            packages: Default::default(),
+            audit: None,
+            strict: false,
+            require_signed_packages: false,
         };
         let result = enforce_release_policy(&release, &policy);
         assert!(result.is_err());
     }
 
+    #[test]
+    fn require_signed_packages_rejects_unsigned_lock_entries() {
+        let entry = (
+            "@scope/pkg@1.0.0".to_string(),
+            String::new(),
+            json!({ "signature": null }),
+            "sha512-abc".to_string(),
+        );
+        let mut policy = SecurityPolicy::default();
+        assert!(verify_locked_signature("@scope/pkg", "1.0.0", &entry, &policy).is_ok());
+        policy.require_signed_packages = true;
+        let err = verify_locked_signature("@scope/pkg", "1.0.0", &entry, &policy).unwrap_err();
+        assert!(err.to_string().contains("is not signed"));
+    }
+
+    #[tokio::test]
+    async fn rejects_unsigned_release_when_lock_recorded_a_signature() {
+        let source = LinkhashSource::new("http://127.0.0.1:9".to_string());
+        let release = sample_release(None, None);
+        let policy = SecurityPolicy::default();
+        let unsigned = (
+            "@scope/pkg@1.0.0".to_string(),
+            String::new(),
+            json!({ "signature": null }),
+            "sha512-abc".to_string(),
+        );
+        let result = verify_release_signature(
+            &source,
+            &release,
+            "sha512-abc",
+            "sha512-tar",
+            &policy,
+            Some(&unsigned),
+        );
+        assert!(result.await.unwrap().is_none());
+
+        let signed = (
+            "@scope/pkg@1.0.0".to_string(),
+            String::new(),
+            json!({ "signature": { "value": "sig", "publicKey": "ssh-ed25519 AAAA" } }),
+            "sha512-abc".to_string(),
+        );
+        let result = verify_release_signature(
+            &source,
+            &release,
+            "sha512-abc",
+            "sha512-tar",
+            &policy,
+            Some(&signed),
+        );
+        let err = result.await.unwrap_err().to_string();
+        assert!(err.contains("deka.lock recorded a signature"));
+    }
+
+    #[tokio::test]
+    async fn rejected_signature_leaves_php_modules_untouched() {
+        let root = tempfile::tempdir().unwrap();
+        let cache = CachePaths {
+            global: root.path().join("cache"),
+            node: root.path().join("cache/node"),
+            archive: root.path().join("cache/archives"),
+            meta: root.path().join("cache/meta"),
+            tmp: root.path().join("cache/tmp"),
+            node_modules: root.path().join("node_modules"),
+        };
+        cache.ensure().unwrap();
+        let destination = root.path().join("php_modules/@scope/pkg");
+        fs::create_dir_all(&destination).unwrap();
+        fs::write(destination.join("index.phpx"), "installed").unwrap();
+
+        let mut release = sample_release(None, None);
+        release.signature = Some("unused".to_string());
+        release.integrity = Some("sha512-published".to_string());
+        let source = LinkhashSource::new("http://127.0.0.1:9".to_string());
+        let mut archive = GzEncoder::new(Vec::new(), Compression::default());
+        archive.write_all(b"not the published archive").unwrap();
+        let result = install_signed_php_archive(
+            &source,
+            &release,
+            &archive.finish().unwrap(),
+            &SecurityPolicy::default(),
+            &cache,
+            &cache.cache_dir("php+pkg"),
+            &destination,
+            None,
+        )
+        .await;
+
+        let err = result.unwrap_err().to_string();
+        assert!(err.contains("does not match the published integrity"));
+        assert_eq!(
+            fs::read_to_string(destination.join("index.phpx")).unwrap(),
+            "installed"
+        );
+        assert_eq!(fs::read_dir(&destination).unwrap().count(), 1);
+        assert!(!cache.cache_dir("php+pkg").exists());
+    }
+
     #[test]
     fn allows_install_when_policy_has_no_denies() {
         let release = sample_release(
@@ -724,10 +1532,60 @@ mod tests {
                 ..SecurityScope::default()
             },
             prompt: true,
+            packages: Default::default(),
+            audit: None,
+            strict: false,
+            require_signed_packages: false,
         };
         let result = enforce_release_policy(&release, &policy);
         assert!(result.is_ok());
     }
+
+    fn node_entry(descriptor: &str, metadata: serde_json::Value) -> LockEntry {
+        (
+            descriptor.to_string(),
+            String::new(),
+            metadata,
+            String::new(),
+        )
+    }
+
+    #[test]
+    fn frozen_install_compares_every_placed_copy() {
+        let locked = BTreeMap::from([
+            ("lib".to_string(), node_entry("lib@1.0.0", json!({}))),
+            (
+                "app/node_modules/lib".to_string(),
+                node_entry("lib@2.0.0", json!({})),
+            ),
+            ("stale".to_string(), node_entry("stale@1.0.0", json!({}))),
+            (
+                "@esbuild/win32-x64".to_string(),
+                node_entry(
+                    "@esbuild/win32-x64@0.20.0",
+                    json!({ "os": ["win32"], "cpu": ["x64"] }),
+                ),
+            ),
+        ]);
+        let mut placed = BTreeMap::from([
+            ("lib".to_string(), "lib@1.0.0".to_string()),
+            ("app/node_modules/lib".to_string(), "lib@2.0.0".to_string()),
+            ("stale".to_string(), "stale@1.0.0".to_string()),
+        ]);
+        assert!(frozen_node_drift(&locked, &placed, "linux", "x64").is_empty());
+
+        placed.remove("stale");
+        placed.insert("extra".to_string(), "extra@1.0.0".to_string());
+        placed.insert("app/node_modules/lib".to_string(), "lib@2.1.0".to_string());
+        assert_eq!(
+            frozen_node_drift(&locked, &placed, "linux", "x64"),
+            [
+                "  ~ app/node_modules/lib (locked lib@2.0.0, resolved lib@2.1.0)",
+                "  + extra (extra@1.0.0)",
+                "  - stale (stale@1.0.0)",
+            ]
+        );
+    }
 }
 
 pub fn run_probe(path: &PathBuf) -> Result<()> {
@@ -739,16 +1597,30 @@ pub fn run_probe(path: &PathBuf) -> Result<()> {
 async fn install_node_package(
     cache: &CachePaths,
     bun_lock: Option<&BunLock>,
+    resolutions: &NpmResolutions,
+    node_lock: &BTreeMap<String, lock::LockEntry>,
+    mode: InstallMode,
     spec: &str,
     lock_key: Option<&str>,
 ) -> Result<InstallResult> {
     let (name, version_spec) = parse_package_spec(spec);
-    let metadata = fetch_npm_metadata(&name).await?;
+    let metadata = resolutions.metadata(&name).await?;
     let lock_entry = bun_lock.and_then(|lock| lock.lookup(lock_key, &name));
-    let version = lock_entry
-        .map(|entry| entry.version.clone())
-        .or_else(|| resolve_package_version(&metadata, version_spec.as_deref()))
-        .context("unable to resolve package version")?;
+    let version = match lock_entry {
+        Some(entry) => {
+            resolutions.pin(&name, &entry.version);
+            entry.version.clone()
+        }
+        None => resolutions
+            .resolve(&name, &metadata, version_spec.as_deref())
+            .with_context(|| {
+                format!(
+                    "no version of {} matches `{}`",
+                    name,
+                    version_spec.as_deref().unwrap_or("latest")
+                )
+            })?,
+    };
 
     let version_info = metadata
         .get("versions")
@@ -756,8 +1628,42 @@ async fn install_node_package(
         .context("missing version info")?;
     let key = cache_key(&name, &version);
     let cache_dir = cache.cache_dir(&key);
+    let descriptor = format!("{}@{}", name, version);
+    // Nested copies are keyed by install path, so any entry locking this
+    // exact version vouches for the archive.
+    let locked_integrity = node_lock
+        .values()
+        .find(|entry| entry.0 == descriptor)
+        .map(|entry| entry.3.clone());
+    if mode.frozen && locked_integrity.is_none() {
+        bail!(
+            "deka.lock is out of date: {} is not locked (--frozen-lockfile)",
+            descriptor
+        );
+    }
+    let dist_integrity = version_info
+        .get("dist")
+        .and_then(|dist| dist.get("integrity"))
+        .and_then(|value| value.as_str())
+        .map(|s| s.to_string());
+    let expected = locked_integrity.as_deref().or(dist_integrity.as_deref());
+    let cached = match cached_archive(cache, &key, expected, mode)? {
+        // The unpacked cache dir is not covered by the archive hash; rebuild
+        // it from the bytes that were just verified before it is linked.
+        Some(bytes) => {
+            extract_tarball_bytes(&bytes, &cache_dir, &cache.tmp)?;
+            true
+        }
+        None => false,
+    };
+    if mode.offline && !cached {
+        bail!(
+            "offline: {} is not in the package cache (run `deka install` online first)",
+            descriptor
+        );
+    }
 
-    let (integrity, resolved) = if cache_dir.exists() {
+    let (integrity, resolved) = if cached {
         let meta_path = cache.metadata_path(&key);
         let meta = crate::cache::read_metadata(&meta_path);
         let integrity = meta
@@ -794,15 +1700,18 @@ async fn install_node_package(
             .and_then(|value| value.as_str())
             .context("tarball URL missing")?;
         let bytes = download_tarball(tarball_url).await?;
-        let integrity = version_info
-            .get("dist")
-            .and_then(|dist| dist.get("integrity"))
-            .and_then(|value| value.as_str())
-            .map(|s| s.to_string())
-            .unwrap_or_else(|| compute_sha512(&bytes));
+        if let Some(expected) = &dist_integrity {
+            verify_integrity(&bytes, expected)
+                .with_context(|| format!("downloaded {} failed verification", descriptor))?;
+        }
+        if let Some(expected) = &locked_integrity {
+            verify_integrity(&bytes, expected)
+                .with_context(|| format!("downloaded {} does not match deka.lock", descriptor))?;
+        }
+        let integrity = dist_integrity.unwrap_or_else(|| compute_sha512(&bytes));
         let archive_path = cache.archive_path(&key);
         fs::write(&archive_path, &bytes)?;
-        extract_tarball(&archive_path, &cache_dir, &cache.tmp)?;
+        extract_tarball_bytes(&bytes, &cache_dir, &cache.tmp)?;
         let meta = json!({
             "integrity": integrity,
             "resolved": tarball_url,
@@ -860,6 +1769,27 @@ async fn install_node_package(
     })
 }
 
+/// Cached archive bytes for `key` after checking them against `integrity`.
+/// A corrupt archive is an error offline; online it is dropped so the caller
+/// downloads a fresh copy.
+fn cached_archive(
+    cache: &CachePaths,
+    key: &str,
+    integrity: Option<&str>,
+    mode: InstallMode,
+) -> Result<Option<Vec<u8>>> {
+    match cache.read_archive(key, integrity) {
+        Ok(bytes) => Ok(bytes),
+        Err(err) if mode.offline => Err(err),
+        Err(err) => {
+            if !mode.quiet {
+                eprintln!("⚠️  {:#}; downloading again", err);
+            }
+            Ok(None)
+        }
+    }
+}
+
 fn collect_spec_list(value: Option<&Value>) -> Vec<String> {
     if let Some(Value::Object(map)) = value {
         map.iter()
@@ -889,6 +1819,8 @@ fn build_lock_metadata(value: &Value) -> Value {
         "peerDependencies",
         "optionalDependencies",
         "bin",
+        "os",
+        "cpu",
     ] {
         if let Some(val) = value.get(key) {
             if !val.is_null() {
@@ -899,6 +1831,38 @@ fn build_lock_metadata(value: &Value) -> Value {
     Value::Object(map)
 }
 
+/// Differences between the copies an install placed and the node lock, one
+/// line per addition (`+`), removal (`-`) or version change (`~`). Locked
+/// copies restricted to another platform are not expected here.
+fn frozen_node_drift(
+    locked: &BTreeMap<String, lock::LockEntry>,
+    placed: &BTreeMap<String, String>,
+    os: &str,
+    cpu: &str,
+) -> Vec<String> {
+    let mut drift = Vec::new();
+    for (key, descriptor) in placed {
+        match locked.get(key) {
+            None => drift.push(format!("  + {} ({})", key, descriptor)),
+            Some(entry) if &entry.0 != descriptor => drift.push(format!(
+                "  ~ {} (locked {}, resolved {})",
+                key, entry.0, descriptor
+            )),
+            Some(_) => {}
+        }
+    }
+    for (key, entry) in locked {
+        if placed.contains_key(key)
+            || !matches_requirement(entry.2.get("os"), os)
+            || !matches_requirement(entry.2.get("cpu"), cpu)
+        {
+            continue;
+        }
+        drift.push(format!("  - {} ({})", key, entry.0));
+    }
+    drift
+}
+
 fn collect_project_specs() -> Result<Vec<String>> {
     let manifest = fs::read_to_string("package.json")
         .context("package.json not found in current directory")?;
@@ -938,7 +1902,7 @@ fn prompt_yes_no(message: &str) -> Result<bool> {
     Ok(normalized == "y" || normalized == "yes")
 }
 
-fn emit_summary(installed: usize, duration_ms: u64, quiet: bool) -> Result<()> {
+pub(crate) fn emit_summary(installed: usize, duration_ms: u64, quiet: bool) -> Result<()> {
     if quiet {
         return Ok(());
     }
@@ -1026,8 +1990,10 @@ impl InstallContext {
         true
     }
 
+    /// Target directory for `name@version`, or `None` when that exact copy
+    /// is already placed there (compatible ranges resolve to one version).
     fn determine_install_path(
-        &self,
+        &mut self,
         name: &str,
         version: &str,
         lock_key: Option<&str>,
@@ -1069,7 +2035,7 @@ impl InstallContext {
         let path_str = target_path.to_string_lossy();
         let install_key = format!("{}:{}@{}", path_str, name, version);
 
-        if self.installed.contains(&install_key) {
+        if !self.installed.insert(install_key.clone()) {
             if std::env::var("DEKA_DEBUG").is_ok() {
                 eprintln!(
                     "[DEBUG] {} already installed at {:?}, skipping",
@@ -1,10 +1,17 @@
+pub mod audit;
 pub mod bun_lock;
 pub mod cache;
 pub mod install;
 pub mod lock;
+pub mod manage;
 pub mod npm;
 pub mod payload;
+pub mod php_resolver;
+pub mod semver;
+pub mod signature;
 pub mod spec;
 
+pub use audit::{AuditFinding, Severity, run_audit};
 pub use install::run_install;
+pub use manage::{OutdatedPackage, outdated_packages, run_remove, run_update};
 pub use payload::InstallPayload;
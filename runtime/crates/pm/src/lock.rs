@@ -4,7 +4,7 @@ use serde_json::Value;
 use std::collections::BTreeMap;
 use std::fs::File;
 use std::io::Read;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 pub const LOCKFILE_NAME: &str = "deka.lock";
 
@@ -41,6 +41,39 @@ impl Default for DekaLock {
 
 pub type LockEntry = (String, String, Value, String);
 
+/// Node entries are keyed by install path below `node_modules`, so a nested
+/// copy (`a/node_modules/b`) keeps its own entry next to the hoisted `b`.
+/// Hoisted packages are keyed by their plain name.
+pub fn node_lock_key(node_modules: &Path, install_path: &Path) -> String {
+    install_path
+        .strip_prefix(node_modules)
+        .unwrap_or(install_path)
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Key that `dependency` resolves to from the node entry at `from`: the
+/// closest nested copy up the tree, then the hoisted one.
+pub fn resolve_node_key(
+    packages: &BTreeMap<String, LockEntry>,
+    from: &str,
+    dependency: &str,
+) -> Option<String> {
+    let mut scope = Some(from);
+    while let Some(dir) = scope {
+        let key = format!("{dir}/node_modules/{dependency}");
+        if packages.contains_key(&key) {
+            return Some(key);
+        }
+        scope = dir.rsplit_once("/node_modules/").map(|(parent, _)| parent);
+    }
+    packages
+        .contains_key(dependency)
+        .then(|| dependency.to_string())
+}
+
 fn lock_path() -> Option<PathBuf> {
     let cwd = std::env::current_dir().ok()?;
     Some(cwd.join(LOCKFILE_NAME))
@@ -94,3 +127,17 @@ pub fn update_lock_entry(
     write_lockfile(&lock)?;
     Ok(())
 }
+
+/// Replace every entry of one ecosystem, e.g. with a freshly resolved graph.
+pub fn replace_lock_section(ecosystem: &str, packages: BTreeMap<String, LockEntry>) -> Result<()> {
+    let mut lock = read_lockfile();
+    match ecosystem {
+        "node" => lock.node.packages = packages,
+        "php" => lock.php.packages = packages,
+        other => {
+            return Err(anyhow!("unknown ecosystem {}", other));
+        }
+    }
+    write_lockfile(&lock)?;
+    Ok(())
+}
@@ -0,0 +1,481 @@
+//! Dependency maintenance: `deka outdated`, `deka update` and `deka remove`.
+//!
+//! Direct dependencies come from `package.json` (node) and the
+//! `dependencies` object of `deka.json` (php); everything else is reached
+//! through the dependency names recorded in `deka.lock` metadata.
+
+use crate::{
+    install::{
+        InstallMode, fetch_php_summary, install_node_specs, linkhash_registry_url,
+        load_php_dependencies, locked_php_version, normalize_php_spec, php_modules_path_for,
+        run_php_install, save_php_dependencies,
+    },
+    lock::{self, LockEntry},
+    npm::{fetch_npm_metadata, resolve_package_version},
+    php_resolver::ROOT_REQUIRER,
+    semver::{VersionReq, max_satisfying},
+    spec::{Ecosystem, parse_package_spec},
+};
+use anyhow::{Context, Result, bail};
+use serde_json::Value;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    fs,
+    path::PathBuf,
+};
+
+const NODE_SECTIONS: [&str; 3] = ["dependencies", "devDependencies", "optionalDependencies"];
+
+/// Metadata fields of a lock entry that name further dependencies.
+const EDGE_FIELDS: [&str; 3] = ["dependencies", "optionalDependencies", "peerDependencies"];
+
+#[derive(Debug, Clone)]
+pub struct OutdatedPackage {
+    pub ecosystem: Ecosystem,
+    pub name: String,
+    /// Range declared in `package.json` / `deka.json`.
+    pub range: String,
+    /// Version recorded in `deka.lock`, if installed.
+    pub current: Option<String>,
+    /// Newest version the declared range allows.
+    pub wanted: Option<String>,
+    /// Newest published version.
+    pub latest: Option<String>,
+}
+
+impl OutdatedPackage {
+    pub fn is_outdated(&self) -> bool {
+        self.current.is_none() || self.current != self.wanted || self.current != self.latest
+    }
+}
+
+/// Current, wanted and latest versions for every direct dependency.
+pub async fn outdated_packages() -> Result<Vec<OutdatedPackage>> {
+    let lock_file = lock::read_lockfile();
+    let mut out = Vec::new();
+
+    for (name, range) in load_node_dependencies()? {
+        let metadata = fetch_npm_metadata(&name).await?;
+        let current = lock_file
+            .node
+            .packages
+            .get(&name)
+            .and_then(|entry| parse_package_spec(&entry.0).1);
+        out.push(OutdatedPackage {
+            ecosystem: Ecosystem::Node,
+            wanted: resolve_package_version(&metadata, Some(&range)),
+            latest: resolve_package_version(&metadata, Some("latest")),
+            name,
+            range,
+            current,
+        });
+    }
+
+    let registry = linkhash_registry_url();
+    for (name, range) in load_php_dependencies()? {
+        let req = VersionReq::parse(&range)
+            .with_context(|| format!("invalid version range for {} in deka.json", name))?;
+        let summary = fetch_php_summary(&registry, &name).await?;
+        let versions = || {
+            summary
+                .versions
+                .iter()
+                .chain(summary.latest.iter())
+                .map(String::as_str)
+        };
+        let latest = summary.latest.clone().or_else(|| {
+            max_satisfying(versions(), &VersionReq::any()).map(|version| version.to_string())
+        });
+        out.push(OutdatedPackage {
+            ecosystem: Ecosystem::Php,
+            current: lock_file
+                .php
+                .packages
+                .get(&name)
+                .and_then(locked_php_version)
+                .map(|version| version.to_string()),
+            wanted: max_satisfying(versions(), &req).map(|version| version.to_string()),
+            latest,
+            name,
+            range,
+        });
+    }
+    Ok(out)
+}
+
+/// Move `names` (every direct dependency when empty) to the newest versions
+/// their declared ranges allow. Other packages keep their locked versions.
+pub async fn run_update(
+    names: Vec<String>,
+    ecosystem: Option<Ecosystem>,
+    quiet: bool,
+) -> Result<()> {
+    let node_deps = load_node_dependencies()?;
+    let php_deps = load_php_dependencies()?;
+    let lock_file = lock::read_lockfile();
+    let (node_targets, php_targets) = if names.is_empty() {
+        (
+            node_deps.keys().cloned().collect::<HashSet<_>>(),
+            php_deps.keys().cloned().collect::<BTreeSet<_>>(),
+        )
+    } else {
+        let mut node_targets = HashSet::new();
+        let mut php_targets = BTreeSet::new();
+        for raw in &names {
+            match classify(
+                raw,
+                ecosystem,
+                &node_deps,
+                &php_deps,
+                &lock_file.php.packages,
+            )? {
+                (Ecosystem::Node, name) => node_targets.insert(name),
+                (Ecosystem::Php, name) => php_targets.insert(name),
+            };
+        }
+        (node_targets, php_targets)
+    };
+    let mode = InstallMode {
+        quiet,
+        ..InstallMode::default()
+    };
+
+    if !php_targets.is_empty() {
+        // Re-requesting a package with its declared range drops its lock
+        // preference, so it resolves to the newest match. Packages locked
+        // before deka.json tracked dependencies get added to it.
+        let specs = php_targets
+            .iter()
+            .map(|name| match php_deps.get(name) {
+                Some(range) => format!("{}@{}", name, range),
+                None => name.clone(),
+            })
+            .collect::<Vec<_>>();
+        run_php_install(specs, mode).await?;
+    }
+
+    if !node_targets.is_empty() {
+        let specs = node_deps
+            .iter()
+            .map(|(name, range)| {
+                let locked = lock_file.node.packages.get(name);
+                match locked {
+                    Some(entry) if !node_targets.contains(name) => entry.0.clone(),
+                    _ if range.trim().is_empty() => name.clone(),
+                    _ => format!("{}@{}", name, range),
+                }
+            })
+            .collect::<Vec<_>>();
+        install_node_specs(specs, Ecosystem::Node, mode, Some(&node_targets)).await?;
+        prune(Ecosystem::Node, node_deps.keys().cloned().collect(), quiet)?;
+    }
+    Ok(())
+}
+
+/// Drop `names` from the project manifests, `deka.lock` and the install
+/// directories, then prune dependencies nothing else requires.
+pub fn run_remove(names: Vec<String>, ecosystem: Option<Ecosystem>, quiet: bool) -> Result<()> {
+    if names.is_empty() {
+        bail!("no packages given (usage: deka remove <package>...)");
+    }
+    let mut node_deps = load_node_dependencies()?;
+    let mut php_deps = load_php_dependencies()?;
+    let lock_file = lock::read_lockfile();
+    let mut node_removed = BTreeSet::new();
+    let mut php_removed = BTreeSet::new();
+    for raw in &names {
+        match classify(
+            raw,
+            ecosystem,
+            &node_deps,
+            &php_deps,
+            &lock_file.php.packages,
+        )? {
+            (Ecosystem::Node, name) => {
+                node_deps.remove(&name);
+                node_removed.insert(name);
+            }
+            (Ecosystem::Php, name) => {
+                php_deps.remove(&name);
+                php_removed.insert(name);
+            }
+        }
+    }
+
+    // A removed package another dependency still requires leaves the
+    // manifests but keeps its locked copy; pruning treats it as transitive.
+    let php_roots = php_roots(&php_deps, &lock_file.php.packages, &php_removed);
+    let still_required = reachable(&lock_file.php.packages, php_roots.iter().cloned())
+        .into_iter()
+        .chain(reachable(
+            &lock_file.node.packages,
+            node_deps.keys().cloned(),
+        ))
+        .filter(|name| php_removed.contains(name) || node_removed.contains(name));
+    if !quiet {
+        for name in still_required {
+            eprintln!(" {} is still required by another dependency; kept", name);
+        }
+    }
+
+    if !node_removed.is_empty() {
+        save_node_dependencies(&node_removed)?;
+        prune(Ecosystem::Node, node_deps.keys().cloned().collect(), quiet)?;
+    }
+    if !php_removed.is_empty() {
+        save_php_dependencies(&php_deps)?;
+        prune(Ecosystem::Php, php_roots, quiet)?;
+    }
+    Ok(())
+}
+
+/// Work out which ecosystem `raw` belongs to and its normalized name.
+fn classify(
+    raw: &str,
+    hint: Option<Ecosystem>,
+    node_deps: &BTreeMap<String, String>,
+    php_deps: &BTreeMap<String, String>,
+    php_locked: &BTreeMap<String, LockEntry>,
+) -> Result<(Ecosystem, String)> {
+    let (name, _) = parse_package_spec(raw.trim());
+    if hint != Some(Ecosystem::Php) && node_deps.contains_key(&name) {
+        return Ok((Ecosystem::Node, name));
+    }
+    if hint != Some(Ecosystem::Node)
+        && let Ok(php_name) = normalize_php_spec(&name)
+        && (php_deps.contains_key(&php_name) || php_locked.contains_key(&php_name))
+    {
+        return Ok((Ecosystem::Php, php_name));
+    }
+    bail!("{} is not a dependency in package.json or deka.json", name)
+}
+
+/// Roots of the php graph: `deka.json` dependencies plus packages locked
+/// before `deka.json` tracked dependencies.
+fn php_roots(
+    php_deps: &BTreeMap<String, String>,
+    php_locked: &BTreeMap<String, LockEntry>,
+    removed: &BTreeSet<String>,
+) -> BTreeSet<String> {
+    let legacy = php_locked
+        .iter()
+        .filter(|(name, entry)| entry.2.get("requestedBy").is_none() && !removed.contains(*name))
+        .map(|(name, _)| name.clone());
+    php_deps.keys().cloned().chain(legacy).collect()
+}
+
+/// Keys in `packages` reachable from `roots` through the dependency names
+/// recorded in each lock entry's metadata. A dependency resolves to the
+/// closest nested node copy before the hoisted (or php) entry of that name.
+fn reachable(
+    packages: &BTreeMap<String, LockEntry>,
+    roots: impl IntoIterator<Item = String>,
+) -> BTreeSet<String> {
+    let mut seen = BTreeSet::new();
+    let mut stack = roots.into_iter().collect::<Vec<_>>();
+    while let Some(key) = stack.pop() {
+        let Some(entry) = packages.get(&key) else {
+            continue;
+        };
+        if !seen.insert(key.clone()) {
+            continue;
+        }
+        for field in EDGE_FIELDS {
+            if let Some(Value::Object(deps)) = entry.2.get(field) {
+                stack.extend(
+                    deps.keys()
+                        .filter_map(|dep| lock::resolve_node_key(packages, &key, dep))
+                        .filter(|key| !seen.contains(key)),
+                );
+            }
+        }
+    }
+    seen
+}
+
+/// Remove lock entries and installed copies no longer reachable from `roots`.
+fn prune(ecosystem: Ecosystem, roots: BTreeSet<String>, quiet: bool) -> Result<()> {
+    let mut lock_file = lock::read_lockfile();
+    let section = match ecosystem {
+        Ecosystem::Node => &mut lock_file.node.packages,
+        Ecosystem::Php => &mut lock_file.php.packages,
+    };
+    let keep = reachable(section, roots.iter().cloned());
+    let orphans = section
+        .keys()
+        .filter(|name| !keep.contains(*name))
+        .cloned()
+        .collect::<Vec<_>>();
+    for name in &orphans {
+        section.remove(name);
+        let path = install_path(ecosystem, name)?;
+        if path.exists() {
+            fs::remove_dir_all(&path)
+                .with_context(|| format!("failed to remove {}", path.display()))?;
+        }
+        if !quiet {
+            eprintln!(" - {}", name);
+        }
+    }
+    if ecosystem == Ecosystem::Php {
+        // Requirers that were just removed no longer pull anything in.
+        for entry in section.values_mut() {
+            if let Some(Value::Array(requested_by)) = entry.2.get_mut("requestedBy") {
+                requested_by.retain(|requirer| match requirer.as_str() {
+                    Some(ROOT_REQUIRER) => true,
+                    Some(requirer) => keep.contains(&parse_package_spec(requirer).0),
+                    None => false,
+                });
+            }
+        }
+        // Direct dependencies dropped from deka.json lose their root marker.
+        for (name, entry) in section.iter_mut() {
+            if roots.contains(name) {
+                continue;
+            }
+            if let Some(Value::Array(requested_by)) = entry.2.get_mut("requestedBy") {
+                requested_by.retain(|requirer| requirer.as_str() != Some(ROOT_REQUIRER));
+            }
+        }
+    }
+    lock::write_lockfile(&lock_file)?;
+    if !quiet && !orphans.is_empty() {
+        eprintln!(" {} package(s) removed", orphans.len());
+    }
+    Ok(())
+}
+
+fn install_path(ecosystem: Ecosystem, name: &str) -> Result<PathBuf> {
+    match ecosystem {
+        Ecosystem::Php => php_modules_path_for(name),
+        Ecosystem::Node => {
+            let cwd = std::env::current_dir().context("failed to resolve current directory")?;
+            Ok(name
+                .split('/')
+                .fold(cwd.join("node_modules"), |path, segment| path.join(segment)))
+        }
+    }
+}
+
+/// Direct node dependencies from `package.json`, if there is one.
+fn load_node_dependencies() -> Result<BTreeMap<String, String>> {
+    let Some(pkg_json) = read_package_json()? else {
+        return Ok(BTreeMap::new());
+    };
+    let mut deps = BTreeMap::new();
+    for section in NODE_SECTIONS {
+        if let Some(Value::Object(map)) = pkg_json.get(section) {
+            for (name, value) in map {
+                if let Some(range) = value.as_str() {
+                    deps.insert(name.clone(), range.trim().to_string());
+                }
+            }
+        }
+    }
+    Ok(deps)
+}
+
+/// Drop `removed` from every dependency section of `package.json`.
+fn save_node_dependencies(removed: &BTreeSet<String>) -> Result<()> {
+    let Some(mut pkg_json) = read_package_json()? else {
+        return Ok(());
+    };
+    for section in NODE_SECTIONS {
+        if let Some(Value::Object(map)) = pkg_json.get_mut(section) {
+            map.retain(|name, _| !removed.contains(name));
+        }
+    }
+    let mut out = serde_json::to_string_pretty(&pkg_json)?;
+    out.push('\n');
+    fs::write("package.json", out).context("failed to write package.json")?;
+    Ok(())
+}
+
+fn read_package_json() -> Result<Option<Value>> {
+    let raw = match fs::read_to_string("package.json") {
+        Ok(raw) => raw,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err).context("failed to read package.json"),
+    };
+    let json = serde_json::from_str(&raw).context("failed to parse package.json")?;
+    Ok(Some(json))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{php_roots, reachable};
+    use crate::lock::LockEntry;
+    use serde_json::{Value, json};
+    use std::collections::{BTreeMap, BTreeSet};
+
+    fn entry(metadata: Value) -> LockEntry {
+        (String::new(), String::new(), metadata, String::new())
+    }
+
+    #[test]
+    fn finds_orphans_through_lock_metadata() {
+        let packages = BTreeMap::from([
+            (
+                "app".to_string(),
+                entry(json!({ "dependencies": { "lib": "^1.0.0" } })),
+            ),
+            (
+                "lib".to_string(),
+                entry(json!({ "optionalDependencies": { "native": "1.0.0" } })),
+            ),
+            ("native".to_string(), entry(json!({}))),
+            (
+                "old".to_string(),
+                entry(json!({ "dependencies": { "lib": "^1.0.0", "leftpad": "*" } })),
+            ),
+            ("leftpad".to_string(), entry(json!({}))),
+        ]);
+        let keep = reachable(&packages, ["app".to_string()]);
+        assert_eq!(
+            keep.into_iter().collect::<Vec<_>>(),
+            ["app", "lib", "native"]
+        );
+    }
+
+    #[test]
+    fn follows_nested_node_copies_before_hoisted_ones() {
+        let packages = BTreeMap::from([
+            (
+                "app".to_string(),
+                entry(json!({ "dependencies": { "lib": "^2.0.0" } })),
+            ),
+            ("lib".to_string(), entry(json!({}))),
+            (
+                "app/node_modules/lib".to_string(),
+                entry(json!({ "dependencies": { "leftpad": "*" } })),
+            ),
+            ("leftpad".to_string(), entry(json!({}))),
+            ("other/node_modules/lib".to_string(), entry(json!({}))),
+        ]);
+        let keep = reachable(&packages, ["app".to_string()]);
+        assert_eq!(
+            keep.into_iter().collect::<Vec<_>>(),
+            ["app", "app/node_modules/lib", "leftpad"]
+        );
+    }
+
+    #[test]
+    fn keeps_legacy_php_entries_as_roots() {
+        let locked = BTreeMap::from([
+            (
+                "@acme/http".to_string(),
+                entry(json!({ "requestedBy": ["deka.json"] })),
+            ),
+            ("@acme/legacy".to_string(), entry(json!({}))),
+            ("@acme/gone".to_string(), entry(json!({}))),
+        ]);
+        let deps = BTreeMap::from([("@acme/http".to_string(), "^1.0".to_string())]);
+        let removed = BTreeSet::from(["@acme/gone".to_string()]);
+        assert_eq!(
+            php_roots(&deps, &locked, &removed)
+                .into_iter()
+                .collect::<Vec<_>>(),
+            ["@acme/http", "@acme/legacy"]
+        );
+    }
+}
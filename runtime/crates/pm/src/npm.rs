@@ -1,5 +1,11 @@
+use crate::cache::sanitize_name;
+use crate::semver::{Version, VersionReq};
 use anyhow::{Context, Result, bail};
-use serde_json::Value;
+use serde_json::{Map, Value, json};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 use urlencoding::encode;
 
 pub async fn fetch_npm_metadata(name: &str) -> Result<Value> {
@@ -24,22 +30,231 @@ pub async fn fetch_npm_metadata(name: &str) -> Result<Value> {
     Ok(value)
 }
 
+/// Registry-shaped metadata built from the extracted packages in the cache,
+/// listing only the versions available offline.
+pub fn cached_npm_metadata(node_cache: &Path, name: &str) -> Result<Value> {
+    let prefix = format!("{}@", sanitize_name(name));
+    let mut versions = Map::new();
+    if let Ok(entries) = fs::read_dir(node_cache) {
+        for entry in entries.flatten() {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            let Some(version) = file_name.strip_prefix(&prefix) else {
+                continue;
+            };
+            let manifest = fs::read_to_string(entry.path().join("package.json"))
+                .ok()
+                .and_then(|raw| serde_json::from_str::<Value>(&raw).ok())
+                .unwrap_or_else(|| json!({}));
+            versions.insert(version.to_string(), manifest);
+        }
+    }
+    if versions.is_empty() {
+        bail!(
+            "offline: {} is not in the package cache (run `deka install` online first)",
+            name
+        );
+    }
+    Ok(json!({ "versions": versions }))
+}
+
+/// Registry metadata and the versions picked so far during one install, so
+/// compatible ranges across the dependency tree settle on a single copy.
+#[derive(Default)]
+pub struct NpmResolutions {
+    metadata: Mutex<HashMap<String, Arc<Value>>>,
+    chosen: Mutex<HashMap<String, Vec<String>>>,
+    /// Set for `--offline`: metadata comes from this cache directory.
+    offline_cache: Option<PathBuf>,
+}
+
+impl NpmResolutions {
+    pub fn offline(node_cache: PathBuf) -> Self {
+        Self {
+            offline_cache: Some(node_cache),
+            ..Self::default()
+        }
+    }
+
+    pub async fn metadata(&self, name: &str) -> Result<Arc<Value>> {
+        if let Some(metadata) = self.metadata.lock().unwrap().get(name) {
+            return Ok(metadata.clone());
+        }
+        let metadata = match &self.offline_cache {
+            Some(node_cache) => cached_npm_metadata(node_cache, name)?,
+            None => fetch_npm_metadata(name).await?,
+        };
+        let metadata = Arc::new(metadata);
+        self.metadata
+            .lock()
+            .unwrap()
+            .insert(name.to_string(), metadata.clone());
+        Ok(metadata)
+    }
+
+    /// Resolve `hint` for `name`, reusing a version already chosen for another
+    /// dependent when it satisfies the range.
+    pub fn resolve(&self, name: &str, metadata: &Value, hint: Option<&str>) -> Option<String> {
+        let mut chosen = self.chosen.lock().unwrap();
+        let preferred = chosen.get(name).map(Vec::as_slice).unwrap_or_default();
+        let version = resolve_package_version_preferring(metadata, hint, preferred)?;
+        record_choice(&mut chosen, name, &version);
+        Some(version)
+    }
+
+    /// Record a version pinned elsewhere (e.g. by `bun.lock`).
+    pub fn pin(&self, name: &str, version: &str) {
+        let mut chosen = self.chosen.lock().unwrap();
+        record_choice(&mut chosen, name, version);
+    }
+}
+
+fn record_choice(chosen: &mut HashMap<String, Vec<String>>, name: &str, version: &str) {
+    let versions = chosen.entry(name.to_string()).or_default();
+    if !versions.iter().any(|existing| existing == version) {
+        versions.push(version.to_string());
+    }
+}
+
 pub fn resolve_package_version(metadata: &Value, hint: Option<&str>) -> Option<String> {
+    resolve_package_version_preferring(metadata, hint, &[])
+}
+
+/// npm resolution order: an exact version key, then a dist-tag, then the
+/// range. For ranges an already `preferred` version wins, then `latest` when
+/// it satisfies, then the highest matching version.
+pub fn resolve_package_version_preferring(
+    metadata: &Value,
+    hint: Option<&str>,
+    preferred: &[String],
+) -> Option<String> {
+    let versions = metadata.get("versions").and_then(Value::as_object)?;
+    let tags = metadata.get("dist-tags").and_then(Value::as_object);
+    let tag = |name: &str| {
+        tags.and_then(|tags| tags.get(name))
+            .and_then(Value::as_str)
+            .filter(|version| versions.contains_key(*version))
+            .map(str::to_string)
+    };
+
+    let hint = hint.map(str::trim).filter(|hint| !hint.is_empty());
     if let Some(hint) = hint {
-        if metadata.get("versions").and_then(|v| v.get(hint)).is_some() {
+        if versions.contains_key(hint) {
             return Some(hint.to_string());
         }
+        if let Some(version) = tag(hint) {
+            return Some(version);
+        }
+    }
+    let req = match hint {
+        Some(hint) => VersionReq::parse(hint).ok()?,
+        None => VersionReq::any(),
+    };
+
+    let best = |candidates: &mut dyn Iterator<Item = &String>| {
+        candidates
+            .filter(|raw| versions.contains_key(raw.as_str()))
+            .filter_map(|raw| Version::parse(raw).ok().map(|version| (version, raw)))
+            .filter(|(version, _)| req.matches(version))
+            .max_by(|a, b| a.0.cmp(&b.0))
+            .map(|(_, raw)| raw.clone())
+    };
+    if let Some(version) = best(&mut preferred.iter()) {
+        return Some(version);
     }
+    if let Some(latest) = tag("latest")
+        && Version::parse(&latest).is_ok_and(|version| req.matches(&version))
+    {
+        return Some(latest);
+    }
+    best(&mut versions.keys())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        NpmResolutions, cached_npm_metadata, resolve_package_version,
+        resolve_package_version_preferring,
+    };
+    use serde_json::json;
 
-    if let Some(Value::String(latest)) = metadata.get("dist-tags").and_then(|v| v.get("latest")) {
-        return Some(latest.clone());
+    fn metadata() -> serde_json::Value {
+        json!({
+            "dist-tags": { "latest": "4.2.1", "next": "5.0.0-rc.1" },
+            "versions": {
+                "3.9.0": {},
+                "4.0.0": {},
+                "4.2.1": {},
+                "4.3.0": {},
+                "5.0.0-rc.1": {}
+            }
+        })
     }
 
-    if let Some(Value::Object(map)) = metadata.get("versions") {
-        if let Some(first) = map.keys().next() {
-            return Some(first.clone());
-        }
+    #[test]
+    fn resolves_ranges_tags_and_exact_versions() {
+        let meta = metadata();
+        let resolve = |hint: Option<&str>| resolve_package_version(&meta, hint);
+        assert_eq!(resolve(Some("4.0.0")).as_deref(), Some("4.0.0"));
+        assert_eq!(resolve(Some("next")).as_deref(), Some("5.0.0-rc.1"));
+        assert_eq!(resolve(None).as_deref(), Some("4.2.1"));
+        // latest satisfies the range, so npm keeps it over the newer 4.3.0
+        assert_eq!(resolve(Some("^4.0.0")).as_deref(), Some("4.2.1"));
+        assert_eq!(resolve(Some("~4.3")).as_deref(), Some("4.3.0"));
+        assert_eq!(resolve(Some(">=1 <4")).as_deref(), Some("3.9.0"));
+        assert_eq!(
+            resolve(Some("^3 || ^5.0.0-rc.0")).as_deref(),
+            Some("5.0.0-rc.1")
+        );
+        assert_eq!(resolve(Some("3.x - 4.0")).as_deref(), Some("4.0.0"));
+        assert_eq!(resolve(Some("^6")), None);
+        assert_eq!(resolve(Some("github:user/repo")), None);
     }
 
-    None
+    #[test]
+    fn builds_offline_metadata_from_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let cached = dir.path().join("@scope+pkg@1.2.0");
+        std::fs::create_dir_all(&cached).unwrap();
+        std::fs::write(
+            cached.join("package.json"),
+            r#"{"name":"@scope/pkg","dependencies":{"dep":"^1.0.0"}}"#,
+        )
+        .unwrap();
+        std::fs::create_dir_all(dir.path().join("php+@scope+pkg@9.0.0")).unwrap();
+
+        let meta = cached_npm_metadata(dir.path(), "@scope/pkg").unwrap();
+        assert_eq!(
+            resolve_package_version(&meta, Some("^1")).as_deref(),
+            Some("1.2.0")
+        );
+        assert_eq!(
+            meta.pointer("/versions/1.2.0/dependencies/dep"),
+            Some(&json!("^1.0.0"))
+        );
+        assert!(cached_npm_metadata(dir.path(), "missing").is_err());
+    }
+
+    #[test]
+    fn prefers_versions_already_in_the_tree() {
+        let meta = metadata();
+        let preferred = vec!["4.0.0".to_string()];
+        assert_eq!(
+            resolve_package_version_preferring(&meta, Some("^4.0.0"), &preferred).as_deref(),
+            Some("4.0.0")
+        );
+        assert_eq!(
+            resolve_package_version_preferring(&meta, Some("^4.1.0"), &preferred).as_deref(),
+            Some("4.2.1")
+        );
+
+        let resolutions = NpmResolutions::default();
+        assert_eq!(
+            resolutions.resolve("pkg", &meta, Some("~4.3")).as_deref(),
+            Some("4.3.0")
+        );
+        assert_eq!(
+            resolutions.resolve("pkg", &meta, Some("^4.0.0")).as_deref(),
+            Some("4.3.0")
+        );
+    }
 }
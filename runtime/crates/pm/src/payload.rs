@@ -15,6 +15,12 @@ pub struct InstallPayload {
     pub quiet: bool,
     #[serde(default)]
     pub rehash: bool,
+    /// Install from the package cache only; fail on any cache miss.
+    #[serde(default)]
+    pub offline: bool,
+    /// Fail instead of changing `deka.lock`.
+    #[serde(default)]
+    pub frozen_lockfile: bool,
 }
 
 impl InstallPayload {
@@ -34,6 +40,8 @@ impl InstallPayload {
             prompt: false,
             quiet: false,
             rehash: false,
+            offline: false,
+            frozen_lockfile: false,
         }
     }
 }
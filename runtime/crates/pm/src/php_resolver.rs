@@ -0,0 +1,462 @@
+//! Dependency graph resolution for php packages.
+//!
+//! Resolution is a backtracking search: each package tries the versions that
+//! satisfy every constraint placed on it by `deka.json` and by the packages
+//! selected so far, the locked version first and then newest to oldest. When
+//! a later package has no fitting version the search steps back and tries
+//! the next candidate of the most recent decision. If every combination
+//! fails, the first conflict found is reported with every requirer.
+
+use crate::semver::{Version, VersionReq};
+use anyhow::{Result, bail};
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+
+/// Requirer label for constraints coming from the project itself.
+pub const ROOT_REQUIRER: &str = "deka.json";
+
+/// Upper bound on candidate versions tried before giving up.
+const MAX_ATTEMPTS: usize = 10_000;
+
+pub(crate) trait PhpPackageSource {
+    /// Every published version of `name`.
+    async fn versions(&self, name: &str) -> Result<Vec<String>>;
+    /// Dependency ranges declared by `name@version`, keyed by package name.
+    async fn dependencies(&self, name: &str, version: &str) -> Result<BTreeMap<String, String>>;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedPhpPackage {
+    pub name: String,
+    pub version: Version,
+    /// Declared dependency name -> the version it resolved to.
+    pub dependencies: BTreeMap<String, String>,
+    /// `deka.json` and/or `name@version` of the packages that pulled this in.
+    pub requested_by: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PhpResolution {
+    pub packages: BTreeMap<String, ResolvedPhpPackage>,
+}
+
+struct Constraint {
+    requirer: String,
+    req: VersionReq,
+}
+
+#[derive(Default)]
+struct SourceCache {
+    versions: HashMap<String, Vec<Version>>,
+    dependencies: HashMap<(String, Version), BTreeMap<String, String>>,
+}
+
+impl SourceCache {
+    async fn versions<S: PhpPackageSource>(
+        &mut self,
+        source: &S,
+        name: &str,
+    ) -> Result<&[Version]> {
+        if !self.versions.contains_key(name) {
+            let mut versions = source
+                .versions(name)
+                .await?
+                .iter()
+                .filter_map(|raw| Version::parse(raw).ok())
+                .collect::<Vec<_>>();
+            versions.sort();
+            versions.dedup();
+            self.versions.insert(name.to_string(), versions);
+        }
+        Ok(&self.versions[name])
+    }
+
+    async fn dependencies<S: PhpPackageSource>(
+        &mut self,
+        source: &S,
+        name: &str,
+        version: &Version,
+    ) -> Result<&BTreeMap<String, String>> {
+        let key = (name.to_string(), version.clone());
+        if !self.dependencies.contains_key(&key) {
+            let deps = source.dependencies(name, &version.to_string()).await?;
+            self.dependencies.insert(key.clone(), deps);
+        }
+        Ok(&self.dependencies[&key])
+    }
+}
+
+/// Resolve `roots` (package -> range) into a fully pinned graph.
+pub(crate) async fn resolve_php_graph<S: PhpPackageSource>(
+    source: &S,
+    roots: &BTreeMap<String, VersionReq>,
+    locked: &BTreeMap<String, Version>,
+) -> Result<PhpResolution> {
+    let mut cache = SourceCache::default();
+    let mut selected: BTreeMap<String, Version> = BTreeMap::new();
+    let mut decisions: Vec<Decision> = Vec::new();
+    let mut first_conflict = None;
+
+    for _ in 0..MAX_ATTEMPTS {
+        let constraints = collect_constraints(source, &mut cache, roots, &selected).await?;
+        match next_step(source, &mut cache, &constraints, &selected, locked).await? {
+            Step::Done => {
+                return build_resolution(source, &mut cache, &constraints, selected).await;
+            }
+            Step::Decide(decision) => decisions.push(decision),
+            Step::Conflict(message) => {
+                first_conflict.get_or_insert(message);
+            }
+        }
+        // Move to the next untried candidate, undoing exhausted decisions.
+        loop {
+            let Some(decision) = decisions.last_mut() else {
+                bail!(first_conflict.unwrap_or_default());
+            };
+            selected.remove(&decision.name);
+            if let Some(version) = decision.candidates.pop_front() {
+                selected.insert(decision.name.clone(), version);
+                break;
+            }
+            decisions.pop();
+        }
+    }
+
+    bail!(
+        "php dependency resolution gave up after trying {} versions; pin conflicting packages in deka.json",
+        MAX_ATTEMPTS
+    )
+}
+
+/// A package whose version was chosen during the search, with the
+/// candidates still left to try if that choice leads to a conflict.
+struct Decision {
+    name: String,
+    candidates: VecDeque<Version>,
+}
+
+enum Step {
+    Done,
+    Decide(Decision),
+    Conflict(String),
+}
+
+/// Check the current selection against `constraints`, then pick the next
+/// package that still needs a version.
+async fn next_step<S: PhpPackageSource>(
+    source: &S,
+    cache: &mut SourceCache,
+    constraints: &BTreeMap<String, Vec<Constraint>>,
+    selected: &BTreeMap<String, Version>,
+    locked: &BTreeMap<String, Version>,
+) -> Result<Step> {
+    let fits = |reqs: &[Constraint], version: &Version| reqs.iter().all(|c| c.req.matches(version));
+    for (name, reqs) in constraints {
+        if let Some(version) = selected.get(name)
+            && !fits(reqs, version)
+        {
+            let versions = cache.versions(source, name).await?;
+            return Ok(Step::Conflict(conflict_message(name, reqs, versions)));
+        }
+    }
+    let Some((name, reqs)) = constraints
+        .iter()
+        .find(|(name, _)| !selected.contains_key(*name))
+    else {
+        return Ok(Step::Done);
+    };
+    let versions = cache.versions(source, name).await?;
+    let locked_fit = locked
+        .get(name)
+        .filter(|version| versions.contains(version) && fits(reqs, version));
+    let candidates = locked_fit
+        .into_iter()
+        .chain(
+            versions
+                .iter()
+                .rev()
+                .filter(|version| Some(*version) != locked_fit && fits(reqs, version)),
+        )
+        .cloned()
+        .collect::<VecDeque<_>>();
+    if candidates.is_empty() {
+        return Ok(Step::Conflict(conflict_message(name, reqs, versions)));
+    }
+    Ok(Step::Decide(Decision {
+        name: name.clone(),
+        candidates,
+    }))
+}
+
+/// Walk the graph from `roots` through the current selection and gather the
+/// constraints placed on every reachable package.
+async fn collect_constraints<S: PhpPackageSource>(
+    source: &S,
+    cache: &mut SourceCache,
+    roots: &BTreeMap<String, VersionReq>,
+    selected: &BTreeMap<String, Version>,
+) -> Result<BTreeMap<String, Vec<Constraint>>> {
+    let mut constraints: BTreeMap<String, Vec<Constraint>> = BTreeMap::new();
+    let mut queue = VecDeque::new();
+    let mut seen = BTreeSet::new();
+    for (name, req) in roots {
+        constraints
+            .entry(name.clone())
+            .or_default()
+            .push(Constraint {
+                requirer: ROOT_REQUIRER.to_string(),
+                req: req.clone(),
+            });
+        if seen.insert(name.clone()) {
+            queue.push_back(name.clone());
+        }
+    }
+
+    while let Some(name) = queue.pop_front() {
+        let Some(version) = selected.get(&name) else {
+            continue;
+        };
+        let requirer = format!("{}@{}", name, version);
+        let deps = cache.dependencies(source, &name, version).await?.clone();
+        for (dep, range) in deps {
+            let req = VersionReq::parse(&range).map_err(|err| {
+                anyhow::anyhow!(
+                    "{} declares an invalid range for {}: {}",
+                    requirer,
+                    dep,
+                    err
+                )
+            })?;
+            constraints
+                .entry(dep.clone())
+                .or_default()
+                .push(Constraint {
+                    requirer: requirer.clone(),
+                    req,
+                });
+            if seen.insert(dep.clone()) {
+                queue.push_back(dep);
+            }
+        }
+    }
+    Ok(constraints)
+}
+
+async fn build_resolution<S: PhpPackageSource>(
+    source: &S,
+    cache: &mut SourceCache,
+    constraints: &BTreeMap<String, Vec<Constraint>>,
+    selected: BTreeMap<String, Version>,
+) -> Result<PhpResolution> {
+    let mut packages = BTreeMap::new();
+    for (name, version) in &selected {
+        let deps = cache.dependencies(source, name, version).await?;
+        let dependencies = deps
+            .keys()
+            .filter_map(|dep| {
+                selected
+                    .get(dep)
+                    .map(|pinned| (dep.clone(), pinned.to_string()))
+            })
+            .collect();
+        let requested_by = constraints
+            .get(name)
+            .map(|reqs| reqs.iter().map(|c| c.requirer.clone()).collect())
+            .unwrap_or_default();
+        packages.insert(
+            name.clone(),
+            ResolvedPhpPackage {
+                name: name.clone(),
+                version: version.clone(),
+                dependencies,
+                requested_by,
+            },
+        );
+    }
+    Ok(PhpResolution { packages })
+}
+
+fn conflict_message(name: &str, reqs: &[Constraint], versions: &[Version]) -> String {
+    if versions.is_empty() {
+        return format!("no published versions found for {}", name);
+    }
+    let mut message = format!("no version of {} satisfies all constraints:", name);
+    for constraint in reqs {
+        message.push_str(&format!(
+            "\n  {} requires {}",
+            constraint.requirer, constraint.req
+        ));
+    }
+    let available = versions
+        .iter()
+        .rev()
+        .take(8)
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ");
+    message.push_str(&format!("\n  available: {}", available));
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PhpPackageSource, ROOT_REQUIRER, resolve_php_graph};
+    use crate::semver::{Version, VersionReq};
+    use anyhow::Result;
+    use std::collections::BTreeMap;
+
+    type Releases = BTreeMap<&'static str, Vec<(&'static str, &'static str)>>;
+
+    /// name -> version -> declared dependencies
+    struct FakeRegistry(BTreeMap<&'static str, Releases>);
+
+    impl PhpPackageSource for FakeRegistry {
+        async fn versions(&self, name: &str) -> Result<Vec<String>> {
+            Ok(self
+                .0
+                .get(name)
+                .map(|versions| versions.keys().map(|v| v.to_string()).collect())
+                .unwrap_or_default())
+        }
+
+        async fn dependencies(
+            &self,
+            name: &str,
+            version: &str,
+        ) -> Result<BTreeMap<String, String>> {
+            Ok(self.0[name][version]
+                .iter()
+                .map(|(dep, range)| (dep.to_string(), range.to_string()))
+                .collect())
+        }
+    }
+
+    fn registry() -> FakeRegistry {
+        let mut packages = BTreeMap::new();
+        packages.insert(
+            "@acme/http",
+            BTreeMap::from([
+                ("1.0.0", vec![("@acme/log", "^1.0")]),
+                (
+                    "1.2.0",
+                    vec![("@acme/log", "^1.1"), ("@acme/str", "~2.0.0")],
+                ),
+                ("2.0.0", vec![("@acme/log", "^2.0")]),
+            ]),
+        );
+        packages.insert(
+            "@acme/log",
+            BTreeMap::from([("1.0.0", vec![]), ("1.1.3", vec![]), ("2.0.0", vec![])]),
+        );
+        packages.insert(
+            "@acme/str",
+            BTreeMap::from([("2.0.1", vec![]), ("2.0.4", vec![]), ("2.1.0", vec![])]),
+        );
+        packages.insert(
+            "@acme/orm",
+            BTreeMap::from([("1.0.0", vec![("@acme/log", "^2.0")])]),
+        );
+        packages.insert(
+            "@acme/cli",
+            BTreeMap::from([
+                ("1.0.0", vec![("@acme/log", "^1.0")]),
+                ("1.1.0", vec![("@acme/log", "^2.0")]),
+            ]),
+        );
+        FakeRegistry(packages)
+    }
+
+    fn roots(entries: &[(&str, &str)]) -> BTreeMap<String, VersionReq> {
+        entries
+            .iter()
+            .map(|(name, range)| (name.to_string(), VersionReq::parse(range).unwrap()))
+            .collect()
+    }
+
+    fn resolve(
+        roots: &BTreeMap<String, VersionReq>,
+        locked: &BTreeMap<String, Version>,
+    ) -> Result<super::PhpResolution> {
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(resolve_php_graph(&registry(), roots, locked))
+    }
+
+    #[test]
+    fn resolves_transitive_dependencies() {
+        let resolution = resolve(&roots(&[("@acme/http", "^1.0")]), &BTreeMap::new()).unwrap();
+        let pinned = resolution
+            .packages
+            .values()
+            .map(|pkg| format!("{}@{}", pkg.name, pkg.version))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            pinned,
+            ["@acme/http@1.2.0", "@acme/log@1.1.3", "@acme/str@2.0.4"]
+        );
+        let http = &resolution.packages["@acme/http"];
+        assert_eq!(http.requested_by, [ROOT_REQUIRER]);
+        assert_eq!(http.dependencies["@acme/str"], "2.0.4");
+        assert_eq!(
+            resolution.packages["@acme/log"].requested_by,
+            ["@acme/http@1.2.0"]
+        );
+    }
+
+    #[test]
+    fn prefers_locked_versions_that_still_fit() {
+        let locked = BTreeMap::from([
+            ("@acme/http".to_string(), Version::parse("1.0.0").unwrap()),
+            ("@acme/log".to_string(), Version::parse("1.0.0").unwrap()),
+        ]);
+        let resolution = resolve(&roots(&[("@acme/http", "^1.0")]), &locked).unwrap();
+        assert_eq!(
+            resolution.packages["@acme/http"].version.to_string(),
+            "1.0.0"
+        );
+        assert_eq!(
+            resolution.packages["@acme/log"].version.to_string(),
+            "1.0.0"
+        );
+        assert!(!resolution.packages.contains_key("@acme/str"));
+    }
+
+    #[test]
+    fn backtracks_to_older_versions_on_conflict() {
+        let resolution = resolve(
+            &roots(&[("@acme/cli", "^1.0"), ("@acme/http", "^1.0")]),
+            &BTreeMap::new(),
+        )
+        .unwrap();
+        let pinned = resolution
+            .packages
+            .values()
+            .map(|pkg| format!("{}@{}", pkg.name, pkg.version))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            pinned,
+            [
+                "@acme/cli@1.0.0",
+                "@acme/http@1.2.0",
+                "@acme/log@1.1.3",
+                "@acme/str@2.0.4"
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_conflicts_with_every_requirer() {
+        let err = resolve(
+            &roots(&[("@acme/http", "^1.0"), ("@acme/orm", "^1.0")]),
+            &BTreeMap::new(),
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(err.contains("no version of @acme/log satisfies all constraints"));
+        assert!(err.contains("@acme/http@1.2.0 requires ^1.1"));
+        assert!(err.contains("@acme/orm@1.0.0 requires ^2.0"));
+
+        let missing = resolve(&roots(&[("@acme/none", "*")]), &BTreeMap::new()).unwrap_err();
+        assert!(missing.to_string().contains("no published versions"));
+    }
+}
@@ -0,0 +1,557 @@
+//! Semantic versions and range constraints.
+//!
+//! Ranges accept the npm grammar (`^1.2`, `~1.2.3`, `1.x`, `>=1 <2`,
+//! `1.0 - 2.3`, `a || b`) plus the composer spellings used by php packages
+//! (`,` between comparators and a single `|` between alternatives).
+
+use anyhow::{Result, bail};
+use std::cmp::Ordering;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Identifier {
+    Numeric(u64),
+    Alpha(String),
+}
+
+impl Ord for Identifier {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Identifier::Numeric(a), Identifier::Numeric(b)) => a.cmp(b),
+            (Identifier::Numeric(_), Identifier::Alpha(_)) => Ordering::Less,
+            (Identifier::Alpha(_), Identifier::Numeric(_)) => Ordering::Greater,
+            (Identifier::Alpha(a), Identifier::Alpha(b)) => a.cmp(b),
+        }
+    }
+}
+
+impl PartialOrd for Identifier {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for Identifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Identifier::Numeric(value) => write!(f, "{}", value),
+            Identifier::Alpha(value) => f.write_str(value),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Version {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+    pub pre: Vec<Identifier>,
+}
+
+impl Version {
+    pub fn new(major: u64, minor: u64, patch: u64) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+            pre: Vec::new(),
+        }
+    }
+
+    /// Parses `1.2.3`, `v1.2.3` and `1.2.3-beta.1+build`; build metadata is dropped.
+    pub fn parse(raw: &str) -> Result<Self> {
+        let trimmed = raw.trim();
+        let text = trimmed.trim_start_matches('=').trim_start_matches('v');
+        let text = text.split('+').next().unwrap_or("");
+        let (core, pre) = match text.split_once('-') {
+            Some((core, pre)) => (core, Some(pre)),
+            None => (text, None),
+        };
+        let mut parts = core.split('.');
+        let mut next = || -> Result<u64> {
+            let part = parts.next().unwrap_or("");
+            part.parse::<u64>()
+                .map_err(|_| anyhow::anyhow!("invalid version `{}`", trimmed))
+        };
+        let version = Version {
+            major: next()?,
+            minor: next()?,
+            patch: next()?,
+            pre: match pre {
+                Some(pre) => parse_prerelease(pre, trimmed)?,
+                None => Vec::new(),
+            },
+        };
+        if parts.next().is_some() {
+            bail!("invalid version `{}`", trimmed);
+        }
+        Ok(version)
+    }
+
+    pub fn is_prerelease(&self) -> bool {
+        !self.pre.is_empty()
+    }
+
+    fn same_release(&self, other: &Version) -> bool {
+        self.major == other.major && self.minor == other.minor && self.patch == other.patch
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.major
+            .cmp(&other.major)
+            .then(self.minor.cmp(&other.minor))
+            .then(self.patch.cmp(&other.patch))
+            .then_with(|| match (self.pre.is_empty(), other.pre.is_empty()) {
+                (true, true) => Ordering::Equal,
+                (true, false) => Ordering::Greater,
+                (false, true) => Ordering::Less,
+                (false, false) => self.pre.cmp(&other.pre),
+            })
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)?;
+        if !self.pre.is_empty() {
+            let pre = self
+                .pre
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(".");
+            write!(f, "-{}", pre)?;
+        }
+        Ok(())
+    }
+}
+
+fn parse_prerelease(raw: &str, full: &str) -> Result<Vec<Identifier>> {
+    raw.split('.')
+        .map(|part| {
+            if part.is_empty() {
+                bail!("invalid version `{}`", full);
+            }
+            Ok(match part.parse::<u64>() {
+                Ok(value) => Identifier::Numeric(value),
+                Err(_) => Identifier::Alpha(part.to_string()),
+            })
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Exact,
+    Greater,
+    GreaterEq,
+    Less,
+    LessEq,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Comparator {
+    op: Op,
+    version: Version,
+}
+
+impl Comparator {
+    fn matches(&self, version: &Version) -> bool {
+        match self.op {
+            Op::Exact => version == &self.version,
+            Op::Greater => version > &self.version,
+            Op::GreaterEq => version >= &self.version,
+            Op::Less => version < &self.version,
+            Op::LessEq => version <= &self.version,
+        }
+    }
+}
+
+/// A version with some trailing components left open (`1`, `1.2`, `1.x`, `*`).
+#[derive(Debug, Clone)]
+struct Partial {
+    major: Option<u64>,
+    minor: Option<u64>,
+    patch: Option<u64>,
+    pre: Vec<Identifier>,
+}
+
+impl Partial {
+    fn parse(raw: &str) -> Result<Self> {
+        let text = raw.trim().trim_start_matches('v');
+        let text = text.split('+').next().unwrap_or("");
+        let (core, pre) = match text.split_once('-') {
+            Some((core, pre)) => (core, parse_prerelease(pre, raw)?),
+            None => (text, Vec::new()),
+        };
+        let mut parts = core.split('.');
+        let mut next = || -> Result<Option<u64>> {
+            match parts.next() {
+                None | Some("x") | Some("X") | Some("*") => Ok(None),
+                Some(part) => part
+                    .parse::<u64>()
+                    .map(Some)
+                    .map_err(|_| anyhow::anyhow!("invalid version range `{}`", raw)),
+            }
+        };
+        let major = if core.is_empty() { None } else { next()? };
+        let minor = if major.is_some() { next()? } else { None };
+        let patch = if minor.is_some() { next()? } else { None };
+        if parts.next().is_some() {
+            bail!("invalid version range `{}`", raw);
+        }
+        Ok(Self {
+            major,
+            minor,
+            patch,
+            pre,
+        })
+    }
+
+    fn floor(&self) -> Version {
+        Version {
+            major: self.major.unwrap_or(0),
+            minor: self.minor.unwrap_or(0),
+            patch: self.patch.unwrap_or(0),
+            pre: self.pre.clone(),
+        }
+    }
+
+    /// First version past everything this partial covers, if it is open.
+    fn ceiling(&self) -> Option<Version> {
+        match (self.major, self.minor, self.patch) {
+            (None, _, _) => None,
+            (Some(major), None, _) => Some(Version::new(major + 1, 0, 0)),
+            (Some(major), Some(minor), None) => Some(Version::new(major, minor + 1, 0)),
+            (Some(_), Some(_), Some(_)) => None,
+        }
+    }
+
+    fn is_any(&self) -> bool {
+        self.major.is_none()
+    }
+}
+
+/// A parsed range: any of `sets` may match, and every comparator in a set must.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionReq {
+    raw: String,
+    sets: Vec<Vec<Comparator>>,
+}
+
+impl VersionReq {
+    pub fn parse(raw: &str) -> Result<Self> {
+        let trimmed = raw.trim();
+        let mut sets = Vec::new();
+        for alternative in split_alternatives(trimmed) {
+            sets.push(parse_set(alternative.trim(), trimmed)?);
+        }
+        Ok(Self {
+            raw: trimmed.to_string(),
+            sets,
+        })
+    }
+
+    /// Matches exactly one version.
+    pub fn exact(version: &Version) -> Self {
+        Self {
+            raw: version.to_string(),
+            sets: vec![vec![Comparator {
+                op: Op::Exact,
+                version: version.clone(),
+            }]],
+        }
+    }
+
+    pub fn any() -> Self {
+        Self {
+            raw: "*".to_string(),
+            sets: vec![Vec::new()],
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.raw
+    }
+
+    /// Prereleases only match when some comparator in the same set names a
+    /// prerelease of the same `major.minor.patch`, as npm does.
+    pub fn matches(&self, version: &Version) -> bool {
+        self.sets.iter().any(|set| {
+            if !set.iter().all(|cmp| cmp.matches(version)) {
+                return false;
+            }
+            !version.is_prerelease()
+                || set
+                    .iter()
+                    .any(|cmp| cmp.version.is_prerelease() && cmp.version.same_release(version))
+        })
+    }
+}
+
+impl fmt::Display for VersionReq {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.raw)
+    }
+}
+
+/// Highest version that satisfies `req`, ignoring entries that do not parse.
+pub fn max_satisfying<'a, I>(versions: I, req: &VersionReq) -> Option<Version>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    versions
+        .into_iter()
+        .filter_map(|raw| Version::parse(raw).ok())
+        .filter(|version| req.matches(version))
+        .max()
+}
+
+fn split_alternatives(raw: &str) -> Vec<&str> {
+    if raw.contains("||") {
+        raw.split("||").collect()
+    } else {
+        raw.split('|').collect()
+    }
+}
+
+fn parse_set(raw: &str, full: &str) -> Result<Vec<Comparator>> {
+    if let Some((low, high)) = raw.split_once(" - ") {
+        let low = Partial::parse(low)?;
+        let high = Partial::parse(high)?;
+        let mut set = vec![Comparator {
+            op: Op::GreaterEq,
+            version: low.floor(),
+        }];
+        if !high.is_any() {
+            set.push(match high.ceiling() {
+                Some(ceiling) => Comparator {
+                    op: Op::Less,
+                    version: ceiling,
+                },
+                None => Comparator {
+                    op: Op::LessEq,
+                    version: high.floor(),
+                },
+            });
+        }
+        return Ok(set);
+    }
+
+    let mut set = Vec::new();
+    let mut pending_op: Option<&str> = None;
+    for token in raw
+        .split(|ch: char| ch == ',' || ch.is_whitespace())
+        .filter(|token| !token.is_empty())
+    {
+        let token = match pending_op.take() {
+            Some(op) => format!("{}{}", op, token),
+            None => token.to_string(),
+        };
+        if matches!(
+            token.as_str(),
+            ">" | ">=" | "<" | "<=" | "=" | "^" | "~" | "~>"
+        ) {
+            pending_op = Some(match token.as_str() {
+                ">" => ">",
+                ">=" => ">=",
+                "<" => "<",
+                "<=" => "<=",
+                "=" => "=",
+                "^" => "^",
+                _ => "~",
+            });
+            continue;
+        }
+        desugar(&token, full, &mut set)?;
+    }
+    if pending_op.is_some() {
+        bail!("invalid version range `{}`", full);
+    }
+    Ok(set)
+}
+
+fn desugar(token: &str, full: &str, set: &mut Vec<Comparator>) -> Result<()> {
+    let mut push = |op: Op, version: Version| set.push(Comparator { op, version });
+    let (op, rest) = split_operator(token);
+    if rest.is_empty() && !op.is_empty() {
+        bail!("invalid version range `{}`", full);
+    }
+    let partial = Partial::parse(rest)?;
+
+    match op {
+        "^" => {
+            if partial.is_any() {
+                return Ok(());
+            }
+            push(Op::GreaterEq, partial.floor());
+            let upper = match (partial.major, partial.minor, partial.patch) {
+                (Some(major), _, _) if major > 0 => Version::new(major + 1, 0, 0),
+                (Some(0), None, _) => Version::new(1, 0, 0),
+                (Some(0), Some(minor), _) if minor > 0 => Version::new(0, minor + 1, 0),
+                (Some(0), Some(0), None) => Version::new(0, 1, 0),
+                (_, _, patch) => Version::new(0, 0, patch.unwrap_or(0) + 1),
+            };
+            push(Op::Less, upper);
+        }
+        "~" | "~>" => {
+            if partial.is_any() {
+                return Ok(());
+            }
+            push(Op::GreaterEq, partial.floor());
+            let upper = match (partial.major, partial.minor) {
+                (Some(major), None) => Version::new(major + 1, 0, 0),
+                (Some(major), Some(minor)) => Version::new(major, minor + 1, 0),
+                _ => unreachable!("partial has a major version"),
+            };
+            push(Op::Less, upper);
+        }
+        ">" => match partial.ceiling() {
+            Some(ceiling) => push(Op::GreaterEq, ceiling),
+            None if partial.is_any() => push(Op::Less, Version::new(0, 0, 0)),
+            None => push(Op::Greater, partial.floor()),
+        },
+        ">=" => {
+            if !partial.is_any() {
+                push(Op::GreaterEq, partial.floor());
+            }
+        }
+        "<" => push(
+            Op::Less,
+            if partial.is_any() {
+                Version::new(0, 0, 0)
+            } else {
+                partial.floor()
+            },
+        ),
+        "<=" => match partial.ceiling() {
+            Some(ceiling) => push(Op::Less, ceiling),
+            None if partial.is_any() => {}
+            None => push(Op::LessEq, partial.floor()),
+        },
+        _ => {
+            if partial.is_any() {
+                return Ok(());
+            }
+            match partial.ceiling() {
+                Some(ceiling) => {
+                    push(Op::GreaterEq, partial.floor());
+                    push(Op::Less, ceiling);
+                }
+                None => push(Op::Exact, partial.floor()),
+            }
+        }
+    }
+    Ok(())
+}
+
+fn split_operator(token: &str) -> (&str, &str) {
+    for op in [">=", "<=", "~>", ">", "<", "=", "^", "~"] {
+        if let Some(rest) = token.strip_prefix(op) {
+            return (op, rest);
+        }
+    }
+    ("", token)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Version, VersionReq, max_satisfying};
+
+    fn matches(req: &str, version: &str) -> bool {
+        VersionReq::parse(req)
+            .unwrap()
+            .matches(&Version::parse(version).unwrap())
+    }
+
+    #[test]
+    fn orders_versions_with_prereleases() {
+        let mut versions = [
+            "1.0.0",
+            "1.0.0-beta.2",
+            "1.0.0-alpha",
+            "0.9.9",
+            "1.0.0-beta.10",
+        ]
+        .iter()
+        .map(|raw| Version::parse(raw).unwrap())
+        .collect::<Vec<_>>();
+        versions.sort();
+        let sorted = versions.iter().map(ToString::to_string).collect::<Vec<_>>();
+        assert_eq!(
+            sorted,
+            [
+                "0.9.9",
+                "1.0.0-alpha",
+                "1.0.0-beta.2",
+                "1.0.0-beta.10",
+                "1.0.0"
+            ]
+        );
+        assert_eq!(
+            Version::parse("v2.1.0+build.5").unwrap().to_string(),
+            "2.1.0"
+        );
+        assert!(Version::parse("1.2").is_err());
+    }
+
+    #[test]
+    fn matches_caret_tilde_and_x_ranges() {
+        assert!(matches("^1.2.3", "1.9.0"));
+        assert!(!matches("^1.2.3", "2.0.0"));
+        assert!(!matches("^1.2.3", "1.2.2"));
+        assert!(matches("^0.2.3", "0.2.9"));
+        assert!(!matches("^0.2.3", "0.3.0"));
+        assert!(!matches("^0.0.3", "0.0.4"));
+        assert!(matches("~1.2.3", "1.2.9"));
+        assert!(!matches("~1.2.3", "1.3.0"));
+        assert!(matches("~1", "1.9.9"));
+        assert!(matches("1.x", "1.4.0"));
+        assert!(!matches("1.2", "1.3.0"));
+        assert!(matches("*", "3.0.0"));
+        assert!(matches("", "0.0.1"));
+        assert!(matches("1.2.3", "1.2.3"));
+        assert!(!matches("1.2.3", "1.2.4"));
+    }
+
+    #[test]
+    fn matches_comparator_sets_and_alternatives() {
+        assert!(matches(">=1.0 <2.0", "1.5.0"));
+        assert!(!matches(">=1.0 <2.0", "2.0.0"));
+        assert!(matches(">= 1.0, < 2.0", "1.0.0"));
+        assert!(matches("^1.0 || ^3.0", "3.1.0"));
+        assert!(matches("^1.0 | ^3.0", "1.1.0"));
+        assert!(!matches("^1.0 || ^3.0", "2.0.0"));
+        assert!(matches("1.0 - 2.3", "2.3.9"));
+        assert!(!matches("1.0 - 2.3.0", "2.3.1"));
+        assert!(matches(">1.2", "1.3.0"));
+        assert!(!matches(">1.2", "1.2.5"));
+        assert!(matches("<=1.2", "1.2.9"));
+        assert!(VersionReq::parse(">=").is_err());
+        assert!(VersionReq::parse("^1.a").is_err());
+    }
+
+    #[test]
+    fn prereleases_need_an_explicit_opt_in() {
+        assert!(!matches("^1.0.0", "1.1.0-beta.1"));
+        assert!(matches("^1.1.0-beta.0", "1.1.0-beta.1"));
+        assert!(!matches("^1.1.0-beta.0", "1.2.0-beta.1"));
+        assert!(matches("^1.1.0-beta.0", "1.2.0"));
+    }
+
+    #[test]
+    fn picks_highest_satisfying_version() {
+        let req = VersionReq::parse("^1.2").unwrap();
+        let best = max_satisfying(["1.1.0", "1.4.2", "1.10.0", "2.0.0", "nope"], &req);
+        assert_eq!(best.unwrap().to_string(), "1.10.0");
+        assert!(max_satisfying(["2.0.0"], &req).is_none());
+    }
+}
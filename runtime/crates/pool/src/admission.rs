@@ -0,0 +1,437 @@
+//! Admission control for pooled requests.
+//!
+//! Each handler gets a number of concurrency slots and a bounded queue of
+//! requests waiting for one. When both are full the request is shed right
+//! away with a 503 and `Retry-After` instead of sitting in a worker queue
+//! until `queue_timeout_ms` fails it. Critical requests (health checks,
+//! admin routes) bypass admission entirely.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::Deserialize;
+use tokio::sync::oneshot;
+
+use crate::isolate_pool::RequestParts;
+
+/// Per-handler overrides for the admission limits.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub struct AdmissionLimits {
+    #[serde(default)]
+    pub max_queue: Option<usize>,
+    #[serde(default)]
+    pub max_concurrency: Option<usize>,
+}
+
+/// Admission configuration (`serve.admission` in deka.json)
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct AdmissionConfig {
+    /// Requests allowed to wait for a slot per handler (0 = unbounded).
+    /// Without a concurrency limit this caps in-flight requests instead.
+    pub max_queue: usize,
+    /// Requests executing concurrently per handler (0 = unlimited)
+    pub max_concurrency: usize,
+    /// Value of the `Retry-After` header on shed requests
+    pub retry_after_secs: u64,
+    /// Path prefixes treated as critical and never shed
+    pub priority_paths: Vec<String>,
+    /// Per-handler overrides keyed by handler name
+    pub handlers: HashMap<String, AdmissionLimits>,
+}
+
+impl Default for AdmissionConfig {
+    fn default() -> Self {
+        Self {
+            max_queue: 0,
+            max_concurrency: 0,
+            retry_after_secs: 1,
+            priority_paths: ["/health", "/healthz", "/readyz", "/_deka/"]
+                .into_iter()
+                .map(String::from)
+                .collect(),
+            handlers: HashMap::new(),
+        }
+    }
+}
+
+impl AdmissionConfig {
+    /// Environment variables:
+    /// - ISOLATE_MAX_QUEUE: Waiting requests per handler (0 = unbounded)
+    /// - ISOLATE_MAX_CONCURRENCY: Concurrent requests per handler (0 = unlimited)
+    /// - ISOLATE_RETRY_AFTER_SECS: Retry-After value for shed requests
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let parse = |name: &str| std::env::var(name).ok().and_then(|s| s.parse().ok());
+        Self {
+            max_queue: parse("ISOLATE_MAX_QUEUE")
+                .map(|v: u64| v as usize)
+                .unwrap_or(defaults.max_queue),
+            max_concurrency: parse("ISOLATE_MAX_CONCURRENCY")
+                .map(|v: u64| v as usize)
+                .unwrap_or(defaults.max_concurrency),
+            retry_after_secs: parse("ISOLATE_RETRY_AFTER_SECS")
+                .unwrap_or(defaults.retry_after_secs),
+            ..defaults
+        }
+    }
+
+    /// Resolve `(max_queue, max_concurrency)` for a handler.
+    pub fn limits(&self, handler_name: &str) -> (usize, usize) {
+        let handler = self.handlers.get(handler_name).copied().unwrap_or_default();
+        (
+            handler.max_queue.unwrap_or(self.max_queue),
+            handler.max_concurrency.unwrap_or(self.max_concurrency),
+        )
+    }
+}
+
+/// Priority class of an incoming request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestPriority {
+    /// Health checks and admin routes: bypass admission
+    Critical,
+    Normal,
+}
+
+impl RequestPriority {
+    pub fn classify(config: &AdmissionConfig, request_parts: Option<&RequestParts>) -> Self {
+        let Some(parts) = request_parts else {
+            return Self::Normal;
+        };
+        let path = request_path(&parts.url);
+        let critical = config.priority_paths.iter().any(|prefix| {
+            path == prefix.trim_end_matches('/')
+                || (prefix.ends_with('/') && path.starts_with(prefix.as_str()))
+                || path
+                    .strip_prefix(prefix.as_str())
+                    .is_some_and(|rest| rest.starts_with('/'))
+        });
+        if critical {
+            Self::Critical
+        } else {
+            Self::Normal
+        }
+    }
+}
+
+fn request_path(url: &str) -> &str {
+    let without_origin = match url.find("://") {
+        Some(index) => {
+            let rest = &url[index + 3..];
+            rest.find('/').map(|slash| &rest[slash..]).unwrap_or("/")
+        }
+        None => url,
+    };
+    without_origin
+        .split(['?', '#'])
+        .next()
+        .unwrap_or(without_origin)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RejectReason {
+    /// The handler's wait queue was full
+    QueueFull,
+    /// The request waited for a slot longer than the queue timeout
+    QueueTimeout,
+}
+
+/// A request shed by admission control
+#[derive(Debug, Clone)]
+pub struct Rejection {
+    pub reason: RejectReason,
+    pub retry_after_secs: u64,
+}
+
+impl Rejection {
+    /// The 503 response envelope returned to the client in place of the handler result.
+    pub fn response_value(&self) -> serde_json::Value {
+        let message = match self.reason {
+            RejectReason::QueueFull => "Service Unavailable: handler queue is full",
+            RejectReason::QueueTimeout => "Service Unavailable: timed out waiting for handler",
+        };
+        serde_json::json!({
+            "status": 503,
+            "headers": {
+                "content-type": "text/plain; charset=utf-8",
+                "retry-after": self.retry_after_secs.to_string(),
+            },
+            "body": message,
+        })
+    }
+}
+
+#[derive(Default)]
+struct HandlerSlots {
+    running: usize,
+    waiters: VecDeque<(u64, oneshot::Sender<()>)>,
+    next_waiter: u64,
+}
+
+type SlotTable = Arc<Mutex<HashMap<String, HandlerSlots>>>;
+
+/// Held for the duration of an admitted request; frees or hands over its slot on drop.
+pub(crate) struct AdmissionPermit {
+    slot: Option<(SlotTable, String)>,
+    pub(crate) waited: bool,
+}
+
+impl Drop for AdmissionPermit {
+    fn drop(&mut self) {
+        if let Some((table, handler_name)) = self.slot.take() {
+            release_slot(&table, &handler_name);
+        }
+    }
+}
+
+/// Pending wait for a slot. If the request is cancelled after a slot was
+/// handed over but before it was claimed, the slot is passed on here.
+struct PendingSlot {
+    table: SlotTable,
+    handler_name: String,
+    id: u64,
+    rx: oneshot::Receiver<()>,
+    claimed: bool,
+}
+
+impl Drop for PendingSlot {
+    fn drop(&mut self) {
+        if self.claimed {
+            return;
+        }
+        {
+            let mut table = self.table.lock().unwrap_or_else(|e| e.into_inner());
+            if let Some(slots) = table.get_mut(&self.handler_name) {
+                slots.waiters.retain(|(id, _)| *id != self.id);
+            }
+        }
+        if self.rx.try_recv().is_ok() {
+            release_slot(&self.table, &self.handler_name);
+        }
+    }
+}
+
+fn release_slot(table: &SlotTable, handler_name: &str) {
+    let mut table = table.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(slots) = table.get_mut(handler_name) else {
+        return;
+    };
+    while let Some((_, waiter)) = slots.waiters.pop_front() {
+        if waiter.send(()).is_ok() {
+            return;
+        }
+    }
+    slots.running = slots.running.saturating_sub(1);
+    if slots.running == 0 {
+        table.remove(handler_name);
+    }
+}
+
+pub(crate) struct AdmissionController {
+    config: AdmissionConfig,
+    slots: SlotTable,
+}
+
+impl AdmissionController {
+    pub(crate) fn new(config: AdmissionConfig) -> Self {
+        Self {
+            config,
+            slots: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub(crate) fn classify(&self, request_parts: Option<&RequestParts>) -> RequestPriority {
+        RequestPriority::classify(&self.config, request_parts)
+    }
+
+    /// Wait for a slot for `handler_name`, or shed the request.
+    /// A zero `queue_timeout` waits indefinitely.
+    pub(crate) async fn admit(
+        &self,
+        handler_name: &str,
+        queue_timeout: Duration,
+    ) -> Result<AdmissionPermit, Rejection> {
+        let (max_queue, max_concurrency) = self.config.limits(handler_name);
+        if max_queue == 0 && max_concurrency == 0 {
+            return Ok(AdmissionPermit {
+                slot: None,
+                waited: false,
+            });
+        }
+
+        let pending = {
+            let mut table = self.slots.lock().unwrap_or_else(|e| e.into_inner());
+            let slots = table.entry(handler_name.to_string()).or_default();
+            let slot_limit = if max_concurrency == 0 {
+                max_queue
+            } else {
+                max_concurrency
+            };
+            if slots.running < slot_limit {
+                slots.running += 1;
+                None
+            } else if max_concurrency == 0 || (max_queue > 0 && slots.waiters.len() >= max_queue) {
+                return Err(self.reject(RejectReason::QueueFull));
+            } else {
+                let (tx, rx) = oneshot::channel();
+                let id = slots.next_waiter;
+                slots.next_waiter += 1;
+                slots.waiters.push_back((id, tx));
+                Some(PendingSlot {
+                    table: Arc::clone(&self.slots),
+                    handler_name: handler_name.to_string(),
+                    id,
+                    rx,
+                    claimed: false,
+                })
+            }
+        };
+
+        let Some(mut pending) = pending else {
+            return Ok(self.permit(handler_name, false));
+        };
+
+        let granted = if queue_timeout.is_zero() {
+            (&mut pending.rx).await.is_ok()
+        } else {
+            matches!(
+                tokio::time::timeout(queue_timeout, &mut pending.rx).await,
+                Ok(Ok(()))
+            )
+        };
+        if granted {
+            pending.claimed = true;
+            return Ok(self.permit(handler_name, true));
+        }
+        // Dropping `pending` passes on a slot that raced with the timeout.
+        drop(pending);
+        Err(self.reject(RejectReason::QueueTimeout))
+    }
+
+    fn permit(&self, handler_name: &str, waited: bool) -> AdmissionPermit {
+        AdmissionPermit {
+            slot: Some((Arc::clone(&self.slots), handler_name.to_string())),
+            waited,
+        }
+    }
+
+    fn reject(&self, reason: RejectReason) -> Rejection {
+        Rejection {
+            reason,
+            retry_after_secs: self.config.retry_after_secs,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parts(url: &str) -> RequestParts {
+        RequestParts {
+            url: url.to_string(),
+            method: "GET".to_string(),
+            headers: Vec::new(),
+            body: None,
+        }
+    }
+
+    #[test]
+    fn classifies_health_and_admin_paths_as_critical() {
+        let config = AdmissionConfig::default();
+        let classify = |url: &str| RequestPriority::classify(&config, Some(&parts(url)));
+        assert_eq!(
+            classify("http://localhost/health"),
+            RequestPriority::Critical
+        );
+        assert_eq!(
+            classify("http://localhost/healthz?probe=1"),
+            RequestPriority::Critical
+        );
+        assert_eq!(
+            classify("http://localhost/_deka/stats"),
+            RequestPriority::Critical
+        );
+        assert_eq!(
+            classify("http://localhost/healthcare"),
+            RequestPriority::Normal
+        );
+        assert_eq!(classify("http://localhost/"), RequestPriority::Normal);
+        assert_eq!(
+            RequestPriority::classify(&config, None),
+            RequestPriority::Normal
+        );
+    }
+
+    #[test]
+    fn handler_overrides_take_precedence() {
+        let mut config = AdmissionConfig {
+            max_queue: 10,
+            max_concurrency: 4,
+            ..AdmissionConfig::default()
+        };
+        config.handlers.insert(
+            "api.phpx".to_string(),
+            AdmissionLimits {
+                max_queue: None,
+                max_concurrency: Some(1),
+            },
+        );
+        assert_eq!(config.limits("api.phpx"), (10, 1));
+        assert_eq!(config.limits("main.phpx"), (10, 4));
+    }
+
+    #[tokio::test]
+    async fn sheds_when_slots_and_queue_are_full() {
+        let controller = AdmissionController::new(AdmissionConfig {
+            max_queue: 1,
+            max_concurrency: 1,
+            retry_after_secs: 3,
+            ..AdmissionConfig::default()
+        });
+
+        let running = controller.admit("api", Duration::ZERO).await.expect("slot");
+        let queued = {
+            let controller = &controller;
+            async move { controller.admit("api", Duration::from_secs(5)).await }
+        };
+        tokio::pin!(queued);
+        assert!(futures_poll_pending(&mut queued).await);
+
+        let rejection = match controller.admit("api", Duration::ZERO).await {
+            Err(rejection) => rejection,
+            Ok(_) => panic!("expected queue full"),
+        };
+        assert_eq!(rejection.reason, RejectReason::QueueFull);
+        assert_eq!(rejection.response_value()["headers"]["retry-after"], "3");
+
+        drop(running);
+        let permit = queued.await.expect("slot handed over");
+        assert!(permit.waited);
+    }
+
+    #[tokio::test]
+    async fn times_out_waiting_for_a_slot() {
+        let controller = AdmissionController::new(AdmissionConfig {
+            max_concurrency: 1,
+            ..AdmissionConfig::default()
+        });
+        let _running = controller.admit("api", Duration::ZERO).await.expect("slot");
+        let rejection = match controller.admit("api", Duration::from_millis(10)).await {
+            Err(rejection) => rejection,
+            Ok(_) => panic!("expected timeout"),
+        };
+        assert_eq!(rejection.reason, RejectReason::QueueTimeout);
+        assert!(controller.slots.lock().unwrap()["api"].waiters.is_empty());
+    }
+
+    async fn futures_poll_pending<F: std::future::Future + Unpin>(future: &mut F) -> bool {
+        tokio::select! {
+            biased;
+            _ = future => false,
+            _ = tokio::task::yield_now() => true,
+        }
+    }
+}
@@ -0,0 +1,435 @@
+//! Adaptive per-handler isolate scaling.
+//!
+//! A worker holds at most one warm isolate per handler, so the number of
+//! isolates serving a handler is the number of workers in its replica set.
+//! The autoscaler grows that set when queue depth per replica or observed
+//! latency exceeds its targets, shrinks it after the handler has been idle,
+//! and routes each request to the less loaded of two replicas.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Minimum time between two scaling decisions for the same handler.
+const SCALE_COOLDOWN: Duration = Duration::from_secs(1);
+/// Smoothing factor for the per-handler latency moving average.
+const LATENCY_EWMA_ALPHA: f64 = 0.2;
+const DECISION_HISTORY_LIMIT: usize = 20;
+
+/// Autoscaler configuration
+#[derive(Debug, Clone)]
+pub struct AutoscaleConfig {
+    /// Enable adaptive scaling (default: false)
+    pub enabled: bool,
+    /// Replicas kept for a handler once it has been seen (default: 1)
+    pub min_replicas: usize,
+    /// Replica ceiling per handler (0 = number of workers)
+    pub max_replicas: usize,
+    /// In-flight requests per replica before scaling up (default: 2)
+    pub target_queue_depth: usize,
+    /// Latency moving average that triggers a scale up when requests are queued (0 = disabled)
+    pub target_latency_ms: u64,
+    /// Idle time before a replica is released (default: 30s)
+    pub scale_down_idle_secs: u64,
+    /// Pre-created isolates each worker keeps ready for new replicas (default: 0)
+    pub warm_spares: usize,
+}
+
+impl Default for AutoscaleConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            min_replicas: 1,
+            max_replicas: 0,
+            target_queue_depth: 2,
+            target_latency_ms: 0,
+            scale_down_idle_secs: 30,
+            warm_spares: 0,
+        }
+    }
+}
+
+impl AutoscaleConfig {
+    /// Environment variables:
+    /// - ISOLATE_AUTOSCALE: Enable adaptive scaling (default: false)
+    /// - ISOLATE_AUTOSCALE_MIN / ISOLATE_AUTOSCALE_MAX: Replica bounds per handler
+    /// - ISOLATE_AUTOSCALE_QUEUE_TARGET: In-flight requests per replica
+    /// - ISOLATE_AUTOSCALE_LATENCY_MS: Latency target in ms (0 = disabled)
+    /// - ISOLATE_AUTOSCALE_IDLE_SECS: Idle seconds before scaling down
+    /// - ISOLATE_WARM_SPARES: Spare isolates per worker
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let parse = |name: &str| std::env::var(name).ok().and_then(|s| s.parse().ok());
+        Self {
+            enabled: std::env::var("ISOLATE_AUTOSCALE")
+                .map(|v| v != "false" && v != "0")
+                .unwrap_or(defaults.enabled),
+            min_replicas: parse("ISOLATE_AUTOSCALE_MIN")
+                .map(|v: u64| v as usize)
+                .unwrap_or(defaults.min_replicas),
+            max_replicas: parse("ISOLATE_AUTOSCALE_MAX")
+                .map(|v: u64| v as usize)
+                .unwrap_or(defaults.max_replicas),
+            target_queue_depth: parse("ISOLATE_AUTOSCALE_QUEUE_TARGET")
+                .map(|v: u64| v as usize)
+                .unwrap_or(defaults.target_queue_depth),
+            target_latency_ms: parse("ISOLATE_AUTOSCALE_LATENCY_MS")
+                .unwrap_or(defaults.target_latency_ms),
+            scale_down_idle_secs: parse("ISOLATE_AUTOSCALE_IDLE_SECS")
+                .unwrap_or(defaults.scale_down_idle_secs),
+            warm_spares: parse("ISOLATE_WARM_SPARES")
+                .map(|v: u64| v as usize)
+                .unwrap_or(defaults.warm_spares),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScaleAction {
+    Up,
+    Down,
+}
+
+/// A single scaling decision, reported through `WorkerStats`.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ScaleDecision {
+    pub handler_name: String,
+    pub worker_id: usize,
+    pub action: ScaleAction,
+    /// Replica count after the decision
+    pub replicas: usize,
+    pub reason: String,
+    pub at_ms: u64,
+}
+
+struct HandlerScale {
+    replicas: Vec<usize>,
+    in_flight: usize,
+    latency_ewma_ms: f64,
+    last_active: Instant,
+    last_change: Instant,
+}
+
+#[derive(Default)]
+struct AutoscalerState {
+    handlers: HashMap<String, HandlerScale>,
+    decisions: HashMap<usize, VecDeque<ScaleDecision>>,
+    scale_ups: HashMap<usize, u64>,
+    scale_downs: HashMap<usize, u64>,
+}
+
+/// Per-worker scaling counters for `WorkerStats`.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct WorkerScaleStats {
+    pub(crate) scale_ups: u64,
+    pub(crate) scale_downs: u64,
+    pub(crate) recent_decisions: Vec<ScaleDecision>,
+}
+
+pub(crate) struct Autoscaler {
+    config: AutoscaleConfig,
+    num_workers: usize,
+    state: Mutex<AutoscalerState>,
+}
+
+impl Autoscaler {
+    pub(crate) fn new(config: AutoscaleConfig, num_workers: usize) -> Self {
+        Self {
+            config,
+            num_workers: num_workers.max(1),
+            state: Mutex::new(AutoscalerState::default()),
+        }
+    }
+
+    fn max_replicas(&self) -> usize {
+        if self.config.max_replicas == 0 {
+            self.num_workers
+        } else {
+            self.config.max_replicas.min(self.num_workers)
+        }
+    }
+
+    /// Pick a worker for `handler_name` and register the request as in flight.
+    ///
+    /// `loads` holds queued + active requests per worker. Returns the chosen
+    /// worker and any scaling decisions made, including scale-downs of other
+    /// idle handlers whose isolates should now be released.
+    pub(crate) fn route(
+        &self,
+        handler_name: &str,
+        loads: &[usize],
+        exclude: Option<usize>,
+        seed: u64,
+    ) -> (usize, Vec<ScaleDecision>) {
+        let now = Instant::now();
+        let min_replicas = self.config.min_replicas.clamp(1, self.max_replicas());
+        let max_replicas = self.max_replicas();
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let mut decisions = self.scale_down_idle(&mut state, now, min_replicas);
+
+        let handler = state
+            .handlers
+            .entry(handler_name.to_string())
+            .or_insert_with(|| HandlerScale {
+                replicas: Vec::new(),
+                in_flight: 0,
+                latency_ewma_ms: 0.0,
+                last_active: now,
+                last_change: now.checked_sub(SCALE_COOLDOWN).unwrap_or(now),
+            });
+
+        while handler.replicas.len() < min_replicas {
+            let Some(worker) = least_loaded_outside(loads, &handler.replicas, exclude) else {
+                break;
+            };
+            handler.replicas.push(worker);
+        }
+
+        let queued_per_replica = handler.in_flight / handler.replicas.len().max(1);
+        let over_queue = queued_per_replica >= self.config.target_queue_depth.max(1);
+        let over_latency = self.config.target_latency_ms > 0
+            && handler.in_flight >= handler.replicas.len()
+            && handler.latency_ewma_ms > self.config.target_latency_ms as f64;
+        if (over_queue || over_latency)
+            && handler.replicas.len() < max_replicas
+            && now.duration_since(handler.last_change) >= SCALE_COOLDOWN
+            && let Some(worker) = least_loaded_outside(loads, &handler.replicas, exclude)
+        {
+            handler.replicas.push(worker);
+            handler.last_change = now;
+            let reason = if over_queue {
+                format!(
+                    "{} in flight across {} replicas (target {})",
+                    handler.in_flight,
+                    handler.replicas.len() - 1,
+                    self.config.target_queue_depth
+                )
+            } else {
+                format!(
+                    "latency {:.1}ms above target {}ms",
+                    handler.latency_ewma_ms, self.config.target_latency_ms
+                )
+            };
+            decisions.push(ScaleDecision {
+                handler_name: handler_name.to_string(),
+                worker_id: worker,
+                action: ScaleAction::Up,
+                replicas: handler.replicas.len(),
+                reason,
+                at_ms: now_millis(),
+            });
+        }
+
+        let candidates: Vec<usize> = handler
+            .replicas
+            .iter()
+            .copied()
+            .filter(|worker| Some(*worker) != exclude)
+            .collect();
+        let chosen = if candidates.is_empty() {
+            least_loaded_outside(loads, &[], exclude).unwrap_or(0)
+        } else {
+            power_of_two_choices(&candidates, loads, seed)
+        };
+
+        handler.in_flight += 1;
+        handler.last_active = now;
+
+        for decision in &decisions {
+            record_decision(&mut state, decision.clone());
+        }
+        (chosen, decisions)
+    }
+
+    /// Mark a request as finished and fold its latency into the moving average.
+    pub(crate) fn complete(&self, handler_name: &str, latency: Duration) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(handler) = state.handlers.get_mut(handler_name) {
+            handler.in_flight = handler.in_flight.saturating_sub(1);
+            handler.last_active = Instant::now();
+            let latency_ms = latency.as_secs_f64() * 1000.0;
+            handler.latency_ewma_ms = if handler.latency_ewma_ms == 0.0 {
+                latency_ms
+            } else {
+                LATENCY_EWMA_ALPHA * latency_ms
+                    + (1.0 - LATENCY_EWMA_ALPHA) * handler.latency_ewma_ms
+            };
+        }
+    }
+
+    pub(crate) fn worker_stats(&self, worker_id: usize) -> WorkerScaleStats {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        WorkerScaleStats {
+            scale_ups: state.scale_ups.get(&worker_id).copied().unwrap_or(0),
+            scale_downs: state.scale_downs.get(&worker_id).copied().unwrap_or(0),
+            recent_decisions: state
+                .decisions
+                .get(&worker_id)
+                .map(|decisions| decisions.iter().cloned().collect())
+                .unwrap_or_default(),
+        }
+    }
+
+    fn scale_down_idle(
+        &self,
+        state: &mut AutoscalerState,
+        now: Instant,
+        min_replicas: usize,
+    ) -> Vec<ScaleDecision> {
+        let idle_after = Duration::from_secs(self.config.scale_down_idle_secs);
+        let mut decisions = Vec::new();
+        for (name, handler) in state.handlers.iter_mut() {
+            if handler.in_flight > 0
+                || handler.replicas.len() <= min_replicas
+                || now.duration_since(handler.last_active) < idle_after
+                || now.duration_since(handler.last_change) < idle_after
+            {
+                continue;
+            }
+            let Some(worker) = handler.replicas.pop() else {
+                continue;
+            };
+            handler.last_change = now;
+            decisions.push(ScaleDecision {
+                handler_name: name.clone(),
+                worker_id: worker,
+                action: ScaleAction::Down,
+                replicas: handler.replicas.len(),
+                reason: format!("idle for {}s", idle_after.as_secs()),
+                at_ms: now_millis(),
+            });
+        }
+        decisions
+    }
+}
+
+fn record_decision(state: &mut AutoscalerState, decision: ScaleDecision) {
+    let counter = match decision.action {
+        ScaleAction::Up => &mut state.scale_ups,
+        ScaleAction::Down => &mut state.scale_downs,
+    };
+    *counter.entry(decision.worker_id).or_insert(0) += 1;
+
+    let history = state.decisions.entry(decision.worker_id).or_default();
+    history.push_back(decision);
+    if history.len() > DECISION_HISTORY_LIMIT {
+        history.pop_front();
+    }
+}
+
+fn least_loaded_outside(loads: &[usize], taken: &[usize], exclude: Option<usize>) -> Option<usize> {
+    loads
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| !taken.contains(index) && Some(*index) != exclude)
+        .min_by_key(|(_, load)| **load)
+        .map(|(index, _)| index)
+}
+
+/// Sample two distinct candidates and keep the one with the lower load.
+pub(crate) fn power_of_two_choices(candidates: &[usize], loads: &[usize], seed: u64) -> usize {
+    match candidates.len() {
+        0 => 0,
+        1 => candidates[0],
+        len => {
+            let hash = splitmix64(seed);
+            let first = (hash % len as u64) as usize;
+            let offset = 1 + ((hash >> 32) % (len as u64 - 1)) as usize;
+            let second = (first + offset) % len;
+            let (a, b) = (candidates[first], candidates[second]);
+            let load = |worker: usize| loads.get(worker).copied().unwrap_or(usize::MAX);
+            if load(b) < load(a) { b } else { a }
+        }
+    }
+}
+
+fn splitmix64(seed: u64) -> u64 {
+    let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+fn now_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> AutoscaleConfig {
+        AutoscaleConfig {
+            enabled: true,
+            target_queue_depth: 2,
+            ..AutoscaleConfig::default()
+        }
+    }
+
+    #[test]
+    fn power_of_two_prefers_less_loaded_candidate() {
+        let loads = vec![5, 0, 9, 3];
+        for seed in 0..64 {
+            let chosen = power_of_two_choices(&[0, 1], &loads, seed);
+            assert_eq!(chosen, 1);
+        }
+        let chosen = power_of_two_choices(&[2], &loads, 7);
+        assert_eq!(chosen, 2);
+    }
+
+    #[test]
+    fn scales_up_when_queue_depth_exceeds_target() {
+        let autoscaler = Autoscaler::new(config(), 4);
+        let loads = vec![0, 0, 0, 0];
+
+        let (first, decisions) = autoscaler.route("api", &loads, None, 1);
+        assert!(decisions.is_empty());
+        let (_, decisions) = autoscaler.route("api", &loads, None, 2);
+        assert!(decisions.is_empty());
+
+        let (_, decisions) = autoscaler.route("api", &loads, None, 3);
+        assert_eq!(decisions.len(), 1);
+        assert_eq!(decisions[0].action, ScaleAction::Up);
+        assert_eq!(decisions[0].replicas, 2);
+        assert_ne!(decisions[0].worker_id, first);
+
+        let stats = autoscaler.worker_stats(decisions[0].worker_id);
+        assert_eq!(stats.scale_ups, 1);
+        assert_eq!(stats.recent_decisions.len(), 1);
+    }
+
+    #[test]
+    fn scales_down_idle_handlers_to_minimum() {
+        let autoscaler = Autoscaler::new(
+            AutoscaleConfig {
+                scale_down_idle_secs: 0,
+                ..config()
+            },
+            2,
+        );
+        let loads = vec![0, 0];
+        {
+            let mut state = autoscaler.state.lock().unwrap();
+            state.handlers.insert(
+                "api".to_string(),
+                HandlerScale {
+                    replicas: vec![0, 1],
+                    in_flight: 0,
+                    latency_ewma_ms: 0.0,
+                    last_active: Instant::now() - Duration::from_secs(5),
+                    last_change: Instant::now() - Duration::from_secs(5),
+                },
+            );
+        }
+
+        let (_, decisions) = autoscaler.route("web", &loads, None, 1);
+        assert_eq!(decisions.len(), 1);
+        assert_eq!(decisions[0].handler_name, "api");
+        assert_eq!(decisions[0].action, ScaleAction::Down);
+        assert_eq!(decisions[0].replicas, 1);
+    }
+}
@@ -0,0 +1,428 @@
+//! Content-hashed cache for compiled `.phpx` modules.
+//!
+//! A module's cache key covers the compiler version, its own source, the
+//! diagnostics ignored through `deka.json` and the type-checked export
+//! signatures of every module it imports (transitively). Editing a function
+//! body only recompiles that file; changing an exported signature changes
+//! the key of every importer. The manifest in `.cache/phpx_js/`
+//! keeps keys and import edges across runs so stale dependents are dropped
+//! as soon as the change is seen.
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+
+use modules_php::validation::diagnostics::project_ignored_codes;
+use modules_php::validation::signatures::export_fingerprint;
+use phpx_js::{COMPILER_VERSION, SourceMap, parse_source_module_meta};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::esm_loader::resolve_import_path;
+
+const MANIFEST_FILE: &str = "manifest.json";
+const SOURCE_MAP_COMMENT: &str = "//# sourceMappingURL=";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Manifest {
+    compiler: String,
+    modules: BTreeMap<String, ManifestEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ManifestEntry {
+    key: String,
+    signature: String,
+    /// Project-relative paths of directly imported `.phpx` modules.
+    imports: Vec<String>,
+    has_source_map: bool,
+}
+
+/// Export signature and direct `.phpx` imports of a module on disk.
+#[derive(Debug, Clone, Default)]
+struct ModuleInfo {
+    signature: String,
+    imports: Vec<PathBuf>,
+}
+
+/// Compiled output read back from the cache.
+pub struct CachedModule {
+    pub js: String,
+    pub source_map: Option<SourceMap>,
+}
+
+pub struct CompileCache {
+    project_root: PathBuf,
+    cache_dir: PathBuf,
+    manifest: RefCell<Manifest>,
+    /// Per-loader memo; a new loader (isolate) re-reads sources from disk.
+    modules: RefCell<HashMap<PathBuf, ModuleInfo>>,
+}
+
+impl CompileCache {
+    pub fn open(project_root: PathBuf, cache_dir: PathBuf) -> Self {
+        let manifest = read_manifest(&cache_dir);
+        Self {
+            project_root,
+            cache_dir,
+            manifest: RefCell::new(manifest),
+            modules: RefCell::new(HashMap::new()),
+        }
+    }
+
+    pub fn cache_path_for(&self, path: &Path) -> PathBuf {
+        let rel = path.strip_prefix(&self.project_root).unwrap_or(path);
+        let mut out = self.cache_dir.join(rel);
+        out.set_extension("js");
+        out
+    }
+
+    /// Cache key for `path` given its current `source`.
+    pub fn key_for(&self, path: &Path, source: &str) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(COMPILER_VERSION);
+        hasher.update([0]);
+        hasher.update(self.relative(path));
+        hasher.update([0]);
+        hasher.update(source);
+        // Output is only cached for modules that validated, so un-ignoring a
+        // code has to bring its diagnostic back.
+        let mut ignored = project_ignored_codes(&path.to_string_lossy());
+        ignored.sort();
+        ignored.dedup();
+        for code in ignored {
+            hasher.update([0]);
+            hasher.update(code);
+        }
+
+        let mut deps = BTreeMap::new();
+        let mut stack = self.module_info(path, Some(source)).imports;
+        while let Some(dep) = stack.pop() {
+            if dep == path || deps.contains_key(&dep) {
+                continue;
+            }
+            let info = self.module_info(&dep, None);
+            stack.extend(info.imports);
+            deps.insert(dep, info.signature);
+        }
+        for (dep, signature) in deps {
+            hasher.update([0]);
+            hasher.update(self.relative(&dep));
+            hasher.update([0]);
+            hasher.update(signature);
+        }
+        format!("{:x}", hasher.finalize())
+    }
+
+    /// Cached output for `path`, if it was stored under `key`.
+    pub fn load(&self, path: &Path, key: &str) -> Option<CachedModule> {
+        let entry = self
+            .manifest
+            .borrow()
+            .modules
+            .get(&self.relative(path))
+            .filter(|entry| entry.key == key)
+            .cloned()?;
+        let cache_path = self.cache_path_for(path);
+        let mut js = std::fs::read_to_string(&cache_path).ok()?;
+        if !entry.has_source_map {
+            return Some(CachedModule {
+                js,
+                source_map: None,
+            });
+        }
+        let raw = std::fs::read_to_string(source_map_path(&cache_path)).ok()?;
+        let source_map = SourceMap::from_json(&raw).ok()?;
+        if let Some(index) = js.rfind(SOURCE_MAP_COMMENT) {
+            js.truncate(index);
+        }
+        Some(CachedModule {
+            js,
+            source_map: Some(source_map),
+        })
+    }
+
+    /// Write compiled output and record it under `key`. If the module's export
+    /// signature changed, everything importing it is evicted.
+    pub fn store(
+        &self,
+        path: &Path,
+        key: &str,
+        source: &str,
+        js: &str,
+        source_map: Option<&SourceMap>,
+    ) {
+        let cache_path = self.cache_path_for(path);
+        if let Some(parent) = cache_path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        match source_map {
+            Some(map) => {
+                // The cached copy points at an external map so tools opening
+                // `.cache/phpx_js/` still resolve back to the `.phpx` file.
+                let map_path = source_map_path(&cache_path);
+                let map_name = map_path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                let _ = std::fs::write(&map_path, map.to_json());
+                let _ = std::fs::write(
+                    &cache_path,
+                    format!(
+                        "{}\n{}",
+                        js.trim_end(),
+                        SourceMap::to_url_comment(&map_name)
+                    ),
+                );
+            }
+            None => {
+                let _ = std::fs::write(&cache_path, js);
+            }
+        }
+
+        let info = self.module_info(path, Some(source));
+        let rel = self.relative(path);
+        let entry = ManifestEntry {
+            key: key.to_string(),
+            signature: info.signature,
+            imports: info.imports.iter().map(|dep| self.relative(dep)).collect(),
+            has_source_map: source_map.is_some(),
+        };
+        let mut manifest = self.manifest.borrow_mut();
+        let previous = manifest.modules.insert(rel.clone(), entry.clone());
+        let mut evicted = Vec::new();
+        if previous.is_some_and(|previous| previous.signature != entry.signature) {
+            for dependent in dependents_of(&manifest, &rel) {
+                manifest.modules.remove(&dependent);
+                evicted.push(dependent);
+            }
+        }
+        drop(manifest);
+
+        for dependent in &evicted {
+            let cache_path = self.cache_path_for(&self.project_root.join(dependent));
+            let _ = std::fs::remove_file(source_map_path(&cache_path));
+            let _ = std::fs::remove_file(cache_path);
+        }
+        self.save(&rel, &evicted);
+    }
+
+    fn module_info(&self, path: &Path, source: Option<&str>) -> ModuleInfo {
+        if let Some(info) = self.modules.borrow().get(path) {
+            return info.clone();
+        }
+        let source = match source {
+            Some(source) => Some(source.to_string()),
+            None => std::fs::read_to_string(path).ok(),
+        };
+        let info = source
+            .map(|source| ModuleInfo {
+                signature: export_fingerprint(&source, &path.to_string_lossy()),
+                imports: parse_source_module_meta(&source)
+                    .imports
+                    .iter()
+                    .filter_map(|decl| {
+                        resolve_import_path(&self.project_root, path, decl.from.trim())
+                    })
+                    .filter(|dep| dep.extension().and_then(|ext| ext.to_str()) == Some("phpx"))
+                    .collect(),
+            })
+            .unwrap_or_default();
+        self.modules
+            .borrow_mut()
+            .insert(path.to_path_buf(), info.clone());
+        info
+    }
+
+    fn relative(&self, path: &Path) -> String {
+        path.strip_prefix(&self.project_root)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .replace('\\', "/")
+    }
+
+    /// Merge this loader's changes into the manifest on disk. Isolates load
+    /// modules concurrently, so entries written by others are kept.
+    fn save(&self, updated: &str, evicted: &[String]) {
+        let mut on_disk = read_manifest(&self.cache_dir);
+        if let Some(entry) = self.manifest.borrow().modules.get(updated) {
+            on_disk.modules.insert(updated.to_string(), entry.clone());
+        }
+        for dependent in evicted {
+            on_disk.modules.remove(dependent);
+        }
+        let Ok(raw) = serde_json::to_string_pretty(&on_disk) else {
+            return;
+        };
+        let tmp = self
+            .cache_dir
+            .join(format!("{}.{}.tmp", MANIFEST_FILE, std::process::id()));
+        if std::fs::write(&tmp, raw).is_ok() {
+            let _ = std::fs::rename(&tmp, self.cache_dir.join(MANIFEST_FILE));
+        }
+    }
+}
+
+fn read_manifest(cache_dir: &Path) -> Manifest {
+    std::fs::read_to_string(cache_dir.join(MANIFEST_FILE))
+        .ok()
+        .and_then(|raw| serde_json::from_str::<Manifest>(&raw).ok())
+        .filter(|manifest| manifest.compiler == COMPILER_VERSION)
+        .unwrap_or_else(|| Manifest {
+            compiler: COMPILER_VERSION.to_string(),
+            modules: BTreeMap::new(),
+        })
+}
+
+/// Modules that transitively import `module`, via the recorded import edges.
+fn dependents_of(manifest: &Manifest, module: &str) -> Vec<String> {
+    let mut seen = HashSet::new();
+    let mut queue = VecDeque::from([module.to_string()]);
+    let mut out = Vec::new();
+    while let Some(current) = queue.pop_front() {
+        for (name, entry) in &manifest.modules {
+            if entry.imports.contains(&current) && seen.insert(name.clone()) {
+                if name != module {
+                    out.push(name.clone());
+                }
+                queue.push_back(name.clone());
+            }
+        }
+    }
+    out
+}
+
+fn source_map_path(cache_path: &Path) -> PathBuf {
+    let mut path = cache_path.to_path_buf().into_os_string();
+    path.push(".map");
+    PathBuf::from(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn temp_project(name: &str) -> PathBuf {
+        let stamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("time")
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!(
+            "deka-compile-cache-{}-{}-{}",
+            name,
+            std::process::id(),
+            stamp
+        ));
+        std::fs::create_dir_all(dir.join(".cache/phpx_js")).expect("create temp project");
+        dir
+    }
+
+    #[test]
+    fn signature_ignores_function_bodies() {
+        let root = temp_project("signature");
+        let path = root.join("math.phpx");
+        let file_path = path.to_string_lossy();
+        let source = "export function add($a: int, $b: int): int {\n  return $a + $b\n}\n\
+struct Point {\n  $x: int\n  $y: int\n}\n\
+export { Point }\n";
+        let signature = export_fingerprint(source, &file_path);
+        assert!(!signature.is_empty());
+
+        let edited = source.replace("return $a + $b", "\n  return $b + $a");
+        assert_eq!(signature, export_fingerprint(&edited, &file_path));
+        let retyped = source.replace("$b: int): int", "$b: float): float");
+        assert_ne!(signature, export_fingerprint(&retyped, &file_path));
+        let reshaped = source.replace("$y: int", "$y: float");
+        assert_ne!(signature, export_fingerprint(&reshaped, &file_path));
+
+        let _ = std::fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn key_covers_ignored_diagnostics() {
+        let root = temp_project("ignore");
+        let main = root.join("main.phpx");
+        let source = "$x = 1\n";
+        std::fs::write(&main, source).expect("write main");
+
+        let cache = CompileCache::open(root.clone(), root.join(".cache/phpx_js"));
+        let plain = cache.key_for(&main, source);
+        let manifest = root.join("deka.json");
+        std::fs::write(
+            &manifest,
+            r#"{ "phpx": { "diagnostics": { "ignore": ["PX0301", "px0306"] } } }"#,
+        )
+        .expect("write deka.json");
+        let ignoring = cache.key_for(&main, source);
+        assert_ne!(plain, ignoring);
+
+        std::fs::write(
+            &manifest,
+            r#"{ "phpx": { "diagnostics": { "ignore": ["PX0306", "PX0301"] } } }"#,
+        )
+        .expect("write deka.json");
+        assert_eq!(cache.key_for(&main, source), ignoring);
+
+        let _ = std::fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn reuses_output_until_a_dependency_signature_changes() {
+        let root = temp_project("deps");
+        let util = root.join("util.phpx");
+        let main = root.join("main.phpx");
+        let util_source = "export function twice($n: int): int {\n  return $n * 2\n}\n";
+        let main_source = "import { twice } from './util.phpx'\n$x = twice(2)\n";
+        std::fs::write(&util, util_source).expect("write util");
+        std::fs::write(&main, main_source).expect("write main");
+
+        let cache = CompileCache::open(root.clone(), root.join(".cache/phpx_js"));
+        let util_key = cache.key_for(&util, util_source);
+        let main_key = cache.key_for(&main, main_source);
+        cache.store(
+            &util,
+            &util_key,
+            util_source,
+            "export function twice() {}",
+            None,
+        );
+        cache.store(&main, &main_key, main_source, "let x = twice(2);", None);
+
+        // A fresh loader sees the stored output.
+        let cache = CompileCache::open(root.clone(), root.join(".cache/phpx_js"));
+        let cached = cache.load(&main, &cache.key_for(&main, main_source));
+        assert_eq!(
+            cached.map(|module| module.js).as_deref(),
+            Some("let x = twice(2);")
+        );
+
+        // Body edits keep dependents cached.
+        let body_edit = util_source.replace("$n * 2", "$n + $n");
+        std::fs::write(&util, &body_edit).expect("write util");
+        let cache = CompileCache::open(root.clone(), root.join(".cache/phpx_js"));
+        assert!(
+            cache
+                .load(&main, &cache.key_for(&main, main_source))
+                .is_some()
+        );
+
+        // Signature edits evict dependents through the import graph.
+        let signature_edit = util_source.replace("$n: int", "$n: float");
+        std::fs::write(&util, &signature_edit).expect("write util");
+        let cache = CompileCache::open(root.clone(), root.join(".cache/phpx_js"));
+        let main_key = cache.key_for(&main, main_source);
+        assert!(cache.load(&main, &main_key).is_none());
+        let util_key = cache.key_for(&util, &signature_edit);
+        cache.store(
+            &util,
+            &util_key,
+            &signature_edit,
+            "export function twice() {}",
+            None,
+        );
+        assert!(!cache.cache_path_for(&main).exists());
+
+        let _ = std::fs::remove_dir_all(root);
+    }
+}
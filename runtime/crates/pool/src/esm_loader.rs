@@ -17,20 +17,25 @@ use deno_core::resolve_import;
 use deno_error::JsErrorBox;
 
 use phpx_js::build_stdlib_prelude;
-use phpx_js::compile_phpx_source_to_js;
+use phpx_js::SourceMap;
+use phpx_js::compile_phpx_source_to_js_with_source_map;
 use phpx_js::parse_source_module_meta;
 use phpx_js::SourceModuleMeta;
 use runtime_core::module_spec::{is_bare_module_specifier, module_spec_aliases};
 
+use crate::compile_cache::CompileCache;
+use crate::validation::register_source_map;
+
 #[derive(Clone)]
 pub struct PhpxEsmLoader {
     project_root: PathBuf,
-    cache_dir: PathBuf,
+    cache: Rc<CompileCache>,
     entry_specifier: ModuleSpecifier,
     wrapper_specifier: ModuleSpecifier,
     prelude_specifier: ModuleSpecifier,
     prelude_source: String,
     sources: Rc<RefCell<HashMap<String, ModuleSourceCode>>>,
+    source_maps: bool,
 }
 
 impl PhpxEsmLoader {
@@ -52,21 +57,22 @@ impl PhpxEsmLoader {
             )
         });
         Ok(Self {
+            cache: Rc::new(CompileCache::open(project_root.clone(), cache_dir)),
             project_root,
-            cache_dir,
             entry_specifier,
             wrapper_specifier,
             prelude_specifier,
             prelude_source,
             sources: Rc::new(RefCell::new(HashMap::new())),
+            source_maps: false,
         })
     }
 
-    fn cache_path_for(&self, path: &Path) -> PathBuf {
-        let rel = path.strip_prefix(&self.project_root).unwrap_or(path);
-        let mut out = self.cache_dir.join(rel);
-        out.set_extension("js");
-        out
+    /// Embed inline source maps in compiled `.phpx` modules so debuggers and
+    /// dev-mode stack traces show the original source.
+    pub fn with_source_maps(mut self, enabled: bool) -> Self {
+        self.source_maps = enabled;
+        self
     }
 
     fn load_js_source(&self, path: &Path) -> Result<ModuleSourceCode, JsErrorBox> {
@@ -75,7 +81,10 @@ impl PhpxEsmLoader {
         Ok(ModuleSourceCode::String(text.into()))
     }
 
-    fn load_phpx_source(&self, path: &Path) -> Result<ModuleSourceCode, JsErrorBox> {
+    fn load_phpx_source(
+        &self,
+        path: &Path,
+    ) -> Result<(ModuleSourceCode, Option<SourceMap>), JsErrorBox> {
         let input = path
             .to_str()
             .ok_or_else(|| JsErrorBox::generic(format!("invalid path: {}", path.display())))?;
@@ -84,16 +93,21 @@ impl PhpxEsmLoader {
         let meta = parse_source_module_meta(&source);
         ensure_project_layout(&self.project_root, &meta)
             .map_err(|err| JsErrorBox::generic(err))?;
-        let js = compile_phpx_source_to_js(&source, input, meta)
-            .map_err(|err| JsErrorBox::generic(err))?;
-
-        let cache_path = self.cache_path_for(path);
-        if let Some(parent) = cache_path.parent() {
-            let _ = std::fs::create_dir_all(parent);
+        // Validation and emission dominate cold starts; reuse the last output
+        // while the source and the export signatures it imports are unchanged.
+        let key = self.cache.key_for(path, &source);
+        if let Some(cached) = self.cache.load(path, &key) {
+            return Ok((
+                ModuleSourceCode::String(cached.js.into()),
+                cached.source_map,
+            ));
         }
-        let _ = std::fs::write(&cache_path, &js);
+        let (js, source_map) = compile_phpx_source_to_js_with_source_map(&source, input, meta)
+            .map_err(|err| JsErrorBox::generic(err))?;
+        self.cache
+            .store(path, &key, &source, &js, source_map.as_ref());
 
-        Ok(ModuleSourceCode::String(js.into()))
+        Ok((ModuleSourceCode::String(js.into()), source_map))
     }
 
     fn resolve_phpx_module_spec(&self, specifier: &str) -> Option<PathBuf> {
@@ -141,13 +155,19 @@ impl PhpxEsmLoader {
             .to_file_path()
             .map_err(|_| JsErrorBox::generic("Only file:// URLs are supported"))?;
         let ext = path.extension().and_then(|ext| ext.to_str()).unwrap_or("");
-        let mut code = match ext {
+        let (mut code, source_map) = match ext {
             "phpx" => self.load_phpx_source(&path)?,
-            _ => self.load_js_source(&path)?,
+            _ => (self.load_js_source(&path)?, None),
         };
         if specifier == &self.entry_specifier {
             code = append_entry_footer(code);
         }
+        if let Some(map) = source_map {
+            if self.source_maps {
+                code = append_source_map(code, &map);
+            }
+            register_source_map(specifier.as_str(), map);
+        }
         Ok(ModuleSource::new(ModuleType::JavaScript, code, specifier, None))
     }
 
@@ -407,7 +427,7 @@ fn resolve_phpx_module_spec(project_root: &Path, specifier: &str) -> Option<Path
     None
 }
 
-fn resolve_import_path(
+pub(crate) fn resolve_import_path(
     project_root: &Path,
     referrer: &Path,
     specifier: &str,
@@ -453,6 +473,20 @@ fn is_bare_specifier(spec: &str) -> bool {
     is_bare_module_specifier(spec)
 }
 
+fn append_source_map(code: ModuleSourceCode, map: &SourceMap) -> ModuleSourceCode {
+    match code {
+        ModuleSourceCode::String(source) => {
+            let mut text = source.to_owned();
+            if !text.ends_with('\n') {
+                text.push('\n');
+            }
+            text.push_str(&map.to_inline_comment());
+            ModuleSourceCode::String(text.into())
+        }
+        other => other,
+    }
+}
+
 fn append_entry_footer(code: ModuleSourceCode) -> ModuleSourceCode {
     const FOOTER: &str = "\nif (typeof globalThis.app === \"undefined\" && typeof app !== \"undefined\") {\n\
   const __candidate = app;\n\
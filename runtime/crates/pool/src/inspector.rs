@@ -0,0 +1,306 @@
+//! Chrome DevTools Protocol bridge for pool isolates.
+//!
+//! When `PoolConfig::inspector` is set, every runtime is created with the V8
+//! inspector enabled and registered as a debug target. A small HTTP server on
+//! its own thread serves the `/json` discovery endpoints that Chrome and VS
+//! Code poll, and bridges each `/ws/<id>` websocket to an inspector session on
+//! the isolate's worker thread.
+
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use axum::Router;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Json, Response};
+use axum::routing::get;
+use deno_core::futures::channel::mpsc::{UnboundedSender, unbounded};
+use deno_core::futures::{SinkExt, StreamExt};
+use deno_core::{InspectorMsg, InspectorSessionKind, InspectorSessionProxy};
+
+pub const DEFAULT_INSPECTOR_ADDR: &str = "127.0.0.1:9229";
+
+static SERVER: OnceLock<Arc<InspectorServer>> = OnceLock::new();
+
+#[derive(Debug, Clone)]
+pub struct InspectorConfig {
+    /// Address the DevTools discovery and websocket server listens on
+    pub addr: SocketAddr,
+    /// Pause before the entry module's first statement until a debugger attaches
+    pub break_on_start: bool,
+}
+
+impl InspectorConfig {
+    /// Parse the value of `--inspect[=host:port]`. Accepts `host:port`, `:port`
+    /// or a bare port; an empty value uses `DEFAULT_INSPECTOR_ADDR`.
+    pub fn parse(value: Option<&str>, break_on_start: bool) -> Result<Self, String> {
+        let value = value.map(str::trim).unwrap_or("");
+        let addr = if value.is_empty() {
+            DEFAULT_INSPECTOR_ADDR.to_string()
+        } else if let Some(port) = value.strip_prefix(':') {
+            format!("127.0.0.1:{}", port)
+        } else if value.chars().all(|ch| ch.is_ascii_digit()) {
+            format!("127.0.0.1:{}", value)
+        } else {
+            value.to_string()
+        };
+        let addr = addr
+            .parse::<SocketAddr>()
+            .map_err(|err| format!("invalid inspector address '{}': {}", value, err))?;
+        Ok(Self {
+            addr,
+            break_on_start,
+        })
+    }
+}
+
+struct InspectorTarget {
+    title: String,
+    url: String,
+    session_tx: UnboundedSender<InspectorSessionProxy>,
+}
+
+pub(crate) struct InspectorServer {
+    addr: SocketAddr,
+    targets: Mutex<BTreeMap<String, InspectorTarget>>,
+    next_id: AtomicU64,
+    break_pending: AtomicBool,
+}
+
+/// Keeps a target listed while its isolate is alive.
+pub(crate) struct InspectorRegistration {
+    server: Arc<InspectorServer>,
+    id: String,
+}
+
+impl Drop for InspectorRegistration {
+    fn drop(&mut self) {
+        if let Ok(mut targets) = self.server.targets.lock() {
+            targets.remove(&self.id);
+        }
+    }
+}
+
+impl InspectorServer {
+    /// Start the process-wide inspector server, or return the running one.
+    /// Both pools share a server so their isolates appear under one address.
+    pub(crate) fn ensure(config: &InspectorConfig) -> Result<Arc<Self>, String> {
+        if let Some(server) = SERVER.get() {
+            return Ok(Arc::clone(server));
+        }
+        let listener = std::net::TcpListener::bind(config.addr)
+            .map_err(|err| format!("failed to bind inspector on {}: {}", config.addr, err))?;
+        listener
+            .set_nonblocking(true)
+            .map_err(|err| format!("failed to configure inspector listener: {}", err))?;
+        let addr = listener.local_addr().unwrap_or(config.addr);
+        let server = Arc::new(Self {
+            addr,
+            targets: Mutex::new(BTreeMap::new()),
+            next_id: AtomicU64::new(1),
+            break_pending: AtomicBool::new(config.break_on_start),
+        });
+        if SERVER.set(Arc::clone(&server)).is_err() {
+            return Ok(Arc::clone(SERVER.get().expect("inspector server set")));
+        }
+
+        let state = Arc::clone(&server);
+        std::thread::Builder::new()
+            .name("deka-inspector".to_string())
+            .spawn(move || {
+                let rt = tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                    .expect("Failed to create tokio runtime for inspector");
+                rt.block_on(async move {
+                    let listener = match tokio::net::TcpListener::from_std(listener) {
+                        Ok(listener) => listener,
+                        Err(err) => {
+                            tracing::error!("inspector listener failed: {}", err);
+                            return;
+                        }
+                    };
+                    let app = Router::new()
+                        .route("/json/version", get(version))
+                        .route("/json", get(list))
+                        .route("/json/list", get(list))
+                        .route("/ws/:id", get(connect))
+                        .with_state(state);
+                    if let Err(err) = axum::serve(listener, app).await {
+                        tracing::error!("inspector server failed: {}", err);
+                    }
+                });
+            })
+            .map_err(|err| format!("failed to spawn inspector thread: {}", err))?;
+
+        deka_stdio::log(
+            "inspect",
+            &format!(
+                "Debugger listening on {} (targets: http://{}/json/list)",
+                addr, addr
+            ),
+        );
+        Ok(server)
+    }
+
+    pub(crate) fn register(
+        self: &Arc<Self>,
+        title: &str,
+        url: &str,
+        session_tx: UnboundedSender<InspectorSessionProxy>,
+    ) -> InspectorRegistration {
+        let id = format!("deka-{}", self.next_id.fetch_add(1, Ordering::Relaxed));
+        if let Ok(mut targets) = self.targets.lock() {
+            targets.insert(
+                id.clone(),
+                InspectorTarget {
+                    title: title.to_string(),
+                    url: url.to_string(),
+                    session_tx,
+                },
+            );
+        }
+        deka_stdio::log(
+            "inspect",
+            &format!("Target {} ready at ws://{}/ws/{}", title, self.addr, id),
+        );
+        InspectorRegistration {
+            server: Arc::clone(self),
+            id,
+        }
+    }
+
+    /// True exactly once when `--inspect-brk` is set: the first isolate to load
+    /// its entry module waits for a debugger, later isolates start normally.
+    pub(crate) fn take_break_on_start(&self) -> bool {
+        self.break_pending.swap(false, Ordering::Relaxed)
+    }
+
+    fn describe(&self) -> Vec<serde_json::Value> {
+        let Ok(targets) = self.targets.lock() else {
+            return Vec::new();
+        };
+        targets
+            .iter()
+            .map(|(id, target)| {
+                let ws = format!("{}/ws/{}", self.addr, id);
+                serde_json::json!({
+                    "id": id,
+                    "type": "node",
+                    "title": target.title,
+                    "url": target.url,
+                    "description": "deka isolate",
+                    "webSocketDebuggerUrl": format!("ws://{}", ws),
+                    "devtoolsFrontendUrl": format!(
+                        "devtools://devtools/bundled/js_app.html?experiments=true&v8only=true&ws={}",
+                        ws
+                    ),
+                })
+            })
+            .collect()
+    }
+}
+
+async fn version() -> Json<serde_json::Value> {
+    Json(serde_json::json!({
+        "Browser": format!("deka/{}", env!("CARGO_PKG_VERSION")),
+        "Protocol-Version": "1.3",
+        "V8-Version": deno_core::v8::V8::get_version(),
+    }))
+}
+
+async fn list(State(server): State<Arc<InspectorServer>>) -> Json<serde_json::Value> {
+    Json(serde_json::Value::Array(server.describe()))
+}
+
+async fn connect(
+    State(server): State<Arc<InspectorServer>>,
+    Path(id): Path<String>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    let session_tx = server
+        .targets
+        .lock()
+        .ok()
+        .and_then(|targets| targets.get(&id).map(|target| target.session_tx.clone()));
+    let Some(session_tx) = session_tx else {
+        return (
+            StatusCode::NOT_FOUND,
+            format!("unknown inspector target: {}", id),
+        )
+            .into_response();
+    };
+    ws.on_upgrade(move |socket| bridge_session(socket, session_tx))
+}
+
+/// Pump CDP messages between the websocket and an inspector session proxy.
+async fn bridge_session(socket: WebSocket, session_tx: UnboundedSender<InspectorSessionProxy>) {
+    let (outbound_tx, mut outbound_rx) = unbounded::<InspectorMsg>();
+    let (mut inbound_tx, inbound_rx) = unbounded::<String>();
+    let proxy = InspectorSessionProxy {
+        tx: outbound_tx,
+        rx: inbound_rx,
+        kind: InspectorSessionKind::NonBlocking {
+            wait_for_disconnect: true,
+        },
+    };
+    if session_tx.unbounded_send(proxy).is_err() {
+        return;
+    }
+    deka_stdio::log("inspect", "Debugger attached.");
+
+    let (mut ws_sender, mut ws_receiver) = socket.split();
+    let write_task = tokio::spawn(async move {
+        while let Some(message) = outbound_rx.next().await {
+            if ws_sender
+                .send(Message::Text(message.content))
+                .await
+                .is_err()
+            {
+                break;
+            }
+        }
+    });
+
+    while let Some(message) = ws_receiver.next().await {
+        match message {
+            Ok(Message::Text(text)) => {
+                if inbound_tx.send(text).await.is_err() {
+                    break;
+                }
+            }
+            Ok(Message::Close(_)) => break,
+            Ok(_) => {}
+            Err(_) => break,
+        }
+    }
+
+    write_task.abort();
+    deka_stdio::log("inspect", "Debugger detached.");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_inspect_address_forms() {
+        let default = InspectorConfig::parse(None, false).unwrap();
+        assert_eq!(default.addr.to_string(), DEFAULT_INSPECTOR_ADDR);
+
+        let port = InspectorConfig::parse(Some("9230"), true).unwrap();
+        assert_eq!(port.addr.to_string(), "127.0.0.1:9230");
+        assert!(port.break_on_start);
+
+        let colon = InspectorConfig::parse(Some(":9231"), false).unwrap();
+        assert_eq!(colon.addr.to_string(), "127.0.0.1:9231");
+
+        let full = InspectorConfig::parse(Some("0.0.0.0:9300"), false).unwrap();
+        assert_eq!(full.addr.to_string(), "0.0.0.0:9300");
+
+        assert!(InspectorConfig::parse(Some("localhost"), false).is_err());
+    }
+}
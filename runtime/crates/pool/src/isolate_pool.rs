@@ -40,6 +40,7 @@ use tokio::sync::{mpsc, oneshot};
 
 static POOL_IDS: AtomicU64 = AtomicU64::new(1);
 const REQUEST_BATCH_MAX: usize = 8;
+const INSPECTOR_POLL_INTERVAL: Duration = Duration::from_millis(50);
 static PERF_PROFILE_ENABLED: OnceLock<bool> = OnceLock::new();
 static PERF_COUNT: AtomicU64 = AtomicU64::new(0);
 static PERF_QUEUE_TOTAL_MS: AtomicU64 = AtomicU64::new(0);
@@ -54,7 +55,15 @@ thread_local! {
     static CURRENT_POOL_ID: Cell<Option<u64>> = Cell::new(None);
 }
 
+use crate::admission::{
+    AdmissionConfig, AdmissionController, AdmissionPermit, RejectReason, RequestPriority,
+};
+use crate::autoscale::{
+    AutoscaleConfig, Autoscaler, ScaleAction, ScaleDecision, power_of_two_choices,
+};
+use crate::inspector::{InspectorConfig, InspectorRegistration, InspectorServer};
 use crate::validation;
+use crate::watchdog::{BudgetViolation, BudgetWatchdog, ExecutionBudget};
 use crate::esm_loader::{
     PhpxEsmLoader, entry_wrapper_path, hash_module_graph, resolve_project_root,
 };
@@ -137,12 +146,24 @@ pub struct PoolConfig {
     pub enable_metrics: bool,
     /// Enable V8 code cache for handler compilation
     pub enable_code_cache: bool,
-    /// Request execution timeout in milliseconds (0 = no timeout)
+    /// Request wall-clock budget in milliseconds (0 = no timeout)
     pub request_timeout_ms: u64,
+    /// Request CPU time budget in milliseconds (0 = unlimited)
+    pub cpu_budget_ms: u64,
+    /// Per-handler budget overrides keyed by handler name
+    pub handler_budgets: HashMap<String, ExecutionBudget>,
+    /// Include the captured stack in error responses when a budget is exceeded (dev mode)
+    pub expose_stack_traces: bool,
     /// Max time a request can sit in the queue in milliseconds (0 = no timeout)
     pub queue_timeout_ms: u64,
+    /// Per-handler queue bounds, concurrency limits and load shedding
+    pub admission: AdmissionConfig,
     /// Scheduler strategy for routing requests to workers
     pub scheduler_strategy: SchedulerStrategy,
+    /// Adaptive per-handler isolate scaling
+    pub autoscale: AutoscaleConfig,
+    /// Expose isolates to Chrome DevTools / VS Code (`--inspect`)
+    pub inspector: Option<InspectorConfig>,
     /// Enable per-request profiling data (op timings)
     pub introspect_profiling: bool,
 }
@@ -157,8 +178,14 @@ impl Default for PoolConfig {
             enable_metrics: true,
             enable_code_cache: true,
             request_timeout_ms: 30_000,
+            cpu_budget_ms: 0,
+            handler_budgets: HashMap::new(),
+            expose_stack_traces: false,
             queue_timeout_ms: 10_000,
+            admission: AdmissionConfig::default(),
             scheduler_strategy: SchedulerStrategy::LeastLoaded,
+            autoscale: AutoscaleConfig::default(),
+            inspector: None,
             introspect_profiling: false,
         }
     }
@@ -173,9 +200,12 @@ impl PoolConfig {
     /// - ISOLATE_IDLE_TIMEOUT: Idle timeout in seconds (0 = never evict)
     /// - ISOLATE_METRICS: Enable metrics (default: true)
     /// - ISOLATE_CODE_CACHE: Enable V8 code cache (default: true)
-    /// - ISOLATE_REQUEST_TIMEOUT_MS: Request timeout in ms (0 = no timeout)
+    /// - ISOLATE_REQUEST_TIMEOUT_MS: Request wall-clock budget in ms (0 = no timeout)
+    /// - ISOLATE_CPU_BUDGET_MS: Request CPU time budget in ms (0 = unlimited)
     /// - ISOLATE_QUEUE_TIMEOUT_MS: Queue timeout in ms (0 = no timeout)
-    /// - ISOLATE_SCHEDULER: "consistent" or "least_loaded"
+    /// - ISOLATE_MAX_QUEUE / ISOLATE_MAX_CONCURRENCY: see `AdmissionConfig::from_env`
+    /// - ISOLATE_SCHEDULER: "consistent", "least_loaded" or "p2c"
+    /// - ISOLATE_AUTOSCALE*: see `AutoscaleConfig::from_env`
     pub fn from_env() -> Self {
         let default_workers = default_num_workers();
         Self {
@@ -201,14 +231,23 @@ impl PoolConfig {
                 .ok()
                 .and_then(|s| s.parse().ok())
                 .unwrap_or(30_000),
+            cpu_budget_ms: std::env::var("ISOLATE_CPU_BUDGET_MS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(0),
+            handler_budgets: HashMap::new(),
+            expose_stack_traces: false,
             queue_timeout_ms: std::env::var("ISOLATE_QUEUE_TIMEOUT_MS")
                 .ok()
                 .and_then(|s| s.parse().ok())
                 .unwrap_or(10_000),
+            admission: AdmissionConfig::from_env(),
             scheduler_strategy: std::env::var("ISOLATE_SCHEDULER")
                 .ok()
                 .and_then(|value| SchedulerStrategy::from_env(&value))
                 .unwrap_or(SchedulerStrategy::LeastLoaded),
+            autoscale: AutoscaleConfig::from_env(),
+            inspector: None,
             introspect_profiling: std::env::var("INTROSPECT_PROFILING")
                 .map(|value| value != "false" && value != "0")
                 .unwrap_or(false),
@@ -225,6 +264,8 @@ fn default_num_workers() -> usize {
 pub enum SchedulerStrategy {
     ConsistentHash,
     LeastLoaded,
+    /// Sample two workers at random and pick the less loaded one
+    PowerOfTwoChoices,
 }
 
 impl SchedulerStrategy {
@@ -232,6 +273,7 @@ impl SchedulerStrategy {
         match value.to_lowercase().as_str() {
             "consistent" | "hash" => Some(Self::ConsistentHash),
             "least_loaded" | "least" => Some(Self::LeastLoaded),
+            "power_of_two" | "p2c" => Some(Self::PowerOfTwoChoices),
             _ => None,
         }
     }
@@ -333,6 +375,8 @@ enum WorkerControl {
     GetAllMetrics {
         response_tx: oneshot::Sender<Vec<(HandlerKey, IsolateMetrics)>>,
     },
+    /// Drop the isolate for a handler after the autoscaler released this replica
+    ReleaseIsolate { key: HandlerKey },
     /// Get recent request traces for this worker
     GetRecentRequests {
         response_tx: oneshot::Sender<Vec<RequestTrace>>,
@@ -352,6 +396,14 @@ pub struct PoolMetrics {
     pub cache_hits: AtomicU64,
     pub cache_misses: AtomicU64,
     pub evictions: AtomicU64,
+    /// Requests that had to wait for a handler concurrency slot
+    pub admission_queued: AtomicU64,
+    /// Critical requests that skipped admission control
+    pub admission_bypassed: AtomicU64,
+    /// Requests shed because the handler queue was full
+    pub shed_queue_full: AtomicU64,
+    /// Requests shed after waiting too long for a slot
+    pub shed_queue_timeout: AtomicU64,
 }
 
 impl Default for PoolMetrics {
@@ -361,6 +413,10 @@ impl Default for PoolMetrics {
             cache_hits: AtomicU64::new(0),
             cache_misses: AtomicU64::new(0),
             evictions: AtomicU64::new(0),
+            admission_queued: AtomicU64::new(0),
+            admission_bypassed: AtomicU64::new(0),
+            shed_queue_full: AtomicU64::new(0),
+            shed_queue_timeout: AtomicU64::new(0),
         }
     }
 }
@@ -387,7 +443,13 @@ impl PoolMetrics {
             "cache_hits": hits,
             "cache_misses": misses,
             "cache_hit_rate": self.cache_hit_rate(),
-            "evictions": evictions
+            "evictions": evictions,
+            "admission": {
+                "queued": self.admission_queued.load(Ordering::Relaxed),
+                "bypassed": self.admission_bypassed.load(Ordering::Relaxed),
+                "shed_queue_full": self.shed_queue_full.load(Ordering::Relaxed),
+                "shed_queue_timeout": self.shed_queue_timeout.load(Ordering::Relaxed)
+            }
         })
     }
 }
@@ -398,6 +460,7 @@ impl PoolMetrics {
 struct WorkerLoad {
     queued_requests: AtomicUsize,
     active_requests: AtomicUsize,
+    spare_isolates: AtomicUsize,
 }
 
 impl Default for WorkerLoad {
@@ -405,6 +468,7 @@ impl Default for WorkerLoad {
         Self {
             queued_requests: AtomicUsize::new(0),
             active_requests: AtomicUsize::new(0),
+            spare_isolates: AtomicUsize::new(0),
         }
     }
 }
@@ -722,6 +786,8 @@ pub struct IsolatePool {
     request_seq: AtomicU64,
     introspect_profiling: Arc<AtomicBool>,
     pool_id: u64,
+    autoscaler: Option<Autoscaler>,
+    admission: AdmissionController,
 }
 
 impl IsolatePool {
@@ -735,6 +801,11 @@ impl IsolatePool {
         let mut workers = Vec::with_capacity(config.num_workers);
         let pool_id = POOL_IDS.fetch_add(1, Ordering::Relaxed);
         let core_ids = core_affinity::get_core_ids();
+        let inspector = config.inspector.as_ref().and_then(|inspector| {
+            InspectorServer::ensure(inspector)
+                .map_err(|err| tracing::error!("Inspector disabled: {}", err))
+                .ok()
+        });
 
         tracing::info!(
             "Initializing isolate pool: {} workers, {} max isolates/worker",
@@ -751,6 +822,7 @@ impl IsolatePool {
             let load = Arc::new(WorkerLoad::default());
             let worker_load = Arc::clone(&load);
             let profiling = Arc::clone(&introspect_profiling);
+            let worker_inspector = inspector.clone();
             let core_id = core_ids
                 .as_ref()
                 .and_then(|ids| ids.get(worker_id % ids.len()).cloned());
@@ -767,6 +839,7 @@ impl IsolatePool {
                     worker_load,
                     ext_provider,
                     profiling,
+                    worker_inspector,
                 );
                 worker.run(rx, ctrl_rx);
             });
@@ -779,6 +852,12 @@ impl IsolatePool {
             });
         }
 
+        let autoscaler = config
+            .autoscale
+            .enabled
+            .then(|| Autoscaler::new(config.autoscale.clone(), config.num_workers));
+        let admission = AdmissionController::new(config.admission.clone());
+
         Self {
             workers,
             config,
@@ -786,6 +865,8 @@ impl IsolatePool {
             request_seq: AtomicU64::new(0),
             introspect_profiling,
             pool_id,
+            autoscaler,
+            admission,
         }
     }
 
@@ -803,8 +884,28 @@ impl IsolatePool {
                     .to_string(),
             );
         }
-        let worker_index = self.select_worker_with_exclude(&handler_key, current_worker);
-        let request_id = self.next_request_id();
+
+        let _permit = match self.admit(&handler_key, &request_data).await {
+            Ok(permit) => permit,
+            Err(response) => return Ok(response),
+        };
+
+        let request_seq = self.request_seq.fetch_add(1, Ordering::Relaxed);
+        let request_id = format!("req_{}", request_seq);
+        let worker_index = match &self.autoscaler {
+            Some(autoscaler) => {
+                let (index, decisions) = autoscaler.route(
+                    &handler_key.name,
+                    &self.worker_loads(),
+                    current_worker,
+                    request_seq,
+                );
+                self.apply_scale_decisions(decisions);
+                index
+            }
+            None => self.select_worker_with_exclude(&handler_key, current_worker, request_seq),
+        };
+        let handler_name = self.autoscaler.as_ref().map(|_| handler_key.name.clone());
         let enqueued_at = Instant::now();
 
         let (response_tx, response_rx) = oneshot::channel();
@@ -822,14 +923,106 @@ impl IsolatePool {
             .queued_requests
             .fetch_add(1, Ordering::Relaxed);
 
-        self.workers[worker_index]
+        let sent = self.workers[worker_index]
             .request_tx
             .send(request)
-            .map_err(|_| "Worker thread dead".to_string())?;
+            .map_err(|_| "Worker thread dead".to_string());
+        let response = match sent {
+            Ok(()) => response_rx
+                .await
+                .map_err(|_| "Worker dropped response channel".to_string()),
+            Err(err) => Err(err),
+        };
 
-        response_rx
-            .await
-            .map_err(|_| "Worker dropped response channel".to_string())
+        if let (Some(autoscaler), Some(name)) = (&self.autoscaler, handler_name) {
+            autoscaler.complete(&name, enqueued_at.elapsed());
+        }
+        response
+    }
+
+    /// Apply admission control to HTTP requests. Shed requests get a 503
+    /// response envelope with `Retry-After` in place of the handler result.
+    async fn admit(
+        &self,
+        handler_key: &HandlerKey,
+        request_data: &RequestData,
+    ) -> Result<Option<AdmissionPermit>, IsolateResponse> {
+        if request_data.mode != ExecutionMode::Request {
+            return Ok(None);
+        }
+        if self.admission.classify(request_data.request_parts.as_ref()) == RequestPriority::Critical
+        {
+            self.metrics
+                .admission_bypassed
+                .fetch_add(1, Ordering::Relaxed);
+            return Ok(None);
+        }
+
+        let queue_timeout = Duration::from_millis(self.config.queue_timeout_ms);
+        match self.admission.admit(&handler_key.name, queue_timeout).await {
+            Ok(permit) => {
+                if permit.waited {
+                    self.metrics
+                        .admission_queued
+                        .fetch_add(1, Ordering::Relaxed);
+                }
+                Ok(Some(permit))
+            }
+            Err(rejection) => {
+                let counter = match rejection.reason {
+                    RejectReason::QueueFull => &self.metrics.shed_queue_full,
+                    RejectReason::QueueTimeout => &self.metrics.shed_queue_timeout,
+                };
+                counter.fetch_add(1, Ordering::Relaxed);
+                tracing::debug!(
+                    "Shed request for {} ({:?}), retry after {}s",
+                    handler_key.name,
+                    rejection.reason,
+                    rejection.retry_after_secs
+                );
+                Err(IsolateResponse {
+                    success: true,
+                    error: None,
+                    result: Some(rejection.response_value()),
+                    warm_time_us: 0,
+                    total_time_us: 0,
+                    cache_hit: false,
+                })
+            }
+        }
+    }
+
+    fn worker_loads(&self) -> Vec<usize> {
+        self.workers
+            .iter()
+            .map(|worker| {
+                worker.load.queued_requests.load(Ordering::Relaxed)
+                    + worker.load.active_requests.load(Ordering::Relaxed)
+            })
+            .collect()
+    }
+
+    /// Release isolates on workers the autoscaler scaled a handler down from.
+    /// Scale-ups need no message: the new replica warms its isolate on the
+    /// first routed request, from a spare when one is available.
+    fn apply_scale_decisions(&self, decisions: Vec<ScaleDecision>) {
+        for decision in decisions {
+            tracing::debug!(
+                "Autoscale {:?} {} on worker {} ({} replicas): {}",
+                decision.action,
+                decision.handler_name,
+                decision.worker_id,
+                decision.replicas,
+                decision.reason
+            );
+            if decision.action == ScaleAction::Down {
+                if let Some(worker) = self.workers.get(decision.worker_id) {
+                    let _ = worker.control_tx.send(WorkerControl::ReleaseIsolate {
+                        key: HandlerKey::new(decision.handler_name),
+                    });
+                }
+            }
+        }
     }
 
     /// Hash handler key to worker index
@@ -842,7 +1035,12 @@ impl IsolatePool {
         (hasher.finish() as usize) % self.workers.len()
     }
 
-    fn select_worker_with_exclude(&self, key: &HandlerKey, exclude: Option<usize>) -> usize {
+    fn select_worker_with_exclude(
+        &self,
+        key: &HandlerKey,
+        exclude: Option<usize>,
+        seed: u64,
+    ) -> usize {
         if self.workers.len() <= 1 {
             return 0;
         }
@@ -871,14 +1069,15 @@ impl IsolatePool {
                 }
                 best.map(|(index, _)| index).unwrap_or(0)
             }
+            SchedulerStrategy::PowerOfTwoChoices => {
+                let candidates: Vec<usize> = (0..self.workers.len())
+                    .filter(|index| Some(*index) != exclude)
+                    .collect();
+                power_of_two_choices(&candidates, &self.worker_loads(), seed)
+            }
         }
     }
 
-    fn next_request_id(&self) -> String {
-        let id = self.request_seq.fetch_add(1, Ordering::Relaxed);
-        format!("req_{}", id)
-    }
-
     /// Get full pool stats as JSON (for /stats endpoint)
     pub fn stats(&self) -> serde_json::Value {
         serde_json::json!({
@@ -890,11 +1089,23 @@ impl IsolatePool {
                 "metrics_enabled": self.config.enable_metrics,
                 "code_cache_enabled": self.config.enable_code_cache,
                 "request_timeout_ms": self.config.request_timeout_ms,
+                "cpu_budget_ms": self.config.cpu_budget_ms,
+                "handler_budgets": self.config.handler_budgets.len(),
                 "queue_timeout_ms": self.config.queue_timeout_ms,
+                "max_queue_per_handler": self.config.admission.max_queue,
+                "max_concurrency_per_handler": self.config.admission.max_concurrency,
                 "scheduler": match self.config.scheduler_strategy {
                     SchedulerStrategy::ConsistentHash => "consistent_hash",
                     SchedulerStrategy::LeastLoaded => "least_loaded",
+                    SchedulerStrategy::PowerOfTwoChoices => "power_of_two_choices",
                 },
+                "autoscale": self.config.autoscale.enabled,
+                "warm_spares": self.config.autoscale.warm_spares,
+                "inspector": self
+                    .config
+                    .inspector
+                    .as_ref()
+                    .map(|inspector| inspector.addr.to_string()),
                 "introspect_profiling": self.introspect_profiling.load(Ordering::Relaxed)
             },
             "metrics": self.metrics.to_json()
@@ -1040,12 +1251,22 @@ impl IsolatePool {
                         0.0
                     };
 
+                    let scale = self
+                        .autoscaler
+                        .as_ref()
+                        .map(|autoscaler| autoscaler.worker_stats(worker_id))
+                        .unwrap_or_default();
+
                     stats.push(WorkerStats {
                         worker_id,
                         active_isolates,
+                        spare_isolates: worker.load.spare_isolates.load(Ordering::Relaxed),
                         queued_requests: worker.load.queued_requests.load(Ordering::Relaxed),
                         total_requests,
                         avg_latency_ms: avg_latency,
+                        scale_ups: scale.scale_ups,
+                        scale_downs: scale.scale_downs,
+                        recent_scale_decisions: scale.recent_decisions,
                     });
                 }
             }
@@ -1111,9 +1332,15 @@ pub enum SortBy {
 pub struct WorkerStats {
     pub worker_id: usize,
     pub active_isolates: usize,
+    /// Pre-created isolates waiting to be assigned to a handler
+    pub spare_isolates: usize,
     pub queued_requests: usize,
     pub total_requests: u64,
     pub avg_latency_ms: f64,
+    /// Autoscaler decisions that added or released a replica on this worker
+    pub scale_ups: u64,
+    pub scale_downs: u64,
+    pub recent_scale_decisions: Vec<ScaleDecision>,
 }
 
 /// State of a request for observability
@@ -1152,6 +1379,12 @@ pub struct RequestTrace {
     pub heap_delta_bytes: i64,
     pub response_status: Option<u16>,
     pub response_body: Option<String>,
+    /// JS stack captured when the request was terminated for exceeding its budget
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub termination_stack: Vec<String>,
+    /// The same stack narrowed to `.phpx` frames, remapped to PHP source
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub php_stack: Vec<String>,
 }
 
 const REQUEST_HISTORY_LIMIT: usize = 200;
@@ -1180,6 +1413,17 @@ struct WarmIsolate {
     op_metrics: Option<Rc<OpTimingTracker>>,
     handler_loaded: bool,
     entry_specifier: Option<ModuleSpecifier>,
+    /// Lists the isolate as a debug target while it is alive
+    _inspector: Option<InspectorRegistration>,
+}
+
+/// A runtime created ahead of demand so a new replica skips isolate startup
+struct SpareIsolate {
+    handler_entry: Option<String>,
+    runtime: JsRuntime,
+    op_metrics: Option<Rc<OpTimingTracker>>,
+    entry_specifier: Option<ModuleSpecifier>,
+    inspector: Option<InspectorRegistration>,
 }
 
 // ========== Worker Thread ==========
@@ -1194,16 +1438,21 @@ struct WorkerThread {
     load: Arc<WorkerLoad>,
     isolates: HashMap<HandlerKey, WarmIsolate>,
     lru_order: Vec<HandlerKey>, // Front = oldest, back = newest
+    spares: Vec<SpareIsolate>,
+    /// Entry of the most recently created isolate; spares are warmed for it
+    spare_entry: Option<Option<String>>,
     code_cache: HashMap<u64, Vec<u8>>,
     extensions_provider: Arc<dyn Fn() -> Vec<Extension> + Send + Sync>,
     request_history: VecDeque<RequestTrace>,
     deka_args: serde_json::Value,
+    watchdog: BudgetWatchdog,
+    inspector: Option<Arc<InspectorServer>>,
 }
 
 enum ExecutionOutcome {
     Ok(serde_json::Value),
     Err(String),
-    TimedOut,
+    BudgetExceeded(BudgetViolation),
 }
 
 impl WorkerThread {
@@ -1215,6 +1464,7 @@ impl WorkerThread {
         load: Arc<WorkerLoad>,
         extensions_provider: Arc<dyn Fn() -> Vec<Extension> + Send + Sync>,
         introspect_profiling: Arc<AtomicBool>,
+        inspector: Option<Arc<InspectorServer>>,
     ) -> Self {
         let deka_args = std::env::var("DEKA_ARGS").unwrap_or_else(|_| "[]".to_string());
         let deka_args = serde_json::from_str(&deka_args).unwrap_or_else(|_| serde_json::json!([]));
@@ -1227,10 +1477,14 @@ impl WorkerThread {
             load,
             isolates: HashMap::new(),
             lru_order: Vec::new(),
+            spares: Vec::new(),
+            spare_entry: None,
             code_cache: HashMap::new(),
             extensions_provider,
             request_history: VecDeque::new(),
             deka_args,
+            watchdog: BudgetWatchdog::spawn(worker_id),
+            inspector,
         }
     }
 
@@ -1252,6 +1506,7 @@ impl WorkerThread {
         tracing::debug!("Worker {} started", self.worker_id);
 
         rt.block_on(async {
+            let mut inspector_tick = tokio::time::interval(INSPECTOR_POLL_INTERVAL);
             loop {
                 tokio::select! {
                     // Handle regular requests
@@ -1269,11 +1524,16 @@ impl WorkerThread {
                             let response = self.process_request(&request).await;
                             let _ = request.response_tx.send(response);
                         }
+                        self.replenish_spare();
                     }
                     // Handle control commands
                     Some(cmd) = ctrl_rx.recv() => {
                         self.handle_control(cmd);
                     }
+                    // Keep debugger sessions responsive between requests
+                    _ = inspector_tick.tick(), if self.inspector.is_some() => {
+                        self.poll_inspected_isolates();
+                    }
                     // Both channels closed - shutdown
                     else => break,
                 }
@@ -1283,6 +1543,19 @@ impl WorkerThread {
         tracing::debug!("Worker {} shutting down", self.worker_id);
     }
 
+    /// Idle isolates are not polled, so DevTools messages would otherwise
+    /// queue until the next request arrives.
+    fn poll_inspected_isolates(&mut self) {
+        let mut cx = std::task::Context::from_waker(std::task::Waker::noop());
+        for isolate in self.isolates.values_mut() {
+            if isolate.handler_loaded {
+                let _ = isolate
+                    .runtime
+                    .poll_event_loop(&mut cx, deno_core::PollEventLoopOptions::default());
+            }
+        }
+    }
+
     /// Handle control commands
     fn handle_control(&mut self, cmd: WorkerControl) {
         match cmd {
@@ -1291,6 +1564,8 @@ impl WorkerThread {
                 self.isolates.clear();
                 self.lru_order.clear();
                 self.code_cache.clear();
+                self.spares.clear();
+                self.load.spare_isolates.store(0, Ordering::Relaxed);
                 tracing::debug!("Worker {} evicted {} isolates", self.worker_id, count);
                 let _ = response_tx.send(count);
             }
@@ -1338,6 +1613,16 @@ impl WorkerThread {
 
                 let _ = response_tx.send(all_metrics);
             }
+            WorkerControl::ReleaseIsolate { key } => {
+                if self.isolates.remove(&key).is_some() {
+                    self.lru_order.retain(|k| k != &key);
+                    tracing::debug!(
+                        "Worker {} released isolate after scale down: {}",
+                        self.worker_id,
+                        key.name
+                    );
+                }
+            }
             WorkerControl::GetRecentRequests { response_tx } => {
                 let history: Vec<RequestTrace> = self.request_history.iter().cloned().collect();
                 let _ = response_tx.send(history);
@@ -1383,6 +1668,8 @@ impl WorkerThread {
                     heap_delta_bytes: 0,
                     response_status: None,
                     response_body: None,
+                    termination_stack: Vec::new(),
+                    php_stack: Vec::new(),
                 });
                 return IsolateResponse {
                     success: false,
@@ -1479,13 +1766,17 @@ impl WorkerThread {
                 heap_delta_bytes: 0,
                 response_status: None,
                 response_body: None,
+                termination_stack: Vec::new(),
+                php_stack: Vec::new(),
             });
         }
 
         self.load.active_requests.fetch_add(1, Ordering::Relaxed);
 
         // Execute in the isolate
+        runtime_core::security_audit::set_current_request(Some(request.request_id.clone()));
         let (exec_result, exec_profile) = self.execute_in_isolate(&key, &request).await;
+        runtime_core::security_audit::set_current_request(None);
 
         let total_time = start.elapsed();
         self.load.active_requests.fetch_sub(1, Ordering::Relaxed);
@@ -1532,6 +1823,8 @@ impl WorkerThread {
                 summary
             );
         }
+        let mut termination_stack = Vec::new();
+        let mut php_stack = Vec::new();
         let (response, state, response_status, response_body) = match exec_result {
             ExecutionOutcome::Ok(result) => (
                 IsolateResponse {
@@ -1546,20 +1839,34 @@ impl WorkerThread {
                 None,
                 None,
             ),
-            ExecutionOutcome::TimedOut => {
+            ExecutionOutcome::BudgetExceeded(violation) => {
+                // Terminated isolates stay unusable; drop it so the next request gets a fresh one.
                 self.isolates.remove(&key);
                 self.lru_order.retain(|k| k != &key);
+                tracing::warn!(
+                    "Worker {} handler {} terminated: {}",
+                    self.worker_id,
+                    request.handler_key.name,
+                    violation.message_with_stack()
+                );
+                let error = if self.config.expose_stack_traces {
+                    validation::remap_stack_trace(&violation.message_with_stack())
+                } else {
+                    violation.message()
+                };
+                termination_stack = violation.stack.clone();
+                php_stack = violation.php_stack.clone();
                 (
                     IsolateResponse {
                         success: false,
-                        error: Some("Handler execution timed out".to_string()),
+                        error: Some(error),
                         result: None,
                         warm_time_us: warm_time.as_micros() as u64,
                         total_time_us: total_time.as_micros() as u64,
                         cache_hit,
                     },
                     RequestState::Failed {
-                        error: "timeout".to_string(),
+                        error: violation.message(),
                         duration_ms,
                     },
                     None,
@@ -1567,8 +1874,10 @@ impl WorkerThread {
                 )
             }
             ExecutionOutcome::Err(e) => {
-                let error =
-                    validation::analyze_runtime_error(&e, &request.request_data.handler_code);
+                let error = validation::analyze_runtime_error(
+                    &validation::remap_stack_trace(&e),
+                    &request.request_data.handler_code,
+                );
                 (
                     IsolateResponse {
                         success: false,
@@ -1610,6 +1919,9 @@ impl WorkerThread {
                 response_status,
                 response_body,
             );
+            if !termination_stack.is_empty() {
+                self.attach_termination_stack(&request.request_id, termination_stack, php_stack);
+            }
         }
 
         if perf_profile_enabled() {
@@ -1696,6 +2008,22 @@ impl WorkerThread {
         }
     }
 
+    fn attach_termination_stack(
+        &mut self,
+        request_id: &str,
+        stack: Vec<String>,
+        php_stack: Vec<String>,
+    ) {
+        if let Some(entry) = self
+            .request_history
+            .iter_mut()
+            .find(|entry| entry.id == request_id)
+        {
+            entry.termination_stack = stack;
+            entry.php_stack = php_stack;
+        }
+    }
+
     /// Ensure we have a valid isolate for this handler, creating if needed
     async fn ensure_isolate(
         &mut self,
@@ -1761,14 +2089,70 @@ impl WorkerThread {
         Ok((false, start.elapsed()))
     }
 
-    /// Create a new warm isolate
+    /// Create a new warm isolate, taking a spare runtime when one matches the entry
     fn create_warm_isolate(
-        &self,
+        &mut self,
         source_hash: u64,
         handler_entry: Option<&str>,
     ) -> Result<WarmIsolate, String> {
+        self.spare_entry = Some(handler_entry.map(str::to_string));
+        let spare = match self
+            .spares
+            .iter()
+            .position(|spare| spare.handler_entry.as_deref() == handler_entry)
+        {
+            Some(index) => {
+                self.load.spare_isolates.fetch_sub(1, Ordering::Relaxed);
+                self.spares.swap_remove(index)
+            }
+            None => self.create_runtime(handler_entry)?,
+        };
+
+        Ok(WarmIsolate {
+            isolate_id: format!("isolate_{}", nanoid!(10, &ID_ALPHABET)),
+            runtime: spare.runtime,
+            last_used: Instant::now(),
+            request_count: 1,
+            active_requests: 0,
+            source_hash,
+            bootstrapped: false, // Will be bootstrapped on first request
+            total_cpu_time: Duration::ZERO,
+            created_at: Instant::now(),
+            heap_used_bytes: 0,
+            heap_limit_bytes: 0,
+            state: IsolateState::Idle,
+            op_metrics: spare.op_metrics,
+            handler_loaded: false,
+            entry_specifier: spare.entry_specifier,
+            _inspector: spare.inspector,
+        })
+    }
+
+    /// Top the spare pool up by one runtime when the worker has nothing queued
+    fn replenish_spare(&mut self) {
+        let target = self.config.autoscale.warm_spares;
+        if target == 0 || self.spares.len() >= target {
+            return;
+        }
+        if self.load.queued_requests.load(Ordering::Relaxed) > 0 {
+            return;
+        }
+        let Some(handler_entry) = self.spare_entry.clone() else {
+            return;
+        };
+        match self.create_runtime(handler_entry.as_deref()) {
+            Ok(spare) => {
+                self.spares.push(spare);
+                self.load.spare_isolates.fetch_add(1, Ordering::Relaxed);
+            }
+            Err(err) => {
+                tracing::debug!("Worker {} failed to warm spare: {}", self.worker_id, err);
+            }
+        }
+    }
+
+    fn create_runtime(&self, handler_entry: Option<&str>) -> Result<SpareIsolate, String> {
         let extensions = (self.extensions_provider)();
-        let isolate_id = format!("isolate_{}", nanoid!(10, &ID_ALPHABET));
 
         let op_metrics = if self.introspect_profiling.load(Ordering::Relaxed) {
             Some(Rc::new(OpTimingTracker::default()))
@@ -1781,8 +2165,9 @@ impl WorkerThread {
             let wrapper_path = entry_wrapper_path(&project_root);
             let wrapper_specifier = ModuleSpecifier::from_file_path(&wrapper_path)
                 .map_err(|_| "invalid entry wrapper path".to_string())?;
-            let loader =
-                PhpxEsmLoader::new(project_root, entry_path).map_err(|err| err.to_string())?;
+            let loader = PhpxEsmLoader::new(project_root, entry_path)
+                .map_err(|err| err.to_string())?
+                .with_source_maps(self.inspector.is_some() || self.config.expose_stack_traces);
             let loader: Rc<dyn deno_core::ModuleLoader> = Rc::new(loader);
             (Some(loader), Some(wrapper_specifier))
         } else {
@@ -1795,25 +2180,25 @@ impl WorkerThread {
                 .as_ref()
                 .map(|metrics| metrics.clone().op_metrics_factory_fn()),
             module_loader,
+            inspector: self.inspector.is_some(),
+            is_main: self.inspector.is_some(),
             ..Default::default()
         });
+        let inspector = self.inspector.as_ref().map(|server| {
+            let title = handler_entry.unwrap_or("handler");
+            let url = handler_entry
+                .and_then(|entry| ModuleSpecifier::from_file_path(entry).ok())
+                .map(|url| url.to_string())
+                .unwrap_or_else(|| title.to_string());
+            server.register(title, &url, runtime.inspector().get_session_sender())
+        });
 
-        Ok(WarmIsolate {
-            isolate_id,
+        Ok(SpareIsolate {
+            handler_entry: handler_entry.map(str::to_string),
             runtime,
-            last_used: Instant::now(),
-            request_count: 1,
-            active_requests: 0,
-            source_hash,
-            bootstrapped: false, // Will be bootstrapped on first request
-            total_cpu_time: Duration::ZERO,
-            created_at: Instant::now(),
-            heap_used_bytes: 0,
-            heap_limit_bytes: 0,
-            state: IsolateState::Idle,
             op_metrics,
-            handler_loaded: false,
             entry_specifier,
+            inspector,
         })
     }
 
@@ -1825,7 +2210,12 @@ impl WorkerThread {
     ) -> (ExecutionOutcome, ExecutionProfile) {
         // Get mutable reference to isolate
         let use_code_cache = self.config.enable_code_cache;
-        let (isolates, code_cache) = (&mut self.isolates, &mut self.code_cache);
+        let (isolates, code_cache, watchdog, inspector) = (
+            &mut self.isolates,
+            &mut self.code_cache,
+            &self.watchdog,
+            &self.inspector,
+        );
         let isolate = isolates
             .get_mut(key)
             .ok_or_else(|| "Isolate not found".to_string());
@@ -2194,6 +2584,90 @@ impl WorkerThread {
                     };
                 }
 
+                if (typeof globalThis.__dekaScopeCall !== 'function') {
+                    // Compiled package modules route exports, top-level code,
+                    // methods and closures through here. Scope tokens never leave
+                    // this closure, so package code cannot exit its own scope.
+                    const ops = (Deno && Deno.core && Deno.core.ops) ? Deno.core.ops : {};
+                    const scopeEnter = ops.op_php_security_scope_enter;
+                    const scopeExit = ops.op_php_security_scope_exit;
+                    const scopeSuspend = ops.op_php_security_scope_suspend;
+                    const scopeResume = ops.op_php_security_scope_resume;
+                    const scopeOps = [scopeEnter, scopeExit, scopeSuspend, scopeResume]
+                        .every((op) => typeof op === 'function');
+                    const scopeCall = (pkg, fn, self, args) => {
+                        if (!scopeOps) {
+                            return fn.apply(self, args);
+                        }
+                        const wrap = (value) => typeof value === 'function'
+                            ? function (...inner) { return scopeCall(pkg, value, this, inner); }
+                            : value;
+                        const token = scopeEnter(String(pkg));
+                        let result;
+                        try {
+                            result = fn.apply(self, args);
+                        } finally {
+                            scopeExit(token);
+                        }
+                        if (result && typeof result.then === 'function') {
+                            // Async bodies hold a scope of their own across awaits.
+                            return Promise.resolve(result).then(wrap);
+                        }
+                        return wrap(result);
+                    };
+                    // Each async package call owns one token: it is parked at every
+                    // await and pushed back when that call resumes, so code running
+                    // in between is never attributed to the package.
+                    const callTokens = new WeakMap();
+                    const scopeBegin = (pkg) => {
+                        const scope = Object.freeze({});
+                        if (scopeOps) {
+                            callTokens.set(scope, scopeEnter(String(pkg)));
+                        }
+                        return scope;
+                    };
+                    const scopeAwait = (scope, value) => {
+                        const token = callTokens.get(scope);
+                        if (token !== undefined) {
+                            scopeSuspend(token);
+                        }
+                        return Promise.resolve(value).then(
+                            (result) => ({ result }),
+                            (error) => ({ error, failed: true }),
+                        );
+                    };
+                    const scopeResumed = (scope, outcome) => {
+                        const token = callTokens.get(scope);
+                        if (token !== undefined) {
+                            scopeResume(token);
+                        }
+                        if (outcome.failed) {
+                            throw outcome.error;
+                        }
+                        return outcome.result;
+                    };
+                    const scopeEnd = (scope) => {
+                        const token = callTokens.get(scope);
+                        if (token !== undefined) {
+                            callTokens.delete(scope);
+                            scopeExit(token);
+                        }
+                    };
+                    for (const [name, value] of [
+                        ['__dekaScopeCall', scopeCall],
+                        ['__dekaScopeBegin', scopeBegin],
+                        ['__dekaScopeAwait', scopeAwait],
+                        ['__dekaScopeResumed', scopeResumed],
+                        ['__dekaScopeEnd', scopeEnd],
+                    ]) {
+                        Object.defineProperty(globalThis, name, {
+                            value,
+                            writable: false,
+                            configurable: false,
+                        });
+                    }
+                }
+
                 if (typeof globalThis.__dekaRuntime !== 'object') {
                     globalThis.__dekaRuntime = {
                         executePhpx: async function(_source, file, _props) {
@@ -2628,6 +3102,16 @@ impl WorkerThread {
                         );
                     }
                 };
+                if inspector
+                    .as_ref()
+                    .is_some_and(|server| server.take_break_on_start())
+                {
+                    deka_stdio::log("inspect", "Waiting for the debugger to attach...");
+                    isolate
+                        .runtime
+                        .inspector()
+                        .wait_for_session_and_break_on_next_statement();
+                }
                 let eval = isolate.runtime.mod_evaluate(module_id);
                 if let Err(err) = isolate
                     .runtime
@@ -2694,20 +3178,14 @@ impl WorkerThread {
 
         // Track CPU time for this execution
         let cpu_start = get_thread_cpu_time();
-        let timeout_ms = self.config.request_timeout_ms;
-        let timeout_flag = Arc::new(AtomicUsize::new(0));
-        let timeout_flag_handle = Arc::clone(&timeout_flag);
+        let (cpu_budget_ms, wall_budget_ms) = ExecutionBudget::resolve(
+            &self.config.handler_budgets,
+            &key.name,
+            self.config.cpu_budget_ms,
+            self.config.request_timeout_ms,
+        );
         let isolate_handle = isolate.runtime.v8_isolate().thread_safe_handle();
-
-        let watchdog = if timeout_ms > 0 {
-            Some(tokio::spawn(async move {
-                tokio::time::sleep(Duration::from_millis(timeout_ms)).await;
-                timeout_flag_handle.store(1, Ordering::Relaxed);
-                isolate_handle.terminate_execution();
-            }))
-        } else {
-            None
-        };
+        let budget = watchdog.arm(isolate_handle, cpu_budget_ms, wall_budget_ms);
 
         let exec_start = Instant::now();
         let mut needs_event_loop = false;
@@ -2732,9 +3210,7 @@ impl WorkerThread {
         let result = match result {
             Ok(value) => value,
             Err(err) => {
-                if let Some(watchdog) = watchdog {
-                    watchdog.abort();
-                }
+                let violation = budget.and_then(|budget| budget.finish());
                 if let Some(code) = parse_exit_code(&err) {
                     isolate.active_requests = 0;
                     isolate.state = IsolateState::Idle;
@@ -2752,6 +3228,9 @@ impl WorkerThread {
                     0,
                     0,
                 );
+                if let Some(violation) = violation {
+                    return (ExecutionOutcome::BudgetExceeded(violation), profile);
+                }
                 return (
                     ExecutionOutcome::Err(format!("Handler execution failed: {}", err)),
                     profile,
@@ -2782,14 +3261,24 @@ impl WorkerThread {
 
         let event_loop_ms = if needs_event_loop {
             let event_start = Instant::now();
-            if let Err(err) = isolate
+            let event_loop = isolate
                 .runtime
-                .run_event_loop(deno_core::PollEventLoopOptions::default())
-                .await
-            {
-                if let Some(watchdog) = watchdog {
-                    watchdog.abort();
-                }
+                .run_event_loop(deno_core::PollEventLoopOptions::default());
+            // Terminating V8 cannot interrupt a pending async op, so bound the loop itself.
+            let event_result = match budget.as_ref().and_then(|budget| budget.remaining_wall()) {
+                Some(remaining) => match tokio::time::timeout(remaining, event_loop).await {
+                    Ok(result) => result,
+                    Err(_) => {
+                        if let Some(budget) = budget.as_ref() {
+                            budget.expire_wall();
+                        }
+                        Ok(())
+                    }
+                },
+                None => event_loop.await,
+            };
+            if let Err(err) = event_result {
+                let violation = budget.and_then(|budget| budget.finish());
                 isolate.active_requests = 0;
                 isolate.state = IsolateState::Idle;
                 let profile = finalize_profile(
@@ -2799,6 +3288,9 @@ impl WorkerThread {
                     event_start.elapsed().as_millis() as u64,
                     0,
                 );
+                if let Some(violation) = violation {
+                    return (ExecutionOutcome::BudgetExceeded(violation), profile);
+                }
                 return (
                     ExecutionOutcome::Err(format!("Event loop failed: {}", err)),
                     profile,
@@ -2809,9 +3301,7 @@ impl WorkerThread {
             0
         };
 
-        if let Some(watchdog) = watchdog {
-            watchdog.abort();
-        }
+        let violation = budget.and_then(|budget| budget.finish());
 
         // Calculate CPU time consumed
         let cpu_elapsed = get_thread_cpu_time() - cpu_start;
@@ -2881,13 +3371,13 @@ impl WorkerThread {
 
         isolate.active_requests = 0;
         isolate.state = IsolateState::Idle;
-        let outcome = if timeout_flag.load(Ordering::Relaxed) == 1 {
+        let outcome = if let Some(violation) = violation {
             isolate.state = IsolateState::Stuck {
                 request_id: request.request_id.clone(),
                 started_at: Instant::now(),
                 timeout_triggered: true,
             };
-            ExecutionOutcome::TimedOut
+            ExecutionOutcome::BudgetExceeded(violation)
         } else {
             outcome
         };
@@ -1,7 +1,17 @@
+pub mod admission;
+pub mod autoscale;
+pub mod compile_cache;
+pub mod inspector;
 pub mod isolate_pool;
 pub mod esm_loader;
 pub mod validation;
+pub mod watchdog;
 
+pub use admission::{AdmissionConfig, AdmissionLimits, RejectReason, Rejection, RequestPriority};
+pub use autoscale::{AutoscaleConfig, ScaleAction, ScaleDecision};
+pub use compile_cache::{CachedModule, CompileCache};
+pub use inspector::{DEFAULT_INSPECTOR_ADDR, InspectorConfig};
 pub use isolate_pool::*;
 pub use esm_loader::*;
 pub use validation::*;
+pub use watchdog::{BudgetKind, BudgetViolation, ExecutionBudget};
@@ -1,5 +1,119 @@
 //! Beautiful error formatting for validation errors using deka-validation.
 
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, OnceLock};
+
+use phpx_js::SourceMap;
+
+/// Source maps kept for stack remapping; the oldest registration goes first.
+const MAX_PHPX_SOURCE_MAPS: usize = 512;
+
+/// Source maps of compiled `.phpx` modules, keyed by module specifier.
+static PHPX_SOURCE_MAPS: OnceLock<Mutex<SourceMapRegistry>> = OnceLock::new();
+
+fn phpx_source_maps() -> &'static Mutex<SourceMapRegistry> {
+    PHPX_SOURCE_MAPS.get_or_init(|| Mutex::new(SourceMapRegistry::default()))
+}
+
+#[derive(Default)]
+struct SourceMapRegistry {
+    maps: HashMap<String, SourceMap>,
+    /// Specifiers from oldest to newest registration.
+    order: VecDeque<String>,
+}
+
+impl SourceMapRegistry {
+    fn insert(&mut self, specifier: &str, map: SourceMap, capacity: usize) {
+        if self.maps.insert(specifier.to_string(), map).is_some() {
+            self.order.retain(|existing| existing != specifier);
+        }
+        self.order.push_back(specifier.to_string());
+        while self.order.len() > capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.maps.remove(&oldest);
+            }
+        }
+    }
+}
+
+/// Remember the source map of a compiled `.phpx` module so runtime errors can
+/// be reported against the original source.
+pub fn register_source_map(specifier: &str, map: SourceMap) {
+    if let Ok(mut registry) = phpx_source_maps().lock() {
+        registry.insert(specifier, map, MAX_PHPX_SOURCE_MAPS);
+    }
+}
+
+/// Rewrite stack locations such as `file:///app/index.phpx:120:5`, which point
+/// at the generated JS, to the `.phpx` line and column they were compiled from.
+pub fn remap_stack_trace(message: &str) -> String {
+    let Ok(registry) = phpx_source_maps().lock() else {
+        return message.to_string();
+    };
+    let maps = &registry.maps;
+    if maps.is_empty() {
+        return message.to_string();
+    }
+
+    let mut out = String::with_capacity(message.len());
+    let mut rest = message;
+    while let Some(idx) = rest.find(".phpx:") {
+        let name_end = idx + ".phpx".len();
+        let name_start = rest[..name_end]
+            .rfind(|c: char| c.is_whitespace() || c == '(')
+            .map(|pos| pos + 1)
+            .unwrap_or(0);
+        let remapped = parse_position(&rest[name_end + 1..]).and_then(|(line, col, len)| {
+            let map = maps.get(&rest[name_start..name_end])?;
+            let mapping = map.lookup_nearest(line.checked_sub(1)?, col.saturating_sub(1))?;
+            Some((map, mapping, len))
+        });
+        match remapped {
+            Some((map, mapping, len)) => {
+                out.push_str(&rest[..name_start]);
+                out.push_str(&format!(
+                    "{}:{}:{}",
+                    map.source,
+                    mapping.source_line + 1,
+                    mapping.source_column + 1
+                ));
+                rest = &rest[name_end + 1 + len..];
+            }
+            None => {
+                out.push_str(&rest[..name_end]);
+                rest = &rest[name_end..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// PHP-level view of a captured JS stack: the frames that fall inside compiled
+/// `.phpx` modules, remapped to their source positions.
+pub fn phpx_stack_frames(frames: &[String]) -> Vec<String> {
+    frames
+        .iter()
+        .filter_map(|frame| {
+            let remapped = remap_stack_trace(frame);
+            (remapped != *frame).then_some(remapped)
+        })
+        .collect()
+}
+
+/// Parse a `line:col` prefix, returning both numbers and the bytes consumed.
+fn parse_position(text: &str) -> Option<(usize, usize, usize)> {
+    let line_len = text.bytes().take_while(u8::is_ascii_digit).count();
+    let rest = text[line_len..].strip_prefix(':')?;
+    let col_len = rest.bytes().take_while(u8::is_ascii_digit).count();
+    if line_len == 0 || col_len == 0 {
+        return None;
+    }
+    let line = text[..line_len].parse().ok()?;
+    let col = rest[..col_len].parse().ok()?;
+    Some((line, col, line_len + 1 + col_len))
+}
+
 /// Format a validation error with the shared Deka formatting.
 pub fn format_validation_error(
     source_code: &str,
@@ -54,6 +168,56 @@ const app = new Router()"#;
         assert!(error.contains("= help:"));
     }
 
+    #[test]
+    fn remaps_phpx_stack_frames_to_source_positions() {
+        let mut map = SourceMap::new("/app/remap_test.phpx.js", "/app/remap_test.phpx");
+        for (generated_line, source_line) in [(40, 0), (41, 1)] {
+            map.mappings.push(phpx_js::Mapping {
+                generated_line,
+                generated_column: 0,
+                source_line,
+                source_column: 0,
+            });
+        }
+        register_source_map("file:///app/remap_test.phpx", map);
+
+        let remapped = remap_stack_trace(
+            "ReferenceError: missing is not defined\n    at __phpx_main (file:///app/remap_test.phpx:42:9)\n    at file:///app/other.phpx:3:1",
+        );
+        assert!(remapped.contains("at __phpx_main (/app/remap_test.phpx:2:1)"));
+        assert!(remapped.contains("at file:///app/other.phpx:3:1"));
+    }
+
+    #[test]
+    fn source_map_registry_drops_the_oldest_registration() {
+        let mut registry = SourceMapRegistry::default();
+        for name in ["a", "b", "a", "c"] {
+            registry.insert(name, SourceMap::new(name, name), 2);
+        }
+        assert!(registry.maps.contains_key("a"));
+        assert!(registry.maps.contains_key("c"));
+        assert!(!registry.maps.contains_key("b"));
+        assert_eq!(registry.order, ["a", "c"]);
+    }
+
+    #[test]
+    fn php_stack_keeps_only_phpx_frames() {
+        let mut map = SourceMap::new("/app/php_stack.phpx.js", "/app/php_stack.phpx");
+        map.mappings.push(phpx_js::Mapping {
+            generated_line: 9,
+            generated_column: 0,
+            source_line: 2,
+            source_column: 4,
+        });
+        register_source_map("file:///app/php_stack.phpx", map);
+
+        let frames = phpx_stack_frames(&[
+            "spin (file:///app/php_stack.phpx:10:3)".to_string(),
+            "runHandler (ext:deka/handler.js:12:1)".to_string(),
+        ]);
+        assert_eq!(frames, vec!["spin (/app/php_stack.phpx:3:5)".to_string()]);
+    }
+
     #[test]
     fn test_error_at_start_of_file() {
         let source = "import fs from 'fs'";
@@ -3,6 +3,7 @@ pub mod error_formatter;
 pub mod handler_validator;
 
 pub use error_analysis::analyze_runtime_error;
+pub use error_formatter::{phpx_stack_frames, register_source_map, remap_stack_trace};
 pub use handler_validator::{
     PoolOptions, PoolWorkers, ServeOptions, extract_pool_options, extract_serve_options,
     validate_handler,
@@ -0,0 +1,528 @@
+//! Per-request execution budgets for pooled isolates.
+//!
+//! Each worker thread owns a watchdog thread. While a request is executing, the
+//! watchdog polls the worker's thread CPU clock and the wall clock. When either
+//! budget is exceeded it requests a V8 interrupt, records the JS stack from
+//! inside the interrupt (so we know where the handler was stuck), maps the
+//! frames of compiled `.phpx` modules back to PHP source positions, and then
+//! terminates execution. The tokio-based timeout this replaces could never fire
+//! for synchronous loops because it shared the worker's current-thread runtime.
+
+use std::collections::HashMap;
+use std::ffi::c_void;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use deno_core::v8;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+/// How long to wait for the interrupt to run before terminating without a stack.
+const INTERRUPT_GRACE: Duration = Duration::from_millis(100);
+const STACK_FRAME_LIMIT: usize = 32;
+
+/// CPU and wall-clock limits for a single request, in milliseconds.
+/// `None` falls back to the pool default; `Some(0)` disables the limit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize)]
+pub struct ExecutionBudget {
+    #[serde(default)]
+    pub cpu_ms: Option<u64>,
+    #[serde(default)]
+    pub wall_ms: Option<u64>,
+}
+
+impl ExecutionBudget {
+    /// Resolve per-handler overrides against pool-wide defaults.
+    pub fn resolve(
+        overrides: &HashMap<String, ExecutionBudget>,
+        handler_name: &str,
+        default_cpu_ms: u64,
+        default_wall_ms: u64,
+    ) -> (u64, u64) {
+        let handler = overrides.get(handler_name).copied().unwrap_or_default();
+        (
+            handler.cpu_ms.unwrap_or(default_cpu_ms),
+            handler.wall_ms.unwrap_or(default_wall_ms),
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetKind {
+    Cpu,
+    Wall,
+}
+
+/// Details of a request terminated for exceeding its budget.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct BudgetViolation {
+    pub kind: BudgetKind,
+    pub limit_ms: u64,
+    /// Formatted JS frames at the point of termination, innermost first.
+    pub stack: Vec<String>,
+    /// Frames of compiled `.phpx` modules, remapped to their PHP source.
+    pub php_stack: Vec<String>,
+}
+
+impl BudgetViolation {
+    pub fn message(&self) -> String {
+        match self.kind {
+            BudgetKind::Cpu => format!("Handler exceeded CPU budget of {}ms", self.limit_ms),
+            BudgetKind::Wall => {
+                format!("Handler exceeded wall-clock budget of {}ms", self.limit_ms)
+            }
+        }
+    }
+
+    /// Message with the captured stack appended, for dev-mode error responses.
+    pub fn message_with_stack(&self) -> String {
+        let mut message = self.message();
+        if !self.php_stack.is_empty() {
+            message.push_str("\n  PHP stack:");
+            for frame in &self.php_stack {
+                message.push_str("\n    at ");
+                message.push_str(frame);
+            }
+            message.push_str("\n  JS stack:");
+        }
+        if self.stack.is_empty() {
+            message.push_str("\n    (no JavaScript frames: awaiting an async operation)");
+        }
+        for frame in &self.stack {
+            message.push_str("\n    at ");
+            message.push_str(frame);
+        }
+        message
+    }
+}
+
+// ========== Thread CPU Clock ==========
+
+/// Handle to another thread's CPU clock, readable from the watchdog thread.
+#[cfg(target_os = "linux")]
+#[derive(Clone, Copy)]
+struct ThreadCpuClock(libc::clockid_t);
+
+#[cfg(target_os = "linux")]
+impl ThreadCpuClock {
+    fn current() -> Option<Self> {
+        let mut clock_id: libc::clockid_t = 0;
+        let rc = unsafe { libc::pthread_getcpuclockid(libc::pthread_self(), &mut clock_id) };
+        (rc == 0).then_some(Self(clock_id))
+    }
+
+    fn elapsed(&self) -> Duration {
+        let mut ts = libc::timespec {
+            tv_sec: 0,
+            tv_nsec: 0,
+        };
+        unsafe {
+            libc::clock_gettime(self.0, &mut ts);
+        }
+        Duration::new(ts.tv_sec as u64, ts.tv_nsec as u32)
+    }
+}
+
+#[cfg(target_os = "macos")]
+#[derive(Clone, Copy)]
+struct ThreadCpuClock(mach2::mach_types::thread_act_t);
+
+#[cfg(target_os = "macos")]
+impl ThreadCpuClock {
+    fn current() -> Option<Self> {
+        Some(Self(unsafe { mach2::mach_init::mach_thread_self() }))
+    }
+
+    fn elapsed(&self) -> Duration {
+        use libc::{THREAD_BASIC_INFO, thread_basic_info, thread_info};
+
+        unsafe {
+            let mut info = std::mem::zeroed::<thread_basic_info>();
+            let mut count = (std::mem::size_of::<thread_basic_info>()
+                / std::mem::size_of::<libc::c_int>()) as u32;
+            let kr = thread_info(
+                self.0,
+                THREAD_BASIC_INFO as u32,
+                &mut info as *mut _ as *mut _,
+                &mut count,
+            );
+            if kr != 0 {
+                return Duration::ZERO;
+            }
+            let user = Duration::new(
+                info.user_time.seconds as u64,
+                info.user_time.microseconds as u32 * 1000,
+            );
+            let sys = Duration::new(
+                info.system_time.seconds as u64,
+                info.system_time.microseconds as u32 * 1000,
+            );
+            user + sys
+        }
+    }
+}
+
+/// Fallback for unsupported platforms: CPU budgets are not enforced.
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+#[derive(Clone, Copy)]
+struct ThreadCpuClock;
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+impl ThreadCpuClock {
+    fn current() -> Option<Self> {
+        None
+    }
+
+    fn elapsed(&self) -> Duration {
+        Duration::ZERO
+    }
+}
+
+// ========== Watchdog ==========
+
+/// State for one armed request, shared with the watchdog thread and the
+/// V8 interrupt callback.
+struct WatchTicket {
+    isolate: v8::IsolateHandle,
+    started_at: Instant,
+    cpu_start: Duration,
+    cpu_ms: u64,
+    wall_ms: u64,
+    violation: Mutex<Option<BudgetViolation>>,
+    tripped_at: Mutex<Option<Instant>>,
+    stack_captured: AtomicBool,
+    /// Set once the request finished; late interrupts must not touch the isolate.
+    done: AtomicBool,
+}
+
+impl WatchTicket {
+    fn check(&self, clock: Option<ThreadCpuClock>) -> Option<(BudgetKind, u64)> {
+        if self.wall_ms > 0 && self.started_at.elapsed() >= Duration::from_millis(self.wall_ms) {
+            return Some((BudgetKind::Wall, self.wall_ms));
+        }
+        let cpu_used = clock.map(|clock| clock.elapsed().saturating_sub(self.cpu_start));
+        if self.cpu_ms > 0
+            && cpu_used.is_some_and(|used| used >= Duration::from_millis(self.cpu_ms))
+        {
+            return Some((BudgetKind::Cpu, self.cpu_ms));
+        }
+        None
+    }
+
+    fn record(&self, kind: BudgetKind, limit_ms: u64) -> bool {
+        let mut violation = self.violation.lock().unwrap_or_else(|e| e.into_inner());
+        if violation.is_some() {
+            return false;
+        }
+        *violation = Some(BudgetViolation {
+            kind,
+            limit_ms,
+            stack: Vec::new(),
+            php_stack: Vec::new(),
+        });
+        *self.tripped_at.lock().unwrap_or_else(|e| e.into_inner()) = Some(Instant::now());
+        true
+    }
+}
+
+#[derive(Default)]
+struct WatchState {
+    armed: Option<Arc<WatchTicket>>,
+    shutdown: bool,
+}
+
+#[derive(Default)]
+struct WatchShared {
+    state: Mutex<WatchState>,
+    wake: Condvar,
+}
+
+/// Watchdog thread bound to the worker thread that created it.
+pub(crate) struct BudgetWatchdog {
+    shared: Arc<WatchShared>,
+    clock: Option<ThreadCpuClock>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl BudgetWatchdog {
+    /// Must be called on the worker thread whose CPU time should be measured.
+    pub(crate) fn spawn(worker_id: usize) -> Self {
+        let shared = Arc::new(WatchShared::default());
+        let clock = ThreadCpuClock::current();
+        let thread_shared = Arc::clone(&shared);
+        let thread = thread::Builder::new()
+            .name(format!("deka-watchdog-{}", worker_id))
+            .spawn(move || watch_loop(thread_shared, clock))
+            .map_err(|err| tracing::warn!("watchdog for worker {} failed: {}", worker_id, err))
+            .ok();
+
+        Self {
+            shared,
+            clock,
+            thread,
+        }
+    }
+
+    /// Start watching a request. Returns `None` when both budgets are disabled.
+    pub(crate) fn arm(
+        &self,
+        isolate: v8::IsolateHandle,
+        cpu_ms: u64,
+        wall_ms: u64,
+    ) -> Option<BudgetGuard> {
+        if (cpu_ms == 0 && wall_ms == 0) || self.thread.is_none() {
+            return None;
+        }
+
+        let ticket = Arc::new(WatchTicket {
+            isolate,
+            started_at: Instant::now(),
+            cpu_start: self.clock.map(|clock| clock.elapsed()).unwrap_or_default(),
+            cpu_ms,
+            wall_ms,
+            violation: Mutex::new(None),
+            tripped_at: Mutex::new(None),
+            stack_captured: AtomicBool::new(false),
+            done: AtomicBool::new(false),
+        });
+
+        let mut state = self.shared.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.armed = Some(Arc::clone(&ticket));
+        self.shared.wake.notify_one();
+
+        Some(BudgetGuard {
+            ticket,
+            shared: Arc::clone(&self.shared),
+        })
+    }
+}
+
+impl Drop for BudgetWatchdog {
+    fn drop(&mut self) {
+        {
+            let mut state = self.shared.state.lock().unwrap_or_else(|e| e.into_inner());
+            state.shutdown = true;
+            state.armed = None;
+        }
+        self.shared.wake.notify_one();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Armed budget for the request currently executing on a worker.
+pub(crate) struct BudgetGuard {
+    ticket: Arc<WatchTicket>,
+    shared: Arc<WatchShared>,
+}
+
+impl BudgetGuard {
+    /// Wall-clock time left, used to bound the async event loop.
+    pub(crate) fn remaining_wall(&self) -> Option<Duration> {
+        if self.ticket.wall_ms == 0 {
+            return None;
+        }
+        Some(
+            Duration::from_millis(self.ticket.wall_ms)
+                .saturating_sub(self.ticket.started_at.elapsed()),
+        )
+    }
+
+    /// Record a wall-clock violation detected outside of JS execution, e.g. while
+    /// the event loop was waiting on an async op that never resolved.
+    pub(crate) fn expire_wall(&self) {
+        self.ticket.record(BudgetKind::Wall, self.ticket.wall_ms);
+    }
+
+    /// Disarm the watchdog and report whether the budget was exceeded.
+    pub(crate) fn finish(self) -> Option<BudgetViolation> {
+        self.ticket.done.store(true, Ordering::SeqCst);
+        {
+            let mut state = self.shared.state.lock().unwrap_or_else(|e| e.into_inner());
+            if state
+                .armed
+                .as_ref()
+                .is_some_and(|armed| Arc::ptr_eq(armed, &self.ticket))
+            {
+                state.armed = None;
+            }
+        }
+        self.ticket
+            .violation
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take()
+    }
+}
+
+fn watch_loop(shared: Arc<WatchShared>, clock: Option<ThreadCpuClock>) {
+    loop {
+        let ticket = {
+            let mut state = shared.state.lock().unwrap_or_else(|e| e.into_inner());
+            while state.armed.is_none() && !state.shutdown {
+                state = shared.wake.wait(state).unwrap_or_else(|e| e.into_inner());
+            }
+            if state.shutdown {
+                return;
+            }
+            state.armed.clone()
+        };
+        let Some(ticket) = ticket else {
+            continue;
+        };
+
+        thread::sleep(POLL_INTERVAL);
+        if ticket.done.load(Ordering::SeqCst) {
+            continue;
+        }
+
+        let tripped_at = *ticket.tripped_at.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(tripped_at) = tripped_at {
+            // The interrupt only runs while JS is on the stack; if it has not run by
+            // now, terminate without a stack so a blocked isolate cannot hang the worker.
+            if !ticket.stack_captured.load(Ordering::SeqCst)
+                && tripped_at.elapsed() >= INTERRUPT_GRACE
+            {
+                ticket.stack_captured.store(true, Ordering::SeqCst);
+                ticket.isolate.terminate_execution();
+            }
+            continue;
+        }
+
+        let Some((kind, limit_ms)) = ticket.check(clock) else {
+            continue;
+        };
+        if ticket.record(kind, limit_ms) {
+            let data = Arc::into_raw(Arc::clone(&ticket)) as *mut c_void;
+            if !ticket
+                .isolate
+                .request_interrupt(capture_stack_and_terminate, data)
+            {
+                // Isolate is gone; reclaim the reference handed to V8.
+                drop(unsafe { Arc::from_raw(data as *const WatchTicket) });
+                ticket.isolate.terminate_execution();
+            }
+        }
+    }
+}
+
+unsafe extern "C" fn capture_stack_and_terminate(isolate: &mut v8::Isolate, data: *mut c_void) {
+    let ticket = unsafe { Arc::from_raw(data as *const WatchTicket) };
+    if ticket.done.load(Ordering::SeqCst) || ticket.stack_captured.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    let stack = capture_stack(isolate);
+    let php_stack = crate::validation::phpx_stack_frames(&stack);
+    if let Some(violation) = ticket
+        .violation
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_mut()
+    {
+        violation.stack = stack;
+        violation.php_stack = php_stack;
+    }
+    isolate.terminate_execution();
+}
+
+fn capture_stack(isolate: &mut v8::Isolate) -> Vec<String> {
+    v8::scope!(let scope, isolate);
+    let Some(trace) = v8::StackTrace::current_stack_trace(scope, STACK_FRAME_LIMIT) else {
+        return Vec::new();
+    };
+
+    let mut frames = Vec::new();
+    for index in 0..trace.get_frame_count() {
+        let Some(frame) = trace.get_frame(scope, index) else {
+            continue;
+        };
+        let function = frame
+            .get_function_name(scope)
+            .map(|name| name.to_rust_string_lossy(scope))
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| "<anonymous>".to_string());
+        let script = frame
+            .get_script_name_or_source_url(scope)
+            .map(|name| name.to_rust_string_lossy(scope))
+            .unwrap_or_else(|| "<unknown>".to_string());
+        frames.push(format!(
+            "{} ({}:{}:{})",
+            function,
+            script,
+            frame.get_line_number(),
+            frame.get_column()
+        ));
+    }
+    frames
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn handler_overrides_fall_back_to_pool_defaults() {
+        let mut overrides = HashMap::new();
+        overrides.insert(
+            "api.phpx".to_string(),
+            ExecutionBudget {
+                cpu_ms: Some(50),
+                wall_ms: None,
+            },
+        );
+        overrides.insert(
+            "report.phpx".to_string(),
+            ExecutionBudget {
+                cpu_ms: Some(0),
+                wall_ms: Some(120_000),
+            },
+        );
+
+        assert_eq!(
+            ExecutionBudget::resolve(&overrides, "api.phpx", 1_000, 30_000),
+            (50, 30_000)
+        );
+        assert_eq!(
+            ExecutionBudget::resolve(&overrides, "report.phpx", 1_000, 30_000),
+            (0, 120_000)
+        );
+        assert_eq!(
+            ExecutionBudget::resolve(&overrides, "other.phpx", 1_000, 30_000),
+            (1_000, 30_000)
+        );
+    }
+
+    #[test]
+    fn violation_message_includes_stack_frames() {
+        let violation = BudgetViolation {
+            kind: BudgetKind::Cpu,
+            limit_ms: 200,
+            stack: vec!["spin (file:///app/index.phpx.js:3:5)".to_string()],
+            php_stack: Vec::new(),
+        };
+        assert_eq!(violation.message(), "Handler exceeded CPU budget of 200ms");
+        assert!(
+            violation
+                .message_with_stack()
+                .ends_with("\n    at spin (file:///app/index.phpx.js:3:5)")
+        );
+    }
+
+    #[test]
+    fn violation_message_lists_php_frames_before_js_frames() {
+        let violation = BudgetViolation {
+            kind: BudgetKind::Wall,
+            limit_ms: 1_000,
+            stack: vec!["spin (file:///app/index.phpx:42:9)".to_string()],
+            php_stack: vec!["spin (/app/index.phpx:3:5)".to_string()],
+        };
+        assert_eq!(
+            violation.message_with_stack(),
+            "Handler exceeded wall-clock budget of 1000ms\n  PHP stack:\n    at spin (/app/index.phpx:3:5)\n  JS stack:\n    at spin (file:///app/index.phpx:42:9)"
+        );
+    }
+}
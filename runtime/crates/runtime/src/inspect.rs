@@ -0,0 +1,32 @@
+use core::Context;
+use pool::{InspectorConfig, PoolConfig};
+
+/// Resolve `--inspect[=addr]` / `--inspect-brk[=addr]` into an inspector config.
+pub(crate) fn inspector_config(context: &Context) -> Result<Option<InspectorConfig>, String> {
+    let flag = |name: &str| context.args.flags.get(name).copied().unwrap_or(false);
+    let break_on_start = flag("--inspect-brk");
+    if !break_on_start && !flag("--inspect") {
+        return Ok(None);
+    }
+    let addr = context
+        .args
+        .params
+        .get("--inspect-brk")
+        .or_else(|| context.args.params.get("--inspect"));
+    InspectorConfig::parse(addr.map(String::as_str), break_on_start).map(Some)
+}
+
+/// Debug sessions run on a single worker with budgets and autoscaling off, so
+/// breakpoints always land in the same isolate and a paused request is never
+/// terminated by the watchdog.
+pub(crate) fn apply_inspector(config: &mut PoolConfig, inspector: &InspectorConfig) {
+    config.num_workers = 1;
+    config.request_timeout_ms = 0;
+    config.cpu_budget_ms = 0;
+    config.handler_budgets.clear();
+    config.queue_timeout_ms = 0;
+    config.autoscale.enabled = false;
+    config.autoscale.warm_spares = 0;
+    config.enable_code_cache = false;
+    config.inspector = Some(inspector.clone());
+}
@@ -2,11 +2,14 @@ use core::Context;
 
 mod env;
 mod extensions;
+mod inspect;
 mod js_pipeline;
 mod run;
 mod security;
 mod serve;
 
+pub use security::{SecurityCheck, check_security_policy};
+
 pub fn run(context: &Context) {
     run::run(context);
 }
@@ -3,10 +3,12 @@ use std::sync::Arc;
 
 use crate::env::init_env;
 use crate::extensions::extensions_for_mode;
+use crate::inspect::{apply_inspector, inspector_config};
 use crate::js_pipeline::build_phpx_handler_bundle;
 use crate::security::resolve_security_policy;
 use core::Context;
 use engine::{RuntimeEngine, config as runtime_config, set_engine};
+use modules_php::validation::diagnostics::{ErrorFormat, format_json_diagnostics};
 use modules_php::validation::{format_validation_error, modules::validate_module_resolution};
 use platform::Platform;
 use platform_server::ServerPlatform;
@@ -114,9 +116,13 @@ async fn run_async(context: &Context) -> Result<(), String> {
     let mut pool_config = PoolConfig::from_env();
     // Run mode should allow long-lived servers without timing out.
     pool_config.request_timeout_ms = 0;
+    pool_config.cpu_budget_ms = 0;
     if let Some(enabled) = runtime_cfg.code_cache_enabled() {
         pool_config.enable_code_cache = enabled;
     }
+    if let Some(inspector) = inspector_config(context)? {
+        apply_inspector(&mut pool_config, &inspector);
+    }
 
     let serve_mode_for_extensions = serve_mode.clone();
     let extensions_provider = Arc::new(move || extensions_for_mode(&serve_mode_for_extensions));
@@ -186,6 +192,9 @@ fn validate_phpx_modules(handler_path: &str) -> Result<(), String> {
                 .map_err(|err| format!("Failed to read PHPX handler {}: {}", path, err))
         },
         &|source, path| validate_module_resolution(source, path),
-        &|source, path, error| format_validation_error(source, path, error),
+        &|source, path, error| match ErrorFormat::from_env() {
+            ErrorFormat::Human => format_validation_error(source, path, error),
+            ErrorFormat::Json => format_json_diagnostics(path, std::slice::from_ref(error), &[]),
+        },
     )
 }
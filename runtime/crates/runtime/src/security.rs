@@ -1,7 +1,7 @@
 use core::Context;
 use runtime_core::security_policy::{
-    RuleList, SecurityCliOverrides, merge_policy_with_cli, parse_deka_security_policy,
-    policy_to_json,
+    PolicyDiagnostic, PolicyDiagnosticLevel, RuleList, SecurityCliOverrides, SecurityPolicy,
+    blocking_diagnostics, merge_policy_with_cli, parse_deka_security_policy, policy_to_json,
 };
 use core::ServeMode;
 
@@ -12,7 +12,21 @@ pub struct ResolvedSecurityPolicy {
     pub warnings: Vec<String>,
 }
 
-pub fn resolve_security_policy(context: &Context) -> Result<ResolvedSecurityPolicy, String> {
+/// Outcome of validating `deka.json` and applying CLI overrides, shared by
+/// `deka run`/`deka serve` startup and `deka security check`.
+pub struct SecurityCheck {
+    pub diagnostics: Vec<PolicyDiagnostic>,
+    pub policy: SecurityPolicy,
+    pub strict: bool,
+}
+
+impl SecurityCheck {
+    pub fn blocking(&self) -> Vec<&PolicyDiagnostic> {
+        blocking_diagnostics(&self.diagnostics, self.strict)
+    }
+}
+
+pub fn check_security_policy(context: &Context) -> Result<SecurityCheck, String> {
     let deka_json_path = context.env.cwd.join("deka.json");
     let root = if deka_json_path.is_file() {
         let raw = std::fs::read_to_string(&deka_json_path)
@@ -24,52 +38,60 @@ pub fn resolve_security_policy(context: &Context) -> Result<ResolvedSecurityPoli
     };
 
     let parsed = parse_deka_security_policy(&root);
-    if parsed.has_errors() {
-        let mut lines = Vec::new();
-        for diag in parsed.diagnostics {
-            if matches!(
-                diag.level,
-                runtime_core::security_policy::PolicyDiagnosticLevel::Error
-            ) {
-                lines.push(format!("{} at {}: {}", diag.code, diag.path, diag.message));
-            }
-        }
+    let overrides = SecurityCliOverrides::from_flags(&context.args.flags);
+    let mut policy = parsed.policy;
+    if context.args.flags.contains_key("--dev") {
+        apply_dev_defaults(&mut policy, &context.handler.resolved.directory);
+    }
+    let policy = merge_policy_with_cli(policy, &overrides);
+    Ok(SecurityCheck {
+        diagnostics: parsed.diagnostics,
+        strict: policy.strict,
+        policy,
+    })
+}
+
+pub fn resolve_security_policy(context: &Context) -> Result<ResolvedSecurityPolicy, String> {
+    let check = check_security_policy(context)?;
+    let blocking = check.blocking();
+    if !blocking.is_empty() {
+        let lines = blocking
+            .iter()
+            .map(|diag| format!("{}: {}", diag.level.as_str(), diag))
+            .collect::<Vec<_>>();
+        let hint = if check.strict {
+            "\nstrict mode is on: fix the warnings above or drop `security.strict`/--strict-security"
+        } else {
+            ""
+        };
         return Err(format!(
-            "invalid security policy:\n{}",
-            lines.join("\n")
+            "invalid security policy:\n{}{}",
+            lines.join("\n"),
+            hint
         ));
     }
 
     let project_kind = ProjectKind::from_mode(&context.handler.resolved.mode);
-    let warnings = parsed
+    let warnings = check
         .diagnostics
         .iter()
-        .filter(|diag| {
-            matches!(
-                diag.level,
-                runtime_core::security_policy::PolicyDiagnosticLevel::Warning
-            )
-        })
+        .filter(|diag| matches!(diag.level, PolicyDiagnosticLevel::Warning))
         .map(|diag| format_warning(diag, project_kind))
         .collect::<Vec<_>>();
 
-    let overrides = SecurityCliOverrides::from_flags(&context.args.flags);
-    let mut policy = parsed.policy;
-    if context.args.flags.contains_key("--dev") {
-        apply_dev_defaults(&mut policy, &context.handler.resolved.directory);
-    }
-    let merged = merge_policy_with_cli(policy, &overrides);
+    let merged = check.policy;
     let policy_json = serde_json::to_string(&policy_to_json(&merged))
         .map_err(|err| format!("failed to serialize security policy: {}", err))?;
     let summary = format!(
-        "default-deny; allow(run={}, dynamic={}, wasm={}) deny(run={}, dynamic={}, net={}) prompt={}",
+        "default-deny; allow(run={}, dynamic={}, wasm={}) deny(run={}, dynamic={}, net={}) prompt={}{}",
         summarize_rule(&merged.allow.run),
         merged.allow.dynamic,
         summarize_rule(&merged.allow.wasm),
         summarize_rule(&merged.deny.run),
         merged.deny.dynamic,
         summarize_rule(&merged.deny.net),
-        merged.prompt
+        merged.prompt,
+        if merged.strict { " strict" } else { "" }
     );
 
     Ok(ResolvedSecurityPolicy {
@@ -80,7 +102,7 @@ pub fn resolve_security_policy(context: &Context) -> Result<ResolvedSecurityPoli
     })
 }
 
-fn apply_dev_defaults(policy: &mut runtime_core::security_policy::SecurityPolicy, root: &std::path::Path) {
+fn apply_dev_defaults(policy: &mut SecurityPolicy, root: &std::path::Path) {
     if matches!(policy.allow.read, RuleList::None) {
         policy.allow.read = RuleList::List(vec![root.to_string_lossy().to_string()]);
     }
@@ -135,10 +157,10 @@ impl ProjectKind {
 }
 
 fn format_warning(
-    diag: &runtime_core::security_policy::PolicyDiagnostic,
+    diag: &PolicyDiagnostic,
     project_kind: ProjectKind,
 ) -> String {
-    let mut message = format!("{} at {}: {}", diag.code, diag.path, diag.message);
+    let mut message = diag.to_string();
     if matches!(
         diag.code,
         "SECURITY_POLICY_BROAD_ALLOW" | "SECURITY_POLICY_WEAK_ALLOW"
@@ -5,6 +5,7 @@ use std::time::Duration;
 
 use crate::env::init_env;
 use crate::extensions::extensions_for_mode;
+use crate::inspect::{apply_inspector, inspector_config};
 use crate::js_pipeline::build_phpx_handler_bundle;
 use crate::security::resolve_security_policy;
 use core::Context;
@@ -102,12 +103,18 @@ async fn serve_async(context: &Context) -> Result<(), String> {
     let mut serve_options = pool::validation::ServeOptions::default();
     apply_cli_serve_overrides(context, &mut serve_options);
 
-    let (server_pool_config, user_pool_config) = configure_pools(
+    let (mut server_pool_config, mut user_pool_config) = configure_pools(
         &handler_source,
         &handler_path,
         &serve_options,
+        &resolved.config,
         watch_enabled,
+        dev_mode,
     );
+    if let Some(inspector) = inspector_config(context)? {
+        apply_inspector(&mut server_pool_config, &inspector);
+        apply_inspector(&mut user_pool_config, &inspector);
+    }
     let server_pool_workers = server_pool_config.num_workers;
 
     let serve_mode = resolved.mode.clone();
@@ -227,7 +234,9 @@ fn configure_pools(
     handler_source: &str,
     handler_path: &str,
     serve_options: &pool::validation::ServeOptions,
+    serve_config: &runtime_config::ServeConfig,
     watch_enabled: bool,
+    dev_mode: bool,
 ) -> (PoolConfig, PoolConfig) {
     let runtime_cfg = runtime_config::RuntimeConfig::load();
     let mut server_pool_config = PoolConfig::from_env();
@@ -273,6 +282,26 @@ fn configure_pools(
         user_pool_config.enable_code_cache = enabled;
     }
 
+    if let Some(budget) = serve_config.budget.as_ref() {
+        for config in [&mut server_pool_config, &mut user_pool_config] {
+            if let Some(cpu_ms) = budget.default.cpu_ms {
+                config.cpu_budget_ms = cpu_ms;
+            }
+            if let Some(wall_ms) = budget.default.wall_ms {
+                config.request_timeout_ms = wall_ms;
+            }
+            config.handler_budgets = budget.handlers.clone();
+        }
+    }
+
+    if let Some(admission) = serve_config.admission.as_ref() {
+        server_pool_config.admission = admission.clone();
+        user_pool_config.admission = admission.clone();
+    }
+
+    server_pool_config.expose_stack_traces = dev_mode;
+    user_pool_config.expose_stack_traces = dev_mode;
+
     if watch_enabled {
         server_pool_config.enable_code_cache = false;
         user_pool_config.enable_code_cache = false;
@@ -4,5 +4,8 @@ pub mod module_spec;
 pub mod modules;
 pub mod process;
 pub mod security;
+pub mod security_audit;
 pub mod security_policy;
+pub mod security_rules;
 pub mod validation;
+pub mod vlq;
@@ -0,0 +1,431 @@
+//! Append-only audit trail of security decisions.
+//!
+//! When `security.audit` is set in `deka.json`, every allow/deny/prompt
+//! decision made by the capability checks is recorded as one event. Events are
+//! written as JSON lines or into a sqlite table; both backends share the event
+//! shape, the query filter and the summary defined here so the runtime writer
+//! and `deka security audit` agree on the format.
+
+use serde_json::{Map, Value, json};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const DEFAULT_AUDIT_PATH: &str = ".cache/security/audit.jsonl";
+
+pub const SQLITE_SCHEMA: &str = "CREATE TABLE IF NOT EXISTS security_audit (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    ts_ms INTEGER NOT NULL,
+    decision TEXT NOT NULL,
+    capability TEXT NOT NULL,
+    target TEXT NOT NULL,
+    origin TEXT,
+    module TEXT,
+    request_id TEXT,
+    rule TEXT
+);
+CREATE INDEX IF NOT EXISTS security_audit_ts ON security_audit (ts_ms);";
+
+pub const SQLITE_INSERT: &str = "INSERT INTO security_audit
+    (ts_ms, decision, capability, target, origin, module, request_id, rule)
+    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)";
+
+pub const SQLITE_SELECT: &str =
+    "SELECT ts_ms, decision, capability, target, origin, module, request_id, rule
+    FROM security_audit ORDER BY id";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditFormat {
+    Jsonl,
+    Sqlite,
+}
+
+impl AuditFormat {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AuditFormat::Jsonl => "jsonl",
+            AuditFormat::Sqlite => "sqlite",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "jsonl" | "json" => Some(AuditFormat::Jsonl),
+            "sqlite" | "sqlite3" | "db" => Some(AuditFormat::Sqlite),
+            _ => None,
+        }
+    }
+
+    /// `.sqlite`, `.sqlite3` and `.db` files default to sqlite, anything else to JSONL.
+    pub fn from_path(path: &str) -> Self {
+        let ext = Path::new(path)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or("");
+        AuditFormat::parse(ext).unwrap_or(AuditFormat::Jsonl)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditConfig {
+    /// Log location; relative paths resolve against the project root
+    pub path: String,
+    pub format: AuditFormat,
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        Self {
+            path: DEFAULT_AUDIT_PATH.to_string(),
+            format: AuditFormat::Jsonl,
+        }
+    }
+}
+
+impl AuditConfig {
+    pub fn resolve_path(&self, root: &Path) -> PathBuf {
+        let path = Path::new(&self.path);
+        if path.is_absolute() {
+            path.to_path_buf()
+        } else {
+            root.join(path)
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({ "path": self.path, "format": self.format.as_str() })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum AuditDecision {
+    Allow,
+    Deny,
+    PromptGranted,
+    PromptDenied,
+    Persisted,
+}
+
+impl AuditDecision {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AuditDecision::Allow => "allow",
+            AuditDecision::Deny => "deny",
+            AuditDecision::PromptGranted => "prompt-granted",
+            AuditDecision::PromptDenied => "prompt-denied",
+            AuditDecision::Persisted => "persisted",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim() {
+            "allow" => Some(AuditDecision::Allow),
+            "deny" => Some(AuditDecision::Deny),
+            "prompt-granted" => Some(AuditDecision::PromptGranted),
+            "prompt-denied" => Some(AuditDecision::PromptDenied),
+            "persisted" => Some(AuditDecision::Persisted),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditEvent {
+    pub ts_ms: u64,
+    pub decision: AuditDecision,
+    pub capability: String,
+    pub target: String,
+    /// `project-owned`, `third-party`, `runtime-internal` or `unknown`
+    pub origin: Option<String>,
+    /// Installed package the call was attributed to, if any
+    pub module: Option<String>,
+    pub request_id: Option<String>,
+    /// Policy rule that decided the check, e.g. `allow.net: *.example.com`
+    pub rule: Option<String>,
+}
+
+impl AuditEvent {
+    pub fn new(decision: AuditDecision, capability: &str, target: Option<&str>) -> Self {
+        Self {
+            ts_ms: now_millis(),
+            decision,
+            capability: capability.to_string(),
+            target: target.unwrap_or("*").to_string(),
+            origin: None,
+            module: None,
+            request_id: current_request(),
+            rule: None,
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut out = Map::new();
+        out.insert("ts_ms".to_string(), json!(self.ts_ms));
+        out.insert("decision".to_string(), json!(self.decision.as_str()));
+        out.insert("capability".to_string(), json!(self.capability));
+        out.insert("target".to_string(), json!(self.target));
+        for (key, value) in [
+            ("origin", &self.origin),
+            ("module", &self.module),
+            ("request_id", &self.request_id),
+            ("rule", &self.rule),
+        ] {
+            if let Some(value) = value {
+                out.insert(key.to_string(), json!(value));
+            }
+        }
+        Value::Object(out)
+    }
+
+    pub fn from_json(value: &Value) -> Option<Self> {
+        let text = |key: &str| value.get(key).and_then(|v| v.as_str()).map(str::to_string);
+        Some(Self {
+            ts_ms: value.get("ts_ms")?.as_u64()?,
+            decision: AuditDecision::parse(value.get("decision")?.as_str()?)?,
+            capability: text("capability")?,
+            target: text("target")?,
+            origin: text("origin"),
+            module: text("module"),
+            request_id: text("request_id"),
+            rule: text("rule"),
+        })
+    }
+}
+
+/// Append one event as a JSON line. The file is opened in append mode so
+/// concurrent writers never truncate or rewrite earlier entries.
+pub fn append_jsonl(path: &Path, event: &AuditEvent) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut line = event.to_json().to_string();
+    line.push('\n');
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(line.as_bytes())
+}
+
+/// Read every well-formed event; malformed lines (e.g. a torn final write)
+/// are skipped.
+pub fn read_jsonl(path: &Path) -> std::io::Result<Vec<AuditEvent>> {
+    let file = std::fs::File::open(path)?;
+    let mut events = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(event) = serde_json::from_str::<Value>(&line)
+            .ok()
+            .and_then(|value| AuditEvent::from_json(&value))
+        {
+            events.push(event);
+        }
+    }
+    Ok(events)
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AuditQuery {
+    pub since_ms: Option<u64>,
+    pub until_ms: Option<u64>,
+    pub capability: Option<String>,
+    pub decision: Option<AuditDecision>,
+    pub module: Option<String>,
+    pub request_id: Option<String>,
+    pub target_contains: Option<String>,
+}
+
+impl AuditQuery {
+    pub fn matches(&self, event: &AuditEvent) -> bool {
+        if self.since_ms.is_some_and(|since| event.ts_ms < since) {
+            return false;
+        }
+        if self.until_ms.is_some_and(|until| event.ts_ms > until) {
+            return false;
+        }
+        if self
+            .capability
+            .as_deref()
+            .is_some_and(|capability| capability != event.capability)
+        {
+            return false;
+        }
+        if self
+            .decision
+            .is_some_and(|decision| decision != event.decision)
+        {
+            return false;
+        }
+        if self
+            .module
+            .as_deref()
+            .is_some_and(|module| event.module.as_deref() != Some(module))
+        {
+            return false;
+        }
+        if self
+            .request_id
+            .as_deref()
+            .is_some_and(|request_id| event.request_id.as_deref() != Some(request_id))
+        {
+            return false;
+        }
+        if self
+            .target_contains
+            .as_deref()
+            .is_some_and(|needle| !event.target.contains(needle))
+        {
+            return false;
+        }
+        true
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditSummaryRow {
+    pub capability: String,
+    pub decision: AuditDecision,
+    pub count: usize,
+    pub distinct_targets: usize,
+    /// Most frequent target for this capability/decision pair
+    pub top_target: String,
+}
+
+/// Group events by capability and decision, most frequent first.
+pub fn summarize(events: &[AuditEvent]) -> Vec<AuditSummaryRow> {
+    let mut groups: BTreeMap<(String, AuditDecision), BTreeMap<String, usize>> = BTreeMap::new();
+    for event in events {
+        *groups
+            .entry((event.capability.clone(), event.decision))
+            .or_default()
+            .entry(event.target.clone())
+            .or_default() += 1;
+    }
+    let mut rows: Vec<AuditSummaryRow> = groups
+        .into_iter()
+        .map(|((capability, decision), targets)| {
+            let count = targets.values().sum();
+            let top_target = targets
+                .iter()
+                .max_by(|a, b| a.1.cmp(b.1).then_with(|| b.0.cmp(a.0)))
+                .map(|(target, _)| target.clone())
+                .unwrap_or_default();
+            AuditSummaryRow {
+                capability,
+                decision,
+                count,
+                distinct_targets: targets.len(),
+                top_target,
+            }
+        })
+        .collect();
+    rows.sort_by_key(|row| std::cmp::Reverse(row.count));
+    rows
+}
+
+thread_local! {
+    static CURRENT_REQUEST: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Worker threads run one request at a time; the pool tags the thread with the
+/// request id so decisions made during it can be correlated.
+pub fn set_current_request(request_id: Option<String>) {
+    CURRENT_REQUEST.with(|slot| *slot.borrow_mut() = request_id);
+}
+
+pub fn current_request() -> Option<String> {
+    CURRENT_REQUEST.with(|slot| slot.borrow().clone())
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(decision: AuditDecision, capability: &str, target: &str) -> AuditEvent {
+        AuditEvent {
+            ts_ms: 1_000,
+            decision,
+            capability: capability.to_string(),
+            target: target.to_string(),
+            origin: None,
+            module: None,
+            request_id: None,
+            rule: None,
+        }
+    }
+
+    #[test]
+    fn jsonl_round_trip_appends() {
+        let dir = std::env::temp_dir().join(format!("deka-audit-test-{}", now_millis()));
+        let path = dir.join("nested/audit.jsonl");
+        let mut first = event(AuditDecision::Allow, "net", "db.internal:5432");
+        first.module = Some("@acme/orm".to_string());
+        first.request_id = Some("req_7".to_string());
+        first.rule = Some("allow.net: *.internal:5432".to_string());
+        append_jsonl(&path, &first).unwrap();
+        append_jsonl(&path, &event(AuditDecision::Deny, "env", "AWS_SECRET")).unwrap();
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(b"{\"ts_ms\":")
+            .unwrap();
+
+        let events = read_jsonl(&path).unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0], first);
+        assert_eq!(events[1].decision, AuditDecision::Deny);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn query_filters_and_summary_groups() {
+        let mut events = vec![
+            event(AuditDecision::Allow, "net", "a:443"),
+            event(AuditDecision::Allow, "net", "a:443"),
+            event(AuditDecision::Allow, "net", "b:443"),
+            event(AuditDecision::Deny, "net", "evil:443"),
+            event(AuditDecision::PromptGranted, "read", "./data"),
+        ];
+        events[3].module = Some("@evil/pkg".to_string());
+        events[4].ts_ms = 5_000;
+
+        let query = AuditQuery {
+            decision: Some(AuditDecision::Deny),
+            ..AuditQuery::default()
+        };
+        let denied: Vec<_> = events.iter().filter(|e| query.matches(e)).collect();
+        assert_eq!(denied.len(), 1);
+        assert_eq!(denied[0].module.as_deref(), Some("@evil/pkg"));
+
+        let query = AuditQuery {
+            since_ms: Some(2_000),
+            ..AuditQuery::default()
+        };
+        assert_eq!(events.iter().filter(|e| query.matches(e)).count(), 1);
+
+        let summary = summarize(&events);
+        assert_eq!(summary[0].capability, "net");
+        assert_eq!(summary[0].decision, AuditDecision::Allow);
+        assert_eq!(summary[0].count, 3);
+        assert_eq!(summary[0].distinct_targets, 2);
+        assert_eq!(summary[0].top_target, "a:443");
+    }
+
+    #[test]
+    fn infers_format_from_extension() {
+        assert_eq!(AuditFormat::from_path("audit.jsonl"), AuditFormat::Jsonl);
+        assert_eq!(AuditFormat::from_path("audit.sqlite"), AuditFormat::Sqlite);
+        assert_eq!(AuditFormat::from_path("logs/audit.db"), AuditFormat::Sqlite);
+    }
+}
@@ -1,5 +1,9 @@
 use serde_json::{Map, Value, json};
-use std::collections::{BTreeSet, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+
+use crate::security_audit::{AuditConfig, AuditFormat};
+use crate::security_rules::NetRule;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum RuleList {
@@ -26,6 +30,22 @@ pub struct SecurityScope {
     pub dynamic: bool,
 }
 
+impl SecurityScope {
+    /// Rule list for a list-valued capability (`dynamic` is a flag, not a list).
+    pub fn rule(&self, capability: &str) -> Option<&RuleList> {
+        match capability {
+            "read" => Some(&self.read),
+            "write" => Some(&self.write),
+            "net" => Some(&self.net),
+            "env" => Some(&self.env),
+            "run" => Some(&self.run),
+            "db" => Some(&self.db),
+            "wasm" => Some(&self.wasm),
+            _ => None,
+        }
+    }
+}
+
 impl Default for SecurityScope {
     fn default() -> Self {
         Self {
@@ -41,11 +61,48 @@ impl Default for SecurityScope {
     }
 }
 
+/// Grants for a single installed package (`security.packages["@scope/name"]`).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PackagePolicy {
+    pub allow: SecurityScope,
+    pub deny: SecurityScope,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SecurityPolicy {
     pub allow: SecurityScope,
     pub deny: SecurityScope,
     pub prompt: bool,
+    /// Per-package grants. Once any package is listed, code running inside a
+    /// third-party package only gets what its own entry allows, and never more
+    /// than the app-wide policy.
+    pub packages: BTreeMap<String, PackagePolicy>,
+    /// Where capability decisions are logged; `None` disables the audit log.
+    pub audit: Option<AuditConfig>,
+    /// Refuse to start when the policy has warnings as well as errors.
+    pub strict: bool,
+    /// Refuse to install php packages without a valid publisher signature.
+    pub require_signed_packages: bool,
+}
+
+impl SecurityPolicy {
+    pub fn scopes_packages(&self) -> bool {
+        !self.packages.is_empty()
+    }
+
+    /// Grants nothing and never prompts. Used when a policy handed to the
+    /// runtime cannot be parsed, so a broken policy fails closed.
+    pub fn deny_all() -> Self {
+        Self {
+            allow: SecurityScope::default(),
+            deny: SecurityScope::default(),
+            prompt: false,
+            packages: BTreeMap::new(),
+            audit: None,
+            strict: true,
+            require_signed_packages: true,
+        }
+    }
 }
 
 impl Default for SecurityPolicy {
@@ -57,6 +114,10 @@ impl Default for SecurityPolicy {
             },
             deny: SecurityScope::default(),
             prompt: true,
+            packages: BTreeMap::new(),
+            audit: None,
+            strict: false,
+            require_signed_packages: false,
         }
     }
 }
@@ -67,6 +128,15 @@ pub enum PolicyDiagnosticLevel {
     Error,
 }
 
+impl PolicyDiagnosticLevel {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Warning => "warning",
+            Self::Error => "error",
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct PolicyDiagnostic {
     pub level: PolicyDiagnosticLevel,
@@ -75,6 +145,23 @@ pub struct PolicyDiagnostic {
     pub message: String,
 }
 
+impl PolicyDiagnostic {
+    pub fn to_json(&self) -> Value {
+        json!({
+            "level": self.level.as_str(),
+            "code": self.code,
+            "path": self.path,
+            "message": self.message,
+        })
+    }
+}
+
+impl fmt::Display for PolicyDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {}: {}", self.code, self.path, self.message)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct PolicyParseOutcome {
     pub policy: SecurityPolicy,
@@ -87,6 +174,22 @@ impl PolicyParseOutcome {
             .iter()
             .any(|diag| matches!(diag.level, PolicyDiagnosticLevel::Error))
     }
+
+    pub fn blocking(&self, strict: bool) -> Vec<&PolicyDiagnostic> {
+        blocking_diagnostics(&self.diagnostics, strict)
+    }
+}
+
+/// Diagnostics that stop the runtime from starting: errors always, and
+/// warnings too once strict mode is on.
+pub fn blocking_diagnostics(
+    diagnostics: &[PolicyDiagnostic],
+    strict: bool,
+) -> Vec<&PolicyDiagnostic> {
+    diagnostics
+        .iter()
+        .filter(|diag| strict || matches!(diag.level, PolicyDiagnosticLevel::Error))
+        .collect()
 }
 
 #[derive(Debug, Clone, Default)]
@@ -109,6 +212,7 @@ pub struct SecurityCliOverrides {
     pub deny_dynamic: bool,
     pub deny_wasm: bool,
     pub no_prompt: bool,
+    pub strict: bool,
 }
 
 impl SecurityCliOverrides {
@@ -132,6 +236,7 @@ impl SecurityCliOverrides {
             deny_dynamic: flag_set(flags, "--deny-dynamic"),
             deny_wasm: flag_set(flags, "--deny-wasm"),
             no_prompt: flag_set(flags, "--no-prompt"),
+            strict: flag_set(flags, "--strict-security"),
         }
     }
 }
@@ -204,16 +309,36 @@ pub fn merge_policy_with_cli(
     if cli.no_prompt {
         base.prompt = false;
     }
+    if cli.strict {
+        base.strict = true;
+    }
 
     base
 }
 
 pub fn policy_to_json(policy: &SecurityPolicy) -> Value {
+    let packages: Map<String, Value> = policy
+        .packages
+        .iter()
+        .map(|(name, package)| {
+            (
+                name.clone(),
+                json!({
+                    "allow": scope_to_json(&package.allow),
+                    "deny": scope_to_json(&package.deny),
+                }),
+            )
+        })
+        .collect();
     json!({
         "security": {
             "allow": scope_to_json(&policy.allow),
             "deny": scope_to_json(&policy.deny),
-            "prompt": policy.prompt
+            "prompt": policy.prompt,
+            "packages": packages,
+            "audit": policy.audit.as_ref().map(AuditConfig::to_json),
+            "strict": policy.strict,
+            "requireSignedPackages": policy.require_signed_packages
         }
     })
 }
@@ -255,7 +380,10 @@ pub fn parse_deka_security_policy(root: &Value) -> PolicyParseOutcome {
     };
 
     for key in security_obj.keys() {
-        if key != "allow" && key != "deny" && key != "prompt" {
+        if !matches!(
+            key.as_str(),
+            "allow" | "deny" | "prompt" | "packages" | "audit" | "strict" | "requireSignedPackages"
+        ) {
             diagnostics.push(diag(
                 PolicyDiagnosticLevel::Warning,
                 "SECURITY_POLICY_UNKNOWN_KEY",
@@ -271,6 +399,12 @@ pub fn parse_deka_security_policy(root: &Value) -> PolicyParseOutcome {
     if let Some(deny) = security_obj.get("deny") {
         policy.deny = parse_scope("$.security.deny", deny, &mut diagnostics);
     }
+    if let Some(packages) = security_obj.get("packages") {
+        policy.packages = parse_packages("$.security.packages", packages, &mut diagnostics);
+    }
+    if let Some(audit) = security_obj.get("audit") {
+        policy.audit = parse_audit("$.security.audit", audit, &mut diagnostics);
+    }
     if let Some(prompt) = security_obj.get("prompt") {
         if let Some(value) = prompt.as_bool() {
             policy.prompt = value;
@@ -283,6 +417,30 @@ pub fn parse_deka_security_policy(root: &Value) -> PolicyParseOutcome {
             ));
         }
     }
+    if let Some(strict) = security_obj.get("strict") {
+        if let Some(value) = strict.as_bool() {
+            policy.strict = value;
+        } else {
+            diagnostics.push(diag(
+                PolicyDiagnosticLevel::Error,
+                "SECURITY_POLICY_INVALID_STRICT",
+                "$.security.strict",
+                "Expected boolean for `strict`",
+            ));
+        }
+    }
+    if let Some(required) = security_obj.get("requireSignedPackages") {
+        if let Some(value) = required.as_bool() {
+            policy.require_signed_packages = value;
+        } else {
+            diagnostics.push(diag(
+                PolicyDiagnosticLevel::Error,
+                "SECURITY_POLICY_INVALID_REQUIRE_SIGNED",
+                "$.security.requireSignedPackages",
+                "Expected boolean for `requireSignedPackages`",
+            ));
+        }
+    }
 
     PolicyParseOutcome {
         policy,
@@ -290,6 +448,133 @@ pub fn parse_deka_security_policy(root: &Value) -> PolicyParseOutcome {
     }
 }
 
+/// `audit` accepts `true` (default JSONL path), a path string, or
+/// `{ "path": ..., "format": "jsonl" | "sqlite" }`.
+fn parse_audit(
+    path: &str,
+    value: &Value,
+    diagnostics: &mut Vec<PolicyDiagnostic>,
+) -> Option<AuditConfig> {
+    match value {
+        Value::Null | Value::Bool(false) => None,
+        Value::Bool(true) => Some(AuditConfig::default()),
+        Value::String(file) if !file.trim().is_empty() => Some(AuditConfig {
+            path: file.trim().to_string(),
+            format: AuditFormat::from_path(file.trim()),
+        }),
+        Value::Object(obj) => {
+            let file = match obj.get("path") {
+                None => AuditConfig::default().path,
+                Some(Value::String(file)) if !file.trim().is_empty() => file.trim().to_string(),
+                Some(_) => {
+                    diagnostics.push(diag(
+                        PolicyDiagnosticLevel::Error,
+                        "SECURITY_POLICY_INVALID_AUDIT",
+                        &format!("{}.path", path),
+                        "Expected non-empty string for `path`",
+                    ));
+                    return None;
+                }
+            };
+            let format = match obj.get("format") {
+                None => AuditFormat::from_path(&file),
+                Some(raw) => match raw.as_str().and_then(AuditFormat::parse) {
+                    Some(format) => format,
+                    None => {
+                        diagnostics.push(diag(
+                            PolicyDiagnosticLevel::Error,
+                            "SECURITY_POLICY_INVALID_AUDIT",
+                            &format!("{}.format", path),
+                            "Expected \"jsonl\" or \"sqlite\" for `format`",
+                        ));
+                        return None;
+                    }
+                },
+            };
+            Some(AuditConfig { path: file, format })
+        }
+        _ => {
+            diagnostics.push(diag(
+                PolicyDiagnosticLevel::Error,
+                "SECURITY_POLICY_INVALID_AUDIT",
+                path,
+                "Expected boolean, path string, or object for `audit`",
+            ));
+            None
+        }
+    }
+}
+
+fn parse_packages(
+    path: &str,
+    value: &Value,
+    diagnostics: &mut Vec<PolicyDiagnostic>,
+) -> BTreeMap<String, PackagePolicy> {
+    let mut packages = BTreeMap::new();
+    let Some(obj) = value.as_object() else {
+        diagnostics.push(diag(
+            PolicyDiagnosticLevel::Error,
+            "SECURITY_POLICY_INVALID_TYPE",
+            path,
+            "Expected object for `packages`",
+        ));
+        return packages;
+    };
+
+    for (name, entry) in obj {
+        let entry_path = format!("{}[\"{}\"]", path, name);
+        if !is_package_name(name) {
+            diagnostics.push(diag(
+                PolicyDiagnosticLevel::Error,
+                "SECURITY_POLICY_INVALID_PACKAGE",
+                &entry_path,
+                "Package keys must be scoped names like \"@scope/name\"",
+            ));
+            continue;
+        }
+        let Some(entry_obj) = entry.as_object() else {
+            diagnostics.push(diag(
+                PolicyDiagnosticLevel::Error,
+                "SECURITY_POLICY_SCOPE_NOT_OBJECT",
+                &entry_path,
+                "Expected object with `allow` and/or `deny`",
+            ));
+            continue;
+        };
+        for key in entry_obj.keys() {
+            if key != "allow" && key != "deny" {
+                diagnostics.push(diag(
+                    PolicyDiagnosticLevel::Warning,
+                    "SECURITY_POLICY_UNKNOWN_KEY",
+                    &format!("{}.{}", entry_path, key),
+                    "Unknown key in package policy",
+                ));
+            }
+        }
+        let mut package = PackagePolicy::default();
+        if let Some(allow) = entry_obj.get("allow") {
+            package.allow = parse_scope(&format!("{}.allow", entry_path), allow, diagnostics);
+        }
+        if let Some(deny) = entry_obj.get("deny") {
+            package.deny = parse_scope(&format!("{}.deny", entry_path), deny, diagnostics);
+        }
+        packages.insert(name.clone(), package);
+    }
+    packages
+}
+
+/// `@scope/name`, the only form installed php_modules packages take.
+fn is_package_name(name: &str) -> bool {
+    let Some(rest) = name.strip_prefix('@') else {
+        return false;
+    };
+    let mut parts = rest.split('/');
+    matches!(
+        (parts.next(), parts.next(), parts.next()),
+        (Some(scope), Some(pkg), None) if !scope.is_empty() && !pkg.is_empty()
+    )
+}
+
 fn parse_scope(
     path: &str,
     value: &Value,
@@ -386,6 +671,9 @@ fn parse_rule_list(
             ));
             return RuleList::None;
         }
+        if !rule_item_is_valid(path, path, item, diagnostics) {
+            return RuleList::None;
+        }
         if path.contains(".allow.") {
             if let Some(message) = weak_allow_warning(path, item) {
                 diagnostics.push(diag(
@@ -421,6 +709,9 @@ fn parse_rule_list(
                 ));
                 continue;
             }
+            if !rule_item_is_valid(path, &format!("{}[{}]", path, idx), trimmed, diagnostics) {
+                continue;
+            }
             if path.contains(".allow.") {
                 if let Some(message) = weak_allow_warning(path, trimmed) {
                     diagnostics.push(diag(
@@ -448,6 +739,31 @@ fn parse_rule_list(
     RuleList::None
 }
 
+/// Reject rule items that the capability's grammar cannot parse, so a typo
+/// fails loudly instead of silently never matching.
+fn rule_item_is_valid(
+    path: &str,
+    item_path: &str,
+    item: &str,
+    diagnostics: &mut Vec<PolicyDiagnostic>,
+) -> bool {
+    if capability_from_path(path) != Some("net") || item == "*" {
+        return true;
+    }
+    match NetRule::parse(item) {
+        Ok(_) => true,
+        Err(reason) => {
+            diagnostics.push(diag(
+                PolicyDiagnosticLevel::Error,
+                "SECURITY_POLICY_INVALID_NET_RULE",
+                item_path,
+                &format!("Invalid net rule \"{}\": {}", item, reason),
+            ));
+            false
+        }
+    }
+}
+
 fn broad_allow_hint(path: &str) -> Option<String> {
     if path.ends_with(".read") {
         return Some("Prefer explicit folders like \"./src\" or \"./php_modules\".".to_string());
@@ -528,7 +844,11 @@ fn is_broad_allow_item(capability: &str, item: &str) -> bool {
                 || normalized == "./"
                 || normalized == "/*"
                 || normalized == "./*"
+                || normalized == "/**"
+                || normalized == "./**"
         }
+        "net" => matches!(normalized, "*:*" | "0.0.0.0/0" | "::/0" | "[::]/0"),
+        "env" => normalized == "*" || normalized == "**",
         _ => false,
     }
 }
@@ -580,6 +900,7 @@ mod tests {
         PolicyDiagnosticLevel, RuleList, SecurityCliOverrides, merge_policy_with_cli,
         parse_deka_security_policy, policy_to_json,
     };
+    use crate::security_audit::AuditFormat;
 
     #[test]
     fn default_policy_when_key_missing() {
@@ -738,4 +1059,151 @@ mod tests {
                 && d.code == "SECURITY_POLICY_WEAK_ALLOW")
         );
     }
+
+    #[test]
+    fn validates_net_rule_grammar() {
+        let parsed = parse_deka_security_policy(&serde_json::json!({
+            "security": {
+                "allow": { "net": ["*.internal.example:443", "10.0.0.0/8", "api.*.example"] },
+                "deny": { "net": "10.0.0.0/40" }
+            }
+        }));
+        assert!(parsed.has_errors());
+        let invalid: Vec<&str> = parsed
+            .diagnostics
+            .iter()
+            .filter(|d| d.code == "SECURITY_POLICY_INVALID_NET_RULE")
+            .map(|d| d.path.as_str())
+            .collect();
+        assert_eq!(
+            invalid,
+            vec!["$.security.allow.net[2]", "$.security.deny.net"]
+        );
+        assert_eq!(
+            parsed.policy.allow.net,
+            RuleList::List(vec![
+                "*.internal.example:443".to_string(),
+                "10.0.0.0/8".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn parses_package_scopes() {
+        let parsed = parse_deka_security_policy(&serde_json::json!({
+            "security": {
+                "allow": { "net": true },
+                "packages": {
+                    "@acme/mailer": {
+                        "allow": { "net": ["smtp.example.com:587"] },
+                        "deny": { "env": true }
+                    },
+                    "mailer": { "allow": { "net": true } }
+                }
+            }
+        }));
+        let invalid: Vec<&str> = parsed
+            .diagnostics
+            .iter()
+            .filter(|d| d.code == "SECURITY_POLICY_INVALID_PACKAGE")
+            .map(|d| d.path.as_str())
+            .collect();
+        assert_eq!(invalid, vec!["$.security.packages[\"mailer\"]"]);
+        assert!(parsed.policy.scopes_packages());
+        let mailer = parsed.policy.packages.get("@acme/mailer").unwrap();
+        assert_eq!(
+            mailer.allow.rule("net"),
+            Some(&RuleList::List(vec!["smtp.example.com:587".to_string()]))
+        );
+        assert_eq!(mailer.deny.rule("env"), Some(&RuleList::All));
+
+        let out = policy_to_json(&parsed.policy);
+        assert_eq!(
+            out.pointer("/security/packages/@acme~1mailer/allow/net/0")
+                .and_then(|v| v.as_str()),
+            Some("smtp.example.com:587")
+        );
+        let round_trip = parse_deka_security_policy(&out);
+        assert_eq!(round_trip.policy.packages, parsed.policy.packages);
+    }
+
+    #[test]
+    fn parses_audit_config() {
+        let parse = |audit: serde_json::Value| {
+            parse_deka_security_policy(&serde_json::json!({ "security": { "audit": audit } }))
+        };
+        assert_eq!(parse(serde_json::json!(false)).policy.audit, None);
+        let default = parse(serde_json::json!(true)).policy.audit.unwrap();
+        assert_eq!(default.format, AuditFormat::Jsonl);
+        let sqlite = parse(serde_json::json!("logs/audit.sqlite"))
+            .policy
+            .audit
+            .unwrap();
+        assert_eq!(sqlite.format, AuditFormat::Sqlite);
+        let explicit = parse(serde_json::json!({ "path": "audit.log", "format": "sqlite" }));
+        assert_eq!(
+            explicit.policy.audit.as_ref().unwrap().format,
+            AuditFormat::Sqlite
+        );
+        assert!(parse(serde_json::json!({ "format": "csv" })).has_errors());
+
+        let round_trip = parse_deka_security_policy(&policy_to_json(&explicit.policy));
+        assert_eq!(round_trip.policy.audit, explicit.policy.audit);
+    }
+
+    #[test]
+    fn strict_mode_blocks_on_warnings() {
+        let parsed = parse_deka_security_policy(&serde_json::json!({
+            "security": {
+                "allow": { "read": true },
+                "strict": true
+            }
+        }));
+        assert!(!parsed.has_errors());
+        assert!(parsed.policy.strict);
+        assert!(parsed.blocking(false).is_empty());
+        let blocking = parsed.blocking(parsed.policy.strict);
+        assert_eq!(blocking.len(), 1);
+        assert!(
+            blocking[0]
+                .to_string()
+                .starts_with("SECURITY_POLICY_BROAD_ALLOW at $.security.allow.read: ")
+        );
+
+        let invalid = parse_deka_security_policy(&serde_json::json!({
+            "security": { "strict": "yes" }
+        }));
+        assert_eq!(
+            invalid.blocking(false)[0].code,
+            "SECURITY_POLICY_INVALID_STRICT"
+        );
+
+        let merged = merge_policy_with_cli(
+            super::SecurityPolicy::default(),
+            &SecurityCliOverrides {
+                strict: true,
+                ..SecurityCliOverrides::default()
+            },
+        );
+        assert!(merged.strict);
+        assert!(!super::SecurityPolicy::deny_all().prompt);
+    }
+
+    #[test]
+    fn parses_require_signed_packages() {
+        let parsed = parse_deka_security_policy(&serde_json::json!({
+            "security": { "requireSignedPackages": true }
+        }));
+        assert!(parsed.diagnostics.is_empty());
+        assert!(parsed.policy.require_signed_packages);
+        assert!(!super::SecurityPolicy::default().require_signed_packages);
+
+        let invalid = parse_deka_security_policy(&serde_json::json!({
+            "security": { "requireSignedPackages": 1 }
+        }));
+        assert_eq!(
+            invalid.diagnostics[0].code,
+            "SECURITY_POLICY_INVALID_REQUIRE_SIGNED"
+        );
+    }
 }
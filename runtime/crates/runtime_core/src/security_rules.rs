@@ -0,0 +1,440 @@
+//! Rule-item grammar for security policies.
+//!
+//! - net: `[scheme://]host[:port]`, where host is `*`, a name, a `*.suffix`
+//!   wildcard, an IP literal or a CIDR block (`10.0.0.0/8`, `[fd00::]/8`), and
+//!   port is a number, a range (`8000-8999`) or `*`. A rule without a port
+//!   matches every port.
+//! - env: exact names or globs such as `APP_*`.
+//! - read/write: path prefixes, or globs using `*`, `?` and `**`.
+
+use std::net::IpAddr;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HostPattern {
+    Any,
+    Name(String),
+    /// `*.example.com`, stored as `.example.com`; does not match the apex
+    Suffix(String),
+    Ip(IpAddr),
+    Cidr(IpAddr, u8),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetRule {
+    pub scheme: Option<String>,
+    pub host: HostPattern,
+    /// Inclusive port range; `None` matches any port
+    pub ports: Option<(u16, u16)>,
+}
+
+/// A connection being checked against net rules. `addrs` holds the addresses
+/// the host resolved to, so IP rules also apply to names (DNS rebinding).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetTarget {
+    pub scheme: Option<String>,
+    pub host: String,
+    pub port: Option<u16>,
+    pub addrs: Vec<IpAddr>,
+}
+
+impl NetTarget {
+    /// Parse `[scheme://]host[:port]`. Unparseable ports are ignored.
+    pub fn parse(target: &str) -> Self {
+        let (scheme, rest) = split_scheme(target.trim());
+        let (host, port) = split_host_port(rest);
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        Self {
+            scheme,
+            host: host.to_ascii_lowercase(),
+            port: port.and_then(|port| port.parse().ok()),
+            addrs: host.parse().into_iter().collect(),
+        }
+    }
+
+    pub fn with_addrs(mut self, addrs: impl IntoIterator<Item = IpAddr>) -> Self {
+        for addr in addrs {
+            if !self.addrs.contains(&addr) {
+                self.addrs.push(addr);
+            }
+        }
+        self
+    }
+}
+
+impl NetRule {
+    pub fn parse(item: &str) -> Result<Self, String> {
+        let item = item.trim();
+        if item.is_empty() {
+            return Err("empty net rule".to_string());
+        }
+        let (scheme, rest) = split_scheme(item);
+        let (host, port) = split_host_port(rest);
+        let ports = match port {
+            None | Some("*") => None,
+            Some(port) => Some(parse_port_range(port)?),
+        };
+        Ok(Self {
+            scheme,
+            host: parse_host_pattern(host)?,
+            ports,
+        })
+    }
+
+    /// Allow semantics: names match by name; IP and CIDR rules match a name
+    /// only when every address it resolved to is covered.
+    pub fn allows(&self, target: &NetTarget) -> bool {
+        if !self.matches_scheme_and_port(target) {
+            return false;
+        }
+        match &self.host {
+            HostPattern::Ip(_) | HostPattern::Cidr(_, _) => {
+                !target.addrs.is_empty() && target.addrs.iter().all(|addr| self.covers(addr))
+            }
+            _ => self.matches_name(&target.host),
+        }
+    }
+
+    /// Deny semantics: a rule matches when the name matches or any resolved
+    /// address is covered, so a permitted name cannot resolve to a denied IP.
+    pub fn denies(&self, target: &NetTarget) -> bool {
+        if !self.matches_scheme_and_port(target) {
+            return false;
+        }
+        self.matches_name(&target.host) || target.addrs.iter().any(|addr| self.covers(addr))
+    }
+
+    fn matches_scheme_and_port(&self, target: &NetTarget) -> bool {
+        // A scheme rule without a port implies the scheme's default port, which
+        // is how it applies to raw connects that carry no scheme.
+        let ports = self.ports.or_else(|| {
+            self.scheme
+                .as_deref()
+                .and_then(default_port)
+                .map(|port| (port, port))
+        });
+        if let Some(scheme) = &self.scheme {
+            match &target.scheme {
+                Some(target_scheme) if !scheme.eq_ignore_ascii_case(target_scheme) => return false,
+                None if ports.is_none() => return false,
+                _ => {}
+            }
+        }
+        match (ports, target.port) {
+            (None, _) => true,
+            (Some((lo, hi)), Some(port)) => (lo..=hi).contains(&port),
+            (Some(_), None) => target.scheme.is_some() && self.ports.is_none(),
+        }
+    }
+
+    fn matches_name(&self, host: &str) -> bool {
+        match &self.host {
+            HostPattern::Any => true,
+            HostPattern::Name(name) => name == host,
+            HostPattern::Suffix(suffix) => host.len() > suffix.len() && host.ends_with(suffix),
+            HostPattern::Ip(_) | HostPattern::Cidr(_, _) => {
+                host.parse::<IpAddr>().is_ok_and(|addr| self.covers(&addr))
+            }
+        }
+    }
+
+    fn covers(&self, addr: &IpAddr) -> bool {
+        match &self.host {
+            HostPattern::Any => true,
+            HostPattern::Ip(ip) => normalize_ip(*ip) == normalize_ip(*addr),
+            HostPattern::Cidr(network, prefix) => cidr_contains(*network, *prefix, *addr),
+            HostPattern::Name(_) | HostPattern::Suffix(_) => false,
+        }
+    }
+}
+
+fn default_port(scheme: &str) -> Option<u16> {
+    match scheme {
+        "http" | "ws" => Some(80),
+        "https" | "wss" => Some(443),
+        "postgres" | "postgresql" => Some(5432),
+        "mysql" => Some(3306),
+        "redis" => Some(6379),
+        _ => None,
+    }
+}
+
+fn split_scheme(value: &str) -> (Option<String>, &str) {
+    match value.split_once("://") {
+        Some((scheme, rest)) => (Some(scheme.to_ascii_lowercase()), rest),
+        None => (None, value),
+    }
+}
+
+/// Split `host[:port]`, handling `[v6]:port`, bare IPv6 and CIDR suffixes.
+fn split_host_port(value: &str) -> (&str, Option<&str>) {
+    let value = value.trim_end_matches('/');
+    if value.starts_with('[')
+        && let Some(end) = value.find(']')
+    {
+        let rest = &value[end + 1..];
+        let (mask, port) = match rest.find(':') {
+            Some(idx) => (&rest[..idx], Some(&rest[idx + 1..])),
+            None => (rest, None),
+        };
+        return (&value[..end + 1 + mask.len()], port);
+    }
+    if value.matches(':').count() > 1 {
+        return (value, None);
+    }
+    match value.split_once(':') {
+        Some((host, port)) => (host, Some(port)),
+        None => (value, None),
+    }
+}
+
+fn parse_port_range(value: &str) -> Result<(u16, u16), String> {
+    let parse = |part: &str| {
+        part.trim()
+            .parse::<u16>()
+            .map_err(|_| format!("invalid port '{}'", part))
+    };
+    let (lo, hi) = match value.split_once('-') {
+        Some((lo, hi)) => (parse(lo)?, parse(hi)?),
+        None => {
+            let port = parse(value)?;
+            (port, port)
+        }
+    };
+    if lo > hi {
+        return Err(format!("invalid port range '{}'", value));
+    }
+    Ok((lo, hi))
+}
+
+fn parse_host_pattern(value: &str) -> Result<HostPattern, String> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err("missing host".to_string());
+    }
+    if value == "*" {
+        return Ok(HostPattern::Any);
+    }
+    if let Some((addr, prefix)) = value.split_once('/') {
+        let addr = addr.trim_start_matches('[').trim_end_matches(']');
+        let network = addr
+            .parse::<IpAddr>()
+            .map_err(|_| format!("invalid CIDR address '{}'", addr))?;
+        let max = if network.is_ipv4() { 32 } else { 128 };
+        let prefix = prefix
+            .parse::<u8>()
+            .ok()
+            .filter(|prefix| *prefix <= max)
+            .ok_or_else(|| format!("invalid CIDR prefix '/{}'", prefix))?;
+        return Ok(HostPattern::Cidr(network, prefix));
+    }
+    let bare = value.trim_start_matches('[').trim_end_matches(']');
+    if let Ok(ip) = bare.parse::<IpAddr>() {
+        return Ok(HostPattern::Ip(ip));
+    }
+    if let Some(suffix) = value.strip_prefix("*.") {
+        if suffix.is_empty() || suffix.contains('*') {
+            return Err(format!("invalid host wildcard '{}'", value));
+        }
+        return Ok(HostPattern::Suffix(format!(
+            ".{}",
+            suffix.to_ascii_lowercase()
+        )));
+    }
+    if value.contains('*') {
+        return Err(format!(
+            "host wildcards must be a leading '*.' label: '{}'",
+            value
+        ));
+    }
+    Ok(HostPattern::Name(value.to_ascii_lowercase()))
+}
+
+/// Compare IPv4-mapped IPv6 addresses as IPv4.
+fn normalize_ip(addr: IpAddr) -> IpAddr {
+    match addr {
+        IpAddr::V6(v6) => v6
+            .to_ipv4_mapped()
+            .map(IpAddr::V4)
+            .unwrap_or(IpAddr::V6(v6)),
+        v4 => v4,
+    }
+}
+
+fn cidr_contains(network: IpAddr, prefix: u8, addr: IpAddr) -> bool {
+    match (normalize_ip(network), normalize_ip(addr)) {
+        (IpAddr::V4(network), IpAddr::V4(addr)) => {
+            let mask = u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0);
+            u32::from(network) & mask == u32::from(addr) & mask
+        }
+        (IpAddr::V6(network), IpAddr::V6(addr)) => {
+            let mask = u128::MAX.checked_shl(128 - prefix as u32).unwrap_or(0);
+            u128::from(network) & mask == u128::from(addr) & mask
+        }
+        _ => false,
+    }
+}
+
+pub fn has_glob(pattern: &str) -> bool {
+    pattern.contains(['*', '?', '['])
+}
+
+/// Glob for flat names (env keys): `*` matches any run of characters.
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    match_chars(&pattern, &text, false)
+}
+
+/// Glob for `/`-separated paths: `*` and `?` stay within a segment, `**`
+/// spans segments, and `[abc]` matches one character from the set.
+pub fn path_glob_match(pattern: &str, path: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let path: Vec<char> = path.chars().collect();
+    match_chars(&pattern, &path, true)
+}
+
+fn match_chars(pattern: &[char], text: &[char], path_mode: bool) -> bool {
+    match pattern.first() {
+        None => text.is_empty(),
+        Some('*') if path_mode && pattern.get(1) == Some(&'*') => {
+            let mut rest = &pattern[2..];
+            // `**/` also matches zero directories.
+            if rest.first() == Some(&'/') {
+                if match_chars(&rest[1..], text, path_mode) {
+                    return true;
+                }
+                rest = &pattern[2..];
+            }
+            (0..=text.len()).any(|skip| match_chars(rest, &text[skip..], path_mode))
+        }
+        Some('*') => {
+            let rest = &pattern[1..];
+            for skip in 0..=text.len() {
+                if match_chars(rest, &text[skip..], path_mode) {
+                    return true;
+                }
+                if path_mode && text.get(skip) == Some(&'/') {
+                    break;
+                }
+            }
+            false
+        }
+        Some('?') => match text.first() {
+            Some(ch) if !(path_mode && *ch == '/') => {
+                match_chars(&pattern[1..], &text[1..], path_mode)
+            }
+            _ => false,
+        },
+        Some('[') => {
+            let Some(end) = pattern.iter().position(|ch| *ch == ']') else {
+                return text.first() == Some(&'[')
+                    && match_chars(&pattern[1..], &text[1..], path_mode);
+            };
+            let set = &pattern[1..end];
+            let (negate, set) = match set.first() {
+                Some('!') | Some('^') => (true, &set[1..]),
+                _ => (false, set),
+            };
+            let Some(ch) = text.first() else {
+                return false;
+            };
+            let mut found = false;
+            let mut idx = 0;
+            while idx < set.len() {
+                if idx + 2 < set.len() && set[idx + 1] == '-' {
+                    found |= (set[idx]..=set[idx + 2]).contains(ch);
+                    idx += 3;
+                } else {
+                    found |= set[idx] == *ch;
+                    idx += 1;
+                }
+            }
+            found != negate && match_chars(&pattern[end + 1..], &text[1..], path_mode)
+        }
+        Some(expected) => {
+            text.first() == Some(expected) && match_chars(&pattern[1..], &text[1..], path_mode)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target(value: &str, addrs: &[&str]) -> NetTarget {
+        NetTarget::parse(value).with_addrs(addrs.iter().map(|addr| addr.parse().unwrap()))
+    }
+
+    #[test]
+    fn host_wildcards_match_subdomains_and_ports() {
+        let rule = NetRule::parse("*.internal.example:443").unwrap();
+        assert!(rule.allows(&target("api.internal.example:443", &[])));
+        assert!(rule.allows(&target("a.b.internal.example:443", &[])));
+        assert!(!rule.allows(&target("internal.example:443", &[])));
+        assert!(!rule.allows(&target("api.internal.example:80", &[])));
+
+        let range = NetRule::parse("localhost:8000-8999").unwrap();
+        assert!(range.allows(&target("localhost:8080", &[])));
+        assert!(!range.allows(&target("localhost:9000", &[])));
+
+        let any_port = NetRule::parse("db.local").unwrap();
+        assert!(any_port.allows(&target("DB.local:5432", &[])));
+    }
+
+    #[test]
+    fn cidr_rules_cover_literals_and_resolved_addresses() {
+        let rule = NetRule::parse("10.0.0.0/8").unwrap();
+        assert!(rule.allows(&target("10.1.2.3:5432", &[])));
+        assert!(!rule.allows(&target("11.0.0.1:5432", &[])));
+        assert!(rule.allows(&target("db.corp:5432", &["10.0.0.7"])));
+        assert!(!rule.allows(&target("db.corp:5432", &["10.0.0.7", "8.8.8.8"])));
+
+        let v6 = NetRule::parse("[fd00::]/8:443").unwrap();
+        assert!(v6.allows(&target("[fd12::1]:443", &[])));
+        assert!(!v6.allows(&target("[fe80::1]:443", &[])));
+    }
+
+    #[test]
+    fn deny_rules_block_names_resolving_to_denied_addresses() {
+        let deny = NetRule::parse("169.254.169.254").unwrap();
+        let rebound = target("metadata.attacker.example:80", &["169.254.169.254"]);
+        assert!(deny.denies(&rebound));
+        assert!(!deny.allows(&target("metadata.attacker.example:80", &[])));
+        assert!(deny.denies(&target("[::ffff:169.254.169.254]:80", &[])));
+    }
+
+    #[test]
+    fn scheme_rules_require_matching_scheme() {
+        let rule = NetRule::parse("https://api.example.com").unwrap();
+        assert!(rule.allows(&target("https://api.example.com", &[])));
+        assert!(!rule.allows(&target("http://api.example.com", &[])));
+        assert!(rule.allows(&target("api.example.com:443", &[])));
+        assert!(!rule.allows(&target("api.example.com:80", &[])));
+    }
+
+    #[test]
+    fn rejects_malformed_net_rules() {
+        assert!(NetRule::parse("10.0.0.0/33").is_err());
+        assert!(NetRule::parse("api.*.example").is_err());
+        assert!(NetRule::parse("localhost:9000-8000").is_err());
+        assert!(NetRule::parse("localhost:http").is_err());
+    }
+
+    #[test]
+    fn globs_for_env_and_paths() {
+        assert!(glob_match("APP_*", "APP_SECRET"));
+        assert!(!glob_match("APP_*", "DATABASE_URL"));
+        assert!(path_glob_match("/srv/app/*.phpx", "/srv/app/main.phpx"));
+        assert!(!path_glob_match(
+            "/srv/app/*.phpx",
+            "/srv/app/lib/util.phpx"
+        ));
+        assert!(path_glob_match(
+            "/srv/app/**/*.phpx",
+            "/srv/app/lib/util.phpx"
+        ));
+        assert!(path_glob_match("/srv/app/**/*.phpx", "/srv/app/main.phpx"));
+        assert!(path_glob_match("/srv/log/[ab]?.txt", "/srv/log/a1.txt"));
+        assert!(!path_glob_match("/srv/log/[!ab]?.txt", "/srv/log/a1.txt"));
+    }
+}
@@ -0,0 +1,86 @@
+//! Base64 VLQ codec for source map v3 `mappings` segments.
+
+const BASE64_DIGITS: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Append `value` to `out` as a base64 VLQ field.
+pub fn encode_vlq(out: &mut String, value: i64) {
+    let mut vlq = if value < 0 {
+        ((-value) << 1) | 1
+    } else {
+        value << 1
+    };
+    loop {
+        let mut digit = vlq & 0b11111;
+        vlq >>= 5;
+        if vlq > 0 {
+            digit |= 0b100000;
+        }
+        out.push(BASE64_DIGITS[digit as usize] as char);
+        if vlq == 0 {
+            break;
+        }
+    }
+}
+
+/// Decode every field of one comma-separated mappings segment.
+pub fn decode_vlq(segment: &str) -> Result<Vec<i64>, String> {
+    let mut values = Vec::new();
+    let mut value = 0i64;
+    let mut shift = 0;
+    for byte in segment.bytes() {
+        let digit = BASE64_DIGITS
+            .iter()
+            .position(|d| *d == byte)
+            .ok_or_else(|| format!("invalid base64 digit in source map: {}", byte as char))?
+            as i64;
+        value += (digit & 0b11111) << shift;
+        if digit & 0b100000 != 0 {
+            shift += 5;
+            continue;
+        }
+        values.push(if value & 1 == 1 {
+            -(value >> 1)
+        } else {
+            value >> 1
+        });
+        value = 0;
+        shift = 0;
+    }
+    if values.is_empty() {
+        return Err("empty source map segment".to_string());
+    }
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_what_it_encodes() {
+        let mut out = String::new();
+        for value in [0, 1, -1, 16, -300, 123456] {
+            encode_vlq(&mut out, value);
+        }
+        let mut decoded = Vec::new();
+        let mut rest = out.as_str();
+        while !rest.is_empty() {
+            let end = rest
+                .bytes()
+                .position(|b| BASE64_DIGITS.iter().position(|d| *d == b).unwrap() & 0b100000 == 0)
+                .unwrap();
+            decoded.extend(decode_vlq(&rest[..=end]).unwrap());
+            rest = &rest[end + 1..];
+        }
+        assert_eq!(decoded, vec![0, 1, -1, 16, -300, 123456]);
+    }
+
+    #[test]
+    fn encodes_negative_offsets() {
+        let mut out = String::new();
+        encode_vlq(&mut out, -1);
+        encode_vlq(&mut out, 16);
+        assert_eq!(out, "DgB");
+    }
+}